@@ -1,14 +1,33 @@
 use jeriya_backend::{
+    clip_plane::ClipPlane,
+    debug::DebugShadingMode,
     elements::{self, point_cloud::PointCloud, rigid_mesh::RigidMesh},
     gpu_index_allocator::ProvideAllocateGpuIndex,
     immediate::{CommandBuffer, CommandBufferBuilder, ImmediateRenderingFrame},
-    instances::{camera_instance::CameraInstance, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance},
+    instances::{
+        camera_instance::CameraInstance,
+        instance_group::InstanceGroup,
+        point_cloud_instance::PointCloudInstance,
+        rigid_mesh_instance::RigidMeshInstance,
+        rigid_mesh_instance_group::{RaycastHit, RaycastPrecision},
+    },
+    overlay::{OverlayCommandBuffer, OverlayCommandBufferBuilder},
+    render_target::RenderTargetId,
     resources::{mesh_attributes::MeshAttributes, point_cloud_attributes::PointCloudAttributes, ProvideResourceReceiver},
-    transactions::ProvideTransactionProcessor,
+    statistics::RendererStatistics,
+    transactions::{ProvideTransactionProcessor, Transaction},
+    ui::UiFrame,
+    viewport::Viewport,
     Backend, Result,
 };
 use jeriya_content::asset_importer::AssetImporter;
-use jeriya_shared::{features::info_log_features, tracy_client::Client, winit::window::WindowId, DebugInfo, RendererConfig, WindowConfig};
+use jeriya_shared::{
+    features::info_log_features,
+    nalgebra::{Matrix4, Vector3},
+    tracy_client::Client,
+    winit::window::{Window, WindowId},
+    ByteColor4, ColorSpace, DebugInfo, Handle, HeadlessConfig, RendererConfig, WindowConfig, WindowMode,
+};
 
 use std::{
     marker::PhantomData,
@@ -39,7 +58,7 @@ where
     /// ```
     /// # use std::sync::Arc;
     /// # use jeriya_shared::{
-    /// #     FrameRate, RendererConfig, WindowConfig,
+    /// #     ColorSpace, FrameRate, PresentMode, RendererConfig, WindowConfig,
     /// #     winit::{
     /// #         dpi::LogicalSize,
     /// #         event::{Event, WindowEvent},
@@ -67,6 +86,8 @@ where
     ///         WindowConfig {
     ///             window: &window,
     ///             frame_rate: FrameRate::Unlimited,
+    ///             present_mode: PresentMode::Mailbox,
+    ///             color_space: ColorSpace::Sdr,
     ///         },
     ///     ])
     ///     .build()
@@ -107,10 +128,206 @@ where
             .render_immediate_command_buffer(immediate_rendering_frame, command_buffer)
     }
 
+    /// Creates a new [`OverlayCommandBufferBuilder`]
+    pub fn create_overlay_command_buffer_builder(&self, debug_info: DebugInfo) -> Result<OverlayCommandBufferBuilder> {
+        Ok(OverlayCommandBufferBuilder::new(debug_info))
+    }
+
+    /// Renders an [`OverlayCommandBuffer`] on top of the scene for the given [`ImmediateRenderingFrame`].
+    ///
+    /// The overlay is drawn in pixels after the scene and its post-processing so that HUDs and annotations are neither
+    /// occluded nor shaded. It is kept for as long as the [`CommandBuffer`]s of the same [`ImmediateRenderingFrame`].
+    pub fn render_overlay_command_buffer(
+        &self,
+        immediate_rendering_frame: &ImmediateRenderingFrame,
+        command_buffer: OverlayCommandBuffer,
+    ) -> Result<()> {
+        self.backend
+            .render_overlay_command_buffer(immediate_rendering_frame, command_buffer)
+    }
+
+    /// Renders a [`UiFrame`] of a user interface library like egui on top of the overlay of the given window.
+    ///
+    /// The meshes are rendered every frame until the next `UiFrame` is received for the window. See `jeriya_egui`
+    /// for the integration of egui.
+    pub fn render_ui_frame(&self, window_id: WindowId, ui_frame: UiFrame) -> Result<()> {
+        self.backend.render_ui_frame(window_id, ui_frame)
+    }
+
+    /// Sets the transforms of many [`RigidMeshInstance`]s and sends them to the [`Backend`] as a single [`Transaction`].
+    ///
+    /// This is meant for syncing the bodies of a physics simulation like rapier3d every fixed timestep. All transforms
+    /// end up in one [`Transaction`] so that the [`Backend`] processes them in one go instead of once per body. Handles
+    /// of [`RigidMeshInstance`]s that were removed from the [`InstanceGroup`] are skipped. Returns the number of
+    /// [`RigidMeshInstance`]s whose transform was set.
+    pub fn apply_transforms(&self, instance_group: &mut InstanceGroup, transforms: &[(Handle<RigidMeshInstance>, Matrix4<f32>)]) -> usize {
+        let mut transaction = Transaction::with_capacity(transforms.len());
+        let count = instance_group
+            .rigid_mesh_instances()
+            .mutate_via(&mut transaction)
+            .set_transforms(transforms);
+        self.backend.process(transaction);
+        count
+    }
+
+    /// Casts a ray against the [`RigidMeshInstance`]s of the [`InstanceGroup`] and returns the hits sorted by distance.
+    ///
+    /// This runs on the CPU and is meant for editor-style selection. The ray is tested against the world space bounding
+    /// boxes of the instances and, depending on the [`RaycastPrecision`], against their triangles. The bounding volume
+    /// hierarchy of the [`InstanceGroup`] is rebuilt when the [`RigidMeshInstance`]s have been mutated since the last raycast.
+    pub fn raycast(
+        &self,
+        instance_group: &mut InstanceGroup,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        precision: RaycastPrecision,
+    ) -> Vec<RaycastHit> {
+        instance_group.rigid_mesh_instances().raycast(origin, direction, precision)
+    }
+
+    /// Sets the callback that is called with the new width and height in pixels after the swapchain of the given window
+    /// has been resized.
+    ///
+    /// The swapchain is resized on the presenter thread which also calls the callback. It's not called while the window is
+    /// minimized. Size-dependent state of the application like the aspect ratio of the cameras can be updated here.
+    pub fn set_resize_callback(&self, window_id: WindowId, resize_callback: impl FnMut(u32, u32) + Send + 'static) -> Result<()> {
+        self.backend.set_resize_callback(window_id, Some(Box::new(resize_callback)))
+    }
+
+    /// Removes the callback that has been set with [`Renderer::set_resize_callback`] for the given window.
+    pub fn remove_resize_callback(&self, window_id: WindowId) -> Result<()> {
+        self.backend.set_resize_callback(window_id, None)
+    }
+
+    /// Returns the [`ColorSpace`]s in which the given window can be presented.
+    ///
+    /// HDR monitors support [`ColorSpace::Hdr10`] or [`ColorSpace::ScRgb`] which can be requested with the `color_space`
+    /// of the [`WindowConfig`]. [`ColorSpace::Sdr`] is always supported.
+    pub fn supported_color_spaces(&self, window_id: WindowId) -> Result<Vec<ColorSpace>> {
+        self.backend.supported_color_spaces(window_id)
+    }
+
+    /// Switches the `window` between windowed, borderless and exclusive fullscreen.
+    ///
+    /// The swapchain of the window is recreated for the [`WindowMode`] and keeps its [`PresentMode`](jeriya_shared::PresentMode).
+    /// When the monitor doesn't report any video modes, [`WindowMode::Exclusive`] falls back to the borderless fullscreen.
+    pub fn set_window_mode(&self, window: &Window, window_mode: WindowMode) -> Result<()> {
+        window.set_fullscreen(window_mode.fullscreen(window));
+        self.backend.set_window_mode(window.id(), window_mode)
+    }
+
     /// Sets the active camera for the given window.
     pub fn set_active_camera(&self, window_id: WindowId, camera_instance: &CameraInstance) -> Result<()> {
         self.backend.set_active_camera(window_id, camera_instance)
     }
+
+    /// Creates a render target of `width` x `height` pixels into which the scene is rendered from the `camera_instance`.
+    ///
+    /// This is used for mirrors, minimaps or security-camera views. The render target is rendered in every frame of the
+    /// given window before the scene and can be displayed with [`TexturedQuad::from_render_target`] in the overlay of the
+    /// window. It is removed together with the `camera_instance`.
+    ///
+    /// [`TexturedQuad::from_render_target`]: jeriya_backend::overlay::TexturedQuad::from_render_target
+    pub fn create_render_target(
+        &self,
+        window_id: WindowId,
+        camera_instance: &CameraInstance,
+        width: u32,
+        height: u32,
+    ) -> Result<RenderTargetId> {
+        self.backend.create_render_target(window_id, camera_instance, width, height)
+    }
+
+    /// Removes the render target from the given window.
+    pub fn remove_render_target(&self, window_id: WindowId, render_target_id: RenderTargetId) -> Result<()> {
+        self.backend.remove_render_target(window_id, render_target_id)
+    }
+
+    /// Splits the given window into the `viewports` which are rendered in the given order.
+    ///
+    /// Every [`Viewport`] renders the scene from its own `CameraInstance`, which allows showing top, front, side and
+    /// perspective views in a single window. The immediate rendering and the debug lines are still rendered across the
+    /// whole window from the active camera. A `Viewport` is removed together with its `CameraInstance`. Passing no
+    /// viewports renders the scene into the whole window from the active camera again.
+    pub fn set_viewports(&self, window_id: WindowId, viewports: Vec<Viewport>) -> Result<()> {
+        self.backend.set_viewports(window_id, viewports)
+    }
+
+    /// Sets the [`ClipPlane`]s of the given window to render cross sections through the models and point clouds.
+    ///
+    /// Everything on the negative side of any of the planes, i.e. opposite to its normal, is clipped. The points of point clouds
+    /// are clipped as a whole. Passing no clip planes renders the whole scene again. Returns an error when more than
+    /// [`MAX_CLIP_PLANES`] planes are given or when the device doesn't support clip distances.
+    ///
+    /// [`MAX_CLIP_PLANES`]: jeriya_backend::clip_plane::MAX_CLIP_PLANES
+    pub fn set_clip_planes(&self, window_id: WindowId, clip_planes: Vec<ClipPlane>) -> Result<()> {
+        self.backend.set_clip_planes(window_id, clip_planes)
+    }
+
+    /// Enables or disables the rendering of the bounding volumes for the given window.
+    ///
+    /// When enabled, the bounding boxes of all `RigidMeshInstance`s, their meshlets and the visible
+    /// point cloud clusters are drawn as lines on top of the scene.
+    pub fn set_debug_bounding_volumes(&self, window_id: WindowId, enabled: bool) -> Result<()> {
+        self.backend.set_debug_bounding_volumes(window_id, enabled)
+    }
+
+    /// Sets the [`DebugShadingMode`] for the given window.
+    pub fn set_debug_shading_mode(&self, window_id: WindowId, debug_shading_mode: DebugShadingMode) -> Result<()> {
+        self.backend.set_debug_shading_mode(window_id, debug_shading_mode)
+    }
+
+    /// Enables or disables the eye-dome lighting for the given window.
+    ///
+    /// Eye-dome lighting darkens the pixels at depth discontinuities so that the shape of point
+    /// clouds without normals becomes visible. It's applied to the whole frame after the scene
+    /// has been rendered.
+    pub fn set_eye_dome_lighting(&self, window_id: WindowId, enabled: bool) -> Result<()> {
+        self.backend.set_eye_dome_lighting(window_id, enabled)
+    }
+
+    /// Sets the color of the outline in the given window.
+    ///
+    /// The outline is drawn around the silhouette of the `RigidMeshInstance`s that are selected
+    /// with `set_selected` so that editors can highlight them. It stays visible when the selected
+    /// instances are occluded.
+    pub fn set_selection_outline_color(&self, window_id: WindowId, color: ByteColor4) -> Result<()> {
+        self.backend.set_selection_outline_color(window_id, color)
+    }
+
+    /// Sets the maximum number of points that are rendered for the point clouds in the given window.
+    ///
+    /// The clusters of the point clouds are refined until either the desired point density on the
+    /// screen or the budget is reached. The budget is shared evenly between the visible point cloud
+    /// instances. `None` removes the limit.
+    pub fn set_maximum_rendered_points(&self, window_id: WindowId, maximum_rendered_points: Option<usize>) -> Result<()> {
+        self.backend.set_maximum_rendered_points(window_id, maximum_rendered_points)
+    }
+
+    /// Enables or disables the performance HUD for the given window.
+    ///
+    /// The HUD plots the CPU frame time, the number of visible meshlets and the upload bandwidth
+    /// of the most recent frames in the upper left corner of the window so that no profiler has to
+    /// be attached to get runtime numbers.
+    pub fn set_performance_hud(&self, window_id: WindowId, enabled: bool) -> Result<()> {
+        self.backend.set_performance_hud(window_id, enabled)
+    }
+
+    /// Returns the [`RendererStatistics`] with the CPU frame time and the number of pending
+    /// transactions of every window, the usage of the GPU memory and the number of resources.
+    ///
+    /// The statistics are cheap to gather so that they can be shown in a stats overlay every frame.
+    pub fn statistics(&self) -> Result<RendererStatistics> {
+        self.backend.statistics()
+    }
+
+    /// Writes the compiled pipelines to the `pipeline_cache_path` of the [`RendererConfig`] so that they are loaded on the next start.
+    ///
+    /// The cache is not written automatically because the `Renderer` is usually still referenced by its threads when the
+    /// application exits. It should be saved before exiting and can be saved again at any time, e.g. after loading a scene.
+    pub fn save_pipeline_cache(&self) -> Result<()> {
+        self.backend.save_pipeline_cache()
+    }
 }
 
 impl<B: Backend> ProvideResourceReceiver for Renderer<B> {
@@ -190,6 +407,7 @@ where
 {
     _phantom: PhantomData<B>,
     window_configs: &'a [WindowConfig<'a>],
+    headless_configs: &'a [HeadlessConfig],
     renderer_config: Option<RendererConfig>,
     backend_config: Option<B::BackendConfig>,
     asset_importer: Option<Arc<AssetImporter>>,
@@ -203,6 +421,7 @@ where
         Self {
             _phantom: PhantomData,
             window_configs: &[],
+            headless_configs: &[],
             renderer_config: None,
             backend_config: None,
             asset_importer: None,
@@ -229,6 +448,12 @@ where
         self
     }
 
+    /// Adds render targets that are rendered offscreen when no display is available
+    pub fn add_headless(mut self, headless_configs: &'a [HeadlessConfig]) -> Self {
+        self.headless_configs = headless_configs;
+        self
+    }
+
     pub fn build(self) -> Result<Arc<Renderer<B>>> {
         // Create a Tracy client before the backend is created because the first thread creating a Client is called "Main thread".
         let _tracy_client = Client::start();
@@ -243,7 +468,13 @@ where
         let renderer_config = self.renderer_config.unwrap_or_default();
         let backend_config = self.backend_config.unwrap_or_default();
         let asset_importer = self.asset_importer.expect("Asset importer must be set");
-        let backend = B::new(renderer_config, backend_config, asset_importer, self.window_configs)?;
+        let backend = B::new(
+            renderer_config,
+            backend_config,
+            asset_importer,
+            self.window_configs,
+            self.headless_configs,
+        )?;
         Ok(Arc::new(Renderer::new(backend)))
     }
 }
@@ -279,16 +510,23 @@ fn run_deadlock_detection() {
 #[cfg(test)]
 mod tests {
     use jeriya_backend::{
+        clip_plane::ClipPlane,
+        debug::DebugShadingMode,
         elements::{self, point_cloud::PointCloud, rigid_mesh::RigidMesh},
         gpu_index_allocator::{AllocateGpuIndex, GpuIndexAllocation},
         immediate::{CommandBuffer, ImmediateRenderingFrame},
         instances::{camera_instance::CameraInstance, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance},
+        overlay::OverlayCommandBuffer,
+        render_target::RenderTargetId,
         resources::{mesh_attributes::MeshAttributes, point_cloud_attributes::PointCloudAttributes, ResourceEvent, ResourceReceiver},
+        statistics::RendererStatistics,
         transactions::{Transaction, TransactionProcessor},
-        Backend,
+        ui::UiFrame,
+        viewport::Viewport,
+        Backend, ResizeCallback,
     };
     use jeriya_content::asset_importer::AssetImporter;
-    use jeriya_shared::{winit::window::WindowId, WindowConfig};
+    use jeriya_shared::{winit::window::WindowId, ByteColor4, ColorSpace, HeadlessConfig, WindowConfig, WindowMode};
     use std::sync::{
         mpsc::{channel, Sender},
         Arc,
@@ -300,7 +538,7 @@ mod tests {
         use jeriya_backend::immediate::{ImmediateRenderingFrame, LineConfig, LineList};
         use jeriya_backend_ash::AshBackend;
         use jeriya_content::asset_importer::AssetImporter;
-        use jeriya_shared::{debug_info, nalgebra::Vector3, FrameRate, WindowConfig};
+        use jeriya_shared::{debug_info, nalgebra::Vector3, ColorSpace, FrameRate, PresentMode, WindowConfig};
         use jeriya_test::create_window;
 
         use crate::Renderer;
@@ -311,6 +549,8 @@ mod tests {
             let window_config = WindowConfig {
                 window: &window,
                 frame_rate: FrameRate::Unlimited,
+                present_mode: PresentMode::Mailbox,
+                color_space: ColorSpace::Sdr,
             };
             let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
             let renderer = Renderer::<AshBackend>::builder()
@@ -398,6 +638,7 @@ mod tests {
             _backend_config: Self::BackendConfig,
             _asset_importer: Arc<AssetImporter>,
             _window_configs: &[WindowConfig],
+            _headless_configs: &[HeadlessConfig],
         ) -> jeriya_backend::Result<Arc<Self>>
         where
             Self: Sized,
@@ -415,8 +656,86 @@ mod tests {
             Ok(())
         }
 
+        fn render_overlay_command_buffer(
+            &self,
+            _immediate_rendering_frame: &ImmediateRenderingFrame,
+            _command_buffer: OverlayCommandBuffer,
+        ) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn render_ui_frame(&self, _window_id: WindowId, _ui_frame: UiFrame) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_resize_callback(&self, _window_id: WindowId, _resize_callback: Option<ResizeCallback>) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn supported_color_spaces(&self, _window_id: WindowId) -> jeriya_backend::Result<Vec<ColorSpace>> {
+            Ok(vec![ColorSpace::Sdr])
+        }
+
+        fn set_window_mode(&self, _window_id: WindowId, _window_mode: WindowMode) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
         fn set_active_camera(&self, _window_id: WindowId, _camera_instance: &CameraInstance) -> jeriya_backend::Result<()> {
             Ok(())
         }
+
+        fn create_render_target(
+            &self,
+            _window_id: WindowId,
+            _camera_instance: &CameraInstance,
+            _width: u32,
+            _height: u32,
+        ) -> jeriya_backend::Result<RenderTargetId> {
+            Ok(RenderTargetId(0))
+        }
+
+        fn remove_render_target(&self, _window_id: WindowId, _render_target_id: RenderTargetId) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_viewports(&self, _window_id: WindowId, _viewports: Vec<Viewport>) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_clip_planes(&self, _window_id: WindowId, _clip_planes: Vec<ClipPlane>) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_debug_bounding_volumes(&self, _window_id: WindowId, _enabled: bool) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_debug_shading_mode(&self, _window_id: WindowId, _debug_shading_mode: DebugShadingMode) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_eye_dome_lighting(&self, _window_id: WindowId, _enabled: bool) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_selection_outline_color(&self, _window_id: WindowId, _color: ByteColor4) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_maximum_rendered_points(&self, _window_id: WindowId, _maximum_rendered_points: Option<usize>) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn set_performance_hud(&self, _window_id: WindowId, _enabled: bool) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
+
+        fn statistics(&self) -> jeriya_backend::Result<RendererStatistics> {
+            Ok(RendererStatistics::default())
+        }
+
+        fn save_pipeline_cache(&self) -> jeriya_backend::Result<()> {
+            Ok(())
+        }
     }
 }
@@ -1,17 +1,27 @@
 use std::sync::Arc;
 
 use jeriya_content::asset_importer::AssetImporter;
-use jeriya_shared::{winit::window::WindowId, RendererConfig, WindowConfig};
+use jeriya_shared::{winit::window::WindowId, ByteColor4, ColorSpace, HeadlessConfig, RendererConfig, WindowConfig, WindowMode};
 
 use crate::{
+    clip_plane::ClipPlane,
+    debug::DebugShadingMode,
     elements::{self, point_cloud::PointCloud, rigid_mesh::RigidMesh},
     gpu_index_allocator::AllocateGpuIndex,
     immediate::{CommandBuffer, ImmediateRenderingFrame},
     instances::{camera_instance::CameraInstance, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance},
+    overlay::OverlayCommandBuffer,
+    render_target::RenderTargetId,
     resources::{mesh_attributes::MeshAttributes, point_cloud_attributes::PointCloudAttributes, ResourceReceiver},
+    statistics::RendererStatistics,
     transactions::TransactionProcessor,
+    ui::UiFrame,
+    viewport::Viewport,
 };
 
+/// Callback that is called with the new width and height in pixels after the swapchain of a window has been resized
+pub type ResizeCallback = Box<dyn FnMut(u32, u32) + Send>;
+
 /// Rendering backend that is used by the [`Renderer`]
 pub trait Backend:
     Sized
@@ -29,12 +39,13 @@ pub trait Backend:
 {
     type BackendConfig: Default;
 
-    /// Creates a new [`Backend`]
+    /// Creates a new [`Backend`] that renders into the given windows and headless render targets
     fn new(
         renderer_config: RendererConfig,
         backend_config: Self::BackendConfig,
         asset_importer: Arc<AssetImporter>,
         window_configs: &[WindowConfig],
+        headless_configs: &[HeadlessConfig],
     ) -> crate::Result<Arc<Self>>
     where
         Self: Sized;
@@ -46,6 +57,79 @@ pub trait Backend:
         command_buffer: CommandBuffer,
     ) -> crate::Result<()>;
 
+    /// Renders the given [`OverlayCommandBuffer`] on top of the scene in the next frame
+    fn render_overlay_command_buffer(
+        &self,
+        immediate_rendering_frame: &ImmediateRenderingFrame,
+        command_buffer: OverlayCommandBuffer,
+    ) -> crate::Result<()>;
+
+    /// Renders the given [`UiFrame`] on top of the overlay of the given window until the next `UiFrame` is received
+    fn render_ui_frame(&self, window_id: WindowId, ui_frame: UiFrame) -> crate::Result<()>;
+
+    /// Sets the callback that is called after the swapchain of the given window has been resized. `None` removes the callback.
+    fn set_resize_callback(&self, window_id: WindowId, resize_callback: Option<ResizeCallback>) -> crate::Result<()>;
+
+    /// Returns the [`ColorSpace`]s in which the given window can be presented. [`ColorSpace::Sdr`] is always supported.
+    fn supported_color_spaces(&self, window_id: WindowId) -> crate::Result<Vec<ColorSpace>>;
+
+    /// Sets the [`WindowMode`] of the given window so that its swapchain is recreated with the present modes that are
+    /// preferred for the mode.
+    fn set_window_mode(&self, window_id: WindowId, window_mode: WindowMode) -> crate::Result<()>;
+
     /// Sets the active camera for the given window
     fn set_active_camera(&self, window_id: WindowId, camera_instance: &CameraInstance) -> crate::Result<()>;
+
+    /// Creates a render target of `width` x `height` pixels into which the scene is rendered from the `camera_instance`
+    ///
+    /// The scene is rendered into the render target in every frame of the given window.
+    ///
+    /// The render target is removed when the `camera_instance` is removed.
+    fn create_render_target(
+        &self,
+        window_id: WindowId,
+        camera_instance: &CameraInstance,
+        width: u32,
+        height: u32,
+    ) -> crate::Result<RenderTargetId>;
+
+    /// Removes the render target from the given window
+    fn remove_render_target(&self, window_id: WindowId, render_target_id: RenderTargetId) -> crate::Result<()>;
+
+    /// Splits the given window into the `viewports` which are rendered in the given order
+    ///
+    /// When no viewports are given, the scene is rendered into the whole window from the active camera.
+    fn set_viewports(&self, window_id: WindowId, viewports: Vec<Viewport>) -> crate::Result<()>;
+
+    /// Sets the [`ClipPlane`]s of the given window
+    ///
+    /// Everything on the negative side of a plane is not rendered. At most [`MAX_CLIP_PLANES`] planes are supported.
+    /// Returns an error when the device doesn't support clip distances.
+    ///
+    /// [`MAX_CLIP_PLANES`]: crate::clip_plane::MAX_CLIP_PLANES
+    fn set_clip_planes(&self, window_id: WindowId, clip_planes: Vec<ClipPlane>) -> crate::Result<()>;
+
+    /// Enables or disables the rendering of the bounding volumes for the given window
+    fn set_debug_bounding_volumes(&self, window_id: WindowId, enabled: bool) -> crate::Result<()>;
+
+    /// Sets the [`DebugShadingMode`] for the given window
+    fn set_debug_shading_mode(&self, window_id: WindowId, debug_shading_mode: DebugShadingMode) -> crate::Result<()>;
+
+    /// Enables or disables the eye-dome lighting post-process for the given window
+    fn set_eye_dome_lighting(&self, window_id: WindowId, enabled: bool) -> crate::Result<()>;
+
+    /// Sets the color of the outline around the selected [`RigidMeshInstance`](crate::instances::rigid_mesh_instance::RigidMeshInstance)s in the given window
+    fn set_selection_outline_color(&self, window_id: WindowId, color: ByteColor4) -> crate::Result<()>;
+
+    /// Sets the maximum number of points that are rendered for the point clouds in the given window. `None` removes the limit.
+    fn set_maximum_rendered_points(&self, window_id: WindowId, maximum_rendered_points: Option<usize>) -> crate::Result<()>;
+
+    /// Enables or disables the [`PerformanceHud`](crate::performance_hud::PerformanceHud) for the given window
+    fn set_performance_hud(&self, window_id: WindowId, enabled: bool) -> crate::Result<()>;
+
+    /// Returns the [`RendererStatistics`] of all windows, the GPU memory and the resources
+    fn statistics(&self) -> crate::Result<RendererStatistics>;
+
+    /// Writes the compiled pipelines to the `pipeline_cache_path` of the [`RendererConfig`]. Does nothing when no path is configured.
+    fn save_pipeline_cache(&self) -> crate::Result<()>;
 }
@@ -0,0 +1,75 @@
+use jeriya_shared::nalgebra::{Unit, Vector3, Vector4};
+
+/// Maximum number of [`ClipPlane`]s per window
+pub const MAX_CLIP_PLANES: usize = 4;
+
+/// Plane in world space that cuts away the part of the scene behind it
+///
+/// Everything on the side of the plane into which the `normal` points is kept. Together with the other clip planes of a
+/// window, this results in cross-section views through the rigid meshes and point clouds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipPlane {
+    normal: Unit<Vector3<f32>>,
+    distance: f32,
+}
+
+impl ClipPlane {
+    /// Creates a new `ClipPlane` with the points `p` for which `normal.dot(p) + distance == 0`
+    pub fn new(normal: Unit<Vector3<f32>>, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    /// Creates a new `ClipPlane` that contains the `point`
+    pub fn from_point_and_normal(point: &Vector3<f32>, normal: Unit<Vector3<f32>>) -> Self {
+        Self::new(normal, -normal.dot(point))
+    }
+
+    /// Returns the normal that points to the side of the plane which is kept
+    pub fn normal(&self) -> &Unit<Vector3<f32>> {
+        &self.normal
+    }
+
+    /// Returns the distance of the plane from the origin along the negative normal
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Returns the signed distance of the `point` to the plane. Points with a negative distance are clipped.
+    pub fn signed_distance(&self, point: &Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+
+    /// Returns whether the `point` is cut away by the plane
+    pub fn clips(&self, point: &Vector3<f32>) -> bool {
+        self.signed_distance(point) < 0.0
+    }
+
+    /// Returns the plane as `(normal.x, normal.y, normal.z, distance)` like it's passed to the shaders
+    pub fn as_vector4(&self) -> Vector4<f32> {
+        self.normal.into_inner().push(self.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_point_and_normal() {
+        let clip_plane = ClipPlane::from_point_and_normal(&Vector3::new(0.0, 2.0, 0.0), Vector3::y_axis());
+        assert_eq!(clip_plane.distance(), -2.0);
+        assert_eq!(clip_plane.signed_distance(&Vector3::new(5.0, 3.0, 1.0)), 1.0);
+        assert!(!clip_plane.clips(&Vector3::new(0.0, 2.0, 0.0)));
+        assert!(clip_plane.clips(&Vector3::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn as_vector4() {
+        let clip_plane = ClipPlane::new(-Vector3::x_axis(), 3.0);
+        let plane = clip_plane.as_vector4();
+        assert_eq!(plane, Vector4::new(-1.0, 0.0, 0.0, 3.0));
+        // The shaders evaluate the distance with a dot product in homogeneous coordinates
+        let point = Vector3::new(4.0, 1.0, 1.0);
+        assert_eq!(plane.dot(&point.push(1.0)), clip_plane.signed_distance(&point));
+    }
+}
@@ -0,0 +1,22 @@
+/// Determines how the scene is shaded. Everything except [`DebugShadingMode::None`] is meant for debugging.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugShadingMode {
+    /// Regular shading
+    #[default]
+    None,
+    /// Object space normals mapped to RGB
+    Normals,
+    /// Depth of the fragment in the depth buffer
+    Depth,
+    /// Every meshlet and point cloud cluster is shaded in a different color
+    MeshletId,
+    /// Every instance is shaded in a different color
+    InstanceId,
+    /// Brightness indicates how often a pixel has been shaded. Depth testing is disabled in this mode.
+    Overdraw,
+    /// Meshlets and point cloud clusters are colored by how many triangles or points they contain
+    /// relative to their capacity. Underfilled meshlets and clusters are red, full ones are blue.
+    FillLevel,
+    /// Point cloud clusters are colored by their depth in the cluster hierarchy
+    ClusterDepth,
+}
@@ -4,7 +4,7 @@ use jeriya_shared::parking_lot::Mutex;
 
 use crate::gpu_index_allocator::{AllocateGpuIndex, GpuIndexAllocation, GpuIndexAllocator, ProvideAllocateGpuIndex};
 
-use self::rigid_mesh::RigidMesh;
+use self::{camera::Camera, point_cloud::PointCloud, rigid_mesh::RigidMesh};
 
 pub mod camera;
 pub mod camera_group;
@@ -25,6 +25,8 @@ impl MockRenderer {
         Arc::new(Self {
             backend: Arc::new(MockBackend {
                 rigid_mesh_gpu_index_allocator: Mutex::new(GpuIndexAllocator::new(100)),
+                camera_gpu_index_allocator: Mutex::new(GpuIndexAllocator::new(100)),
+                point_cloud_gpu_index_allocator: Mutex::new(GpuIndexAllocator::new(100)),
             }),
         })
     }
@@ -37,8 +39,24 @@ impl ProvideAllocateGpuIndex<RigidMesh> for MockRenderer {
     }
 }
 
+impl ProvideAllocateGpuIndex<Camera> for MockRenderer {
+    type AllocateGpuIndex = MockBackend;
+    fn provide_gpu_index_allocator(&self) -> Weak<Self::AllocateGpuIndex> {
+        Arc::downgrade(&self.backend)
+    }
+}
+
+impl ProvideAllocateGpuIndex<PointCloud> for MockRenderer {
+    type AllocateGpuIndex = MockBackend;
+    fn provide_gpu_index_allocator(&self) -> Weak<Self::AllocateGpuIndex> {
+        Arc::downgrade(&self.backend)
+    }
+}
+
 pub struct MockBackend {
     rigid_mesh_gpu_index_allocator: Mutex<GpuIndexAllocator<RigidMesh>>,
+    camera_gpu_index_allocator: Mutex<GpuIndexAllocator<Camera>>,
+    point_cloud_gpu_index_allocator: Mutex<GpuIndexAllocator<PointCloud>>,
 }
 
 impl AllocateGpuIndex<RigidMesh> for MockBackend {
@@ -50,3 +68,23 @@ impl AllocateGpuIndex<RigidMesh> for MockBackend {
         self.rigid_mesh_gpu_index_allocator.lock().free_gpu_index(gpu_index_allocation)
     }
 }
+
+impl AllocateGpuIndex<Camera> for MockBackend {
+    fn allocate_gpu_index(&self) -> Option<GpuIndexAllocation<Camera>> {
+        self.camera_gpu_index_allocator.lock().allocate_gpu_index()
+    }
+
+    fn free_gpu_index(&self, gpu_index_allocation: GpuIndexAllocation<Camera>) {
+        self.camera_gpu_index_allocator.lock().free_gpu_index(gpu_index_allocation)
+    }
+}
+
+impl AllocateGpuIndex<PointCloud> for MockBackend {
+    fn allocate_gpu_index(&self) -> Option<GpuIndexAllocation<PointCloud>> {
+        self.point_cloud_gpu_index_allocator.lock().allocate_gpu_index()
+    }
+
+    fn free_gpu_index(&self, gpu_index_allocation: GpuIndexAllocation<PointCloud>) {
+        self.point_cloud_gpu_index_allocator.lock().free_gpu_index(gpu_index_allocation)
+    }
+}
@@ -1,4 +1,8 @@
-use jeriya_shared::{debug_info, nalgebra::Matrix4, nalgebra_glm, thiserror, DebugInfo, Handle};
+use jeriya_shared::{
+    debug_info,
+    nalgebra::{Matrix4, Vector4},
+    nalgebra_glm, thiserror, DebugInfo, Handle,
+};
 
 use crate::{
     gpu_index_allocator::GpuIndexAllocation,
@@ -9,6 +13,8 @@ use crate::{
 pub enum Error {
     #[error("The allocation of the Camera failed")]
     AllocationFailed,
+    #[error("The matrix of the custom projection can't be inverted")]
+    NonInvertibleProjection,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -18,6 +24,7 @@ pub enum Event {
     Noop,
     Insert(Camera),
     UpdateProjection(GpuIndexAllocation<Camera>, CameraProjection),
+    Remove(GpuIndexAllocation<Camera>),
 }
 
 /// Type of projection for a camera.
@@ -37,6 +44,12 @@ pub enum CameraProjection {
         near: f32,
         far: f32,
     },
+    /// Arbitrary projection matrix, e.g. an off-axis projection for multi-projector setups
+    ///
+    /// Like the other projections, the matrix must map the view space of a right-handed coordinate system to a depth
+    /// range from 0 to 1. The near and far planes are derived from the matrix and have to be perpendicular to the
+    /// view direction. The matrix must be invertible or the [`Camera`] is rejected.
+    Custom(Matrix4<f32>),
 }
 
 impl CameraProjection {
@@ -52,26 +65,54 @@ impl CameraProjection {
                 far,
             } => nalgebra_glm::ortho_rh_zo(left, right, bottom, top, near, far),
             CameraProjection::Perspective { fov, aspect, near, far } => nalgebra_glm::perspective_rh_zo(aspect, fov, near, far),
+            CameraProjection::Custom(projection_matrix) => projection_matrix,
+        }
+    }
+
+    /// Returns an error when the `CameraProjection` is a [`CameraProjection::Custom`] whose matrix can't be inverted
+    ///
+    /// The near and far planes of such a projection can't be derived and the culling would receive `NaN`.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            CameraProjection::Custom(projection_matrix) if projection_matrix.try_inverse().is_none() => Err(Error::NonInvertibleProjection),
+            _ => Ok(()),
         }
     }
 
     /// Returns the value of the near plane for `CameraProjection`.
+    ///
+    /// Returns `NaN` for a [`CameraProjection::Custom`] whose matrix can't be inverted. See [`CameraProjection::validate`].
     pub fn znear(&self) -> f32 {
         match *self {
             CameraProjection::Orthographic { near, .. } => near,
             CameraProjection::Perspective { near, .. } => near,
+            CameraProjection::Custom(projection_matrix) => view_space_distance(&projection_matrix, 0.0),
         }
     }
 
     /// Returns the value of the far plane for `CameraProjection`.
+    ///
+    /// Returns `NaN` for a [`CameraProjection::Custom`] whose matrix can't be inverted. See [`CameraProjection::validate`].
     pub fn zfar(&self) -> f32 {
         match *self {
             CameraProjection::Orthographic { far, .. } => far,
             CameraProjection::Perspective { far, .. } => far,
+            CameraProjection::Custom(projection_matrix) => view_space_distance(&projection_matrix, 1.0),
         }
     }
 }
 
+/// Returns the distance from the camera to the plane that is mapped to the given depth in ndc
+fn view_space_distance(projection_matrix: &Matrix4<f32>, ndc_depth: f32) -> f32 {
+    projection_matrix
+        .try_inverse()
+        .map(|inverse| {
+            let view_space_position = inverse * Vector4::new(0.0, 0.0, ndc_depth, 1.0);
+            -view_space_position.z / view_space_position.w
+        })
+        .unwrap_or(f32::NAN)
+}
+
 impl Default for CameraProjection {
     fn default() -> Self {
         Self::Orthographic {
@@ -133,12 +174,16 @@ impl<'g, 't, P: PushEvent> CameraAccessMut<'g, 't, P> {
     }
 
     /// Sets the [`CameraProjection`] of the [`Camera`].
-    pub fn set_projection(&mut self, projection: CameraProjection) {
+    ///
+    /// Returns an error and keeps the previous projection when the [`CameraProjection`] is invalid.
+    pub fn set_projection(&mut self, projection: CameraProjection) -> Result<()> {
+        projection.validate()?;
         self.camera.projection = projection;
         self.transaction.push_event(transactions::Event::Camera(Event::UpdateProjection(
             self.camera.gpu_index_allocation,
             self.camera.projection.clone(),
-        )))
+        )));
+        Ok(())
     }
 }
 
@@ -162,8 +207,10 @@ impl CameraBuilder {
     }
 
     pub(crate) fn build(self, handle: Handle<Camera>, gpu_index_allocation: GpuIndexAllocation<Camera>) -> Result<Camera> {
+        let projection = self.projection.unwrap_or_default();
+        projection.validate()?;
         Ok(Camera {
-            projection: self.projection.unwrap_or_default(),
+            projection,
             debug_info: self.debug_info.unwrap_or_else(|| debug_info!("Anonymous Camera")),
             handle,
             gpu_index_allocation,
@@ -225,4 +272,29 @@ mod tests {
         let projection = nalgebra_glm::perspective_rh_zo(1.2, 90.0, 0.1, 100.0);
         assert_eq!(camera.projection().projection_matrix(), projection);
     }
+
+    #[test]
+    fn custom() {
+        // Off-axis projection whose frustum is shifted to the right
+        let mut projection_matrix = nalgebra_glm::perspective_rh_zo(1.2, 1.0, 0.1, 100.0);
+        projection_matrix[(0, 2)] = 0.5;
+        let projection = CameraProjection::Custom(projection_matrix);
+        assert_eq!(projection.projection_matrix(), projection_matrix);
+        assert!((projection.znear() - 0.1).abs() < 1e-4);
+        assert!((projection.zfar() - 100.0).abs() < 1e-1);
+
+        let projection = CameraProjection::Custom(nalgebra_glm::ortho_rh_zo(-1.0, 1.0, -1.0, 1.0, 2.0, 10.0));
+        assert!((projection.znear() - 2.0).abs() < 1e-4);
+        assert!((projection.zfar() - 10.0).abs() < 1e-4);
+
+        assert!(CameraProjection::Custom(Matrix4::zeros()).znear().is_nan());
+    }
+
+    #[test]
+    fn non_invertible_custom() {
+        let result = Camera::builder()
+            .with_projection(CameraProjection::Custom(Matrix4::zeros()))
+            .build(Handle::zero(), GpuIndexAllocation::new_unchecked(0));
+        assert!(matches!(result, Err(Error::NonInvertibleProjection)));
+    }
 }
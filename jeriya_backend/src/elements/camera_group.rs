@@ -85,4 +85,22 @@ impl<'g, 't, P: PushEvent> CameraGroupAccessMut<'g, 't, P> {
                 handle
             })
     }
+
+    /// Removes the [`Camera`] with the given [`Handle`] from the [`CameraGroup`].
+    ///
+    /// The GPU index of the [`Camera`] is freed so that it can be reused by subsequent insertions.
+    /// Returns `None` when the [`Handle`] doesn't reference a [`Camera`] in the [`CameraGroup`].
+    pub fn remove(&mut self, handle: &Handle<Camera>) -> Option<Camera> {
+        let camera = self.camera_group.indexing_container.get(handle)?.clone();
+        self.camera_group.indexing_container.remove_with(handle, camera.clone());
+        self.transaction
+            .push_event(transactions::Event::Camera(camera::Event::Remove(*camera.gpu_index_allocation())));
+        let gpu_index_allocator = self
+            .camera_group
+            .gpu_index_allocator
+            .upgrade()
+            .expect("the gpu_index_allocator was dropped");
+        gpu_index_allocator.free_gpu_index(*camera.gpu_index_allocation());
+        Some(camera)
+    }
 }
@@ -20,6 +20,10 @@ pub enum Error {
     MeshAttributes(#[from] mesh_attributes::Error),
     #[error("RigidMeshError: {0}")]
     RigidMesh(#[from] rigid_mesh::Error),
+    #[error("The reloaded model has {actual} meshes but the RigidMeshCollection has {expected}")]
+    MeshCountChanged { expected: usize, actual: usize },
+    #[error("The RigidMesh of the RigidMeshCollection was not found in the ElementGroup")]
+    RigidMeshNotFound,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -53,6 +57,51 @@ impl RigidMeshCollection {
         })
     }
 
+    /// Replaces the [`MeshAttributes`] of the [`RigidMesh`]es with the meshes of the given model, e.g. when the
+    /// model changed on disk. The [`RigidMesh`]es keep their [`Handle`]s so that their instances render the new
+    /// meshes as soon as the transaction is processed. The previous [`MeshAttributes`] are removed from the
+    /// [`ResourceGroup`] which frees their GPU memory.
+    pub fn reload(
+        &mut self,
+        model: &jeriya_content::model::ModelAsset,
+        resource_group: &mut ResourceGroup,
+        element_group: &mut ElementGroup,
+        transaction: &mut impl PushEvent,
+    ) -> Result<()> {
+        if model.meshes.len() != self.rigid_meshes.len() {
+            return Err(Error::MeshCountChanged {
+                expected: self.rigid_meshes.len(),
+                actual: model.meshes.len(),
+            });
+        }
+
+        // All MeshAttributes are inserted before any RigidMesh is changed so that a failure leaves the collection intact
+        let mut mesh_attributes = Vec::with_capacity(model.meshes.len());
+        for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+            match insert_attributes(&model.name, mesh_index, mesh, resource_group) {
+                Ok(inserted) => mesh_attributes.push(inserted),
+                Err(err) => {
+                    for inserted in &mesh_attributes {
+                        resource_group.mesh_attributes().remove(inserted.handle());
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        for (rigid_mesh, mesh_attributes) in self.rigid_meshes.iter().zip(&mesh_attributes) {
+            let previous_mesh_attributes = element_group
+                .rigid_meshes()
+                .mutate_via(transaction)
+                .set_mesh_attributes(rigid_mesh, mesh_attributes.clone())
+                .ok_or(Error::RigidMeshNotFound)?;
+            resource_group.mesh_attributes().remove(previous_mesh_attributes.handle());
+        }
+        self.mesh_attributes = mesh_attributes;
+
+        Ok(())
+    }
+
     /// Returns the [`MeshAttributes`]es.
     pub fn mesh_attributes(&self) -> &[Arc<MeshAttributes>] {
         &self.mesh_attributes
@@ -64,23 +113,32 @@ impl RigidMeshCollection {
     }
 }
 
-/// Inserts the [`MeshAttributes`] and [`RigidMesh`] into the [`ResourceGroup`] and [`ElementGroup`].
-fn insert_attributes_and_mesh(
+/// Inserts the [`MeshAttributes`] of the mesh into the [`ResourceGroup`].
+fn insert_attributes(
     model_name: &str,
     mesh_index: usize,
     mesh: &jeriya_content::model::Mesh,
     resource_group: &mut ResourceGroup,
-    element_group: &mut ElementGroup,
-    transaction: &mut impl PushEvent,
-) -> Result<(Arc<MeshAttributes>, Handle<RigidMesh>)> {
-    // Insert the MeshAttributes
+) -> Result<Arc<MeshAttributes>> {
     let mesh_attributes_builder = MeshAttributes::builder()
         .with_debug_info(debug_info!(format!("MeshAttributes-Model-{}-Mesh-{}", model_name, mesh_index)))
         .with_vertex_positions(mesh.simple_mesh.vertex_positions.clone())
         .with_vertex_normals(mesh.simple_mesh.vertex_normals.clone())
         .with_indices(mesh.simple_mesh.indices.clone())
         .with_meshlets(mesh.meshlets.clone());
-    let mesh_attributes = resource_group.mesh_attributes().insert_with(mesh_attributes_builder)?;
+    Ok(resource_group.mesh_attributes().insert_with(mesh_attributes_builder)?)
+}
+
+/// Inserts the [`MeshAttributes`] and [`RigidMesh`] into the [`ResourceGroup`] and [`ElementGroup`].
+fn insert_attributes_and_mesh(
+    model_name: &str,
+    mesh_index: usize,
+    mesh: &jeriya_content::model::Mesh,
+    resource_group: &mut ResourceGroup,
+    element_group: &mut ElementGroup,
+    transaction: &mut impl PushEvent,
+) -> Result<(Arc<MeshAttributes>, Handle<RigidMesh>)> {
+    let mesh_attributes = insert_attributes(model_name, mesh_index, mesh, resource_group)?;
 
     // Insert the RigidMesh
     let rigid_mesh_builder = RigidMesh::builder()
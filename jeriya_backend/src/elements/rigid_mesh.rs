@@ -18,6 +18,8 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Event {
     Noop,
     Insert(RigidMesh),
+    /// The [`RigidMesh`] changed and has to be written to the GPU again
+    Update(RigidMesh),
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -47,6 +49,11 @@ impl RigidMesh {
         &self.mesh_attributes
     }
 
+    /// Sets the [`MeshAttributes`] of the [`RigidMesh`] and returns the previous ones
+    pub(crate) fn set_mesh_attributes(&mut self, mesh_attributes: Arc<MeshAttributes>) -> Arc<MeshAttributes> {
+        std::mem::replace(&mut self.mesh_attributes, mesh_attributes)
+    }
+
     /// Returns the preferred [`MeshRepresentation`] of the [`RigidMesh`]
     pub fn preferred_mesh_representation(&self) -> &MeshRepresentation {
         &self.preferred_mesh_representation
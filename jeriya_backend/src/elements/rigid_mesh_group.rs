@@ -4,6 +4,7 @@ use jeriya_shared::{DebugInfo, Handle, IndexingContainer};
 
 use crate::{
     gpu_index_allocator::{AllocateGpuIndex, ProvideAllocateGpuIndex},
+    resources::mesh_attributes::MeshAttributes,
     transactions::{self, PushEvent},
 };
 
@@ -79,6 +80,19 @@ impl<'g, 't, P: PushEvent> RigidMeshGroupAccessMut<'g, 't, P> {
                 handle
             })
     }
+
+    /// Replaces the [`MeshAttributes`] of the [`RigidMesh`] with the given [`Handle`] and returns the previous ones.
+    ///
+    /// The [`RigidMeshInstance`](crate::instances::rigid_mesh_instance::RigidMeshInstance)s of the [`RigidMesh`] render
+    /// the new [`MeshAttributes`] as soon as the transaction is processed. Returns `None` when the [`Handle`] doesn't
+    /// reference a [`RigidMesh`] in the [`RigidMeshGroup`].
+    pub fn set_mesh_attributes(&mut self, handle: &Handle<RigidMesh>, mesh_attributes: Arc<MeshAttributes>) -> Option<Arc<MeshAttributes>> {
+        let rigid_mesh = self.rigid_mesh_group.indexing_container.get_mut(handle)?;
+        let previous_mesh_attributes = rigid_mesh.set_mesh_attributes(mesh_attributes);
+        self.transaction
+            .push_event(transactions::Event::RigidMesh(rigid_mesh::Event::Update(rigid_mesh.clone())));
+        Some(previous_mesh_attributes)
+    }
 }
 
 #[cfg(test)]
@@ -120,4 +134,34 @@ mod tests {
         // Assert GpuIndexAllocator
         assert_eq!(renderer_mock.backend.rigid_mesh_gpu_index_allocator.lock().len(), 1);
     }
+
+    #[test]
+    fn set_mesh_attributes() {
+        let mesh_attributes = new_dummy_mesh_attributes();
+        let new_mesh_attributes = new_dummy_mesh_attributes();
+
+        let renderer_mock = elements::MockRenderer::new();
+        let mut transaction = Transaction::new();
+        let mut rigid_mesh_group = RigidMeshGroup::new(&renderer_mock, debug_info!("my_rigid_mesh_group"));
+        let rigid_mesh_builder = RigidMesh::builder()
+            .with_mesh_attributes(mesh_attributes.clone())
+            .with_debug_info(debug_info!("my_rigid_mesh"));
+        let rigid_mesh_handle = rigid_mesh_group
+            .mutate_via(&mut transaction)
+            .insert_with(rigid_mesh_builder)
+            .unwrap();
+
+        let previous = rigid_mesh_group
+            .mutate_via(&mut transaction)
+            .set_mesh_attributes(&rigid_mesh_handle, new_mesh_attributes.clone())
+            .unwrap();
+        assert!(Arc::ptr_eq(&previous, &mesh_attributes));
+        let rigid_mesh = rigid_mesh_group.get(&rigid_mesh_handle).unwrap();
+        assert!(Arc::ptr_eq(rigid_mesh.mesh_attributes(), &new_mesh_attributes));
+
+        // Assert Transaction
+        assert_eq!(transaction.len(), 2);
+        let last = transaction.process().into_iter().last().unwrap();
+        assert!(matches!(last, transactions::Event::RigidMesh(rigid_mesh::Event::Update(_))));
+    }
 }
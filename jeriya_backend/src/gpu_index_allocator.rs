@@ -6,6 +6,26 @@ use jeriya_shared::derive_where::derive_where;
 pub trait AllocateGpuIndex<T>: Send + Sync {
     fn allocate_gpu_index(&self) -> Option<GpuIndexAllocation<T>>;
     fn free_gpu_index(&self, gpu_index_allocation: GpuIndexAllocation<T>);
+
+    /// Allocates `count` indices at once. Either all or none of the indices are allocated.
+    ///
+    /// The default implementation calls [`AllocateGpuIndex::allocate_gpu_index`] for every index. Implementations
+    /// that have to lock the allocator should override it to lock only once.
+    fn allocate_gpu_indices(&self, count: usize) -> Option<Vec<GpuIndexAllocation<T>>> {
+        let mut gpu_index_allocations = Vec::with_capacity(count);
+        for _ in 0..count {
+            match self.allocate_gpu_index() {
+                Some(gpu_index_allocation) => gpu_index_allocations.push(gpu_index_allocation),
+                None => {
+                    for gpu_index_allocation in gpu_index_allocations {
+                        self.free_gpu_index(gpu_index_allocation);
+                    }
+                    return None;
+                }
+            }
+        }
+        Some(gpu_index_allocations)
+    }
 }
 
 /// Trait that is implemented by the renderer to provide a [`AllocateGpuIndex`] implementation.
@@ -46,6 +66,23 @@ impl<T> GpuIndexAllocator<T> {
         }
     }
 
+    /// Allocates `count` indices at once. Either all or none of the indices are allocated.
+    pub fn allocate_gpu_indices(&mut self, count: usize) -> Option<Vec<GpuIndexAllocation<T>>> {
+        if self.free_list.len() + (self.capacity - self.next_index) < count {
+            return None;
+        }
+        let from_free_list = count.min(self.free_list.len());
+        let mut gpu_index_allocations = self
+            .free_list
+            .drain(..from_free_list)
+            .map(GpuIndexAllocation::new_unchecked)
+            .collect::<Vec<_>>();
+        let remaining = count - from_free_list;
+        gpu_index_allocations.extend((self.next_index..self.next_index + remaining).map(GpuIndexAllocation::new_unchecked));
+        self.next_index += remaining;
+        Some(gpu_index_allocations)
+    }
+
     /// Frees the given index
     pub fn free_gpu_index(&mut self, gpu_index_allocation: GpuIndexAllocation<T>) {
         self.free_list.push_back(gpu_index_allocation.index());
@@ -113,4 +150,22 @@ mod tests {
         assert_eq!(allocator.len(), 0);
         assert!(allocator.is_empty());
     }
+
+    #[test]
+    fn allocate_gpu_indices() {
+        let mut allocator = GpuIndexAllocator::<u32>::new(4);
+        let a1 = allocator.allocate_gpu_index().unwrap();
+        allocator.allocate_gpu_index().unwrap();
+        allocator.free_gpu_index(a1);
+
+        // Not enough indices left so nothing is allocated
+        assert_eq!(allocator.allocate_gpu_indices(4), None);
+        assert_eq!(allocator.len(), 1);
+
+        let indices = allocator.allocate_gpu_indices(3).unwrap();
+        assert_eq!(indices.iter().map(GpuIndexAllocation::index).collect::<Vec<_>>(), vec![0, 2, 3]);
+        assert_eq!(allocator.len(), 4);
+        assert_eq!(allocator.allocate_gpu_index(), None);
+        assert_eq!(allocator.allocate_gpu_indices(0), Some(Vec::new()));
+    }
 }
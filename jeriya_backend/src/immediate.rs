@@ -3,11 +3,14 @@ use std::{
     time::{Duration, Instant},
 };
 
+use jeriya_content::font::FontAsset;
 use jeriya_shared::{
-    nalgebra::{Matrix4, Vector3, Vector4},
+    nalgebra::{Matrix4, Vector2, Vector3, Vector4},
     AsDebugInfo, DebugInfo,
 };
 
+use crate::overlay::OverlayCommandBuffer;
+
 /// Identifies a frame for immediate rendering.
 ///
 /// Create an `ImmediateRenderingFrame` with [`ImmediateRenderingFrame::new`] once per frame
@@ -58,6 +61,8 @@ pub struct ImmediateRenderingFrameTask {
     pub is_timed_out: bool,
     pub immediate_rendering_frame: ImmediateRenderingFrame,
     pub command_buffers: Vec<CommandBuffer>,
+    /// Command buffers that are rendered on top of the scene
+    pub overlay_command_buffers: Vec<OverlayCommandBuffer>,
 }
 
 /// Timeout for an [`ImmediateRenderingFrame`]
@@ -80,11 +85,121 @@ impl Timeout {
     }
 }
 
+/// Coordinate system in which the positions of the immediate points, lines and triangles are given
+///
+/// The matrix that is set with [`CommandBufferBuilder::matrix`] is applied in every `ImmediateSpace`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImmediateSpace {
+    /// The positions are transformed by the active camera and occluded by the scene.
+    #[default]
+    World,
+    /// The positions are given in pixels from the upper left corner of the window. The primitives are rendered on top of the scene.
+    Pixels,
+    /// The positions are given from (0, 0) in the upper left to (1, 1) in the lower right corner of the window. The
+    /// primitives are rendered on top of the scene.
+    Normalized,
+}
+
+/// Configuration for immediate point rendering
+#[derive(Debug, Clone)]
+pub struct PointConfig {
+    pub color: Vector4<f32>,
+    /// Diameter of the points in pixels
+    pub size: f32,
+}
+
+impl Default for PointConfig {
+    fn default() -> Self {
+        Self {
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            size: 1.0,
+        }
+    }
+}
+
+/// Individual points for immediate rendering
+#[derive(Debug, Clone)]
+pub struct PointList {
+    positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<Vector4<f32>>>,
+    sizes: Option<Vec<f32>>,
+    config: PointConfig,
+}
+
+impl PointList {
+    /// Creates a new `PointList` from the given positions
+    pub fn new(positions: Vec<Vector3<f32>>, config: PointConfig) -> Self {
+        Self {
+            positions,
+            colors: None,
+            sizes: None,
+            config,
+        }
+    }
+
+    /// Sets a color for every point of the `PointList`. The colors are multiplied with the color of the [`PointConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of colors doesn't match the number of positions.
+    pub fn with_colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.positions.len(),
+            "Number of colors must match the number of positions"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Sets a size in pixels for every point of the `PointList`. The sizes replace the size of the [`PointConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of sizes doesn't match the number of positions.
+    pub fn with_sizes(mut self, sizes: Vec<f32>) -> Self {
+        assert_eq!(
+            sizes.len(),
+            self.positions.len(),
+            "Number of sizes must match the number of positions"
+        );
+        self.sizes = Some(sizes);
+        self
+    }
+
+    /// Returns the positions of the `PointList`
+    pub fn positions(&self) -> &[Vector3<f32>] {
+        &self.positions
+    }
+
+    /// Returns the colors of the points or `None` if only the color of the [`PointConfig`] is used
+    pub fn colors(&self) -> Option<&[Vector4<f32>]> {
+        self.colors.as_deref()
+    }
+
+    /// Returns the size of the point with the given `index`
+    pub fn size(&self, index: usize) -> f32 {
+        match &self.sizes {
+            Some(sizes) => sizes[index],
+            None => self.config.size,
+        }
+    }
+
+    /// Returns the [`PointConfig`] of the `PointList`
+    pub fn config(&self) -> &PointConfig {
+        &self.config
+    }
+}
+
 /// Configuration for immediate line rendering
 #[derive(Debug, Clone)]
 pub struct LineConfig {
     pub color: Vector4<f32>,
     pub line_width: f32,
+    /// Whether the lines are occluded by the scene. Lines without depth test are drawn on top of the scene.
+    ///
+    /// Only applies to [`ImmediateSpace::World`] because the screen spaces are always drawn on top of the scene.
+    pub depth_test: bool,
 }
 
 impl Default for LineConfig {
@@ -92,6 +207,7 @@ impl Default for LineConfig {
         Self {
             color: Vector4::new(1.0, 1.0, 1.0, 1.0),
             line_width: 1.0,
+            depth_test: true,
         }
     }
 }
@@ -100,6 +216,7 @@ impl Default for LineConfig {
 #[derive(Debug, Clone)]
 pub struct LineList {
     positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<Vector4<f32>>>,
     config: LineConfig,
 }
 
@@ -111,7 +228,11 @@ impl LineList {
     /// - Panics if the number of positions is not even.
     pub fn new(positions: Vec<Vector3<f32>>, config: LineConfig) -> Self {
         assert!(positions.len() % 2 == 0, "Number of vertices must be even");
-        Self { positions, config }
+        Self {
+            positions,
+            colors: None,
+            config,
+        }
     }
 
     /// Returns the vertices of the `LineList`
@@ -119,6 +240,26 @@ impl LineList {
         &self.positions
     }
 
+    /// Sets a color for every vertex of the `LineList`. The vertex colors are multiplied with the color of the [`LineConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of colors doesn't match the number of positions.
+    pub fn with_colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.positions.len(),
+            "Number of colors must match the number of positions"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Returns the vertex colors of the `LineList` or `None` if only the color of the [`LineConfig`] is used
+    pub fn colors(&self) -> Option<&[Vector4<f32>]> {
+        self.colors.as_deref()
+    }
+
     /// Returns the [`LineConfig`] of the `LineList`
     pub fn config(&self) -> &LineConfig {
         &self.config
@@ -129,13 +270,18 @@ impl LineList {
 #[derive(Debug, Clone)]
 pub struct LineStrip {
     positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<Vector4<f32>>>,
     config: LineConfig,
 }
 
 impl LineStrip {
     /// Creates a new `LineStrip` from the given positions
     pub fn new(positions: Vec<Vector3<f32>>, config: LineConfig) -> Self {
-        Self { positions, config }
+        Self {
+            positions,
+            colors: None,
+            config,
+        }
     }
 
     /// Returns the positions of the `LineStrip`
@@ -143,6 +289,26 @@ impl LineStrip {
         &self.positions
     }
 
+    /// Sets a color for every vertex of the `LineStrip`. The vertex colors are multiplied with the color of the [`LineConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of colors doesn't match the number of positions.
+    pub fn with_colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.positions.len(),
+            "Number of colors must match the number of positions"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Returns the vertex colors of the `LineStrip` or `None` if only the color of the [`LineConfig`] is used
+    pub fn colors(&self) -> Option<&[Vector4<f32>]> {
+        self.colors.as_deref()
+    }
+
     /// Returns the [`LineConfig`] of the `LineStrip`
     pub fn config(&self) -> &LineConfig {
         &self.config
@@ -153,12 +319,17 @@ impl LineStrip {
 #[derive(Debug, Clone)]
 pub struct TriangleConfig {
     pub color: Vector4<f32>,
+    /// Whether the triangles are occluded by the scene. Triangles without depth test are drawn on top of the scene.
+    ///
+    /// Only applies to [`ImmediateSpace::World`] because the screen spaces are always drawn on top of the scene.
+    pub depth_test: bool,
 }
 
 impl Default for TriangleConfig {
     fn default() -> Self {
         Self {
             color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            depth_test: true,
         }
     }
 }
@@ -167,6 +338,7 @@ impl Default for TriangleConfig {
 #[derive(Debug, Clone)]
 pub struct TriangleList {
     positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<Vector4<f32>>>,
     config: TriangleConfig,
 }
 
@@ -178,7 +350,11 @@ impl TriangleList {
     /// - Panics if the number of positions is not a multiple of 3.
     pub fn new(positions: Vec<Vector3<f32>>, config: TriangleConfig) -> Self {
         assert!(positions.len() % 3 == 0, "Number of vertices must be a multiple of 3");
-        Self { positions, config }
+        Self {
+            positions,
+            colors: None,
+            config,
+        }
     }
 
     /// Returns the positions of the `LineStrip`
@@ -186,6 +362,26 @@ impl TriangleList {
         &self.positions
     }
 
+    /// Sets a color for every vertex of the `TriangleList`. The vertex colors are multiplied with the color of the [`TriangleConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of colors doesn't match the number of positions.
+    pub fn with_colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.positions.len(),
+            "Number of colors must match the number of positions"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Returns the vertex colors of the `TriangleList` or `None` if only the color of the [`TriangleConfig`] is used
+    pub fn colors(&self) -> Option<&[Vector4<f32>]> {
+        self.colors.as_deref()
+    }
+
     /// Returns the [`TriangleConfig`] of the `TriangleStrip`
     pub fn config(&self) -> &TriangleConfig {
         &self.config
@@ -196,13 +392,18 @@ impl TriangleList {
 #[derive(Debug, Clone)]
 pub struct TriangleStrip {
     positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<Vector4<f32>>>,
     config: TriangleConfig,
 }
 
 impl TriangleStrip {
     /// Creates a new `TriangleStrip` from the given positions
     pub fn new(positions: Vec<Vector3<f32>>, config: TriangleConfig) -> Self {
-        Self { positions, config }
+        Self {
+            positions,
+            colors: None,
+            config,
+        }
     }
 
     /// Returns the positions of the `TriangleStrip`
@@ -210,33 +411,232 @@ impl TriangleStrip {
         &self.positions
     }
 
+    /// Sets a color for every vertex of the `TriangleStrip`. The vertex colors are multiplied with the color of the [`TriangleConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of colors doesn't match the number of positions.
+    pub fn with_colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.positions.len(),
+            "Number of colors must match the number of positions"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Returns the vertex colors of the `TriangleStrip` or `None` if only the color of the [`TriangleConfig`] is used
+    pub fn colors(&self) -> Option<&[Vector4<f32>]> {
+        self.colors.as_deref()
+    }
+
     /// Returns the [`TriangleConfig`] of the `TriangleStrip`
     pub fn config(&self) -> &TriangleConfig {
         &self.config
     }
 }
 
+/// Indexed triangles for immediate rendering
+///
+/// The vertices are shared between the triangles so that meshes like spheres don't have to be expanded into a [`TriangleList`].
+#[derive(Debug, Clone)]
+pub struct IndexedTriangleList {
+    positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<Vector4<f32>>>,
+    indices: Vec<u32>,
+    config: TriangleConfig,
+}
+
+impl IndexedTriangleList {
+    /// Creates a new `IndexedTriangleList` from the given positions and indices into the positions
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of indices is not a multiple of 3.
+    /// - Panics if an index is out of bounds of the positions.
+    pub fn new(positions: Vec<Vector3<f32>>, indices: Vec<u32>, config: TriangleConfig) -> Self {
+        assert!(indices.len().is_multiple_of(3), "Number of indices must be a multiple of 3");
+        assert!(
+            indices.iter().all(|index| (*index as usize) < positions.len()),
+            "Indices must be smaller than the number of positions"
+        );
+        Self {
+            positions,
+            colors: None,
+            indices,
+            config,
+        }
+    }
+
+    /// Returns the positions of the `IndexedTriangleList`
+    pub fn positions(&self) -> &[Vector3<f32>] {
+        &self.positions
+    }
+
+    /// Sets a color for every vertex of the `IndexedTriangleList`. The vertex colors are multiplied with the color of the [`TriangleConfig`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of colors doesn't match the number of positions.
+    pub fn with_colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.positions.len(),
+            "Number of colors must match the number of positions"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Returns the vertex colors of the `IndexedTriangleList` or `None` if only the color of the [`TriangleConfig`] is used
+    pub fn colors(&self) -> Option<&[Vector4<f32>]> {
+        self.colors.as_deref()
+    }
+
+    /// Returns the indices into the positions of the `IndexedTriangleList`
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Returns the [`TriangleConfig`] of the `IndexedTriangleList`
+    pub fn config(&self) -> &TriangleConfig {
+        &self.config
+    }
+}
+
+/// Coordinate system in which a [`Text`] is placed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextSpace {
+    /// The text lies in the xy-plane of the current matrix and its size is given in world units. It is occluded by the scene.
+    #[default]
+    World,
+    /// The position is given in pixels from the upper left corner of the window and the size in pixels. The
+    /// current matrix is ignored and the text is rendered on top of the scene.
+    Screen,
+    /// The position is given in world units and transformed by the current matrix. The text always faces the camera,
+    /// its size is given in pixels and it is rendered on top of the scene.
+    Billboard,
+}
+
+/// Configuration for immediate text rendering
+#[derive(Debug, Clone)]
+pub struct TextConfig {
+    pub color: Vector4<f32>,
+    /// Height of an em in the units of the [`TextSpace`]
+    pub size: f32,
+    pub space: TextSpace,
+}
+
+impl Default for TextConfig {
+    fn default() -> Self {
+        Self {
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            size: 1.0,
+            space: TextSpace::World,
+        }
+    }
+}
+
+/// Label for immediate rendering that is drawn with the signed distance field atlas of a [`FontAsset`]
+#[derive(Debug, Clone)]
+pub struct Text {
+    text: String,
+    font: Arc<FontAsset>,
+    position: Vector3<f32>,
+    config: TextConfig,
+}
+
+impl Text {
+    /// Creates a new `Text` whose first baseline starts at the given `position`
+    pub fn new(text: impl Into<String>, font: Arc<FontAsset>, position: Vector3<f32>, config: TextConfig) -> Self {
+        Self {
+            text: text.into(),
+            font,
+            position,
+            config,
+        }
+    }
+
+    /// Returns the text of the `Text`
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the [`FontAsset`] with which the `Text` is rendered
+    pub fn font(&self) -> &Arc<FontAsset> {
+        &self.font
+    }
+
+    /// Returns the position of the `Text`
+    pub fn position(&self) -> &Vector3<f32> {
+        &self.position
+    }
+
+    /// Returns the [`TextConfig`] of the `Text`
+    pub fn config(&self) -> &TextConfig {
+        &self.config
+    }
+
+    /// Returns the positions and texture coordinates of the triangle list that displays the glyphs of the `Text`
+    ///
+    /// For [`TextSpace::Billboard`] the positions are offsets in pixels from the projected position of the `Text`.
+    pub fn vertices(&self) -> (Vec<Vector3<f32>>, Vec<Vector2<f32>>) {
+        // The layout has the y-axis pointing up whereas the y-axis of the screen points down
+        let (origin, y_direction) = match self.config.space {
+            TextSpace::World => (self.position, 1.0),
+            TextSpace::Screen => (self.position, -1.0),
+            TextSpace::Billboard => (Vector3::zeros(), 1.0),
+        };
+        let to_position = |x: f32, y: f32| origin + Vector3::new(x, y * y_direction, 0.0) * self.config.size;
+        let quads = self.font.layout(&self.text);
+        let mut positions = Vec::with_capacity(quads.len() * 6);
+        let mut texture_coordinates = Vec::with_capacity(quads.len() * 6);
+        for quad in quads {
+            let corners = [
+                (quad.min.x, quad.min.y, quad.uv_min.x, quad.uv_max.y),
+                (quad.max.x, quad.min.y, quad.uv_max.x, quad.uv_max.y),
+                (quad.max.x, quad.max.y, quad.uv_max.x, quad.uv_min.y),
+                (quad.min.x, quad.max.y, quad.uv_min.x, quad.uv_min.y),
+            ];
+            for index in [0, 1, 2, 0, 2, 3] {
+                let (x, y, u, v) = corners[index];
+                positions.push(to_position(x, y));
+                texture_coordinates.push(Vector2::new(u, v));
+            }
+        }
+        (positions, texture_coordinates)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ImmediateCommand {
     Matrix(Matrix4<f32>),
+    Space(ImmediateSpace),
+    PointList(PointList),
     LineList(LineList),
     LineStrip(LineStrip),
     TriangleList(TriangleList),
     TriangleStrip(TriangleStrip),
+    IndexedTriangleList(IndexedTriangleList),
+    Text(Text),
 }
 
 /// Command buffer for immediate rendering.
+///
+/// The commands are shared between the clones of a `CommandBuffer` so that the backend can reuse the vertex data of a
+/// `CommandBuffer` that is rendered in multiple frames.
 #[derive(Clone)]
 pub struct CommandBuffer {
-    commands: Vec<ImmediateCommand>,
+    commands: Arc<Vec<ImmediateCommand>>,
     debug_info: DebugInfo,
 }
 
 impl CommandBuffer {
-    /// Creates a new `CommandBuffer` from the given `command_buffer`.
-    pub fn new(debug_info: DebugInfo) -> Self {
+    /// Creates a new `CommandBuffer` from the given `commands`.
+    pub fn new(commands: Vec<ImmediateCommand>, debug_info: DebugInfo) -> Self {
         Self {
-            commands: Vec::new(),
+            commands: Arc::new(commands),
             debug_info,
         }
     }
@@ -244,6 +644,13 @@ impl CommandBuffer {
     pub fn commands(&self) -> &[ImmediateCommand] {
         &self.commands
     }
+
+    /// Returns an identifier that is shared by all clones of the `CommandBuffer`.
+    ///
+    /// The identifier might be reused by another `CommandBuffer` after all clones have been dropped.
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.commands) as usize
+    }
 }
 
 impl AsDebugInfo for CommandBuffer {
@@ -254,20 +661,39 @@ impl AsDebugInfo for CommandBuffer {
 
 /// Creates new command buffer in the [`ImmediateRenderingBackend`].
 pub struct CommandBufferBuilder {
-    command_buffer: CommandBuffer,
+    commands: Vec<ImmediateCommand>,
+    debug_info: DebugInfo,
 }
 
 impl CommandBufferBuilder {
     /// Creates a new `CommandBufferBuilder`.
     pub fn new(debug_info: DebugInfo) -> Self {
         Self {
-            command_buffer: CommandBuffer::new(debug_info),
+            commands: Vec::new(),
+            debug_info,
         }
     }
 
     /// Sets the matrix to be used for the following draw calls.
     pub fn matrix(mut self, matrix: Matrix4<f32>) -> crate::Result<Self> {
-        self.command_buffer.commands.push(ImmediateCommand::Matrix(matrix));
+        self.commands.push(ImmediateCommand::Matrix(matrix));
+        Ok(self)
+    }
+
+    /// Sets the [`ImmediateSpace`] in which the positions of the following points, lines and triangles are given.
+    pub fn space(mut self, space: ImmediateSpace) -> crate::Result<Self> {
+        self.commands.push(ImmediateCommand::Space(space));
+        Ok(self)
+    }
+
+    /// Pushes new [`PointList`]s to the `CommandBufferBuilder`.
+    pub fn push_point_lists(mut self, point_lists: &[PointList]) -> crate::Result<Self> {
+        for point_list in point_lists {
+            if point_list.positions().is_empty() {
+                continue;
+            }
+            self.commands.push(ImmediateCommand::PointList(point_list.clone()));
+        }
         Ok(self)
     }
 
@@ -277,7 +703,7 @@ impl CommandBufferBuilder {
             if line_list.positions().is_empty() {
                 continue;
             }
-            self.command_buffer.commands.push(ImmediateCommand::LineList(line_list.clone()));
+            self.commands.push(ImmediateCommand::LineList(line_list.clone()));
         }
         Ok(self)
     }
@@ -288,7 +714,7 @@ impl CommandBufferBuilder {
             if line_strip.positions().is_empty() {
                 continue;
             }
-            self.command_buffer.commands.push(ImmediateCommand::LineStrip(line_strip.clone()));
+            self.commands.push(ImmediateCommand::LineStrip(line_strip.clone()));
         }
         Ok(self)
     }
@@ -299,9 +725,7 @@ impl CommandBufferBuilder {
             if triangle_list.positions().is_empty() {
                 continue;
             }
-            self.command_buffer
-                .commands
-                .push(ImmediateCommand::TriangleList(triangle_list.clone()));
+            self.commands.push(ImmediateCommand::TriangleList(triangle_list.clone()));
         }
         Ok(self)
     }
@@ -312,21 +736,245 @@ impl CommandBufferBuilder {
             if triangle_strip.positions().is_empty() {
                 continue;
             }
-            self.command_buffer
-                .commands
-                .push(ImmediateCommand::TriangleStrip(triangle_strip.clone()));
+            self.commands.push(ImmediateCommand::TriangleStrip(triangle_strip.clone()));
+        }
+        Ok(self)
+    }
+
+    /// Pushes triangles whose vertices are given by `indices` into the `positions` to the `CommandBufferBuilder`
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of indices is not a multiple of 3.
+    /// - Panics if an index is out of bounds of the `positions`.
+    pub fn push_indexed_triangles(
+        mut self,
+        positions: Vec<Vector3<f32>>,
+        indices: Vec<u32>,
+        config: TriangleConfig,
+    ) -> crate::Result<Self> {
+        if indices.is_empty() {
+            return Ok(self);
+        }
+        let indexed_triangle_list = IndexedTriangleList::new(positions, indices, config);
+        self.commands.push(ImmediateCommand::IndexedTriangleList(indexed_triangle_list));
+        Ok(self)
+    }
+
+    /// Pushes new [`IndexedTriangleList`]s to the `CommandBufferBuilder`
+    pub fn push_indexed_triangle_lists(mut self, indexed_triangle_lists: &[IndexedTriangleList]) -> crate::Result<Self> {
+        for indexed_triangle_list in indexed_triangle_lists {
+            if indexed_triangle_list.indices().is_empty() {
+                continue;
+            }
+            self.commands
+                .push(ImmediateCommand::IndexedTriangleList(indexed_triangle_list.clone()));
+        }
+        Ok(self)
+    }
+
+    /// Pushes new [`Text`]s to the `CommandBufferBuilder`
+    pub fn push_texts(mut self, texts: &[Text]) -> crate::Result<Self> {
+        for text in texts {
+            if text.text().trim().is_empty() {
+                continue;
+            }
+            self.commands.push(ImmediateCommand::Text(text.clone()));
         }
         Ok(self)
     }
 
+    /// Pushes a label at the `position` in the world that always faces the camera
+    ///
+    /// The size of the [`TextConfig`] is given in pixels and its space is replaced by [`TextSpace::Billboard`].
+    pub fn push_text_3d(
+        self,
+        position: Vector3<f32>,
+        text: impl Into<String>,
+        font: Arc<FontAsset>,
+        config: TextConfig,
+    ) -> crate::Result<Self> {
+        let config = TextConfig {
+            space: TextSpace::Billboard,
+            ..config
+        };
+        self.push_texts(&[Text::new(text, font, position, config)])
+    }
+
     /// Finalizes the creation of the [`CommandBuffer`].
     pub fn build(self) -> crate::Result<CommandBuffer> {
-        Ok(self.command_buffer)
+        Ok(CommandBuffer::new(self.commands, self.debug_info))
     }
 }
 
 impl AsDebugInfo for CommandBufferBuilder {
     fn as_debug_info(&self) -> &DebugInfo {
-        self.command_buffer.as_debug_info()
+        &self.debug_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use jeriya_content::font::Glyph;
+    use jeriya_shared::debug_info;
+
+    use super::*;
+
+    fn font() -> Arc<FontAsset> {
+        let mut glyphs = BTreeMap::new();
+        glyphs.insert(
+            'A',
+            Glyph {
+                uv_min: Vector2::new(0.0, 0.0),
+                uv_max: Vector2::new(0.5, 1.0),
+                size: Vector2::new(0.5, 1.0),
+                offset: Vector2::new(0.0, 0.0),
+                advance: 0.5,
+            },
+        );
+        Arc::new(FontAsset::new(2, 1, vec![255, 0], glyphs, 1.0, 0.0, 1.0))
+    }
+
+    #[test]
+    fn text_vertices() {
+        let position = Vector3::new(1.0, 2.0, 3.0);
+        let world = Text::new(
+            "AA",
+            font(),
+            position,
+            TextConfig {
+                size: 2.0,
+                ..Default::default()
+            },
+        );
+        let (positions, texture_coordinates) = world.vertices();
+        assert_eq!(positions.len(), 12);
+        assert_eq!(texture_coordinates.len(), 12);
+        assert_eq!(positions[0], Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(texture_coordinates[0], Vector2::new(0.0, 1.0));
+        assert_eq!(positions[2], Vector3::new(2.0, 4.0, 3.0));
+        assert_eq!(texture_coordinates[2], Vector2::new(0.5, 0.0));
+        // The second glyph starts where the first one ends
+        assert_eq!(positions[6], Vector3::new(2.0, 2.0, 3.0));
+
+        let screen_config = TextConfig {
+            size: 2.0,
+            space: TextSpace::Screen,
+            ..Default::default()
+        };
+        let (positions, _) = Text::new("A", font(), position, screen_config).vertices();
+        assert_eq!(positions[2], Vector3::new(2.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn line_list_with_colors() {
+        let positions = vec![Vector3::zeros(), Vector3::x()];
+        let line_list = LineList::new(positions.clone(), LineConfig::default());
+        assert_eq!(line_list.colors(), None);
+        let colors = vec![Vector4::new(1.0, 0.0, 0.0, 1.0), Vector4::new(0.0, 0.0, 1.0, 1.0)];
+        let line_list = line_list.with_colors(colors.clone());
+        assert_eq!(line_list.colors(), Some(colors.as_slice()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn triangle_list_with_too_few_colors() {
+        let positions = vec![Vector3::zeros(), Vector3::x(), Vector3::y()];
+        TriangleList::new(positions, TriangleConfig::default()).with_colors(vec![Vector4::zeros()]);
+    }
+
+    #[test]
+    fn point_list_sizes() {
+        let positions = vec![Vector3::zeros(), Vector3::x()];
+        let config = PointConfig {
+            size: 4.0,
+            ..Default::default()
+        };
+        let point_list = PointList::new(positions, config);
+        assert_eq!(point_list.size(1), 4.0);
+        let point_list = point_list.with_sizes(vec![2.0, 8.0]);
+        assert_eq!(point_list.size(0), 2.0);
+        assert_eq!(point_list.size(1), 8.0);
+    }
+
+    #[test]
+    fn push_indexed_triangles() {
+        let positions = vec![Vector3::zeros(), Vector3::x(), Vector3::y(), Vector3::z()];
+        let command_buffer = CommandBufferBuilder::new(debug_info!("my_command_buffer"))
+            .push_indexed_triangles(positions.clone(), vec![0, 1, 2, 0, 2, 3], TriangleConfig::default())
+            .unwrap()
+            .push_indexed_triangles(positions, Vec::new(), TriangleConfig::default())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(command_buffer.commands().len(), 1);
+        let ImmediateCommand::IndexedTriangleList(indexed_triangle_list) = &command_buffer.commands()[0] else {
+            panic!("expected an IndexedTriangleList");
+        };
+        assert_eq!(indexed_triangle_list.positions().len(), 4);
+        assert_eq!(indexed_triangle_list.indices(), &[0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn push_indexed_triangle_lists_with_colors() {
+        let positions = vec![Vector3::zeros(), Vector3::x(), Vector3::y()];
+        let colors = vec![Vector4::new(1.0, 0.0, 0.0, 1.0); 3];
+        let indexed_triangle_list =
+            IndexedTriangleList::new(positions, vec![0, 1, 2], TriangleConfig::default()).with_colors(colors.clone());
+        let command_buffer = CommandBufferBuilder::new(debug_info!("my_command_buffer"))
+            .push_indexed_triangle_lists(&[indexed_triangle_list])
+            .unwrap()
+            .build()
+            .unwrap();
+        let ImmediateCommand::IndexedTriangleList(indexed_triangle_list) = &command_buffer.commands()[0] else {
+            panic!("expected an IndexedTriangleList");
+        };
+        assert_eq!(indexed_triangle_list.colors(), Some(colors.as_slice()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn indexed_triangle_list_index_out_of_bounds() {
+        IndexedTriangleList::new(vec![Vector3::zeros(); 3], vec![0, 1, 3], TriangleConfig::default());
+    }
+
+    #[test]
+    fn push_text_3d() {
+        let command_buffer = CommandBufferBuilder::new(debug_info!("my_command_buffer"))
+            .push_text_3d(Vector3::new(1.0, 2.0, 3.0), "A", font(), TextConfig::default())
+            .unwrap()
+            .build()
+            .unwrap();
+        let ImmediateCommand::Text(text) = &command_buffer.commands()[0] else {
+            panic!("expected a Text");
+        };
+        assert_eq!(text.config().space, TextSpace::Billboard);
+        // The vertices are offsets from the position of the label
+        let (positions, _) = text.vertices();
+        assert_eq!(positions[0], Vector3::new(0.0, 0.0, 0.0));
+        assert_eq!(positions[2], Vector3::new(0.5, 1.0, 0.0));
+    }
+
+    #[test]
+    fn clones_share_id() {
+        let command_buffer = CommandBufferBuilder::new(debug_info!("my_command_buffer")).build().unwrap();
+        let other_command_buffer = CommandBufferBuilder::new(debug_info!("my_other_command_buffer")).build().unwrap();
+        assert_eq!(command_buffer.id(), command_buffer.clone().id());
+        assert_ne!(command_buffer.id(), other_command_buffer.id());
+    }
+
+    #[test]
+    fn push_texts_skips_whitespace() {
+        let command_buffer = CommandBufferBuilder::new(debug_info!("my_command_buffer"))
+            .push_texts(&[
+                Text::new(" ", font(), Vector3::zeros(), TextConfig::default()),
+                Text::new("A", font(), Vector3::zeros(), TextConfig::default()),
+            ])
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(command_buffer.commands().len(), 1);
     }
 }
@@ -1,3 +1,12 @@
+use std::sync::{Arc, Weak};
+
+use jeriya_shared::parking_lot::Mutex;
+
+use crate::gpu_index_allocator::{AllocateGpuIndex, GpuIndexAllocation, GpuIndexAllocator, ProvideAllocateGpuIndex};
+
+use self::{camera_instance::CameraInstance, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance};
+
+pub mod animation;
 pub mod camera_instance;
 pub mod camera_instance_group;
 pub mod instance_group;
@@ -5,3 +14,122 @@ pub mod point_cloud_instance;
 pub mod point_cloud_instance_group;
 pub mod rigid_mesh_instance;
 pub mod rigid_mesh_instance_group;
+pub mod scene_graph;
+
+/// A mock that acts as the renderer in the context of instances.
+pub struct MockRenderer {
+    backend: Arc<MockBackend>,
+}
+
+impl MockRenderer {
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            backend: Arc::new(MockBackend {
+                rigid_mesh_instance_gpu_index_allocator: Mutex::new(GpuIndexAllocator::new(100)),
+                point_cloud_instance_gpu_index_allocator: Mutex::new(GpuIndexAllocator::new(100)),
+                camera_instance_gpu_index_allocator: Mutex::new(GpuIndexAllocator::new(100)),
+            }),
+        })
+    }
+}
+
+impl ProvideAllocateGpuIndex<RigidMeshInstance> for MockRenderer {
+    type AllocateGpuIndex = MockBackend;
+    fn provide_gpu_index_allocator(&self) -> Weak<Self::AllocateGpuIndex> {
+        Arc::downgrade(&self.backend)
+    }
+}
+
+impl ProvideAllocateGpuIndex<PointCloudInstance> for MockRenderer {
+    type AllocateGpuIndex = MockBackend;
+    fn provide_gpu_index_allocator(&self) -> Weak<Self::AllocateGpuIndex> {
+        Arc::downgrade(&self.backend)
+    }
+}
+
+impl ProvideAllocateGpuIndex<CameraInstance> for MockRenderer {
+    type AllocateGpuIndex = MockBackend;
+    fn provide_gpu_index_allocator(&self) -> Weak<Self::AllocateGpuIndex> {
+        Arc::downgrade(&self.backend)
+    }
+}
+
+pub struct MockBackend {
+    rigid_mesh_instance_gpu_index_allocator: Mutex<GpuIndexAllocator<RigidMeshInstance>>,
+    point_cloud_instance_gpu_index_allocator: Mutex<GpuIndexAllocator<PointCloudInstance>>,
+    camera_instance_gpu_index_allocator: Mutex<GpuIndexAllocator<CameraInstance>>,
+}
+
+impl AllocateGpuIndex<RigidMeshInstance> for MockBackend {
+    fn allocate_gpu_index(&self) -> Option<GpuIndexAllocation<RigidMeshInstance>> {
+        self.rigid_mesh_instance_gpu_index_allocator.lock().allocate_gpu_index()
+    }
+
+    fn free_gpu_index(&self, gpu_index_allocation: GpuIndexAllocation<RigidMeshInstance>) {
+        self.rigid_mesh_instance_gpu_index_allocator
+            .lock()
+            .free_gpu_index(gpu_index_allocation)
+    }
+}
+
+impl AllocateGpuIndex<PointCloudInstance> for MockBackend {
+    fn allocate_gpu_index(&self) -> Option<GpuIndexAllocation<PointCloudInstance>> {
+        self.point_cloud_instance_gpu_index_allocator.lock().allocate_gpu_index()
+    }
+
+    fn free_gpu_index(&self, gpu_index_allocation: GpuIndexAllocation<PointCloudInstance>) {
+        self.point_cloud_instance_gpu_index_allocator
+            .lock()
+            .free_gpu_index(gpu_index_allocation)
+    }
+}
+
+impl AllocateGpuIndex<CameraInstance> for MockBackend {
+    fn allocate_gpu_index(&self) -> Option<GpuIndexAllocation<CameraInstance>> {
+        self.camera_instance_gpu_index_allocator.lock().allocate_gpu_index()
+    }
+
+    fn free_gpu_index(&self, gpu_index_allocation: GpuIndexAllocation<CameraInstance>) {
+        self.camera_instance_gpu_index_allocator.lock().free_gpu_index(gpu_index_allocation)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use jeriya_shared::{debug_info, Handle};
+
+    use crate::{
+        elements::{self, rigid_mesh::RigidMesh, rigid_mesh_group::RigidMeshGroup},
+        resources::tests::new_dummy_mesh_attributes,
+        transactions::Transaction,
+    };
+
+    use super::{instance_group::InstanceGroup, rigid_mesh_instance::RigidMeshInstance, MockRenderer};
+
+    /// Creates an [`InstanceGroup`] that contains a single [`RigidMeshInstance`].
+    ///
+    /// The returned [`MockRenderer`] has to outlive the [`InstanceGroup`].
+    pub fn new_instance_group_with_rigid_mesh_instance() -> (Arc<MockRenderer>, InstanceGroup, Handle<RigidMeshInstance>) {
+        let mut transaction = Transaction::new();
+        let elements_renderer = elements::MockRenderer::new();
+        let mut rigid_mesh_group = RigidMeshGroup::new(&elements_renderer, debug_info!("my_rigid_mesh_group"));
+        let rigid_mesh_handle = rigid_mesh_group
+            .mutate_via(&mut transaction)
+            .insert_with(RigidMesh::builder().with_mesh_attributes(new_dummy_mesh_attributes()))
+            .unwrap();
+        let rigid_mesh = rigid_mesh_group.get(&rigid_mesh_handle).unwrap();
+
+        let renderer = MockRenderer::new();
+        let mut instance_group = InstanceGroup::new(&renderer, debug_info!("my_instance_group"));
+        let rigid_mesh_instance_handle = instance_group
+            .rigid_mesh_instances()
+            .mutate_via(&mut transaction)
+            .insert_with(RigidMeshInstance::builder().with_rigid_mesh(rigid_mesh))
+            .unwrap();
+        transaction.process();
+        (renderer, instance_group, rigid_mesh_instance_handle)
+    }
+}
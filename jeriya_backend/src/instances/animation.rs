@@ -0,0 +1,399 @@
+use std::{sync::Arc, time::Duration};
+
+use jeriya_shared::{
+    nalgebra::{Matrix4, Translation3, UnitQuaternion, Vector3},
+    DebugInfo, Handle,
+};
+
+use crate::{
+    instances::{
+        camera_instance::{CameraInstance, CameraTransform},
+        instance_group::InstanceGroup,
+        rigid_mesh_instance::RigidMeshInstance,
+    },
+    transactions::PushEvent,
+};
+
+/// Determines how the value of a [`Track`] is computed between two [`Keyframe`]s
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// The value of the previous [`Keyframe`] is held until the next [`Keyframe`] is reached
+    Step,
+    /// Vectors are interpolated linearly and rotations are interpolated spherically
+    #[default]
+    Linear,
+}
+
+/// Values that can be interpolated between two [`Keyframe`]s
+pub trait Interpolate: Clone {
+    /// Returns the value between `self` and `other` where `t` is in the range `[0, 1]`
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for Vector3<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+impl Interpolate for UnitQuaternion<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        // `try_slerp` fails for opposite rotations in which case the normalized lerp is good enough
+        self.try_slerp(other, t, f32::EPSILON).unwrap_or_else(|| self.nlerp(other, t))
+    }
+}
+
+/// Value of a [`Track`] at a point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    /// Time of the [`Keyframe`] in seconds since the start of the animation
+    pub time: f32,
+    pub value: T,
+}
+
+/// Sequence of [`Keyframe`]s that are sorted by time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+    interpolation: Interpolation,
+}
+
+impl<T: Interpolate> Track<T> {
+    /// Creates an empty [`Track`] with the given [`Interpolation`]
+    pub fn new(interpolation: Interpolation) -> Self {
+        Self {
+            keyframes: Vec::new(),
+            interpolation,
+        }
+    }
+
+    /// Inserts a [`Keyframe`] so that the [`Keyframe`]s stay sorted. A [`Keyframe`] at the same time is replaced.
+    pub fn with_keyframe(mut self, time: f32, value: T) -> Self {
+        self.insert(time, value);
+        self
+    }
+
+    /// Inserts a [`Keyframe`] so that the [`Keyframe`]s stay sorted. A [`Keyframe`] at the same time is replaced.
+    pub fn insert(&mut self, time: f32, value: T) {
+        match self.keyframes.binary_search_by(|keyframe| keyframe.time.total_cmp(&time)) {
+            Ok(index) => self.keyframes[index].value = value,
+            Err(index) => self.keyframes.insert(index, Keyframe { time, value }),
+        }
+    }
+
+    /// Returns the [`Keyframe`]s of the [`Track`]
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Returns the [`Interpolation`] of the [`Track`]
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Returns the time of the last [`Keyframe`] or `0.0` when the [`Track`] is empty
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Returns the value at the given time. Before the first and after the last [`Keyframe`] the value is clamped.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if time <= first.time {
+            return Some(first.value.clone());
+        }
+        if time >= last.time {
+            return Some(last.value.clone());
+        }
+        let next_index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let previous = &self.keyframes[next_index - 1];
+        let next = &self.keyframes[next_index];
+        match self.interpolation {
+            Interpolation::Step => Some(previous.value.clone()),
+            Interpolation::Linear => {
+                let t = (time - previous.time) / (next.time - previous.time);
+                Some(previous.value.interpolate(&next.value, t))
+            }
+        }
+    }
+}
+
+impl<T: Interpolate> Default for Track<T> {
+    fn default() -> Self {
+        Self::new(Interpolation::default())
+    }
+}
+
+/// Position, rotation and scale that result from sampling a [`TransformAnimation`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledTransform {
+    pub position: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl SampledTransform {
+    /// Returns the matrix that first scales, then rotates and finally translates.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Translation3::from(self.position).to_homogeneous() * self.rotation.to_homogeneous() * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+
+    /// Returns the [`CameraTransform`] whose default forward and up directions are rotated. The scale is ignored.
+    pub fn camera_transform(&self) -> CameraTransform {
+        let default = CameraTransform::default();
+        CameraTransform {
+            position: self.position,
+            forward: self.rotation * default.forward,
+            up: self.rotation * default.up,
+        }
+    }
+}
+
+/// Animation of the position, rotation and scale of an instance
+///
+/// Tracks that are empty leave the respective component at its identity value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformAnimation {
+    pub position: Track<Vector3<f32>>,
+    pub rotation: Track<UnitQuaternion<f32>>,
+    pub scale: Track<Vector3<f32>>,
+    /// When `true`, the animation restarts after the last [`Keyframe`] instead of holding the last value
+    pub looping: bool,
+}
+
+impl TransformAnimation {
+    /// Returns the time of the last [`Keyframe`] of all [`Track`]s
+    pub fn duration(&self) -> f32 {
+        self.position.duration().max(self.rotation.duration()).max(self.scale.duration())
+    }
+
+    /// Returns the transform at the given time in seconds since the start of the animation
+    pub fn sample(&self, time: f32) -> SampledTransform {
+        let duration = self.duration();
+        let time = if self.looping && duration > 0.0 {
+            time.rem_euclid(duration)
+        } else {
+            time
+        };
+        SampledTransform {
+            position: self.position.sample(time).unwrap_or_else(Vector3::zeros),
+            rotation: self.rotation.sample(time).unwrap_or_else(UnitQuaternion::identity),
+            scale: self.scale.sample(time).unwrap_or_else(|| Vector3::new(1.0, 1.0, 1.0)),
+        }
+    }
+}
+
+/// Instance whose transform is controlled by a [`TransformAnimation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationTarget {
+    RigidMeshInstance(Handle<RigidMeshInstance>),
+    CameraInstance(Handle<CameraInstance>),
+}
+
+/// [`TransformAnimation`] that is bound to an [`AnimationTarget`]
+#[derive(Debug, Clone)]
+pub struct AnimationBinding {
+    target: AnimationTarget,
+    animation: Arc<TransformAnimation>,
+    start_time: f32,
+}
+
+impl AnimationBinding {
+    /// Returns the [`AnimationTarget`] of the [`AnimationBinding`]
+    pub fn target(&self) -> &AnimationTarget {
+        &self.target
+    }
+
+    /// Returns the [`TransformAnimation`] of the [`AnimationBinding`]
+    pub fn animation(&self) -> &Arc<TransformAnimation> {
+        &self.animation
+    }
+
+    /// Returns the time of the [`Animator`] at which the [`AnimationBinding`] was created
+    pub fn start_time(&self) -> f32 {
+        self.start_time
+    }
+}
+
+/// Plays [`TransformAnimation`]s on the instances of an [`InstanceGroup`]
+///
+/// The [`Animator`] is advanced once per update tick with [`Animator::update`] which samples all bound animations
+/// and pushes the resulting transforms to the instances via the given transaction.
+pub struct Animator {
+    bindings: Vec<AnimationBinding>,
+    time: f32,
+    debug_info: DebugInfo,
+}
+
+impl Animator {
+    /// Creates a new [`Animator`] without any [`AnimationBinding`]s
+    pub fn new(debug_info: DebugInfo) -> Self {
+        Self {
+            bindings: Vec::new(),
+            time: 0.0,
+            debug_info,
+        }
+    }
+
+    /// Binds the [`TransformAnimation`] to the [`AnimationTarget`] starting at the current time. An animation that is
+    /// already bound to the [`AnimationTarget`] is replaced.
+    pub fn bind(&mut self, target: AnimationTarget, animation: Arc<TransformAnimation>) {
+        self.unbind(&target);
+        self.bindings.push(AnimationBinding {
+            target,
+            animation,
+            start_time: self.time,
+        });
+    }
+
+    /// Removes the [`TransformAnimation`] from the [`AnimationTarget`]. Returns `true` if an animation was bound.
+    pub fn unbind(&mut self, target: &AnimationTarget) -> bool {
+        let len = self.bindings.len();
+        self.bindings.retain(|binding| &binding.target != target);
+        self.bindings.len() != len
+    }
+
+    /// Returns the [`AnimationBinding`]s of the [`Animator`]
+    pub fn bindings(&self) -> &[AnimationBinding] {
+        &self.bindings
+    }
+
+    /// Returns the time in seconds that has passed in all calls to [`Animator::update`]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns the [`DebugInfo`] of the [`Animator`]
+    pub fn debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+
+    /// Advances the time by `delta` and sets the transforms of all [`AnimationTarget`]s via the given transaction.
+    ///
+    /// Bindings whose instance has been removed from the [`InstanceGroup`] are dropped.
+    pub fn update(&mut self, delta: Duration, instance_group: &mut InstanceGroup, transaction: &mut impl PushEvent) {
+        self.time += delta.as_secs_f32();
+        let time = self.time;
+        self.bindings.retain(|binding| {
+            let sampled_transform = binding.animation.sample(time - binding.start_time);
+            match &binding.target {
+                AnimationTarget::RigidMeshInstance(handle) => match instance_group.rigid_mesh_instances().get_mut(handle) {
+                    Some(rigid_mesh_instance) => {
+                        rigid_mesh_instance
+                            .mutate_via(transaction)
+                            .set_transform(sampled_transform.matrix());
+                        true
+                    }
+                    None => false,
+                },
+                AnimationTarget::CameraInstance(handle) => match instance_group.camera_instances().get_mut(handle) {
+                    Some(camera_instance) => {
+                        camera_instance
+                            .mutate_via(transaction)
+                            .set_transform(sampled_transform.camera_transform());
+                        true
+                    }
+                    None => false,
+                },
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use jeriya_shared::debug_info;
+
+    use crate::{
+        instances::{rigid_mesh_instance, tests::new_instance_group_with_rigid_mesh_instance},
+        transactions::{self, Transaction},
+    };
+
+    use super::*;
+
+    fn assert_near(a: &Vector3<f32>, b: &Vector3<f32>) {
+        assert!((a - b).norm() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn sample_track() {
+        let track = Track::new(Interpolation::Linear)
+            .with_keyframe(2.0, Vector3::new(2.0, 0.0, 0.0))
+            .with_keyframe(0.0, Vector3::new(0.0, 0.0, 0.0))
+            .with_keyframe(1.0, Vector3::new(0.0, 4.0, 0.0));
+        assert_eq!(track.keyframes().len(), 3);
+        assert_eq!(track.duration(), 2.0);
+        assert_near(&track.sample(-1.0).unwrap(), &Vector3::new(0.0, 0.0, 0.0));
+        assert_near(&track.sample(0.5).unwrap(), &Vector3::new(0.0, 2.0, 0.0));
+        assert_near(&track.sample(1.5).unwrap(), &Vector3::new(1.0, 2.0, 0.0));
+        assert_near(&track.sample(3.0).unwrap(), &Vector3::new(2.0, 0.0, 0.0));
+
+        let track = Track::new(Interpolation::Step)
+            .with_keyframe(0.0, Vector3::new(0.0, 0.0, 0.0))
+            .with_keyframe(1.0, Vector3::new(1.0, 0.0, 0.0));
+        assert_near(&track.sample(0.99).unwrap(), &Vector3::new(0.0, 0.0, 0.0));
+        assert_near(&track.sample(1.0).unwrap(), &Vector3::new(1.0, 0.0, 0.0));
+
+        assert_eq!(Track::<Vector3<f32>>::default().sample(0.0), None);
+    }
+
+    #[test]
+    fn sample_rotation() {
+        let animation = TransformAnimation {
+            rotation: Track::default()
+                .with_keyframe(0.0, UnitQuaternion::identity())
+                .with_keyframe(1.0, UnitQuaternion::from_axis_angle(&Vector3::y_axis(), FRAC_PI_2)),
+            looping: true,
+            ..TransformAnimation::default()
+        };
+        let expected = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), FRAC_PI_2 / 2.0);
+        assert!(animation.sample(0.5).rotation.angle_to(&expected) < 1e-5);
+        // Looping wraps the time around
+        assert!(animation.sample(1.5).rotation.angle_to(&expected) < 1e-5);
+        assert_near(&animation.sample(0.5).scale, &Vector3::new(1.0, 1.0, 1.0));
+
+        let camera_transform = animation.sample(0.999999).camera_transform();
+        assert_near(&camera_transform.forward, &Vector3::new(1.0, 0.0, 0.0));
+        assert_near(&camera_transform.up, &Vector3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn update() {
+        let (_renderer, mut instance_group, rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        let animation = Arc::new(TransformAnimation {
+            position: Track::default()
+                .with_keyframe(0.0, Vector3::new(0.0, 0.0, 0.0))
+                .with_keyframe(2.0, Vector3::new(2.0, 0.0, 0.0)),
+            ..TransformAnimation::default()
+        });
+        let mut animator = Animator::new(debug_info!("my_animator"));
+        let target = AnimationTarget::RigidMeshInstance(rigid_mesh_instance);
+        animator.bind(target, animation);
+
+        let mut transaction = Transaction::new();
+        animator.update(Duration::from_secs(1), &mut instance_group, &mut transaction);
+        let events = transaction.process();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateTransform(_, transform))
+                if transform == &Matrix4::new_translation(&Vector3::new(1.0, 0.0, 0.0))
+        ));
+
+        // Removed instances are unbound
+        let mut transaction = Transaction::new();
+        instance_group
+            .rigid_mesh_instances()
+            .mutate_via(&mut transaction)
+            .remove(&rigid_mesh_instance);
+        transaction.process();
+        let mut transaction = Transaction::new();
+        animator.update(Duration::from_secs(1), &mut instance_group, &mut transaction);
+        assert!(transaction.process().is_empty());
+        assert!(animator.bindings().is_empty());
+        assert!(!animator.unbind(&target));
+    }
+}
@@ -26,6 +26,7 @@ pub enum Event {
     Noop,
     Insert(CameraInstance),
     UpdateViewMatrix(GpuIndexAllocation<CameraInstance>, Matrix4<f32>),
+    Remove(GpuIndexAllocation<CameraInstance>),
 }
 
 #[derive(new, Debug, Clone, PartialEq)]
@@ -84,4 +84,24 @@ impl<'g, 't, P: PushEvent> CameraInstanceGroupAccessMut<'g, 't, P> {
                 handle
             })
     }
+
+    /// Removes the [`CameraInstance`] with the given [`Handle`] from the [`CameraInstanceGroup`].
+    ///
+    /// The GPU index of the [`CameraInstance`] is freed so that it can be reused by subsequent insertions.
+    /// Returns `None` when the [`Handle`] doesn't reference a [`CameraInstance`] in the [`CameraInstanceGroup`].
+    pub fn remove(&mut self, handle: &Handle<CameraInstance>) -> Option<CameraInstance> {
+        let camera_instance = self.camera_group.indexing_container.get(handle)?.clone();
+        self.camera_group.indexing_container.remove_with(handle, camera_instance.clone());
+        self.transaction
+            .push_event(transactions::Event::CameraInstance(camera_instance::Event::Remove(
+                *camera_instance.gpu_index_allocation(),
+            )));
+        let gpu_index_allocator = self
+            .camera_group
+            .gpu_index_allocator
+            .upgrade()
+            .expect("the gpu_index_allocator was dropped");
+        gpu_index_allocator.free_gpu_index(*camera_instance.gpu_index_allocation());
+        Some(camera_instance)
+    }
 }
@@ -1,6 +1,10 @@
 use jeriya_shared::{debug_info, nalgebra::Matrix4, thiserror, DebugInfo, Handle};
 
-use crate::{elements::point_cloud::PointCloud, gpu_index_allocator::GpuIndexAllocation};
+use crate::{
+    elements::point_cloud::PointCloud,
+    gpu_index_allocator::GpuIndexAllocation,
+    transactions::{self, PushEvent},
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -17,6 +21,21 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Event {
     Noop,
     Insert(PointCloudInstance),
+    /// Inserts many [`PointCloudInstance`]s at once. Emitted by `PointCloudInstanceGroupAccessMut::insert_many_with`.
+    InsertMany(Vec<PointCloudInstance>),
+    UpdateTransform(GpuIndexAllocation<PointCloudInstance>, Matrix4<f32>),
+    UpdatePointRenderMode(GpuIndexAllocation<PointCloudInstance>, PointRenderMode),
+}
+
+/// Determines how the points of a [`PointCloudInstance`] are rendered
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum PointRenderMode {
+    /// Every point is a camera-facing triangle with a fixed size on the screen
+    #[default]
+    Triangles,
+    /// Every point is a disc that is oriented by the normal of the point. The `radius` is given
+    /// in the space of the point cloud so that the size on the screen decreases with the distance.
+    Splats { radius: f32 },
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +45,7 @@ pub struct PointCloudInstance {
     handle: Handle<PointCloudInstance>,
     gpu_index_allocation: GpuIndexAllocation<PointCloudInstance>,
     transform: Matrix4<f32>,
+    point_render_mode: PointRenderMode,
     debug_info: DebugInfo,
 }
 
@@ -59,10 +79,54 @@ impl PointCloudInstance {
         &self.transform
     }
 
+    /// Returns the [`PointRenderMode`] of the [`PointCloudInstance`]
+    pub fn point_render_mode(&self) -> PointRenderMode {
+        self.point_render_mode
+    }
+
     /// Returns the [`DebugInfo`] of the [`PointCloudInstance`]
     pub fn debug_info(&self) -> &DebugInfo {
         &self.debug_info
     }
+
+    pub fn mutate_via<'g, 't>(&'g mut self, transaction: &'t mut impl PushEvent) -> PointCloudInstanceAccessMut<'g, 't, impl PushEvent> {
+        PointCloudInstanceAccessMut::new(self, transaction)
+    }
+}
+
+pub struct PointCloudInstanceAccessMut<'g, 't, P: PushEvent> {
+    point_cloud_instance: &'g mut PointCloudInstance,
+    transaction: &'t mut P,
+}
+
+impl<'g, 't, P: PushEvent> PointCloudInstanceAccessMut<'g, 't, P> {
+    /// Creates a new [`PointCloudInstanceAccessMut`] for a [`PointCloudInstance`].
+    pub fn new(point_cloud_instance: &'g mut PointCloudInstance, transaction: &'t mut P) -> Self {
+        Self {
+            point_cloud_instance,
+            transaction,
+        }
+    }
+
+    /// Sets the transform of the [`PointCloudInstance`].
+    pub fn set_transform(&mut self, transform: Matrix4<f32>) {
+        self.point_cloud_instance.transform = transform;
+        self.transaction
+            .push_event(transactions::Event::PointCloudInstance(Event::UpdateTransform(
+                self.point_cloud_instance.gpu_index_allocation,
+                self.point_cloud_instance.transform,
+            )));
+    }
+
+    /// Sets the [`PointRenderMode`] of the [`PointCloudInstance`].
+    pub fn set_point_render_mode(&mut self, point_render_mode: PointRenderMode) {
+        self.point_cloud_instance.point_render_mode = point_render_mode;
+        self.transaction
+            .push_event(transactions::Event::PointCloudInstance(Event::UpdatePointRenderMode(
+                self.point_cloud_instance.gpu_index_allocation,
+                self.point_cloud_instance.point_render_mode,
+            )));
+    }
 }
 
 #[derive(Default)]
@@ -70,6 +134,7 @@ pub struct PointCloudInstanceBuilder {
     point_cloud_handle: Option<Handle<PointCloud>>,
     point_cloud_gpu_index_allocation: Option<GpuIndexAllocation<PointCloud>>,
     transform: Option<Matrix4<f32>>,
+    point_render_mode: Option<PointRenderMode>,
     debug_info: Option<DebugInfo>,
 }
 
@@ -87,6 +152,12 @@ impl PointCloudInstanceBuilder {
         self
     }
 
+    /// Sets the [`PointRenderMode`] of the [`PointCloudInstance`]
+    pub fn with_point_render_mode(mut self, point_render_mode: PointRenderMode) -> Self {
+        self.point_render_mode = Some(point_render_mode);
+        self
+    }
+
     /// Sets the [`DebugInfo`] of the [`PointCloudInstance`]
     pub fn with_debug_info(mut self, debug_info: DebugInfo) -> Self {
         self.debug_info = Some(debug_info);
@@ -108,6 +179,7 @@ impl PointCloudInstanceBuilder {
             gpu_index_allocation,
             debug_info: self.debug_info.unwrap_or_else(|| debug_info!("Anonymous PointCloudInstance")),
             transform: self.transform.unwrap_or(Matrix4::identity()),
+            point_render_mode: self.point_render_mode.unwrap_or_default(),
         })
     }
 }
@@ -30,6 +30,11 @@ impl PointCloudInstanceGroup {
         self.indexing_container.get(handle)
     }
 
+    /// Returns the [`PointCloudInstance`] with the given [`Handle`] mutably
+    pub fn get_mut(&mut self, handle: &Handle<PointCloudInstance>) -> Option<&mut PointCloudInstance> {
+        self.indexing_container.get_mut(handle)
+    }
+
     /// Returns the [`DebugInfo`] of the [`PointCloudInstanceGroup`]
     pub fn debug_info(&self) -> &DebugInfo {
         &self.debug_info
@@ -84,4 +89,65 @@ impl<'g, 't, P: PushEvent> PointCloudInstanceGroupAccessMut<'g, 't, P> {
                 handle
             })
     }
+
+    /// Inserts many [`PointCloudInstance`]s into the [`PointCloudInstanceGroup`] at once.
+    ///
+    /// The GPU indices are allocated in a single batch and a single [`point_cloud_instance::Event::InsertMany`] is pushed
+    /// into the transaction. When one of the builders fails, none of the [`PointCloudInstance`]s are inserted.
+    pub fn insert_many_with(
+        &mut self,
+        point_cloud_instance_builders: impl IntoIterator<Item = PointCloudInstanceBuilder>,
+    ) -> point_cloud_instance::Result<Vec<Handle<PointCloudInstance>>> {
+        let point_cloud_instance_builders = point_cloud_instance_builders.into_iter().collect::<Vec<_>>();
+        let gpu_index_allocator = self
+            .point_cloud_group
+            .gpu_index_allocator
+            .upgrade()
+            .expect("the gpu_index_allocator was dropped");
+        let gpu_index_allocations = gpu_index_allocator
+            .allocate_gpu_indices(point_cloud_instance_builders.len())
+            .ok_or(Error::AllocationFailed)?;
+
+        let mut handles = Vec::with_capacity(point_cloud_instance_builders.len());
+        for (point_cloud_instance_builder, gpu_index_allocation) in point_cloud_instance_builders.into_iter().zip(&gpu_index_allocations) {
+            let result = self
+                .point_cloud_group
+                .indexing_container
+                .insert_with(|handle| point_cloud_instance_builder.build(*handle, *gpu_index_allocation));
+            match result {
+                Ok(handle) => handles.push(handle),
+                Err(err) => {
+                    for handle in &handles {
+                        let point_cloud_instance = self
+                            .point_cloud_group
+                            .indexing_container
+                            .get(handle)
+                            .expect("inserted value not found")
+                            .clone();
+                        self.point_cloud_group.indexing_container.remove_with(handle, point_cloud_instance);
+                    }
+                    for gpu_index_allocation in gpu_index_allocations {
+                        gpu_index_allocator.free_gpu_index(gpu_index_allocation);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let point_cloud_instances = handles
+            .iter()
+            .map(|handle| {
+                self.point_cloud_group
+                    .indexing_container
+                    .get(handle)
+                    .expect("just inserted value not found")
+                    .clone()
+            })
+            .collect();
+        self.transaction
+            .push_event(transactions::Event::PointCloudInstance(point_cloud_instance::Event::InsertMany(
+                point_cloud_instances,
+            )));
+        Ok(handles)
+    }
 }
@@ -1,6 +1,17 @@
-use jeriya_shared::{debug_info, nalgebra::Matrix4, thiserror, DebugInfo, Handle};
+use std::sync::Arc;
 
-use crate::{elements::rigid_mesh::RigidMesh, gpu_index_allocator::GpuIndexAllocation};
+use jeriya_shared::{
+    debug_info,
+    nalgebra::{Matrix4, Rotation3, Translation3, Unit, Vector3, Vector4},
+    thiserror, DebugInfo, Handle,
+};
+
+use crate::{
+    elements::rigid_mesh::RigidMesh,
+    gpu_index_allocator::GpuIndexAllocation,
+    resources::mesh_attributes::MeshAttributes,
+    transactions::{self, PushEvent},
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -17,19 +28,84 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Event {
     Noop,
     Insert(RigidMeshInstance),
+    /// Inserts many [`RigidMeshInstance`]s at once. Emitted by `RigidMeshInstanceGroupAccessMut::insert_many_with`.
+    InsertMany(Vec<RigidMeshInstance>),
+    UpdateTransform(GpuIndexAllocation<RigidMeshInstance>, Matrix4<f32>),
+    UpdateColor(GpuIndexAllocation<RigidMeshInstance>, Vector4<f32>),
+    UpdateSelected(GpuIndexAllocation<RigidMeshInstance>, bool),
+    UpdateMotion(GpuIndexAllocation<RigidMeshInstance>, Motion),
+    Remove(GpuIndexAllocation<RigidMeshInstance>),
+}
+
+/// Simple motion of a [`RigidMeshInstance`] that is advanced on the GPU every frame
+///
+/// The motion starts when the backend receives the [`Transaction`](crate::transactions::Transaction)
+/// that sets it and is applied on top of the transform of the [`RigidMeshInstance`]. Animating
+/// instances this way doesn't require a [`Transaction`](crate::transactions::Transaction) per frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Motion {
+    #[default]
+    None,
+    /// Rotates the instance around the `axis` through its origin. The `axis` is given in the local space of the instance.
+    Spin {
+        axis: Unit<Vector3<f32>>,
+        /// Angular velocity in radians per second
+        angular_velocity: f32,
+    },
+    /// Rotates the instance around the `axis` through `center`. Both are given in world space.
+    Orbit {
+        center: Vector3<f32>,
+        axis: Unit<Vector3<f32>>,
+        /// Angular velocity in radians per second
+        angular_velocity: f32,
+    },
+    /// Moves the instance with the given velocity in units per second
+    LinearVelocity(Vector3<f32>),
+}
+
+impl Motion {
+    /// Returns the transform of an instance with the transform `base` after the motion has been running for `elapsed_seconds`.
+    ///
+    /// This is the reference for the evaluation on the GPU.
+    pub fn transform_at(&self, base: &Matrix4<f32>, elapsed_seconds: f32) -> Matrix4<f32> {
+        match self {
+            Motion::None => *base,
+            Motion::Spin { axis, angular_velocity } => {
+                base * Rotation3::from_axis_angle(axis, angular_velocity * elapsed_seconds).to_homogeneous()
+            }
+            Motion::Orbit {
+                center,
+                axis,
+                angular_velocity,
+            } => {
+                Translation3::from(*center).to_homogeneous()
+                    * Rotation3::from_axis_angle(axis, angular_velocity * elapsed_seconds).to_homogeneous()
+                    * Translation3::from(-center).to_homogeneous()
+                    * base
+            }
+            Motion::LinearVelocity(velocity) => Translation3::from(velocity * elapsed_seconds).to_homogeneous() * base,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct RigidMeshInstance {
     rigid_mesh_handle: Handle<RigidMesh>,
     rigid_mesh_gpu_index_allocation: GpuIndexAllocation<RigidMesh>,
+    mesh_attributes: Arc<MeshAttributes>,
     handle: Handle<RigidMeshInstance>,
     gpu_index_allocation: GpuIndexAllocation<RigidMeshInstance>,
     transform: Matrix4<f32>,
+    color: Vector4<f32>,
+    selected: bool,
+    motion: Motion,
     debug_info: DebugInfo,
 }
 
 impl RigidMeshInstance {
+    /// Color of a [`RigidMeshInstance`] that doesn't tint the mesh
+    pub const NO_TINT: Vector4<f32> = Vector4::new(1.0, 1.0, 1.0, 0.0);
+
     pub fn builder() -> RigidMeshInstanceBuilder {
         RigidMeshInstanceBuilder::new()
     }
@@ -44,6 +120,11 @@ impl RigidMeshInstance {
         &self.rigid_mesh_gpu_index_allocation
     }
 
+    /// Returns the [`MeshAttributes`] of the [`RigidMesh`] that this [`RigidMeshInstance`] is an instance of.
+    pub fn mesh_attributes(&self) -> &Arc<MeshAttributes> {
+        &self.mesh_attributes
+    }
+
     /// Returns the [`Handle`] of the [`RigidMeshInstance`]
     pub fn handle(&self) -> &Handle<RigidMeshInstance> {
         &self.handle
@@ -59,16 +140,98 @@ impl RigidMeshInstance {
         &self.transform
     }
 
+    /// Returns the RGBA tint of the [`RigidMeshInstance`]
+    ///
+    /// The RGB components are blended over the shaded color of the mesh with the alpha component as weight.
+    pub fn color(&self) -> &Vector4<f32> {
+        &self.color
+    }
+
+    /// Returns whether the [`RigidMeshInstance`] is selected
+    ///
+    /// Selected instances are outlined by the backend so that editors can highlight them.
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    /// Returns the [`Motion`] that is applied on top of the transform of the [`RigidMeshInstance`]
+    pub fn motion(&self) -> &Motion {
+        &self.motion
+    }
+
     /// Returns the [`DebugInfo`] of the [`RigidMeshInstance`]
     pub fn debug_info(&self) -> &DebugInfo {
         &self.debug_info
     }
+
+    pub fn mutate_via<'g, 't>(&'g mut self, transaction: &'t mut impl PushEvent) -> RigidMeshInstanceAccessMut<'g, 't, impl PushEvent> {
+        RigidMeshInstanceAccessMut::new(self, transaction)
+    }
+}
+
+pub struct RigidMeshInstanceAccessMut<'g, 't, P: PushEvent> {
+    rigid_mesh_instance: &'g mut RigidMeshInstance,
+    transaction: &'t mut P,
+}
+
+impl<'g, 't, P: PushEvent> RigidMeshInstanceAccessMut<'g, 't, P> {
+    /// Creates a new [`RigidMeshInstanceAccessMut`] for a [`RigidMeshInstance`].
+    pub fn new(rigid_mesh_instance: &'g mut RigidMeshInstance, transaction: &'t mut P) -> Self {
+        Self {
+            rigid_mesh_instance,
+            transaction,
+        }
+    }
+
+    /// Sets the transform of the [`RigidMeshInstance`].
+    pub fn set_transform(&mut self, transform: Matrix4<f32>) {
+        self.rigid_mesh_instance.transform = transform;
+        self.transaction
+            .push_event(transactions::Event::RigidMeshInstance(Event::UpdateTransform(
+                self.rigid_mesh_instance.gpu_index_allocation,
+                self.rigid_mesh_instance.transform,
+            )));
+    }
+
+    /// Sets the RGBA tint of the [`RigidMeshInstance`]. See [`RigidMeshInstance::color`].
+    pub fn set_color(&mut self, color: Vector4<f32>) {
+        self.rigid_mesh_instance.color = color;
+        self.transaction
+            .push_event(transactions::Event::RigidMeshInstance(Event::UpdateColor(
+                self.rigid_mesh_instance.gpu_index_allocation,
+                self.rigid_mesh_instance.color,
+            )));
+    }
+
+    /// Sets whether the [`RigidMeshInstance`] is selected. See [`RigidMeshInstance::is_selected`].
+    pub fn set_selected(&mut self, selected: bool) {
+        self.rigid_mesh_instance.selected = selected;
+        self.transaction
+            .push_event(transactions::Event::RigidMeshInstance(Event::UpdateSelected(
+                self.rigid_mesh_instance.gpu_index_allocation,
+                self.rigid_mesh_instance.selected,
+            )));
+    }
+
+    /// Sets the [`Motion`] of the [`RigidMeshInstance`]. See [`Motion`].
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.rigid_mesh_instance.motion = motion;
+        self.transaction
+            .push_event(transactions::Event::RigidMeshInstance(Event::UpdateMotion(
+                self.rigid_mesh_instance.gpu_index_allocation,
+                self.rigid_mesh_instance.motion,
+            )));
+    }
 }
 
 pub struct RigidMeshInstanceBuilder {
     rigid_mesh_handle: Option<Handle<RigidMesh>>,
     rigid_mesh_gpu_index_allocation: Option<GpuIndexAllocation<RigidMesh>>,
+    mesh_attributes: Option<Arc<MeshAttributes>>,
     transform: Option<Matrix4<f32>>,
+    color: Option<Vector4<f32>>,
+    selected: Option<bool>,
+    motion: Option<Motion>,
     debug_info: Option<DebugInfo>,
 }
 
@@ -77,7 +240,11 @@ impl RigidMeshInstanceBuilder {
         Self {
             rigid_mesh_handle: None,
             rigid_mesh_gpu_index_allocation: None,
+            mesh_attributes: None,
             transform: None,
+            color: None,
+            selected: None,
+            motion: None,
             debug_info: None,
         }
     }
@@ -86,6 +253,7 @@ impl RigidMeshInstanceBuilder {
     pub fn with_rigid_mesh(mut self, rigid_mesh: &RigidMesh) -> Self {
         self.rigid_mesh_handle = Some(*rigid_mesh.handle());
         self.rigid_mesh_gpu_index_allocation = Some(*rigid_mesh.gpu_index_allocation());
+        self.mesh_attributes = Some(rigid_mesh.mesh_attributes().clone());
         self
     }
 
@@ -95,6 +263,24 @@ impl RigidMeshInstanceBuilder {
         self
     }
 
+    /// Sets the RGBA tint of the [`RigidMeshInstance`]. See [`RigidMeshInstance::color`].
+    pub fn with_color(mut self, color: Vector4<f32>) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Sets whether the [`RigidMeshInstance`] is selected. See [`RigidMeshInstance::is_selected`].
+    pub fn with_selected(mut self, selected: bool) -> Self {
+        self.selected = Some(selected);
+        self
+    }
+
+    /// Sets the [`Motion`] of the [`RigidMeshInstance`]. See [`Motion`].
+    pub fn with_motion(mut self, motion: Motion) -> Self {
+        self.motion = Some(motion);
+        self
+    }
+
     /// Sets the [`DebugInfo`] of the [`RigidMeshInstance`]
     pub fn with_debug_info(mut self, debug_info: DebugInfo) -> Self {
         self.debug_info = Some(debug_info);
@@ -109,13 +295,60 @@ impl RigidMeshInstanceBuilder {
     ) -> Result<RigidMeshInstance> {
         let rigid_mesh_handle = self.rigid_mesh_handle.ok_or(Error::RigidMeshNotSet)?;
         let rigid_mesh_gpu_index_allocation = self.rigid_mesh_gpu_index_allocation.ok_or(Error::AllocationFailed)?;
+        let mesh_attributes = self.mesh_attributes.ok_or(Error::RigidMeshNotSet)?;
         Ok(RigidMeshInstance {
             debug_info: self.debug_info.unwrap_or_else(|| debug_info!("Anonymous RigidMeshInstance")),
             rigid_mesh_handle,
             rigid_mesh_gpu_index_allocation,
+            mesh_attributes,
             handle,
             gpu_index_allocation,
             transform: self.transform.unwrap_or(Matrix4::identity()),
+            color: self.color.unwrap_or(RigidMeshInstance::NO_TINT),
+            selected: self.selected.unwrap_or(false),
+            motion: self.motion.unwrap_or_default(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use jeriya_shared::nalgebra::Point3;
+
+    use super::*;
+
+    #[test]
+    fn motion_transform_at() {
+        let base = Matrix4::new_translation(&Vector3::new(1.0, 0.0, 0.0));
+        let origin = Point3::origin();
+        let position = |motion: Motion, elapsed_seconds: f32| motion.transform_at(&base, elapsed_seconds).transform_point(&origin);
+        let offset = |motion: Motion, elapsed_seconds: f32| {
+            motion
+                .transform_at(&base, elapsed_seconds)
+                .transform_point(&Point3::new(0.0, 0.0, 1.0))
+        };
+
+        assert_eq!(position(Motion::None, 10.0), Point3::new(1.0, 0.0, 0.0));
+
+        let linear_velocity = Motion::LinearVelocity(Vector3::new(0.0, 2.0, 0.0));
+        assert!((position(linear_velocity, 1.5) - Point3::new(1.0, 3.0, 0.0)).norm() < 1e-5);
+
+        // Spinning keeps the position but rotates the local axes
+        let spin = Motion::Spin {
+            axis: Vector3::y_axis(),
+            angular_velocity: FRAC_PI_2,
+        };
+        assert!((position(spin, 1.0) - Point3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+        assert!((offset(spin, 1.0) - Point3::new(2.0, 0.0, 0.0)).norm() < 1e-5);
+
+        let orbit = Motion::Orbit {
+            center: Vector3::zeros(),
+            axis: Vector3::y_axis(),
+            angular_velocity: FRAC_PI_2,
+        };
+        assert!((position(orbit, 1.0) - Point3::new(0.0, 0.0, -1.0)).norm() < 1e-5);
+        assert!((position(orbit, 2.0) - Point3::new(-1.0, 0.0, 0.0)).norm() < 1e-5);
+    }
+}
@@ -1,16 +1,42 @@
 use std::sync::{Arc, Weak};
 
-use jeriya_shared::{DebugInfo, Handle, IndexingContainer};
+use jeriya_shared::{
+    bvh::Bvh,
+    nalgebra::{Matrix4, Point3, Vector3},
+    DebugInfo, Handle, IndexingContainer,
+};
 
 use crate::{
     gpu_index_allocator::{AllocateGpuIndex, ProvideAllocateGpuIndex},
     instances::rigid_mesh_instance::{self, Error, RigidMeshInstance, RigidMeshInstanceBuilder},
+    resources::mesh_attributes::MeshAttributes,
     transactions::{self, PushEvent},
 };
 
+/// Determines against which geometry of the [`RigidMeshInstance`]s a ray is tested
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RaycastPrecision {
+    /// The ray is only tested against the [`AABB`](jeriya_shared::aabb::AABB)s of the [`RigidMeshInstance`]s
+    #[default]
+    BoundingBox,
+    /// The ray is tested against the triangles of the [`RigidMeshInstance`]s whose [`AABB`](jeriya_shared::aabb::AABB) is hit
+    Triangles,
+}
+
+/// [`RigidMeshInstance`] that has been hit by a ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    pub handle: Handle<RigidMeshInstance>,
+    /// Distance from the origin of the ray to the hit in world space
+    pub distance: f32,
+}
+
 pub struct RigidMeshInstanceGroup {
     gpu_index_allocator: Weak<dyn AllocateGpuIndex<RigidMeshInstance>>,
     indexing_container: IndexingContainer<RigidMeshInstance>,
+    /// Hierarchy over the world space [`AABB`](jeriya_shared::aabb::AABB)s of the [`RigidMeshInstance`]s. It's reset
+    /// whenever the [`RigidMeshInstance`]s might change and rebuilt by the next raycast.
+    bvh: Option<Bvh<Handle<RigidMeshInstance>>>,
     debug_info: DebugInfo,
 }
 
@@ -20,6 +46,7 @@ impl RigidMeshInstanceGroup {
         Self {
             gpu_index_allocator: gpu_index_allocator.provide_gpu_index_allocator(),
             indexing_container: IndexingContainer::new(),
+            bvh: None,
             debug_info,
         }
     }
@@ -29,6 +56,50 @@ impl RigidMeshInstanceGroup {
         self.indexing_container.get(handle)
     }
 
+    /// Returns the [`RigidMeshInstance`] with the given [`Handle`] mutably
+    pub fn get_mut(&mut self, handle: &Handle<RigidMeshInstance>) -> Option<&mut RigidMeshInstance> {
+        self.bvh = None;
+        self.indexing_container.get_mut(handle)
+    }
+
+    /// Returns the [`RigidMeshInstance`]s that are hit by the ray sorted by the distance to the `origin`
+    ///
+    /// The [`Motion`](rigid_mesh_instance::Motion)s of the [`RigidMeshInstance`]s are not taken into account because they
+    /// are only evaluated on the GPU. The bounding volume hierarchy is rebuilt when the [`RigidMeshInstance`]s have been
+    /// mutated since the last raycast.
+    pub fn raycast(&mut self, origin: Vector3<f32>, direction: Vector3<f32>, precision: RaycastPrecision) -> Vec<RaycastHit> {
+        let Some(direction) = direction.try_normalize(f32::EPSILON) else {
+            return Vec::new();
+        };
+        let indexing_container = &self.indexing_container;
+        let bvh = self.bvh.get_or_insert_with(|| {
+            let values = indexing_container.iter().map(|(handle, rigid_mesh_instance)| {
+                let aabb = rigid_mesh_instance
+                    .mesh_attributes()
+                    .bounding_box()
+                    .transformed(rigid_mesh_instance.transform());
+                (aabb, handle)
+            });
+            Bvh::new(values)
+        });
+        let mut hits = bvh
+            .raycast(&origin, &direction)
+            .into_iter()
+            .filter_map(|(handle, distance)| {
+                let distance = match precision {
+                    RaycastPrecision::BoundingBox => distance,
+                    RaycastPrecision::Triangles => {
+                        let rigid_mesh_instance = indexing_container.get(handle).expect("BVH contains a removed instance");
+                        intersect_triangles(rigid_mesh_instance, &origin, &direction)?
+                    }
+                };
+                Some(RaycastHit { handle: *handle, distance })
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
     /// Returns the [`DebugInfo`] of the [`RigidMeshInstanceGroup`]
     pub fn debug_info(&self) -> &DebugInfo {
         &self.debug_info
@@ -36,6 +107,7 @@ impl RigidMeshInstanceGroup {
 
     /// Returns a [`RigidMeshInstanceGroupAccessMut`] that can be used to mutate the [`RigidMeshInstanceGroup`] via the given [`Transaction`] or [`TransactionRecorder`].
     pub fn mutate_via<'g, 't, P: PushEvent>(&'g mut self, transaction: &'t mut P) -> RigidMeshInstanceGroupAccessMut<'g, 't, P> {
+        self.bvh = None;
         RigidMeshInstanceGroupAccessMut {
             rigid_mesh_group: self,
             transaction,
@@ -83,4 +155,379 @@ impl<'g, 't, P: PushEvent> RigidMeshInstanceGroupAccessMut<'g, 't, P> {
                 handle
             })
     }
+
+    /// Inserts many [`RigidMeshInstance`]s into the [`RigidMeshInstanceGroup`] at once.
+    ///
+    /// The GPU indices are allocated in a single batch and a single [`rigid_mesh_instance::Event::InsertMany`] is pushed
+    /// into the transaction. When one of the builders fails, none of the [`RigidMeshInstance`]s are inserted.
+    pub fn insert_many_with(
+        &mut self,
+        rigid_mesh_instance_builders: impl IntoIterator<Item = RigidMeshInstanceBuilder>,
+    ) -> rigid_mesh_instance::Result<Vec<Handle<RigidMeshInstance>>> {
+        let rigid_mesh_instance_builders = rigid_mesh_instance_builders.into_iter().collect::<Vec<_>>();
+        let gpu_index_allocator = self
+            .rigid_mesh_group
+            .gpu_index_allocator
+            .upgrade()
+            .expect("the gpu_index_allocator was dropped");
+        let gpu_index_allocations = gpu_index_allocator
+            .allocate_gpu_indices(rigid_mesh_instance_builders.len())
+            .ok_or(Error::AllocationFailed)?;
+
+        let mut handles = Vec::with_capacity(rigid_mesh_instance_builders.len());
+        for (rigid_mesh_instance_builder, gpu_index_allocation) in rigid_mesh_instance_builders.into_iter().zip(&gpu_index_allocations) {
+            let result = self
+                .rigid_mesh_group
+                .indexing_container
+                .insert_with(|handle| rigid_mesh_instance_builder.build(*handle, *gpu_index_allocation));
+            match result {
+                Ok(handle) => handles.push(handle),
+                Err(err) => {
+                    for handle in &handles {
+                        let rigid_mesh_instance = self
+                            .rigid_mesh_group
+                            .indexing_container
+                            .get(handle)
+                            .expect("inserted value not found")
+                            .clone();
+                        self.rigid_mesh_group.indexing_container.remove_with(handle, rigid_mesh_instance);
+                    }
+                    for gpu_index_allocation in gpu_index_allocations {
+                        gpu_index_allocator.free_gpu_index(gpu_index_allocation);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let rigid_mesh_instances = handles
+            .iter()
+            .map(|handle| {
+                self.rigid_mesh_group
+                    .indexing_container
+                    .get(handle)
+                    .expect("just inserted value not found")
+                    .clone()
+            })
+            .collect();
+        self.transaction
+            .push_event(transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::InsertMany(
+                rigid_mesh_instances,
+            )));
+        Ok(handles)
+    }
+
+    /// Removes the [`RigidMeshInstance`] with the given [`Handle`] from the [`RigidMeshInstanceGroup`].
+    ///
+    /// The GPU index of the [`RigidMeshInstance`] is freed so that it can be reused by subsequent insertions.
+    /// Returns `None` when the [`Handle`] doesn't reference a [`RigidMeshInstance`] in the [`RigidMeshInstanceGroup`].
+    pub fn remove(&mut self, handle: &Handle<RigidMeshInstance>) -> Option<RigidMeshInstance> {
+        let rigid_mesh_instance = self.rigid_mesh_group.indexing_container.get(handle)?.clone();
+        self.rigid_mesh_group
+            .indexing_container
+            .remove_with(handle, rigid_mesh_instance.clone());
+        self.transaction
+            .push_event(transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::Remove(
+                *rigid_mesh_instance.gpu_index_allocation(),
+            )));
+        let gpu_index_allocator = self
+            .rigid_mesh_group
+            .gpu_index_allocator
+            .upgrade()
+            .expect("the gpu_index_allocator was dropped");
+        gpu_index_allocator.free_gpu_index(*rigid_mesh_instance.gpu_index_allocation());
+        Some(rigid_mesh_instance)
+    }
+
+    /// Sets the transforms of many [`RigidMeshInstance`]s at once.
+    ///
+    /// Handles that don't reference a [`RigidMeshInstance`] in the [`RigidMeshInstanceGroup`] are skipped so that
+    /// instances that were removed in the meantime don't abort the whole batch. Returns the number of
+    /// [`RigidMeshInstance`]s whose transform was set.
+    pub fn set_transforms(&mut self, transforms: &[(Handle<RigidMeshInstance>, Matrix4<f32>)]) -> usize {
+        let mut count = 0;
+        for (handle, transform) in transforms {
+            if let Some(rigid_mesh_instance) = self.rigid_mesh_group.indexing_container.get_mut(handle) {
+                rigid_mesh_instance.mutate_via(self.transaction).set_transform(*transform);
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+/// Returns the distance to the closest triangle of the [`RigidMeshInstance`] that is hit by the ray in world space
+fn intersect_triangles(rigid_mesh_instance: &RigidMeshInstance, origin: &Vector3<f32>, direction: &Vector3<f32>) -> Option<f32> {
+    // Transforming the ray into the local space keeps the distances along the ray
+    let inverse_transform = rigid_mesh_instance.transform().try_inverse()?;
+    let local_origin = inverse_transform.transform_point(&Point3::from(*origin)).coords;
+    let local_direction = inverse_transform.transform_vector(direction);
+    triangles(rigid_mesh_instance.mesh_attributes())
+        .filter_map(|triangle| intersect_triangle(&local_origin, &local_direction, &triangle))
+        .min_by(f32::total_cmp)
+}
+
+/// Returns the triangles of the [`MeshAttributes`] which are given by the indices, the meshlets or the vertex positions
+fn triangles(mesh_attributes: &MeshAttributes) -> Box<dyn Iterator<Item = [Vector3<f32>; 3]> + '_> {
+    let positions = mesh_attributes.vertex_positions();
+    if let Some(indices) = mesh_attributes.indices() {
+        Box::new(
+            indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]].map(|index| positions[index as usize])),
+        )
+    } else if let Some(meshlets) = mesh_attributes.meshlets() {
+        Box::new(meshlets.iter().flat_map(move |meshlet| {
+            meshlet
+                .local_indices
+                .iter()
+                .map(move |triangle| triangle.map(|local_index| positions[meshlet.global_indices[local_index as usize] as usize]))
+        }))
+    } else {
+        Box::new(positions.chunks_exact(3).map(|triangle| [triangle[0], triangle[1], triangle[2]]))
+    }
+}
+
+/// Möller-Trumbore intersection of a ray with a triangle. Returns the distance in multiples of the `direction`.
+fn intersect_triangle(origin: &Vector3<f32>, direction: &Vector3<f32>, triangle: &[Vector3<f32>; 3]) -> Option<f32> {
+    let edge1 = triangle[1] - triangle[0];
+    let edge2 = triangle[2] - triangle[0];
+    let p = direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+    let s = origin - triangle[0];
+    let u = s.dot(&p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&edge1);
+    let v = direction.dot(&q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let distance = edge2.dot(&q) * inverse_determinant;
+    (distance >= 0.0).then_some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_shared::{
+        debug_info,
+        nalgebra::{Vector3, Vector4},
+    };
+
+    use crate::{
+        elements::{self, rigid_mesh::RigidMesh},
+        instances::{self, tests::new_instance_group_with_rigid_mesh_instance},
+        resources::{self, mesh_attributes_group::MeshAttributesGroup, tests::new_dummy_mesh_attributes},
+        transactions::Transaction,
+    };
+
+    use super::*;
+
+    #[test]
+    fn insert_many_with() {
+        let mut transaction = Transaction::new();
+        let elements_renderer = elements::MockRenderer::new();
+        let mut element_group = elements::element_group::ElementGroup::new(&elements_renderer, debug_info!("my_element_group"));
+        let rigid_mesh_handle = element_group
+            .rigid_meshes()
+            .mutate_via(&mut transaction)
+            .insert_with(RigidMesh::builder().with_mesh_attributes(new_dummy_mesh_attributes()))
+            .unwrap();
+        let rigid_mesh = element_group.rigid_meshes().get(&rigid_mesh_handle).unwrap();
+        transaction.process();
+
+        let renderer = instances::MockRenderer::new();
+        let mut rigid_mesh_instance_group = RigidMeshInstanceGroup::new(&renderer, debug_info!("my_rigid_mesh_instance_group"));
+
+        // A failing builder doesn't insert anything
+        let mut transaction = Transaction::new();
+        let result = rigid_mesh_instance_group.mutate_via(&mut transaction).insert_many_with([
+            RigidMeshInstance::builder().with_rigid_mesh(rigid_mesh),
+            RigidMeshInstance::builder(),
+        ]);
+        assert!(matches!(result, Err(Error::RigidMeshNotSet)));
+        assert!(transaction.process().is_empty());
+
+        let mut transaction = Transaction::new();
+        let handles = rigid_mesh_instance_group
+            .mutate_via(&mut transaction)
+            .insert_many_with((0..3).map(|i| {
+                RigidMeshInstance::builder()
+                    .with_rigid_mesh(rigid_mesh)
+                    .with_transform(Matrix4::new_translation(&Vector3::new(i as f32, 0.0, 0.0)))
+            }))
+            .unwrap();
+        assert_eq!(handles.len(), 3);
+        let events = transaction.process();
+        assert_eq!(events.len(), 1);
+        let transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::InsertMany(rigid_mesh_instances)) = &events[0] else {
+            panic!("unexpected event")
+        };
+        // The GPU indices of the failed insertion were freed again
+        let mut gpu_indices = rigid_mesh_instances
+            .iter()
+            .map(|rigid_mesh_instance| rigid_mesh_instance.gpu_index_allocation().index())
+            .collect::<Vec<_>>();
+        gpu_indices.sort();
+        assert_eq!(gpu_indices, vec![0, 1, 2]);
+        for (i, handle) in handles.iter().enumerate() {
+            let rigid_mesh_instance = rigid_mesh_instance_group.get(handle).unwrap();
+            assert_eq!(
+                rigid_mesh_instance.transform(),
+                &Matrix4::new_translation(&Vector3::new(i as f32, 0.0, 0.0))
+            );
+        }
+    }
+
+    #[test]
+    fn raycast() {
+        let mut transaction = Transaction::new();
+        let resources_renderer = resources::MockRenderer::new();
+        let mut mesh_attributes_group = MeshAttributesGroup::new(&resources_renderer, debug_info!("my_mesh_attributes_group"));
+        let mesh_attributes = mesh_attributes_group
+            .insert_with(
+                MeshAttributes::builder()
+                    .with_vertex_positions(vec![
+                        Vector3::new(-1.0, -1.0, 0.0),
+                        Vector3::new(1.0, -1.0, 0.0),
+                        Vector3::new(0.0, 1.0, 0.0),
+                    ])
+                    .with_vertex_normals(vec![Vector3::z(); 3])
+                    .with_indices(vec![0, 1, 2]),
+            )
+            .unwrap();
+        let elements_renderer = elements::MockRenderer::new();
+        let mut element_group = elements::element_group::ElementGroup::new(&elements_renderer, debug_info!("my_element_group"));
+        let rigid_mesh_handle = element_group
+            .rigid_meshes()
+            .mutate_via(&mut transaction)
+            .insert_with(RigidMesh::builder().with_mesh_attributes(mesh_attributes))
+            .unwrap();
+        let rigid_mesh = element_group.rigid_meshes().get(&rigid_mesh_handle).unwrap();
+
+        // Two triangles behind each other
+        let renderer = instances::MockRenderer::new();
+        let mut rigid_mesh_instance_group = RigidMeshInstanceGroup::new(&renderer, debug_info!("my_rigid_mesh_instance_group"));
+        let handles = rigid_mesh_instance_group
+            .mutate_via(&mut transaction)
+            .insert_many_with([0.0, 5.0].map(|z| {
+                RigidMeshInstance::builder()
+                    .with_rigid_mesh(rigid_mesh)
+                    .with_transform(Matrix4::new_translation(&Vector3::new(0.0, 0.0, z)))
+            }))
+            .unwrap();
+        transaction.process();
+
+        let direction = Vector3::new(0.0, 0.0, 2.0);
+        let hits = rigid_mesh_instance_group.raycast(Vector3::new(0.0, 0.0, -10.0), direction, RaycastPrecision::Triangles);
+        assert_eq!(hits.iter().map(|hit| hit.handle).collect::<Vec<_>>(), handles);
+        assert!((hits[0].distance - 10.0).abs() < 1e-5);
+        assert!((hits[1].distance - 15.0).abs() < 1e-5);
+
+        // The ray hits the bounding boxes next to the triangles
+        let origin = Vector3::new(0.9, 0.9, -10.0);
+        assert_eq!(
+            rigid_mesh_instance_group
+                .raycast(origin, direction, RaycastPrecision::BoundingBox)
+                .len(),
+            2
+        );
+        assert!(rigid_mesh_instance_group
+            .raycast(origin, direction, RaycastPrecision::Triangles)
+            .is_empty());
+
+        // Removed instances are not hit
+        let mut transaction = Transaction::new();
+        rigid_mesh_instance_group.mutate_via(&mut transaction).remove(&handles[0]);
+        transaction.process();
+        let hits = rigid_mesh_instance_group.raycast(Vector3::new(0.0, 0.0, -10.0), direction, RaycastPrecision::Triangles);
+        assert_eq!(hits.iter().map(|hit| hit.handle).collect::<Vec<_>>(), vec![handles[1]]);
+    }
+
+    #[test]
+    fn set_transforms() {
+        let (_renderer, mut instance_group, rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        let transform = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0));
+
+        let mut transaction = Transaction::with_capacity(1);
+        let count = instance_group
+            .rigid_mesh_instances()
+            .mutate_via(&mut transaction)
+            .set_transforms(&[(rigid_mesh_instance, transform)]);
+        assert_eq!(count, 1);
+        assert_eq!(transaction.process().len(), 1);
+        assert_eq!(
+            instance_group.rigid_mesh_instances().get(&rigid_mesh_instance).unwrap().transform(),
+            &transform
+        );
+
+        // Stale handles are skipped
+        let mut transaction = Transaction::new();
+        let mut rigid_mesh_instances = instance_group.rigid_mesh_instances().mutate_via(&mut transaction);
+        rigid_mesh_instances.remove(&rigid_mesh_instance);
+        assert_eq!(rigid_mesh_instances.set_transforms(&[(rigid_mesh_instance, transform)]), 0);
+        assert_eq!(transaction.process().len(), 1);
+    }
+
+    #[test]
+    fn set_color() {
+        let (_renderer, mut instance_group, rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        assert_eq!(
+            instance_group.rigid_mesh_instances().get(&rigid_mesh_instance).unwrap().color(),
+            &RigidMeshInstance::NO_TINT
+        );
+
+        let color = Vector4::new(1.0, 0.0, 0.0, 0.5);
+        let mut transaction = Transaction::new();
+        instance_group
+            .rigid_mesh_instances()
+            .get_mut(&rigid_mesh_instance)
+            .unwrap()
+            .mutate_via(&mut transaction)
+            .set_color(color);
+        let events = transaction.process();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateColor(_, c)) if c == &color
+        ));
+        assert_eq!(
+            instance_group.rigid_mesh_instances().get(&rigid_mesh_instance).unwrap().color(),
+            &color
+        );
+    }
+
+    #[test]
+    fn set_selected() {
+        let (_renderer, mut instance_group, rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        assert!(!instance_group
+            .rigid_mesh_instances()
+            .get(&rigid_mesh_instance)
+            .unwrap()
+            .is_selected());
+
+        let mut transaction = Transaction::new();
+        instance_group
+            .rigid_mesh_instances()
+            .get_mut(&rigid_mesh_instance)
+            .unwrap()
+            .mutate_via(&mut transaction)
+            .set_selected(true);
+        let events = transaction.process();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateSelected(_, true))
+        ));
+        assert!(instance_group
+            .rigid_mesh_instances()
+            .get(&rigid_mesh_instance)
+            .unwrap()
+            .is_selected());
+    }
 }
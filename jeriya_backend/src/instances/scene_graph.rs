@@ -0,0 +1,364 @@
+use jeriya_shared::{nalgebra::Matrix4, thiserror, DebugInfo, Handle, IndexingContainer};
+
+use crate::{
+    instances::{instance_group::InstanceGroup, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance},
+    transactions::PushEvent,
+};
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("The SceneNode doesn't exist in the SceneGraph")]
+    UnknownNode,
+    #[error("The SceneNode can't become a descendant of itself")]
+    CycleDetected,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Instance whose transform is controlled by a [`SceneNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachedInstance {
+    RigidMesh(Handle<RigidMeshInstance>),
+    PointCloud(Handle<PointCloudInstance>),
+}
+
+/// Node in the [`SceneGraph`] whose world transform is the product of the world transform of its parent and its local transform
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+    parent: Option<Handle<SceneNode>>,
+    children: Vec<Handle<SceneNode>>,
+    local_transform: Matrix4<f32>,
+    world_transform: Matrix4<f32>,
+    instances: Vec<AttachedInstance>,
+}
+
+impl SceneNode {
+    /// Creates a [`SceneNode`] whose world transform is computed from the transform of its parent
+    fn new(parent: Option<Handle<SceneNode>>, local_transform: Matrix4<f32>, parent_transform: Matrix4<f32>) -> Self {
+        Self {
+            parent,
+            children: Vec::new(),
+            local_transform,
+            world_transform: parent_transform * local_transform,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Returns the parent of the [`SceneNode`] or `None` when it is a root
+    pub fn parent(&self) -> Option<&Handle<SceneNode>> {
+        self.parent.as_ref()
+    }
+
+    /// Returns the children of the [`SceneNode`]
+    pub fn children(&self) -> &[Handle<SceneNode>] {
+        &self.children
+    }
+
+    /// Returns the transform of the [`SceneNode`] relative to its parent
+    pub fn local_transform(&self) -> &Matrix4<f32> {
+        &self.local_transform
+    }
+
+    /// Returns the transform of the [`SceneNode`] relative to the world
+    pub fn world_transform(&self) -> &Matrix4<f32> {
+        &self.world_transform
+    }
+
+    /// Returns the instances that are attached to the [`SceneNode`]
+    pub fn instances(&self) -> &[AttachedInstance] {
+        &self.instances
+    }
+}
+
+/// Hierarchy of transforms on top of an [`InstanceGroup`]
+///
+/// Instances are attached to [`SceneNode`]s. Whenever the transform of a [`SceneNode`] changes, the world transforms
+/// of the [`SceneNode`] and all of its descendants are recomputed and pushed to the attached instances in the same
+/// transaction.
+pub struct SceneGraph {
+    nodes: IndexingContainer<SceneNode>,
+    debug_info: DebugInfo,
+}
+
+impl SceneGraph {
+    /// Creates a new empty [`SceneGraph`]
+    pub fn new(debug_info: DebugInfo) -> Self {
+        Self {
+            nodes: IndexingContainer::new(),
+            debug_info,
+        }
+    }
+
+    /// Returns the [`SceneNode`] with the given [`Handle`]
+    pub fn get(&self, handle: &Handle<SceneNode>) -> Option<&SceneNode> {
+        self.nodes.get(handle)
+    }
+
+    /// Returns the number of [`SceneNode`]s in the [`SceneGraph`]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the [`SceneGraph`] contains no [`SceneNode`]s
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the [`DebugInfo`] of the [`SceneGraph`]
+    pub fn debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+
+    /// Returns a [`SceneGraphAccessMut`] that can be used to mutate the [`SceneGraph`] and the instances in the given
+    /// [`InstanceGroup`] via the given [`Transaction`] or [`TransactionRecorder`].
+    pub fn mutate_via<'g, 'i, 't, P: PushEvent>(
+        &'g mut self,
+        instance_group: &'i mut InstanceGroup,
+        transaction: &'t mut P,
+    ) -> SceneGraphAccessMut<'g, 'i, 't, P> {
+        SceneGraphAccessMut {
+            scene_graph: self,
+            instance_group,
+            transaction,
+        }
+    }
+
+    fn node(&self, handle: &Handle<SceneNode>) -> Result<&SceneNode> {
+        self.nodes.get(handle).ok_or(Error::UnknownNode)
+    }
+
+    fn node_mut(&mut self, handle: &Handle<SceneNode>) -> Result<&mut SceneNode> {
+        self.nodes.get_mut(handle).ok_or(Error::UnknownNode)
+    }
+
+    /// Returns `true` if `ancestor` is `handle` or one of its ancestors.
+    fn is_ancestor_of(&self, ancestor: &Handle<SceneNode>, handle: &Handle<SceneNode>) -> bool {
+        let mut current = Some(*handle);
+        while let Some(node_handle) = current {
+            if &node_handle == ancestor {
+                return true;
+            }
+            current = self.nodes.get(&node_handle).and_then(|node| node.parent);
+        }
+        false
+    }
+}
+
+pub struct SceneGraphAccessMut<'g, 'i, 't, P: PushEvent> {
+    scene_graph: &'g mut SceneGraph,
+    instance_group: &'i mut InstanceGroup,
+    transaction: &'t mut P,
+}
+
+impl<'g, 'i, 't, P: PushEvent> SceneGraphAccessMut<'g, 'i, 't, P> {
+    /// Inserts a new [`SceneNode`] with the given transform relative to the `parent`. The [`SceneNode`] is a root when
+    /// `parent` is `None`.
+    pub fn insert(&mut self, parent: Option<&Handle<SceneNode>>, local_transform: Matrix4<f32>) -> Result<Handle<SceneNode>> {
+        let parent_transform = match parent {
+            Some(parent) => *self.scene_graph.node(parent)?.world_transform(),
+            None => Matrix4::identity(),
+        };
+        let handle = self
+            .scene_graph
+            .nodes
+            .insert(SceneNode::new(parent.copied(), local_transform, parent_transform));
+        if let Some(parent) = parent {
+            self.scene_graph.node_mut(parent)?.children.push(handle);
+        }
+        Ok(handle)
+    }
+
+    /// Attaches the instance to the [`SceneNode`] and sets the transform of the instance to the world transform of the [`SceneNode`].
+    pub fn attach(&mut self, handle: &Handle<SceneNode>, instance: AttachedInstance) -> Result<()> {
+        let node = self.scene_graph.node_mut(handle)?;
+        if !node.instances.contains(&instance) {
+            node.instances.push(instance);
+        }
+        let world_transform = node.world_transform;
+        self.set_instance_transform(instance, world_transform);
+        Ok(())
+    }
+
+    /// Detaches the instance from the [`SceneNode`]. The transform of the instance is left unchanged.
+    pub fn detach(&mut self, handle: &Handle<SceneNode>, instance: AttachedInstance) -> Result<()> {
+        self.scene_graph
+            .node_mut(handle)?
+            .instances
+            .retain(|attached| attached != &instance);
+        Ok(())
+    }
+
+    /// Sets the transform of the [`SceneNode`] relative to its parent and updates the instances of the [`SceneNode`] and its descendants.
+    pub fn set_local_transform(&mut self, handle: &Handle<SceneNode>, local_transform: Matrix4<f32>) -> Result<()> {
+        self.scene_graph.node_mut(handle)?.local_transform = local_transform;
+        self.update_world_transforms(handle)
+    }
+
+    /// Moves the [`SceneNode`] to the given `parent` while keeping its local transform. The [`SceneNode`] becomes a root
+    /// when `parent` is `None`.
+    pub fn set_parent(&mut self, handle: &Handle<SceneNode>, parent: Option<&Handle<SceneNode>>) -> Result<()> {
+        let previous_parent = self.scene_graph.node(handle)?.parent;
+        if let Some(parent) = parent {
+            self.scene_graph.node(parent)?;
+            if self.scene_graph.is_ancestor_of(handle, parent) {
+                return Err(Error::CycleDetected);
+            }
+        }
+        if let Some(previous_parent) = previous_parent {
+            self.scene_graph
+                .node_mut(&previous_parent)?
+                .children
+                .retain(|child| child != handle);
+        }
+        if let Some(parent) = parent {
+            self.scene_graph.node_mut(parent)?.children.push(*handle);
+        }
+        self.scene_graph.node_mut(handle)?.parent = parent.copied();
+        self.update_world_transforms(handle)
+    }
+
+    /// Removes the [`SceneNode`] together with all of its descendants. The attached instances are not removed from the
+    /// [`InstanceGroup`] and keep their last transform.
+    pub fn remove(&mut self, handle: &Handle<SceneNode>) -> Result<()> {
+        let parent = self.scene_graph.node(handle)?.parent;
+        if let Some(parent) = parent {
+            self.scene_graph.node_mut(&parent)?.children.retain(|child| child != handle);
+        }
+        let mut stack = vec![*handle];
+        while let Some(handle) = stack.pop() {
+            let placeholder = SceneNode::new(None, Matrix4::identity(), Matrix4::identity());
+            if let Some(node) = self.scene_graph.nodes.remove_with(&handle, placeholder) {
+                stack.extend(node.children);
+            }
+        }
+        Ok(())
+    }
+
+    /// Recomputes the world transforms of the [`SceneNode`] and its descendants and pushes them to the attached instances.
+    fn update_world_transforms(&mut self, handle: &Handle<SceneNode>) -> Result<()> {
+        let parent_transform = match self.scene_graph.node(handle)?.parent {
+            Some(parent) => *self.scene_graph.node(&parent)?.world_transform(),
+            None => Matrix4::identity(),
+        };
+        let mut stack = vec![(*handle, parent_transform)];
+        while let Some((handle, parent_transform)) = stack.pop() {
+            let node = self.scene_graph.node_mut(&handle)?;
+            node.world_transform = parent_transform * node.local_transform;
+            let world_transform = node.world_transform;
+            stack.extend(node.children.iter().map(|child| (*child, world_transform)));
+            let instances = node.instances.clone();
+            for instance in instances {
+                self.set_instance_transform(instance, world_transform);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the transform of the instance. Instances that were removed from the [`InstanceGroup`] are skipped.
+    fn set_instance_transform(&mut self, instance: AttachedInstance, transform: Matrix4<f32>) {
+        match instance {
+            AttachedInstance::RigidMesh(handle) => {
+                if let Some(rigid_mesh_instance) = self.instance_group.rigid_mesh_instances().get_mut(&handle) {
+                    rigid_mesh_instance.mutate_via(&mut *self.transaction).set_transform(transform);
+                }
+            }
+            AttachedInstance::PointCloud(handle) => {
+                if let Some(point_cloud_instance) = self.instance_group.point_cloud_instances().get_mut(&handle) {
+                    point_cloud_instance.mutate_via(&mut *self.transaction).set_transform(transform);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_shared::{debug_info, nalgebra::Vector3};
+
+    use crate::{
+        instances::{rigid_mesh_instance, tests::new_instance_group_with_rigid_mesh_instance},
+        transactions::{self, Transaction},
+    };
+
+    use super::*;
+
+    fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32> {
+        Matrix4::new_translation(&Vector3::new(x, y, z))
+    }
+
+    #[test]
+    fn parent_transform_propagated() {
+        let (_renderer, mut instance_group, rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        let mut scene_graph = SceneGraph::new(debug_info!("my_scene_graph"));
+
+        let mut transaction = Transaction::new();
+        let mut access = scene_graph.mutate_via(&mut instance_group, &mut transaction);
+        let root = access.insert(None, translation(1.0, 0.0, 0.0)).unwrap();
+        let child = access.insert(Some(&root), translation(0.0, 2.0, 0.0)).unwrap();
+        access.attach(&child, AttachedInstance::RigidMesh(rigid_mesh_instance)).unwrap();
+        assert_eq!(scene_graph.get(&child).unwrap().world_transform(), &translation(1.0, 2.0, 0.0));
+        assert_eq!(
+            instance_group.rigid_mesh_instances().get(&rigid_mesh_instance).unwrap().transform(),
+            &translation(1.0, 2.0, 0.0)
+        );
+        assert_eq!(transaction.len(), 1);
+        transaction.process();
+
+        // Moving the root updates the instance of the child in a single transaction
+        let mut transaction = Transaction::new();
+        scene_graph
+            .mutate_via(&mut instance_group, &mut transaction)
+            .set_local_transform(&root, translation(5.0, 0.0, 0.0))
+            .unwrap();
+        let events = transaction.process();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateTransform(_, transform))
+                if transform == &translation(5.0, 2.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn set_parent() {
+        let (_renderer, mut instance_group, rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        let mut scene_graph = SceneGraph::new(debug_info!("my_scene_graph"));
+
+        let mut transaction = Transaction::new();
+        let mut access = scene_graph.mutate_via(&mut instance_group, &mut transaction);
+        let a = access.insert(None, translation(1.0, 0.0, 0.0)).unwrap();
+        let b = access.insert(None, translation(0.0, 1.0, 0.0)).unwrap();
+        let c = access.insert(Some(&a), translation(0.0, 0.0, 1.0)).unwrap();
+        access.attach(&c, AttachedInstance::RigidMesh(rigid_mesh_instance)).unwrap();
+
+        access.set_parent(&c, Some(&b)).unwrap();
+        assert_eq!(access.set_parent(&b, Some(&c)), Err(Error::CycleDetected));
+        assert_eq!(access.set_parent(&b, Some(&b)), Err(Error::CycleDetected));
+        assert!(scene_graph.get(&a).unwrap().children().is_empty());
+        assert_eq!(scene_graph.get(&b).unwrap().children(), &[c]);
+        assert_eq!(
+            instance_group.rigid_mesh_instances().get(&rigid_mesh_instance).unwrap().transform(),
+            &translation(0.0, 1.0, 1.0)
+        );
+        transaction.process();
+    }
+
+    #[test]
+    fn remove() {
+        let (_renderer, mut instance_group, _rigid_mesh_instance) = new_instance_group_with_rigid_mesh_instance();
+        let mut scene_graph = SceneGraph::new(debug_info!("my_scene_graph"));
+
+        let mut transaction = Transaction::new();
+        let mut access = scene_graph.mutate_via(&mut instance_group, &mut transaction);
+        let root = access.insert(None, Matrix4::identity()).unwrap();
+        let child = access.insert(Some(&root), Matrix4::identity()).unwrap();
+        access.insert(Some(&child), Matrix4::identity()).unwrap();
+        let other = access.insert(None, Matrix4::identity()).unwrap();
+        access.remove(&child).unwrap();
+        assert_eq!(access.remove(&child), Err(Error::UnknownNode));
+        assert_eq!(scene_graph.len(), 2);
+        assert!(scene_graph.get(&root).unwrap().children().is_empty());
+        assert!(scene_graph.get(&other).is_some());
+        transaction.process();
+    }
+}
@@ -1,21 +1,32 @@
 mod backend;
+pub mod clip_plane;
+pub mod debug;
 pub mod elements;
 pub mod gpu_index_allocator;
 pub mod immediate;
 pub mod instances;
+pub mod overlay;
+pub mod performance_hud;
+pub mod render_target;
 pub mod resources;
+pub mod statistics;
 pub mod transactions;
+pub mod ui;
+pub mod viewport;
 
 pub use backend::*;
 use jeriya_shared::{thiserror, winit::window::WindowId};
+use render_target::RenderTargetId;
 
 /// Error type for the whole library
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("No windows are given")]
+    #[error("No windows or headless render targets are given")]
     ExpectedWindow,
     #[error("The given window id is not known")]
     UnknownWindowId(WindowId),
+    #[error("The given render target is not known: {0:?}")]
+    UnknownRenderTarget(RenderTargetId),
     #[error("The maximum capacity of elements is reached: {0}")]
     MaximumCapacityReached(usize),
     #[error("Error from the backend: {0}")]
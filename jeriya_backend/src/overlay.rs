@@ -0,0 +1,364 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use jeriya_shared::{
+    nalgebra::{Vector2, Vector3, Vector4},
+    AsDebugInfo, DebugInfo,
+};
+
+use crate::{immediate::LineConfig, render_target::RenderTargetId};
+
+/// Identifies an [`OverlayImage`]. Clones of an `OverlayImage` share its id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OverlayImageId(u64);
+
+/// Image with 8-bit RGBA texels that is displayed by a [`TexturedQuad`]
+///
+/// The image is uploaded per window when it is rendered for the first time and is identified by its [`OverlayImageId`].
+/// It is released again when a frame of the window doesn't render it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayImage {
+    id: OverlayImageId,
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl OverlayImage {
+    /// Creates a new `OverlayImage` from tightly packed rows of RGBA texels starting with the top row
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `width` or `height` is zero.
+    /// - Panics if the length of `rgba` doesn't match the dimensions.
+    pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Self {
+        assert!(width > 0 && height > 0, "OverlayImage must not be empty");
+        assert_eq!(
+            rgba.len(),
+            width as usize * height as usize * 4,
+            "Length of the texels doesn't match the dimensions"
+        );
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = OverlayImageId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        Self { id, width, height, rgba }
+    }
+
+    /// Returns the id of the `OverlayImage`
+    pub fn id(&self) -> OverlayImageId {
+        self.id
+    }
+
+    /// Returns the width of the `OverlayImage` in texels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the `OverlayImage` in texels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the RGBA texels of the `OverlayImage`
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+}
+
+/// Filled axis-aligned rectangle in pixels from the upper left corner of the window
+#[derive(Debug, Clone)]
+pub struct Rectangle {
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+    color: Vector4<f32>,
+}
+
+impl Rectangle {
+    /// Creates a new `Rectangle` from its upper left corner `min` and its lower right corner `max`
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>, color: Vector4<f32>) -> Self {
+        Self { min, max, color }
+    }
+
+    /// Returns the upper left corner of the `Rectangle`
+    pub fn min(&self) -> &Vector2<f32> {
+        &self.min
+    }
+
+    /// Returns the lower right corner of the `Rectangle`
+    pub fn max(&self) -> &Vector2<f32> {
+        &self.max
+    }
+
+    /// Returns the color of the `Rectangle`
+    pub fn color(&self) -> &Vector4<f32> {
+        &self.color
+    }
+
+    /// Returns the positions of the triangle list that fills the `Rectangle`
+    pub fn positions(&self) -> Vec<Vector3<f32>> {
+        quad_vertices(&self.min, &self.max, &Vector2::zeros(), &Vector2::zeros()).0
+    }
+}
+
+/// Line segment in pixels from the upper left corner of the window
+#[derive(Debug, Clone)]
+pub struct Line {
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    config: LineConfig,
+}
+
+impl Line {
+    /// Creates a new `Line` from `start` to `end`
+    pub fn new(start: Vector2<f32>, end: Vector2<f32>, config: LineConfig) -> Self {
+        Self { start, end, config }
+    }
+
+    /// Returns the start of the `Line`
+    pub fn start(&self) -> &Vector2<f32> {
+        &self.start
+    }
+
+    /// Returns the end of the `Line`
+    pub fn end(&self) -> &Vector2<f32> {
+        &self.end
+    }
+
+    /// Returns the [`LineConfig`] of the `Line`
+    pub fn config(&self) -> &LineConfig {
+        &self.config
+    }
+
+    /// Returns the positions of the line list that displays the `Line`
+    pub fn positions(&self) -> Vec<Vector3<f32>> {
+        vec![self.start.push(0.0), self.end.push(0.0)]
+    }
+}
+
+/// Texture that is displayed by a [`TexturedQuad`]
+#[derive(Debug, Clone)]
+pub enum QuadTexture {
+    Image(Arc<OverlayImage>),
+    /// Image of a render target of the same window. The quad is skipped when the render target doesn't exist.
+    RenderTarget(RenderTargetId),
+}
+
+/// Axis-aligned rectangle in pixels from the upper left corner of the window that displays a [`QuadTexture`]
+#[derive(Debug, Clone)]
+pub struct TexturedQuad {
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+    texture: QuadTexture,
+    tint: Vector4<f32>,
+}
+
+impl TexturedQuad {
+    /// Creates a new `TexturedQuad` from its upper left corner `min` and its lower right corner `max`
+    ///
+    /// The texels of the `image` are multiplied with the `tint`.
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>, image: Arc<OverlayImage>, tint: Vector4<f32>) -> Self {
+        Self {
+            min,
+            max,
+            texture: QuadTexture::Image(image),
+            tint,
+        }
+    }
+
+    /// Creates a new `TexturedQuad` that displays the image of the render target with the given id
+    ///
+    /// The texels of the render target are multiplied with the `tint`.
+    pub fn from_render_target(min: Vector2<f32>, max: Vector2<f32>, render_target_id: RenderTargetId, tint: Vector4<f32>) -> Self {
+        Self {
+            min,
+            max,
+            texture: QuadTexture::RenderTarget(render_target_id),
+            tint,
+        }
+    }
+
+    /// Returns the upper left corner of the `TexturedQuad`
+    pub fn min(&self) -> &Vector2<f32> {
+        &self.min
+    }
+
+    /// Returns the lower right corner of the `TexturedQuad`
+    pub fn max(&self) -> &Vector2<f32> {
+        &self.max
+    }
+
+    /// Returns the [`QuadTexture`] that is displayed by the `TexturedQuad`
+    pub fn texture(&self) -> &QuadTexture {
+        &self.texture
+    }
+
+    /// Returns the [`OverlayImage`] that is displayed by the `TexturedQuad` or `None` when it displays a render target
+    pub fn image(&self) -> Option<&Arc<OverlayImage>> {
+        match &self.texture {
+            QuadTexture::Image(image) => Some(image),
+            QuadTexture::RenderTarget(_) => None,
+        }
+    }
+
+    /// Returns the color with which the texels are multiplied
+    pub fn tint(&self) -> &Vector4<f32> {
+        &self.tint
+    }
+
+    /// Returns the positions and texture coordinates of the triangle list that displays the `TexturedQuad`
+    pub fn vertices(&self) -> (Vec<Vector3<f32>>, Vec<Vector2<f32>>) {
+        quad_vertices(&self.min, &self.max, &Vector2::new(0.0, 0.0), &Vector2::new(1.0, 1.0))
+    }
+}
+
+/// Returns the two triangles of the quad between the upper left corner `min` and the lower right corner `max`
+fn quad_vertices(
+    min: &Vector2<f32>,
+    max: &Vector2<f32>,
+    uv_min: &Vector2<f32>,
+    uv_max: &Vector2<f32>,
+) -> (Vec<Vector3<f32>>, Vec<Vector2<f32>>) {
+    let corners = [
+        (Vector3::new(min.x, min.y, 0.0), Vector2::new(uv_min.x, uv_min.y)),
+        (Vector3::new(min.x, max.y, 0.0), Vector2::new(uv_min.x, uv_max.y)),
+        (Vector3::new(max.x, max.y, 0.0), Vector2::new(uv_max.x, uv_max.y)),
+        (Vector3::new(max.x, min.y, 0.0), Vector2::new(uv_max.x, uv_min.y)),
+    ];
+    [0, 1, 2, 0, 2, 3].into_iter().map(|index| corners[index]).unzip()
+}
+
+#[derive(Debug, Clone)]
+pub enum OverlayCommand {
+    Rectangle(Rectangle),
+    Line(Line),
+    TexturedQuad(TexturedQuad),
+}
+
+/// Command buffer for the 2D overlay that is rendered on top of the scene.
+///
+/// All coordinates are in pixels from the upper left corner of the window. The commands are rendered in
+/// the order in which they have been pushed so that later commands are drawn over earlier ones.
+#[derive(Clone)]
+pub struct OverlayCommandBuffer {
+    commands: Vec<OverlayCommand>,
+    debug_info: DebugInfo,
+}
+
+impl OverlayCommandBuffer {
+    /// Creates a new empty `OverlayCommandBuffer`
+    pub fn new(debug_info: DebugInfo) -> Self {
+        Self {
+            commands: Vec::new(),
+            debug_info,
+        }
+    }
+
+    pub fn commands(&self) -> &[OverlayCommand] {
+        &self.commands
+    }
+}
+
+impl AsDebugInfo for OverlayCommandBuffer {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+/// Creates a new [`OverlayCommandBuffer`]
+pub struct OverlayCommandBufferBuilder {
+    command_buffer: OverlayCommandBuffer,
+}
+
+impl OverlayCommandBufferBuilder {
+    /// Creates a new `OverlayCommandBufferBuilder`
+    pub fn new(debug_info: DebugInfo) -> Self {
+        Self {
+            command_buffer: OverlayCommandBuffer::new(debug_info),
+        }
+    }
+
+    /// Pushes new [`Rectangle`]s to the `OverlayCommandBufferBuilder`
+    pub fn push_rectangles(mut self, rectangles: &[Rectangle]) -> crate::Result<Self> {
+        for rectangle in rectangles {
+            self.command_buffer.commands.push(OverlayCommand::Rectangle(rectangle.clone()));
+        }
+        Ok(self)
+    }
+
+    /// Pushes new [`Line`]s to the `OverlayCommandBufferBuilder`
+    pub fn push_lines(mut self, lines: &[Line]) -> crate::Result<Self> {
+        for line in lines {
+            self.command_buffer.commands.push(OverlayCommand::Line(line.clone()));
+        }
+        Ok(self)
+    }
+
+    /// Pushes new [`TexturedQuad`]s to the `OverlayCommandBufferBuilder`
+    pub fn push_textured_quads(mut self, textured_quads: &[TexturedQuad]) -> crate::Result<Self> {
+        for textured_quad in textured_quads {
+            self.command_buffer
+                .commands
+                .push(OverlayCommand::TexturedQuad(textured_quad.clone()));
+        }
+        Ok(self)
+    }
+
+    /// Finalizes the creation of the [`OverlayCommandBuffer`]
+    pub fn build(self) -> crate::Result<OverlayCommandBuffer> {
+        Ok(self.command_buffer)
+    }
+}
+
+impl AsDebugInfo for OverlayCommandBufferBuilder {
+    fn as_debug_info(&self) -> &DebugInfo {
+        self.command_buffer.as_debug_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_shared::debug_info;
+
+    use super::*;
+
+    #[test]
+    fn textured_quad_vertices() {
+        let image = Arc::new(OverlayImage::new(1, 1, vec![255; 4]));
+        let textured_quad = TexturedQuad::new(Vector2::new(10.0, 20.0), Vector2::new(30.0, 60.0), image, Vector4::repeat(1.0));
+        let (positions, texture_coordinates) = textured_quad.vertices();
+        assert_eq!(positions.len(), 6);
+        assert_eq!(positions[0], Vector3::new(10.0, 20.0, 0.0));
+        assert_eq!(texture_coordinates[0], Vector2::new(0.0, 0.0));
+        assert_eq!(positions[2], Vector3::new(30.0, 60.0, 0.0));
+        assert_eq!(texture_coordinates[2], Vector2::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn commands_keep_their_order() {
+        let command_buffer = OverlayCommandBufferBuilder::new(debug_info!("my_overlay_command_buffer"))
+            .push_rectangles(&[Rectangle::new(Vector2::zeros(), Vector2::new(1.0, 1.0), Vector4::repeat(1.0))])
+            .unwrap()
+            .push_lines(&[Line::new(Vector2::zeros(), Vector2::new(1.0, 1.0), LineConfig::default())])
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(matches!(
+            command_buffer.commands(),
+            [OverlayCommand::Rectangle(_), OverlayCommand::Line(_)]
+        ));
+    }
+
+    #[test]
+    fn image_ids_are_unique() {
+        let image = OverlayImage::new(1, 1, vec![255; 4]);
+        assert_ne!(image.id(), OverlayImage::new(1, 1, vec![255; 4]).id());
+        assert_eq!(image.id(), image.clone().id());
+    }
+
+    #[test]
+    #[should_panic]
+    fn image_with_wrong_length() {
+        OverlayImage::new(2, 2, vec![0; 4]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::{collections::VecDeque, time::Duration};
+
+use jeriya_shared::{
+    debug_info,
+    nalgebra::{Vector2, Vector4},
+};
+
+use crate::{
+    immediate::LineConfig,
+    overlay::{Line, OverlayCommandBuffer, OverlayCommandBufferBuilder, Rectangle},
+};
+
+/// Measurements of a single frame that are plotted by the [`PerformanceHud`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerformanceSample {
+    /// Time that the CPU spent on the frame
+    pub frame_time: Duration,
+    /// Number of meshlets that passed the culling
+    pub visible_meshlets: u32,
+    /// Bytes per second that have been copied from the host to the device
+    pub upload_bandwidth: f64,
+}
+
+/// Overlay that plots the [`PerformanceSample`]s of the most recent frames in the upper left corner of a window
+///
+/// From top to bottom, the graphs show the frame time, the visible meshlets and the upload bandwidth. The frame time
+/// graph contains a reference line at [`PerformanceHud::TARGET_FRAME_TIME`]. The other graphs are scaled to the
+/// maximum of the plotted samples.
+#[derive(Debug, Clone)]
+pub struct PerformanceHud {
+    samples: VecDeque<PerformanceSample>,
+    history_length: usize,
+}
+
+impl Default for PerformanceHud {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_HISTORY_LENGTH)
+    }
+}
+
+impl PerformanceHud {
+    /// Number of frames that are plotted by default
+    pub const DEFAULT_HISTORY_LENGTH: usize = 120;
+
+    /// Frame time at which the reference line of the frame time graph is drawn
+    pub const TARGET_FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+    const POSITION: Vector2<f32> = Vector2::new(10.0, 10.0);
+    const GRAPH_SIZE: Vector2<f32> = Vector2::new(240.0, 50.0);
+    const GRAPH_SPACING: f32 = 6.0;
+
+    /// Creates a new `PerformanceHud` that plots the last `history_length` samples
+    ///
+    /// # Panics
+    ///
+    /// Panics if `history_length` is smaller than 2.
+    pub fn new(history_length: usize) -> Self {
+        assert!(history_length >= 2, "PerformanceHud must plot at least two samples");
+        Self {
+            samples: VecDeque::with_capacity(history_length),
+            history_length,
+        }
+    }
+
+    /// Pushes the [`PerformanceSample`] of the most recent frame and drops the oldest one when the history is full
+    pub fn push_sample(&mut self, sample: PerformanceSample) {
+        if self.samples.len() == self.history_length {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Returns the plotted [`PerformanceSample`]s from the oldest to the most recent one
+    pub fn samples(&self) -> impl Iterator<Item = &PerformanceSample> {
+        self.samples.iter()
+    }
+
+    /// Creates the [`OverlayCommandBuffer`] that displays the graphs
+    pub fn overlay_command_buffer(&self) -> crate::Result<OverlayCommandBuffer> {
+        let frame_times = self.map_samples(|sample| sample.frame_time.as_secs_f32());
+        let visible_meshlets = self.map_samples(|sample| sample.visible_meshlets as f32);
+        let upload_bandwidths = self.map_samples(|sample| sample.upload_bandwidth as f32);
+        let target_frame_time = Self::TARGET_FRAME_TIME.as_secs_f32();
+
+        let graphs = [
+            (frame_times, Vector4::new(0.3, 1.0, 0.3, 1.0), Some(target_frame_time)),
+            (visible_meshlets, Vector4::new(0.3, 0.8, 1.0, 1.0), None),
+            (upload_bandwidths, Vector4::new(1.0, 0.6, 0.2, 1.0), None),
+        ];
+
+        let mut rectangles = Vec::new();
+        let mut lines = Vec::new();
+        for (index, (values, color, reference)) in graphs.into_iter().enumerate() {
+            let min = Self::POSITION + Vector2::new(0.0, index as f32 * (Self::GRAPH_SIZE.y + Self::GRAPH_SPACING));
+            let max = min + Self::GRAPH_SIZE;
+            rectangles.push(Rectangle::new(min, max, Vector4::new(0.0, 0.0, 0.0, 0.6)));
+
+            // The reference stays in the lower half of the graph so that exceeding it is visible
+            let scale = values
+                .iter()
+                .copied()
+                .fold(reference.map_or(0.0, |reference| 2.0 * reference), f32::max);
+            if let Some(reference) = reference {
+                let y = graph_y(reference, scale, &min, &max);
+                let config = LineConfig {
+                    color: Vector4::new(1.0, 1.0, 0.0, 0.5),
+                    ..Default::default()
+                };
+                lines.push(Line::new(Vector2::new(min.x, y), Vector2::new(max.x, y), config));
+            }
+            lines.extend(graph_lines(&values, self.history_length, scale, &min, &max, color));
+        }
+
+        OverlayCommandBufferBuilder::new(debug_info!("PerformanceHud"))
+            .push_rectangles(&rectangles)?
+            .push_lines(&lines)?
+            .build()
+    }
+
+    fn map_samples(&self, f: impl Fn(&PerformanceSample) -> f32) -> Vec<f32> {
+        self.samples.iter().map(f).collect()
+    }
+}
+
+/// Returns the y coordinate of `value` in a graph from `min` to `max` in which `scale` is at the top
+fn graph_y(value: f32, scale: f32, min: &Vector2<f32>, max: &Vector2<f32>) -> f32 {
+    let normalized = if scale > 0.0 { (value / scale).clamp(0.0, 1.0) } else { 0.0 };
+    max.y - normalized * (max.y - min.y)
+}
+
+/// Returns the line strip that connects the `values` from left to right. The most recent value is at the right border.
+fn graph_lines(
+    values: &[f32],
+    history_length: usize,
+    scale: f32,
+    min: &Vector2<f32>,
+    max: &Vector2<f32>,
+    color: Vector4<f32>,
+) -> Vec<Line> {
+    let step = (max.x - min.x) / (history_length - 1) as f32;
+    let first_x = max.x - (values.len().saturating_sub(1)) as f32 * step;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| Vector2::new(first_x + index as f32 * step, graph_y(*value, scale, min, max)))
+        .collect::<Vec<_>>();
+    let config = LineConfig {
+        color,
+        ..Default::default()
+    };
+    points
+        .windows(2)
+        .map(|window| Line::new(window[0], window[1], config.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::overlay::OverlayCommand;
+
+    use super::*;
+
+    #[test]
+    fn history_length() {
+        let mut performance_hud = PerformanceHud::new(3);
+        for visible_meshlets in 0..5 {
+            performance_hud.push_sample(PerformanceSample {
+                visible_meshlets,
+                ..Default::default()
+            });
+        }
+        let visible_meshlets = performance_hud.samples().map(|sample| sample.visible_meshlets).collect::<Vec<_>>();
+        assert_eq!(visible_meshlets, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn graph() {
+        let min = Vector2::new(0.0, 0.0);
+        let max = Vector2::new(100.0, 10.0);
+        let lines = graph_lines(&[0.0, 5.0, 10.0], 5, 10.0, &min, &max, Vector4::repeat(1.0));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].start(), &Vector2::new(50.0, 10.0));
+        assert_eq!(lines[0].end(), &Vector2::new(75.0, 5.0));
+        assert_eq!(lines[1].end(), &Vector2::new(100.0, 0.0));
+    }
+
+    #[test]
+    fn overlay_command_buffer() {
+        let mut performance_hud = PerformanceHud::default();
+        assert_eq!(performance_hud.overlay_command_buffer().unwrap().commands().len(), 4);
+        performance_hud.push_sample(PerformanceSample::default());
+        performance_hud.push_sample(PerformanceSample::default());
+        let command_buffer = performance_hud.overlay_command_buffer().unwrap();
+        let rectangles = command_buffer
+            .commands()
+            .iter()
+            .filter(|command| matches!(command, OverlayCommand::Rectangle(_)))
+            .count();
+        assert_eq!(rectangles, 3);
+        assert_eq!(command_buffer.commands().len(), 3 + 1 + 3);
+    }
+}
@@ -0,0 +1,6 @@
+/// Identifies a render target into which the scene is rendered from a [`CameraInstance`](crate::instances::camera_instance::CameraInstance)
+///
+/// Render targets are created with [`Backend::create_render_target`](crate::Backend::create_render_target). Their image can be
+/// displayed with a [`TexturedQuad`](crate::overlay::TexturedQuad) in the overlay of the same window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RenderTargetId(pub u64);
@@ -5,9 +5,12 @@ pub mod point_cloud_attributes_group;
 pub mod resource_group;
 mod texture2d;
 
-use std::sync::{
-    mpsc::{Receiver, Sender},
-    Arc, Weak,
+use std::{
+    fmt,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc, Weak,
+    },
 };
 
 pub use texture2d::*;
@@ -43,6 +46,53 @@ pub enum ResourceEvent {
     PointCloudAttributes(Vec<PointCloudAttributesEvent>),
 }
 
+/// State of the upload of a [`Resource`] to the GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadState {
+    /// The upload has been requested but is not done yet
+    Pending,
+    /// The [`Resource`] has been uploaded to the GPU and can be rendered
+    Uploaded,
+}
+
+/// Callback that is called when the upload of a [`Resource`] to the GPU is done
+pub type UploadCompletionCallback = Box<dyn FnOnce() + Send>;
+
+/// Is passed to the backend with the insertion of a [`Resource`] so that the backend can report when the upload is done
+pub struct UploadNotifier {
+    upload_state: Arc<Mutex<UploadState>>,
+    completion_callback: Mutex<Option<UploadCompletionCallback>>,
+}
+
+impl UploadNotifier {
+    /// Creates a new [`UploadNotifier`] that reports to the given `upload_state`
+    pub(crate) fn new(upload_state: Arc<Mutex<UploadState>>, completion_callback: Option<UploadCompletionCallback>) -> Self {
+        Self {
+            upload_state,
+            completion_callback: Mutex::new(completion_callback),
+        }
+    }
+
+    /// Marks the upload as done and calls the completion callback if one was given
+    ///
+    /// The completion callback is only called the first time this method is called.
+    pub fn notify_uploaded(&self) {
+        *self.upload_state.lock() = UploadState::Uploaded;
+        if let Some(completion_callback) = self.completion_callback.lock().take() {
+            completion_callback();
+        }
+    }
+}
+
+impl fmt::Debug for UploadNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadNotifier")
+            .field("upload_state", &*self.upload_state.lock())
+            .field("has_completion_callback", &self.completion_callback.lock().is_some())
+            .finish()
+    }
+}
+
 /// A [`ResourceReceiver`] that can be used for testing
 pub struct MockBackend {
     pub sender: Sender<ResourceEvent>,
@@ -141,8 +191,6 @@ pub mod tests {
                 panic!("failed to receive event")
             };
             assert_eq!(mesh_attributes_events.len(), 1);
-            // At the time of writing, the MeshAttributesEvent has only the Insert variant
-            #[allow(irrefutable_let_patterns)]
             let $p = &mesh_attributes_events[0] else {
                 panic!("unexpected event")
             };
@@ -159,8 +207,6 @@ pub mod tests {
                 panic!("failed to receive event")
             };
             assert_eq!(point_cloud_attributes_events.len(), 1);
-            // At the time of writing, the PointCloudAttributesEvent has only the Insert variant
-            #[allow(irrefutable_let_patterns)]
             let $p = &point_cloud_attributes_events[0] else {
                 panic!("unexpected event")
             };
@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
 use jeriya_content::model::Meshlet;
-use jeriya_shared::{debug_info, log::info, nalgebra::Vector3, thiserror, AsDebugInfo, DebugInfo, Handle};
+use jeriya_shared::{aabb::AABB, debug_info, log::info, nalgebra::Vector3, thiserror, AsDebugInfo, DebugInfo, Handle};
 
-use crate::gpu_index_allocator::GpuIndexAllocation;
+use crate::{gpu_index_allocator::GpuIndexAllocation, resources::UploadCompletionCallback};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AttributeType {
@@ -44,6 +44,7 @@ pub struct MeshAttributes {
     vertex_normals: Vec<Vector3<f32>>,
     indices: Option<Vec<u32>>,
     meshlets: Option<Vec<Meshlet>>,
+    bounding_box: AABB,
     handle: Handle<Arc<MeshAttributes>>,
     gpu_index_allocation: GpuIndexAllocation<MeshAttributes>,
     debug_info: DebugInfo,
@@ -55,6 +56,24 @@ impl MeshAttributes {
         MeshAttributeBuilder::new()
     }
 
+    /// Creates [`MeshAttributes`] without any vertex data which take the place of removed [`MeshAttributes`] in the [`MeshAttributesGroup`](crate::resources::mesh_attributes_group::MeshAttributesGroup)
+    pub(crate) fn placeholder(
+        handle: Handle<Arc<MeshAttributes>>,
+        gpu_index_allocation: GpuIndexAllocation<MeshAttributes>,
+        debug_info: DebugInfo,
+    ) -> Self {
+        Self {
+            vertex_positions: Vec::new(),
+            vertex_normals: Vec::new(),
+            indices: None,
+            meshlets: None,
+            bounding_box: AABB::empty(),
+            handle,
+            gpu_index_allocation,
+            debug_info,
+        }
+    }
+
     /// Returns the vertex positions
     pub fn vertex_positions(&self) -> &Vec<Vector3<f32>> {
         &self.vertex_positions
@@ -65,6 +84,11 @@ impl MeshAttributes {
         &self.vertex_normals
     }
 
+    /// Returns the [`AABB`] of the vertex positions
+    pub fn bounding_box(&self) -> &AABB {
+        &self.bounding_box
+    }
+
     /// Returns the indices
     pub fn indices(&self) -> Option<&Vec<u32>> {
         self.indices.as_ref()
@@ -123,6 +147,7 @@ pub struct MeshAttributeBuilder {
     indices: Option<Vec<u32>>,
     meshlets: Option<Vec<Meshlet>>,
     debug_info: Option<DebugInfo>,
+    upload_completion_callback: Option<UploadCompletionCallback>,
 }
 
 impl MeshAttributeBuilder {
@@ -133,6 +158,7 @@ impl MeshAttributeBuilder {
             indices: None,
             meshlets: None,
             debug_info: None,
+            upload_completion_callback: None,
         }
     }
 
@@ -174,6 +200,19 @@ impl MeshAttributeBuilder {
         self
     }
 
+    /// Sets a callback that is called when the upload of the [`MeshAttributes`] to the GPU is done
+    ///
+    /// This is an optional field
+    pub fn with_upload_completion_callback(mut self, upload_completion_callback: impl FnOnce() + Send + 'static) -> Self {
+        self.upload_completion_callback = Some(Box::new(upload_completion_callback));
+        self
+    }
+
+    /// Takes the upload completion callback out of the builder so that it can be passed to the backend
+    pub(crate) fn take_upload_completion_callback(&mut self) -> Option<UploadCompletionCallback> {
+        self.upload_completion_callback.take()
+    }
+
     /// Builds the [`MeshAttributes`]
     pub(crate) fn build(
         self,
@@ -236,6 +275,7 @@ impl MeshAttributeBuilder {
         }
 
         Ok(MeshAttributes {
+            bounding_box: AABB::from_slice(&vertex_positions),
             vertex_positions,
             vertex_normals,
             indices: self.indices,
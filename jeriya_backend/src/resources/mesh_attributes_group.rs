@@ -1,17 +1,21 @@
-use std::sync::{mpsc::Sender, Arc, Weak};
+use std::{
+    collections::HashMap,
+    sync::{mpsc::Sender, Arc, Weak},
+};
 
-use jeriya_shared::{DebugInfo, Handle, IndexingContainer};
+use jeriya_shared::{parking_lot::Mutex, DebugInfo, Handle, IndexingContainer};
 
 use crate::{
     gpu_index_allocator::{AllocateGpuIndex, ProvideAllocateGpuIndex},
     resources::{
         mesh_attributes::{self, MeshAttributeBuilder, MeshAttributes},
-        ProvideResourceReceiver, ResourceEvent, ResourceReceiver,
+        ProvideResourceReceiver, ResourceEvent, ResourceReceiver, UploadNotifier, UploadState,
     },
 };
 
 pub struct MeshAttributesGroup {
     mesh_attributes: IndexingContainer<Arc<MeshAttributes>>,
+    upload_states: HashMap<Handle<Arc<MeshAttributes>>, Arc<Mutex<UploadState>>>,
     resource_event_sender: Sender<ResourceEvent>,
     gpu_index_allocator: Weak<dyn AllocateGpuIndex<MeshAttributes>>,
     debug_info: DebugInfo,
@@ -27,6 +31,7 @@ impl MeshAttributesGroup {
         let gpu_index_allocator = backend.provide_gpu_index_allocator();
         Self {
             mesh_attributes: IndexingContainer::new(),
+            upload_states: HashMap::new(),
             resource_event_sender,
             gpu_index_allocator,
             debug_info,
@@ -34,7 +39,11 @@ impl MeshAttributesGroup {
     }
 
     /// Inserts a [`MeshAttributes`] into the [`MeshAttributesGroup`]
-    pub fn insert_with(&mut self, mesh_attributes_builder: MeshAttributeBuilder) -> mesh_attributes::Result<Arc<MeshAttributes>> {
+    ///
+    /// The upload to the GPU happens asynchronously. Use [`MeshAttributesGroup::upload_state`] or
+    /// [`MeshAttributeBuilder::with_upload_completion_callback`] to get notified when it is done.
+    pub fn insert_with(&mut self, mut mesh_attributes_builder: MeshAttributeBuilder) -> mesh_attributes::Result<Arc<MeshAttributes>> {
+        let upload_completion_callback = mesh_attributes_builder.take_upload_completion_callback();
         let handle = self.mesh_attributes.insert_with(|handle| {
             let gpu_index_allocator = &self.gpu_index_allocator.upgrade().expect("gpu index allocator cannot be dropped");
             let gpu_index_allocation = gpu_index_allocator
@@ -47,15 +56,47 @@ impl MeshAttributesGroup {
             result
         })?;
         let value = self.mesh_attributes.get(&handle).expect("just inserted value not found").clone();
+        let upload_state = Arc::new(Mutex::new(UploadState::Pending));
+        self.upload_states.insert(handle, upload_state.clone());
         self.resource_event_sender
             .send(ResourceEvent::MeshAttributes(vec![MeshAttributesEvent::Insert {
                 handle,
                 mesh_attributes: value.clone(),
+                upload_notifier: UploadNotifier::new(upload_state, upload_completion_callback),
             }]))
             .expect("resource event cannot be sent");
         Ok(value)
     }
 
+    /// Removes the [`MeshAttributes`] with the given [`Handle`] from the [`MeshAttributesGroup`]
+    ///
+    /// The backend frees the GPU memory of the vertex data and the GPU index when it has processed the removal.
+    /// Returns `None` when the [`Handle`] doesn't reference a [`MeshAttributes`] in the [`MeshAttributesGroup`].
+    pub fn remove(&mut self, handle: &Handle<Arc<MeshAttributes>>) -> Option<Arc<MeshAttributes>> {
+        let mesh_attributes = self.mesh_attributes.get(handle)?.clone();
+        let placeholder = MeshAttributes::placeholder(
+            *handle,
+            *mesh_attributes.gpu_index_allocation(),
+            mesh_attributes.debug_info().clone(),
+        );
+        self.mesh_attributes.remove_with(handle, Arc::new(placeholder));
+        self.upload_states.remove(handle);
+        self.resource_event_sender
+            .send(ResourceEvent::MeshAttributes(vec![MeshAttributesEvent::Remove {
+                handle: *handle,
+                mesh_attributes: mesh_attributes.clone(),
+            }]))
+            .expect("resource event cannot be sent");
+        Some(mesh_attributes)
+    }
+
+    /// Returns the [`UploadState`] of the [`MeshAttributes`] with the given [`Handle`]
+    ///
+    /// Returns `None` when the [`Handle`] doesn't reference a [`MeshAttributes`] in the [`MeshAttributesGroup`].
+    pub fn upload_state(&self, handle: &Handle<Arc<MeshAttributes>>) -> Option<UploadState> {
+        self.upload_states.get(handle).map(|upload_state| *upload_state.lock())
+    }
+
     /// Returns the [`DebugInfo`] of the [`MeshAttributesGroup`].
     pub fn debug_info(&self) -> &DebugInfo {
         &self.debug_info
@@ -68,6 +109,11 @@ pub enum MeshAttributesEvent {
     Insert {
         handle: Handle<Arc<MeshAttributes>>,
         mesh_attributes: Arc<MeshAttributes>,
+        upload_notifier: UploadNotifier,
+    },
+    Remove {
+        handle: Handle<Arc<MeshAttributes>>,
+        mesh_attributes: Arc<MeshAttributes>,
     },
 }
 
@@ -91,10 +137,60 @@ mod tests {
         mesh_attributes_group.insert_with(mesh_attributes_builder).unwrap();
         match_one_mesh_attributes_event!(
             renderer,
-            MeshAttributesEvent::Insert { handle, mesh_attributes },
+            MeshAttributesEvent::Insert { handle, mesh_attributes, .. },
             assert_eq!(handle.index(), 0);
             assert_eq!(mesh_attributes.debug_info().name(), "my_attributes");
         );
         assert_events_empty(&renderer);
     }
+
+    #[test]
+    fn remove() {
+        let renderer = MockRenderer::new();
+        let mut mesh_attributes_group = MeshAttributesGroup::new(&renderer, debug_info!("my_mesh_attributes_group"));
+        let mesh_attributes_builder = MeshAttributes::builder()
+            .with_vertex_positions(vec![Vector3::new(0.0, 0.0, 0.0)])
+            .with_vertex_normals(vec![Vector3::new(0.0, 1.0, 0.0)])
+            .with_debug_info(debug_info!("my_attributes"));
+        let mesh_attributes = mesh_attributes_group.insert_with(mesh_attributes_builder).unwrap();
+        match_one_mesh_attributes_event!(renderer, MeshAttributesEvent::Insert { .. },);
+        let removed = mesh_attributes_group.remove(mesh_attributes.handle()).unwrap();
+        assert_eq!(removed.debug_info().name(), "my_attributes");
+        match_one_mesh_attributes_event!(
+            renderer,
+            MeshAttributesEvent::Remove { handle, .. },
+            assert_eq!(handle, mesh_attributes.handle());
+        );
+        assert!(mesh_attributes_group.remove(mesh_attributes.handle()).is_none());
+        assert_events_empty(&renderer);
+    }
+
+    #[test]
+    fn upload_state() {
+        let renderer = MockRenderer::new();
+        let mut mesh_attributes_group = MeshAttributesGroup::new(&renderer, debug_info!("my_mesh_attributes_group"));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mesh_attributes_builder = MeshAttributes::builder()
+            .with_vertex_positions(vec![Vector3::new(0.0, 0.0, 0.0)])
+            .with_vertex_normals(vec![Vector3::new(0.0, 1.0, 0.0)])
+            .with_upload_completion_callback(move || sender.send(()).unwrap())
+            .with_debug_info(debug_info!("my_attributes"));
+        let mesh_attributes = mesh_attributes_group.insert_with(mesh_attributes_builder).unwrap();
+        assert_eq!(
+            mesh_attributes_group.upload_state(mesh_attributes.handle()),
+            Some(UploadState::Pending)
+        );
+        match_one_mesh_attributes_event!(
+            renderer,
+            MeshAttributesEvent::Insert { upload_notifier, .. },
+            upload_notifier.notify_uploaded();
+        );
+        assert_eq!(
+            mesh_attributes_group.upload_state(mesh_attributes.handle()),
+            Some(UploadState::Uploaded)
+        );
+        receiver.try_recv().unwrap();
+        mesh_attributes_group.remove(mesh_attributes.handle()).unwrap();
+        assert_eq!(mesh_attributes_group.upload_state(mesh_attributes.handle()), None);
+    }
 }
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use jeriya_content::point_cloud::clustered_point_cloud::{ClusterIndex, Page};
+use jeriya_content::{
+    common::AssetKey,
+    point_cloud::clustered_point_cloud::{ClusterIndex, Page, PageStreamingInfo},
+};
 use jeriya_shared::{debug_info, nalgebra::Vector3, thiserror, ByteColor3, DebugInfo, Handle};
 
 use crate::gpu_index_allocator::GpuIndexAllocation;
@@ -21,12 +24,22 @@ pub enum Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Processed point cloud asset from which the backend requests the `Page`s of streamed [`PointCloudAttributes`] at runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageStreamingSource {
+    /// Asset from which the `Page`s are requested
+    pub asset_key: AssetKey,
+    /// Number of `Page`s in the asset
+    pub pages_len: usize,
+}
+
 #[derive(Debug)]
 pub struct PointCloudAttributes {
     point_positions: Vec<Vector3<f32>>,
     point_colors: Vec<ByteColor3>,
     root_cluster_index: ClusterIndex,
     pages: Vec<Page>,
+    page_streaming_source: Option<PageStreamingSource>,
     handle: Handle<Arc<PointCloudAttributes>>,
     gpu_index_allocation: GpuIndexAllocation<PointCloudAttributes>,
     debug_info: DebugInfo,
@@ -38,6 +51,24 @@ impl PointCloudAttributes {
         PointCloudAttributesBuilder::default()
     }
 
+    /// Creates [`PointCloudAttributes`] without any point data which take the place of removed [`PointCloudAttributes`] in the [`PointCloudAttributesGroup`](crate::resources::point_cloud_attributes_group::PointCloudAttributesGroup)
+    pub(crate) fn placeholder(
+        handle: Handle<Arc<PointCloudAttributes>>,
+        gpu_index_allocation: GpuIndexAllocation<PointCloudAttributes>,
+        debug_info: DebugInfo,
+    ) -> Self {
+        Self {
+            point_positions: Vec::new(),
+            point_colors: Vec::new(),
+            root_cluster_index: ClusterIndex::default(),
+            pages: Vec::new(),
+            page_streaming_source: None,
+            handle,
+            gpu_index_allocation,
+            debug_info,
+        }
+    }
+
     /// Returns the point positions of the `PointCloudAttributes`
     pub fn point_positions(&self) -> &[Vector3<f32>] {
         &self.point_positions
@@ -53,6 +84,11 @@ impl PointCloudAttributes {
         &self.pages
     }
 
+    /// Returns the [`PageStreamingSource`] when the pages of the `PointCloudAttributes` are streamed instead of being uploaded at once
+    pub fn page_streaming_source(&self) -> Option<&PageStreamingSource> {
+        self.page_streaming_source.as_ref()
+    }
+
     /// Returns the root cluster index of the `PointCloudAttributes`
     pub fn root_cluster_index(&self) -> ClusterIndex {
         self.root_cluster_index.clone()
@@ -88,6 +124,7 @@ pub struct PointCloudAttributesBuilder {
     point_positions: Option<Vec<Vector3<f32>>>,
     point_colors: Option<Vec<ByteColor3>>,
     pages: Option<Vec<Page>>,
+    page_streaming_source: Option<PageStreamingSource>,
     root_cluster_index: Option<ClusterIndex>,
     debug_info: Option<DebugInfo>,
 }
@@ -111,6 +148,18 @@ impl PointCloudAttributesBuilder {
         self
     }
 
+    /// Streams the pages of the [`PointCloudAttributes`] from the processed point cloud asset instead of uploading them
+    /// at once. The backend requests the pages depending on the distance and the level of detail at runtime. The
+    /// `page_streaming_info` can be obtained with [`AssetImporter::import_page_streaming_info`](jeriya_content::asset_importer::AssetImporter::import_page_streaming_info).
+    pub fn with_page_streaming(mut self, asset_key: AssetKey, page_streaming_info: PageStreamingInfo) -> Self {
+        self.root_cluster_index = Some(page_streaming_info.root_cluster_index);
+        self.page_streaming_source = Some(PageStreamingSource {
+            asset_key,
+            pages_len: page_streaming_info.pages_len,
+        });
+        self
+    }
+
     /// Sets the root cluster index of the [`PointCloudAttributes`]
     pub fn with_root_cluster_index(mut self, root_cluster_index: ClusterIndex) -> Self {
         self.root_cluster_index = Some(root_cluster_index);
@@ -134,6 +183,7 @@ impl PointCloudAttributesBuilder {
             point_colors: self.point_colors.unwrap_or_default(),
             root_cluster_index: self.root_cluster_index.unwrap_or_default(),
             pages: self.pages.unwrap_or_default(),
+            page_streaming_source: self.page_streaming_source,
             handle,
             gpu_index_allocation,
             debug_info: self.debug_info.unwrap_or_else(|| debug_info!("Anonymous-PointCloudAttributes")),
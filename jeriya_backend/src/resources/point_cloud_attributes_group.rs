@@ -16,6 +16,10 @@ pub enum PointCloudAttributesEvent {
         handle: Handle<Arc<PointCloudAttributes>>,
         point_cloud_attributes: Arc<PointCloudAttributes>,
     },
+    Remove {
+        handle: Handle<Arc<PointCloudAttributes>>,
+        point_cloud_attributes: Arc<PointCloudAttributes>,
+    },
 }
 
 pub struct PointCloudAttributesGroup {
@@ -71,6 +75,27 @@ impl PointCloudAttributesGroup {
         Ok(value)
     }
 
+    /// Removes the [`PointCloudAttributes`] with the given [`Handle`] from the [`PointCloudAttributesGroup`]
+    ///
+    /// The backend frees the GPU memory of the point data and the GPU index when it has processed the removal.
+    /// Returns `None` when the [`Handle`] doesn't reference a [`PointCloudAttributes`] in the [`PointCloudAttributesGroup`].
+    pub fn remove(&mut self, handle: &Handle<Arc<PointCloudAttributes>>) -> Option<Arc<PointCloudAttributes>> {
+        let point_cloud_attributes = self.point_cloud_attributes.get(handle)?.clone();
+        let placeholder = PointCloudAttributes::placeholder(
+            *handle,
+            *point_cloud_attributes.gpu_index_allocation(),
+            point_cloud_attributes.debug_info().clone(),
+        );
+        self.point_cloud_attributes.remove_with(handle, Arc::new(placeholder));
+        self.resource_event_sender
+            .send(ResourceEvent::PointCloudAttributes(vec![PointCloudAttributesEvent::Remove {
+                handle: *handle,
+                point_cloud_attributes: point_cloud_attributes.clone(),
+            }]))
+            .expect("resource event cannot be sent");
+        Some(point_cloud_attributes)
+    }
+
     /// Returns the [`DebugInfo`] of the [`PointCloudAttributesGroup`].
     pub fn debug_info(&self) -> &DebugInfo {
         &self.debug_info
@@ -102,4 +127,25 @@ mod tests {
         );
         assert_events_empty(&renderer);
     }
+
+    #[test]
+    fn remove() {
+        let renderer = MockRenderer::new();
+        let mut point_cloud_attributes_group = PointCloudAttributesGroup::new(&renderer, debug_info!("my_point_cloud_attributes_group"));
+        let point_cloud_attributes_builder = PointCloudAttributes::builder()
+            .with_point_positions(vec![Vector3::new(0.0, 0.0, 0.0)])
+            .with_point_colors(vec![ByteColor3::new(0, 0, 0)])
+            .with_debug_info(debug_info!("my_attributes"));
+        let point_cloud_attributes = point_cloud_attributes_group.insert_with(point_cloud_attributes_builder).unwrap();
+        match_one_point_cloud_attributes_event!(renderer, PointCloudAttributesEvent::Insert { .. },);
+        let removed = point_cloud_attributes_group.remove(point_cloud_attributes.handle()).unwrap();
+        assert_eq!(removed.debug_info().name(), "my_attributes");
+        match_one_point_cloud_attributes_event!(
+            renderer,
+            PointCloudAttributesEvent::Remove { handle, .. },
+            assert_eq!(handle, point_cloud_attributes.handle());
+        );
+        assert!(point_cloud_attributes_group.remove(point_cloud_attributes.handle()).is_none());
+        assert_events_empty(&renderer);
+    }
 }
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use jeriya_shared::winit::window::WindowId;
+
+/// Statistics of the renderer that are gathered from all windows
+///
+/// The statistics are a snapshot of the state at the time they were queried and are cheap enough to be queried every
+/// frame for a stats overlay or for logging.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RendererStatistics {
+    /// Statistics of the windows and headless render targets in no particular order
+    pub windows: Vec<WindowStatistics>,
+    pub memory: MemoryUsage,
+    pub resources: ResourceCounts,
+}
+
+impl RendererStatistics {
+    /// Returns the [`WindowStatistics`] of the given window
+    pub fn window(&self, window_id: WindowId) -> Option<&WindowStatistics> {
+        self.windows.iter().find(|window| window.window_id == window_id)
+    }
+}
+
+/// Statistics of a single window
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowStatistics {
+    pub window_id: WindowId,
+    /// Time that the CPU spent on the most recent frame without waiting for the frame rate limit and the GPU. `None` when no frame has been rendered yet.
+    pub cpu_frame_time: Option<Duration>,
+    /// Number of transactions that have been received but are not yet applied to all frames in flight
+    pub pending_transactions: usize,
+}
+
+/// Usage of the GPU memory that has been allocated by the backend
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Number of memory blocks that have been allocated from the device
+    pub block_count: usize,
+    /// Number of allocations in all blocks
+    pub allocation_count: usize,
+    /// Sum of the sizes of all blocks in bytes
+    pub allocated_bytes: u64,
+    /// Sum of the sizes of all allocations in bytes
+    pub used_bytes: u64,
+}
+
+impl MemoryUsage {
+    /// Returns the fraction of the allocated bytes that is in use in the range `[0, 1]`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_backend::statistics::MemoryUsage;
+    /// let memory_usage = MemoryUsage {
+    ///     allocated_bytes: 1024,
+    ///     used_bytes: 256,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(memory_usage.usage(), 0.25);
+    /// assert_eq!(MemoryUsage::default().usage(), 0.0);
+    /// ```
+    pub fn usage(&self) -> f32 {
+        if self.allocated_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes as f32 / self.allocated_bytes as f32
+    }
+}
+
+/// Number of elements, instances and resources that are currently alive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceCounts {
+    pub mesh_attributes: usize,
+    pub point_cloud_attributes: usize,
+    pub cameras: usize,
+    pub camera_instances: usize,
+    pub rigid_meshes: usize,
+    pub rigid_mesh_instances: usize,
+    pub point_clouds: usize,
+    pub point_cloud_instances: usize,
+}
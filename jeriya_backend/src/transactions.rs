@@ -125,6 +125,16 @@ impl Transaction {
         Self::default()
     }
 
+    /// Creates a new [`Transaction`] with space for at least `capacity` [`Event`]s
+    ///
+    /// The same rules as for [`Transaction::new`] apply.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            is_considered_processed: false,
+            events: Vec::with_capacity(capacity),
+        }
+    }
+
     /// Starts the recording of a [`Transaction`].
     ///
     /// The [`Transaction`] is sent to the [`TransactionProcessor`] when the [`TransactionRecorder`]
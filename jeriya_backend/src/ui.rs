@@ -0,0 +1,92 @@
+use jeriya_shared::{nalgebra::Vector2, SamplerConfig};
+
+/// Identifies a texture of the user interface
+///
+/// The ids are chosen by the user interface library. A texture keeps its id until it is freed with [`UiFrame::textures_free`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UiTextureId(pub u64);
+
+/// Creates a texture or updates a region of it
+#[derive(Debug, Clone)]
+pub struct UiTextureDelta {
+    pub id: UiTextureId,
+    /// Upper left corner of the region that is updated or `None` when the whole texture is (re-)created
+    pub position: Option<[u32; 2]>,
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed rows of RGBA texels with premultiplied alpha starting with the top row
+    pub rgba: Vec<u8>,
+    pub sampler_config: SamplerConfig,
+}
+
+impl UiTextureDelta {
+    /// Returns whether the `UiTextureDelta` updates only a region of an existing texture
+    pub fn is_partial(&self) -> bool {
+        self.position.is_some()
+    }
+}
+
+/// Vertex of a [`UiMesh`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiVertex {
+    /// Position in pixels from the upper left corner of the window
+    pub position: Vector2<f32>,
+    pub texture_coordinates: Vector2<f32>,
+    /// RGBA color with premultiplied alpha
+    pub color: [u8; 4],
+}
+
+/// Indexed triangle list of the user interface that is clipped to a rectangle
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiMesh {
+    /// Upper left corner of the clip rectangle in pixels
+    pub clip_rect_min: Vector2<f32>,
+    /// Lower right corner of the clip rectangle in pixels
+    pub clip_rect_max: Vector2<f32>,
+    pub texture: UiTextureId,
+    pub vertices: Vec<UiVertex>,
+    /// Three indices into `vertices` per triangle
+    pub indices: Vec<u32>,
+}
+
+impl UiMesh {
+    /// Returns the vertices of the triangle list in the order of the `indices`
+    pub fn triangle_vertices(&self) -> impl Iterator<Item = &UiVertex> {
+        self.indices.iter().map(|index| &self.vertices[*index as usize])
+    }
+}
+
+/// Output of one frame of a user interface library like egui that is rendered on top of the overlay
+///
+/// The textures in `textures_set` are applied before the `meshes` are rendered and the textures in
+/// `textures_free` are removed afterwards. A `UiFrame` replaces the meshes of the previous one so that
+/// the user interface is rendered every frame, even when it is updated less often.
+#[derive(Debug, Clone, Default)]
+pub struct UiFrame {
+    pub textures_set: Vec<UiTextureDelta>,
+    pub meshes: Vec<UiMesh>,
+    pub textures_free: Vec<UiTextureId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangle_vertices() {
+        let vertex = |x: f32| UiVertex {
+            position: Vector2::new(x, 0.0),
+            texture_coordinates: Vector2::zeros(),
+            color: [255; 4],
+        };
+        let mesh = UiMesh {
+            clip_rect_min: Vector2::zeros(),
+            clip_rect_max: Vector2::new(100.0, 100.0),
+            texture: UiTextureId(0),
+            vertices: vec![vertex(0.0), vertex(1.0), vertex(2.0), vertex(3.0)],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+        let positions = mesh.triangle_vertices().map(|vertex| vertex.position.x).collect::<Vec<_>>();
+        assert_eq!(positions, vec![0.0, 1.0, 2.0, 0.0, 2.0, 3.0]);
+    }
+}
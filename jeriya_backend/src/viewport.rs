@@ -0,0 +1,133 @@
+use jeriya_shared::ByteColor4;
+
+use crate::{gpu_index_allocator::GpuIndexAllocation, instances::camera_instance::CameraInstance};
+
+/// Rectangle relative to the size of the window where (0, 0) is the upper left and (1, 1) the lower right corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    /// `ViewportRect` that covers the whole window
+    pub const FULL: Self = Self::new(0.0, 0.0, 1.0, 1.0);
+
+    /// Creates a new `ViewportRect` from its upper left corner and its size relative to the size of the window
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Returns the offset and the size of the `ViewportRect` in pixels for a window of `window_width` x `window_height` pixels
+    ///
+    /// The edges are rounded to the nearest pixel so that adjacent `ViewportRect`s don't overlap or leave gaps between them.
+    /// The result is clamped to the window and might be empty.
+    pub fn to_pixels(&self, window_width: u32, window_height: u32) -> ([u32; 2], [u32; 2]) {
+        let edge = |relative: f32, size: u32| (relative * size as f32).round().clamp(0.0, size as f32) as u32;
+        let min = [edge(self.x, window_width), edge(self.y, window_height)];
+        let max = [
+            edge(self.x + self.width, window_width).max(min[0]),
+            edge(self.y + self.height, window_height).max(min[1]),
+        ];
+        (min, [max[0] - min[0], max[1] - min[1]])
+    }
+}
+
+/// Determines what is cleared in the area of a [`Viewport`] before the scene is rendered into it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewportClear {
+    /// The color is cleared with the given color and the depth is cleared
+    ColorAndDepth(ByteColor4),
+    /// Only the depth is cleared so that the scene is rendered on top of the viewports that have been rendered before
+    Depth,
+    /// Nothing is cleared so that the scene is depth tested against the viewports that have been rendered before
+    None,
+}
+
+/// Area of a window into which the scene is rendered from its own [`CameraInstance`]
+///
+/// The viewports of a window are set with [`Backend::set_viewports`](crate::Backend::set_viewports) and are rendered in
+/// the order in which they are given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Viewport {
+    camera_instance: GpuIndexAllocation<CameraInstance>,
+    rect: ViewportRect,
+    scissor: Option<ViewportRect>,
+    clear: ViewportClear,
+}
+
+impl Viewport {
+    /// Creates a new `Viewport` that renders the scene from the `camera_instance` into the `rect` of the window
+    ///
+    /// The `Viewport` is scissored to the `rect` and clears its color and depth with black.
+    pub fn new(camera_instance: &CameraInstance, rect: ViewportRect) -> Self {
+        Self {
+            camera_instance: *camera_instance.gpu_index_allocation(),
+            rect,
+            scissor: None,
+            clear: ViewportClear::ColorAndDepth(ByteColor4::new(0, 0, 0, 255)),
+        }
+    }
+
+    /// Sets the rectangle relative to the size of the window outside of which nothing is rendered
+    pub fn with_scissor(mut self, scissor: ViewportRect) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+
+    /// Sets what is cleared before the scene is rendered into the `Viewport`
+    pub fn with_clear(mut self, clear: ViewportClear) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    /// Returns the [`CameraInstance`] from which the scene is rendered
+    pub fn camera_instance(&self) -> &GpuIndexAllocation<CameraInstance> {
+        &self.camera_instance
+    }
+
+    /// Returns the area of the window into which the scene is rendered
+    pub fn rect(&self) -> &ViewportRect {
+        &self.rect
+    }
+
+    /// Returns the scissor rectangle which defaults to the [`Viewport::rect`]
+    pub fn scissor(&self) -> &ViewportRect {
+        self.scissor.as_ref().unwrap_or(&self.rect)
+    }
+
+    /// Returns what is cleared before the scene is rendered into the `Viewport`
+    pub fn clear(&self) -> &ViewportClear {
+        &self.clear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full() {
+        assert_eq!(ViewportRect::FULL.to_pixels(640, 480), ([0, 0], [640, 480]));
+    }
+
+    #[test]
+    fn adjacent_rects_have_no_gaps() {
+        let left = ViewportRect::new(0.0, 0.0, 1.0 / 3.0, 1.0);
+        let right = ViewportRect::new(1.0 / 3.0, 0.0, 2.0 / 3.0, 1.0);
+        let (left_offset, left_size) = left.to_pixels(100, 50);
+        let (right_offset, right_size) = right.to_pixels(100, 50);
+        assert_eq!(left_offset[0] + left_size[0], right_offset[0]);
+        assert_eq!(right_offset[0] + right_size[0], 100);
+    }
+
+    #[test]
+    fn clamped_to_window() {
+        let rect = ViewportRect::new(0.5, -0.5, 1.0, 1.0);
+        assert_eq!(rect.to_pixels(100, 100), ([50, 0], [50, 50]));
+        let outside = ViewportRect::new(2.0, 0.0, 1.0, 1.0);
+        assert_eq!(outside.to_pixels(100, 100), ([100, 0], [0, 100]));
+    }
+}
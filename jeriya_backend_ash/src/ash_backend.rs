@@ -1,34 +1,55 @@
 use std::{
     collections::HashMap,
     iter,
+    ops::Range,
     sync::{
+        atomic::{AtomicU64, Ordering},
         mpsc::{self, Receiver, Sender, TryRecvError},
         Arc,
     },
     thread,
 };
 
+use ash::vk;
+
 use crate::{
     backend_shared::BackendShared,
     command_buffer::CommandBuffer,
     command_buffer_builder::CommandBufferBuilder,
     command_pool::{CommandPool, CommandPoolCreateFlags},
+    custom_pass::{CustomPass, CustomPassId, CustomPassPosition},
     debug::{set_panic_on_message, ValidationLayerCallback},
     device::Device,
     entry::Entry,
+    frame_capture::{CapturedFrame, FrameCaptureConfig, FrameCaptureHandle, FrameCaptureTarget},
+    frame_telemetry::FrameTelemetry,
+    frame_timings::FrameTimings,
     instance::Instance,
-    physical_device::PhysicalDevice,
+    memory_allocator::MemoryStatistics,
+    optional_features::OptionalFeatures,
+    physical_device::{PhysicalDevice, PhysicalDeviceInfo},
+    post_processing_pass::PostProcessingPass,
     presenter::{Presenter, PresenterEvent},
+    presenter_shared::PresentationTarget,
     queue_plan::QueuePlan,
+    readback::{DepthImage, DepthReadbackRequest, DepthRegion, Readback, ReadbackBuffer, ReadbackRequest},
+    retired_resources::RetiredResource,
     shader_interface,
+    staged_push_only_buffer::{Relocation, StagedPushOnlyBuffer},
     surface::Surface,
-    Config, ValidationLayerConfig,
+    user_compute_pass::UserComputePass,
+    user_interface::UserInterface,
+    Config, PhysicalDeviceFeature, ValidationLayerConfig,
 };
 use jeriya_backend::{
+    clip_plane::{ClipPlane, MAX_CLIP_PLANES},
+    debug::DebugShadingMode,
     elements::{self, point_cloud::PointCloud, rigid_mesh::RigidMesh},
     gpu_index_allocator::{AllocateGpuIndex, GpuIndexAllocation},
     immediate::{self, ImmediateRenderingFrame},
     instances::{camera_instance::CameraInstance, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance},
+    overlay::OverlayCommandBuffer,
+    render_target::RenderTargetId,
     resources::{
         mesh_attributes::{MeshAttributes, MeshAttributesGpuState},
         mesh_attributes_group::MeshAttributesEvent,
@@ -36,10 +57,13 @@ use jeriya_backend::{
         point_cloud_attributes_group::PointCloudAttributesEvent,
         ResourceEvent, ResourceReceiver,
     },
+    statistics::{MemoryUsage, RendererStatistics, ResourceCounts},
     transactions::{self, PushEvent, Transaction, TransactionProcessor},
-    Backend,
+    ui::UiFrame,
+    viewport::Viewport,
+    Backend, ResizeCallback,
 };
-use jeriya_content::{asset_importer::AssetImporter, model::Meshlet, point_cloud::clustered_point_cloud::Page, shader::ShaderAsset};
+use jeriya_content::{asset_importer::AssetImporter, model::Meshlet, shader::ShaderAsset};
 use jeriya_macros::profile;
 use jeriya_shared::{
     debug_info,
@@ -47,16 +71,19 @@ use jeriya_shared::{
     nalgebra::Vector4,
     tracy_client::Client,
     winit::window::WindowId,
-    AsDebugInfo, RendererConfig, WindowConfig,
+    AsDebugInfo, ByteColor4, ColorSpace, HeadlessConfig, PresentMode, RendererConfig, WindowConfig, WindowMode,
 };
 
 pub struct AshBackend {
     presenters: HashMap<WindowId, Presenter>,
-    _surfaces: HashMap<WindowId, Arc<Surface>>,
+    surfaces: HashMap<WindowId, Arc<Surface>>,
     _validation_layer_callback: Option<ValidationLayerCallback>,
     _instance: Arc<Instance>,
     _entry: Arc<Entry>,
     backend_shared: Arc<BackendShared>,
+    /// Id of the next render target that is created
+    next_render_target_id: AtomicU64,
+    next_custom_pass_id: AtomicU64,
 }
 
 impl ResourceReceiver for AshBackend {
@@ -144,6 +171,13 @@ impl AllocateGpuIndex<RigidMeshInstance> for AshBackend {
             .lock()
             .free_gpu_index(gpu_index_allocation);
     }
+
+    fn allocate_gpu_indices(&self, count: usize) -> Option<Vec<GpuIndexAllocation<RigidMeshInstance>>> {
+        self.backend_shared
+            .rigid_mesh_instance_gpu_index_allocator
+            .lock()
+            .allocate_gpu_indices(count)
+    }
 }
 
 impl AllocateGpuIndex<PointCloudInstance> for AshBackend {
@@ -160,6 +194,13 @@ impl AllocateGpuIndex<PointCloudInstance> for AshBackend {
             .lock()
             .free_gpu_index(gpu_index_allocation);
     }
+
+    fn allocate_gpu_indices(&self, count: usize) -> Option<Vec<GpuIndexAllocation<PointCloudInstance>>> {
+        self.backend_shared
+            .point_cloud_instance_gpu_index_allocator
+            .lock()
+            .allocate_gpu_indices(count)
+    }
 }
 
 impl AllocateGpuIndex<MeshAttributes> for AshBackend {
@@ -191,6 +232,239 @@ impl AllocateGpuIndex<PointCloudAttributes> for AshBackend {
     }
 }
 
+impl AshBackend {
+    /// Returns the physical devices from which the device of the backend can be selected with the
+    /// [`PhysicalDeviceSelection`](crate::PhysicalDeviceSelection) of the [`Config`].
+    pub fn available_physical_devices() -> jeriya_backend::Result<Vec<PhysicalDeviceInfo>> {
+        let entry = Entry::new()?;
+        let instance = Instance::new(&entry, "jeriya_physical_device_enumeration", false)?;
+        Ok(PhysicalDevice::enumerate(&instance)?)
+    }
+
+    /// Reads the given `range` of the [`ReadbackBuffer`] back to the host after the next frame of the given window has been rendered.
+    ///
+    /// The `range` is measured in `u32`s. The returned [`Readback`] receives the data asynchronously when the frame
+    /// has finished rendering on the GPU. When the `range` is out of bounds, the [`Readback`] is aborted.
+    ///
+    /// # Example
+    ///
+    /// Reading the number of visible meshlets:
+    ///
+    /// ```ignore
+    /// let buffer = ReadbackBuffer::VisibleRigidMeshMeshlets;
+    /// let readback = renderer.backend().read_buffer(window.id(), buffer, buffer.count_range())?;
+    /// let visible_meshlets = readback.recv()?[0];
+    /// ```
+    pub fn read_buffer(&self, window_id: WindowId, buffer: ReadbackBuffer, range: Range<usize>) -> jeriya_backend::Result<Readback> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let (readback_request, readback) = ReadbackRequest::new(buffer, range);
+        presenter.send(PresenterEvent::Readback(readback_request));
+        Ok(readback)
+    }
+
+    /// Reads the `region` of the depth buffer back to the host after the next frame of the given window has been rendered.
+    ///
+    /// The returned [`Readback`] receives the [`DepthImage`] asynchronously when the frame has finished rendering on
+    /// the GPU. The depth buffer contains the scene of all viewports but not the overlay. When the `region` is empty or
+    /// exceeds the window, the [`Readback`] is aborted.
+    ///
+    /// # Example
+    ///
+    /// Probing the depth under the cursor:
+    ///
+    /// ```ignore
+    /// let readback = renderer.backend().read_depth_buffer(window.id(), DepthRegion::pixel(cursor_x, cursor_y))?;
+    /// if let Some(depth_image) = readback.try_recv()? {
+    ///     let depth = depth_image.get(0, 0);
+    /// }
+    /// ```
+    pub fn read_depth_buffer(&self, window_id: WindowId, region: DepthRegion) -> jeriya_backend::Result<Readback<DepthImage>> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let (depth_readback_request, readback) = DepthReadbackRequest::new(region);
+        presenter.send(PresenterEvent::ReadDepthBuffer(depth_readback_request));
+        Ok(readback)
+    }
+
+    /// Returns the GPU times of the passes of the most recently completed frame of the given window.
+    ///
+    /// Returns `None` when no frame has been completed yet.
+    pub fn frame_timings(&self, window_id: WindowId) -> jeriya_backend::Result<Option<FrameTimings>> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        Ok(presenter.frame_timings())
+    }
+
+    /// Returns the number of visible instances, meshlets and clusters of the most recently completed frame of the given window.
+    ///
+    /// Returns `None` when no frame has been completed yet. The [`FrameTelemetry`] lags one or two frames behind the
+    /// recorded frames which is sufficient for adjusting LOD budgets.
+    pub fn frame_telemetry(&self, window_id: WindowId) -> jeriya_backend::Result<Option<FrameTelemetry>> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        Ok(presenter.frame_telemetry())
+    }
+
+    /// Starts capturing the presented frames of the given window.
+    ///
+    /// Every `interval`th frame of the [`FrameCaptureConfig`] is copied from the swapchain and written to its target on a
+    /// separate thread. With a `time_step`, the motions on the GPU advance by exactly that step per presented frame so that
+    /// a flythrough results in the same frames every time it is recorded. A capture that is already running for the window
+    /// is replaced.
+    ///
+    /// ```ignore
+    /// let frame_capture_handle = backend.start_frame_capture(window.id(), FrameCaptureConfig::png_sequence("capture", 60))?;
+    /// // ... run the flythrough
+    /// backend.stop_frame_capture(window.id())?;
+    /// let frame_count = frame_capture_handle.wait()?;
+    /// ```
+    pub fn start_frame_capture(&self, window_id: WindowId, config: FrameCaptureConfig) -> jeriya_backend::Result<FrameCaptureHandle> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        Ok(presenter.start_frame_capture(config, &self.backend_shared)?)
+    }
+
+    /// Stops capturing the presented frames of the given window
+    ///
+    /// The [`FrameCaptureHandle`] that was returned by [`AshBackend::start_frame_capture`] can be used to wait until
+    /// the frames that have already been captured are written.
+    pub fn stop_frame_capture(&self, window_id: WindowId) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.stop_frame_capture();
+        Ok(())
+    }
+
+    /// Renders the next frame of the given window or headless render target and reads it back to the host
+    ///
+    /// Blocks until the frame has finished rendering. This replaces a frame capture that is running for the window.
+    ///
+    /// ```ignore
+    /// let headless_config = HeadlessConfig::new(256, 256, FrameRate::Unlimited);
+    /// let backend = AshBackend::new(renderer_config, backend_config, asset_importer, &[], &[headless_config.clone()])?;
+    /// let captured_frame = backend.read_frame(headless_config.window_id)?;
+    /// ```
+    pub fn read_frame(&self, window_id: WindowId) -> jeriya_backend::Result<CapturedFrame> {
+        let (sender, receiver) = mpsc::channel();
+        let config = FrameCaptureConfig {
+            target: FrameCaptureTarget::Callback(Box::new(move |captured_frame| {
+                // Only the first frame is received and the remaining ones are discarded
+                let _ = sender.send(captured_frame);
+            })),
+            interval: 1,
+            time_step: None,
+        };
+        let frame_capture_handle = self.start_frame_capture(window_id, config)?;
+        let captured_frame = receiver.recv();
+        self.stop_frame_capture(window_id)?;
+        frame_capture_handle.wait()?;
+        captured_frame.map_err(|_| crate::Error::FrameCaptureFailed("no frame was captured".to_owned()).into())
+    }
+
+    /// Renders the next frame of the given window and reads the image of the render target back to the host
+    ///
+    /// Blocks until the frame has finished rendering. Returns [`UnknownRenderTarget`](jeriya_backend::Error::UnknownRenderTarget)
+    /// when the render target doesn't exist in that frame.
+    pub fn read_render_target(&self, window_id: WindowId, render_target_id: RenderTargetId) -> jeriya_backend::Result<CapturedFrame> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let (sender, receiver) = mpsc::sync_channel(1);
+        presenter.send(PresenterEvent::ReadRenderTarget { render_target_id, sender });
+        receiver
+            .recv()
+            .map_err(|_| jeriya_backend::Error::UnknownRenderTarget(render_target_id))
+    }
+
+    /// Adds a [`CustomPass`] that is recorded at the given [`CustomPassPosition`] in every frame of the given window
+    ///
+    /// The barriers between the pass and the built-in passes are derived from the [`ResourceAccess`](crate::ResourceAccess)es
+    /// that the pass declares. Passes at the same position are recorded in the order in which they have been added.
+    pub fn add_custom_pass(
+        &self,
+        window_id: WindowId,
+        position: CustomPassPosition,
+        custom_pass: Arc<dyn CustomPass>,
+    ) -> jeriya_backend::Result<CustomPassId> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let custom_pass_id = CustomPassId(self.next_custom_pass_id.fetch_add(1, Ordering::Relaxed));
+        presenter.add_custom_pass(custom_pass_id, position, custom_pass);
+        Ok(custom_pass_id)
+    }
+
+    /// Adds a [`UserComputePass`] that is dispatched every frame of the given window after the [`CustomPass`]es at its position
+    ///
+    /// The compute pipeline is created from the shader asset when the frame graph is compiled. Frames are rendered
+    /// without the pass while the asset is not imported or the pipeline can't be created.
+    pub fn add_user_compute_pass(&self, window_id: WindowId, user_compute_pass: UserComputePass) -> jeriya_backend::Result<CustomPassId> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let custom_pass_id = CustomPassId(self.next_custom_pass_id.fetch_add(1, Ordering::Relaxed));
+        presenter.add_user_compute_pass(custom_pass_id, user_compute_pass);
+        Ok(custom_pass_id)
+    }
+
+    /// Adds a [`PostProcessingPass`] that is rendered every frame of the given window after the scene has been rendered
+    ///
+    /// Frames are rendered without the pass while the shader asset is not imported or the pipeline can't be created.
+    pub fn add_post_processing_pass(
+        &self,
+        window_id: WindowId,
+        post_processing_pass: PostProcessingPass,
+    ) -> jeriya_backend::Result<CustomPassId> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let custom_pass_id = CustomPassId(self.next_custom_pass_id.fetch_add(1, Ordering::Relaxed));
+        presenter.add_post_processing_pass(custom_pass_id, post_processing_pass);
+        Ok(custom_pass_id)
+    }
+
+    /// Removes the [`CustomPass`], [`UserComputePass`] or [`PostProcessingPass`] from the given window. It's kept alive until the frames that recorded it have finished rendering.
+    pub fn remove_custom_pass(&self, window_id: WindowId, custom_pass_id: CustomPassId) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        if !presenter.remove_custom_pass(custom_pass_id) {
+            return Err(crate::Error::NotFound.into());
+        }
+        Ok(())
+    }
+
+    /// Returns the [`MemoryStatistics`] of the GPU memory that is suballocated for the buffers of the backend
+    pub fn memory_statistics(&self) -> MemoryStatistics {
+        self.backend_shared.device.memory_statistics()
+    }
+
+    /// Returns the [`OptionalFeatures`] that were negotiated with the physical device.
+    ///
+    /// Render paths that depend on a disabled [`OptionalFeature`](crate::OptionalFeature) fall back to a reduced functionality.
+    pub fn optional_features(&self) -> OptionalFeatures {
+        self.backend_shared.device.optional_features
+    }
+}
+
 #[profile]
 impl Backend for AshBackend {
     type BackendConfig = Config;
@@ -200,11 +474,12 @@ impl Backend for AshBackend {
         backend_config: Self::BackendConfig,
         asset_importer: Arc<AssetImporter>,
         window_configs: &[WindowConfig],
+        headless_configs: &[HeadlessConfig],
     ) -> jeriya_backend::Result<Arc<Self>>
     where
         Self: Sized,
     {
-        if window_configs.is_empty() {
+        if window_configs.is_empty() && headless_configs.is_empty() {
             return Err(jeriya_backend::Error::ExpectedWindow);
         }
 
@@ -248,10 +523,11 @@ impl Backend for AshBackend {
             .collect::<crate::Result<HashMap<WindowId, Arc<Surface>>>>()?;
 
         info!("Creating PhysicalDevice");
-        let physical_device = PhysicalDevice::new(&instance)?;
+        let physical_device = PhysicalDevice::with_selection(&instance, &backend_config.physical_device_selection)?;
 
         info!("Creating QueueSelection");
-        let queue_plan = QueuePlan::new(&instance, &physical_device, surfaces.iter())?;
+        let headless_window_ids = headless_configs.iter().map(|config| config.window_id);
+        let queue_plan = QueuePlan::with_headless(&instance, &physical_device, surfaces.iter(), headless_window_ids)?;
 
         info!("Creating Device");
         let device = Device::new(physical_device, &instance, queue_plan)?;
@@ -265,30 +541,58 @@ impl Backend for AshBackend {
             &asset_importer,
         )?);
 
-        let presenters = surfaces
-            .iter()
-            .zip(window_configs)
+        let surface_targets = surfaces.iter().zip(window_configs).map(|((window_id, surface), window_config)| {
+            let presentation_target = PresentationTarget::Surface(surface.clone());
+            (
+                *window_id,
+                window_config.frame_rate,
+                window_config.present_mode,
+                window_config.color_space,
+                presentation_target,
+            )
+        });
+        let headless_targets = headless_configs.iter().map(|headless_config| {
+            let extent = vk::Extent2D {
+                width: headless_config.width,
+                height: headless_config.height,
+            };
+            (
+                headless_config.window_id,
+                headless_config.frame_rate,
+                PresentMode::default(),
+                ColorSpace::Sdr,
+                PresentationTarget::Offscreen(extent),
+            )
+        });
+        let presenters = surface_targets
+            .chain(headless_targets)
             .enumerate()
-            .map(|(presenter_index, ((window_id, surface), window_config))| {
-                info!("Creating presenter for window {window_id:?}");
-                let presenter = Presenter::new(
-                    presenter_index,
-                    *window_id,
-                    backend_shared.clone(),
-                    window_config.frame_rate,
-                    surface,
-                )?;
-                Ok((*window_id, presenter))
-            })
+            .map(
+                |(presenter_index, (window_id, frame_rate, present_mode, color_space, presentation_target))| {
+                    info!("Creating presenter for window {window_id:?}");
+                    let presenter = Presenter::new(
+                        presenter_index,
+                        window_id,
+                        backend_shared.clone(),
+                        frame_rate,
+                        present_mode,
+                        color_space,
+                        presentation_target,
+                    )?;
+                    Ok((window_id, presenter))
+                },
+            )
             .collect::<jeriya_backend::Result<HashMap<_, _>>>()?;
 
         let backend = Arc::new(Self {
             _entry: entry,
             _instance: instance,
-            _surfaces: surfaces,
+            surfaces,
             _validation_layer_callback: validation_layer_callback,
             presenters,
             backend_shared,
+            next_render_target_id: AtomicU64::new(0),
+            next_custom_pass_id: AtomicU64::new(0),
         });
 
         info!("Creating resource thread");
@@ -331,6 +635,59 @@ impl Backend for AshBackend {
         Ok(())
     }
 
+    fn render_overlay_command_buffer(
+        &self,
+        immediate_rendering_frame: &ImmediateRenderingFrame,
+        command_buffer: OverlayCommandBuffer,
+    ) -> jeriya_backend::Result<()> {
+        for presenter in self.presenters.values() {
+            presenter.send(PresenterEvent::RenderOverlayCommandBuffer {
+                command_buffer: command_buffer.clone(),
+                immediate_rendering_frame: immediate_rendering_frame.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn render_ui_frame(&self, window_id: WindowId, ui_frame: UiFrame) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        UserInterface::validate(&ui_frame)?;
+        presenter.send(PresenterEvent::RenderUiFrame(ui_frame));
+        Ok(())
+    }
+
+    fn set_resize_callback(&self, window_id: WindowId, resize_callback: Option<ResizeCallback>) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_resize_callback(resize_callback);
+        Ok(())
+    }
+
+    fn supported_color_spaces(&self, window_id: WindowId) -> jeriya_backend::Result<Vec<ColorSpace>> {
+        if !self.presenters.contains_key(&window_id) {
+            return Err(jeriya_backend::Error::UnknownWindowId(window_id));
+        }
+        match self.surfaces.get(&window_id) {
+            Some(surface) => Ok(surface.supported_color_spaces(&self.backend_shared.device.physical_device)?),
+            // Headless render targets are always SDR
+            None => Ok(vec![ColorSpace::Sdr]),
+        }
+    }
+
+    fn set_window_mode(&self, window_id: WindowId, window_mode: WindowMode) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_window_mode(window_mode);
+        Ok(())
+    }
+
     fn set_active_camera(&self, window_id: WindowId, camera_instance: &CameraInstance) -> jeriya_backend::Result<()> {
         let presenter = self
             .presenters
@@ -339,6 +696,140 @@ impl Backend for AshBackend {
         presenter.set_active_camera(camera_instance);
         Ok(())
     }
+
+    fn create_render_target(
+        &self,
+        window_id: WindowId,
+        camera_instance: &CameraInstance,
+        width: u32,
+        height: u32,
+    ) -> jeriya_backend::Result<RenderTargetId> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        let render_target_id = RenderTargetId(self.next_render_target_id.fetch_add(1, Ordering::Relaxed));
+        presenter.create_render_target(render_target_id, camera_instance, vk::Extent2D { width, height })?;
+        Ok(render_target_id)
+    }
+
+    fn remove_render_target(&self, window_id: WindowId, render_target_id: RenderTargetId) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        if !presenter.remove_render_target(render_target_id) {
+            return Err(jeriya_backend::Error::UnknownRenderTarget(render_target_id));
+        }
+        Ok(())
+    }
+
+    fn set_viewports(&self, window_id: WindowId, viewports: Vec<Viewport>) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_viewports(viewports);
+        Ok(())
+    }
+
+    fn set_clip_planes(&self, window_id: WindowId, clip_planes: Vec<ClipPlane>) -> jeriya_backend::Result<()> {
+        if clip_planes.len() > MAX_CLIP_PLANES {
+            return Err(jeriya_backend::Error::MaximumCapacityReached(MAX_CLIP_PLANES));
+        }
+        if !clip_planes.is_empty() && !self.backend_shared.device.optional_features.shader_clip_distance {
+            return Err(crate::Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::ShaderClipDistance).into());
+        }
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_clip_planes(clip_planes);
+        Ok(())
+    }
+
+    fn set_debug_bounding_volumes(&self, window_id: WindowId, enabled: bool) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_debug_bounding_volumes(enabled);
+        Ok(())
+    }
+
+    fn set_debug_shading_mode(&self, window_id: WindowId, debug_shading_mode: DebugShadingMode) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_debug_shading_mode(debug_shading_mode);
+        Ok(())
+    }
+
+    fn set_eye_dome_lighting(&self, window_id: WindowId, enabled: bool) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_eye_dome_lighting(enabled);
+        Ok(())
+    }
+
+    fn set_selection_outline_color(&self, window_id: WindowId, color: ByteColor4) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_selection_outline_color(color);
+        Ok(())
+    }
+
+    fn set_maximum_rendered_points(&self, window_id: WindowId, maximum_rendered_points: Option<usize>) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_maximum_rendered_points(maximum_rendered_points);
+        Ok(())
+    }
+
+    fn set_performance_hud(&self, window_id: WindowId, enabled: bool) -> jeriya_backend::Result<()> {
+        let presenter = self
+            .presenters
+            .get(&window_id)
+            .ok_or(jeriya_backend::Error::UnknownWindowId(window_id))?;
+        presenter.set_performance_hud(enabled);
+        Ok(())
+    }
+
+    fn statistics(&self) -> jeriya_backend::Result<RendererStatistics> {
+        let memory_statistics = self.memory_statistics();
+        let backend_shared = &self.backend_shared;
+        Ok(RendererStatistics {
+            windows: self.presenters.values().map(Presenter::statistics).collect(),
+            memory: MemoryUsage {
+                block_count: memory_statistics.block_count(),
+                allocation_count: memory_statistics.allocation_count(),
+                allocated_bytes: memory_statistics.block_bytes(),
+                used_bytes: memory_statistics.used_bytes(),
+            },
+            resources: ResourceCounts {
+                mesh_attributes: backend_shared.mesh_attributes_gpu_index_allocator.lock().len(),
+                point_cloud_attributes: backend_shared.point_cloud_attributes_gpu_index_allocator.lock().len(),
+                cameras: backend_shared.camera_gpu_index_allocator.lock().len(),
+                camera_instances: backend_shared.camera_instance_gpu_index_allocator.lock().len(),
+                rigid_meshes: backend_shared.rigid_mesh_gpu_index_allocator.lock().len(),
+                rigid_mesh_instances: backend_shared.rigid_mesh_instance_gpu_index_allocator.lock().len(),
+                point_clouds: backend_shared.point_cloud_gpu_index_allocator.lock().len(),
+                point_cloud_instances: backend_shared.point_cloud_instance_gpu_index_allocator.lock().len(),
+            },
+        })
+    }
+
+    fn save_pipeline_cache(&self) -> jeriya_backend::Result<()> {
+        self.backend_shared.pipeline_cache.save_to_path()?;
+        Ok(())
+    }
 }
 
 fn run_resource_thread(resource_event_receiver: Receiver<ResourceEvent>, backend: &Arc<AshBackend>) -> jeriya_backend::Result<()> {
@@ -356,7 +847,10 @@ fn run_resource_thread(resource_event_receiver: Receiver<ResourceEvent>, backend
         drop(queue_poll_span);
 
         match resource_event {
-            ResourceEvent::FrameStart => {}
+            ResourceEvent::FrameStart => {
+                free_retired_resources(backend)?;
+                update_point_cloud_page_streaming(backend)?;
+            }
             ResourceEvent::MeshAttributes(mesh_attributes_events) => {
                 handle_mesh_attributes_events(backend, mesh_attributes_events)?;
             }
@@ -378,21 +872,25 @@ fn run_asset_import_thread(asset_importer: Arc<AssetImporter>, backend: &Arc<Ash
             .recv()
             .map_err(|_| jeriya_backend::Error::ConnectionToAssetImporterLost)?;
 
-        match shader_receiver.try_recv() {
-            Ok(asset_import_result) => match asset_import_result.as_ref() {
-                Ok(asset) => {
-                    if asset.value().is_some() {
-                        for presenter in backend.presenters.values() {
-                            presenter.send(PresenterEvent::ShaderImported(asset.clone()));
+        // Several shaders might have been imported since the last notification
+        loop {
+            match shader_receiver.try_recv() {
+                Ok(asset_import_result) => match asset_import_result.as_ref() {
+                    Ok(asset) => {
+                        if asset.value().is_some() {
+                            info!("Shader '{}' was imported", asset.asset_key().as_str());
+                            for presenter in backend.presenters.values() {
+                                presenter.send(PresenterEvent::ShaderImported(asset.clone()));
+                            }
+                        } else {
+                            warn!("Newly imported asset was detected but the asset is None.");
                         }
-                    } else {
-                        warn!("Newly imported asset was detected but the asset is None.");
                     }
-                }
-                Err(err) => error!("Failed to import asset: {}", err),
-            },
-            Err(TryRecvError::Disconnected) => return Err(jeriya_backend::Error::ConnectionToAssetImporterLost),
-            Err(TryRecvError::Empty) => {}
+                    Err(err) => error!("Failed to import asset: {}", err),
+                },
+                Err(TryRecvError::Disconnected) => return Err(jeriya_backend::Error::ConnectionToAssetImporterLost),
+                Err(TryRecvError::Empty) => break,
+            }
         }
     }
 }
@@ -423,6 +921,8 @@ fn handle_point_cloud_attributes_events(
     let mut command_buffer_builder = CommandBufferBuilder::new(&backend_shared.device, &mut command_buffer)?;
     command_buffer_builder.begin_command_buffer_for_one_time_submit()?;
 
+    let mut retired_resources = Vec::new();
+
     // Handle mesh attributes events
     for point_cloud_attributes_event in point_cloud_attributes_events {
         match point_cloud_attributes_event {
@@ -432,6 +932,17 @@ fn handle_point_cloud_attributes_events(
             } => {
                 let _span = jeriya_shared::span!("Insert point cloud attributes");
 
+                backend_shared
+                    .point_cloud_attributes_gpu_indices
+                    .lock()
+                    .insert(handle, *point_cloud_attributes.gpu_index_allocation());
+
+                // The pages of streamed PointCloudAttributes are uploaded when they are requested
+                if point_cloud_attributes.page_streaming_source().is_some() {
+                    backend_shared.point_cloud_page_streaming.lock().insert(point_cloud_attributes);
+                    continue;
+                }
+
                 // Upload the point positions to the GPU
                 let point_positions4 = point_cloud_attributes
                     .point_positions()
@@ -460,66 +971,7 @@ fn handle_point_cloud_attributes_events(
                 let point_cloud_pages = point_cloud_attributes
                     .pages()
                     .iter()
-                    .map(|page| {
-                        let point_positions = page
-                            .point_positions()
-                            .iter()
-                            .map(|v| Vector4::new(v.x, v.y, v.z, 0.0))
-                            .chain(std::iter::repeat(Vector4::zeros()).take(Page::MAX_POINTS - page.point_positions().len()))
-                            .collect::<Vec<_>>()
-                            .try_into()
-                            .expect("point positions have wrong length");
-                        let point_colors = page
-                            .point_colors()
-                            .iter()
-                            .map(|v| v.as_vector4())
-                            .chain(std::iter::repeat(Vector4::zeros()).take(Page::MAX_POINTS - page.point_colors().len()))
-                            .collect::<Vec<_>>()
-                            .try_into()
-                            .expect("point colors have wrong length");
-                        let padding = std::iter::repeat(shader_interface::PointCloudCluster::default())
-                            .take(Page::MAX_CLUSTERS - page.clusters().len());
-                        let clusters = page
-                            .clusters()
-                            .iter()
-                            .map(|cluster| shader_interface::PointCloudCluster {
-                                center_radius: Vector4::new(cluster.center.x, cluster.center.y, cluster.center.z, cluster.radius),
-                                points_start_offset: cluster.index_start,
-                                points_len: cluster.len,
-                                level: cluster.level as u32,
-                                depth: cluster.depth as u32,
-                                children_count: cluster.children.len() as u32,
-                                children_page_indices: cluster
-                                    .children
-                                    .iter()
-                                    .map(|child| child.page_index as u32)
-                                    .chain(std::iter::repeat(u32::MAX).take(2 - cluster.children.len()))
-                                    .collect::<Vec<_>>()
-                                    .try_into()
-                                    .expect("clusters have wrong length"),
-                                children_cluster_indices: cluster
-                                    .children
-                                    .iter()
-                                    .map(|child| child.cluster_index as u32)
-                                    .chain(std::iter::repeat(u32::MAX).take(2 - cluster.children.len()))
-                                    .collect::<Vec<_>>()
-                                    .try_into()
-                                    .expect("clusters have wrong length"),
-                                padding: [0; 3],
-                            })
-                            .chain(padding)
-                            .collect::<Vec<_>>()
-                            .try_into()
-                            .expect("clusters have wrong length");
-                        shader_interface::PointCloudPage {
-                            points_len: page.point_positions().len() as u32,
-                            clusters_len: page.clusters().len() as u32,
-                            _padding: [0; 2],
-                            point_positions,
-                            point_colors,
-                            clusters,
-                        }
-                    })
+                    .map(|page| shader_interface::PointCloudPage::new(page, |page_index| page_index as u32))
                     .collect::<Vec<_>>();
 
                 let pages_start_offset = backend_shared
@@ -560,6 +1012,21 @@ fn handle_point_cloud_attributes_events(
                 let point_cloud_attributes_gpu_states2 = backend_shared.point_cloud_attributes_gpu_states.clone();
                 let backend2 = backend.clone();
                 command_buffer_builder.push_finished_operation(Box::new(move || {
+                    // The PointCloudAttributes might have been removed while they were uploaded
+                    if !backend2
+                        .backend_shared
+                        .point_cloud_attributes_gpu_indices
+                        .lock()
+                        .contains_key(&handle)
+                    {
+                        info!(
+                            "Upload of removed PointCloudAttributes {} ({:?}) is ignored",
+                            point_cloud_attributes.debug_info().format_one_line(),
+                            handle
+                        );
+                        return Ok(());
+                    }
+
                     point_cloud_attributes_gpu_states2
                         .lock()
                         .insert(handle, PointCloudAttributesGpuState::Uploaded);
@@ -580,12 +1047,146 @@ fn handle_point_cloud_attributes_events(
                     Ok(())
                 }));
             }
+            PointCloudAttributesEvent::Remove {
+                handle,
+                point_cloud_attributes,
+            } => {
+                let _span = jeriya_shared::span!("Remove point cloud attributes");
+
+                // Notify the frames that the PointCloudAttributes must not be rendered anymore
+                let mut transaction = Transaction::new();
+                transaction.push_event(transactions::Event::SetPointCloudAttributesActive {
+                    gpu_index_allocation: *point_cloud_attributes.gpu_index_allocation(),
+                    is_active: false,
+                });
+                backend.process(transaction);
+
+                backend_shared.point_cloud_attributes_gpu_states.lock().remove(&handle);
+                backend_shared.point_cloud_attributes_gpu_indices.lock().remove(&handle);
+                let mut point_cloud_page_streaming = backend_shared.point_cloud_page_streaming.lock();
+                point_cloud_page_streaming.remove(point_cloud_attributes.gpu_index_allocation().index());
+
+                // The point data, the streamed pages and the GPU index are freed when the GPU doesn't access them anymore
+                retired_resources.push(RetiredResource::PointCloudAttributes(
+                    *point_cloud_attributes.gpu_index_allocation(),
+                ));
+                retired_resources.extend(
+                    point_cloud_page_streaming
+                        .take_evicted_slots()
+                        .into_iter()
+                        .map(RetiredResource::PointCloudPage),
+                );
+
+                info!(
+                    "Removed PointCloudAttributes {} ({:?})",
+                    point_cloud_attributes.debug_info().format_one_line(),
+                    handle
+                );
+            }
         }
     }
     command_buffer_builder.end_command_buffer()?;
 
     let mut queues = backend_shared.queue_scheduler.queues();
     queues.transfer_queue().submit(command_buffer)?;
+    retire_resources(backend_shared, queues.transfer_queue().submission_count(), retired_resources);
+
+    Ok(())
+}
+
+#[profile]
+fn update_point_cloud_page_streaming(backend: &Arc<AshBackend>) -> jeriya_backend::Result<()> {
+    let backend_shared = &backend.backend_shared;
+
+    let mut point_cloud_page_streaming = backend_shared.point_cloud_page_streaming.lock();
+    if !point_cloud_page_streaming.is_streaming() {
+        return Ok(());
+    }
+
+    let _span = jeriya_shared::span!("Update point cloud page streaming");
+
+    let mut queues = backend_shared.queue_scheduler.queues();
+    let command_pool = CommandPool::new(
+        &backend_shared.device,
+        queues.transfer_queue(),
+        CommandPoolCreateFlags::ResetCommandBuffer,
+        debug_info!("PointCloudPageStreaming-CommandPool"),
+    )?;
+    drop(queues);
+
+    let mut command_buffer = CommandBuffer::new(
+        &backend_shared.device,
+        &command_pool.clone(),
+        debug_info!("PointCloudPageStreaming-CommandBuffer"),
+    )?;
+    let mut command_buffer_builder = CommandBufferBuilder::new(&backend_shared.device, &mut command_buffer)?;
+    command_buffer_builder.begin_command_buffer_for_one_time_submit()?;
+
+    let uploaded_root_pages = point_cloud_page_streaming.update(
+        &mut backend_shared.static_point_cloud_pages_buffer.lock(),
+        &mut command_buffer_builder,
+    )?;
+    let retired_resources = point_cloud_page_streaming
+        .take_evicted_slots()
+        .into_iter()
+        .map(RetiredResource::PointCloudPage)
+        .collect::<Vec<_>>();
+    drop(point_cloud_page_streaming);
+
+    // The PointCloudAttributes can be rendered as soon as their root page is resident
+    for (point_cloud_attributes, root_page_slot) in uploaded_root_pages {
+        let point_cloud_attributes_gpu = shader_interface::PointCloudAttributes {
+            root_cluster_page_index: root_page_slot as u32,
+            root_cluster_cluster_index: point_cloud_attributes.root_cluster_index().cluster_index as u32,
+            ..Default::default()
+        };
+        backend_shared
+            .point_cloud_attributes_buffer
+            .lock()
+            .set_memory_unaligned_index(point_cloud_attributes.gpu_index_allocation().index(), &point_cloud_attributes_gpu)?;
+
+        let point_cloud_attributes_gpu_states2 = backend_shared.point_cloud_attributes_gpu_states.clone();
+        let backend2 = backend.clone();
+        command_buffer_builder.push_finished_operation(Box::new(move || {
+            // The PointCloudAttributes might have been removed while the root page was uploaded
+            let handle = *point_cloud_attributes.handle();
+            if !backend2
+                .backend_shared
+                .point_cloud_attributes_gpu_indices
+                .lock()
+                .contains_key(&handle)
+            {
+                info!(
+                    "Root page of the removed PointCloudAttributes {} is ignored",
+                    point_cloud_attributes.debug_info().format_one_line()
+                );
+                return Ok(());
+            }
+
+            point_cloud_attributes_gpu_states2
+                .lock()
+                .insert(handle, PointCloudAttributesGpuState::Uploaded);
+
+            // Notify the frames that the PointCloudAttributes are ready
+            let mut transaction = Transaction::new();
+            transaction.push_event(transactions::Event::SetPointCloudAttributesActive {
+                gpu_index_allocation: *point_cloud_attributes.gpu_index_allocation(),
+                is_active: true,
+            });
+            backend2.process(transaction);
+
+            info!(
+                "Root page of the streamed PointCloudAttributes {} is resident",
+                point_cloud_attributes.debug_info().format_one_line()
+            );
+            Ok(())
+        }));
+    }
+    command_buffer_builder.end_command_buffer()?;
+
+    let mut queues = backend_shared.queue_scheduler.queues();
+    queues.transfer_queue().submit(command_buffer)?;
+    retire_resources(backend_shared, queues.transfer_queue().submission_count(), retired_resources);
 
     Ok(())
 }
@@ -618,10 +1219,16 @@ fn handle_mesh_attributes_events(
     let mut command_buffer_builder = CommandBufferBuilder::new(&backend_shared.device, &mut command_buffer)?;
     command_buffer_builder.begin_command_buffer_for_one_time_submit()?;
 
+    let mut retired_resources = Vec::new();
+
     // Handle mesh attributes events
     for mesh_attributes_event in mesh_attributes_events {
         match mesh_attributes_event {
-            MeshAttributesEvent::Insert { handle, mesh_attributes } => {
+            MeshAttributesEvent::Insert {
+                handle,
+                mesh_attributes,
+                upload_notifier,
+            } => {
                 let _span = jeriya_shared::span!("Insert mesh attributes");
 
                 // Upload the vertex positions to the GPU
@@ -722,6 +1329,10 @@ fn handle_mesh_attributes_events(
                     meshlets_len,
                 };
                 info!("Inserting a new MeshAttributes: {mesh_attributes_gpu:#?}",);
+                backend_shared
+                    .mesh_attributes_gpu_indices
+                    .lock()
+                    .insert(handle, *mesh_attributes.gpu_index_allocation());
                 backend_shared
                     .mesh_attributes_buffer
                     .lock()
@@ -731,6 +1342,16 @@ fn handle_mesh_attributes_events(
                 let mesh_attributes_gpu_states2 = backend_shared.mesh_attributes_gpu_states.clone();
                 let backend2 = backend.clone();
                 command_buffer_builder.push_finished_operation(Box::new(move || {
+                    // The MeshAttributes might have been removed while they were uploaded
+                    if !backend2.backend_shared.mesh_attributes_gpu_indices.lock().contains_key(&handle) {
+                        info!(
+                            "Upload of removed MeshAttributes {} ({:?}) is ignored",
+                            mesh_attributes.as_debug_info().format_one_line(),
+                            handle
+                        );
+                        return Ok(());
+                    }
+
                     mesh_attributes_gpu_states2.lock().insert(handle, MeshAttributesGpuState::Uploaded);
 
                     // Notify the frames that the MeshAttributes are ready
@@ -746,16 +1367,226 @@ fn handle_mesh_attributes_events(
                         mesh_attributes.as_debug_info().format_one_line(),
                         handle
                     );
+                    upload_notifier.notify_uploaded();
                     Ok(())
                 }));
             }
+            MeshAttributesEvent::Remove { handle, mesh_attributes } => {
+                let _span = jeriya_shared::span!("Remove mesh attributes");
+
+                // Notify the frames that the MeshAttributes must not be rendered anymore
+                let mut transaction = Transaction::new();
+                transaction.push_event(transactions::Event::SetMeshAttributeActive {
+                    gpu_index_allocation: *mesh_attributes.gpu_index_allocation(),
+                    is_active: false,
+                });
+                backend.process(transaction);
+
+                backend_shared.mesh_attributes_gpu_states.lock().remove(&handle);
+                backend_shared.mesh_attributes_gpu_indices.lock().remove(&handle);
+
+                // The vertex data and the GPU index are freed when the GPU doesn't access them anymore
+                retired_resources.push(RetiredResource::MeshAttributes(*mesh_attributes.gpu_index_allocation()));
+
+                info!(
+                    "Removed MeshAttributes {} ({:?})",
+                    mesh_attributes.as_debug_info().format_one_line(),
+                    handle
+                );
+            }
         }
     }
     command_buffer_builder.end_command_buffer()?;
 
     let mut queues = backend_shared.queue_scheduler.queues();
     queues.transfer_queue().submit(command_buffer)?;
+    retire_resources(backend_shared, queues.transfer_queue().submission_count(), retired_resources);
+
+    Ok(())
+}
+
+/// Retires the resources that might still be written by the uploads that have been submitted to the
+/// transfer queue until it has completed `transfer_submissions` submissions
+fn retire_resources(backend_shared: &BackendShared, transfer_submissions: u64, retired_resources: Vec<RetiredResource>) {
+    let mut retired = backend_shared.retired_resources.lock();
+    for retired_resource in retired_resources {
+        retired.retire(retired_resource, transfer_submissions);
+    }
+}
+
+/// Frees the data and the GPU indices of the removed resources that are no longer accessed by the GPU
+#[profile]
+fn free_retired_resources(backend: &Arc<AshBackend>) -> jeriya_backend::Result<()> {
+    let backend_shared = &backend.backend_shared;
+
+    let completed_transfer_submissions = backend_shared
+        .queue_scheduler
+        .queues()
+        .transfer_queue()
+        .completed_submission_count();
+    let released_resources = backend_shared
+        .retired_resources
+        .lock()
+        .release(completed_transfer_submissions, &backend_shared.frames_in_flight.lock());
+    if released_resources.is_empty() {
+        return Ok(());
+    }
+
+    let _span = jeriya_shared::span!("Free retired resources");
+    let mut has_freed_mesh_attributes = false;
+    for released_resource in released_resources {
+        match released_resource {
+            RetiredResource::MeshAttributes(gpu_index_allocation) => {
+                // Free the vertex data of the MeshAttributes in the static buffers
+                let mesh_attributes_gpu = backend_shared
+                    .mesh_attributes_buffer
+                    .lock()
+                    .get_memory_unaligned_index(gpu_index_allocation.index())?;
+                let range = |start: u64, len: u64| start as usize..(start + len) as usize;
+                backend_shared.static_vertex_position_buffer.lock().free(range(
+                    mesh_attributes_gpu.vertex_positions_start_offset,
+                    mesh_attributes_gpu.vertex_positions_len,
+                ));
+                backend_shared.static_vertex_normals_buffer.lock().free(range(
+                    mesh_attributes_gpu.vertex_normals_start_offset,
+                    mesh_attributes_gpu.vertex_normals_len,
+                ));
+                backend_shared
+                    .static_indices_buffer
+                    .lock()
+                    .free(range(mesh_attributes_gpu.indices_start_offset, mesh_attributes_gpu.indices_len));
+                backend_shared
+                    .static_meshlet_buffer
+                    .lock()
+                    .free(range(mesh_attributes_gpu.meshlets_start_offset, mesh_attributes_gpu.meshlets_len));
+
+                backend.free_gpu_index(gpu_index_allocation);
+                has_freed_mesh_attributes = true;
+            }
+            RetiredResource::PointCloudAttributes(gpu_index_allocation) => {
+                // Free the point data of the PointCloudAttributes in the static buffers
+                let point_cloud_attributes_gpu = backend_shared
+                    .point_cloud_attributes_buffer
+                    .lock()
+                    .get_memory_unaligned_index(gpu_index_allocation.index())?;
+                let range = |start: u32, len: u32| start as usize..(start + len) as usize;
+                backend_shared.static_point_positions_buffer.lock().free(range(
+                    point_cloud_attributes_gpu.point_positions_start_offset,
+                    point_cloud_attributes_gpu.points_len,
+                ));
+                backend_shared.static_point_colors_buffer.lock().free(range(
+                    point_cloud_attributes_gpu.point_colors_start_offset,
+                    point_cloud_attributes_gpu.points_len,
+                ));
+                backend_shared.static_point_cloud_pages_buffer.lock().free(range(
+                    point_cloud_attributes_gpu.pages_start_offset,
+                    point_cloud_attributes_gpu.pages_len,
+                ));
+
+                backend.free_gpu_index(gpu_index_allocation);
+            }
+            RetiredResource::PointCloudPage(slot) => {
+                backend_shared.static_point_cloud_pages_buffer.lock().free(slot..slot + 1);
+            }
+            RetiredResource::BindlessTexture(_) => {
+                unreachable!("the slots of the bindless textures are retired by the presenters")
+            }
+        }
+    }
+
+    if has_freed_mesh_attributes {
+        compact_static_mesh_attributes_buffers(backend)?;
+    }
+
+    Ok(())
+}
+
+/// Compacts the static buffers of the [`MeshAttributes`] when more than half of their elements have been freed
+/// and updates the offsets of the remaining [`MeshAttributes`].
+///
+/// The frames read the offsets from the `mesh_attributes_buffer` that is shared by all frames in flight. That's why
+/// the presenters are stopped and the device has to be idle before the data and the offsets are relocated.
+fn compact_static_mesh_attributes_buffers(backend: &AshBackend) -> jeriya_backend::Result<()> {
+    fn needs_compaction<T: Clone + 'static + Send + Sync>(buffer: &StagedPushOnlyBuffer<T>) -> bool {
+        buffer.free_len() > 0 && buffer.free_len() >= buffer.len() / 2
+    }
+
+    let backend_shared = &backend.backend_shared;
+    if !needs_compaction(&backend_shared.static_vertex_position_buffer.lock())
+        && !needs_compaction(&backend_shared.static_vertex_normals_buffer.lock())
+        && !needs_compaction(&backend_shared.static_indices_buffer.lock())
+        && !needs_compaction(&backend_shared.static_meshlet_buffer.lock())
+    {
+        return Ok(());
+    }
+
+    let _span = jeriya_shared::span!("Compact static mesh attributes buffers");
+
+    // The presenters are locked before the buffers because they lock the buffers while recording a frame
+    let _presenter_shared_guards = backend.presenters.values().map(Presenter::lock_frames).collect::<Vec<_>>();
+    {
+        // Locking all the queues at once so that no thread can submit to any
+        // queue while waiting for the frames in flight to finish.
+        let _lock = backend_shared.queue_scheduler.queues();
+        backend_shared.device.wait_for_idle()?;
+    }
 
+    let mut static_vertex_position_buffer = backend_shared.static_vertex_position_buffer.lock();
+    let mut static_vertex_normals_buffer = backend_shared.static_vertex_normals_buffer.lock();
+    let mut static_indices_buffer = backend_shared.static_indices_buffer.lock();
+    let mut static_meshlet_buffer = backend_shared.static_meshlet_buffer.lock();
+    let mut queues = backend_shared.queue_scheduler.queues();
+    let command_pool = CommandPool::new(
+        &backend_shared.device,
+        queues.transfer_queue(),
+        CommandPoolCreateFlags::ResetCommandBuffer,
+        debug_info!("MeshAttributes-Compaction-CommandPool"),
+    )?;
+    drop(queues);
+
+    let mut command_buffer = CommandBuffer::new(
+        &backend_shared.device,
+        &command_pool,
+        debug_info!("MeshAttributes-Compaction-CommandBuffer"),
+    )?;
+    let mut command_buffer_builder = CommandBufferBuilder::new(&backend_shared.device, &mut command_buffer)?;
+    command_buffer_builder.begin_command_buffer_for_one_time_submit()?;
+
+    // Uploads that have been submitted before must have finished writing to the old buffers
+    command_buffer_builder.transfer_to_transfer_command_barrier();
+    let vertex_position_relocations = static_vertex_position_buffer.compact(&mut command_buffer_builder)?;
+    let vertex_normal_relocations = static_vertex_normals_buffer.compact(&mut command_buffer_builder)?;
+    let index_relocations = static_indices_buffer.compact(&mut command_buffer_builder)?;
+    let meshlet_relocations = static_meshlet_buffer.compact(&mut command_buffer_builder)?;
+    command_buffer_builder.end_command_buffer()?;
+
+    let mut queues = backend_shared.queue_scheduler.queues();
+    queues.transfer_queue().submit_and_wait_idle(command_buffer)?;
+    drop(queues);
+
+    // Relocate the offsets of the remaining MeshAttributes including the removed ones that haven't been freed yet
+    let mut mesh_attributes_buffer = backend_shared.mesh_attributes_buffer.lock();
+    let mesh_attributes_gpu_indices = backend_shared.mesh_attributes_gpu_indices.lock();
+    let retired_resources = backend_shared.retired_resources.lock();
+    for gpu_index_allocation in mesh_attributes_gpu_indices.values().chain(retired_resources.mesh_attributes()) {
+        let mut mesh_attributes_gpu = mesh_attributes_buffer.get_memory_unaligned_index(gpu_index_allocation.index())?;
+        let relocate = |relocations: &[Relocation], offset: u64| Relocation::relocate(relocations, offset as usize) as u64;
+        mesh_attributes_gpu.vertex_positions_start_offset =
+            relocate(&vertex_position_relocations, mesh_attributes_gpu.vertex_positions_start_offset);
+        mesh_attributes_gpu.vertex_normals_start_offset =
+            relocate(&vertex_normal_relocations, mesh_attributes_gpu.vertex_normals_start_offset);
+        mesh_attributes_gpu.indices_start_offset = relocate(&index_relocations, mesh_attributes_gpu.indices_start_offset);
+        mesh_attributes_gpu.meshlets_start_offset = relocate(&meshlet_relocations, mesh_attributes_gpu.meshlets_start_offset);
+        mesh_attributes_buffer.set_memory_unaligned_index(gpu_index_allocation.index(), &mesh_attributes_gpu)?;
+    }
+
+    info!(
+        "Compacted the static mesh attributes buffers to {} vertex positions, {} vertex normals, {} indices and {} meshlets",
+        static_vertex_position_buffer.len(),
+        static_vertex_normals_buffer.len(),
+        static_indices_buffer.len(),
+        static_meshlet_buffer.len()
+    );
     Ok(())
 }
 
@@ -764,7 +1595,7 @@ mod tests {
     use super::*;
 
     mod backend_new {
-        use jeriya_shared::FrameRate;
+        use jeriya_shared::{ColorSpace, FrameRate, PresentMode};
         use jeriya_test::create_window;
 
         use super::*;
@@ -780,9 +1611,11 @@ mod tests {
             let window_config = WindowConfig {
                 window: &window,
                 frame_rate: FrameRate::Unlimited,
+                present_mode: PresentMode::Mailbox,
+                color_space: ColorSpace::Sdr,
             };
             let asset_importer = Arc::new(AssetImporter::default_from("../assets/unprocessed").unwrap());
-            AshBackend::new(renderer_config, backend_config, asset_importer, &[window_config]).unwrap();
+            AshBackend::new(renderer_config, backend_config, asset_importer, &[window_config], &[]).unwrap();
         }
 
         #[test]
@@ -793,9 +1626,11 @@ mod tests {
             let window_config = WindowConfig {
                 window: &window,
                 frame_rate: FrameRate::Unlimited,
+                present_mode: PresentMode::Mailbox,
+                color_space: ColorSpace::Sdr,
             };
             let asset_importer = Arc::new(AssetImporter::default_from("../assets/unprocessed").unwrap());
-            AshBackend::new(renderer_config, backend_config, asset_importer, &[window_config]).unwrap();
+            AshBackend::new(renderer_config, backend_config, asset_importer, &[window_config], &[]).unwrap();
         }
 
         #[test]
@@ -803,8 +1638,52 @@ mod tests {
             let renderer_config = RendererConfig::default();
             let backend_config = Config::default();
             let asset_importer = Arc::new(AssetImporter::default_from("../assets/unprocessed").unwrap());
-            let result = AshBackend::new(renderer_config, backend_config, asset_importer, &[]);
+            let result = AshBackend::new(renderer_config, backend_config, asset_importer, &[], &[]);
             assert!(matches!(result, Err(jeriya_backend::Error::ExpectedWindow)));
         }
+
+        #[test]
+        fn headless() {
+            let renderer_config = RendererConfig::default();
+            let backend_config = Config::default();
+            let headless_config = HeadlessConfig::new(64, 32, FrameRate::Unlimited);
+            let asset_importer = Arc::new(AssetImporter::default_from("../assets/unprocessed").unwrap());
+            let backend = AshBackend::new(
+                renderer_config,
+                backend_config,
+                asset_importer,
+                &[],
+                std::slice::from_ref(&headless_config),
+            )
+            .unwrap();
+            let captured_frame = backend.read_frame(headless_config.window_id).unwrap();
+            assert_eq!((captured_frame.width, captured_frame.height), (64, 32));
+            assert_eq!(captured_frame.rgba.len(), 64 * 32 * 4);
+        }
+
+        #[test]
+        fn unknown_render_target() {
+            let renderer_config = RendererConfig::default();
+            let backend_config = Config::default();
+            let headless_config = HeadlessConfig::new(64, 32, FrameRate::Unlimited);
+            let asset_importer = Arc::new(AssetImporter::default_from("../assets/unprocessed").unwrap());
+            let backend = AshBackend::new(
+                renderer_config,
+                backend_config,
+                asset_importer,
+                &[],
+                std::slice::from_ref(&headless_config),
+            )
+            .unwrap();
+            let render_target_id = RenderTargetId(42);
+            assert!(matches!(
+                backend.remove_render_target(headless_config.window_id, render_target_id),
+                Err(jeriya_backend::Error::UnknownRenderTarget(id)) if id == render_target_id
+            ));
+            assert!(matches!(
+                backend.read_render_target(headless_config.window_id, render_target_id),
+                Err(jeriya_backend::Error::UnknownRenderTarget(id)) if id == render_target_id
+            ));
+        }
     }
 }
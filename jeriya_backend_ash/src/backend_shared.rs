@@ -1,15 +1,24 @@
 use std::{
     collections::HashMap,
     sync::{mpsc::Sender, Arc},
+    time::Instant,
 };
 
 use crate::{
-    buffer::BufferUsageFlags, device::Device, host_visible_buffer::HostVisibleBuffer, page_buffer::PageBuffer,
-    queue_scheduler::QueueScheduler, shader_interface, staged_push_only_buffer::StagedPushOnlyBuffer,
+    buffer::BufferUsageFlags,
+    device::Device,
+    host_visible_buffer::HostVisibleBuffer,
+    page_buffer::PageBuffer,
+    pipeline_cache::PipelineCache,
+    point_cloud_page_streaming::PointCloudPageStreaming,
+    queue_scheduler::QueueScheduler,
+    retired_resources::{FramesInFlight, RetiredResources},
+    shader_interface,
+    staged_push_only_buffer::StagedPushOnlyBuffer,
 };
 use jeriya_backend::{
     elements::{self, point_cloud::PointCloud, rigid_mesh::RigidMesh},
-    gpu_index_allocator::GpuIndexAllocator,
+    gpu_index_allocator::{GpuIndexAllocation, GpuIndexAllocator},
     instances::{camera_instance::CameraInstance, point_cloud_instance::PointCloudInstance, rigid_mesh_instance::RigidMeshInstance},
     resources::{
         mesh_attributes::{MeshAttributes, MeshAttributesGpuState},
@@ -24,18 +33,31 @@ use jeriya_shared::{debug_info, log::info, nalgebra::Vector4, parking_lot::Mutex
 pub struct BackendShared {
     pub device: Arc<Device>,
     pub renderer_config: Arc<RendererConfig>,
+    /// Point in time at which the backend has been created. Times on the GPU are measured relative to it.
+    pub start_instant: Instant,
 
     pub queue_scheduler: QueueScheduler,
 
+    pub pipeline_cache: Arc<PipelineCache>,
+
     pub asset_importer: Arc<AssetImporter>,
 
     pub resource_event_sender: Sender<ResourceEvent>,
 
     pub mesh_attributes_gpu_states: Arc<Mutex<HashMap<Handle<Arc<MeshAttributes>>, MeshAttributesGpuState>>>,
     pub mesh_attributes_buffer: Mutex<HostVisibleBuffer<shader_interface::MeshAttributes>>,
+    /// GPU indices of the [`MeshAttributes`] that haven't been removed. Their offsets have to be updated when the static buffers are compacted.
+    pub mesh_attributes_gpu_indices: Mutex<HashMap<Handle<Arc<MeshAttributes>>, GpuIndexAllocation<MeshAttributes>>>,
 
     pub point_cloud_attributes_gpu_states: Arc<Mutex<HashMap<Handle<Arc<PointCloudAttributes>>, PointCloudAttributesGpuState>>>,
     pub point_cloud_attributes_buffer: Mutex<HostVisibleBuffer<shader_interface::PointCloudAttributes>>,
+    /// GPU indices of the [`PointCloudAttributes`] that haven't been removed
+    pub point_cloud_attributes_gpu_indices: Mutex<HashMap<Handle<Arc<PointCloudAttributes>>, GpuIndexAllocation<PointCloudAttributes>>>,
+
+    /// [`MeshAttributes`], [`PointCloudAttributes`] and point cloud pages that have been removed but might still be accessed by the GPU
+    pub retired_resources: Mutex<RetiredResources>,
+    /// Frames of the presenters that have been started but haven't finished executing on the GPU
+    pub frames_in_flight: Mutex<FramesInFlight>,
 
     pub static_vertex_position_buffer: Mutex<StagedPushOnlyBuffer<Vector4<f32>>>,
     pub static_vertex_normals_buffer: Mutex<StagedPushOnlyBuffer<Vector4<f32>>>,
@@ -46,6 +68,8 @@ pub struct BackendShared {
     pub static_point_cloud_pages_buffer: Mutex<StagedPushOnlyBuffer<shader_interface::PointCloudPage>>,
 
     pub point_cloud_page_buffer: Mutex<PageBuffer<shader_interface::PointCloudPage>>,
+    /// Streams the pages of the [`PointCloudAttributes`] that are not uploaded eagerly
+    pub point_cloud_page_streaming: Mutex<PointCloudPageStreaming>,
 
     pub mesh_attributes_gpu_index_allocator: Arc<Mutex<GpuIndexAllocator<MeshAttributes>>>,
     pub point_cloud_attributes_gpu_index_allocator: Arc<Mutex<GpuIndexAllocator<PointCloudAttributes>>>,
@@ -64,6 +88,12 @@ impl BackendShared {
         resource_sender: Sender<ResourceEvent>,
         asset_importer: &Arc<AssetImporter>,
     ) -> jeriya_backend::Result<Self> {
+        info!("Creating PipelineCache");
+        let pipeline_cache = Arc::new(match &renderer_config.pipeline_cache_path {
+            Some(path) => PipelineCache::persistent(device, path, debug_info!("PipelineCache"))?,
+            None => PipelineCache::new(device, &[], debug_info!("PipelineCache"))?,
+        });
+
         info!("Creating HostVisibleBuffer for MeshAttributes");
         let mesh_attributes_buffer = Mutex::new(HostVisibleBuffer::new(
             device,
@@ -149,6 +179,15 @@ impl BackendShared {
             debug_info!("point_cloud_page_buffer"),
         )?);
 
+        info!("Creating the PointCloudPageStreaming");
+        let point_cloud_page_streaming = Mutex::new(PointCloudPageStreaming::new(
+            asset_importer,
+            // The streamed pages can't occupy more slots than the static point cloud pages buffer has
+            renderer_config
+                .maximum_number_of_streamed_point_cloud_pages
+                .min(renderer_config.maximum_number_of_point_cloud_pages),
+        ));
+
         info!("Creating the QueueScheduler");
         let queue_scheduler = QueueScheduler::new(device)?;
 
@@ -168,13 +207,19 @@ impl BackendShared {
         Ok(Self {
             device: device.clone(),
             renderer_config: renderer_config.clone(),
+            start_instant: Instant::now(),
             queue_scheduler,
+            pipeline_cache,
             asset_importer: asset_importer.clone(),
             resource_event_sender: resource_sender,
             mesh_attributes_buffer,
             mesh_attributes_gpu_states: Arc::new(Mutex::new(HashMap::new())),
+            mesh_attributes_gpu_indices: Mutex::new(HashMap::new()),
             point_cloud_attributes_buffer,
             point_cloud_attributes_gpu_states: Arc::new(Mutex::new(HashMap::new())),
+            point_cloud_attributes_gpu_indices: Mutex::new(HashMap::new()),
+            retired_resources: Mutex::new(RetiredResources::new()),
+            frames_in_flight: Mutex::new(FramesInFlight::new()),
             static_vertex_position_buffer,
             static_vertex_normals_buffer,
             static_indices_buffer,
@@ -183,6 +228,7 @@ impl BackendShared {
             static_point_colors_buffer,
             static_point_cloud_pages_buffer,
             point_cloud_page_buffer,
+            point_cloud_page_streaming,
             mesh_attributes_gpu_index_allocator,
             point_cloud_attributes_gpu_index_allocator,
             camera_gpu_index_allocator,
@@ -193,4 +239,9 @@ impl BackendShared {
             point_cloud_instance_gpu_index_allocator,
         })
     }
+
+    /// Returns the seconds since the backend has been created. See [`shader_interface::PerFrameData::time`].
+    pub fn time(&self) -> f32 {
+        self.start_instant.elapsed().as_secs_f32()
+    }
 }
@@ -0,0 +1,337 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ash::vk;
+use jeriya_shared::{AsDebugInfo, DebugInfo};
+
+use crate::{
+    device::Device,
+    device_image::DeviceImage,
+    retired_resources::{FramesInFlight, RetiredResource, RetiredResources},
+    sampler::Sampler,
+    AsRawVulkan, DebugInfoAshExtension, PhysicalDeviceFeature,
+};
+
+/// Binding of the texture array in the [`sets::BINDLESS_TEXTURES`](crate::shader_interface::sets::BINDLESS_TEXTURES) descriptor set
+pub const BINDLESS_TEXTURES_BINDING: u32 = 0;
+
+/// Descriptor set with a large array of textures that are indexed by integer in the shaders
+///
+/// In contrast to the buffers that are pushed for every pipeline, the set is allocated once and textures
+/// are written into free slots when they are inserted. The array is partially bound so that unused slots
+/// don't have to contain valid descriptors.
+///
+/// Without [`OptionalFeature::DescriptorIndexing`](crate::OptionalFeature::DescriptorIndexing), the set only contains a
+/// single descriptor so that it can still be part of the pipeline layouts, but no textures can be inserted.
+pub struct BindlessTextures {
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    textures: Vec<Option<(Arc<DeviceImage>, Arc<Sampler>)>>,
+    free_indices: Vec<u32>,
+    /// Textures that have been removed but might still be sampled by the frames in flight
+    retired_textures: HashMap<u32, (Arc<DeviceImage>, Arc<Sampler>)>,
+    retired_resources: RetiredResources,
+    capacity: u32,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl Drop for BindlessTextures {
+    fn drop(&mut self) {
+        unsafe {
+            let device = self.device.as_raw_vulkan();
+            // Destroying the pool frees the descriptor set
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+            device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl AsDebugInfo for BindlessTextures {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl BindlessTextures {
+    /// Creates a new `BindlessTextures` with space for `capacity` textures
+    pub fn new(device: &Arc<Device>, capacity: u32, debug_info: DebugInfo) -> crate::Result<Self> {
+        jeriya_shared::assert!(capacity > 0, "BindlessTextures must have a non-zero capacity");
+        let supports_descriptor_indexing = device.optional_features.descriptor_indexing;
+        let descriptor_count = if supports_descriptor_indexing {
+            let maximum_capacity = Self::maximum_capacity(device);
+            if capacity > maximum_capacity {
+                return Err(crate::Error::CapacityExceeded {
+                    index: capacity as usize,
+                    capacity: maximum_capacity as usize,
+                });
+            }
+            capacity
+        } else {
+            1
+        };
+        let (layout_flags, binding_flags, pool_flags) = if supports_descriptor_indexing {
+            (
+                vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL,
+                vk::DescriptorBindingFlags::PARTIALLY_BOUND
+                    | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT
+                    | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND
+                    | vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING,
+                vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND,
+            )
+        } else {
+            Default::default()
+        };
+
+        // Descriptor Set Layout
+        let descriptor_set_layout_bindings = [vk::DescriptorSetLayoutBinding::builder()
+            .binding(BINDLESS_TEXTURES_BINDING)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(descriptor_count)
+            .stage_flags(vk::ShaderStageFlags::ALL)
+            .build()];
+        let binding_flags = [binding_flags];
+        let mut binding_flags_create_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder().binding_flags(&binding_flags);
+        let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .flags(layout_flags)
+            .bindings(&descriptor_set_layout_bindings)
+            .push_next(&mut binding_flags_create_info);
+        let descriptor_set_layout = unsafe {
+            device
+                .as_raw_vulkan()
+                .create_descriptor_set_layout(&descriptor_set_layout_create_info, None)?
+        };
+
+        // Descriptor Pool
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count,
+        }];
+        let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo::builder()
+            .flags(pool_flags)
+            .max_sets(1)
+            .pool_sizes(&pool_sizes);
+        let descriptor_pool = unsafe { device.as_raw_vulkan().create_descriptor_pool(&descriptor_pool_create_info, None)? };
+
+        // Descriptor Set
+        let descriptor_counts = [descriptor_count];
+        let mut variable_descriptor_count_allocate_info =
+            vk::DescriptorSetVariableDescriptorCountAllocateInfo::builder().descriptor_counts(&descriptor_counts);
+        let descriptor_set_layouts = [descriptor_set_layout];
+        let mut descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&descriptor_set_layouts);
+        if supports_descriptor_indexing {
+            descriptor_set_allocate_info = descriptor_set_allocate_info.push_next(&mut variable_descriptor_count_allocate_info);
+        }
+        let descriptor_set = unsafe { device.as_raw_vulkan().allocate_descriptor_sets(&descriptor_set_allocate_info)?[0] };
+
+        let debug_info = debug_info.with_vulkan_ptr(descriptor_set);
+        device.set_debug_name(descriptor_set, &debug_info);
+        Ok(Self {
+            descriptor_set_layout,
+            descriptor_pool,
+            descriptor_set,
+            textures: Vec::new(),
+            free_indices: Vec::new(),
+            retired_textures: HashMap::new(),
+            retired_resources: RetiredResources::new(),
+            capacity,
+            device: device.clone(),
+            debug_info,
+        })
+    }
+
+    /// Returns the maximum number of textures that the device supports in a single update-after-bind array
+    pub fn maximum_capacity(device: &Device) -> u32 {
+        let mut vulkan_1_2_properties = vk::PhysicalDeviceVulkan12Properties::default();
+        let mut properties2 = vk::PhysicalDeviceProperties2::builder().push_next(&mut vulkan_1_2_properties);
+        unsafe {
+            device
+                .instance()
+                .as_raw_vulkan()
+                .get_physical_device_properties2(*device.physical_device.as_raw_vulkan(), &mut properties2);
+        }
+        vulkan_1_2_properties
+            .max_descriptor_set_update_after_bind_sampled_images
+            .min(vulkan_1_2_properties.max_per_stage_descriptor_update_after_bind_sampled_images)
+    }
+
+    /// Inserts the `DeviceImage` into the array and returns the index with which the shaders can access it
+    ///
+    /// The image is sampled with the given [`Sampler`] and has to be in `SHADER_READ_ONLY_OPTIMAL` layout when it is sampled.
+    pub fn insert(&mut self, device_image: &Arc<DeviceImage>, sampler: &Arc<Sampler>) -> crate::Result<u32> {
+        if !self.device.optional_features.descriptor_indexing {
+            return Err(crate::Error::PhysicalDeviceFeatureMissing(
+                PhysicalDeviceFeature::DescriptorIndexing,
+            ));
+        }
+        let index = match self.free_indices.pop() {
+            Some(index) => index,
+            None => {
+                let index = self.textures.len() as u32;
+                if index >= self.capacity {
+                    return Err(crate::Error::CapacityExceeded {
+                        index: index as usize,
+                        capacity: self.capacity as usize,
+                    });
+                }
+                self.textures.push(None);
+                index
+            }
+        };
+
+        // The descriptor can be written while the set is bound in command buffers that don't use this index
+        let image_infos = [vk::DescriptorImageInfo {
+            sampler: *sampler.as_raw_vulkan(),
+            image_view: device_image.image_view(),
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }];
+        let write_descriptor_set = vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_set)
+            .dst_binding(BINDLESS_TEXTURES_BINDING)
+            .dst_array_element(index)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&image_infos)
+            .build();
+        unsafe {
+            self.device.as_raw_vulkan().update_descriptor_sets(&[write_descriptor_set], &[]);
+        }
+
+        self.textures[index as usize] = Some((device_image.clone(), sampler.clone()));
+        Ok(index)
+    }
+
+    /// Removes the texture at the given `index`
+    ///
+    /// The frames in flight might still sample the texture. That's why the slot is only reused after they have finished
+    /// and [`BindlessTextures::release`] has been called. The stale descriptor stays in the array until the slot is reused.
+    /// Shaders of frames that are started afterwards must not access the index anymore.
+    pub fn remove(&mut self, index: u32) -> Option<Arc<DeviceImage>> {
+        let texture = self.textures.get_mut(index as usize)?.take()?;
+        let device_image = texture.0.clone();
+        self.retired_textures.insert(index, texture);
+        // The textures are written on the graphics queue so that there are no transfer submissions to wait for
+        self.retired_resources.retire(RetiredResource::BindlessTexture(index), 0);
+        Some(device_image)
+    }
+
+    /// Frees the slots of the removed textures that are no longer sampled by the frames in flight
+    pub fn release(&mut self, frames_in_flight: &FramesInFlight) {
+        for retired_resource in self.retired_resources.release(0, frames_in_flight) {
+            if let RetiredResource::BindlessTexture(index) = retired_resource {
+                self.retired_textures.remove(&index);
+                self.free_indices.push(index);
+            }
+        }
+    }
+
+    /// Returns the `DeviceImage` at the given `index`
+    #[cfg(test)]
+    pub fn get(&self, index: u32) -> Option<&Arc<DeviceImage>> {
+        self.textures
+            .get(index as usize)?
+            .as_ref()
+            .map(|(device_image, _sampler)| device_image)
+    }
+
+    /// Returns the textures and their samplers that are currently in the array
+    pub fn iter(&self) -> impl Iterator<Item = &(Arc<DeviceImage>, Arc<Sampler>)> {
+        self.textures.iter().flatten()
+    }
+
+    /// Returns the number of textures in the array without the ones that have been removed
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.textures.len() - self.free_indices.len() - self.retired_textures.len()
+    }
+
+    /// Returns whether the array doesn't contain any textures
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of textures that fit into the array
+    #[cfg(test)]
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Returns the layout of the descriptor set which has to be part of the pipeline layouts
+    pub fn descriptor_set_layout(&self) -> vk::DescriptorSetLayout {
+        self.descriptor_set_layout
+    }
+
+    /// Returns the descriptor set that contains the texture array
+    pub fn descriptor_set(&self) -> vk::DescriptorSet {
+        self.descriptor_set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod new {
+        use jeriya_shared::debug_info;
+
+        use crate::{bindless_textures::BindlessTextures, device::TestFixtureDevice};
+
+        #[test]
+        fn smoke() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            assert_eq!(bindless_textures.capacity(), 16);
+            assert!(bindless_textures.is_empty());
+        }
+    }
+
+    mod insert {
+        use std::sync::Arc;
+
+        use ash::vk;
+        use jeriya_shared::debug_info;
+
+        use jeriya_shared::SamplerConfig;
+
+        use crate::{
+            bindless_textures::BindlessTextures, device::TestFixtureDevice, device_image::DeviceImage, retired_resources::FramesInFlight,
+            sampler::Sampler,
+        };
+
+        fn new_device_image(test_fixture_device: &TestFixtureDevice) -> Arc<DeviceImage> {
+            let device_image = DeviceImage::new(
+                &test_fixture_device.device,
+                vk::Extent2D { width: 4, height: 4 },
+                vk::Format::R8G8B8A8_UNORM,
+                debug_info!("my_device_image"),
+            )
+            .unwrap();
+            Arc::new(device_image)
+        }
+
+        #[test]
+        fn reuses_removed_indices() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let mut bindless_textures = BindlessTextures::new(&test_fixture_device.device, 2, debug_info!("my_bindless_textures")).unwrap();
+            let device_image = new_device_image(&test_fixture_device);
+            let sampler = Arc::new(Sampler::new(&test_fixture_device.device, &SamplerConfig::linear(), debug_info!("my_sampler")).unwrap());
+
+            assert_eq!(bindless_textures.insert(&device_image, &sampler).unwrap(), 0);
+            assert_eq!(bindless_textures.insert(&device_image, &sampler).unwrap(), 1);
+            assert!(bindless_textures.insert(&device_image, &sampler).is_err());
+
+            // The slot is only reused after the frames in flight have finished
+            let mut frames_in_flight = FramesInFlight::new();
+            frames_in_flight.start_frame(0, 5);
+            assert!(bindless_textures.remove(0).is_some());
+            assert!(bindless_textures.remove(0).is_none());
+            assert_eq!(bindless_textures.len(), 1);
+            bindless_textures.release(&frames_in_flight);
+            assert!(bindless_textures.insert(&device_image, &sampler).is_err());
+            frames_in_flight.complete_frame(0, 5);
+            bindless_textures.release(&frames_in_flight);
+            assert_eq!(bindless_textures.insert(&device_image, &sampler).unwrap(), 0);
+            assert_eq!(bindless_textures.len(), 2);
+        }
+    }
+}
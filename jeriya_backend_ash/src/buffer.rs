@@ -10,6 +10,7 @@ use crate::{
 
 bitflags! {
     /// Flags that specify the usage of a buffer
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct BufferUsageFlags: u32 {
         // WARNING: Has to match the Vulkan flags by value
         // https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/VkBufferUsageFlagBits.html
@@ -17,6 +18,7 @@ bitflags! {
         const TRANSFER_DST_BIT = 0x00000002;
         const UNIFORM_BUFFER = 0x00000010;
         const STORAGE_BUFFER = 0x00000020;
+        const INDEX_BUFFER = 0x00000040;
         const VERTEX_BUFFER = 0x00000080;
         const INDIRECT_BUFFER = 0x00000100;
     }
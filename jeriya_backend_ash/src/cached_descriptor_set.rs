@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use ash::vk;
+use jeriya_shared::{debug_info, log::info, AsDebugInfo, DebugInfo};
+
+use crate::{
+    descriptor_pool::DescriptorPool, descriptor_set_layout::DescriptorSetLayout, device::Device, push_descriptors::PushDescriptors,
+    AsRawVulkan, DebugInfoAshExtension,
+};
+
+/// Descriptor set for the buffers that stay the same for the whole frame
+///
+/// In contrast to the [`PushDescriptors`], the set is allocated once from a [`DescriptorPool`] and only
+/// rewritten when one of the buffers has been replaced. Binding it only records the handle of the set.
+pub struct CachedDescriptorSet {
+    descriptor_set: vk::DescriptorSet,
+    descriptor_pool: DescriptorPool,
+    /// Buffers that have been written into the set by the last update
+    written_buffers: Vec<(u32, vk::Buffer)>,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl AsDebugInfo for CachedDescriptorSet {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl CachedDescriptorSet {
+    /// Allocates a new `CachedDescriptorSet` with the given [`DescriptorSetLayout`]
+    pub fn new(device: &Arc<Device>, descriptor_set_layout: &Arc<DescriptorSetLayout>, debug_info: DebugInfo) -> crate::Result<Self> {
+        let mut descriptor_pool = DescriptorPool::new(
+            device,
+            descriptor_set_layout,
+            1,
+            debug_info!(format!("DescriptorPool-for-{}", debug_info.name())),
+        )?;
+        let descriptor_set = descriptor_pool.allocate_descriptor_set()?;
+        let debug_info = debug_info.with_vulkan_ptr(descriptor_set);
+        device.set_debug_name(descriptor_set, &debug_info);
+        Ok(Self {
+            descriptor_set,
+            descriptor_pool,
+            written_buffers: Vec::new(),
+            device: device.clone(),
+            debug_info,
+        })
+    }
+
+    /// Returns the [`DescriptorSetLayout`] with which the set has been allocated
+    pub fn descriptor_set_layout(&self) -> &Arc<DescriptorSetLayout> {
+        self.descriptor_pool.descriptor_set_layout()
+    }
+
+    /// Returns the bindings together with the buffers that have been written into the set by the last update
+    pub fn written_buffers(&self) -> &[(u32, vk::Buffer)] {
+        &self.written_buffers
+    }
+
+    /// Writes the `descriptors` into the set when they differ from the ones that have been written by the last update.
+    ///
+    /// Returns whether the set has been written. The set must not be in use by a command buffer that is executing.
+    pub fn update(&mut self, descriptors: &PushDescriptors) -> bool {
+        let buffers = descriptors.buffers();
+        if buffers == self.written_buffers {
+            return false;
+        }
+        info!("Updating the CachedDescriptorSet \"{}\"", self.debug_info.name());
+        let write_descriptor_sets = descriptors
+            .write_descriptor_sets()
+            .iter()
+            .map(|write_descriptor_set| vk::WriteDescriptorSet {
+                dst_set: self.descriptor_set,
+                ..*write_descriptor_set
+            })
+            .collect::<Vec<_>>();
+        unsafe {
+            self.device.as_raw_vulkan().update_descriptor_sets(&write_descriptor_sets, &[]);
+        }
+        self.written_buffers = buffers;
+        true
+    }
+}
+
+impl AsRawVulkan for CachedDescriptorSet {
+    type Output = vk::DescriptorSet;
+    fn as_raw_vulkan(&self) -> &Self::Output {
+        &self.descriptor_set
+    }
+}
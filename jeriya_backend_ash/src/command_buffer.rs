@@ -38,6 +38,7 @@ impl CommandBuffer {
         let command_buffer = unsafe { device.as_raw_vulkan().allocate_command_buffers(&command_buffer_allocate_info)?[0] };
         let completed_fence = Fence::new(device, debug_info!("CommandBuffer-completed-Fence"))?;
         let debug_info = debug_info.with_vulkan_ptr(command_buffer);
+        device.set_debug_name(command_buffer, &debug_info);
         Ok(Self {
             state: CommandBufferState::Initial,
             completed_fence,
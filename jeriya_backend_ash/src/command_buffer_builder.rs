@@ -1,18 +1,26 @@
-use std::{cell::RefCell, ffi::CString, mem, sync::Arc};
+use std::{borrow::Cow, cell::RefCell, ffi::CString, mem, ops::Range, sync::Arc};
 
 use ash::vk;
 use jeriya_shared::{features, parking_lot::Mutex};
 
 use crate::{
+    bindless_textures::BindlessTextures,
     buffer::{Buffer, VertexBuffer},
+    cached_descriptor_set::CachedDescriptorSet,
     command_buffer::{CommandBuffer, CommandBufferState, FinishedOperation},
     compute_pipeline::ComputePipeline,
+    custom_pass::MemoryBarrier,
     debug_label_guard::DebugLabelGuard,
     device::Device,
+    device_image::DeviceImage,
     device_visible_buffer::DeviceVisibleBuffer,
     graphics_pipeline::GraphicsPipeline,
     host_visible_buffer::HostVisibleBuffer,
     push_descriptors::PushDescriptors,
+    query_pool::QueryPool,
+    readback,
+    render_target::RenderTarget,
+    shader_interface::sets,
     swapchain::Swapchain,
     swapchain_depth_buffer::SwapchainDepthBuffer,
     swapchain_framebuffers::SwapchainFramebuffers,
@@ -41,7 +49,7 @@ pub struct CommandBufferBuilder<'buf> {
 
     /// Layout of the last pipeline that was bound if any
     bound_pipeline_layout: RefCell<Option<vk::PipelineLayout>>,
-    label_stack: Vec<&'static str>,
+    label_stack: Vec<Cow<'static, str>>,
 }
 
 impl<'buf> CommandBufferBuilder<'buf> {
@@ -119,6 +127,44 @@ impl<'buf> CommandBufferBuilder<'buf> {
         Ok(self)
     }
 
+    /// Begins the render pass in which the scene is rendered into the [`RenderTarget`]
+    pub fn begin_render_target_render_pass(
+        &mut self,
+        render_pass: &SwapchainRenderPass,
+        render_target: &Arc<RenderTarget>,
+    ) -> crate::Result<&mut Self> {
+        let rect = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: render_target.extent(),
+        };
+
+        let clear_values = [
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.6, 0.6, 0.9, 0.0],
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 },
+            },
+        ];
+
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(render_pass.render_pass)
+            .framebuffer(render_target.framebuffer())
+            .render_area(rect)
+            .clear_values(&clear_values);
+        unsafe {
+            self.device.as_raw_vulkan().cmd_begin_render_pass(
+                *self.command_buffer.as_raw_vulkan(),
+                &render_pass_begin_info,
+                vk::SubpassContents::INLINE,
+            );
+        }
+        self.command_buffer.push_dependency(render_target.clone());
+        Ok(self)
+    }
+
     pub fn end_render_pass(&mut self) -> crate::Result<&mut Self> {
         unsafe {
             self.device
@@ -182,6 +228,35 @@ impl<'buf> CommandBufferBuilder<'buf> {
         self
     }
 
+    /// Binds the `index_buffer` with 32 bit indices for the following indexed draw calls
+    pub fn bind_index_buffer(&mut self, index_buffer: &Arc<HostVisibleBuffer<u32>>) -> &mut Self {
+        unsafe {
+            self.device.as_raw_vulkan().cmd_bind_index_buffer(
+                *self.command_buffer.as_raw_vulkan(),
+                *index_buffer.as_raw_vulkan(),
+                0,
+                vk::IndexType::UINT32,
+            );
+        }
+        self.command_buffer.push_dependency(index_buffer.clone());
+        self
+    }
+
+    /// Draw the vertices of the `index_count` indices starting at `first_index`. The `vertex_offset` is added to the indices.
+    pub fn draw_indexed(&mut self, index_count: u32, first_index: u32, vertex_offset: i32) -> &mut Self {
+        unsafe {
+            self.device.as_raw_vulkan().cmd_draw_indexed(
+                *self.command_buffer.as_raw_vulkan(),
+                index_count,
+                1,
+                first_index,
+                vertex_offset,
+                0,
+            );
+        }
+        self
+    }
+
     /// Draw vertices with the given `vertex_count` and `first_vertex`
     pub fn draw_vertices(&mut self, vertex_count: u32, first_vertex: u32) -> &mut Self {
         unsafe {
@@ -192,6 +267,16 @@ impl<'buf> CommandBufferBuilder<'buf> {
         self
     }
 
+    /// Draw the `vertex_count` vertices of a single instance. The shaders receive the `first_instance` as `gl_InstanceIndex`.
+    pub fn draw_vertices_of_instance(&mut self, vertex_count: u32, first_instance: u32) -> &mut Self {
+        unsafe {
+            self.device
+                .as_raw_vulkan()
+                .cmd_draw(*self.command_buffer.as_raw_vulkan(), vertex_count, 1, 0, first_instance);
+        }
+        self
+    }
+
     /// Copies the contents of the given `HostVisibleBuffer` to the given `DeviceVisibleBuffer`.
     #[cfg(test)]
     pub fn copy_buffer_from_host_to_device<T: Clone + 'static + Send + Sync>(
@@ -200,6 +285,7 @@ impl<'buf> CommandBufferBuilder<'buf> {
         dst: &Arc<DeviceVisibleBuffer<T>>,
     ) -> &mut Self {
         assert_eq!(src.byte_size(), dst.byte_size(), "buffers must have the same size");
+        self.device.record_upload(src.byte_size());
         unsafe {
             let copy_region = vk::BufferCopy {
                 src_offset: 0,
@@ -255,6 +341,510 @@ impl<'buf> CommandBufferBuilder<'buf> {
         }
     }
 
+    /// Copies the contents of the given `HostVisibleBuffer` into the first mip level of the given `DeviceImage`.
+    ///
+    /// All mip levels of the image are transitioned to `TRANSFER_DST_OPTIMAL` so that the image is prepared
+    /// for [`CommandBufferBuilder::generate_mipmaps`]. The buffer has to contain tightly packed texels.
+    pub fn copy_buffer_to_image<T: Clone + 'static + Send + Sync>(
+        &mut self,
+        src: &Arc<HostVisibleBuffer<T>>,
+        dst: &Arc<DeviceImage>,
+    ) -> &mut Self {
+        self.image_layout_barrier(
+            *dst.as_raw_vulkan(),
+            0..dst.mip_level_count(),
+            (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+            (vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE),
+            (vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER),
+        );
+        let copy_region = vk::BufferImageCopy::builder()
+            .image_subresource(
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image_extent(vk::Extent3D {
+                width: dst.extent().width,
+                height: dst.extent().height,
+                depth: 1,
+            })
+            .build();
+        unsafe {
+            self.device.as_raw_vulkan().cmd_copy_buffer_to_image(
+                *self.command_buffer.as_raw_vulkan(),
+                *src.as_raw_vulkan(),
+                *dst.as_raw_vulkan(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[copy_region],
+            );
+        }
+        self.device.record_upload(src.byte_size());
+        self.command_buffer.push_dependency(src.clone());
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
+    /// Generates the mip chain of the given `DeviceImage` by successively blitting each level into the next smaller one.
+    ///
+    /// Expects the first level to contain the image data and all levels to be in `TRANSFER_DST_OPTIMAL` which is the
+    /// case after [`CommandBufferBuilder::copy_buffer_to_image`]. Afterwards, all levels are in `SHADER_READ_ONLY_OPTIMAL`.
+    /// Blitting requires a queue with graphics capabilities.
+    pub fn generate_mipmaps(&mut self, image: &Arc<DeviceImage>) -> &mut Self {
+        let shader_stages =
+            vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER;
+        for mip_level in 1..image.mip_level_count() {
+            // The previous level has been written by the copy or the last blit and becomes the source
+            self.image_layout_barrier(
+                *image.as_raw_vulkan(),
+                mip_level - 1..mip_level,
+                (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+                (vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::TRANSFER_READ),
+                (vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::TRANSFER),
+            );
+
+            let src_extent = image.mip_level_extent(mip_level - 1);
+            let dst_extent = image.mip_level_extent(mip_level);
+            let subresource = |mip_level| {
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(mip_level)
+                    .layer_count(1)
+                    .build()
+            };
+            let image_blit = vk::ImageBlit::builder()
+                .src_subresource(subresource(mip_level - 1))
+                .src_offsets([
+                    vk::Offset3D::default(),
+                    vk::Offset3D {
+                        x: src_extent.width as i32,
+                        y: src_extent.height as i32,
+                        z: 1,
+                    },
+                ])
+                .dst_subresource(subresource(mip_level))
+                .dst_offsets([
+                    vk::Offset3D::default(),
+                    vk::Offset3D {
+                        x: dst_extent.width as i32,
+                        y: dst_extent.height as i32,
+                        z: 1,
+                    },
+                ])
+                .build();
+            unsafe {
+                self.device.as_raw_vulkan().cmd_blit_image(
+                    *self.command_buffer.as_raw_vulkan(),
+                    *image.as_raw_vulkan(),
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    *image.as_raw_vulkan(),
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[image_blit],
+                    vk::Filter::LINEAR,
+                );
+            }
+
+            self.image_layout_barrier(
+                *image.as_raw_vulkan(),
+                mip_level - 1..mip_level,
+                (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+                (vk::AccessFlags::TRANSFER_READ, vk::AccessFlags::SHADER_READ),
+                (vk::PipelineStageFlags::TRANSFER, shader_stages),
+            );
+        }
+
+        // The last level is only ever written
+        let last_mip_level = image.mip_level_count() - 1;
+        self.image_layout_barrier(
+            *image.as_raw_vulkan(),
+            last_mip_level..last_mip_level + 1,
+            (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+            (vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ),
+            (vk::PipelineStageFlags::TRANSFER, shader_stages),
+        );
+        self.command_buffer.push_dependency(image.clone());
+        self
+    }
+
+    /// Copies the `src` buffer into the region of the first mip level of `dst` that starts at `offset`
+    ///
+    /// In contrast to [`CommandBufferBuilder::copy_buffer_to_image`], the image is expected to have been uploaded
+    /// before and to be in `SHADER_READ_ONLY_OPTIMAL`. The other texels are kept and the image is transitioned back
+    /// after the copy. The mip levels are not regenerated, so the image should only have a single level.
+    pub fn copy_buffer_to_image_region<T: Clone + 'static + Send + Sync>(
+        &mut self,
+        src: &Arc<HostVisibleBuffer<T>>,
+        dst: &Arc<DeviceImage>,
+        offset: vk::Offset2D,
+        extent: vk::Extent2D,
+    ) -> &mut Self {
+        let shader_stages =
+            vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER;
+        // The previous frames might still sample the image
+        self.image_layout_barrier(
+            *dst.as_raw_vulkan(),
+            0..1,
+            (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+            (vk::AccessFlags::SHADER_READ, vk::AccessFlags::TRANSFER_WRITE),
+            (shader_stages, vk::PipelineStageFlags::TRANSFER),
+        );
+        let copy_region = vk::BufferImageCopy::builder()
+            .image_subresource(
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image_offset(vk::Offset3D {
+                x: offset.x,
+                y: offset.y,
+                z: 0,
+            })
+            .image_extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .build();
+        unsafe {
+            self.device.as_raw_vulkan().cmd_copy_buffer_to_image(
+                *self.command_buffer.as_raw_vulkan(),
+                *src.as_raw_vulkan(),
+                *dst.as_raw_vulkan(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[copy_region],
+            );
+        }
+        self.image_layout_barrier(
+            *dst.as_raw_vulkan(),
+            0..1,
+            (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+            (vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ),
+            (vk::PipelineStageFlags::TRANSFER, shader_stages),
+        );
+        self.device.record_upload(src.byte_size());
+        self.command_buffer.push_dependency(src.clone());
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
+    /// Copies the swapchain image with the given index into the `HostVisibleBuffer` after the frame has been rendered.
+    ///
+    /// The image has to be in `PRESENT_SRC_KHR` layout and is transitioned back into it after the copy so that it can
+    /// still be presented. The swapchain has to support capturing and the buffer has to fit the tightly packed texels.
+    pub fn copy_swapchain_image_to_buffer(
+        &mut self,
+        swapchain: &Swapchain,
+        swapchain_image_index: usize,
+        dst: &Arc<Mutex<HostVisibleBuffer<u8>>>,
+    ) -> &mut Self {
+        jeriya_shared::assert!(swapchain.supports_capture(), "the swapchain images can't be copied");
+        self.copy_color_image_to_buffer(
+            swapchain.images[swapchain_image_index],
+            swapchain.extent(),
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            (
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ),
+            (vk::AccessFlags::empty(), vk::PipelineStageFlags::BOTTOM_OF_PIPE),
+            dst,
+        );
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
+    /// Copies the swapchain image with the given index into the `DeviceImage` so that it can be sampled while the swapchain
+    /// image is rendered to.
+    ///
+    /// The swapchain image has to be in `PRESENT_SRC_KHR` layout and is transitioned back into it after the copy. The
+    /// `DeviceImage` has to have the extent and format of the swapchain and is left in `SHADER_READ_ONLY_OPTIMAL` layout.
+    pub fn copy_swapchain_image_to_image(
+        &mut self,
+        swapchain: &Swapchain,
+        swapchain_image_index: usize,
+        dst: &Arc<DeviceImage>,
+    ) -> &mut Self {
+        jeriya_shared::assert!(swapchain.supports_capture(), "the swapchain images can't be copied");
+        let src_image = swapchain.images[swapchain_image_index];
+        let dst_image = *dst.as_raw_vulkan();
+        self.image_layout_barrier(
+            src_image,
+            0..1,
+            (vk::ImageLayout::PRESENT_SRC_KHR, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+            (vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::TRANSFER_READ),
+            (vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::TRANSFER),
+        );
+        // The content of the previous frame is discarded
+        self.image_layout_barrier(
+            dst_image,
+            0..1,
+            (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+            (vk::AccessFlags::SHADER_READ, vk::AccessFlags::TRANSFER_WRITE),
+            (vk::PipelineStageFlags::FRAGMENT_SHADER, vk::PipelineStageFlags::TRANSFER),
+        );
+        let subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .layer_count(1)
+            .build();
+        let extent = swapchain.extent();
+        let copy_region = vk::ImageCopy::builder()
+            .src_subresource(subresource)
+            .dst_subresource(subresource)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .build();
+        unsafe {
+            self.device.as_raw_vulkan().cmd_copy_image(
+                *self.command_buffer.as_raw_vulkan(),
+                src_image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst_image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[copy_region],
+            );
+        }
+        self.image_layout_barrier(
+            dst_image,
+            0..1,
+            (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+            (vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ),
+            (vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::FRAGMENT_SHADER),
+        );
+        self.image_layout_barrier(
+            src_image,
+            0..1,
+            (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::PRESENT_SRC_KHR),
+            (vk::AccessFlags::TRANSFER_READ, vk::AccessFlags::COLOR_ATTACHMENT_WRITE),
+            (vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT),
+        );
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
+    /// Copies the color image of the [`RenderTarget`] into the `HostVisibleBuffer` after it has been rendered.
+    ///
+    /// The image has to be in `SHADER_READ_ONLY_OPTIMAL` layout and is transitioned back into it after the copy so that
+    /// it can still be sampled. The buffer has to fit the tightly packed texels.
+    pub fn copy_render_target_to_buffer(
+        &mut self,
+        render_target: &Arc<RenderTarget>,
+        dst: &Arc<Mutex<HostVisibleBuffer<u8>>>,
+    ) -> &mut Self {
+        self.copy_color_image_to_buffer(
+            *render_target.color_image().as_raw_vulkan(),
+            render_target.extent(),
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            // The overlay might have sampled the image before it is transitioned
+            (
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            ),
+            (vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::FRAGMENT_SHADER),
+            dst,
+        );
+        self.command_buffer.push_dependency(render_target.clone());
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
+    /// Copies the `region` of the depth aspect of the depth buffer into the `HostVisibleBuffer` with one `u32` per texel
+    ///
+    /// The depth buffer has to be in `DEPTH_STENCIL_READ_ONLY_OPTIMAL` layout after the scene has been rendered and is
+    /// transitioned back into it after the copy.
+    pub fn copy_depth_buffer_to_buffer(
+        &mut self,
+        swapchain_depth_buffer: &SwapchainDepthBuffer,
+        region: vk::Rect2D,
+        dst: &Arc<Mutex<HostVisibleBuffer<u32>>>,
+    ) -> &mut Self {
+        let image = swapchain_depth_buffer.depth_image;
+        jeriya_shared::assert!(
+            readback::is_depth_readback_format(swapchain_depth_buffer.format),
+            "the depth aspect of the format {:?} can't be copied with one u32 per texel",
+            swapchain_depth_buffer.format
+        );
+        // Both aspects of a combined depth stencil image have to be transitioned together
+        let aspect_mask = vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL;
+        self.image_aspect_layout_barrier(
+            image,
+            aspect_mask,
+            0..1,
+            (
+                vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            ),
+            (vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE, vk::AccessFlags::TRANSFER_READ),
+            (
+                vk::PipelineStageFlags::LATE_FRAGMENT_TESTS | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+            ),
+        );
+        let copy_region = vk::BufferImageCopy::builder()
+            .image_subresource(
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(vk::ImageAspectFlags::DEPTH)
+                    .mip_level(0)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image_offset(vk::Offset3D {
+                x: region.offset.x,
+                y: region.offset.y,
+                z: 0,
+            })
+            .image_extent(vk::Extent3D {
+                width: region.extent.width,
+                height: region.extent.height,
+                depth: 1,
+            })
+            .build();
+        let dst_guard = dst.lock();
+        jeriya_shared::assert!(
+            dst_guard.len() >= region.extent.width as usize * region.extent.height as usize,
+            "the buffer is too small for the region"
+        );
+        unsafe {
+            self.device.as_raw_vulkan().cmd_copy_image_to_buffer(
+                *self.command_buffer.as_raw_vulkan(),
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                *dst_guard.as_raw_vulkan(),
+                &[copy_region],
+            );
+        }
+        drop(dst_guard);
+        self.image_aspect_layout_barrier(
+            image,
+            aspect_mask,
+            0..1,
+            (
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+            ),
+            (vk::AccessFlags::TRANSFER_READ, vk::AccessFlags::empty()),
+            (vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE),
+        );
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
+    /// Copies the first mip level of a color image with 4 bytes per texel into the `HostVisibleBuffer`
+    ///
+    /// The image is transitioned from the `layout` into `TRANSFER_SRC_OPTIMAL` and back. The (access, stage) pairs
+    /// describe the operations before and after the copy.
+    fn copy_color_image_to_buffer(
+        &mut self,
+        image: vk::Image,
+        extent: vk::Extent2D,
+        layout: vk::ImageLayout,
+        src: (vk::AccessFlags, vk::PipelineStageFlags),
+        dst_operations: (vk::AccessFlags, vk::PipelineStageFlags),
+        dst: &Arc<Mutex<HostVisibleBuffer<u8>>>,
+    ) {
+        self.image_layout_barrier(
+            image,
+            0..1,
+            (layout, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+            (src.0, vk::AccessFlags::TRANSFER_READ),
+            (src.1, vk::PipelineStageFlags::TRANSFER),
+        );
+        let copy_region = vk::BufferImageCopy::builder()
+            .image_subresource(
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image_extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .build();
+        let dst_guard = dst.lock();
+        jeriya_shared::assert!(
+            dst_guard.byte_size() >= extent.width as usize * extent.height as usize * 4,
+            "the buffer is too small for the image"
+        );
+        unsafe {
+            self.device.as_raw_vulkan().cmd_copy_image_to_buffer(
+                *self.command_buffer.as_raw_vulkan(),
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                *dst_guard.as_raw_vulkan(),
+                &[copy_region],
+            );
+        }
+        drop(dst_guard);
+        self.image_layout_barrier(
+            image,
+            0..1,
+            (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, layout),
+            (vk::AccessFlags::TRANSFER_READ, dst_operations.0),
+            (vk::PipelineStageFlags::TRANSFER, dst_operations.1),
+        );
+    }
+
+    /// Transitions the given range of mip levels of the color image with the given (src, dst) pairs
+    fn image_layout_barrier(
+        &mut self,
+        image: vk::Image,
+        mip_levels: Range<u32>,
+        layouts: (vk::ImageLayout, vk::ImageLayout),
+        access_masks: (vk::AccessFlags, vk::AccessFlags),
+        stages: (vk::PipelineStageFlags, vk::PipelineStageFlags),
+    ) {
+        self.image_aspect_layout_barrier(image, vk::ImageAspectFlags::COLOR, mip_levels, layouts, access_masks, stages);
+    }
+
+    /// Transitions the given aspects and range of mip levels of the image with the given (src, dst) pairs
+    fn image_aspect_layout_barrier(
+        &mut self,
+        image: vk::Image,
+        aspect_mask: vk::ImageAspectFlags,
+        mip_levels: Range<u32>,
+        layouts: (vk::ImageLayout, vk::ImageLayout),
+        access_masks: (vk::AccessFlags, vk::AccessFlags),
+        stages: (vk::PipelineStageFlags, vk::PipelineStageFlags),
+    ) {
+        let image_memory_barrier = vk::ImageMemoryBarrier::builder()
+            .image(image)
+            .src_access_mask(access_masks.0)
+            .dst_access_mask(access_masks.1)
+            .old_layout(layouts.0)
+            .new_layout(layouts.1)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(
+                vk::ImageSubresourceRange::builder()
+                    .aspect_mask(aspect_mask)
+                    .base_mip_level(mip_levels.start)
+                    .level_count(mip_levels.len() as u32)
+                    .layer_count(1)
+                    .build(),
+            )
+            .build();
+        unsafe {
+            self.device.as_raw_vulkan().cmd_pipeline_barrier(
+                *self.command_buffer.as_raw_vulkan(),
+                stages.0,
+                stages.1,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[image_memory_barrier],
+            )
+        };
+    }
+
     /// Pushes a closure to the list of operations to be executed when the command buffer has finished executing.
     pub fn push_finished_operation(&mut self, finished_operation: FinishedOperation) -> &mut Self {
         self.command_buffer.push_finished_operation(finished_operation);
@@ -445,6 +1035,26 @@ impl<'buf> CommandBufferBuilder<'buf> {
         self
     }
 
+    /// Records the [`MemoryBarrier`] that has been derived for a [`CustomPass`](crate::CustomPass)
+    pub fn memory_barrier(&mut self, memory_barrier: &MemoryBarrier) -> &mut Self {
+        let vk_memory_barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(memory_barrier.src_accesses)
+            .dst_access_mask(memory_barrier.dst_accesses)
+            .build();
+        unsafe {
+            self.device.as_raw_vulkan().cmd_pipeline_barrier(
+                *self.command_buffer.as_raw_vulkan(),
+                memory_barrier.src_stages,
+                memory_barrier.dst_stages,
+                vk::DependencyFlags::empty(),
+                &[vk_memory_barrier],
+                &[],
+                &[],
+            )
+        };
+        self
+    }
+
     pub fn bottom_to_top_pipeline_barrier(&mut self) -> &mut Self {
         let memory_barrier = vk::MemoryBarrier::builder()
             .src_access_mask(vk::AccessFlags::MEMORY_WRITE | vk::AccessFlags::MEMORY_READ)
@@ -520,7 +1130,14 @@ impl<'buf> CommandBufferBuilder<'buf> {
     }
 
     /// Sets line width of the dynamic pipeline state
+    ///
+    /// The line width is 1.0 when the optional feature [`OptionalFeature::WideLines`](crate::OptionalFeature::WideLines) is not enabled.
     pub fn set_line_width(&mut self, line_width: f32) {
+        let line_width = if self.device.optional_features.wide_lines {
+            line_width
+        } else {
+            1.0
+        };
         unsafe {
             self.device
                 .as_raw_vulkan()
@@ -528,6 +1145,64 @@ impl<'buf> CommandBufferBuilder<'buf> {
         }
     }
 
+    /// Sets the scissor rectangle of the dynamic pipeline state in pixels from the upper left corner of the framebuffer
+    pub fn set_scissor(&mut self, scissor: vk::Rect2D) {
+        unsafe {
+            self.device
+                .as_raw_vulkan()
+                .cmd_set_scissor(*self.command_buffer.as_raw_vulkan(), 0, &[scissor]);
+        }
+    }
+
+    /// Sets the viewport of the dynamic pipeline state in pixels from the upper left corner of the framebuffer
+    ///
+    /// The viewport is flipped like the static viewport of the graphics pipelines so that y is pointing up.
+    pub fn set_viewport(&mut self, rect: vk::Rect2D) {
+        let viewport = vk::Viewport {
+            x: rect.offset.x as f32,
+            y: (rect.offset.y + rect.extent.height as i32) as f32,
+            width: rect.extent.width as f32,
+            height: -(rect.extent.height as f32),
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        unsafe {
+            self.device
+                .as_raw_vulkan()
+                .cmd_set_viewport(*self.command_buffer.as_raw_vulkan(), 0, &[viewport]);
+        }
+    }
+
+    /// Clears the depth and stencil attachment and, when a `color` is given, the color attachment in the `rect` of the active render pass
+    pub fn clear_attachments(&mut self, rect: vk::Rect2D, color: Option<[f32; 4]>) {
+        let mut clear_attachments = vec![vk::ClearAttachment {
+            aspect_mask: vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+            color_attachment: 0,
+            clear_value: vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 },
+            },
+        }];
+        if let Some(color) = color {
+            clear_attachments.push(vk::ClearAttachment {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                color_attachment: 0,
+                clear_value: vk::ClearValue {
+                    color: vk::ClearColorValue { float32: color },
+                },
+            });
+        }
+        let clear_rect = vk::ClearRect {
+            rect,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        unsafe {
+            self.device
+                .as_raw_vulkan()
+                .cmd_clear_attachments(*self.command_buffer.as_raw_vulkan(), &clear_attachments, &[clear_rect]);
+        }
+    }
+
     /// Pushes the given descriptors to the command buffer
     pub fn push_descriptors(
         &mut self,
@@ -548,6 +1223,55 @@ impl<'buf> CommandBufferBuilder<'buf> {
         Ok(())
     }
 
+    /// Binds the texture array of the [`BindlessTextures`] to the pipeline that is currently bound
+    ///
+    /// The set has to be bound again after binding a pipeline because the push descriptor layouts of the pipelines differ.
+    pub fn bind_bindless_textures(
+        &mut self,
+        pipeline_bind_point: PipelineBindPoint,
+        bindless_textures: &BindlessTextures,
+    ) -> crate::Result<&mut Self> {
+        let bound_pipeline_layout = self.bound_pipeline_layout.borrow().ok_or(Error::NoPipelineBound)?;
+        unsafe {
+            self.device.as_raw_vulkan().cmd_bind_descriptor_sets(
+                *self.command_buffer.as_raw_vulkan(),
+                pipeline_bind_point.into(),
+                bound_pipeline_layout,
+                sets::BINDLESS_TEXTURES,
+                &[bindless_textures.descriptor_set()],
+                &[],
+            );
+        }
+        // Textures that are removed from the array while the command buffer is executing must stay alive
+        for (device_image, sampler) in bindless_textures.iter() {
+            self.command_buffer.push_dependency(device_image.clone());
+            self.command_buffer.push_dependency(sampler.clone());
+        }
+        Ok(self)
+    }
+
+    /// Binds the [`CachedDescriptorSet`] to the pipeline that is currently bound
+    ///
+    /// Like the bindless textures, the set has to be bound again after binding a pipeline.
+    pub fn bind_cached_descriptor_set(
+        &mut self,
+        pipeline_bind_point: PipelineBindPoint,
+        cached_descriptor_set: &CachedDescriptorSet,
+    ) -> crate::Result<&mut Self> {
+        let bound_pipeline_layout = self.bound_pipeline_layout.borrow().ok_or(Error::NoPipelineBound)?;
+        unsafe {
+            self.device.as_raw_vulkan().cmd_bind_descriptor_sets(
+                *self.command_buffer.as_raw_vulkan(),
+                pipeline_bind_point.into(),
+                bound_pipeline_layout,
+                sets::CACHED_DESCRIPTORS,
+                &[*cached_descriptor_set.as_raw_vulkan()],
+                &[],
+            );
+        }
+        Ok(self)
+    }
+
     /// Dispatches a compute shader
     pub fn dispatch(&mut self, x: u32, y: u32, z: u32) -> &mut Self {
         unsafe {
@@ -569,12 +1293,106 @@ impl<'buf> CommandBufferBuilder<'buf> {
         self
     }
 
+    /// Resets all queries of the given [`QueryPool`]. Must be called outside of a render pass.
+    pub fn reset_query_pool(&mut self, query_pool: &Arc<QueryPool>) -> &mut Self {
+        unsafe {
+            self.device.as_raw_vulkan().cmd_reset_query_pool(
+                *self.command_buffer.as_raw_vulkan(),
+                *query_pool.as_raw_vulkan(),
+                0,
+                query_pool.query_count(),
+            )
+        };
+        self.command_buffer.push_dependency(query_pool.clone());
+        self
+    }
+
+    /// Writes a timestamp to the `query` of the [`QueryPool`] when all previous commands have completed
+    pub fn write_timestamp(&mut self, query_pool: &Arc<QueryPool>, query: u32) -> &mut Self {
+        jeriya_shared::assert!(query < query_pool.query_count(), "query index out of bounds");
+        unsafe {
+            self.device.as_raw_vulkan().cmd_write_timestamp(
+                *self.command_buffer.as_raw_vulkan(),
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                *query_pool.as_raw_vulkan(),
+                query,
+            )
+        };
+        self.command_buffer.push_dependency(query_pool.clone());
+        self
+    }
+
+    /// Begins the `query` of the [`QueryPool`]
+    pub fn begin_query(&mut self, query_pool: &Arc<QueryPool>, query: u32) -> &mut Self {
+        jeriya_shared::assert!(query < query_pool.query_count(), "query index out of bounds");
+        unsafe {
+            self.device.as_raw_vulkan().cmd_begin_query(
+                *self.command_buffer.as_raw_vulkan(),
+                *query_pool.as_raw_vulkan(),
+                query,
+                vk::QueryControlFlags::empty(),
+            )
+        };
+        self.command_buffer.push_dependency(query_pool.clone());
+        self
+    }
+
+    /// Ends the `query` of the [`QueryPool`]
+    pub fn end_query(&mut self, query_pool: &Arc<QueryPool>, query: u32) -> &mut Self {
+        jeriya_shared::assert!(query < query_pool.query_count(), "query index out of bounds");
+        unsafe {
+            self.device
+                .as_raw_vulkan()
+                .cmd_end_query(*self.command_buffer.as_raw_vulkan(), *query_pool.as_raw_vulkan(), query)
+        };
+        self.command_buffer.push_dependency(query_pool.clone());
+        self
+    }
+
+    /// Copies the results of the `query` of the [`QueryPool`] as 32-bit values to the `dst` buffer at the byte offset `dst_offset`.
+    ///
+    /// Waits for the results of the query to become available. Must be called outside of a render pass.
+    pub fn copy_query_pool_results<T: Clone + 'static + Send + Sync>(
+        &mut self,
+        query_pool: &Arc<QueryPool>,
+        query: u32,
+        dst: &Arc<HostVisibleBuffer<T>>,
+        dst_offset: usize,
+    ) -> &mut Self {
+        let stride = query_pool.query_type().result_count() as usize * mem::size_of::<u32>();
+        jeriya_shared::assert!(query < query_pool.query_count(), "query index out of bounds");
+        jeriya_shared::assert!(dst_offset.is_multiple_of(4), "offset must be a multiple of 4");
+        jeriya_shared::assert!(
+            dst_offset + stride <= dst.byte_size(),
+            "can't copy more bytes than the destination buffer contains"
+        );
+        unsafe {
+            self.device.as_raw_vulkan().cmd_copy_query_pool_results(
+                *self.command_buffer.as_raw_vulkan(),
+                *query_pool.as_raw_vulkan(),
+                query,
+                1,
+                *dst.as_raw_vulkan(),
+                dst_offset as u64,
+                stride as u64,
+                vk::QueryResultFlags::WAIT,
+            )
+        };
+        self.command_buffer.push_dependency(query_pool.clone());
+        self.command_buffer.push_dependency(dst.clone());
+        self
+    }
+
     /// Begins a debug label scope
-    pub fn begin_label_scope(&mut self, label: &'static str, color: &[f32; 4]) -> DebugLabelGuard {
+    ///
+    /// The `label` can be the name of a [`DebugInfo`](jeriya_shared::DebugInfo) so that the commands of a resource are grouped in RenderDoc.
+    pub fn begin_label_scope(&mut self, label: impl Into<Cow<'static, str>>, color: &[f32; 4]) -> DebugLabelGuard {
+        let label = label.into();
         if features::LABELING {
             if let Some(debug_utils) = self.device.instance().debug_utils.as_ref() {
-                self.label_stack.push(label);
-                let label = CString::new(label).expect("must be a valid label");
+                self.label_stack.push(label.clone());
+                // Labels of user passes might contain NUL bytes that can't be passed to Vulkan
+                let label = CString::new(label.replace('\0', "")).expect("NUL bytes have been removed");
                 let label_info = vk::DebugUtilsLabelEXT::builder().label_name(&label).color(*color).build();
                 unsafe { debug_utils.cmd_begin_debug_utils_label(*self.command_buffer.as_raw_vulkan(), &label_info) }
             }
@@ -583,10 +1401,10 @@ impl<'buf> CommandBufferBuilder<'buf> {
     }
 
     /// Ends a debug label scope
-    pub fn end_label_scope(&mut self, label: &'static str) -> &mut Self {
+    pub fn end_label_scope(&mut self, label: &str) -> &mut Self {
         if features::LABELING {
             if let Some(debug_utils) = self.device.instance().debug_utils.as_ref() {
-                if self.label_stack.pop() != Some(label) {
+                if self.label_stack.pop().as_deref() != Some(label) {
                     panic!("Unexpected label");
                 }
                 unsafe { debug_utils.cmd_end_debug_utils_label(*self.command_buffer.as_raw_vulkan()) }
@@ -36,6 +36,7 @@ impl CommandPool {
             .queue_family_index(queue_family_index);
         let command_pool = device.as_raw_vulkan().create_command_pool(&command_pool_create_info, None)?;
         let debug_info = debug_info.with_vulkan_ptr(command_pool);
+        device.set_debug_name(command_pool, &debug_info);
         Ok(Arc::new(Self {
             command_pool_create_flags,
             device: device.clone(),
@@ -1,4 +1,6 @@
-use std::{collections::BTreeMap, mem, sync::Arc};
+use std::{borrow::Cow, collections::BTreeMap, mem, sync::Arc};
+
+use ash::vk;
 
 use crate::{
     backend_shared::BackendShared,
@@ -6,35 +8,142 @@ use crate::{
     command_buffer::CommandBuffer,
     command_buffer_builder::{CommandBufferBuilder, PipelineBindPoint},
     compute_pipeline::{GenericComputePipeline, GenericComputePipelineConfig},
+    custom_pass::{CustomPassContext, CustomPassPosition, FrameResourceTracker},
     debug_label_guard::{label_color_blue, label_color_green, label_color_magenta, label_color_red, label_color_yellow},
-    graphics_pipeline::{GenericGraphicsPipeline, GenericGraphicsPipelineConfig, PrimitiveTopology, PushConstants},
+    frame_capture::PendingFrameCapture,
+    frame_timings::PassTimer,
+    graphics_pipeline::{
+        BlendMode, CullMode, DepthMode, GenericGraphicsPipeline, GenericGraphicsPipelineConfig, PipelineRenderPass, PrimitiveTopology,
+        PushConstants, StencilState,
+    },
     host_visible_buffer::HostVisibleBuffer,
     persistent_frame_state::PersistentFrameState,
     presenter_shared::PresenterShared,
-    shader_interface, DispatchIndirectCommand, DrawIndirectCommand,
+    push_descriptors::PushDescriptors,
+    readback::{PendingDepthReadback, PendingReadback, ReadbackRequest},
+    render_target::{RenderTarget, RENDER_TARGET_FORMAT},
+    shader_interface::{self, bindings, sets},
+    specialization_constants::{Billboard, ExpandPoints, LocalSizeX, OutputColorSpace, ScreenSpace},
+    user_compute_pass::{RegisteredUserComputePass, UserComputePassDispatch, USER_COMPUTE_PASS_LOCAL_SIZE_X},
+    AsRawVulkan, DispatchIndirectCommand, DrawIndirectCommand,
+};
+use jeriya_backend::{
+    clip_plane::{ClipPlane, MAX_CLIP_PLANES},
+    debug::DebugShadingMode,
+    elements::rigid_mesh::RigidMesh,
+    gpu_index_allocator::GpuIndexAllocation,
+    immediate::{self, ImmediateCommand, ImmediateRenderingFrameTask, ImmediateSpace, Text, TextSpace},
+    instances::rigid_mesh_instance::RigidMeshInstance,
+    overlay::{OverlayCommand, OverlayCommandBuffer, OverlayImage, QuadTexture},
+    resources::mesh_attributes::MeshAttributes,
+    ui::UiMesh,
+    viewport::ViewportClear,
 };
-use jeriya_backend::immediate::{self, ImmediateCommand, ImmediateRenderingFrameTask};
 use jeriya_content::common::AssetKey;
-use jeriya_shared::{debug_info, nalgebra::Matrix4, plot_with_index, tracy_client::plot, winit::window::WindowId};
+use jeriya_shared::{
+    debug_info,
+    log::{trace, warn},
+    nalgebra::{Matrix4, Vector2, Vector4},
+    parking_lot::Mutex,
+    plot_with_index,
+    tracy_client::plot,
+    winit::window::WindowId,
+    AsDebugInfo, ColorSpace, SamplerConfig,
+};
+
+/// Size of the workgroups of the compute shaders that cull the instances. One invocation is dispatched per instance.
+const CULL_INSTANCES_LOCAL_SIZE_X: u32 = 128;
+
+/// Texture index in the [`PushConstants`] of the overlay pipelines for commands that aren't textured
+const NO_TEXTURE: u32 = u32::MAX;
+
+/// Size of the workgroups of the compute shader that advances the motions of the rigid mesh instances
+const ANIMATE_INSTANCES_LOCAL_SIZE_X: u32 = 128;
+
+/// Value that the selection mask writes into the stencil buffer for the pixels of the selected rigid mesh instances
+const SELECTION_STENCIL_REFERENCE: u8 = 1;
+
+/// Pipelines that render the immediate points, lines and triangles in one [`ImmediateSpace`]
+struct ImmediateGraphicsPipelines {
+    /// Renders quads of two triangles instead of points when the device doesn't support large points
+    point_list: Arc<GenericGraphicsPipeline>,
+    line_list: Arc<GenericGraphicsPipeline>,
+    line_strip: Arc<GenericGraphicsPipeline>,
+    triangle_list: Arc<GenericGraphicsPipeline>,
+    triangle_strip: Arc<GenericGraphicsPipeline>,
+}
+
+/// Pipelines that render the elements of the scene
+struct SceneGraphicsPipelines {
+    indirect_simple: Arc<GenericGraphicsPipeline>,
+    indirect_meshlet: Arc<GenericGraphicsPipeline>,
+    point_cloud: Arc<GenericGraphicsPipeline>,
+    point_cloud_clusters: Arc<GenericGraphicsPipeline>,
+}
+
+/// Pipelines that sample the [`BindlessTextures`](crate::bindless_textures::BindlessTextures)
+struct TexturedGraphicsPipelines {
+    text_world: Arc<GenericGraphicsPipeline>,
+    text_screen: Arc<GenericGraphicsPipeline>,
+    text_billboard: Arc<GenericGraphicsPipeline>,
+    overlay_line_list: Arc<GenericGraphicsPipeline>,
+    overlay_triangle_list: Arc<GenericGraphicsPipeline>,
+    user_interface: Arc<GenericGraphicsPipeline>,
+}
+
+/// Pipelines that outline the selected rigid mesh instances
+struct SelectionPipelines {
+    /// Renders the selected instances into the stencil buffer from which the outline is derived
+    mask: Arc<GenericGraphicsPipeline>,
+    /// Like `mask` but with a dynamic viewport. Only created when the window is split into viewports.
+    viewport_mask: Option<Arc<GenericGraphicsPipeline>>,
+    /// Blends the outline over the pixels around the stencil mask of the selected instances
+    outline: Arc<GenericGraphicsPipeline>,
+}
+
+/// Pipelines and [`PerFrameData`](shader_interface::PerFrameData) with which the scene is rendered from a camera
+struct ScenePass<'a> {
+    pipelines: &'a SceneGraphicsPipelines,
+    per_frame_data_buffer: &'a HostVisibleBuffer<shader_interface::PerFrameData>,
+    push_constants: &'a [PushConstants],
+}
 
 pub struct CompiledFrameGraph {
     command_buffer: Option<CommandBuffer>,
 
-    immediate_graphics_pipeline_line_list: Arc<GenericGraphicsPipeline>,
-    immediate_graphics_pipeline_line_strip: Arc<GenericGraphicsPipeline>,
-    immediate_graphics_pipeline_triangle_list: Arc<GenericGraphicsPipeline>,
-    immediate_graphics_pipeline_triangle_strip: Arc<GenericGraphicsPipeline>,
-    indirect_simple_graphics_pipeline: Arc<GenericGraphicsPipeline>,
-    indirect_meshlet_graphics_pipeline: Arc<GenericGraphicsPipeline>,
-    point_cloud_graphics_pipeline: Arc<GenericGraphicsPipeline>,
-    point_cloud_clusters_graphics_pipeline: Arc<GenericGraphicsPipeline>,
+    immediate_graphics_pipelines_world: ImmediateGraphicsPipelines,
+    /// Pipelines for the commands in [`ImmediateSpace::World`] that are drawn on top of the scene
+    immediate_graphics_pipelines_world_without_depth_test: ImmediateGraphicsPipelines,
+    /// Pipelines for the [`ImmediateSpace`]s in which the positions are given on the screen
+    immediate_graphics_pipelines_screen: ImmediateGraphicsPipelines,
+    /// `None` when the device doesn't support [`OptionalFeature::DescriptorIndexing`](crate::OptionalFeature::DescriptorIndexing)
+    textured_graphics_pipelines: Option<TexturedGraphicsPipelines>,
+    scene_graphics_pipelines: SceneGraphicsPipelines,
+    overdraw_scene_graphics_pipelines: SceneGraphicsPipelines,
+    /// Render targets that are rendered in this frame together with their pipelines
+    render_target_scene_graphics_pipelines: Vec<(Arc<RenderTarget>, SceneGraphicsPipelines)>,
+    /// Pipelines with a dynamic viewport that are only created when the window is split into viewports
+    viewport_scene_graphics_pipelines: Option<SceneGraphicsPipelines>,
+    viewport_overdraw_scene_graphics_pipelines: Option<SceneGraphicsPipelines>,
     device_local_debug_lines_pipeline: Arc<GenericGraphicsPipeline>,
-
+    eye_dome_lighting_pipeline: Arc<GenericGraphicsPipeline>,
+    /// Pipelines of the selection outline. They are only created in [`CompiledFrameGraph::execute`] when rigid mesh
+    /// instances are selected so that their shaders aren't required otherwise.
+    selection_pipelines: Option<SelectionPipelines>,
+    /// Pipeline that encodes the rendered image for an HDR swapchain. `None` when the swapchain is SDR.
+    output_transform_pipeline: Option<Arc<GenericGraphicsPipeline>>,
+    /// Pipelines of the [`PostProcessingPass`](crate::PostProcessingPass)es whose shaders could be compiled
+    post_processing_pipelines: Vec<Arc<GenericGraphicsPipeline>>,
+
+    animate_rigid_mesh_instances_compute_pipeline: Arc<GenericComputePipeline>,
     cull_rigid_mesh_instances_compute_pipeline: Arc<GenericComputePipeline>,
     cull_rigid_mesh_meshlets_compute_pipeline: Arc<GenericComputePipeline>,
     cull_point_cloud_instances_compute_pipeline: Arc<GenericComputePipeline>,
     cull_point_cloud_clusters_compute_pipeline: Arc<GenericComputePipeline>,
     frame_telemetry_compute_pipeline: Arc<GenericComputePipeline>,
+    debug_bounding_volumes_compute_pipeline: Arc<GenericComputePipeline>,
+    /// Pipelines of the [`UserComputePass`]es whose shaders could be compiled
+    user_compute_pipelines: Vec<(RegisteredUserComputePass, Arc<GenericComputePipeline>)>,
 }
 
 impl CompiledFrameGraph {
@@ -46,123 +155,437 @@ impl CompiledFrameGraph {
             ..Default::default()
         };
 
-        let mut create_immediate_graphics_pipeline = |primitive_topology| -> crate::Result<_> {
+        // Without large points, every point is expanded into a quad of two triangles in the vertex shader
+        let expand_points = !presenter_shared.device.optional_features.large_points;
+
+        // Points, lines and triangles on the screen are alpha blended on top of the scene
+        let mut create_immediate_graphics_pipelines = |screen_space: bool, depth_mode: DepthMode| -> crate::Result<_> {
             let config = GenericGraphicsPipelineConfig {
                 vertex_shader: Some(AssetKey::new("shaders/color.vert")),
                 fragment_shader: Some(AssetKey::new("shaders/color.frag")),
-                primitive_topology,
+                depth_mode,
                 use_input_attributes: true,
+                use_vertex_colors: true,
                 use_dynamic_state_line_width: true,
                 ..graphics_pipeline_default.clone()
             };
-            presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)
+            let config = if screen_space {
+                GenericGraphicsPipelineConfig {
+                    cull_mode: CullMode::None,
+                    blend_mode: BlendMode::AlphaBlend,
+                    ..config
+                }
+            } else {
+                config
+            }
+            .with_specialization_constant::<ScreenSpace>(screen_space);
+            let point_list_config = GenericGraphicsPipelineConfig {
+                vertex_shader: Some(AssetKey::new("shaders/point.vert")),
+                primitive_topology: if expand_points {
+                    PrimitiveTopology::TriangleList
+                } else {
+                    PrimitiveTopology::PointList
+                },
+                use_point_attributes: true,
+                ..config.clone()
+            }
+            .with_specialization_constant::<ExpandPoints>(expand_points);
+            let mut create_immediate_graphics_pipeline =
+                |config: GenericGraphicsPipelineConfig| presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config);
+            Ok(ImmediateGraphicsPipelines {
+                point_list: create_immediate_graphics_pipeline(point_list_config)?,
+                line_list: create_immediate_graphics_pipeline(GenericGraphicsPipelineConfig {
+                    primitive_topology: PrimitiveTopology::LineList,
+                    ..config.clone()
+                })?,
+                line_strip: create_immediate_graphics_pipeline(GenericGraphicsPipelineConfig {
+                    primitive_topology: PrimitiveTopology::LineStrip,
+                    ..config.clone()
+                })?,
+                triangle_list: create_immediate_graphics_pipeline(GenericGraphicsPipelineConfig {
+                    primitive_topology: PrimitiveTopology::TriangleList,
+                    ..config.clone()
+                })?,
+                triangle_strip: create_immediate_graphics_pipeline(GenericGraphicsPipelineConfig {
+                    primitive_topology: PrimitiveTopology::TriangleStrip,
+                    ..config
+                })?,
+            })
         };
-        let immediate_graphics_pipeline_line_list = create_immediate_graphics_pipeline(PrimitiveTopology::LineList)?;
-        let immediate_graphics_pipeline_line_strip = create_immediate_graphics_pipeline(PrimitiveTopology::LineStrip)?;
-        let immediate_graphics_pipeline_triangle_list = create_immediate_graphics_pipeline(PrimitiveTopology::TriangleList)?;
-        let immediate_graphics_pipeline_triangle_strip = create_immediate_graphics_pipeline(PrimitiveTopology::TriangleStrip)?;
+        let immediate_graphics_pipelines_world = create_immediate_graphics_pipelines(false, DepthMode::TestAndWrite)?;
+        let immediate_graphics_pipelines_world_without_depth_test = create_immediate_graphics_pipelines(false, DepthMode::Disabled)?;
+        let immediate_graphics_pipelines_screen = create_immediate_graphics_pipelines(true, DepthMode::Disabled)?;
+
+        // Texts, the overlay and the user interface sample the bindless textures
+        let create_textured_graphics_pipelines = |presenter_shared: &mut PresenterShared| -> crate::Result<_> {
+            // The glyphs are alpha blended. Texts in screen space and labels are rendered on top of the scene.
+            let mut create_immediate_text_graphics_pipeline = |space: TextSpace| -> crate::Result<_> {
+                let config = GenericGraphicsPipelineConfig {
+                    vertex_shader: Some(AssetKey::new("shaders/text.vert")),
+                    fragment_shader: Some(AssetKey::new("shaders/text.frag")),
+                    primitive_topology: PrimitiveTopology::TriangleList,
+                    cull_mode: CullMode::None,
+                    blend_mode: BlendMode::AlphaBlend,
+                    depth_mode: match space {
+                        TextSpace::World => DepthMode::TestAndWrite,
+                        TextSpace::Screen | TextSpace::Billboard => DepthMode::Disabled,
+                    },
+                    use_input_attributes: true,
+                    use_texture_coordinates: true,
+                    ..graphics_pipeline_default.clone()
+                }
+                .with_specialization_constant::<ScreenSpace>(space == TextSpace::Screen)
+                .with_specialization_constant::<Billboard>(space == TextSpace::Billboard);
+                presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)
+            };
+            let text_world = create_immediate_text_graphics_pipeline(TextSpace::World)?;
+            let text_screen = create_immediate_text_graphics_pipeline(TextSpace::Screen)?;
+            let text_billboard = create_immediate_text_graphics_pipeline(TextSpace::Billboard)?;
+
+            // The overlay is rendered in pixels on top of the post-processed image
+            let mut create_overlay_graphics_pipeline = |primitive_topology| -> crate::Result<_> {
+                let config = GenericGraphicsPipelineConfig {
+                    vertex_shader: Some(AssetKey::new("shaders/overlay.vert")),
+                    fragment_shader: Some(AssetKey::new("shaders/overlay.frag")),
+                    primitive_topology,
+                    cull_mode: CullMode::None,
+                    blend_mode: BlendMode::AlphaBlend,
+                    depth_mode: DepthMode::Disabled,
+                    render_pass: PipelineRenderPass::PostProcessing,
+                    use_input_attributes: true,
+                    use_texture_coordinates: true,
+                    use_dynamic_state_line_width: true,
+                    ..graphics_pipeline_default.clone()
+                };
+                presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)
+            };
+            let overlay_line_list = create_overlay_graphics_pipeline(PrimitiveTopology::LineList)?;
+            let overlay_triangle_list = create_overlay_graphics_pipeline(PrimitiveTopology::TriangleList)?;
 
-        let point_cloud_graphics_pipeline = {
-            let config = GenericGraphicsPipelineConfig {
-                vertex_shader: Some(AssetKey::new("shaders/point_cloud.vert")),
-                fragment_shader: Some(AssetKey::new("shaders/point_cloud.frag")),
+            // The user interface is clipped per mesh and its colors have premultiplied alpha
+            let user_interface_graphics_pipeline_config = GenericGraphicsPipelineConfig {
+                vertex_shader: Some(AssetKey::new("shaders/user_interface.vert")),
+                fragment_shader: Some(AssetKey::new("shaders/user_interface.frag")),
                 primitive_topology: PrimitiveTopology::TriangleList,
+                cull_mode: CullMode::None,
+                blend_mode: BlendMode::PremultipliedAlpha,
+                depth_mode: DepthMode::Disabled,
+                render_pass: PipelineRenderPass::PostProcessing,
+                use_input_attributes: true,
+                use_texture_coordinates: true,
+                use_vertex_colors: true,
+                use_dynamic_state_scissor: true,
                 ..graphics_pipeline_default.clone()
             };
-            presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)?
+            let user_interface = presenter_shared
+                .vulkan_resource_coordinator
+                .query_graphics_pipeline(&user_interface_graphics_pipeline_config)?;
+
+            Ok(TexturedGraphicsPipelines {
+                text_world,
+                text_screen,
+                text_billboard,
+                overlay_line_list,
+                overlay_triangle_list,
+                user_interface,
+            })
+        };
+        let textured_graphics_pipelines = if presenter_shared.device.optional_features.descriptor_indexing {
+            Some(create_textured_graphics_pipelines(presenter_shared)?)
+        } else {
+            warn!("Texts, the overlay and the user interface are not rendered because the device doesn't support descriptor indexing");
+            None
         };
 
-        let cull_point_cloud_instances_compute_pipeline = {
-            let config = GenericComputePipelineConfig {
-                shader: AssetKey::new("shaders/cull_point_cloud_instances.comp"),
+        let render_targets = presenter_shared
+            .vulkan_resource_coordinator
+            .render_targets()
+            .cloned()
+            .collect::<Vec<_>>();
+        let has_viewports = !presenter_shared.viewports.is_empty();
+
+        let mut create_scene_graphics_pipelines =
+            |base_config: &GenericGraphicsPipelineConfig, blend_mode, depth_mode| -> crate::Result<_> {
+                let mut query = |shader_name: &str| {
+                    let config = GenericGraphicsPipelineConfig {
+                        vertex_shader: Some(AssetKey::new(format!("shaders/{shader_name}.vert"))),
+                        fragment_shader: Some(AssetKey::new(format!("shaders/{shader_name}.frag"))),
+                        primitive_topology: PrimitiveTopology::TriangleList,
+                        blend_mode,
+                        depth_mode,
+                        ..base_config.clone()
+                    };
+                    presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)
+                };
+                Ok(SceneGraphicsPipelines {
+                    indirect_simple: query("indirect_simple")?,
+                    indirect_meshlet: query("indirect_meshlet")?,
+                    point_cloud: query("point_cloud")?,
+                    point_cloud_clusters: query("point_cloud_cluster")?,
+                })
             };
+        let scene_graphics_pipelines =
+            create_scene_graphics_pipelines(&graphics_pipeline_default, BlendMode::Opaque, DepthMode::TestAndWrite)?;
+        let overdraw_scene_graphics_pipelines =
+            create_scene_graphics_pipelines(&graphics_pipeline_default, BlendMode::Additive, DepthMode::Disabled)?;
+
+        // The pipelines of the render targets have the viewport of the render target and are compatible with its render pass
+        let render_target_scene_graphics_pipelines = render_targets
+            .into_iter()
+            .map(|render_target| {
+                let base_config = GenericGraphicsPipelineConfig {
+                    framebuffer_width: render_target.extent().width,
+                    framebuffer_height: render_target.extent().height,
+                    render_pass: PipelineRenderPass::RenderTarget,
+                    ..graphics_pipeline_default.clone()
+                };
+                let pipelines = create_scene_graphics_pipelines(&base_config, BlendMode::Opaque, DepthMode::TestAndWrite)?;
+                Ok((render_target, pipelines))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        // The viewport and the scissor of the viewports are set when they are rendered
+        let (viewport_scene_graphics_pipelines, viewport_overdraw_scene_graphics_pipelines) = if has_viewports {
+            let base_config = GenericGraphicsPipelineConfig {
+                use_dynamic_state_viewport: true,
+                use_dynamic_state_scissor: true,
+                ..graphics_pipeline_default.clone()
+            };
+            (
+                Some(create_scene_graphics_pipelines(
+                    &base_config,
+                    BlendMode::Opaque,
+                    DepthMode::TestAndWrite,
+                )?),
+                Some(create_scene_graphics_pipelines(
+                    &base_config,
+                    BlendMode::Additive,
+                    DepthMode::Disabled,
+                )?),
+            )
+        } else {
+            (None, None)
+        };
+
+        let cull_point_cloud_instances_compute_pipeline = {
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/cull_point_cloud_instances.comp"))
+                .with_specialization_constant::<LocalSizeX>(CULL_INSTANCES_LOCAL_SIZE_X);
             presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
         };
 
         let cull_point_cloud_clusters_compute_pipeline = {
-            let config = GenericComputePipelineConfig {
-                shader: AssetKey::new("shaders/cull_point_cloud_clusters.comp"),
-            };
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/cull_point_cloud_clusters.comp"));
+            presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
+        };
+
+        let animate_rigid_mesh_instances_compute_pipeline = {
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/animate_rigid_mesh_instances.comp"))
+                .with_specialization_constant::<LocalSizeX>(ANIMATE_INSTANCES_LOCAL_SIZE_X);
             presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
         };
 
         let cull_rigid_mesh_instances_compute_pipeline = {
-            let config = GenericComputePipelineConfig {
-                shader: AssetKey::new("shaders/cull_rigid_mesh_instances.comp"),
-            };
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/cull_rigid_mesh_instances.comp"))
+                .with_specialization_constant::<LocalSizeX>(CULL_INSTANCES_LOCAL_SIZE_X);
             presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
         };
 
         let cull_rigid_mesh_meshlets_compute_pipeline = {
-            let config = GenericComputePipelineConfig {
-                shader: AssetKey::new("shaders/cull_rigid_mesh_meshlets.comp"),
-            };
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/cull_rigid_mesh_meshlets.comp"));
+            presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
+        };
+
+        let frame_telemetry_compute_pipeline = {
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/frame_telemetry.comp"));
             presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
         };
 
-        let indirect_simple_graphics_pipeline = {
+        let debug_bounding_volumes_compute_pipeline = {
+            let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/debug_bounding_volumes.comp"));
+            presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
+        };
+
+        let device_local_debug_lines_pipeline = {
             let config = GenericGraphicsPipelineConfig {
-                vertex_shader: Some(AssetKey::new("shaders/indirect_simple.vert")),
-                fragment_shader: Some(AssetKey::new("shaders/indirect_simple.frag")),
-                primitive_topology: PrimitiveTopology::TriangleList,
+                vertex_shader: Some(AssetKey::new("shaders/device_local_debug_line.vert")),
+                fragment_shader: Some(AssetKey::new("shaders/device_local_debug_line.frag")),
+                primitive_topology: PrimitiveTopology::LineList,
                 ..graphics_pipeline_default.clone()
             };
             presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)?
         };
 
-        let indirect_meshlet_graphics_pipeline = {
+        // Fullscreen triangle that multiplies the rendered image with the shading of the eye-dome lighting
+        let eye_dome_lighting_pipeline = {
             let config = GenericGraphicsPipelineConfig {
-                vertex_shader: Some(AssetKey::new("shaders/indirect_meshlet.vert")),
-                fragment_shader: Some(AssetKey::new("shaders/indirect_meshlet.frag")),
+                vertex_shader: Some(AssetKey::new("shaders/eye_dome_lighting.vert")),
+                fragment_shader: Some(AssetKey::new("shaders/eye_dome_lighting.frag")),
                 primitive_topology: PrimitiveTopology::TriangleList,
+                cull_mode: CullMode::None,
+                blend_mode: BlendMode::Multiply,
+                depth_mode: DepthMode::Disabled,
+                render_pass: PipelineRenderPass::PostProcessing,
                 ..graphics_pipeline_default.clone()
             };
             presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)?
         };
 
-        let frame_telemetry_compute_pipeline = {
-            let config = GenericComputePipelineConfig {
-                shader: AssetKey::new("shaders/frame_telemetry.comp"),
-            };
-            presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config)?
+        // Fullscreen triangle that encodes the copy of the rendered image into the color space of the swapchain
+        let output_transform_pipeline = match presenter_shared.vulkan_resource_coordinator.output_transform_images() {
+            Some(_) => {
+                let output_color_space = match presenter_shared.swapchain.color_space() {
+                    ColorSpace::Sdr => 0,
+                    ColorSpace::Hdr10 => 1,
+                    ColorSpace::ScRgb => 2,
+                };
+                let config = GenericGraphicsPipelineConfig {
+                    vertex_shader: Some(AssetKey::new("shaders/output_transform.vert")),
+                    fragment_shader: Some(AssetKey::new("shaders/output_transform.frag")),
+                    primitive_topology: PrimitiveTopology::TriangleList,
+                    cull_mode: CullMode::None,
+                    blend_mode: BlendMode::Opaque,
+                    depth_mode: DepthMode::Disabled,
+                    render_pass: PipelineRenderPass::PostProcessing,
+                    ..graphics_pipeline_default.clone()
+                }
+                .with_specialization_constant::<OutputColorSpace>(output_color_space);
+                Some(presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)?)
+            }
+            None => None,
         };
 
-        let point_cloud_clusters_graphics_pipeline = {
-            let config = GenericGraphicsPipelineConfig {
-                vertex_shader: Some(AssetKey::new("shaders/point_cloud_cluster.vert")),
-                fragment_shader: Some(AssetKey::new("shaders/point_cloud_cluster.frag")),
-                primitive_topology: PrimitiveTopology::TriangleList,
-                ..graphics_pipeline_default.clone()
-            };
-            presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)?
+        // Fullscreen triangles that sample a copy of the rendered image. A pass that can't be compiled is skipped.
+        let post_processing_pipelines = if !presenter_shared.post_processing_passes.is_empty()
+            && presenter_shared
+                .vulkan_resource_coordinator
+                .prepare_post_processing_input_images(&presenter_shared.swapchain)?
+        {
+            presenter_shared
+                .post_processing_passes
+                .clone()
+                .into_iter()
+                .filter_map(|registered_post_processing_pass| {
+                    let fragment_shader = registered_post_processing_pass.post_processing_pass.fragment_shader;
+                    let config = GenericGraphicsPipelineConfig {
+                        vertex_shader: Some(AssetKey::new("shaders/output_transform.vert")),
+                        fragment_shader: Some(fragment_shader.clone()),
+                        primitive_topology: PrimitiveTopology::TriangleList,
+                        cull_mode: CullMode::None,
+                        blend_mode: BlendMode::Opaque,
+                        depth_mode: DepthMode::Disabled,
+                        render_pass: PipelineRenderPass::PostProcessing,
+                        ..graphics_pipeline_default.clone()
+                    };
+                    match presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config) {
+                        Ok(pipeline) => Some(pipeline),
+                        Err(err) => {
+                            trace!("Skipping post-processing pass {fragment_shader:?}: {err:?}");
+                            None
+                        }
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
         };
 
-        let device_local_debug_lines_pipeline = {
-            let config = GenericGraphicsPipelineConfig {
-                vertex_shader: Some(AssetKey::new("shaders/device_local_debug_line.vert")),
-                fragment_shader: Some(AssetKey::new("shaders/device_local_debug_line.frag")),
-                primitive_topology: PrimitiveTopology::LineList,
-                ..graphics_pipeline_default.clone()
-            };
-            presenter_shared.vulkan_resource_coordinator.query_graphics_pipeline(&config)?
-        };
+        // A user compute pass that can't be compiled is skipped instead of failing the frame
+        let user_compute_pipelines = presenter_shared
+            .user_compute_passes
+            .clone()
+            .into_iter()
+            .filter_map(|registered_user_compute_pass| {
+                let user_compute_pass = &registered_user_compute_pass.user_compute_pass;
+                let mut config = GenericComputePipelineConfig::new(user_compute_pass.shader.clone());
+                if user_compute_pass.dispatch == UserComputePassDispatch::PerRigidMeshInstance {
+                    config = config.with_specialization_constant::<LocalSizeX>(USER_COMPUTE_PASS_LOCAL_SIZE_X);
+                }
+                match presenter_shared.vulkan_resource_coordinator.query_compute_pipeline(&config) {
+                    Ok(pipeline) => Some((registered_user_compute_pass, pipeline)),
+                    Err(err) => {
+                        trace!("Skipping user compute pass {:?}: {err:?}", user_compute_pass.shader);
+                        None
+                    }
+                }
+            })
+            .collect();
 
         Ok(CompiledFrameGraph {
             command_buffer: None,
-            immediate_graphics_pipeline_line_list,
-            immediate_graphics_pipeline_line_strip,
-            immediate_graphics_pipeline_triangle_list,
-            immediate_graphics_pipeline_triangle_strip,
+            immediate_graphics_pipelines_world,
+            immediate_graphics_pipelines_world_without_depth_test,
+            immediate_graphics_pipelines_screen,
+            textured_graphics_pipelines,
+            animate_rigid_mesh_instances_compute_pipeline,
             cull_rigid_mesh_instances_compute_pipeline,
             cull_rigid_mesh_meshlets_compute_pipeline,
             cull_point_cloud_instances_compute_pipeline,
             cull_point_cloud_clusters_compute_pipeline,
             frame_telemetry_compute_pipeline,
-            indirect_simple_graphics_pipeline,
-            indirect_meshlet_graphics_pipeline,
-            point_cloud_graphics_pipeline,
-            point_cloud_clusters_graphics_pipeline,
+            debug_bounding_volumes_compute_pipeline,
+            user_compute_pipelines,
+            scene_graphics_pipelines,
+            overdraw_scene_graphics_pipelines,
+            render_target_scene_graphics_pipelines,
+            viewport_scene_graphics_pipelines,
+            viewport_overdraw_scene_graphics_pipelines,
             device_local_debug_lines_pipeline,
+            eye_dome_lighting_pipeline,
+            selection_pipelines: None,
+            output_transform_pipeline,
+            post_processing_pipelines,
+        })
+    }
+
+    /// Creates the [`SelectionPipelines`] for the swapchain and the viewports of the presenter
+    fn create_selection_pipelines(presenter_shared: &mut PresenterShared) -> crate::Result<SelectionPipelines> {
+        let graphics_pipeline_default = GenericGraphicsPipelineConfig {
+            primitive_topology: PrimitiveTopology::TriangleList,
+            framebuffer_width: presenter_shared.swapchain.extent().width,
+            framebuffer_height: presenter_shared.swapchain.extent().height,
+            ..Default::default()
+        };
+
+        // The selected instances are rendered without depth test so that the outline stays visible when they are occluded.
+        // Only the stencil buffer is written.
+        let mask_config = GenericGraphicsPipelineConfig {
+            vertex_shader: Some(AssetKey::new("shaders/selection_mask.vert")),
+            fragment_shader: Some(AssetKey::new("shaders/selection_mask.frag")),
+            cull_mode: CullMode::None,
+            blend_mode: BlendMode::NoColorWrite,
+            depth_mode: DepthMode::Disabled,
+            stencil_state: Some(StencilState::write(SELECTION_STENCIL_REFERENCE)),
+            ..graphics_pipeline_default.clone()
+        };
+        let vulkan_resource_coordinator = &mut presenter_shared.vulkan_resource_coordinator;
+        let mask = vulkan_resource_coordinator.query_graphics_pipeline(&mask_config)?;
+        let viewport_mask = if presenter_shared.viewports.is_empty() {
+            None
+        } else {
+            let config = GenericGraphicsPipelineConfig {
+                use_dynamic_state_viewport: true,
+                use_dynamic_state_scissor: true,
+                ..mask_config
+            };
+            Some(vulkan_resource_coordinator.query_graphics_pipeline(&config)?)
+        };
+
+        // Fullscreen triangle that blends the outline over the pixels around the stencil mask of the selected instances
+        let outline_config = GenericGraphicsPipelineConfig {
+            vertex_shader: Some(AssetKey::new("shaders/output_transform.vert")),
+            fragment_shader: Some(AssetKey::new("shaders/selection_outline.frag")),
+            cull_mode: CullMode::None,
+            blend_mode: BlendMode::AlphaBlend,
+            depth_mode: DepthMode::Disabled,
+            render_pass: PipelineRenderPass::PostProcessing,
+            ..graphics_pipeline_default
+        };
+        let outline = vulkan_resource_coordinator.query_graphics_pipeline(&outline_config)?;
+
+        Ok(SelectionPipelines {
+            mask,
+            viewport_mask,
+            outline,
         })
     }
 
@@ -190,15 +613,32 @@ impl CompiledFrameGraph {
             point_cloud_instance_count: persistent_frame_state.point_cloud_instance_buffer.high_water_mark() as u32,
             framebuffer_width: presenter_shared.swapchain.extent().width,
             framebuffer_height: presenter_shared.swapchain.extent().height,
+            maximum_rendered_points: presenter_shared
+                .maximum_rendered_points
+                .map_or(u32::MAX, |maximum_rendered_points| {
+                    maximum_rendered_points.min(u32::MAX as usize) as u32
+                }),
+            time: presenter_shared.time(backend_shared),
+            clip_plane_count: presenter_shared.clip_planes.len().min(MAX_CLIP_PLANES) as u32,
+            _padding: [0; 2],
+            clip_planes: clip_planes(&presenter_shared.clip_planes),
         };
         persistent_frame_state
             .per_frame_data_buffer
-            .set_memory_unaligned(&[per_frame_data])?;
+            .set_memory_unaligned(std::slice::from_ref(&per_frame_data))?;
         drop(span);
 
-        const LOCAL_SIZE_X: u32 = 128;
-        let cull_compute_shader_group_count =
-            (persistent_frame_state.rigid_mesh_instance_buffer.high_water_mark() as u32 + LOCAL_SIZE_X - 1) / LOCAL_SIZE_X;
+        let span = jeriya_shared::span!("update cached descriptor set");
+        persistent_frame_state.update_cached_descriptor_set(
+            backend_shared,
+            presenter_shared.vulkan_resource_coordinator.cached_descriptor_set_layout(),
+        )?;
+        drop(span);
+
+        let cull_compute_shader_group_count = workgroup_count(
+            persistent_frame_state.rigid_mesh_instance_buffer.high_water_mark() as u32,
+            &self.cull_rigid_mesh_instances_compute_pipeline,
+        );
 
         // Build CommandBuffer
         let command_buffer_span = jeriya_shared::span!("build command buffer");
@@ -225,6 +665,804 @@ impl CompiledFrameGraph {
         builder.fill_buffer(&persistent_frame_state.device_local_debug_lines_buffer, 0, byte_size, 0);
         builder.bottom_to_top_pipeline_barrier();
 
+        // The timestamps of the passes are written into the query pool of the frame and read back when the frame has finished
+        let mut pass_timer = PassTimer::new(&persistent_frame_state.timestamp_query_pool, &mut builder);
+
+        // The pipeline statistics are collected for all passes up to the frame telemetry
+        if let Some(pipeline_statistics_query_pool) = &persistent_frame_state.pipeline_statistics_query_pool {
+            builder.reset_query_pool(pipeline_statistics_query_pool);
+            builder.begin_query(pipeline_statistics_query_pool, 0);
+        }
+
+        // Advance the motions of the rigid mesh instances by overwriting their transforms
+        // before they are read by the culling. Skipped when no instance has a motion.
+        if persistent_frame_state.rigid_mesh_instance_motion_count > 0 {
+            let animate_rigid_mesh_instances_span = jeriya_shared::span!("animate rigid mesh instances");
+            let animate_rigid_mesh_instances_scope = builder.begin_label_scope("AnimateRigidMeshInstances", &label_color_yellow(1.0));
+            pass_timer.begin(&mut builder, "AnimateRigidMeshInstances");
+            {
+                let pipeline = &self.animate_rigid_mesh_instances_compute_pipeline;
+                builder.bind_compute_pipeline(pipeline.as_ref());
+                persistent_frame_state.push_descriptors(PipelineBindPoint::Compute, &pipeline.descriptor_set_layout, &mut builder)?;
+                let group_count = workgroup_count(
+                    persistent_frame_state.rigid_mesh_instance_motion_buffer.high_water_mark() as u32,
+                    pipeline,
+                );
+                builder.dispatch(group_count, 1, 1);
+                builder.compute_to_compute_pipeline_barrier();
+            }
+            pass_timer.end(&mut builder);
+            drop(animate_rigid_mesh_instances_span);
+            animate_rigid_mesh_instances_scope.end(&mut builder);
+        }
+
+        self.append_custom_passes(
+            CustomPassPosition::BeforeCulling,
+            persistent_frame_state,
+            backend_shared,
+            presenter_shared,
+            &mut builder,
+            &mut pass_timer,
+        )?;
+
+        // Render Targets
+        //
+        // The scene is rendered from the cameras of the render targets before the culling for the active camera
+        // overwrites the visible instances. Every render target is culled separately and all of them are timed as one pass.
+        let render_target_ids = self
+            .render_target_scene_graphics_pipelines
+            .iter()
+            .map(|(render_target, _)| render_target.id())
+            .collect::<Vec<_>>();
+        persistent_frame_state
+            .render_target_per_frame_data_buffers
+            .retain(|id, _| render_target_ids.contains(id));
+        if !self.render_target_scene_graphics_pipelines.is_empty() {
+            let render_targets_span = jeriya_shared::span!("render targets");
+            let render_targets_scope = builder.begin_label_scope("RenderTargets", &label_color_green(0.6));
+            pass_timer.begin(&mut builder, "RenderTargets");
+            for (render_target, pipelines) in &self.render_target_scene_graphics_pipelines {
+                let extent = render_target.extent();
+                let render_target_per_frame_data = shader_interface::PerFrameData {
+                    active_camera: render_target.camera_instance().index() as i32,
+                    framebuffer_width: extent.width,
+                    framebuffer_height: extent.height,
+                    ..per_frame_data.clone()
+                };
+                persistent_frame_state.update_render_target_per_frame_data(
+                    render_target.id(),
+                    render_target_per_frame_data,
+                    backend_shared,
+                )?;
+                let per_frame_data_buffer = &persistent_frame_state.render_target_per_frame_data_buffers[&render_target.id()];
+                self.append_culling_commands(persistent_frame_state, &mut builder, per_frame_data_buffer, &mut None)?;
+                builder.begin_render_target_render_pass(
+                    presenter_shared.vulkan_resource_coordinator.render_target_render_pass(),
+                    render_target,
+                )?;
+                self.append_scene_commands(
+                    persistent_frame_state,
+                    backend_shared,
+                    presenter_shared,
+                    &mut builder,
+                    ScenePass {
+                        pipelines,
+                        per_frame_data_buffer,
+                        push_constants: &[PushConstants::default()],
+                    },
+                    &mut None,
+                )?;
+                builder.end_render_pass()?;
+            }
+            pass_timer.end(&mut builder);
+            drop(render_targets_span);
+            render_targets_scope.end(&mut builder);
+        }
+
+        self.append_culling_commands(
+            persistent_frame_state,
+            &mut builder,
+            &persistent_frame_state.per_frame_data_buffer,
+            &mut Some(&mut pass_timer),
+        )?;
+
+        // Push the bounding volumes to the device local debug lines buffer
+        if presenter_shared.debug_bounding_volumes {
+            let debug_bounding_volumes_span = jeriya_shared::span!("debug bounding volumes");
+            let debug_bounding_volumes_scope = builder.begin_label_scope("DebugBoundingVolumes", &label_color_yellow(0.9));
+            pass_timer.begin(&mut builder, "DebugBoundingVolumes");
+            {
+                let pipeline = &self.debug_bounding_volumes_compute_pipeline;
+                builder.bind_compute_pipeline(pipeline.as_ref());
+                persistent_frame_state.push_descriptors(PipelineBindPoint::Compute, &pipeline.descriptor_set_layout, &mut builder)?;
+                builder.compute_to_compute_pipeline_barrier();
+                builder.dispatch(cull_compute_shader_group_count.max(1), 1, 1);
+                builder.bottom_to_top_pipeline_barrier();
+            }
+            pass_timer.end(&mut builder);
+            drop(debug_bounding_volumes_span);
+            debug_bounding_volumes_scope.end(&mut builder);
+        }
+
+        self.append_custom_passes(
+            CustomPassPosition::AfterCulling,
+            persistent_frame_state,
+            backend_shared,
+            presenter_shared,
+            &mut builder,
+            &mut pass_timer,
+        )?;
+
+        // Create the vertex buffers of the immediate command buffers that are rendered for the first time
+        presenter_shared.immediate_vertex_buffers.update(
+            &backend_shared.device,
+            immediate_rendering_frames.values().flat_map(|task| &task.command_buffers),
+        )?;
+
+        // Upload the font atlases and overlay images that are used for the first time because copies aren't allowed in the render pass
+        let renders_textures = self.textured_graphics_pipelines.is_some();
+        if renders_textures {
+            for text in immediate_texts(immediate_rendering_frames) {
+                presenter_shared.immediate_textures.upload_font(
+                    text.font(),
+                    &backend_shared.device,
+                    &mut presenter_shared.vulkan_resource_coordinator,
+                    &mut builder,
+                )?;
+            }
+            for image in overlay_commands(immediate_rendering_frames).filter_map(overlay_image) {
+                presenter_shared.immediate_textures.upload_overlay_image(
+                    image,
+                    &backend_shared.device,
+                    &mut presenter_shared.vulkan_resource_coordinator,
+                    &mut builder,
+                )?;
+            }
+            presenter_shared.immediate_textures.evict_fonts(
+                immediate_texts(immediate_rendering_frames).map(|text| text.font().as_ref()),
+                &mut presenter_shared.vulkan_resource_coordinator,
+            );
+            presenter_shared.immediate_textures.evict_overlay_images(
+                overlay_commands(immediate_rendering_frames)
+                    .filter_map(overlay_image)
+                    .map(Arc::as_ref),
+                &mut presenter_shared.vulkan_resource_coordinator,
+            );
+        }
+        presenter_shared
+            .vulkan_resource_coordinator
+            .bindless_textures_mut()
+            .release(&backend_shared.frames_in_flight.lock());
+        presenter_shared.user_interface.upload_textures(
+            &backend_shared.device,
+            &mut presenter_shared.vulkan_resource_coordinator,
+            &mut builder,
+        )?;
+
+        let rendering_span = jeriya_shared::span!("rendering");
+        let rendering_scope = builder.begin_label_scope("Rendering", &label_color_green(0.8));
+
+        // Render Pass
+        let swapchain_image_index = presenter_shared
+            .frame_index
+            .swapchain_index()
+            .expect("swapchain index must be set before rendering");
+
+        // The debug shading mode is passed to the fragment shaders with the push constants
+        let overdraw = presenter_shared.debug_shading_mode == DebugShadingMode::Overdraw;
+        let scene_push_constants = [PushConstants {
+            debug_shading_mode: presenter_shared.debug_shading_mode.into(),
+            ..Default::default()
+        }];
+
+        // The selected instances are only written into the stencil buffer when there are any. The outline is
+        // skipped when its pipelines can't be created, e.g. because the shaders are missing.
+        if !persistent_frame_state.selected_rigid_mesh_instances.is_empty() && self.selection_pipelines.is_none() {
+            match Self::create_selection_pipelines(presenter_shared) {
+                Ok(selection_pipelines) => self.selection_pipelines = Some(selection_pipelines),
+                Err(err) => trace!("Skipping the selection outline: {err:?}"),
+            }
+        }
+        let selection_pipelines = match persistent_frame_state.selected_rigid_mesh_instances.is_empty() {
+            true => None,
+            false => self.selection_pipelines.as_ref(),
+        };
+
+        if presenter_shared.viewports.is_empty() {
+            builder.begin_render_pass(
+                &presenter_shared.swapchain,
+                presenter_shared.vulkan_resource_coordinator.swapchain_render_pass(),
+                (
+                    presenter_shared.vulkan_resource_coordinator.swapchain_framebuffers(),
+                    swapchain_image_index,
+                ),
+            )?;
+            let scene_graphics_pipelines = if overdraw {
+                &self.overdraw_scene_graphics_pipelines
+            } else {
+                &self.scene_graphics_pipelines
+            };
+            self.append_scene_commands(
+                persistent_frame_state,
+                backend_shared,
+                presenter_shared,
+                &mut builder,
+                ScenePass {
+                    pipelines: scene_graphics_pipelines,
+                    per_frame_data_buffer: &persistent_frame_state.per_frame_data_buffer,
+                    push_constants: &scene_push_constants,
+                },
+                &mut Some(&mut pass_timer),
+            )?;
+            if let Some(selection_pipelines) = selection_pipelines {
+                Self::append_selection_mask_commands(
+                    persistent_frame_state,
+                    backend_shared,
+                    &mut builder,
+                    &selection_pipelines.mask,
+                    &persistent_frame_state.per_frame_data_buffer,
+                    &scene_push_constants,
+                )?;
+            }
+        } else {
+            // Viewports
+            //
+            // Every viewport is culled for its own camera which isn't possible inside of a render pass. The first viewport
+            // clears the whole window and the following ones continue in the render pass that loads the attachments.
+            let viewports_span = jeriya_shared::span!("viewports");
+            let viewports_scope = builder.begin_label_scope("Viewports", &label_color_green(0.7));
+            pass_timer.begin(&mut builder, "Viewports");
+            let viewport_scene_graphics_pipelines = if overdraw {
+                &self.viewport_overdraw_scene_graphics_pipelines
+            } else {
+                &self.viewport_scene_graphics_pipelines
+            }
+            .as_ref()
+            .expect("viewport pipelines must be created when the window has viewports");
+            let extent = presenter_shared.swapchain.extent();
+            for (index, viewport) in presenter_shared.viewports.iter().enumerate() {
+                let (offset, size) = viewport.rect().to_pixels(extent.width, extent.height);
+                let viewport_per_frame_data = shader_interface::PerFrameData {
+                    active_camera: viewport.camera_instance().index() as i32,
+                    framebuffer_width: size[0],
+                    framebuffer_height: size[1],
+                    ..per_frame_data.clone()
+                };
+                persistent_frame_state.update_viewport_per_frame_data(index, viewport_per_frame_data, backend_shared)?;
+                let per_frame_data_buffer = &persistent_frame_state.viewport_per_frame_data_buffers[index];
+                self.append_culling_commands(persistent_frame_state, &mut builder, per_frame_data_buffer, &mut None)?;
+                let render_pass = if index == 0 {
+                    presenter_shared.vulkan_resource_coordinator.swapchain_render_pass()
+                } else {
+                    presenter_shared.vulkan_resource_coordinator.swapchain_load_render_pass()
+                };
+                builder.begin_render_pass(
+                    &presenter_shared.swapchain,
+                    render_pass,
+                    (
+                        presenter_shared.vulkan_resource_coordinator.swapchain_framebuffers(),
+                        swapchain_image_index,
+                    ),
+                )?;
+                // Empty viewports are skipped after the first render pass has cleared the window
+                if let Some(rect) = pixel_rect(offset, size) {
+                    builder.set_viewport(rect);
+                    let (scissor_offset, scissor_size) = viewport.scissor().to_pixels(extent.width, extent.height);
+                    let scissor = pixel_rect(scissor_offset, scissor_size).unwrap_or_default();
+                    builder.set_scissor(scissor);
+                    match viewport.clear() {
+                        ViewportClear::ColorAndDepth(color) => builder.clear_attachments(rect, Some(color.as_vector4().into())),
+                        ViewportClear::Depth => builder.clear_attachments(rect, None),
+                        ViewportClear::None => {}
+                    }
+                    self.append_scene_commands(
+                        persistent_frame_state,
+                        backend_shared,
+                        presenter_shared,
+                        &mut builder,
+                        ScenePass {
+                            pipelines: viewport_scene_graphics_pipelines,
+                            per_frame_data_buffer,
+                            push_constants: &scene_push_constants,
+                        },
+                        &mut None,
+                    )?;
+                    if let Some(selection_pipelines) = selection_pipelines {
+                        let viewport_mask = selection_pipelines
+                            .viewport_mask
+                            .as_ref()
+                            .expect("viewport pipelines must be created when the window has viewports");
+                        Self::append_selection_mask_commands(
+                            persistent_frame_state,
+                            backend_shared,
+                            &mut builder,
+                            viewport_mask,
+                            per_frame_data_buffer,
+                            &scene_push_constants,
+                        )?;
+                    }
+                }
+                builder.end_render_pass()?;
+            }
+            pass_timer.end(&mut builder);
+            drop(viewports_span);
+            viewports_scope.end(&mut builder);
+
+            // The immediate rendering and the debug lines are rendered across the whole window from the active camera
+            builder.begin_render_pass(
+                &presenter_shared.swapchain,
+                presenter_shared.vulkan_resource_coordinator.swapchain_load_render_pass(),
+                (
+                    presenter_shared.vulkan_resource_coordinator.swapchain_framebuffers(),
+                    swapchain_image_index,
+                ),
+            )?;
+        }
+
+        // Render with ImmediateRenderingPipeline
+        pass_timer.begin(&mut builder, "ImmediateRendering");
+        self.append_immediate_rendering_commands(persistent_frame_state, presenter_shared, &mut builder, immediate_rendering_frames)?;
+        pass_timer.end(&mut builder);
+
+        // Render device local debug lines
+        let device_local_debug_lines_span = jeriya_shared::span!("record device local debug lines commands");
+        let device_local_debug_lines_scope = builder.begin_label_scope("DeviceLocalDebugLines", &label_color_yellow(0.8));
+        pass_timer.begin(&mut builder, "DeviceLocalDebugLines");
+        {
+            let pipeline = &self.device_local_debug_lines_pipeline;
+            builder.bind_graphics_pipeline(pipeline.as_ref());
+            persistent_frame_state.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, &mut builder)?;
+            builder.draw_indirect(
+                &persistent_frame_state.device_local_debug_lines_buffer,
+                mem::size_of::<u32>() as u64,
+                1,
+            );
+        }
+        pass_timer.end(&mut builder);
+        drop(device_local_debug_lines_span);
+        device_local_debug_lines_scope.end(&mut builder);
+
+        builder.end_render_pass()?;
+
+        // Eye-Dome Lighting
+        //
+        // The depth buffer is sampled in a separate render pass because the neighboring pixels
+        // have to be compared which isn't possible with input attachments.
+        if presenter_shared.eye_dome_lighting {
+            let eye_dome_lighting_span = jeriya_shared::span!("record eye-dome lighting commands");
+            let eye_dome_lighting_scope = builder.begin_label_scope("EyeDomeLighting", &label_color_green(1.0));
+            pass_timer.begin(&mut builder, "EyeDomeLighting");
+            {
+                let depth_sampler = presenter_shared
+                    .vulkan_resource_coordinator
+                    .query_sampler(&SamplerConfig::nearest())?;
+                builder.begin_render_pass(
+                    &presenter_shared.swapchain,
+                    presenter_shared.vulkan_resource_coordinator.post_processing_render_pass(),
+                    (
+                        presenter_shared.vulkan_resource_coordinator.post_processing_framebuffers(),
+                        swapchain_image_index,
+                    ),
+                )?;
+                let pipeline = &self.eye_dome_lighting_pipeline;
+                builder.bind_graphics_pipeline(pipeline.as_ref());
+                persistent_frame_state.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, &mut builder)?;
+                let depth_buffer = presenter_shared
+                    .vulkan_resource_coordinator
+                    .swapchain_depth_buffers()
+                    .depth_buffers
+                    .get(&presenter_shared.frame_index);
+                let push_descriptors = PushDescriptors::builder(&pipeline.descriptor_set_layout)
+                    .push_combined_image_sampler(
+                        bindings::DEPTH_BUFFER,
+                        depth_buffer.depth_image_view,
+                        &depth_sampler,
+                        vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                    )
+                    .build();
+                builder.push_descriptors(sets::PUSH_DESCRIPTORS, PipelineBindPoint::Graphics, &push_descriptors)?;
+                builder.draw_vertices(3, 0);
+                builder.end_render_pass()?;
+            }
+            pass_timer.end(&mut builder);
+            drop(eye_dome_lighting_span);
+            eye_dome_lighting_scope.end(&mut builder);
+        }
+
+        // Post-Processing
+        //
+        // Every pass samples a copy of the image that the previous passes have rendered and replaces it.
+        let post_processing_sampler = match self.post_processing_pipelines.is_empty() {
+            false => Some(
+                presenter_shared
+                    .vulkan_resource_coordinator
+                    .query_sampler(&SamplerConfig::nearest())?,
+            ),
+            true => None,
+        };
+        if let (Some(sampler), Some(post_processing_input_images)) = (
+            &post_processing_sampler,
+            presenter_shared.vulkan_resource_coordinator.post_processing_input_images(),
+        ) {
+            let post_processing_span = jeriya_shared::span!("record post-processing commands");
+            let post_processing_scope = builder.begin_label_scope("PostProcessing", &label_color_green(0.8));
+            pass_timer.begin(&mut builder, "PostProcessing");
+            let post_processing_input_image = post_processing_input_images.get(&presenter_shared.frame_index);
+            let depth_buffer = presenter_shared
+                .vulkan_resource_coordinator
+                .swapchain_depth_buffers()
+                .depth_buffers
+                .get(&presenter_shared.frame_index);
+            for pipeline in &self.post_processing_pipelines {
+                builder.copy_swapchain_image_to_image(&presenter_shared.swapchain, swapchain_image_index, post_processing_input_image);
+                builder.begin_render_pass(
+                    &presenter_shared.swapchain,
+                    presenter_shared.vulkan_resource_coordinator.post_processing_render_pass(),
+                    (
+                        presenter_shared.vulkan_resource_coordinator.post_processing_framebuffers(),
+                        swapchain_image_index,
+                    ),
+                )?;
+                builder.bind_graphics_pipeline(pipeline.as_ref());
+                persistent_frame_state.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, &mut builder)?;
+                let push_descriptors = PushDescriptors::builder(&pipeline.descriptor_set_layout)
+                    .push_combined_image_sampler(
+                        bindings::POST_PROCESSING_INPUT,
+                        post_processing_input_image.image_view(),
+                        sampler,
+                        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    )
+                    .push_combined_image_sampler(
+                        bindings::DEPTH_BUFFER,
+                        depth_buffer.depth_image_view,
+                        sampler,
+                        vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                    )
+                    .build();
+                builder.push_descriptors(sets::PUSH_DESCRIPTORS, PipelineBindPoint::Graphics, &push_descriptors)?;
+                builder.draw_vertices(3, 0);
+                builder.end_render_pass()?;
+            }
+            pass_timer.end(&mut builder);
+            drop(post_processing_span);
+            post_processing_scope.end(&mut builder);
+        }
+
+        // Selection Outline
+        //
+        // The stencil buffer contains the mask of the selected instances which is dilated by the fragment shader.
+        if let Some(selection_pipelines) = selection_pipelines {
+            let selection_outline_span = jeriya_shared::span!("record selection outline commands");
+            let selection_outline_scope = builder.begin_label_scope("SelectionOutline", &label_color_yellow(1.0));
+            pass_timer.begin(&mut builder, "SelectionOutline");
+            {
+                let stencil_sampler = presenter_shared
+                    .vulkan_resource_coordinator
+                    .query_sampler(&SamplerConfig::nearest())?;
+                builder.begin_render_pass(
+                    &presenter_shared.swapchain,
+                    presenter_shared.vulkan_resource_coordinator.post_processing_render_pass(),
+                    (
+                        presenter_shared.vulkan_resource_coordinator.post_processing_framebuffers(),
+                        swapchain_image_index,
+                    ),
+                )?;
+                let pipeline = &selection_pipelines.outline;
+                builder.bind_graphics_pipeline(pipeline.as_ref());
+                persistent_frame_state.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, &mut builder)?;
+                let depth_buffer = presenter_shared
+                    .vulkan_resource_coordinator
+                    .swapchain_depth_buffers()
+                    .depth_buffers
+                    .get(&presenter_shared.frame_index);
+                let push_descriptors = PushDescriptors::builder(&pipeline.descriptor_set_layout)
+                    .push_combined_image_sampler(
+                        bindings::SELECTION_STENCIL,
+                        depth_buffer.stencil_image_view,
+                        &stencil_sampler,
+                        vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                    )
+                    .build();
+                builder.push_descriptors(sets::PUSH_DESCRIPTORS, PipelineBindPoint::Graphics, &push_descriptors)?;
+                builder.push_constants(&[PushConstants {
+                    color: presenter_shared.selection_outline_color.as_vector4(),
+                    ..Default::default()
+                }])?;
+                builder.draw_vertices(3, 0);
+                builder.end_render_pass()?;
+            }
+            pass_timer.end(&mut builder);
+            drop(selection_outline_span);
+            selection_outline_scope.end(&mut builder);
+        }
+
+        self.append_custom_passes(
+            CustomPassPosition::AfterRendering,
+            persistent_frame_state,
+            backend_shared,
+            presenter_shared,
+            &mut builder,
+            &mut pass_timer,
+        )?;
+
+        // Overlay
+        //
+        // Rendered after the post-processing so that the overlay is neither occluded by nor shaded with the scene.
+        if renders_textures && overlay_commands(immediate_rendering_frames).next().is_some() {
+            let overlay_span = jeriya_shared::span!("record overlay commands");
+            let overlay_scope = builder.begin_label_scope("Overlay", &label_color_magenta(1.0));
+            pass_timer.begin(&mut builder, "Overlay");
+            builder.begin_render_pass(
+                &presenter_shared.swapchain,
+                presenter_shared.vulkan_resource_coordinator.post_processing_render_pass(),
+                (
+                    presenter_shared.vulkan_resource_coordinator.post_processing_framebuffers(),
+                    swapchain_image_index,
+                ),
+            )?;
+            self.append_overlay_commands(
+                persistent_frame_state,
+                backend_shared,
+                presenter_shared,
+                &mut builder,
+                immediate_rendering_frames,
+            )?;
+            builder.end_render_pass()?;
+            pass_timer.end(&mut builder);
+            drop(overlay_span);
+            overlay_scope.end(&mut builder);
+        }
+
+        // User Interface
+        //
+        // Rendered last so that debug UIs and tooling panels are on top of everything else.
+        if renders_textures && presenter_shared.user_interface.has_triangles() {
+            let user_interface_span = jeriya_shared::span!("record user interface commands");
+            let user_interface_scope = builder.begin_label_scope("UserInterface", &label_color_magenta(0.8));
+            pass_timer.begin(&mut builder, "UserInterface");
+            builder.begin_render_pass(
+                &presenter_shared.swapchain,
+                presenter_shared.vulkan_resource_coordinator.post_processing_render_pass(),
+                (
+                    presenter_shared.vulkan_resource_coordinator.post_processing_framebuffers(),
+                    swapchain_image_index,
+                ),
+            )?;
+            self.append_user_interface_commands(persistent_frame_state, backend_shared, presenter_shared, &mut builder)?;
+            builder.end_render_pass()?;
+            pass_timer.end(&mut builder);
+            drop(user_interface_span);
+            user_interface_scope.end(&mut builder);
+        }
+
+        // Output Transform
+        //
+        // The scene, the overlay and the user interface are rendered in SDR. For HDR swapchains, the rendered image is
+        // copied so that it can be sampled and encoded into the color space of the swapchain.
+        let output_transform_sampler = match &self.output_transform_pipeline {
+            Some(_) => Some(
+                presenter_shared
+                    .vulkan_resource_coordinator
+                    .query_sampler(&SamplerConfig::nearest())?,
+            ),
+            None => None,
+        };
+        if let (Some(pipeline), Some(sampler), Some(output_transform_images)) = (
+            &self.output_transform_pipeline,
+            &output_transform_sampler,
+            presenter_shared.vulkan_resource_coordinator.output_transform_images(),
+        ) {
+            let output_transform_span = jeriya_shared::span!("record output transform commands");
+            let output_transform_scope = builder.begin_label_scope("OutputTransform", &label_color_blue(1.0));
+            pass_timer.begin(&mut builder, "OutputTransform");
+            let output_transform_image = output_transform_images.get(&presenter_shared.frame_index);
+            builder.copy_swapchain_image_to_image(&presenter_shared.swapchain, swapchain_image_index, output_transform_image);
+            builder.begin_render_pass(
+                &presenter_shared.swapchain,
+                presenter_shared.vulkan_resource_coordinator.post_processing_render_pass(),
+                (
+                    presenter_shared.vulkan_resource_coordinator.post_processing_framebuffers(),
+                    swapchain_image_index,
+                ),
+            )?;
+            builder.bind_graphics_pipeline(pipeline.as_ref());
+            let push_descriptors = PushDescriptors::builder(&pipeline.descriptor_set_layout)
+                .push_combined_image_sampler(
+                    bindings::OUTPUT_TRANSFORM_INPUT,
+                    output_transform_image.image_view(),
+                    sampler,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                )
+                .build();
+            builder.push_descriptors(sets::PUSH_DESCRIPTORS, PipelineBindPoint::Graphics, &push_descriptors)?;
+            builder.draw_vertices(3, 0);
+            builder.end_render_pass()?;
+            pass_timer.end(&mut builder);
+            drop(output_transform_span);
+            output_transform_scope.end(&mut builder);
+        }
+
+        drop(rendering_span);
+        rendering_scope.end(&mut builder);
+
+        // Frame Capture
+        //
+        // The swapchain image is copied after everything has been rendered into it so that the captured frame is the presented one.
+        if let Some(frame_capture) = &presenter_shared.frame_capture {
+            if let Some(sequence_index) = frame_capture.sequence_index() {
+                let frame_capture_scope = builder.begin_label_scope("FrameCapture", &label_color_yellow(0.8));
+                let extent = presenter_shared.swapchain.extent();
+                let host_visible_buffer = persistent_frame_state.take_frame_capture_buffer(
+                    extent.width as usize * extent.height as usize * 4,
+                    backend_shared,
+                    debug_info!("FrameCapture-HostVisibleBuffer"),
+                )?;
+                builder.copy_swapchain_image_to_buffer(&presenter_shared.swapchain, swapchain_image_index, &host_visible_buffer);
+                persistent_frame_state.pending_frame_capture = Some(PendingFrameCapture {
+                    host_visible_buffer,
+                    sequence_index,
+                    extent,
+                    format: presenter_shared.swapchain.surface_format().format,
+                    sender: frame_capture.sender().clone(),
+                });
+                frame_capture_scope.end(&mut builder);
+            }
+        }
+
+        // Render Target Readback
+        //
+        // Readbacks of render targets that don't exist are dropped so that the receivers are notified.
+        let render_target_readbacks = mem::take(&mut presenter_shared.render_target_readbacks);
+        for (render_target_id, sender) in render_target_readbacks {
+            let Some(render_target) = presenter_shared.vulkan_resource_coordinator.render_target(render_target_id) else {
+                warn!("Readback of the render target {render_target_id:?} is dropped because it doesn't exist");
+                continue;
+            };
+            let extent = render_target.extent();
+            let host_visible_buffer = persistent_frame_state.take_frame_capture_buffer(
+                extent.width as usize * extent.height as usize * 4,
+                backend_shared,
+                debug_info!(format!("RenderTarget-{}-Readback-HostVisibleBuffer", render_target_id.0)),
+            )?;
+            builder.copy_render_target_to_buffer(render_target, &host_visible_buffer);
+            persistent_frame_state.pending_render_target_readbacks.push(PendingFrameCapture {
+                host_visible_buffer,
+                sequence_index: 0,
+                extent,
+                format: RENDER_TARGET_FORMAT,
+                sender,
+            });
+        }
+
+        // Depth Buffer Readback
+        //
+        // Readbacks of regions outside of the window are dropped so that the receivers are notified.
+        let depth_readback_requests = mem::take(&mut presenter_shared.depth_readback_requests);
+        if !depth_readback_requests.is_empty() {
+            let depth_readback_scope = builder.begin_label_scope("DepthReadback", &label_color_yellow(0.6));
+            let extent = presenter_shared.swapchain.extent();
+            let depth_buffer = presenter_shared
+                .vulkan_resource_coordinator
+                .swapchain_depth_buffers()
+                .depth_buffers
+                .get(&presenter_shared.frame_index);
+            for depth_readback_request in depth_readback_requests {
+                let region = depth_readback_request.region;
+                if region.is_empty() || !region.is_within(extent.width, extent.height) {
+                    warn!(
+                        "Readback of the depth buffer region {region:?} is out of bounds of the window with {}x{} pixels",
+                        extent.width, extent.height
+                    );
+                    continue;
+                }
+                // The buffers of the previous readbacks are reused when they are large enough
+                let reusable_buffer_index = persistent_frame_state
+                    .depth_readback_buffers
+                    .iter()
+                    .position(|host_visible_buffer| host_visible_buffer.lock().len() >= region.len());
+                let host_visible_buffer = match reusable_buffer_index {
+                    Some(index) => persistent_frame_state.depth_readback_buffers.swap_remove(index),
+                    None => Arc::new(Mutex::new(HostVisibleBuffer::new(
+                        &backend_shared.device,
+                        &vec![0; region.len()],
+                        BufferUsageFlags::TRANSFER_DST_BIT,
+                        debug_info!("DepthReadback-HostVisibleBuffer"),
+                    )?)),
+                };
+                let rect = vk::Rect2D {
+                    offset: vk::Offset2D {
+                        x: region.x as i32,
+                        y: region.y as i32,
+                    },
+                    extent: vk::Extent2D {
+                        width: region.width,
+                        height: region.height,
+                    },
+                };
+                builder.copy_depth_buffer_to_buffer(depth_buffer, rect, &host_visible_buffer);
+                persistent_frame_state.pending_depth_readbacks.push(PendingDepthReadback {
+                    host_visible_buffer,
+                    format: depth_buffer.format,
+                    region,
+                    sender: depth_readback_request.sender,
+                });
+            }
+            depth_readback_scope.end(&mut builder);
+        }
+
+        if let Some(pipeline_statistics_query_pool) = &persistent_frame_state.pipeline_statistics_query_pool {
+            builder.end_query(pipeline_statistics_query_pool, 0);
+        }
+
+        // Write the frame telemetry data to the buffer
+        let frame_telemetry_span = jeriya_shared::span!("frame telemetry");
+        let frame_telemetry_scope = builder.begin_label_scope("CollectFrameTelemetry", &label_color_yellow(1.0));
+        pass_timer.begin(&mut builder, "CollectFrameTelemetry");
+        {
+            let pipeline = &self.frame_telemetry_compute_pipeline;
+            builder.bind_compute_pipeline(pipeline.as_ref());
+            builder.bottom_to_top_pipeline_barrier();
+            builder.dispatch(1, 1, 1);
+
+            // The frame telemetry compute shader doesn't write the pipeline statistics so that the results can be copied concurrently
+            if let Some(pipeline_statistics_query_pool) = &persistent_frame_state.pipeline_statistics_query_pool {
+                builder.copy_query_pool_results(
+                    pipeline_statistics_query_pool,
+                    0,
+                    &persistent_frame_state.frame_telemetry_buffer,
+                    shader_interface::FrameTelemetry::PIPELINE_STATISTICS_OFFSET,
+                );
+            }
+        }
+        pass_timer.end(&mut builder);
+        persistent_frame_state.frame_telemetry_recorded = true;
+        drop(frame_telemetry_span);
+        frame_telemetry_scope.end(&mut builder);
+
+        // Copy the requested ranges of the buffers to the host
+        let readback_requests = mem::take(&mut presenter_shared.readback_requests);
+        pass_timer.begin(&mut builder, "Readback");
+        self.append_readback_commands(persistent_frame_state, backend_shared, &mut builder, readback_requests)?;
+        pass_timer.end(&mut builder);
+
+        persistent_frame_state.timed_passes = pass_timer.finish();
+
+        builder.end_command_buffer()?;
+
+        drop(command_buffer_span);
+
+        // Insert into Queue
+        let submit_span = jeriya_shared::span!("submit command buffer commands");
+        let mut queues = backend_shared.queue_scheduler.queues();
+        queues.presentation_queue(*window_id).submit_for_rendering_complete(
+            &command_buffer,
+            &persistent_frame_state.image_available_semaphore,
+            &persistent_frame_state.rendering_complete_semaphore,
+            &persistent_frame_state.rendering_complete_fence,
+        )?;
+        drop(queues);
+        drop(submit_span);
+
+        self.command_buffer = Some(command_buffer);
+
+        Ok(())
+    }
+
+    /// Culls the instances, meshlets and point cloud clusters for the camera of the [`PerFrameData`](shader_interface::PerFrameData)
+    ///
+    /// The passes are timed individually when a [`PassTimer`] is given.
+    fn append_culling_commands(
+        &self,
+        frame: &PersistentFrameState,
+        builder: &mut CommandBufferBuilder,
+        per_frame_data_buffer: &HostVisibleBuffer<shader_interface::PerFrameData>,
+        pass_timer: &mut Option<&mut PassTimer>,
+    ) -> crate::Result<()> {
+        let cull_compute_shader_group_count = workgroup_count(
+            frame.rigid_mesh_instance_buffer.high_water_mark() as u32,
+            &self.cull_rigid_mesh_instances_compute_pipeline,
+        );
+
         let culling_span = jeriya_shared::span!("culling");
         let culling_scope = builder.begin_label_scope("Culling", &label_color_magenta(0.8));
 
@@ -248,15 +1486,16 @@ impl CompiledFrameGraph {
 
         let cull_rigid_mesh_instances_span = jeriya_shared::span!("cull rigid mesh instances");
         let cull_rigid_mesh_instances_scope = builder.begin_label_scope("CullRigidMeshInstances", &label_color_red(1.0));
+        begin_timed_pass(pass_timer, builder, "CullRigidMeshInstances");
         {
             // 1. Cull RigidMeshInstances
             let pipeline = &self.cull_rigid_mesh_instances_compute_pipeline;
             builder.bind_compute_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                per_frame_data_buffer,
                 PipelineBindPoint::Compute,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
             )?;
 
             // Make sure that all indirect read operations are finished before writing to the buffer
@@ -268,59 +1507,38 @@ impl CompiledFrameGraph {
             // that will be rendered with the meshlet representation.
             let clear_bytes_count = mem::size_of::<DispatchIndirectCommand>() + mem::size_of::<u32>();
             builder.transfer_to_transfer_command_barrier();
-            builder.fill_buffer(&persistent_frame_state.visible_rigid_mesh_instances, 0, clear_bytes_count as u64, 0);
+            builder.fill_buffer(&frame.visible_rigid_mesh_instances, 0, clear_bytes_count as u64, 0);
 
             // Clear counter for the visible rigid mesh instances that will be rendered with the
             // simple mesh representation.
             let clear_bytes_count = mem::size_of::<u32>();
             builder.transfer_to_transfer_command_barrier();
-            builder.fill_buffer(
-                &persistent_frame_state.visible_rigid_mesh_instances_simple_buffer,
-                0,
-                clear_bytes_count as u64,
-                0,
-            );
+            builder.fill_buffer(&frame.visible_rigid_mesh_instances_simple_buffer, 0, clear_bytes_count as u64, 0);
 
             // Dispatch compute shader for every rigid mesh instance
             builder.transfer_to_compute_pipeline_barrier();
             builder.dispatch(cull_compute_shader_group_count, 1, 1);
         }
+        end_timed_pass(pass_timer, builder);
         drop(cull_rigid_mesh_instances_span);
-        cull_rigid_mesh_instances_scope.end(&mut builder);
-
-        // {
-        //     let mut queues = backend_shared.queue_scheduler.queues();
-        //     let buffer = self
-        //         .visible_rigid_mesh_instances
-        //         .read_into_new_buffer_and_wait(queues.presentation_queue(*window_id), &command_pool)
-        //         .unwrap();
-        //     let work_group_x = buffer.get_memory_unaligned_index(0).unwrap();
-        //     let work_group_y = buffer.get_memory_unaligned_index(1).unwrap();
-        //     let work_group_z = buffer.get_memory_unaligned_index(2).unwrap();
-        //     let count = buffer.get_memory_unaligned_index(4).unwrap();
-        //     eprintln!("instances: {count}, work_group: ({work_group_x}, {work_group_y}, {work_group_z})",);
-        // }
+        cull_rigid_mesh_instances_scope.end(builder);
 
         // Cull Meshlets
         let cull_meshlets_span = jeriya_shared::span!("cull meshlets");
         let cull_meshlets_scope = builder.begin_label_scope("CullMeshlets", &label_color_red(0.8));
+        begin_timed_pass(pass_timer, builder, "CullMeshlets");
         {
             let pipeline = &self.cull_rigid_mesh_meshlets_compute_pipeline;
             builder.bind_compute_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                per_frame_data_buffer,
                 PipelineBindPoint::Compute,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
             )?;
 
             // Clear counter for the visible meshlets
-            builder.fill_buffer(
-                &persistent_frame_state.visible_rigid_mesh_meshlets,
-                0,
-                mem::size_of::<u32>() as u64,
-                0,
-            );
+            builder.fill_buffer(&frame.visible_rigid_mesh_meshlets, 0, mem::size_of::<u32>() as u64, 0);
 
             builder.transfer_to_indirect_command_barrier();
             builder.transfer_to_compute_pipeline_barrier();
@@ -328,25 +1546,12 @@ impl CompiledFrameGraph {
             builder.compute_to_compute_pipeline_barrier();
 
             // Dispatch compute shader for every visible rigid mesh instance
-            builder.dispatch_indirect(&persistent_frame_state.visible_rigid_mesh_instances, 0);
+            builder.dispatch_indirect(&frame.visible_rigid_mesh_instances, 0);
             builder.compute_to_indirect_command_pipeline_barrier();
         }
+        end_timed_pass(pass_timer, builder);
         drop(cull_meshlets_span);
-        cull_meshlets_scope.end(&mut builder);
-
-        // {
-        //     let mut queues = backend_shared.queue_scheduler.queues();
-        //     let buffer = self
-        //         .visible_rigid_mesh_meshlets
-        //         .read_into_new_buffer_and_wait(queues.presentation_queue(*window_id), &command_pool)
-        //         .unwrap();
-        //     let count = buffer.get_memory_unaligned_index(0).unwrap();
-        //     let offset = 1 + backend_shared.renderer_config.maximum_visible_rigid_mesh_meshlets * 4;
-        //     let list = (0..10)
-        //         .map(|i| buffer.get_memory_unaligned_index(offset + i).unwrap())
-        //         .collect::<Vec<_>>();
-        //     eprintln!("meshlets: {count} -> {list:?}");
-        // }
+        cull_meshlets_scope.end(builder);
 
         // Point Cloud Culling
         //
@@ -356,82 +1561,59 @@ impl CompiledFrameGraph {
         // is written to the front of the buffer as in the culling of the rigid mesh instances.
         let cull_point_cloud_instances_span = jeriya_shared::span!("cull point cloud instances");
         let cull_point_cloud_instances_scope = builder.begin_label_scope("CullPointCloudInstances", &label_color_blue(1.0));
+        begin_timed_pass(pass_timer, builder, "CullPointCloudInstances");
         {
             let pipeline = &self.cull_point_cloud_instances_compute_pipeline;
             builder.bind_compute_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                per_frame_data_buffer,
                 PipelineBindPoint::Compute,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
             )?;
 
             // Clear counter for the visible point cloud instances without clusters
-            builder.fill_buffer(
-                &persistent_frame_state.visible_point_cloud_instances_simple,
-                0,
-                mem::size_of::<u32>() as u64,
-                0,
-            );
+            builder.fill_buffer(&frame.visible_point_cloud_instances_simple, 0, mem::size_of::<u32>() as u64, 0);
             builder.transfer_to_compute_pipeline_barrier();
 
             // Clear counter for the visible point cloud instances with clusters
             let offset = mem::size_of::<DispatchIndirectCommand>() as u64;
-            builder.fill_buffer(
-                &persistent_frame_state.visible_point_cloud_instances,
-                offset,
-                mem::size_of::<u32>() as u64,
-                0,
-            );
+            builder.fill_buffer(&frame.visible_point_cloud_instances, offset, mem::size_of::<u32>() as u64, 0);
             builder.transfer_to_compute_pipeline_barrier();
 
             // Dispatch
-            let cull_point_cloud_instances_group_count = {
-                const LOCAL_SIZE_X: u32 = 128;
-                (persistent_frame_state.point_cloud_instance_buffer.high_water_mark() as u32 + LOCAL_SIZE_X - 1) / LOCAL_SIZE_X
-            };
+            let cull_point_cloud_instances_group_count = workgroup_count(
+                frame.point_cloud_instance_buffer.high_water_mark() as u32,
+                &self.cull_point_cloud_instances_compute_pipeline,
+            );
             builder.transfer_to_indirect_command_barrier();
             builder.transfer_to_compute_pipeline_barrier();
             builder.dispatch(cull_point_cloud_instances_group_count, 1, 1);
 
             builder.compute_to_indirect_command_pipeline_barrier();
         }
+        end_timed_pass(pass_timer, builder);
         drop(cull_point_cloud_instances_span);
-        cull_point_cloud_instances_scope.end(&mut builder);
-
-        // {
-        //     let mut queues = backend_shared.queue_scheduler.queues();
-        //     let buffer = self
-        //         .visible_point_cloud_clusters
-        //         .read_into_new_buffer_and_wait(queues.presentation_queue(*window_id), &command_pool)
-        //         .unwrap();
-        //     let count = buffer.get_memory_unaligned_index(0).unwrap();
-        //     let offset = 4 * backend_shared.renderer_config.maximum_number_of_visible_point_cloud_clusters;
-        //     let list = (0..32)
-        //         .map(|i| buffer.get_memory_unaligned_index(offset + i).unwrap())
-        //         .collect::<Vec<_>>();
-        //     eprintln!("point_clouds: {count} -> {list:?}");
-        // }
+        cull_point_cloud_instances_scope.end(builder);
 
         let cull_point_cloud_clusters_span = jeriya_shared::span!("cull point cloud clusters");
         let cull_point_cloud_clusters_scope = builder.begin_label_scope("CullPointCloudClusters", &label_color_blue(0.9));
+        begin_timed_pass(pass_timer, builder, "CullPointCloudClusters");
         {
             let pipeline = &self.cull_point_cloud_clusters_compute_pipeline;
             builder.bind_compute_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                per_frame_data_buffer,
                 PipelineBindPoint::Compute,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
             )?;
 
             // Clear counter for the visible point cloud clusters
-            builder.fill_buffer(
-                &persistent_frame_state.visible_point_cloud_clusters,
-                0,
-                mem::size_of::<u32>() as u64,
-                0,
-            );
+            builder.fill_buffer(&frame.visible_point_cloud_clusters, 0, mem::size_of::<u32>() as u64, 0);
+
+            // Clear counter for the point cloud page requests
+            builder.fill_buffer(&frame.point_cloud_page_requests_buffer, 0, mem::size_of::<u32>() as u64, 0);
 
             // Dispatch
             builder.transfer_to_compute_pipeline_barrier();
@@ -440,186 +1622,255 @@ impl CompiledFrameGraph {
             builder.compute_to_compute_pipeline_barrier();
 
             // Dispatch compute shader for culling the point cloud clusters
-            builder.dispatch_indirect(&persistent_frame_state.visible_point_cloud_instances, 0);
+            builder.dispatch_indirect(&frame.visible_point_cloud_instances, 0);
             builder.compute_to_indirect_command_pipeline_barrier();
 
+            end_timed_pass(pass_timer, builder);
             drop(cull_point_cloud_clusters_span);
-            cull_point_cloud_clusters_scope.end(&mut builder);
+            cull_point_cloud_clusters_scope.end(builder);
 
             // This barrier exists because the device local debug lines buffer is used
             // in the render pass. The barrier shouldn't be active in production code.
             builder.bottom_to_top_pipeline_barrier();
         }
         drop(culling_span);
-        culling_scope.end(&mut builder);
+        culling_scope.end(builder);
 
-        let rendering_span = jeriya_shared::span!("rendering");
-        let rendering_scope = builder.begin_label_scope("Rendering", &label_color_green(0.8));
+        Ok(())
+    }
 
-        // Render Pass
-        let swapchain_image_index = presenter_shared
-            .frame_index
-            .swapchain_index()
-            .expect("swapchain index must be set before rendering");
-        builder.begin_render_pass(
-            &presenter_shared.swapchain,
-            presenter_shared.vulkan_resource_coordinator.swapchain_render_pass(),
-            (
-                presenter_shared.vulkan_resource_coordinator.swapchain_framebuffers(),
-                swapchain_image_index,
-            ),
+    /// Renders the selected rigid mesh instances into the stencil buffer
+    ///
+    /// Every selected instance is drawn with its simple mesh representation and its GPU index as first instance.
+    /// Must be recorded in the render pass of the scene with the `per_frame_data_buffer` of its camera.
+    fn append_selection_mask_commands(
+        frame: &PersistentFrameState,
+        backend_shared: &BackendShared,
+        builder: &mut CommandBufferBuilder,
+        pipeline: &Arc<GenericGraphicsPipeline>,
+        per_frame_data_buffer: &HostVisibleBuffer<shader_interface::PerFrameData>,
+        push_constants: &[PushConstants],
+    ) -> crate::Result<()> {
+        let selection_mask_span = jeriya_shared::span!("record selection mask commands");
+        let selection_mask_scope = builder.begin_label_scope("SelectionMask", &label_color_yellow(1.0));
+
+        builder.bind_graphics_pipeline(pipeline.as_ref());
+        frame.push_descriptors_with_per_frame_data(
+            per_frame_data_buffer,
+            PipelineBindPoint::Graphics,
+            &pipeline.descriptor_set_layout,
+            builder,
         )?;
+        builder.push_constants(push_constants)?;
+
+        let mesh_attributes_buffer = backend_shared.mesh_attributes_buffer.lock();
+        for &rigid_mesh_instance_index in &frame.selected_rigid_mesh_instances {
+            let rigid_mesh_instance = frame
+                .rigid_mesh_instance_buffer
+                .get(&GpuIndexAllocation::<RigidMeshInstance>::new_unchecked(rigid_mesh_instance_index))?;
+            let rigid_mesh = frame.rigid_mesh_buffer.get(&GpuIndexAllocation::<RigidMesh>::new_unchecked(
+                rigid_mesh_instance.rigid_mesh_index as usize,
+            ))?;
+
+            // The MeshAttributes can only be rendered when their upload has finished
+            let Ok(mesh_attributes_index) = usize::try_from(rigid_mesh.mesh_attributes_index) else {
+                continue;
+            };
+            let mesh_attributes_active = frame
+                .mesh_attributes_active_buffer
+                .get(&GpuIndexAllocation::<MeshAttributes>::new_unchecked(mesh_attributes_index))?;
+            if mesh_attributes_active == 0 {
+                continue;
+            }
+
+            // When the mesh has indices, the vertex shader runs per index instead of per vertex
+            let mesh_attributes = mesh_attributes_buffer.get_memory_unaligned_index(mesh_attributes_index)?;
+            let vertex_count = match mesh_attributes.indices_len {
+                0 => mesh_attributes.vertex_positions_len,
+                indices_len => indices_len,
+            };
+            builder.draw_vertices_of_instance(vertex_count as u32, rigid_mesh_instance_index as u32);
+        }
+        drop(mesh_attributes_buffer);
+
+        drop(selection_mask_span);
+        selection_mask_scope.end(builder);
+        Ok(())
+    }
 
+    /// Renders the visible instances and point cloud clusters with the pipelines of the [`ScenePass`]
+    ///
+    /// The passes are timed individually when a [`PassTimer`] is given.
+    fn append_scene_commands(
+        &self,
+        frame: &PersistentFrameState,
+        backend_shared: &BackendShared,
+        presenter_shared: &PresenterShared,
+        builder: &mut CommandBufferBuilder,
+        scene_pass: ScenePass,
+        pass_timer: &mut Option<&mut PassTimer>,
+    ) -> crate::Result<()> {
         // Render with IndirectSimpleGraphicsPipeline
         let indirect_simple_span = jeriya_shared::span!("record indirect simple commands");
         let indirect_simple_scope = builder.begin_label_scope("IndirectSimple", &label_color_red(1.0));
+        begin_timed_pass(pass_timer, builder, "IndirectSimple");
         {
-            let pipeline = &self.indirect_simple_graphics_pipeline;
+            let pipeline = &scene_pass.pipelines.indirect_simple;
             builder.bind_graphics_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                scene_pass.per_frame_data_buffer,
                 PipelineBindPoint::Graphics,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
+            )?;
+            builder.bind_bindless_textures(
+                PipelineBindPoint::Graphics,
+                presenter_shared.vulkan_resource_coordinator.bindless_textures(),
             )?;
+            builder.push_constants(scene_pass.push_constants)?;
             builder.draw_indirect_count(
-                &persistent_frame_state.visible_rigid_mesh_instances_simple_buffer,
+                &frame.visible_rigid_mesh_instances_simple_buffer,
                 mem::size_of::<u32>() as u64,
-                &persistent_frame_state.visible_rigid_mesh_instances_simple_buffer,
+                &frame.visible_rigid_mesh_instances_simple_buffer,
                 0,
-                persistent_frame_state.rigid_mesh_instance_buffer.high_water_mark(),
+                frame.rigid_mesh_instance_buffer.high_water_mark(),
             );
         }
+        end_timed_pass(pass_timer, builder);
         drop(indirect_simple_span);
-        indirect_simple_scope.end(&mut builder);
+        indirect_simple_scope.end(builder);
 
         // Render with IndirectMeshletGraphicsPipeline
         let indirect_meshlet_span = jeriya_shared::span!("record indirect meshlet commands");
         let indirect_meshlet_scope = builder.begin_label_scope("IndirectMeshlet", &label_color_red(0.9));
+        begin_timed_pass(pass_timer, builder, "IndirectMeshlet");
         {
-            let pipeline = &self.indirect_meshlet_graphics_pipeline;
+            let pipeline = &scene_pass.pipelines.indirect_meshlet;
             builder.bind_graphics_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                scene_pass.per_frame_data_buffer,
                 PipelineBindPoint::Graphics,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
+            )?;
+            builder.bind_bindless_textures(
+                PipelineBindPoint::Graphics,
+                presenter_shared.vulkan_resource_coordinator.bindless_textures(),
             )?;
+            builder.push_constants(scene_pass.push_constants)?;
             builder.draw_indirect_count(
-                &persistent_frame_state.visible_rigid_mesh_meshlets,
+                &frame.visible_rigid_mesh_meshlets,
                 mem::size_of::<u32>() as u64,
-                &persistent_frame_state.visible_rigid_mesh_meshlets,
+                &frame.visible_rigid_mesh_meshlets,
                 0,
                 backend_shared.static_meshlet_buffer.lock().len(),
             );
         }
+        end_timed_pass(pass_timer, builder);
         drop(indirect_meshlet_span);
-        indirect_meshlet_scope.end(&mut builder);
+        indirect_meshlet_scope.end(builder);
 
         // Render Point Clouds
         let point_cloud_span = jeriya_shared::span!("record point cloud commands");
         let point_cloud_scope = builder.begin_label_scope("PointCloud", &label_color_blue(1.0));
+        begin_timed_pass(pass_timer, builder, "PointCloud");
         {
-            let pipeline = &self.point_cloud_graphics_pipeline;
+            let pipeline = &scene_pass.pipelines.point_cloud;
             builder.bind_graphics_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                scene_pass.per_frame_data_buffer,
                 PipelineBindPoint::Graphics,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
             )?;
+            builder.push_constants(scene_pass.push_constants)?;
             builder.draw_indirect_count(
-                &persistent_frame_state.visible_point_cloud_instances_simple,
+                &frame.visible_point_cloud_instances_simple,
                 mem::size_of::<u32>() as u64,
-                &persistent_frame_state.visible_point_cloud_instances_simple,
+                &frame.visible_point_cloud_instances_simple,
                 0,
-                persistent_frame_state.point_cloud_instance_buffer.high_water_mark(),
+                frame.point_cloud_instance_buffer.high_water_mark(),
             );
         }
+        end_timed_pass(pass_timer, builder);
         drop(point_cloud_span);
-        point_cloud_scope.end(&mut builder);
+        point_cloud_scope.end(builder);
 
         // Render with PointCloudClusterGraphicsPipeline
         let indirect_meshlet_span = jeriya_shared::span!("record point cloud cluster commands");
         let indirect_meshlet_scope = builder.begin_label_scope("PointCloudCluster", &label_color_blue(0.8));
+        begin_timed_pass(pass_timer, builder, "PointCloudCluster");
         {
-            let pipeline = &self.point_cloud_clusters_graphics_pipeline;
+            let pipeline = &scene_pass.pipelines.point_cloud_clusters;
             builder.bind_graphics_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
+            frame.push_descriptors_with_per_frame_data(
+                scene_pass.per_frame_data_buffer,
                 PipelineBindPoint::Graphics,
                 &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
+                builder,
             )?;
+            builder.push_constants(scene_pass.push_constants)?;
             builder.draw_indirect_count(
-                &persistent_frame_state.visible_point_cloud_clusters,
+                &frame.visible_point_cloud_clusters,
                 std::mem::size_of::<u32>() as u64,
-                &persistent_frame_state.visible_point_cloud_clusters,
+                &frame.visible_point_cloud_clusters,
                 0,
                 backend_shared.renderer_config.maximum_number_of_visible_point_cloud_clusters,
             );
         }
+        end_timed_pass(pass_timer, builder);
         drop(indirect_meshlet_span);
-        indirect_meshlet_scope.end(&mut builder);
+        indirect_meshlet_scope.end(builder);
 
-        // Render with ImmediateRenderingPipeline
-        self.append_immediate_rendering_commands(persistent_frame_state, backend_shared, &mut builder, immediate_rendering_frames)?;
+        Ok(())
+    }
 
-        // Render device local debug lines
-        let device_local_debug_lines_span = jeriya_shared::span!("record device local debug lines commands");
-        let device_local_debug_lines_scope = builder.begin_label_scope("DeviceLocalDebugLines", &label_color_yellow(0.8));
-        {
-            let pipeline = &self.device_local_debug_lines_pipeline;
-            builder.bind_graphics_pipeline(pipeline.as_ref());
-            persistent_frame_state.push_descriptors(
-                PipelineBindPoint::Graphics,
-                &pipeline.descriptor_set_layout,
-                backend_shared,
-                &mut builder,
-            )?;
-            builder.draw_indirect(
-                &persistent_frame_state.device_local_debug_lines_buffer,
-                mem::size_of::<u32>() as u64,
-                1,
-            );
+    fn append_readback_commands(
+        &self,
+        frame: &mut PersistentFrameState,
+        backend_shared: &BackendShared,
+        command_buffer_builder: &mut CommandBufferBuilder,
+        readback_requests: Vec<ReadbackRequest>,
+    ) -> crate::Result<()> {
+        if readback_requests.is_empty() {
+            return Ok(());
         }
-        drop(device_local_debug_lines_span);
-        device_local_debug_lines_scope.end(&mut builder);
-
-        builder.end_render_pass()?;
 
-        drop(rendering_span);
-        rendering_scope.end(&mut builder);
-
-        // Write the frame telemetry data to the buffer
-        let frame_telemetry_span = jeriya_shared::span!("frame telemetry");
-        let frame_telemetry_scope = builder.begin_label_scope("CollectFrameTelemetry", &label_color_yellow(1.0));
-        {
-            let pipeline = &self.frame_telemetry_compute_pipeline;
-            builder.bind_compute_pipeline(pipeline.as_ref());
-            builder.bottom_to_top_pipeline_barrier();
-            builder.dispatch(1, 1, 1);
+        let span = jeriya_shared::span!("readback commands");
+        let scope = command_buffer_builder.begin_label_scope("Readback", &label_color_yellow(0.6));
+
+        // Make sure that all shaders have finished writing to the buffers
+        command_buffer_builder.bottom_to_top_pipeline_barrier();
+
+        for readback_request in readback_requests {
+            let buffer = frame.readback_buffer(readback_request.buffer);
+            let byte_offset = readback_request.range.start * mem::size_of::<u32>();
+            let byte_size = readback_request.range.len() * mem::size_of::<u32>();
+            if byte_size == 0 || byte_offset + byte_size > buffer.byte_size() {
+                // Dropping the request notifies the receiver that the readback was aborted
+                warn!(
+                    "Readback of {:?} with range {:?} is out of bounds of the buffer with {} bytes",
+                    readback_request.buffer,
+                    readback_request.range,
+                    buffer.byte_size()
+                );
+                continue;
+            }
+            let host_visible_buffer = Arc::new(Mutex::new(HostVisibleBuffer::new(
+                &backend_shared.device,
+                &vec![0; readback_request.range.len()],
+                BufferUsageFlags::TRANSFER_DST_BIT,
+                debug_info!(format!("Readback-{:?}-HostVisibleBuffer", readback_request.buffer)),
+            )?));
+            command_buffer_builder.copy_buffer_range_from_device_to_host(buffer, byte_offset, &host_visible_buffer, 0, byte_size);
+            frame.pending_readbacks.push(PendingReadback {
+                host_visible_buffer,
+                sender: readback_request.sender,
+            });
         }
-        drop(frame_telemetry_span);
-        frame_telemetry_scope.end(&mut builder);
-
-        builder.end_command_buffer()?;
-
-        drop(command_buffer_span);
-
-        // Insert into Queue
-        let submit_span = jeriya_shared::span!("submit command buffer commands");
-        let mut queues = backend_shared.queue_scheduler.queues();
-        queues.presentation_queue(*window_id).submit_for_rendering_complete(
-            &command_buffer,
-            &persistent_frame_state.image_available_semaphore,
-            &persistent_frame_state.rendering_complete_semaphore,
-            &persistent_frame_state.rendering_complete_fence,
-        )?;
-        drop(queues);
-        drop(submit_span);
 
-        self.command_buffer = Some(command_buffer);
+        drop(span);
+        scope.end(command_buffer_builder);
 
         Ok(())
     }
@@ -627,7 +1878,7 @@ impl CompiledFrameGraph {
     fn append_immediate_rendering_commands(
         &self,
         frame: &PersistentFrameState,
-        backend_shared: &BackendShared,
+        presenter_shared: &PresenterShared,
         command_buffer_builder: &mut CommandBufferBuilder,
         immediate_rendering_frames: &BTreeMap<&'static str, ImmediateRenderingFrameTask>,
     ) -> crate::Result<()> {
@@ -638,29 +1889,6 @@ impl CompiledFrameGraph {
         let span = jeriya_shared::span!("immediate rendering commands");
         let scope = command_buffer_builder.begin_label_scope("ImmediateRendering", &label_color_yellow(1.0));
 
-        // Collect vertex attributes for all immediate rendering requests
-        let mut data = Vec::new();
-        for task in immediate_rendering_frames.values() {
-            for command_buffer in &task.command_buffers {
-                for command in command_buffer.commands() {
-                    match command {
-                        ImmediateCommand::Matrix(..) => {}
-                        ImmediateCommand::LineList(line_list) => data.extend_from_slice(line_list.positions()),
-                        ImmediateCommand::LineStrip(line_strip) => data.extend_from_slice(line_strip.positions()),
-                        ImmediateCommand::TriangleList(triangle_list) => data.extend_from_slice(triangle_list.positions()),
-                        ImmediateCommand::TriangleStrip(triangle_strip) => data.extend_from_slice(triangle_strip.positions()),
-                    }
-                }
-            }
-        }
-        let vertex_buffer = Arc::new(HostVisibleBuffer::new(
-            &backend_shared.device,
-            data.as_slice(),
-            BufferUsageFlags::VERTEX_BUFFER,
-            debug_info!("Immediate-VertexBuffer"),
-        )?);
-        command_buffer_builder.bind_vertex_buffers(0, &vertex_buffer);
-
         plot_with_index!(
             "immediate_rendering_commands_on_presenter_",
             frame.presenter_index,
@@ -672,98 +1900,219 @@ impl CompiledFrameGraph {
         );
 
         // Append the draw commands
-        let mut first_vertex = 0;
         let mut last_matrix = Matrix4::identity();
+        let extent = presenter_shared.swapchain.extent();
         for task in immediate_rendering_frames.values() {
             for command_buffer in &task.command_buffers {
+                let label = command_buffer
+                    .as_debug_info()
+                    .name
+                    .clone()
+                    .unwrap_or(Cow::Borrowed("ImmediateCommandBuffer"));
+                let command_buffer_scope = command_buffer_builder.begin_label_scope(label, &label_color_yellow(0.9));
+                let vertex_buffers = presenter_shared
+                    .immediate_vertex_buffers
+                    .get(command_buffer)
+                    .expect("vertex buffers of the command buffer must have been created before the render pass");
+                vertex_buffers.bind(command_buffer_builder);
+                let mut text_vertex_counts = vertex_buffers.text_vertex_counts().iter();
+                let mut first_vertex = 0;
+                let mut first_index = 0;
                 let mut last_topology = None;
+                let mut last_space = ImmediateSpace::World;
                 for command in command_buffer.commands() {
+                    // The orthographic matrix of the screen spaces is applied after the matrix of the command buffer
+                    let depth_test = immediate_depth_test(command);
+                    let (pipelines, primitive_matrix) = match last_space {
+                        ImmediateSpace::World if depth_test => (&self.immediate_graphics_pipelines_world, last_matrix),
+                        ImmediateSpace::World => (&self.immediate_graphics_pipelines_world_without_depth_test, last_matrix),
+                        ImmediateSpace::Pixels => (
+                            &self.immediate_graphics_pipelines_screen,
+                            overlay_projection_matrix(extent.width, extent.height) * last_matrix,
+                        ),
+                        ImmediateSpace::Normalized => (
+                            &self.immediate_graphics_pipelines_screen,
+                            overlay_projection_matrix(1, 1) * last_matrix,
+                        ),
+                    };
                     match command {
                         ImmediateCommand::Matrix(matrix) => last_matrix = *matrix,
+                        ImmediateCommand::Space(space) => {
+                            last_space = *space;
+                            // The pipelines of the new space have to be bound by the next command
+                            last_topology = None;
+                        }
+                        ImmediateCommand::PointList(point_list) => {
+                            if last_topology != Some((PrimitiveTopology::PointList, depth_test)) {
+                                let pipeline = &pipelines.point_list;
+                                command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
+                                frame.push_descriptors(
+                                    PipelineBindPoint::Graphics,
+                                    &pipeline.descriptor_set_layout,
+                                    command_buffer_builder,
+                                )?;
+                            }
+                            let push_constants = PushConstants {
+                                color: point_list.config().color,
+                                matrix: primitive_matrix,
+                                ..Default::default()
+                            };
+                            command_buffer_builder.push_constants(&[push_constants])?;
+                            let vertex_count = point_list.positions().len() * vertex_buffers.vertices_per_point();
+                            command_buffer_builder.draw_vertices(vertex_count as u32, first_vertex as u32);
+                            first_vertex += vertex_count;
+                            last_topology = Some((PrimitiveTopology::PointList, depth_test));
+                        }
                         ImmediateCommand::LineList(line_list) => {
-                            if !matches!(last_topology, Some(PrimitiveTopology::LineList)) {
-                                let pipeline = &self.immediate_graphics_pipeline_line_list;
+                            if last_topology != Some((PrimitiveTopology::LineList, depth_test)) {
+                                let pipeline = &pipelines.line_list;
                                 command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
                                 frame.push_descriptors(
                                     PipelineBindPoint::Graphics,
                                     &pipeline.descriptor_set_layout,
-                                    backend_shared,
                                     command_buffer_builder,
                                 )?;
                             }
                             let push_constants = PushConstants {
                                 color: line_list.config().color,
-                                matrix: last_matrix,
+                                matrix: primitive_matrix,
+                                ..Default::default()
                             };
                             command_buffer_builder.push_constants(&[push_constants])?;
                             command_buffer_builder.set_line_width(line_list.config().line_width);
                             command_buffer_builder.draw_vertices(line_list.positions().len() as u32, first_vertex as u32);
                             first_vertex += line_list.positions().len();
-                            last_topology = Some(PrimitiveTopology::LineList);
+                            last_topology = Some((PrimitiveTopology::LineList, depth_test));
                         }
                         ImmediateCommand::LineStrip(line_strip) => {
-                            if !matches!(last_topology, Some(PrimitiveTopology::LineStrip)) {
-                                let pipeline = &self.immediate_graphics_pipeline_line_strip;
+                            if last_topology != Some((PrimitiveTopology::LineStrip, depth_test)) {
+                                let pipeline = &pipelines.line_strip;
                                 command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
                                 frame.push_descriptors(
                                     PipelineBindPoint::Graphics,
                                     &pipeline.descriptor_set_layout,
-                                    backend_shared,
                                     command_buffer_builder,
                                 )?;
                             }
                             let push_constants = PushConstants {
                                 color: line_strip.config().color,
-                                matrix: last_matrix,
+                                matrix: primitive_matrix,
+                                ..Default::default()
                             };
                             command_buffer_builder.push_constants(&[push_constants])?;
                             command_buffer_builder.set_line_width(line_strip.config().line_width);
                             command_buffer_builder.draw_vertices(line_strip.positions().len() as u32, first_vertex as u32);
                             first_vertex += line_strip.positions().len();
-                            last_topology = Some(PrimitiveTopology::LineStrip);
+                            last_topology = Some((PrimitiveTopology::LineStrip, depth_test));
                         }
                         ImmediateCommand::TriangleList(triangle_list) => {
-                            if !matches!(last_topology, Some(PrimitiveTopology::TriangleList)) {
-                                let pipeline = &self.immediate_graphics_pipeline_triangle_list;
+                            if last_topology != Some((PrimitiveTopology::TriangleList, depth_test)) {
+                                let pipeline = &pipelines.triangle_list;
                                 command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
                                 frame.push_descriptors(
                                     PipelineBindPoint::Graphics,
                                     &pipeline.descriptor_set_layout,
-                                    backend_shared,
                                     command_buffer_builder,
                                 )?;
                             }
                             let push_constants = PushConstants {
                                 color: triangle_list.config().color,
-                                matrix: last_matrix,
+                                matrix: primitive_matrix,
+                                ..Default::default()
                             };
                             command_buffer_builder.push_constants(&[push_constants])?;
                             command_buffer_builder.draw_vertices(triangle_list.positions().len() as u32, first_vertex as u32);
                             first_vertex += triangle_list.positions().len();
-                            last_topology = Some(PrimitiveTopology::TriangleList);
+                            last_topology = Some((PrimitiveTopology::TriangleList, depth_test));
                         }
                         ImmediateCommand::TriangleStrip(triangle_strip) => {
-                            if !matches!(last_topology, Some(PrimitiveTopology::TriangleStrip)) {
-                                let pipeline = &self.immediate_graphics_pipeline_triangle_strip;
+                            if last_topology != Some((PrimitiveTopology::TriangleStrip, depth_test)) {
+                                let pipeline = &pipelines.triangle_strip;
                                 command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
                                 frame.push_descriptors(
                                     PipelineBindPoint::Graphics,
                                     &pipeline.descriptor_set_layout,
-                                    backend_shared,
                                     command_buffer_builder,
                                 )?;
                             }
                             let push_constants = PushConstants {
                                 color: triangle_strip.config().color,
-                                matrix: last_matrix,
+                                matrix: primitive_matrix,
+                                ..Default::default()
                             };
                             command_buffer_builder.push_constants(&[push_constants])?;
                             command_buffer_builder.draw_vertices(triangle_strip.positions().len() as u32, first_vertex as u32);
                             first_vertex += triangle_strip.positions().len();
-                            last_topology = Some(PrimitiveTopology::TriangleStrip);
+                            last_topology = Some((PrimitiveTopology::TriangleStrip, depth_test));
+                        }
+                        ImmediateCommand::IndexedTriangleList(indexed_triangle_list) => {
+                            if last_topology != Some((PrimitiveTopology::TriangleList, depth_test)) {
+                                let pipeline = &pipelines.triangle_list;
+                                command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
+                                frame.push_descriptors(
+                                    PipelineBindPoint::Graphics,
+                                    &pipeline.descriptor_set_layout,
+                                    command_buffer_builder,
+                                )?;
+                            }
+                            let push_constants = PushConstants {
+                                color: indexed_triangle_list.config().color,
+                                matrix: primitive_matrix,
+                                ..Default::default()
+                            };
+                            command_buffer_builder.push_constants(&[push_constants])?;
+                            command_buffer_builder.draw_indexed(
+                                indexed_triangle_list.indices().len() as u32,
+                                first_index as u32,
+                                first_vertex as i32,
+                            );
+                            first_vertex += indexed_triangle_list.positions().len();
+                            first_index += indexed_triangle_list.indices().len();
+                            last_topology = Some((PrimitiveTopology::TriangleList, depth_test));
+                        }
+                        ImmediateCommand::Text(text) => {
+                            let vertex_count = *text_vertex_counts
+                                .next()
+                                .expect("vertex count of the text must have been collected");
+                            let Some(textured_graphics_pipelines) = &self.textured_graphics_pipelines else {
+                                first_vertex += vertex_count;
+                                continue;
+                            };
+                            let texture_index = presenter_shared
+                                .immediate_textures
+                                .font_texture_index(text.font())
+                                .expect("font atlas must have been uploaded before the render pass");
+                            let pipeline = match text.config().space {
+                                TextSpace::World => &textured_graphics_pipelines.text_world,
+                                TextSpace::Screen => &textured_graphics_pipelines.text_screen,
+                                TextSpace::Billboard => &textured_graphics_pipelines.text_billboard,
+                            };
+                            command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
+                            frame.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, command_buffer_builder)?;
+                            command_buffer_builder.bind_bindless_textures(
+                                PipelineBindPoint::Graphics,
+                                presenter_shared.vulkan_resource_coordinator.bindless_textures(),
+                            )?;
+                            // The glyphs of a label are placed around its position which is moved into the matrix
+                            let matrix = match text.config().space {
+                                TextSpace::World | TextSpace::Screen => last_matrix,
+                                TextSpace::Billboard => last_matrix * Matrix4::new_translation(text.position()),
+                            };
+                            let push_constants = PushConstants {
+                                color: text.config().color,
+                                matrix,
+                                texture_index,
+                                ..Default::default()
+                            };
+                            command_buffer_builder.push_constants(&[push_constants])?;
+                            command_buffer_builder.draw_vertices(vertex_count as u32, first_vertex as u32);
+                            first_vertex += vertex_count;
+                            // The text pipelines don't match any of the topologies of the other commands
+                            last_topology = None;
                         }
                     }
                 }
+                command_buffer_scope.end(command_buffer_builder);
             }
         }
 
@@ -772,4 +2121,418 @@ impl CompiledFrameGraph {
 
         Ok(())
     }
+
+    /// Records the [`CustomPass`](crate::CustomPass)es at the given `position` together with the barriers that are derived from their accesses
+    fn append_custom_passes(
+        &self,
+        position: CustomPassPosition,
+        persistent_frame_state: &PersistentFrameState,
+        backend_shared: &BackendShared,
+        presenter_shared: &PresenterShared,
+        builder: &mut CommandBufferBuilder,
+        pass_timer: &mut PassTimer,
+    ) -> crate::Result<()> {
+        let custom_passes = presenter_shared
+            .custom_passes
+            .iter()
+            .filter(|registered_custom_pass| registered_custom_pass.position == position)
+            .cloned()
+            .collect::<Vec<_>>();
+        let user_compute_pipelines = self
+            .user_compute_pipelines
+            .iter()
+            .filter(|(registered_user_compute_pass, _)| registered_user_compute_pass.user_compute_pass.position == position)
+            .collect::<Vec<_>>();
+        if custom_passes.is_empty() && user_compute_pipelines.is_empty() {
+            return Ok(());
+        }
+
+        let pass_name = match position {
+            CustomPassPosition::BeforeCulling => "CustomPassesBeforeCulling",
+            CustomPassPosition::AfterCulling => "CustomPassesAfterCulling",
+            CustomPassPosition::AfterRendering => "CustomPassesAfterRendering",
+        };
+        let _span = jeriya_shared::span!("record custom passes");
+        pass_timer.begin(builder, pass_name);
+
+        let swapchain_image_index = presenter_shared
+            .frame_index
+            .swapchain_index()
+            .expect("swapchain index must be set before rendering");
+        let depth_buffer = presenter_shared
+            .vulkan_resource_coordinator
+            .swapchain_depth_buffers()
+            .depth_buffers
+            .get(&presenter_shared.frame_index);
+        let storage_buffers = persistent_frame_state
+            .cached_descriptor_set
+            .as_ref()
+            .map(|cached_descriptor_set| cached_descriptor_set.written_buffers())
+            .unwrap_or_default();
+        let mut frame_resource_tracker = FrameResourceTracker::new(position);
+        for registered_custom_pass in custom_passes {
+            let custom_pass = &registered_custom_pass.custom_pass;
+            let custom_pass_scope = builder.begin_label_scope(custom_pass.name().to_owned(), &label_color_blue(0.8));
+            if let Some(memory_barrier) = frame_resource_tracker.access(&custom_pass.accesses()) {
+                builder.memory_barrier(&memory_barrier);
+            }
+            let context = CustomPassContext {
+                device: backend_shared.device.as_raw_vulkan(),
+                command_buffer: *builder.command_buffer().as_raw_vulkan(),
+                position,
+                extent: presenter_shared.swapchain.extent(),
+                swapchain_image: presenter_shared.swapchain.images[swapchain_image_index],
+                swapchain_image_view: presenter_shared.swapchain.image_views()[swapchain_image_index],
+                swapchain_format: presenter_shared.swapchain.surface_format().format,
+                depth_image: depth_buffer.depth_image,
+                depth_image_view: depth_buffer.depth_image_view,
+                storage_buffers,
+            };
+            let record_result = custom_pass.record(&context);
+            builder.command_buffer().push_dependency(registered_custom_pass.clone());
+            custom_pass_scope.end(builder);
+            if let Err(err) = record_result {
+                pass_timer.end(builder);
+                return Err(err);
+            }
+        }
+        for (registered_user_compute_pass, pipeline) in user_compute_pipelines {
+            let user_compute_pass = &registered_user_compute_pass.user_compute_pass;
+            let user_compute_pass_scope = builder.begin_label_scope(user_compute_pass.shader.to_string(), &label_color_blue(0.6));
+            if let Some(memory_barrier) = frame_resource_tracker.access(&user_compute_pass.accesses()) {
+                builder.memory_barrier(&memory_barrier);
+            }
+            builder.bind_compute_pipeline(pipeline.as_ref());
+            persistent_frame_state.push_descriptors(PipelineBindPoint::Compute, &pipeline.descriptor_set_layout, builder)?;
+            match user_compute_pass.dispatch {
+                UserComputePassDispatch::PerRigidMeshInstance => {
+                    let group_count = workgroup_count(persistent_frame_state.rigid_mesh_instance_buffer.high_water_mark() as u32, pipeline);
+                    if group_count > 0 {
+                        builder.dispatch(group_count, 1, 1);
+                    }
+                }
+                UserComputePassDispatch::WorkgroupCount(x, y, z) => {
+                    builder.dispatch(x, y, z);
+                }
+            }
+            user_compute_pass_scope.end(builder);
+        }
+        if let Some(memory_barrier) = frame_resource_tracker.finish() {
+            builder.memory_barrier(&memory_barrier);
+        }
+
+        pass_timer.end(builder);
+        Ok(())
+    }
+
+    fn append_overlay_commands(
+        &self,
+        frame: &PersistentFrameState,
+        backend_shared: &BackendShared,
+        presenter_shared: &PresenterShared,
+        command_buffer_builder: &mut CommandBufferBuilder,
+        immediate_rendering_frames: &BTreeMap<&'static str, ImmediateRenderingFrameTask>,
+    ) -> crate::Result<()> {
+        let Some(textured_graphics_pipelines) = &self.textured_graphics_pipelines else {
+            return Ok(());
+        };
+
+        // Collect the vertex attributes of all overlay commands
+        let mut positions = Vec::new();
+        let mut texture_coordinates = Vec::new();
+        for command in overlay_commands(immediate_rendering_frames) {
+            match command {
+                OverlayCommand::Rectangle(rectangle) => positions.extend(rectangle.positions()),
+                OverlayCommand::Line(line) => positions.extend(line.positions()),
+                OverlayCommand::TexturedQuad(textured_quad) => {
+                    let (quad_positions, quad_texture_coordinates) = textured_quad.vertices();
+                    positions.extend(quad_positions);
+                    texture_coordinates.extend(quad_texture_coordinates);
+                }
+            }
+            // Only the textured quads have texture coordinates
+            texture_coordinates.resize(positions.len(), Vector2::zeros());
+        }
+        let vertex_buffer = Arc::new(HostVisibleBuffer::new(
+            &backend_shared.device,
+            positions.as_slice(),
+            BufferUsageFlags::VERTEX_BUFFER,
+            debug_info!("Overlay-VertexBuffer"),
+        )?);
+        let texture_coordinate_buffer = Arc::new(HostVisibleBuffer::new(
+            &backend_shared.device,
+            texture_coordinates.as_slice(),
+            BufferUsageFlags::VERTEX_BUFFER,
+            debug_info!("Overlay-TextureCoordinateBuffer"),
+        )?);
+        command_buffer_builder.bind_vertex_buffers(0, &vertex_buffer);
+        command_buffer_builder.bind_vertex_buffers(1, &texture_coordinate_buffer);
+
+        let matrix = overlay_projection_matrix(
+            presenter_shared.swapchain.extent().width,
+            presenter_shared.swapchain.extent().height,
+        );
+
+        // Append the draw commands
+        let mut first_vertex = 0;
+        let mut last_topology = None;
+        for command in overlay_commands(immediate_rendering_frames) {
+            let (topology, pipeline) = match command {
+                OverlayCommand::Line(..) => (PrimitiveTopology::LineList, &textured_graphics_pipelines.overlay_line_list),
+                OverlayCommand::Rectangle(..) | OverlayCommand::TexturedQuad(..) => {
+                    (PrimitiveTopology::TriangleList, &textured_graphics_pipelines.overlay_triangle_list)
+                }
+            };
+            if last_topology != Some(topology) {
+                command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
+                frame.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, command_buffer_builder)?;
+                command_buffer_builder.bind_bindless_textures(
+                    PipelineBindPoint::Graphics,
+                    presenter_shared.vulkan_resource_coordinator.bindless_textures(),
+                )?;
+                last_topology = Some(topology);
+            }
+            let (color, texture_index, vertex_count) = match command {
+                OverlayCommand::Rectangle(rectangle) => (*rectangle.color(), NO_TEXTURE, 6),
+                OverlayCommand::Line(line) => {
+                    command_buffer_builder.set_line_width(line.config().line_width);
+                    (line.config().color, NO_TEXTURE, 2)
+                }
+                OverlayCommand::TexturedQuad(textured_quad) => {
+                    let texture_index = match textured_quad.texture() {
+                        QuadTexture::Image(image) => presenter_shared
+                            .immediate_textures
+                            .overlay_image_texture_index(image)
+                            .expect("overlay image must have been uploaded before the render pass"),
+                        QuadTexture::RenderTarget(render_target_id) => {
+                            match presenter_shared.vulkan_resource_coordinator.render_target(*render_target_id) {
+                                Some(render_target) => render_target.texture_index(),
+                                None => {
+                                    warn!("Skipping TexturedQuad because the render target {render_target_id:?} doesn't exist");
+                                    first_vertex += 6;
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    (*textured_quad.tint(), texture_index, 6)
+                }
+            };
+            let push_constants = PushConstants {
+                color,
+                matrix,
+                texture_index,
+                ..Default::default()
+            };
+            command_buffer_builder.push_constants(&[push_constants])?;
+            command_buffer_builder.draw_vertices(vertex_count, first_vertex);
+            first_vertex += vertex_count;
+        }
+
+        Ok(())
+    }
+
+    fn append_user_interface_commands(
+        &self,
+        frame: &PersistentFrameState,
+        backend_shared: &BackendShared,
+        presenter_shared: &PresenterShared,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<()> {
+        let Some(textured_graphics_pipelines) = &self.textured_graphics_pipelines else {
+            return Ok(());
+        };
+        let meshes = presenter_shared.user_interface.meshes();
+
+        // There is no support for index buffers so the meshes are expanded into triangle lists
+        let vertices = meshes.iter().flat_map(UiMesh::triangle_vertices).collect::<Vec<_>>();
+        let positions = vertices.iter().map(|vertex| vertex.position.push(0.0)).collect::<Vec<_>>();
+        let texture_coordinates = vertices.iter().map(|vertex| vertex.texture_coordinates).collect::<Vec<_>>();
+        let colors = vertices.iter().map(|vertex| vertex.color).collect::<Vec<_>>();
+        let vertex_buffer = Arc::new(HostVisibleBuffer::new(
+            &backend_shared.device,
+            positions.as_slice(),
+            BufferUsageFlags::VERTEX_BUFFER,
+            debug_info!("UserInterface-VertexBuffer"),
+        )?);
+        let texture_coordinate_buffer = Arc::new(HostVisibleBuffer::new(
+            &backend_shared.device,
+            texture_coordinates.as_slice(),
+            BufferUsageFlags::VERTEX_BUFFER,
+            debug_info!("UserInterface-TextureCoordinateBuffer"),
+        )?);
+        let color_buffer = Arc::new(HostVisibleBuffer::new(
+            &backend_shared.device,
+            colors.as_slice(),
+            BufferUsageFlags::VERTEX_BUFFER,
+            debug_info!("UserInterface-ColorBuffer"),
+        )?);
+        command_buffer_builder.bind_vertex_buffers(0, &vertex_buffer);
+        command_buffer_builder.bind_vertex_buffers(1, &texture_coordinate_buffer);
+        command_buffer_builder.bind_vertex_buffers(2, &color_buffer);
+
+        let pipeline = &textured_graphics_pipelines.user_interface;
+        command_buffer_builder.bind_graphics_pipeline(pipeline.as_ref());
+        frame.push_descriptors(PipelineBindPoint::Graphics, &pipeline.descriptor_set_layout, command_buffer_builder)?;
+        command_buffer_builder.bind_bindless_textures(
+            PipelineBindPoint::Graphics,
+            presenter_shared.vulkan_resource_coordinator.bindless_textures(),
+        )?;
+
+        let extent = presenter_shared.swapchain.extent();
+        let matrix = overlay_projection_matrix(extent.width, extent.height);
+
+        // Append the draw commands
+        let mut first_vertex = 0;
+        for mesh in meshes {
+            let vertex_count = mesh.indices.len() as u32;
+            let texture_index = presenter_shared.user_interface.texture_index(mesh.texture);
+            if let (Some(texture_index), Some(scissor)) = (texture_index, user_interface_scissor(mesh, extent)) {
+                command_buffer_builder.set_scissor(scissor);
+                let push_constants = PushConstants {
+                    matrix,
+                    texture_index,
+                    ..Default::default()
+                };
+                command_buffer_builder.push_constants(&[push_constants])?;
+                command_buffer_builder.draw_vertices(vertex_count, first_vertex);
+            }
+            first_vertex += vertex_count;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the clip planes in the layout of the `PerFrameData`. Unused entries are zero.
+fn clip_planes(clip_planes: &[ClipPlane]) -> [Vector4<f32>; MAX_CLIP_PLANES] {
+    let mut result = [Vector4::zeros(); MAX_CLIP_PLANES];
+    for (dst, clip_plane) in result.iter_mut().zip(clip_planes) {
+        *dst = clip_plane.as_vector4();
+    }
+    result
+}
+
+/// Returns the rectangle with the given `offset` and `size` in pixels or `None` when it's empty
+fn pixel_rect(offset: [u32; 2], size: [u32; 2]) -> Option<vk::Rect2D> {
+    (size[0] > 0 && size[1] > 0).then(|| vk::Rect2D {
+        offset: vk::Offset2D {
+            x: offset[0] as i32,
+            y: offset[1] as i32,
+        },
+        extent: vk::Extent2D {
+            width: size[0],
+            height: size[1],
+        },
+    })
+}
+
+/// Returns the scissor rectangle for the clip rectangle of the `mesh` or `None` when nothing of the mesh is visible
+fn user_interface_scissor(mesh: &UiMesh, extent: vk::Extent2D) -> Option<vk::Rect2D> {
+    let min_x = mesh.clip_rect_min.x.round().clamp(0.0, extent.width as f32) as u32;
+    let min_y = mesh.clip_rect_min.y.round().clamp(0.0, extent.height as f32) as u32;
+    let max_x = mesh.clip_rect_max.x.round().clamp(0.0, extent.width as f32) as u32;
+    let max_y = mesh.clip_rect_max.y.round().clamp(0.0, extent.height as f32) as u32;
+    (max_x > min_x && max_y > min_y).then(|| vk::Rect2D {
+        offset: vk::Offset2D {
+            x: min_x as i32,
+            y: min_y as i32,
+        },
+        extent: vk::Extent2D {
+            width: max_x - min_x,
+            height: max_y - min_y,
+        },
+    })
+}
+
+/// Returns the [`OverlayCommand`]s of all immediate rendering frames in the order in which they are rendered
+fn overlay_commands<'a>(
+    immediate_rendering_frames: &'a BTreeMap<&'static str, ImmediateRenderingFrameTask>,
+) -> impl Iterator<Item = &'a OverlayCommand> {
+    immediate_rendering_frames
+        .values()
+        .flat_map(|task| &task.overlay_command_buffers)
+        .flat_map(OverlayCommandBuffer::commands)
+}
+
+/// Returns the [`OverlayImage`] that is displayed by the `command` if it is a [`TexturedQuad`](jeriya_backend::overlay::TexturedQuad)
+fn overlay_image(command: &OverlayCommand) -> Option<&Arc<OverlayImage>> {
+    match command {
+        OverlayCommand::TexturedQuad(textured_quad) => textured_quad.image(),
+        _ => None,
+    }
+}
+
+/// Returns the matrix that transforms pixels from the upper left corner of the framebuffer into clip space
+///
+/// The viewport of the pipelines is flipped so that the y-axis of the clip space is pointing up.
+fn overlay_projection_matrix(framebuffer_width: u32, framebuffer_height: u32) -> Matrix4<f32> {
+    Matrix4::new(
+        2.0 / framebuffer_width as f32,
+        0.0,
+        0.0,
+        -1.0,
+        0.0,
+        -2.0 / framebuffer_height as f32,
+        0.0,
+        1.0,
+        0.0,
+        0.0,
+        1.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    )
+}
+
+/// Returns all [`Text`]s in the immediate rendering frames
+fn immediate_texts<'a>(
+    immediate_rendering_frames: &'a BTreeMap<&'static str, ImmediateRenderingFrameTask>,
+) -> impl Iterator<Item = &'a Text> {
+    immediate_rendering_frames
+        .values()
+        .flat_map(|task| &task.command_buffers)
+        .flat_map(immediate::CommandBuffer::commands)
+        .filter_map(|command| match command {
+            ImmediateCommand::Text(text) => Some(text),
+            _ => None,
+        })
+}
+
+/// Returns whether the immediate command is occluded by the scene
+fn immediate_depth_test(command: &ImmediateCommand) -> bool {
+    match command {
+        ImmediateCommand::LineList(line_list) => line_list.config().depth_test,
+        ImmediateCommand::LineStrip(line_strip) => line_strip.config().depth_test,
+        ImmediateCommand::TriangleList(triangle_list) => triangle_list.config().depth_test,
+        ImmediateCommand::TriangleStrip(triangle_strip) => triangle_strip.config().depth_test,
+        ImmediateCommand::IndexedTriangleList(indexed_triangle_list) => indexed_triangle_list.config().depth_test,
+        ImmediateCommand::Matrix(..) | ImmediateCommand::Space(..) | ImmediateCommand::PointList(..) | ImmediateCommand::Text(..) => true,
+    }
+}
+
+/// Begins the pass when the passes are timed individually
+fn begin_timed_pass(pass_timer: &mut Option<&mut PassTimer>, builder: &mut CommandBufferBuilder, name: &'static str) {
+    if let Some(pass_timer) = pass_timer {
+        pass_timer.begin(builder, name);
+    }
+}
+
+/// Ends the pass that has been begun with [`begin_timed_pass`]
+fn end_timed_pass(pass_timer: &mut Option<&mut PassTimer>, builder: &mut CommandBufferBuilder) {
+    if let Some(pass_timer) = pass_timer {
+        pass_timer.end(builder);
+    }
+}
+
+/// Returns the number of workgroups that are needed to dispatch one invocation per element with the workgroup size of the pipeline
+fn workgroup_count(element_count: u32, pipeline: &GenericComputePipeline) -> u32 {
+    let local_size_x = pipeline
+        .config
+        .specialization_constants
+        .get::<LocalSizeX>()
+        .expect("LocalSizeX must be set for pipelines that dispatch one invocation per element");
+    element_count.div_ceil(local_size_x)
 }
@@ -3,14 +3,16 @@ use std::{ffi::CString, io::Cursor, sync::Arc};
 use ash::vk;
 use jeriya_content::common::AssetKey;
 use jeriya_macros::profile;
-use jeriya_shared::{debug_info, nalgebra::Vector4, AsDebugInfo, DebugInfo};
+use jeriya_shared::{debug_info, AsDebugInfo, DebugInfo};
 
 use crate::{
+    bindless_textures::BindlessTextures,
     descriptor_set_layout::DescriptorSetLayout,
     device::Device,
-    shader_interface::{self, Camera, CameraInstance, MeshAttributes, PerFrameData, PointCloudPage, RigidMesh, RigidMeshInstance},
+    persistent_frame_state::PersistentFrameState,
+    pipeline_cache::PipelineCache,
     shader_module::ShaderModule,
-    specialization_constants::SpecializationConstants,
+    specialization_constants::{LocalSizeX, PipelineSpecializationConstants, SpecializationConstant, SpecializationConstants},
     AsRawVulkan,
 };
 
@@ -22,6 +24,23 @@ pub trait ComputePipeline {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct GenericComputePipelineConfig {
     pub shader: AssetKey,
+    pub specialization_constants: PipelineSpecializationConstants,
+}
+
+impl GenericComputePipelineConfig {
+    /// Creates a new `GenericComputePipelineConfig` for the given shader without pipeline specific specialization constants
+    pub fn new(shader: AssetKey) -> Self {
+        Self {
+            shader,
+            specialization_constants: PipelineSpecializationConstants::default(),
+        }
+    }
+
+    /// Sets the value of the specialization constant `C` for this pipeline
+    pub fn with_specialization_constant<C: SpecializationConstant>(mut self, value: C::Value) -> Self {
+        self.specialization_constants = self.specialization_constants.with::<C>(value);
+        self
+    }
 }
 
 pub struct GenericComputePipeline {
@@ -37,9 +56,12 @@ pub struct GenericComputePipeline {
 impl GenericComputePipeline {
     pub fn new(
         device: &Arc<Device>,
+        pipeline_cache: &PipelineCache,
         config: &GenericComputePipelineConfig,
         shader_spirv: &[u8],
         specialization_constants: &SpecializationConstants,
+        bindless_textures: &BindlessTextures,
+        cached_descriptor_set_layout: &DescriptorSetLayout,
         debug_info: DebugInfo,
     ) -> crate::Result<Self> {
         let entry_name = CString::new("main").expect("Valid c string");
@@ -50,6 +72,13 @@ impl GenericComputePipeline {
             debug_info!("GenericComputePipeline-ShaderModule"),
         )?;
 
+        // The number of workgroups is computed from `LocalSizeX` which must therefore be the workgroup size of the shader
+        let local_size_x_spec_id = shader.reflection().workgroup_size_x_spec_id;
+        if config.specialization_constants.get::<LocalSizeX>().is_some() && local_size_x_spec_id != Some(LocalSizeX::CONSTANT_ID) {
+            return Err(crate::Error::WorkgroupSizeNotSpecialized(LocalSizeX::CONSTANT_ID));
+        }
+
+        let specialization_constants = specialization_constants.extended_with(&config.specialization_constants);
         let specialization_info = vk::SpecializationInfo::builder()
             .map_entries(specialization_constants.map_entries())
             .data(specialization_constants.data())
@@ -62,39 +91,17 @@ impl GenericComputePipeline {
             .specialization_info(&specialization_info)
             .build();
 
-        let descriptor_set_layout = Arc::new(
-            DescriptorSetLayout::builder()
-                .push_uniform_buffer::<PerFrameData>(0, 1)
-                .push_storage_buffer::<Camera>(1, 1)
-                .push_storage_buffer::<CameraInstance>(2, 1)
-                .push_storage_buffer::<u32>(3, 1)
-                .push_storage_buffer::<Vector4<f32>>(5, 1)
-                .push_storage_buffer::<u32>(6, 1)
-                .push_storage_buffer::<Vector4<f32>>(7, 1)
-                .push_storage_buffer::<MeshAttributes>(8, 1)
-                .push_storage_buffer::<RigidMesh>(9, 1)
-                .push_storage_buffer::<u32>(10, 1)
-                .push_storage_buffer::<RigidMeshInstance>(11, 1)
-                .push_storage_buffer::<shader_interface::Meshlet>(12, 1)
-                .push_storage_buffer::<u32>(13, 1)
-                .push_storage_buffer::<u32>(14, 1)
-                .push_storage_buffer::<u32>(15, 1)
-                .push_storage_buffer::<shader_interface::PointCloud>(16, 1)
-                .push_storage_buffer::<shader_interface::PointCloudInstance>(17, 1)
-                .push_storage_buffer::<u32>(18, 1)
-                .push_storage_buffer::<shader_interface::PointCloudAttributes>(19, 1)
-                .push_storage_buffer::<Vector4<f32>>(20, 1)
-                .push_storage_buffer::<Vector4<f32>>(21, 1)
-                .push_storage_buffer::<PointCloudPage>(22, 1)
-                .push_storage_buffer::<u32>(23, 1)
-                .push_storage_buffer::<PointCloudPage>(24, 1)
-                .push_storage_buffer::<u32>(25, 1)
-                .push_storage_buffer::<u32>(26, 1)
-                .push_storage_buffer::<shader_interface::FrameTelemetry>(27, 1)
-                .push_storage_buffer::<f32>(28, 1)
-                .build(device)?,
-        );
-        let descriptor_set_layouts = [*descriptor_set_layout.as_raw_vulkan()];
+        let descriptor_set_layout = Arc::new(DescriptorSetLayout::from_reflection(
+            device,
+            [shader.reflection()],
+            &PersistentFrameState::available_descriptors(),
+            cached_descriptor_set_layout.descriptors(),
+        )?);
+        let descriptor_set_layouts = [
+            *descriptor_set_layout.as_raw_vulkan(),
+            bindless_textures.descriptor_set_layout(),
+            *cached_descriptor_set_layout.as_raw_vulkan(),
+        ];
 
         let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder().set_layouts(&descriptor_set_layouts).build();
         let pipeline_layout = unsafe { device.as_raw_vulkan().create_pipeline_layout(&pipeline_layout_create_info, None)? };
@@ -106,9 +113,11 @@ impl GenericComputePipeline {
         let compute_pipeline = unsafe {
             device
                 .as_raw_vulkan()
-                .create_compute_pipelines(vk::PipelineCache::null(), &[compute_pipeline_info], None)
+                .create_compute_pipelines(*pipeline_cache.as_raw_vulkan(), &[compute_pipeline_info], None)
                 .map_err(|(_, err)| err)?[0]
         };
+        device.set_debug_name(compute_pipeline, &debug_info);
+        device.set_debug_name(pipeline_layout, &debug_info);
 
         Ok(Self {
             config: config.clone(),
@@ -154,25 +163,156 @@ mod tests {
         use jeriya_shared::debug_info;
 
         use crate::{
-            compute_pipeline::GenericComputePipeline, compute_pipeline::GenericComputePipelineConfig, device::TestFixtureDevice,
-            specialization_constants::SpecializationConstants,
+            bindless_textures::BindlessTextures,
+            compute_pipeline::GenericComputePipeline,
+            compute_pipeline::GenericComputePipelineConfig,
+            device::TestFixtureDevice,
+            persistent_frame_state::PersistentFrameState,
+            pipeline_cache::PipelineCache,
+            specialization_constants::{LocalSizeX, SpecializationConstants},
         };
 
         #[test]
         fn smoke() {
             let test_fixture_device = TestFixtureDevice::new().unwrap();
-            let config = GenericComputePipelineConfig {
-                shader: AssetKey::new("test_data/cull_rigid_mesh_instances.comp"),
-            };
+            let config = GenericComputePipelineConfig::new(AssetKey::new("test_data/cull_rigid_mesh_instances.comp"))
+                .with_specialization_constant::<LocalSizeX>(128);
             let specialization_constants = SpecializationConstants::new();
+            let pipeline_cache = PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let cached_descriptor_set_layout = PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap();
             let _compute_pipeline = GenericComputePipeline::new(
                 &test_fixture_device.device,
+                &pipeline_cache,
                 &config,
                 include_bytes!("../test_data/cull_rigid_mesh_instances.comp.spv"),
                 &specialization_constants,
+                &bindless_textures,
+                &cached_descriptor_set_layout,
+                debug_info!("my_compute_pipeline"),
+            )
+            .unwrap();
+        }
+    }
+
+    mod dispatch {
+        use std::sync::{mpsc::channel, Arc};
+
+        use jeriya_content::{asset_importer::AssetImporter, common::AssetKey};
+        use jeriya_shared::debug_info;
+
+        use crate::{
+            backend_shared::BackendShared,
+            bindless_textures::BindlessTextures,
+            command_buffer::CommandBuffer,
+            command_buffer_builder::{CommandBufferBuilder, PipelineBindPoint},
+            command_pool::{CommandPool, CommandPoolCreateFlags},
+            compute_pipeline::{GenericComputePipeline, GenericComputePipelineConfig},
+            device::TestFixtureDevice,
+            persistent_frame_state::PersistentFrameState,
+            queue::Queue,
+            queue_plan::QueueSelection,
+            specialization_constants::{LocalSizeX, SpecializationConstants},
+            Error,
+        };
+
+        fn create_pipeline(
+            backend_shared: &BackendShared,
+            bindless_textures: &BindlessTextures,
+            cached_descriptor_set_layout: &crate::descriptor_set_layout::DescriptorSetLayout,
+            shader_spirv: &[u8],
+            local_size_x: u32,
+        ) -> crate::Result<GenericComputePipeline> {
+            let config = GenericComputePipelineConfig::new(AssetKey::new("test_data/my_compute_shader.comp"))
+                .with_specialization_constant::<LocalSizeX>(local_size_x);
+            GenericComputePipeline::new(
+                &backend_shared.device,
+                &backend_shared.pipeline_cache,
+                &config,
+                shader_spirv,
+                &SpecializationConstants::new(),
+                bindless_textures,
+                cached_descriptor_set_layout,
                 debug_info!("my_compute_pipeline"),
             )
+        }
+
+        #[test]
+        fn non_default_local_size() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let (resource_sender, _resource_receiver) = channel();
+            let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
+            let backend_shared = BackendShared::new(
+                &test_fixture_device.device,
+                &Arc::new(Default::default()),
+                resource_sender,
+                &asset_importer,
+            )
+            .unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let mut frame = PersistentFrameState::new(0, &test_fixture_device.window.id(), &backend_shared).unwrap();
+            let cached_descriptor_set_layout =
+                Arc::new(PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap());
+            frame
+                .update_cached_descriptor_set(&backend_shared, &cached_descriptor_set_layout)
+                .unwrap();
+
+            // The shaders are compiled with a workgroup size of 128 and 1
+            let shaders: [&[u8]; 2] = [
+                include_bytes!("../test_data/cull_rigid_mesh_instances.comp.spv"),
+                include_bytes!("../test_data/cull_point_cloud_instances.comp.spv"),
+            ];
+            let mut queue = Queue::new(
+                &test_fixture_device.device,
+                &QueueSelection::new_unchecked(0, 0),
+                debug_info!("my_queue"),
+            )
+            .unwrap();
+            let command_pool = CommandPool::new(
+                &test_fixture_device.device,
+                &queue,
+                CommandPoolCreateFlags::ResetCommandBuffer,
+                debug_info!("my_command_pool"),
+            )
+            .unwrap();
+            for shader in shaders {
+                let pipeline = create_pipeline(&backend_shared, &bindless_textures, &cached_descriptor_set_layout, shader, 32).unwrap();
+                let mut command_buffer =
+                    CommandBuffer::new(&test_fixture_device.device, &command_pool, debug_info!("my_command_buffer")).unwrap();
+                let mut builder = CommandBufferBuilder::new(&test_fixture_device.device, &mut command_buffer).unwrap();
+                builder.begin_command_buffer().unwrap();
+                builder.bind_compute_pipeline(&pipeline);
+                frame
+                    .push_descriptors(PipelineBindPoint::Compute, &pipeline.descriptor_set_layout, &mut builder)
+                    .unwrap();
+                builder.dispatch(4, 1, 1);
+                builder.end_command_buffer().unwrap();
+                queue.submit_and_wait_idle(command_buffer).unwrap();
+            }
+        }
+
+        #[test]
+        fn workgroup_size_not_specialized() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let (resource_sender, _resource_receiver) = channel();
+            let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
+            let backend_shared = BackendShared::new(
+                &test_fixture_device.device,
+                &Arc::new(Default::default()),
+                resource_sender,
+                &asset_importer,
+            )
             .unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let cached_descriptor_set_layout = PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap();
+            let result = create_pipeline(
+                &backend_shared,
+                &bindless_textures,
+                &cached_descriptor_set_layout,
+                include_bytes!("../test_data/cull_rigid_mesh_meshlets.comp.spv"),
+                32,
+            );
+            assert!(matches!(result, Err(Error::WorkgroupSizeNotSpecialized(16))));
         }
     }
 }
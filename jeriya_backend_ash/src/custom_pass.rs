@@ -0,0 +1,452 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use ash::vk;
+
+use crate::command_buffer::CommandBufferDependency;
+
+/// Position in the frame at which a [`CustomPass`] is recorded
+///
+/// The custom passes at the same position are recorded in the order in which they have been added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CustomPassPosition {
+    /// After the motions of the instances have been advanced and before the render targets and the active camera are culled
+    BeforeCulling,
+    /// After the active camera has been culled and before the scene is rendered into the window
+    AfterCulling,
+    /// After the scene has been rendered and post-processed and before the overlay is rendered
+    AfterRendering,
+}
+
+/// Resource of the frame that is accessed by a [`CustomPass`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FrameResource {
+    /// Buffers of the elements and instances of the scene like the rigid mesh instances and the meshlets
+    SceneBuffers,
+    /// Buffers into which the culling writes the visible instances, meshlets and clusters and the indirect commands
+    CullingResults,
+    /// Swapchain image into which the window is rendered. Its content is only defined at [`CustomPassPosition::AfterRendering`].
+    SwapchainImage,
+    /// Depth buffer of the window into which the scene is rendered
+    DepthBuffer,
+}
+
+/// Whether a [`CustomPass`] reads or writes a [`FrameResource`]. Writing includes reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Kind of commands with which a [`CustomPass`] accesses a [`FrameResource`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PassStage {
+    Compute,
+    /// Draw commands including indirect draws and attachments
+    Graphics,
+    /// Copy and fill commands
+    Transfer,
+}
+
+/// Declares how a [`CustomPass`] accesses a [`FrameResource`] so that the barriers around the pass can be derived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceAccess {
+    pub resource: FrameResource,
+    pub access: Access,
+    pub stage: PassStage,
+}
+
+impl ResourceAccess {
+    /// Creates a new `ResourceAccess` that reads the `resource` in the given `stage`
+    pub fn read(resource: FrameResource, stage: PassStage) -> Self {
+        Self {
+            resource,
+            access: Access::Read,
+            stage,
+        }
+    }
+
+    /// Creates a new `ResourceAccess` that writes the `resource` in the given `stage`
+    pub fn write(resource: FrameResource, stage: PassStage) -> Self {
+        Self {
+            resource,
+            access: Access::Write,
+            stage,
+        }
+    }
+}
+
+/// Compute or raster work of an application that is recorded into the command buffer of a frame
+///
+/// The backend inserts the barriers between the pass and the built-in passes based on the [`ResourceAccess`]es that are
+/// returned by [`CustomPass::accesses`]. Resources that are not declared must not be accessed. The Vulkan objects that
+/// are used by the pass must be kept alive by the pass itself because it's kept alive until the frames in flight that
+/// recorded it have finished rendering.
+pub trait CustomPass: Send + Sync {
+    /// Name of the pass that is used for the debug label around its commands
+    fn name(&self) -> &str;
+
+    /// Returns the [`ResourceAccess`]es of the pass
+    fn accesses(&self) -> Vec<ResourceAccess>;
+
+    /// Records the commands of the pass. It's called outside of a render pass.
+    fn record(&self, context: &CustomPassContext) -> crate::Result<()>;
+}
+
+/// Identifies a [`CustomPass`] that has been added to a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CustomPassId(pub u64);
+
+/// Vulkan handles of the frame that are passed to [`CustomPass::record`]
+pub struct CustomPassContext<'a> {
+    pub(crate) device: &'a ash::Device,
+    pub(crate) command_buffer: vk::CommandBuffer,
+    pub(crate) position: CustomPassPosition,
+    pub(crate) extent: vk::Extent2D,
+    pub(crate) swapchain_image: vk::Image,
+    pub(crate) swapchain_image_view: vk::ImageView,
+    pub(crate) swapchain_format: vk::Format,
+    pub(crate) depth_image: vk::Image,
+    pub(crate) depth_image_view: vk::ImageView,
+    /// Bindings of the [`FrameResource::SceneBuffers`] and [`FrameResource::CullingResults`] together with their buffers
+    pub(crate) storage_buffers: &'a [(u32, vk::Buffer)],
+}
+
+impl<'a> CustomPassContext<'a> {
+    /// Returns the device with which the commands are recorded
+    pub fn device(&self) -> &'a ash::Device {
+        self.device
+    }
+
+    /// Returns the command buffer into which the commands are recorded
+    pub fn command_buffer(&self) -> vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    /// Returns the [`CustomPassPosition`] at which the pass is recorded
+    pub fn position(&self) -> CustomPassPosition {
+        self.position
+    }
+
+    /// Returns the extent of the swapchain in pixels
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Returns the swapchain image into which the window is rendered
+    pub fn swapchain_image(&self) -> vk::Image {
+        self.swapchain_image
+    }
+
+    /// Returns the view of the swapchain image
+    pub fn swapchain_image_view(&self) -> vk::ImageView {
+        self.swapchain_image_view
+    }
+
+    /// Returns the format of the swapchain image
+    pub fn swapchain_format(&self) -> vk::Format {
+        self.swapchain_format
+    }
+
+    /// Returns the layout in which the swapchain image is when the pass is recorded. The pass must restore it.
+    pub fn swapchain_image_layout(&self) -> vk::ImageLayout {
+        match self.position {
+            CustomPassPosition::BeforeCulling | CustomPassPosition::AfterCulling => vk::ImageLayout::UNDEFINED,
+            CustomPassPosition::AfterRendering => vk::ImageLayout::PRESENT_SRC_KHR,
+        }
+    }
+
+    /// Returns the depth image of the window
+    pub fn depth_image(&self) -> vk::Image {
+        self.depth_image
+    }
+
+    /// Returns the view of the depth image
+    pub fn depth_image_view(&self) -> vk::ImageView {
+        self.depth_image_view
+    }
+
+    /// Returns the buffer of the [`FrameResource::SceneBuffers`] or [`FrameResource::CullingResults`] with the given binding
+    ///
+    /// The bindings are the ones of the built-in shaders in [`bindings`](crate::bindings). Returns `None` for bindings
+    /// that don't belong to a storage buffer.
+    pub fn storage_buffer(&self, binding: u32) -> Option<vk::Buffer> {
+        self.storage_buffers
+            .iter()
+            .find(|(storage_buffer_binding, _)| *storage_buffer_binding == binding)
+            .map(|(_, buffer)| *buffer)
+    }
+
+    /// Returns the layout in which the depth image is when the pass is recorded. The pass must restore it.
+    pub fn depth_image_layout(&self) -> vk::ImageLayout {
+        match self.position {
+            CustomPassPosition::BeforeCulling | CustomPassPosition::AfterCulling => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            CustomPassPosition::AfterRendering => vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        }
+    }
+}
+
+/// [`CustomPass`] that has been added to a window
+pub(crate) struct RegisteredCustomPass {
+    pub id: CustomPassId,
+    pub position: CustomPassPosition,
+    pub custom_pass: Arc<dyn CustomPass>,
+}
+
+// Pushed to the command buffer so that the pass is kept alive while the frame is rendered
+impl CommandBufferDependency for RegisteredCustomPass {}
+
+/// Pipeline stages in which a resource is accessed with the given access flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ResourceUsage {
+    pub stages: vk::PipelineStageFlags,
+    pub accesses: vk::AccessFlags,
+}
+
+impl ResourceUsage {
+    const WRITE_ACCESSES: vk::AccessFlags = vk::AccessFlags::from_raw(
+        vk::AccessFlags::SHADER_WRITE.as_raw()
+            | vk::AccessFlags::COLOR_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags::TRANSFER_WRITE.as_raw()
+            | vk::AccessFlags::MEMORY_WRITE.as_raw(),
+    );
+
+    fn new(stages: vk::PipelineStageFlags, accesses: vk::AccessFlags) -> Self {
+        Self { stages, accesses }
+    }
+
+    /// Returns the `ResourceUsage` of the given [`ResourceAccess`]
+    fn of(resource_access: &ResourceAccess) -> Self {
+        use vk::{AccessFlags as A, PipelineStageFlags as S};
+        let write = resource_access.access == Access::Write;
+        let read_write = |read: A, written: A| if write { read | written } else { read };
+        match (resource_access.stage, resource_access.resource) {
+            (PassStage::Compute, _) => Self::new(S::COMPUTE_SHADER, read_write(A::SHADER_READ, A::SHADER_WRITE)),
+            (PassStage::Transfer, _) => Self::new(S::TRANSFER, read_write(A::TRANSFER_READ, A::TRANSFER_WRITE)),
+            (PassStage::Graphics, FrameResource::SceneBuffers | FrameResource::CullingResults) => Self::new(
+                S::DRAW_INDIRECT | S::VERTEX_SHADER | S::FRAGMENT_SHADER,
+                read_write(A::INDIRECT_COMMAND_READ | A::SHADER_READ, A::SHADER_WRITE),
+            ),
+            (PassStage::Graphics, FrameResource::SwapchainImage) => Self::new(
+                S::COLOR_ATTACHMENT_OUTPUT | S::FRAGMENT_SHADER,
+                read_write(A::COLOR_ATTACHMENT_READ | A::SHADER_READ, A::COLOR_ATTACHMENT_WRITE),
+            ),
+            (PassStage::Graphics, FrameResource::DepthBuffer) => Self::new(
+                S::EARLY_FRAGMENT_TESTS | S::LATE_FRAGMENT_TESTS | S::FRAGMENT_SHADER,
+                read_write(A::DEPTH_STENCIL_ATTACHMENT_READ | A::SHADER_READ, A::DEPTH_STENCIL_ATTACHMENT_WRITE),
+            ),
+        }
+    }
+
+    fn is_write(&self) -> bool {
+        self.accesses.intersects(Self::WRITE_ACCESSES)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        Self::new(self.stages | other.stages, self.accesses | other.accesses)
+    }
+}
+
+/// Global memory barrier that is recorded around a [`CustomPass`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct MemoryBarrier {
+    pub src_stages: vk::PipelineStageFlags,
+    pub dst_stages: vk::PipelineStageFlags,
+    pub src_accesses: vk::AccessFlags,
+    pub dst_accesses: vk::AccessFlags,
+}
+
+impl MemoryBarrier {
+    /// Adds the dependency from the `previous` to the `next` usage if one of them writes
+    fn push(&mut self, previous: &ResourceUsage, next: &ResourceUsage) -> bool {
+        if previous.is_empty() || !(previous.is_write() || next.is_write()) {
+            return false;
+        }
+        self.src_stages |= previous.stages;
+        self.dst_stages |= next.stages;
+        // Write-after-read hazards only need an execution dependency
+        self.src_accesses |= previous.accesses & ResourceUsage::WRITE_ACCESSES;
+        self.dst_accesses |= next.accesses;
+        true
+    }
+
+    fn is_empty(&self) -> bool {
+        self.src_stages.is_empty()
+    }
+}
+
+/// Tracks the [`ResourceUsage`]s of the [`FrameResource`]s at a [`CustomPassPosition`] to derive the barriers around the custom passes
+pub(crate) struct FrameResourceTracker {
+    position: CustomPassPosition,
+    usages: BTreeMap<FrameResource, ResourceUsage>,
+    /// Resources that have been accessed by the custom passes
+    accessed: BTreeSet<FrameResource>,
+}
+
+impl FrameResourceTracker {
+    /// Creates a new `FrameResourceTracker` with the usages of the built-in passes that precede the `position`
+    pub fn new(position: CustomPassPosition) -> Self {
+        Self {
+            position,
+            usages: built_in_usages(position, Direction::Preceding),
+            accessed: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the barrier that has to be recorded before a pass with the given `accesses`
+    pub fn access(&mut self, accesses: &[ResourceAccess]) -> Option<MemoryBarrier> {
+        let mut memory_barrier = MemoryBarrier::default();
+        let mut next_usages = BTreeMap::<FrameResource, ResourceUsage>::new();
+        for resource_access in accesses {
+            let usage = next_usages.entry(resource_access.resource).or_default();
+            *usage = usage.union(&ResourceUsage::of(resource_access));
+        }
+        for (resource, next) in next_usages {
+            let previous = self.usages.entry(resource).or_default();
+            *previous = if memory_barrier.push(previous, &next) {
+                next
+            } else {
+                previous.union(&next)
+            };
+            self.accessed.insert(resource);
+        }
+        (!memory_barrier.is_empty()).then_some(memory_barrier)
+    }
+
+    /// Returns the barrier that has to be recorded after the custom passes before the built-in passes continue
+    pub fn finish(&self) -> Option<MemoryBarrier> {
+        let following = built_in_usages(self.position, Direction::Following);
+        let mut memory_barrier = MemoryBarrier::default();
+        for resource in &self.accessed {
+            if let Some(next) = following.get(resource) {
+                memory_barrier.push(&self.usages[resource], next);
+            }
+        }
+        (!memory_barrier.is_empty()).then_some(memory_barrier)
+    }
+}
+
+enum Direction {
+    Preceding,
+    Following,
+}
+
+/// Returns how the built-in passes access the [`FrameResource`]s before or after the `position`
+fn built_in_usages(position: CustomPassPosition, direction: Direction) -> BTreeMap<FrameResource, ResourceUsage> {
+    use vk::{AccessFlags as A, PipelineStageFlags as S};
+    use FrameResource::*;
+    let compute_read = ResourceUsage::new(S::COMPUTE_SHADER, A::SHADER_READ);
+    let compute_write = ResourceUsage::new(S::COMPUTE_SHADER, A::SHADER_READ | A::SHADER_WRITE);
+    let draw_read = ResourceUsage::new(
+        S::DRAW_INDIRECT | S::VERTEX_SHADER | S::FRAGMENT_SHADER,
+        A::INDIRECT_COMMAND_READ | A::SHADER_READ,
+    );
+    let color_write = ResourceUsage::new(S::COLOR_ATTACHMENT_OUTPUT, A::COLOR_ATTACHMENT_WRITE);
+    let depth_write = ResourceUsage::new(
+        S::EARLY_FRAGMENT_TESTS | S::LATE_FRAGMENT_TESTS,
+        A::DEPTH_STENCIL_ATTACHMENT_READ | A::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    );
+    let usages: &[(FrameResource, ResourceUsage)] = match (position, direction) {
+        // The motions of the instances are advanced in a compute shader and the frame starts with a full barrier
+        (CustomPassPosition::BeforeCulling, Direction::Preceding) => &[(SceneBuffers, compute_write), (DepthBuffer, depth_write)],
+        (CustomPassPosition::BeforeCulling, Direction::Following) => &[
+            (SceneBuffers, compute_read.union(&draw_read)),
+            (CullingResults, compute_write.union(&draw_read)),
+            (DepthBuffer, depth_write),
+        ],
+        (CustomPassPosition::AfterCulling, Direction::Preceding) => &[
+            (SceneBuffers, compute_read.union(&draw_read)),
+            (CullingResults, compute_write.union(&draw_read)),
+            (DepthBuffer, depth_write),
+        ],
+        (CustomPassPosition::AfterCulling, Direction::Following) => &[
+            (SceneBuffers, draw_read),
+            (CullingResults, draw_read),
+            (SwapchainImage, color_write),
+            (DepthBuffer, depth_write),
+        ],
+        // The eye-dome lighting samples the depth buffer in the fragment shader
+        (CustomPassPosition::AfterRendering, Direction::Preceding) => &[
+            (SceneBuffers, draw_read),
+            (CullingResults, draw_read),
+            (SwapchainImage, color_write),
+            (
+                DepthBuffer,
+                depth_write.union(&ResourceUsage::new(S::FRAGMENT_SHADER, A::SHADER_READ)),
+            ),
+        ],
+        // The overlay and the user interface are rendered into the swapchain image which is then transformed or captured
+        (CustomPassPosition::AfterRendering, Direction::Following) => &[(
+            SwapchainImage,
+            color_write.union(&ResourceUsage::new(S::TRANSFER, A::TRANSFER_READ)),
+        )],
+    };
+    usages.iter().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_after_built_in_write() {
+        let mut tracker = FrameResourceTracker::new(CustomPassPosition::AfterCulling);
+        let memory_barrier = tracker
+            .access(&[ResourceAccess::read(FrameResource::CullingResults, PassStage::Compute)])
+            .unwrap();
+        assert!(memory_barrier.src_stages.contains(vk::PipelineStageFlags::COMPUTE_SHADER));
+        assert!(memory_barrier.src_accesses.contains(vk::AccessFlags::SHADER_WRITE));
+        assert_eq!(memory_barrier.dst_stages, vk::PipelineStageFlags::COMPUTE_SHADER);
+        assert_eq!(memory_barrier.dst_accesses, vk::AccessFlags::SHADER_READ);
+
+        // Reading doesn't require a barrier to the following render pass
+        assert_eq!(tracker.finish(), None);
+    }
+
+    #[test]
+    fn read_after_read() {
+        let mut tracker = FrameResourceTracker::new(CustomPassPosition::AfterRendering);
+        assert_eq!(
+            tracker.access(&[ResourceAccess::read(FrameResource::SceneBuffers, PassStage::Compute)]),
+            None
+        );
+        assert_eq!(
+            tracker.access(&[ResourceAccess::read(FrameResource::SceneBuffers, PassStage::Transfer)]),
+            None
+        );
+        assert_eq!(tracker.finish(), None);
+    }
+
+    #[test]
+    fn write_before_built_in_read() {
+        let mut tracker = FrameResourceTracker::new(CustomPassPosition::AfterRendering);
+        let memory_barrier = tracker
+            .access(&[ResourceAccess::write(FrameResource::SwapchainImage, PassStage::Graphics)])
+            .unwrap();
+        assert!(memory_barrier.src_accesses.contains(vk::AccessFlags::COLOR_ATTACHMENT_WRITE));
+
+        let memory_barrier = tracker.finish().unwrap();
+        assert!(memory_barrier.src_stages.contains(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT));
+        assert!(memory_barrier.dst_stages.contains(vk::PipelineStageFlags::TRANSFER));
+        assert!(memory_barrier.dst_accesses.contains(vk::AccessFlags::TRANSFER_READ));
+    }
+
+    #[test]
+    fn write_after_read_between_custom_passes() {
+        let mut tracker = FrameResourceTracker::new(CustomPassPosition::BeforeCulling);
+        tracker.access(&[ResourceAccess::read(FrameResource::CullingResults, PassStage::Transfer)]);
+        let memory_barrier = tracker
+            .access(&[ResourceAccess::write(FrameResource::CullingResults, PassStage::Compute)])
+            .unwrap();
+        assert_eq!(memory_barrier.src_stages, vk::PipelineStageFlags::TRANSFER);
+        assert_eq!(memory_barrier.src_accesses, vk::AccessFlags::empty());
+        assert!(tracker.finish().is_some());
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use jeriya_shared::features;
 
 use crate::command_buffer_builder::CommandBufferBuilder;
@@ -24,13 +26,13 @@ pub fn label_color_yellow(value: f32) -> [f32; 4] {
 
 /// Makes sure that a debug label is correctly ended.
 pub struct DebugLabelGuard {
-    label: &'static str,
+    label: Cow<'static, str>,
     correctly_dropped: bool,
 }
 
 impl DebugLabelGuard {
     // Creates a new guard to ensure that a debug label is correctly ended.
-    pub fn new(label: &'static str) -> Self {
+    pub fn new(label: Cow<'static, str>) -> Self {
         Self {
             label,
             // When labeling is enabled, then the guard must be dropped correctly. When it is disabled, then the guard is not needed and therefore always correctly dropped.
@@ -40,7 +42,7 @@ impl DebugLabelGuard {
 
     /// Ends the label scope. This must be called or the `LabelGuard` will panic on drop.
     pub fn end(mut self, command_buffer_builder: &mut CommandBufferBuilder) {
-        command_buffer_builder.end_label_scope(self.label);
+        command_buffer_builder.end_label_scope(&self.label);
         self.correctly_dropped = true;
     }
 }
@@ -48,7 +50,7 @@ impl DebugLabelGuard {
 impl Drop for DebugLabelGuard {
     fn drop(&mut self) {
         // The drop implementation doesn't directly drop the label because it would have to store a reference to the `CommandBufferBuilder` which is mostly uses &mut self and therefore would be exclusively borrowed until the label is dropped.
-        let name = self.label;
+        let name = &self.label;
         if !self.correctly_dropped {
             panic!("LabelGuard for '{name}' was not dropped correctly");
         }
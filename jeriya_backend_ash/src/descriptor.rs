@@ -14,6 +14,7 @@ pub struct Descriptor {
 pub enum DescriptorType {
     UniformBuffer(TypeId),
     StorageBuffer(TypeId),
+    CombinedImageSampler,
 }
 
 impl DescriptorType {
@@ -31,6 +32,7 @@ impl From<DescriptorType> for vk::DescriptorType {
         match descriptor_type {
             DescriptorType::UniformBuffer(_) => vk::DescriptorType::UNIFORM_BUFFER,
             DescriptorType::StorageBuffer(_) => vk::DescriptorType::STORAGE_BUFFER,
+            DescriptorType::CombinedImageSampler => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
         }
     }
 }
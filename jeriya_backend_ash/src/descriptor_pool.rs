@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use ash::vk;
+use jeriya_shared::{AsDebugInfo, DebugInfo};
+
+use crate::{descriptor_set_layout::DescriptorSetLayout, device::Device, AsRawVulkan, DebugInfoAshExtension, Error};
+
+/// Pool from which the descriptor sets of a single [`DescriptorSetLayout`] are allocated
+pub struct DescriptorPool {
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set_layout: Arc<DescriptorSetLayout>,
+    max_sets: u32,
+    allocated_sets: u32,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl Drop for DescriptorPool {
+    fn drop(&mut self) {
+        unsafe {
+            // Destroying the pool frees the descriptor sets
+            self.device.as_raw_vulkan().destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
+
+impl AsDebugInfo for DescriptorPool {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl DescriptorPool {
+    /// Creates a new `DescriptorPool` with space for `max_sets` descriptor sets of the given [`DescriptorSetLayout`]
+    pub fn new(
+        device: &Arc<Device>,
+        descriptor_set_layout: &Arc<DescriptorSetLayout>,
+        max_sets: u32,
+        debug_info: DebugInfo,
+    ) -> crate::Result<Self> {
+        jeriya_shared::assert!(max_sets > 0, "DescriptorPool must have space for at least one set");
+        let pool_sizes = descriptor_set_layout
+            .descriptors()
+            .iter()
+            .map(|descriptor| vk::DescriptorPoolSize {
+                ty: descriptor.descriptor_type.into(),
+                descriptor_count: descriptor.descriptor_count * max_sets,
+            })
+            .collect::<Vec<_>>();
+        let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo::builder().max_sets(max_sets).pool_sizes(&pool_sizes);
+        let descriptor_pool = unsafe { device.as_raw_vulkan().create_descriptor_pool(&descriptor_pool_create_info, None)? };
+        let debug_info = debug_info.with_vulkan_ptr(descriptor_pool);
+        device.set_debug_name(descriptor_pool, &debug_info);
+        Ok(Self {
+            descriptor_pool,
+            descriptor_set_layout: descriptor_set_layout.clone(),
+            max_sets,
+            allocated_sets: 0,
+            device: device.clone(),
+            debug_info,
+        })
+    }
+
+    /// Allocates a descriptor set with the [`DescriptorSetLayout`] of the `DescriptorPool`
+    pub fn allocate_descriptor_set(&mut self) -> crate::Result<vk::DescriptorSet> {
+        if self.allocated_sets >= self.max_sets {
+            return Err(Error::DescriptorPoolDoesntHaveEnoughSpace);
+        }
+        let descriptor_set_layouts = [*self.descriptor_set_layout.as_raw_vulkan()];
+        let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(self.descriptor_pool)
+            .set_layouts(&descriptor_set_layouts);
+        let descriptor_set = unsafe {
+            self.device
+                .as_raw_vulkan()
+                .allocate_descriptor_sets(&descriptor_set_allocate_info)?[0]
+        };
+        self.allocated_sets += 1;
+        Ok(descriptor_set)
+    }
+
+    /// Returns the [`DescriptorSetLayout`] of the descriptor sets that are allocated from the `DescriptorPool`
+    pub fn descriptor_set_layout(&self) -> &Arc<DescriptorSetLayout> {
+        &self.descriptor_set_layout
+    }
+}
+
+impl AsRawVulkan for DescriptorPool {
+    type Output = vk::DescriptorPool;
+    fn as_raw_vulkan(&self) -> &Self::Output {
+        &self.descriptor_pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod allocate_descriptor_set {
+        use std::sync::Arc;
+
+        use jeriya_shared::debug_info;
+
+        use crate::{descriptor_pool::DescriptorPool, descriptor_set_layout::DescriptorSetLayout, device::TestFixtureDevice, Error};
+
+        #[test]
+        fn exceeds_max_sets() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let descriptor_set_layout = Arc::new(
+                DescriptorSetLayout::builder()
+                    .push_storage_buffer::<u32>(1, 1)
+                    .build_for_descriptor_pool(&test_fixture_device.device)
+                    .unwrap(),
+            );
+            let mut descriptor_pool = DescriptorPool::new(
+                &test_fixture_device.device,
+                &descriptor_set_layout,
+                1,
+                debug_info!("my_descriptor_pool"),
+            )
+            .unwrap();
+            descriptor_pool.allocate_descriptor_set().unwrap();
+            assert!(matches!(
+                descriptor_pool.allocate_descriptor_set(),
+                Err(Error::DescriptorPoolDoesntHaveEnoughSpace)
+            ));
+        }
+    }
+}
@@ -5,6 +5,8 @@ use ash::vk::{self};
 use crate::{
     descriptor::{Descriptor, DescriptorType},
     device::Device,
+    shader_interface::sets,
+    spirv_reflection::{ReflectedBinding, ShaderReflection},
     AsRawVulkan,
 };
 
@@ -25,22 +27,26 @@ impl Drop for DescriptorSetLayout {
 }
 
 impl DescriptorSetLayout {
-    /// Creates a new `DescriptorSetLayout` from the given [`Descriptor`]s
-    fn new(device: &Arc<Device>, descriptors: Vec<Descriptor>) -> crate::Result<Self> {
+    /// Creates a new `DescriptorSetLayout` from the given [`Descriptor`]s and the shader stages in which they are used
+    fn new(
+        device: &Arc<Device>,
+        descriptors: Vec<(Descriptor, vk::ShaderStageFlags)>,
+        flags: vk::DescriptorSetLayoutCreateFlags,
+    ) -> crate::Result<Self> {
         let descriptor_set_layout_bindings = descriptors
             .iter()
-            .map(|descriptor| vk::DescriptorSetLayoutBinding {
+            .map(|(descriptor, stage_flags)| vk::DescriptorSetLayoutBinding {
                 binding: descriptor.binding,
                 descriptor_type: descriptor.descriptor_type.into(),
                 descriptor_count: descriptor.descriptor_count,
-                stage_flags: vk::ShaderStageFlags::ALL_GRAPHICS | vk::ShaderStageFlags::COMPUTE,
+                stage_flags: *stage_flags,
                 ..Default::default()
             })
             .collect::<Vec<_>>();
         let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo {
             binding_count: descriptor_set_layout_bindings.len() as u32,
             p_bindings: descriptor_set_layout_bindings.as_ptr(),
-            flags: vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR,
+            flags,
             ..Default::default()
         };
         let descriptor_set_layout = unsafe {
@@ -49,7 +55,7 @@ impl DescriptorSetLayout {
                 .create_descriptor_set_layout(&descriptor_set_layout_create_info, None)?
         };
         Ok(Self {
-            descriptors,
+            descriptors: descriptors.into_iter().map(|(descriptor, _)| descriptor).collect(),
             descriptor_set_layout,
             device: device.clone(),
         })
@@ -60,12 +66,93 @@ impl DescriptorSetLayout {
         DescriptorSetLayoutBuilder::default()
     }
 
+    /// Creates a new push descriptor `DescriptorSetLayout` that contains the bindings that are declared in set
+    /// [`sets::PUSH_DESCRIPTORS`] of the given shaders.
+    ///
+    /// Every declared binding must be contained in the `available_descriptors` with the same descriptor type and count.
+    /// Otherwise the buffer that is bound at runtime wouldn't match the shader's expectations. The bindings in set
+    /// [`sets::CACHED_DESCRIPTORS`] are checked against the `cached_descriptors` in the same way but are not part of
+    /// the layout because they are bound with a cached descriptor set.
+    ///
+    /// Shaders that have been written before the frame buffers moved to set [`sets::CACHED_DESCRIPTORS`] declare them in
+    /// set [`sets::PUSH_DESCRIPTORS`]. These bindings are matched against the `cached_descriptors` and become part of the
+    /// layout so that the buffers are pushed for them.
+    pub fn from_reflection<'a>(
+        device: &Arc<Device>,
+        reflections: impl IntoIterator<Item = &'a ShaderReflection>,
+        available_descriptors: &[Descriptor],
+        cached_descriptors: &[Descriptor],
+    ) -> crate::Result<Self> {
+        let mut descriptors = Vec::new();
+        for (reflected_binding, stage_flags) in ShaderReflection::merge(reflections)? {
+            match reflected_binding.set {
+                sets::PUSH_DESCRIPTORS => {
+                    let is_cached = !contains_binding(available_descriptors, reflected_binding.binding)
+                        && contains_binding(cached_descriptors, reflected_binding.binding);
+                    let descriptors_of_set = if is_cached { cached_descriptors } else { available_descriptors };
+                    let descriptor = matching_descriptor(&reflected_binding, descriptors_of_set)?;
+                    descriptors.push((descriptor.clone(), stage_flags));
+                }
+                sets::CACHED_DESCRIPTORS => {
+                    matching_descriptor(&reflected_binding, cached_descriptors)?;
+                }
+                set => {
+                    return Err(crate::Error::DescriptorMismatch {
+                        binding: reflected_binding.binding,
+                        details: format!(
+                            "only the sets {} and {} are supported but the shader uses set {set}",
+                            sets::PUSH_DESCRIPTORS,
+                            sets::CACHED_DESCRIPTORS
+                        ),
+                    });
+                }
+            }
+        }
+        Self::new(device, descriptors, vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR)
+    }
+
     /// Returns the [`Descriptor`]s of the `DescriptorSetLayout`
     pub fn descriptors(&self) -> &[Descriptor] {
         &self.descriptors
     }
 }
 
+/// Checks if one of the `descriptors` has the given `binding`
+fn contains_binding(descriptors: &[Descriptor], binding: u32) -> bool {
+    descriptors.iter().any(|descriptor| descriptor.binding == binding)
+}
+
+/// Returns the [`Descriptor`] that matches the `reflected_binding` in binding, descriptor type and count
+fn matching_descriptor<'a>(reflected_binding: &ReflectedBinding, descriptors: &'a [Descriptor]) -> crate::Result<&'a Descriptor> {
+    let binding = reflected_binding.binding;
+    let Some(descriptor) = descriptors.iter().find(|descriptor| descriptor.binding == binding) else {
+        return Err(crate::Error::DescriptorMismatch {
+            binding,
+            details: "no buffer is bound to it".to_owned(),
+        });
+    };
+    let descriptor_type = vk::DescriptorType::from(descriptor.descriptor_type);
+    if descriptor_type != reflected_binding.descriptor_type {
+        return Err(crate::Error::DescriptorMismatch {
+            binding,
+            details: format!(
+                "the shader expects {:?} but {descriptor_type:?} is bound",
+                reflected_binding.descriptor_type
+            ),
+        });
+    }
+    if descriptor.descriptor_count != reflected_binding.descriptor_count {
+        return Err(crate::Error::DescriptorMismatch {
+            binding,
+            details: format!(
+                "the shader expects {} descriptors but {} are bound",
+                reflected_binding.descriptor_count, descriptor.descriptor_count
+            ),
+        });
+    }
+    Ok(descriptor)
+}
+
 impl AsRawVulkan for DescriptorSetLayout {
     type Output = vk::DescriptorSetLayout;
     fn as_raw_vulkan(&self) -> &Self::Output {
@@ -93,9 +180,23 @@ impl DescriptorSetLayoutBuilder {
         self
     }
 
-    /// Creates the [`DescriptorSetLayout`] from the given [`Descriptor`]s
-    pub fn build(self, device: &Arc<Device>) -> crate::Result<DescriptorSetLayout> {
-        DescriptorSetLayout::new(device, self.descriptors)
+    /// Adds a [`Descriptor`] of type combined image sampler to the `DescriptorSetLayout`
+    pub fn push_combined_image_sampler(mut self, binding: u32, count: u32) -> Self {
+        self.descriptors
+            .push(Descriptor::new(binding, DescriptorType::CombinedImageSampler, count));
+        self
+    }
+
+    /// Creates the [`DescriptorSetLayout`] from the given [`Descriptor`]s for descriptor sets that are allocated from a [`DescriptorPool`](crate::descriptor_pool::DescriptorPool)
+    pub fn build_for_descriptor_pool(self, device: &Arc<Device>) -> crate::Result<DescriptorSetLayout> {
+        let stage_flags = vk::ShaderStageFlags::ALL_GRAPHICS | vk::ShaderStageFlags::COMPUTE;
+        let descriptors = self.descriptors.into_iter().map(|descriptor| (descriptor, stage_flags)).collect();
+        DescriptorSetLayout::new(device, descriptors, vk::DescriptorSetLayoutCreateFlags::empty())
+    }
+
+    /// Returns the [`Descriptor`]s that have been added to the builder
+    pub fn descriptors(&self) -> &[Descriptor] {
+        &self.descriptors
     }
 }
 
@@ -110,8 +211,121 @@ mod tests {
             let _ = DescriptorSetLayout::builder()
                 .push_uniform_buffer::<f32>(0, 1)
                 .push_storage_buffer::<u32>(1, 1)
-                .build(&test_fixture_device.device)
+                .build_for_descriptor_pool(&test_fixture_device.device)
                 .unwrap();
         }
     }
+
+    mod from_reflection {
+        use ash::vk;
+
+        use crate::{
+            descriptor_set_layout::DescriptorSetLayout,
+            device::TestFixtureDevice,
+            spirv_reflection::{ReflectedBinding, ShaderReflection},
+            Error,
+        };
+
+        fn reflection(descriptor_type: vk::DescriptorType) -> ShaderReflection {
+            reflection_in_set(0, descriptor_type)
+        }
+
+        fn reflection_in_set(set: u32, descriptor_type: vk::DescriptorType) -> ShaderReflection {
+            ShaderReflection {
+                stage: vk::ShaderStageFlags::COMPUTE,
+                bindings: vec![ReflectedBinding {
+                    set,
+                    binding: 1,
+                    descriptor_type,
+                    descriptor_count: 1,
+                }],
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn smoke() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let available = DescriptorSetLayout::builder()
+                .push_uniform_buffer::<f32>(0, 1)
+                .push_storage_buffer::<u32>(1, 1);
+            let descriptor_set_layout = DescriptorSetLayout::from_reflection(
+                &test_fixture_device.device,
+                [&reflection(vk::DescriptorType::STORAGE_BUFFER)],
+                available.descriptors(),
+                &[],
+            )
+            .unwrap();
+            assert_eq!(descriptor_set_layout.descriptors().len(), 1);
+            assert_eq!(descriptor_set_layout.descriptors()[0].binding, 1);
+        }
+
+        #[test]
+        fn type_mismatch() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let available = DescriptorSetLayout::builder().push_storage_buffer::<u32>(1, 1);
+            let result = DescriptorSetLayout::from_reflection(
+                &test_fixture_device.device,
+                [&reflection(vk::DescriptorType::UNIFORM_BUFFER)],
+                available.descriptors(),
+                &[],
+            );
+            assert!(matches!(result, Err(Error::DescriptorMismatch { binding: 1, .. })));
+        }
+
+        #[test]
+        fn missing_binding() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let available = DescriptorSetLayout::builder().push_storage_buffer::<u32>(0, 1);
+            let result = DescriptorSetLayout::from_reflection(
+                &test_fixture_device.device,
+                [&reflection(vk::DescriptorType::STORAGE_BUFFER)],
+                available.descriptors(),
+                &[],
+            );
+            assert!(matches!(result, Err(Error::DescriptorMismatch { binding: 1, .. })));
+        }
+
+        #[test]
+        fn cached_binding() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let cached = DescriptorSetLayout::builder().push_storage_buffer::<u32>(1, 1);
+            let descriptor_set_layout = DescriptorSetLayout::from_reflection(
+                &test_fixture_device.device,
+                [&reflection_in_set(2, vk::DescriptorType::STORAGE_BUFFER)],
+                &[],
+                cached.descriptors(),
+            )
+            .unwrap();
+            assert!(descriptor_set_layout.descriptors().is_empty());
+        }
+
+        #[test]
+        fn cached_binding_in_push_descriptor_set() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let cached = DescriptorSetLayout::builder().push_storage_buffer::<u32>(1, 1);
+            let descriptor_set_layout = DescriptorSetLayout::from_reflection(
+                &test_fixture_device.device,
+                [&reflection(vk::DescriptorType::STORAGE_BUFFER)],
+                &[],
+                cached.descriptors(),
+            )
+            .unwrap();
+            assert_eq!(descriptor_set_layout.descriptors().len(), 1);
+            assert_eq!(descriptor_set_layout.descriptors()[0].binding, 1);
+        }
+
+        #[test]
+        fn unsupported_set() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let available = DescriptorSetLayout::builder().push_storage_buffer::<u32>(1, 1);
+            let result = DescriptorSetLayout::from_reflection(
+                &test_fixture_device.device,
+                [&reflection_in_set(3, vk::DescriptorType::STORAGE_BUFFER)],
+                available.descriptors(),
+                available.descriptors(),
+            );
+            assert!(matches!(result, Err(Error::DescriptorMismatch { binding: 1, .. })));
+        }
+    }
 }
@@ -1,4 +1,13 @@
-use std::{collections::BTreeMap, iter, sync::Arc, thread};
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    iter,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
 
 use ash::{
     extensions::khr,
@@ -6,9 +15,18 @@ use ash::{
 };
 
 use crate::{
-    instance::Instance, physical_device::PhysicalDevice, queue_plan::QueuePlan, AsRawVulkan, Error, Extensions, PhysicalDeviceFeature,
+    instance::Instance,
+    memory_allocator::{MemoryAllocation, MemoryAllocator, MemoryStatistics},
+    optional_features::OptionalFeatures,
+    physical_device::PhysicalDevice,
+    queue_plan::QueuePlan,
+    AsRawVulkan, Error, Extensions, PhysicalDeviceFeature,
+};
+use jeriya_shared::{
+    features,
+    log::{info, trace, warn},
+    DebugInfo,
 };
-use jeriya_shared::log::{info, trace};
 
 pub struct Device {
     device: ash::Device,
@@ -16,52 +34,61 @@ pub struct Device {
     pub extensions: Extensions,
     instance: Arc<Instance>,
     pub queue_plan: QueuePlan,
+    /// The [`OptionalFeatures`] that have been enabled on the device
+    pub optional_features: OptionalFeatures,
+    memory_allocator: MemoryAllocator,
+    /// Number of bytes that have been copied from host visible buffers into device local resources
+    uploaded_bytes: AtomicU64,
 }
 
 impl Drop for Device {
     fn drop(&mut self) {
-        unsafe { self.device.destroy_device(None) };
+        unsafe {
+            self.memory_allocator.destroy(&self.device);
+            self.device.destroy_device(None)
+        };
     }
 }
 
 impl Device {
     /// Creates a new `Device` based on the given [`PhysicalDevice`].
     pub fn new(physical_device: PhysicalDevice, instance: &Arc<Instance>, queue_plan: QueuePlan) -> crate::Result<Arc<Self>> {
-        let features = {
-            let available_features = unsafe {
-                instance
-                    .as_raw_vulkan()
-                    .get_physical_device_features(*physical_device.as_raw_vulkan())
-            };
-            if available_features.wide_lines != vk::TRUE {
-                return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::WideLines));
-            }
-            if available_features.shader_int64 != vk::TRUE {
-                return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::ShaderInt64));
-            }
-            if available_features.multi_draw_indirect != vk::TRUE {
-                return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::MultiDrawIndirect));
-            }
-            vk::PhysicalDeviceFeatures::builder()
-                .wide_lines(true)
-                .shader_int64(true)
-                .multi_draw_indirect(true)
+        let available_features = unsafe {
+            instance
+                .as_raw_vulkan()
+                .get_physical_device_features(*physical_device.as_raw_vulkan())
         };
+        if available_features.shader_int64 != vk::TRUE {
+            return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::ShaderInt64));
+        }
+        if available_features.multi_draw_indirect != vk::TRUE {
+            return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::MultiDrawIndirect));
+        }
 
         // Check for Vulkan 1.2
-        let mut physical_device_vulkan_1_2_features = PhysicalDeviceVulkan12Features::builder().draw_indirect_count(true).build();
+        let mut available_vulkan_1_2_features = PhysicalDeviceVulkan12Features::default();
         let mut features2 = PhysicalDeviceFeatures2::builder()
-            .push_next(&mut physical_device_vulkan_1_2_features)
+            .push_next(&mut available_vulkan_1_2_features)
             .build();
         unsafe {
             instance
                 .as_raw_vulkan()
                 .get_physical_device_features2(*physical_device.as_raw_vulkan(), &mut features2);
         }
-        if physical_device_vulkan_1_2_features.draw_indirect_count != vk::TRUE {
+        if available_vulkan_1_2_features.draw_indirect_count != vk::TRUE {
             return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::DrawIndirectCount));
         };
 
+        let optional_features = OptionalFeatures::negotiate(&available_features, &available_vulkan_1_2_features);
+        let features = vk::PhysicalDeviceFeatures::builder()
+            .shader_int64(true)
+            .multi_draw_indirect(true)
+            .shader_clip_distance(optional_features.shader_clip_distance)
+            .wide_lines(optional_features.wide_lines)
+            .pipeline_statistics_query(optional_features.pipeline_statistics_query)
+            .sampler_anisotropy(optional_features.sampler_anisotropy)
+            .large_points(optional_features.large_points);
+
         // Check for shader draw parameters
         let mut shader_draw_parameters = PhysicalDeviceShaderDrawParametersFeatures::builder()
             .shader_draw_parameters(true)
@@ -130,7 +157,7 @@ impl Device {
             khr::Maintenance1::name().as_ptr(),
         ];
 
-        let mut physical_device_vulkan_1_2_features = PhysicalDeviceVulkan12Features::builder().draw_indirect_count(true).build();
+        let mut physical_device_vulkan_1_2_features = Self::vulkan_1_2_features(&optional_features);
 
         let mut shader_draw_parameters = PhysicalDeviceShaderDrawParametersFeatures::builder()
             .shader_draw_parameters(true)
@@ -156,9 +183,27 @@ impl Device {
             instance: instance.clone(),
             extensions,
             queue_plan,
+            optional_features,
+            memory_allocator: MemoryAllocator::default(),
+            uploaded_bytes: AtomicU64::new(0),
         }))
     }
 
+    /// Vulkan 1.2 features that are required by the renderer and the ones of the enabled [`OptionalFeatures`]
+    fn vulkan_1_2_features(optional_features: &OptionalFeatures) -> PhysicalDeviceVulkan12Features {
+        let descriptor_indexing = optional_features.descriptor_indexing;
+        PhysicalDeviceVulkan12Features::builder()
+            .draw_indirect_count(true)
+            .descriptor_indexing(descriptor_indexing)
+            .runtime_descriptor_array(descriptor_indexing)
+            .shader_sampled_image_array_non_uniform_indexing(descriptor_indexing)
+            .descriptor_binding_partially_bound(descriptor_indexing)
+            .descriptor_binding_variable_descriptor_count(descriptor_indexing)
+            .descriptor_binding_sampled_image_update_after_bind(descriptor_indexing)
+            .descriptor_binding_update_unused_while_pending(descriptor_indexing)
+            .build()
+    }
+
     /// Wait for a device to become idle
     pub fn wait_for_idle(&self) -> crate::Result<()> {
         trace! {
@@ -169,11 +214,83 @@ impl Device {
         Ok(unsafe { self.device.device_wait_idle() }?)
     }
 
+    /// Sets the name of the `debug_info` as the object name of the Vulkan `handle` so that it shows up in tools like RenderDoc.
+    ///
+    /// Does nothing when the `labeling` feature is disabled or when `VK_EXT_debug_utils` hasn't been loaded.
+    pub fn set_debug_name<H: vk::Handle>(&self, handle: H, debug_info: &DebugInfo) {
+        if !features::LABELING {
+            return;
+        }
+        let (Some(debug_utils), Some(name)) = (self.instance.debug_utils.as_ref(), debug_info.name.as_ref()) else {
+            return;
+        };
+        let Ok(name) = CString::new(name.as_bytes()) else {
+            warn!("Failed to set the debug name '{name}' because it contains a nul byte");
+            return;
+        };
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+            .object_type(H::TYPE)
+            .object_handle(handle.as_raw())
+            .object_name(&name);
+        if let Err(err) = unsafe { debug_utils.set_debug_utils_object_name(self.device.handle(), &name_info) } {
+            warn!("Failed to set the debug name of {}: {err}", debug_info.format_one_line());
+        }
+    }
+
     /// Returns the [`Instance`] on which the `Device` was created.
     pub fn instance(&self) -> &Arc<Instance> {
         &self.instance
     }
 
+    /// Suballocates memory with the given `memory_properties` from the [`MemoryAllocator`] of the `Device`.
+    ///
+    /// The `debug_info` of the resource is reported in the [`MemoryStatistics`].
+    ///
+    /// # Safety
+    ///
+    /// The [`MemoryAllocation`] must be returned with [`Device::free_memory`] before the `Device` is dropped.
+    pub unsafe fn allocate_memory(
+        &self,
+        memory_requirements: &vk::MemoryRequirements,
+        memory_properties: vk::MemoryPropertyFlags,
+        debug_info: &DebugInfo,
+    ) -> crate::Result<MemoryAllocation> {
+        let memory_type_index = self
+            .find_memorytype_index(memory_requirements, memory_properties)
+            .ok_or(Error::UnsupportedMemoryType(*memory_requirements))?;
+        let host_visible = self.physical_device.physical_device_memory_properties.memory_types[memory_type_index as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+        self.memory_allocator
+            .allocate(&self.device, memory_requirements, memory_type_index, host_visible, debug_info)
+    }
+
+    /// Returns the [`MemoryAllocation`] to the [`MemoryAllocator`] of the `Device`.
+    ///
+    /// # Safety
+    ///
+    /// The resource that is bound to the `allocation` must not be in use anymore.
+    pub unsafe fn free_memory(&self, allocation: MemoryAllocation) {
+        self.memory_allocator.free(&self.device, allocation);
+    }
+
+    /// Returns the [`MemoryStatistics`] of the memory that has been allocated for the resources of the `Device`
+    pub fn memory_statistics(&self) -> MemoryStatistics {
+        self.memory_allocator.statistics()
+    }
+
+    /// Adds `byte_size` to the number of bytes that have been uploaded to the `Device`
+    pub fn record_upload(&self, byte_size: usize) {
+        self.uploaded_bytes.fetch_add(byte_size as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the number of bytes that have been uploaded to the `Device` since it was created
+    ///
+    /// The difference between two calls is the upload bandwidth in between.
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.uploaded_bytes.load(Ordering::Relaxed)
+    }
+
     /// Find a memory type for the given memory requirements
     pub fn find_memorytype_index(
         &self,
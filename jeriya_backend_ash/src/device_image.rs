@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use ash::vk;
+use jeriya_shared::{AsDebugInfo, DebugInfo};
+
+use crate::{command_buffer::CommandBufferDependency, device::Device, AsRawVulkan, DebugInfoAshExtension};
+
+/// Device local 2D color image with a mip chain that can be sampled in shaders
+///
+/// The mip levels are not expected to be part of the uploaded data. Instead, only the first level is
+/// copied into the image and the remaining levels are generated with
+/// [`CommandBufferBuilder::generate_mipmaps`](crate::command_buffer_builder::CommandBufferBuilder::generate_mipmaps).
+pub struct DeviceImage {
+    image: vk::Image,
+    image_memory: vk::DeviceMemory,
+    image_view: vk::ImageView,
+    extent: vk::Extent2D,
+    mip_level_count: u32,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl Drop for DeviceImage {
+    fn drop(&mut self) {
+        unsafe {
+            let device = self.device.as_raw_vulkan();
+            device.destroy_image_view(self.image_view, None);
+            device.free_memory(self.image_memory, None);
+            device.destroy_image(self.image, None);
+        }
+    }
+}
+
+impl CommandBufferDependency for DeviceImage {}
+
+impl AsDebugInfo for DeviceImage {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl AsRawVulkan for DeviceImage {
+    type Output = vk::Image;
+    fn as_raw_vulkan(&self) -> &Self::Output {
+        &self.image
+    }
+}
+
+impl DeviceImage {
+    /// Creates a new `DeviceImage` with the full mip chain for the given `extent`
+    pub fn new(device: &Arc<Device>, extent: vk::Extent2D, format: vk::Format, debug_info: DebugInfo) -> crate::Result<Self> {
+        Self::with_mip_level_count(device, extent, format, Self::full_mip_level_count(extent), debug_info)
+    }
+
+    /// Creates a new `DeviceImage` with the given number of mip levels
+    ///
+    /// When more than one mip level is requested, the format has to support linear blitting so that the
+    /// levels can be generated on the GPU. Otherwise [`Error::UnsupportedImageFormat`](crate::Error::UnsupportedImageFormat) is returned.
+    pub fn with_mip_level_count(
+        device: &Arc<Device>,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_level_count: u32,
+        debug_info: DebugInfo,
+    ) -> crate::Result<Self> {
+        let usage = vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED;
+        Self::with_usage(device, extent, format, mip_level_count, usage, debug_info)
+    }
+
+    /// Creates a new `DeviceImage` with a single mip level that can be used as the color attachment of a render pass
+    ///
+    /// The image can be sampled and copied to the host after it has been rendered.
+    pub fn new_color_attachment(
+        device: &Arc<Device>,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        debug_info: DebugInfo,
+    ) -> crate::Result<Self> {
+        let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::SAMPLED;
+        Self::with_usage(device, extent, format, 1, usage, debug_info)
+    }
+
+    fn with_usage(
+        device: &Arc<Device>,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_level_count: u32,
+        usage: vk::ImageUsageFlags,
+        debug_info: DebugInfo,
+    ) -> crate::Result<Self> {
+        jeriya_shared::assert!(extent.width > 0 && extent.height > 0, "DeviceImage must have a non-zero extent");
+        jeriya_shared::assert!(
+            (1..=Self::full_mip_level_count(extent)).contains(&mip_level_count),
+            "mip_level_count exceeds the number of levels of the extent"
+        );
+
+        if mip_level_count > 1 && !Self::supports_mipmap_generation(device, format) {
+            return Err(crate::Error::UnsupportedImageFormat(format));
+        }
+
+        // Image
+        let image_create_info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(mip_level_count)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let image = unsafe { device.as_raw_vulkan().create_image(&image_create_info, None)? };
+
+        // Image Memory
+        let image_memory = {
+            let image_memory_requirements = unsafe { device.as_raw_vulkan().get_image_memory_requirements(image) };
+            let image_memory_index = device
+                .find_memorytype_index(&image_memory_requirements, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+                .ok_or_else(|| crate::Error::UnsupportedMemoryType(image_memory_requirements))?;
+            let image_allocate_info = vk::MemoryAllocateInfo::builder()
+                .allocation_size(image_memory_requirements.size)
+                .memory_type_index(image_memory_index);
+            let image_memory = unsafe { device.as_raw_vulkan().allocate_memory(&image_allocate_info, None)? };
+            unsafe {
+                device.as_raw_vulkan().bind_image_memory(image, image_memory, 0)?;
+            }
+            image_memory
+        };
+
+        // Image View
+        let image_view_info = vk::ImageViewCreateInfo::builder()
+            .subresource_range(
+                vk::ImageSubresourceRange::builder()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .level_count(mip_level_count)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image(image)
+            .format(format)
+            .view_type(vk::ImageViewType::TYPE_2D);
+        let image_view = unsafe { device.as_raw_vulkan().create_image_view(&image_view_info, None)? };
+
+        let debug_info = debug_info.with_vulkan_ptr(image);
+        device.set_debug_name(image, &debug_info);
+        device.set_debug_name(image_view, &debug_info);
+        Ok(Self {
+            image,
+            image_memory,
+            image_view,
+            extent,
+            mip_level_count,
+            device: device.clone(),
+            debug_info,
+        })
+    }
+
+    /// Returns the number of mip levels that are needed to downsample the `extent` to 1x1
+    pub fn full_mip_level_count(extent: vk::Extent2D) -> u32 {
+        u32::BITS - extent.width.max(extent.height).max(1).leading_zeros()
+    }
+
+    /// Returns whether the mip levels of images with the given `format` can be generated by blitting
+    pub fn supports_mipmap_generation(device: &Device, format: vk::Format) -> bool {
+        let format_properties = unsafe {
+            device
+                .instance()
+                .as_raw_vulkan()
+                .get_physical_device_format_properties(*device.physical_device.as_raw_vulkan(), format)
+        };
+        format_properties.optimal_tiling_features.contains(
+            vk::FormatFeatureFlags::BLIT_SRC | vk::FormatFeatureFlags::BLIT_DST | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+        )
+    }
+
+    /// Returns the extent of the mip level with the given index
+    pub fn mip_level_extent(&self, mip_level: u32) -> vk::Extent2D {
+        vk::Extent2D {
+            width: (self.extent.width >> mip_level).max(1),
+            height: (self.extent.height >> mip_level).max(1),
+        }
+    }
+
+    /// Returns the extent of the first mip level
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Returns the number of mip levels of the image
+    pub fn mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    /// Returns the view of the image that covers all mip levels
+    pub fn image_view(&self) -> vk::ImageView {
+        self.image_view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod new {
+        use ash::vk;
+        use jeriya_shared::debug_info;
+
+        use crate::{device::TestFixtureDevice, device_image::DeviceImage};
+
+        #[test]
+        fn smoke() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let extent = vk::Extent2D { width: 16, height: 8 };
+            let device_image = DeviceImage::new(
+                &test_fixture_device.device,
+                extent,
+                vk::Format::R8G8B8A8_UNORM,
+                debug_info!("my_device_image"),
+            )
+            .unwrap();
+            assert_eq!(device_image.extent(), extent);
+            assert_eq!(device_image.mip_level_count(), 5);
+            assert_eq!(device_image.mip_level_extent(4), vk::Extent2D { width: 1, height: 1 });
+        }
+    }
+
+    mod generate_mipmaps {
+        use std::sync::Arc;
+
+        use ash::vk;
+        use jeriya_shared::debug_info;
+
+        use crate::{
+            buffer::BufferUsageFlags, command_buffer::tests::TestFixtureCommandBuffer, command_buffer_builder::CommandBufferBuilder,
+            device::TestFixtureDevice, device_image::DeviceImage, host_visible_buffer::HostVisibleBuffer,
+        };
+
+        #[test]
+        fn smoke() {
+            // Fixtures
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let mut test_fixture_command_buffer = TestFixtureCommandBuffer::new(&test_fixture_device).unwrap();
+            let extent = vk::Extent2D { width: 16, height: 8 };
+            let device_image = Arc::new(
+                DeviceImage::new(
+                    &test_fixture_device.device,
+                    extent,
+                    vk::Format::R8G8B8A8_UNORM,
+                    debug_info!("my_device_image"),
+                )
+                .unwrap(),
+            );
+            let texels = vec![255u8; (extent.width * extent.height * 4) as usize];
+            let host_visible_buffer = Arc::new(
+                HostVisibleBuffer::new(
+                    &test_fixture_device.device,
+                    &texels,
+                    BufferUsageFlags::TRANSFER_SRC_BIT,
+                    debug_info!("my_host_visible_buffer"),
+                )
+                .unwrap(),
+            );
+
+            // Record and submit
+            let mut command_buffer_builder =
+                CommandBufferBuilder::begin(&test_fixture_device.device, &mut test_fixture_command_buffer.command_buffer).unwrap();
+            command_buffer_builder
+                .copy_buffer_to_image(&host_visible_buffer, &device_image)
+                .generate_mipmaps(&device_image);
+            test_fixture_command_buffer
+                .queue
+                .submit_and_wait_idle(test_fixture_command_buffer.command_buffer)
+                .unwrap();
+        }
+    }
+
+    mod full_mip_level_count {
+        use ash::vk;
+
+        use crate::device_image::DeviceImage;
+
+        #[test]
+        fn smoke() {
+            let count = |width, height| DeviceImage::full_mip_level_count(vk::Extent2D { width, height });
+            assert_eq!(count(1, 1), 1);
+            assert_eq!(count(2, 1), 2);
+            assert_eq!(count(256, 256), 9);
+            assert_eq!(count(300, 17), 9);
+        }
+    }
+}
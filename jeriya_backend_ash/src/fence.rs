@@ -39,6 +39,7 @@ impl Fence {
         }
         let fence = unsafe { device.as_raw_vulkan().create_fence(&fence_create_info, None)? };
         let debug_info = debug_info.with_vulkan_ptr(fence);
+        device.set_debug_name(fence, &debug_info);
         Ok(Self {
             fence,
             device: device.clone(),
@@ -0,0 +1,310 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use ash::vk;
+use jeriya_shared::{log::warn, parking_lot::Mutex};
+
+use crate::{host_visible_buffer::HostVisibleBuffer, Error};
+
+/// Number of captured frames that can wait for the frame capture thread before further frames are dropped
+const FRAME_CAPTURE_QUEUE_LENGTH: usize = 4;
+
+/// Destination of the frames that are captured with [`AshBackend::start_frame_capture`](crate::AshBackend::start_frame_capture)
+pub enum FrameCaptureTarget {
+    /// Writes the frames into the directory as `frame_000000.png`, `frame_000001.png`, ...
+    PngSequence(PathBuf),
+    /// Passes the frames to the callback. The callback is called on the thread of the frame capture.
+    Callback(Box<dyn FnMut(CapturedFrame) + Send>),
+}
+
+/// Determines which frames of a window are captured and where they are written to
+pub struct FrameCaptureConfig {
+    pub target: FrameCaptureTarget,
+    /// Every `interval`th presented frame is captured. An `interval` of 0 is treated like 1.
+    pub interval: u32,
+    /// Time that passes for the renderer between two presented frames while capturing.
+    ///
+    /// When set, the motions on the GPU advance by exactly this step per presented frame regardless of how long it
+    /// takes to render and write the frames so that flythroughs are recorded deterministically. `None` keeps the
+    /// wall clock.
+    pub time_step: Option<Duration>,
+}
+
+impl FrameCaptureConfig {
+    /// Captures every frame into a PNG sequence in the `directory` as if it were rendered at `frames_per_second`
+    pub fn png_sequence(directory: impl Into<PathBuf>, frames_per_second: u32) -> Self {
+        Self {
+            target: FrameCaptureTarget::PngSequence(directory.into()),
+            interval: 1,
+            time_step: Some(Duration::from_secs_f64(1.0 / frames_per_second.max(1) as f64)),
+        }
+    }
+}
+
+/// Frame that has been captured from the swapchain
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    /// Index of the frame in the capture starting at 0
+    pub sequence_index: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed rows of RGBA texels starting with the top row
+    pub rgba: Vec<u8>,
+}
+
+/// Handle to the thread that writes the captured frames
+pub struct FrameCaptureHandle {
+    join_handle: JoinHandle<crate::Result<u64>>,
+}
+
+impl FrameCaptureHandle {
+    /// Blocks until the capture has been stopped and all captured frames have been written. Returns the number of frames.
+    pub fn wait(self) -> crate::Result<u64> {
+        self.join_handle.join().expect("frame capture thread panicked")
+    }
+}
+
+/// Frame capture that is running in a presenter
+pub(crate) struct FrameCapture {
+    interval: u32,
+    time_step: Option<Duration>,
+    /// Time of the renderer when the capture was started
+    start_time: f32,
+    /// Number of frames that have been presented since the capture was started
+    presented_frames: u64,
+    sender: SyncSender<CapturedFrame>,
+}
+
+impl FrameCapture {
+    /// Starts the thread that writes the frames to the target of the `config`
+    ///
+    /// Frames are dropped when the thread falls behind by more than [`FRAME_CAPTURE_QUEUE_LENGTH`] frames.
+    pub fn start(config: FrameCaptureConfig, start_time: f32) -> (Self, FrameCaptureHandle) {
+        let (sender, receiver) = mpsc::sync_channel(FRAME_CAPTURE_QUEUE_LENGTH);
+        let target = config.target;
+        let join_handle = thread::Builder::new()
+            .name("frame-capture-thread".to_owned())
+            .spawn(move || run_frame_capture_thread(target, receiver))
+            .expect("failed to spawn frame capture thread");
+        let frame_capture = Self {
+            interval: config.interval.max(1),
+            time_step: config.time_step,
+            start_time,
+            presented_frames: 0,
+            sender,
+        };
+        (frame_capture, FrameCaptureHandle { join_handle })
+    }
+
+    /// Returns the time of the renderer for the current frame
+    pub fn time(&self, wall_time: f32) -> f32 {
+        match self.time_step {
+            Some(time_step) => self.start_time + (self.presented_frames as f64 * time_step.as_secs_f64()) as f32,
+            None => wall_time,
+        }
+    }
+
+    /// Returns the index in the sequence when the current frame is captured and `None` when it's skipped
+    pub fn sequence_index(&self) -> Option<u64> {
+        let interval = self.interval as u64;
+        self.presented_frames
+            .is_multiple_of(interval)
+            .then_some(self.presented_frames / interval)
+    }
+
+    /// Returns the sender to which the captured frames are sent
+    pub fn sender(&self) -> &SyncSender<CapturedFrame> {
+        &self.sender
+    }
+
+    /// Advances the capture after the current frame has been presented
+    pub fn next_frame(&mut self) {
+        self.presented_frames += 1;
+    }
+}
+
+/// Frame capture for which the copy command has been recorded but the frame hasn't finished rendering yet.
+pub(crate) struct PendingFrameCapture {
+    pub host_visible_buffer: Arc<Mutex<HostVisibleBuffer<u8>>>,
+    pub sequence_index: u64,
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+    pub sender: SyncSender<CapturedFrame>,
+}
+
+impl PendingFrameCapture {
+    /// Reads the texels from the [`HostVisibleBuffer`] and sends the [`CapturedFrame`] to the frame capture thread.
+    ///
+    /// Must only be called when the command buffer that copies into the [`HostVisibleBuffer`] has finished executing.
+    /// Returns the [`HostVisibleBuffer`] so that it can be reused by the next capture.
+    pub fn finish(self) -> crate::Result<Arc<Mutex<HostVisibleBuffer<u8>>>> {
+        let host_visible_buffer = self.host_visible_buffer.lock();
+        let mut texels = vec![0; host_visible_buffer.len()];
+        host_visible_buffer.get_memory_unaligned(&mut texels)?;
+        drop(host_visible_buffer);
+        // The buffer might be larger than the image when it has been reused
+        texels.truncate(self.extent.width as usize * self.extent.height as usize * 4);
+        convert_to_rgba(self.format, &mut texels)?;
+        let captured_frame = CapturedFrame {
+            sequence_index: self.sequence_index,
+            width: self.extent.width,
+            height: self.extent.height,
+            rgba: texels,
+        };
+        send_captured_frame(&self.sender, captured_frame);
+        Ok(self.host_visible_buffer)
+    }
+}
+
+/// Sends the `captured_frame` without blocking and drops it when the receiver is still busy with the previous frames.
+///
+/// Returns whether the frame has been sent.
+fn send_captured_frame(sender: &SyncSender<CapturedFrame>, captured_frame: CapturedFrame) -> bool {
+    match sender.try_send(captured_frame) {
+        Ok(()) => true,
+        Err(TrySendError::Full(captured_frame)) => {
+            warn!(
+                "Dropping the captured frame {} because the frame capture can't keep up",
+                captured_frame.sequence_index
+            );
+            false
+        }
+        // The frame capture thread has stopped when writing a previous frame failed
+        Err(TrySendError::Disconnected(_)) => false,
+    }
+}
+
+/// Returns whether the texels of a swapchain with the `format` can be captured
+pub(crate) fn is_capturable_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB | vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB
+    )
+}
+
+/// Converts the texels of a swapchain image in place into opaque RGBA texels
+fn convert_to_rgba(format: vk::Format, texels: &mut [u8]) -> crate::Result<()> {
+    let is_bgra = match format {
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+        format => return Err(Error::UnsupportedImageFormat(format)),
+    };
+    for texel in texels.chunks_exact_mut(4) {
+        if is_bgra {
+            texel.swap(0, 2);
+        }
+        // The swapchain is composited as opaque so the alpha channel contains whatever the blending left behind
+        texel[3] = 255;
+    }
+    Ok(())
+}
+
+fn run_frame_capture_thread(mut target: FrameCaptureTarget, receiver: Receiver<CapturedFrame>) -> crate::Result<u64> {
+    if let FrameCaptureTarget::PngSequence(directory) = &target {
+        fs::create_dir_all(directory)?;
+    }
+    let mut frame_count = 0;
+    while let Ok(captured_frame) = receiver.recv() {
+        match &mut target {
+            FrameCaptureTarget::PngSequence(directory) => {
+                let path = directory.join(format!("frame_{:06}.png", captured_frame.sequence_index));
+                image::save_buffer(
+                    &path,
+                    &captured_frame.rgba,
+                    captured_frame.width,
+                    captured_frame.height,
+                    image::ColorType::Rgba8,
+                )
+                .map_err(|err| Error::FrameCaptureFailed(format!("{}: {err}", path.display())))?;
+            }
+            FrameCaptureTarget::Callback(callback) => callback(captured_frame),
+        }
+        frame_count += 1;
+    }
+    Ok(frame_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bgra_is_converted() {
+        let mut texels = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        convert_to_rgba(vk::Format::B8G8R8A8_UNORM, &mut texels).unwrap();
+        assert_eq!(texels, vec![3, 2, 1, 255, 7, 6, 5, 255]);
+    }
+
+    #[test]
+    fn unsupported_format() {
+        assert!(!is_capturable_format(vk::Format::B8G8R8_UNORM));
+        assert!(convert_to_rgba(vk::Format::B8G8R8_UNORM, &mut [0; 3]).is_err());
+    }
+
+    #[test]
+    fn interval_and_time_step() {
+        let config = FrameCaptureConfig {
+            target: FrameCaptureTarget::Callback(Box::new(|_| {})),
+            interval: 2,
+            time_step: Some(Duration::from_millis(500)),
+        };
+        let (mut frame_capture, frame_capture_handle) = FrameCapture::start(config, 10.0);
+        let mut sequence_indices = Vec::new();
+        for _ in 0..5 {
+            sequence_indices.push(frame_capture.sequence_index());
+            frame_capture.next_frame();
+        }
+        assert_eq!(sequence_indices, vec![Some(0), None, Some(1), None, Some(2)]);
+        assert_eq!(frame_capture.time(0.0), 12.5);
+        drop(frame_capture);
+        assert_eq!(frame_capture_handle.wait().unwrap(), 0);
+    }
+
+    #[test]
+    fn frames_are_passed_to_the_callback() {
+        let (sender, receiver) = mpsc::channel();
+        let config = FrameCaptureConfig {
+            target: FrameCaptureTarget::Callback(Box::new(move |captured_frame| sender.send(captured_frame.sequence_index).unwrap())),
+            interval: 1,
+            time_step: None,
+        };
+        let (frame_capture, frame_capture_handle) = FrameCapture::start(config, 0.0);
+        for sequence_index in 0..3 {
+            let captured_frame = CapturedFrame {
+                sequence_index,
+                width: 1,
+                height: 1,
+                rgba: vec![0; 4],
+            };
+            frame_capture.sender().send(captured_frame).unwrap();
+        }
+        drop(frame_capture);
+        assert_eq!(frame_capture_handle.wait().unwrap(), 3);
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn frames_are_dropped_when_the_queue_is_full() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let captured_frame = |sequence_index| CapturedFrame {
+            sequence_index,
+            width: 1,
+            height: 1,
+            rgba: vec![0; 4],
+        };
+        assert!(send_captured_frame(&sender, captured_frame(0)));
+        assert!(!send_captured_frame(&sender, captured_frame(1)));
+        assert_eq!(receiver.recv().unwrap().sequence_index, 0);
+        assert!(send_captured_frame(&sender, captured_frame(2)));
+        drop(receiver);
+        assert!(!send_captured_frame(&sender, captured_frame(3)));
+    }
+}
@@ -39,16 +39,32 @@ where
     }
 
     /// Sets the value at the given index.
+    ///
+    /// The index has to be smaller than the capacity which is guaranteed by the allocator that created the [`GpuIndexAllocation`].
     pub fn set<A>(&mut self, gpu_index_allocation: &GpuIndexAllocation<A>, value: &T) -> crate::Result<()>
     where
         T: Represents<A>,
     {
+        debug_assert!(
+            gpu_index_allocation.index() < self.host_visible_buffer.len(),
+            "the index {} exceeds the capacity {} of the FrameLocalBuffer",
+            gpu_index_allocation.index(),
+            self.host_visible_buffer.len()
+        );
         self.host_visible_buffer
             .set_memory_unaligned_index(gpu_index_allocation.index(), value)?;
         self.high_water_mark = self.high_water_mark.max(gpu_index_allocation.index() + 1);
         Ok(())
     }
 
+    /// Returns the value at the given index.
+    pub fn get<A>(&self, gpu_index_allocation: &GpuIndexAllocation<A>) -> crate::Result<T>
+    where
+        T: Represents<A>,
+    {
+        self.host_visible_buffer.get_memory_unaligned_index(gpu_index_allocation.index())
+    }
+
     /// Returns the count of used values in the [`FrameLocalBuffer`].
     pub fn high_water_mark(&self) -> usize {
         self.high_water_mark
@@ -93,7 +109,7 @@ mod tests {
 
     #[test]
     fn smoke() {
-        #[derive(Default, Clone)]
+        #[derive(Default, Clone, Debug, PartialEq)]
         struct GpuType(u32);
         struct CpuType(u32);
         impl Represents<CpuType> for GpuType {}
@@ -107,5 +123,21 @@ mod tests {
 
         let gpu_index_allocation = GpuIndexAllocation::<CpuType>::new_unchecked(0);
         frame_local_buffer.set(&gpu_index_allocation, &GpuType(73)).unwrap();
+        assert_eq!(frame_local_buffer.get(&gpu_index_allocation).unwrap(), GpuType(73));
+        assert_eq!(frame_local_buffer.high_water_mark(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_exceeds_capacity() {
+        #[derive(Default, Clone, Debug, PartialEq)]
+        struct GpuType(u32);
+        struct CpuType;
+        impl Represents<CpuType> for GpuType {}
+
+        let device_test_fixture = TestFixtureDevice::new().unwrap();
+        let mut frame_local_buffer = FrameLocalBuffer::<GpuType>::new(&device_test_fixture.device, 10, debug_info!("my_buffer")).unwrap();
+        let gpu_index_allocation = GpuIndexAllocation::<CpuType>::new_unchecked(10);
+        frame_local_buffer.set(&gpu_index_allocation, &GpuType(42)).unwrap();
     }
 }
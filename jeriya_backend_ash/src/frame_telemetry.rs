@@ -0,0 +1,117 @@
+use crate::shader_interface;
+
+/// Number of instances, meshlets and clusters that have passed the culling in a frame
+///
+/// The counts are written on the GPU and are therefore only available once the frame has been completed. This is
+/// typically one or two frames after the frame was recorded, depending on the number of frames in flight.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameTelemetry {
+    /// Number of [`RigidMeshInstance`](jeriya_backend::instances::rigid_mesh_instance::RigidMeshInstance) slots that were passed to the culling
+    pub rigid_mesh_instances: u32,
+    /// Number of visible rigid mesh instances that are rendered with meshlets
+    pub visible_rigid_mesh_instances: u32,
+    /// Number of visible rigid mesh instances that are rendered with the simple mesh
+    pub visible_rigid_mesh_instances_simple: u32,
+    pub visible_rigid_mesh_meshlets: u32,
+    /// Number of rendered vertices of the visible meshlets
+    pub visible_rigid_mesh_meshlet_vertices: u32,
+    /// Maximum number of meshlets that can be visible in a frame
+    pub max_visible_rigid_mesh_meshlets: u32,
+
+    /// Number of [`PointCloudInstance`](jeriya_backend::instances::point_cloud_instance::PointCloudInstance) slots that were passed to the culling
+    pub point_cloud_instances: u32,
+    /// Number of visible point cloud instances that are rendered with clusters
+    pub visible_point_cloud_instances: u32,
+    /// Number of visible point cloud instances that are rendered with the simple point cloud
+    pub visible_point_cloud_instances_simple: u32,
+    pub visible_point_cloud_clusters: u32,
+    /// Maximum number of point cloud clusters that can be visible in a frame
+    pub max_visible_point_cloud_clusters: u32,
+}
+
+impl FrameTelemetry {
+    /// Creates the `FrameTelemetry` from the data that the frame telemetry compute shader has written and the
+    /// [`PerFrameData`](shader_interface::PerFrameData) with which the frame was rendered.
+    pub(crate) fn new(frame_telemetry: &shader_interface::FrameTelemetry, per_frame_data: &shader_interface::PerFrameData) -> Self {
+        Self {
+            rigid_mesh_instances: per_frame_data.rigid_mesh_instance_count,
+            visible_rigid_mesh_instances: frame_telemetry.visible_rigid_mesh_instances,
+            visible_rigid_mesh_instances_simple: frame_telemetry.visible_rigid_mesh_instances_simple,
+            visible_rigid_mesh_meshlets: frame_telemetry.visible_rigid_mesh_meshlets,
+            visible_rigid_mesh_meshlet_vertices: frame_telemetry.visible_rigid_mesh_meshlet_vertices,
+            max_visible_rigid_mesh_meshlets: frame_telemetry.max_visible_rigid_mesh_meshlets,
+            point_cloud_instances: per_frame_data.point_cloud_instance_count,
+            visible_point_cloud_instances: frame_telemetry.visible_point_cloud_instances,
+            visible_point_cloud_instances_simple: frame_telemetry.visible_point_cloud_instances_simple,
+            visible_point_cloud_clusters: frame_telemetry.visible_point_cloud_clusters,
+            max_visible_point_cloud_clusters: frame_telemetry.max_visible_point_cloud_clusters,
+        }
+    }
+
+    /// Returns the fraction of the rigid mesh instances that have been culled. `None` when there are no instances.
+    pub fn rigid_mesh_instance_culling_ratio(&self) -> Option<f32> {
+        culling_ratio(
+            self.visible_rigid_mesh_instances + self.visible_rigid_mesh_instances_simple,
+            self.rigid_mesh_instances,
+        )
+    }
+
+    /// Returns the fraction of the point cloud instances that have been culled. `None` when there are no instances.
+    pub fn point_cloud_instance_culling_ratio(&self) -> Option<f32> {
+        culling_ratio(
+            self.visible_point_cloud_instances + self.visible_point_cloud_instances_simple,
+            self.point_cloud_instances,
+        )
+    }
+
+    /// Returns how much of the budget for visible meshlets has been used in the range `[0, 1]`
+    pub fn meshlet_budget_usage(&self) -> f32 {
+        budget_usage(self.visible_rigid_mesh_meshlets, self.max_visible_rigid_mesh_meshlets)
+    }
+
+    /// Returns how much of the budget for visible point cloud clusters has been used in the range `[0, 1]`
+    pub fn point_cloud_cluster_budget_usage(&self) -> f32 {
+        budget_usage(self.visible_point_cloud_clusters, self.max_visible_point_cloud_clusters)
+    }
+}
+
+fn culling_ratio(visible: u32, total: u32) -> Option<f32> {
+    (total > 0).then(|| 1.0 - (visible.min(total) as f32 / total as f32))
+}
+
+fn budget_usage(used: u32, max: u32) -> f32 {
+    if max == 0 {
+        return 0.0;
+    }
+    (used as f32 / max as f32).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn culling_ratios() {
+        let frame_telemetry = FrameTelemetry {
+            rigid_mesh_instances: 10,
+            visible_rigid_mesh_instances: 3,
+            visible_rigid_mesh_instances_simple: 2,
+            ..Default::default()
+        };
+        assert_eq!(frame_telemetry.rigid_mesh_instance_culling_ratio(), Some(0.5));
+        assert_eq!(frame_telemetry.point_cloud_instance_culling_ratio(), None);
+    }
+
+    #[test]
+    fn budget_usages() {
+        let frame_telemetry = FrameTelemetry {
+            visible_rigid_mesh_meshlets: 25,
+            max_visible_rigid_mesh_meshlets: 100,
+            visible_point_cloud_clusters: 10,
+            max_visible_point_cloud_clusters: 0,
+            ..Default::default()
+        };
+        assert_eq!(frame_telemetry.meshlet_budget_usage(), 0.25);
+        assert_eq!(frame_telemetry.point_cloud_cluster_budget_usage(), 0.0);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use jeriya_shared::{
+    log::trace,
+    tracy_client::{Client, PlotName},
+};
+
+use crate::{command_buffer_builder::CommandBufferBuilder, query_pool::QueryPool, Error};
+
+/// GPU time of a single pass in the frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+    pub name: &'static str,
+    pub duration: Duration,
+}
+
+/// GPU times of the passes of a frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameTimings {
+    /// The passes in the order in which they were executed
+    pub passes: Vec<PassTiming>,
+}
+
+impl FrameTimings {
+    /// Creates the `FrameTimings` from the timestamps that were written at the beginning and the end of the passes.
+    ///
+    /// `timestamps` contains two timestamps per pass. The `timestamp_period` is the number of nanoseconds per timestamp tick.
+    pub fn from_timestamps(pass_names: &[&'static str], timestamps: &[u64], timestamp_period: f32) -> Self {
+        jeriya_shared::assert_eq!(pass_names.len() * 2, timestamps.len(), "expected two timestamps per pass");
+        let passes = pass_names
+            .iter()
+            .zip(timestamps.chunks_exact(2))
+            .map(|(name, timestamps)| {
+                let ticks = timestamps[1].saturating_sub(timestamps[0]);
+                PassTiming {
+                    name,
+                    duration: Duration::from_nanos((ticks as f64 * timestamp_period as f64) as u64),
+                }
+            })
+            .collect();
+        Self { passes }
+    }
+
+    /// Returns the [`PassTiming`] of the pass with the given name
+    pub fn get(&self, name: &str) -> Option<&PassTiming> {
+        self.passes.iter().find(|pass| pass.name == name)
+    }
+
+    /// Returns the sum of the GPU times of all passes
+    pub fn total(&self) -> Duration {
+        self.passes.iter().map(|pass| pass.duration).sum()
+    }
+}
+
+/// Writes timestamps at the beginning and the end of the passes in a frame
+pub(crate) struct PassTimer {
+    query_pool: Arc<QueryPool>,
+    pass_names: Vec<&'static str>,
+    is_pass_active: bool,
+    /// Whether timestamps are written for the active pass. Passes are not timed when the [`QueryPool`] is exhausted.
+    is_pass_timed: bool,
+}
+
+impl PassTimer {
+    /// Creates a new `PassTimer` and resets the [`QueryPool`]. Must be called outside of a render pass.
+    pub fn new(query_pool: &Arc<QueryPool>, builder: &mut CommandBufferBuilder) -> Self {
+        builder.reset_query_pool(query_pool);
+        Self {
+            query_pool: query_pool.clone(),
+            pass_names: Vec::new(),
+            is_pass_active: false,
+            is_pass_timed: false,
+        }
+    }
+
+    /// Returns the maximum number of passes that can be timed with a [`QueryPool`] of the given size
+    pub fn max_passes(query_pool: &QueryPool) -> usize {
+        query_pool.query_count() as usize / 2
+    }
+
+    /// Writes the timestamp for the beginning of the pass. The pass is not timed when the [`QueryPool`] is exhausted.
+    pub fn begin(&mut self, builder: &mut CommandBufferBuilder, name: &'static str) {
+        jeriya_shared::assert!(!self.is_pass_active, "a pass is already active");
+        self.is_pass_active = true;
+        self.is_pass_timed = self.pass_names.len() < Self::max_passes(&self.query_pool);
+        if !self.is_pass_timed {
+            trace!("Pass '{name}' is not timed because all timestamp queries of the frame are in use");
+            return;
+        }
+        builder.write_timestamp(&self.query_pool, 2 * self.pass_names.len() as u32);
+        self.pass_names.push(name);
+    }
+
+    /// Writes the timestamp for the end of the pass
+    pub fn end(&mut self, builder: &mut CommandBufferBuilder) {
+        jeriya_shared::assert!(self.is_pass_active, "no pass is active");
+        if self.is_pass_timed {
+            builder.write_timestamp(&self.query_pool, 2 * self.pass_names.len() as u32 - 1);
+        }
+        self.is_pass_active = false;
+    }
+
+    /// Returns the names of the passes that have been timed
+    pub fn finish(self) -> Vec<&'static str> {
+        jeriya_shared::assert!(!self.is_pass_active, "a pass is still active");
+        self.pass_names
+    }
+}
+
+/// Adjusts the frame period of a presenter to the GPU times of the recent frames
+///
+/// Used for [`FrameRate::Adaptive`](jeriya_shared::FrameRate::Adaptive).
+pub(crate) struct AdaptiveFrameRate {
+    min: u32,
+    max: u32,
+    /// Exponential moving average of the GPU time of the frames
+    average_gpu_time: Option<Duration>,
+    period: Duration,
+}
+
+impl AdaptiveFrameRate {
+    /// Fraction of the frame period for which the GPU is allowed to be busy
+    const TARGET_GPU_UTILIZATION: f64 = 0.75;
+    /// Weight of the newest GPU time in the moving average
+    const SMOOTHING: f64 = 0.1;
+    /// Relative change of the period below which the period is kept so that the interval isn't reset every frame
+    const HYSTERESIS: f64 = 0.05;
+
+    /// Creates a new `AdaptiveFrameRate` that starts at the `max` frame rate
+    ///
+    /// Returns [`Error::InvalidAdaptiveFrameRate`] when `min` is 0 or greater than `max`.
+    pub fn new(min: u32, max: u32) -> crate::Result<Self> {
+        if min == 0 || min > max {
+            return Err(Error::InvalidAdaptiveFrameRate { min, max });
+        }
+        Ok(Self {
+            min,
+            max,
+            average_gpu_time: None,
+            period: Duration::from_secs_f64(1.0 / max as f64),
+        })
+    }
+
+    /// Returns the current frame period
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Adds the GPU time of a frame and returns the new period when it changed noticeably
+    pub fn update(&mut self, gpu_time: Duration) -> Option<Duration> {
+        let average_gpu_time = match self.average_gpu_time {
+            Some(average) => average.mul_f64(1.0 - Self::SMOOTHING) + gpu_time.mul_f64(Self::SMOOTHING),
+            None => gpu_time,
+        };
+        self.average_gpu_time = Some(average_gpu_time);
+
+        let min_period = 1.0 / self.max as f64;
+        let max_period = 1.0 / self.min as f64;
+        let period = (average_gpu_time.as_secs_f64() / Self::TARGET_GPU_UTILIZATION).clamp(min_period, max_period);
+        let current_period = self.period.as_secs_f64();
+        if (period - current_period).abs() > current_period * Self::HYSTERESIS {
+            self.period = Duration::from_secs_f64(period);
+            Some(self.period)
+        } else {
+            None
+        }
+    }
+}
+
+/// Plots the [`FrameTimings`] of a presenter in tracy
+#[derive(Default)]
+pub(crate) struct FrameTimingsPlotter {
+    /// Tracy requires the plot names to be `'static`. They are leaked once per pass and cached here.
+    plot_names: HashMap<&'static str, PlotName>,
+}
+
+impl FrameTimingsPlotter {
+    /// Plots the GPU times of the passes in milliseconds
+    pub fn plot(&mut self, presenter_index: usize, frame_timings: &FrameTimings) {
+        let Some(client) = Client::running() else {
+            return;
+        };
+        for pass in &frame_timings.passes {
+            let plot_name = *self
+                .plot_names
+                .entry(pass.name)
+                .or_insert_with(|| PlotName::new_leak(format!("gpu_time_ms_{}_on_presenter_{presenter_index}", pass.name)));
+            client.plot(plot_name, pass.duration.as_secs_f64() * 1000.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_timestamps() {
+        let frame_timings = FrameTimings::from_timestamps(&["Culling", "Rendering"], &[10, 20, 20, 50], 2.0);
+        assert_eq!(frame_timings.passes.len(), 2);
+        assert_eq!(frame_timings.get("Culling").unwrap().duration, Duration::from_nanos(20));
+        assert_eq!(frame_timings.get("Rendering").unwrap().duration, Duration::from_nanos(60));
+        assert_eq!(frame_timings.get("Unknown"), None);
+        assert_eq!(frame_timings.total(), Duration::from_nanos(80));
+    }
+
+    #[test]
+    fn from_timestamps_wrapped() {
+        let frame_timings = FrameTimings::from_timestamps(&["Culling"], &[20, 10], 1.0);
+        assert_eq!(frame_timings.total(), Duration::ZERO);
+    }
+
+    #[test]
+    fn adaptive_frame_rate_stays_at_max_when_idle() {
+        let mut adaptive_frame_rate = AdaptiveFrameRate::new(30, 120).unwrap();
+        assert_eq!(adaptive_frame_rate.update(Duration::from_millis(1)), None);
+        assert_eq!(adaptive_frame_rate.period(), Duration::from_secs_f64(1.0 / 120.0));
+    }
+
+    #[test]
+    fn adaptive_frame_rate_is_clamped_to_min_under_load() {
+        let mut adaptive_frame_rate = AdaptiveFrameRate::new(30, 120).unwrap();
+        for _ in 0..100 {
+            adaptive_frame_rate.update(Duration::from_millis(100));
+        }
+        assert_eq!(adaptive_frame_rate.period(), Duration::from_secs_f64(1.0 / 30.0));
+    }
+
+    #[test]
+    fn adaptive_frame_rate_leaves_headroom() {
+        let mut adaptive_frame_rate = AdaptiveFrameRate::new(30, 120).unwrap();
+        // A GPU time of 15 ms is 75 % of a 20 ms period which corresponds to 50 frames per second
+        assert!(adaptive_frame_rate.update(Duration::from_millis(15)).is_some());
+        assert!((adaptive_frame_rate.period().as_secs_f64() - 0.020).abs() < 1e-6);
+    }
+
+    #[test]
+    fn adaptive_frame_rate_rejects_invalid_range() {
+        assert!(matches!(
+            AdaptiveFrameRate::new(0, 120),
+            Err(Error::InvalidAdaptiveFrameRate { min: 0, max: 120 })
+        ));
+        assert!(matches!(
+            AdaptiveFrameRate::new(60, 30),
+            Err(Error::InvalidAdaptiveFrameRate { min: 60, max: 30 })
+        ));
+    }
+}
@@ -4,18 +4,21 @@ use jeriya_macros::profile;
 use jeriya_shared::{
     debug_info,
     log::info,
-    nalgebra::{Matrix4, Vector3, Vector4},
+    nalgebra::{Matrix4, Vector2, Vector3, Vector4},
     AsDebugInfo, DebugInfo,
 };
 
 use std::{ffi::CString, io::Cursor, mem, sync::Arc};
 
 use crate::{
+    bindless_textures::BindlessTextures,
     descriptor_set_layout::DescriptorSetLayout,
     device::Device,
-    shader_interface::{self, Camera, CameraInstance, MeshAttributes, PerFrameData, PointCloudPage, RigidMesh, RigidMeshInstance},
+    persistent_frame_state::PersistentFrameState,
+    pipeline_cache::PipelineCache,
+    shader_interface,
     shader_module::ShaderModule,
-    specialization_constants::SpecializationConstants,
+    specialization_constants::{PipelineSpecializationConstants, SpecializationConstant, SpecializationConstants},
     swapchain_render_pass::SwapchainRenderPass,
     AsRawVulkan,
 };
@@ -25,6 +28,9 @@ use crate::{
 pub struct PushConstants {
     pub color: Vector4<f32>,
     pub matrix: Matrix4<f32>,
+    pub debug_shading_mode: shader_interface::DebugShadingMode,
+    /// Index into the bindless textures that is sampled by the pipelines which use textures
+    pub texture_index: u32,
 }
 
 pub trait GraphicsPipeline {
@@ -68,6 +74,166 @@ impl From<CullMode> for vk::CullModeFlags {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// The fragment color replaces the color in the framebuffer
+    #[default]
+    Opaque,
+    /// The fragment color is added to the color in the framebuffer
+    Additive,
+    /// The color in the framebuffer is multiplied with the fragment color
+    Multiply,
+    /// The fragment color is blended over the color in the framebuffer according to the alpha of the fragment
+    AlphaBlend,
+    /// Like `AlphaBlend` but the fragment color has already been multiplied with its alpha
+    PremultipliedAlpha,
+    /// The color in the framebuffer is not written. Used for pipelines that only write the depth or stencil buffer.
+    NoColorWrite,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DepthMode {
+    /// Fragments are tested against the depth buffer and write to it
+    #[default]
+    TestAndWrite,
+    /// The depth buffer is neither read nor written
+    Disabled,
+}
+
+/// Comparison of the stencil test between the `reference` of a [`StencilState`] and the value in the stencil buffer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    #[default]
+    Always,
+}
+
+impl From<CompareOp> for vk::CompareOp {
+    fn from(compare_op: CompareOp) -> Self {
+        match compare_op {
+            CompareOp::Never => vk::CompareOp::NEVER,
+            CompareOp::Less => vk::CompareOp::LESS,
+            CompareOp::Equal => vk::CompareOp::EQUAL,
+            CompareOp::LessOrEqual => vk::CompareOp::LESS_OR_EQUAL,
+            CompareOp::Greater => vk::CompareOp::GREATER,
+            CompareOp::NotEqual => vk::CompareOp::NOT_EQUAL,
+            CompareOp::GreaterOrEqual => vk::CompareOp::GREATER_OR_EQUAL,
+            CompareOp::Always => vk::CompareOp::ALWAYS,
+        }
+    }
+}
+
+/// Operation that is applied to the value in the stencil buffer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StencilOp {
+    #[default]
+    Keep,
+    Zero,
+    /// Writes the `reference` of the [`StencilState`]
+    Replace,
+    IncrementAndClamp,
+    DecrementAndClamp,
+    Invert,
+    IncrementAndWrap,
+    DecrementAndWrap,
+}
+
+impl From<StencilOp> for vk::StencilOp {
+    fn from(stencil_op: StencilOp) -> Self {
+        match stencil_op {
+            StencilOp::Keep => vk::StencilOp::KEEP,
+            StencilOp::Zero => vk::StencilOp::ZERO,
+            StencilOp::Replace => vk::StencilOp::REPLACE,
+            StencilOp::IncrementAndClamp => vk::StencilOp::INCREMENT_AND_CLAMP,
+            StencilOp::DecrementAndClamp => vk::StencilOp::DECREMENT_AND_CLAMP,
+            StencilOp::Invert => vk::StencilOp::INVERT,
+            StencilOp::IncrementAndWrap => vk::StencilOp::INCREMENT_AND_WRAP,
+            StencilOp::DecrementAndWrap => vk::StencilOp::DECREMENT_AND_WRAP,
+        }
+    }
+}
+
+/// Stencil test and operations that are applied to front and back faces
+///
+/// The stencil buffer is cleared to 0 at the beginning of the scene render pass and of every viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StencilState {
+    pub compare_op: CompareOp,
+    /// Applied when the stencil test fails
+    pub fail_op: StencilOp,
+    /// Applied when the stencil test and the depth test pass
+    pub pass_op: StencilOp,
+    /// Applied when the stencil test passes but the depth test fails
+    pub depth_fail_op: StencilOp,
+    pub reference: u8,
+    /// Bits that are compared by the stencil test
+    pub compare_mask: u8,
+    /// Bits that are written by the stencil operations
+    pub write_mask: u8,
+}
+
+impl StencilState {
+    /// Creates a `StencilState` that writes the `reference` for every fragment that passes the depth test
+    ///
+    /// This is used to mark the pixels of a mirror or of an object that is outlined afterwards.
+    pub fn write(reference: u8) -> Self {
+        Self {
+            compare_op: CompareOp::Always,
+            fail_op: StencilOp::Keep,
+            pass_op: StencilOp::Replace,
+            depth_fail_op: StencilOp::Keep,
+            reference,
+            compare_mask: u8::MAX,
+            write_mask: u8::MAX,
+        }
+    }
+
+    /// Creates a `StencilState` that only passes the fragments for which `reference <compare_op> stencil` holds without writing the stencil buffer
+    pub fn test(compare_op: CompareOp, reference: u8) -> Self {
+        Self {
+            compare_op,
+            fail_op: StencilOp::Keep,
+            pass_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            reference,
+            compare_mask: u8::MAX,
+            write_mask: 0,
+        }
+    }
+}
+
+impl From<StencilState> for vk::StencilOpState {
+    fn from(stencil_state: StencilState) -> Self {
+        vk::StencilOpState {
+            fail_op: stencil_state.fail_op.into(),
+            pass_op: stencil_state.pass_op.into(),
+            depth_fail_op: stencil_state.depth_fail_op.into(),
+            compare_op: stencil_state.compare_op.into(),
+            compare_mask: stencil_state.compare_mask as u32,
+            write_mask: stencil_state.write_mask as u32,
+            reference: stencil_state.reference as u32,
+        }
+    }
+}
+
+/// Render pass in which the pipeline is used
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PipelineRenderPass {
+    /// Render pass with color and depth attachment in which the scene is rendered
+    #[default]
+    Scene,
+    /// Render pass with only the color attachment that runs after the scene has been rendered
+    PostProcessing,
+    /// Render pass with color and depth attachment in which the scene is rendered into a `RenderTarget`
+    RenderTarget,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PrimitiveTopology {
     #[default]
@@ -97,10 +263,34 @@ pub struct GenericGraphicsPipelineConfig {
     pub primitive_topology: PrimitiveTopology,
     pub polygon_mode: PolygonMode,
     pub cull_mode: CullMode,
+    pub blend_mode: BlendMode,
+    pub depth_mode: DepthMode,
+    /// Stencil test of the pipeline. `None` disables the stencil test. Ignored in [`PipelineRenderPass::PostProcessing`]
+    /// because it has no stencil attachment.
+    pub stencil_state: Option<StencilState>,
+    pub render_pass: PipelineRenderPass,
     pub use_input_attributes: bool,
+    /// Adds the texture coordinates as a second vertex buffer at binding 1 and location 1. Requires `use_input_attributes`.
+    pub use_texture_coordinates: bool,
+    /// Adds the RGBA8 vertex colors as a vertex buffer at binding 2 and location 2. Requires `use_input_attributes`.
+    pub use_vertex_colors: bool,
+    /// Adds the corner offsets and sizes of the immediate points as a vertex buffer at binding 3 and location 3. Requires `use_input_attributes`.
+    pub use_point_attributes: bool,
     pub use_dynamic_state_line_width: bool,
+    pub use_dynamic_state_scissor: bool,
+    /// Makes the viewport a dynamic state so that the `framebuffer_width` and `framebuffer_height` are ignored
+    pub use_dynamic_state_viewport: bool,
     pub framebuffer_width: u32,
     pub framebuffer_height: u32,
+    pub specialization_constants: PipelineSpecializationConstants,
+}
+
+impl GenericGraphicsPipelineConfig {
+    /// Sets the value of the specialization constant `C` for this pipeline
+    pub fn with_specialization_constant<C: SpecializationConstant>(mut self, value: C::Value) -> Self {
+        self.specialization_constants = self.specialization_constants.with::<C>(value);
+        self
+    }
 }
 
 pub struct GenericGraphicsPipeline {
@@ -128,11 +318,14 @@ impl Drop for GenericGraphicsPipeline {
 impl GenericGraphicsPipeline {
     pub fn new(
         device: &Arc<Device>,
+        pipeline_cache: &PipelineCache,
         config: &GenericGraphicsPipelineConfig,
         vertex_shader_spirv: &[u8],
         fragment_shader_spirv: &[u8],
         renderpass: &SwapchainRenderPass,
         specialization_constants: &SpecializationConstants,
+        bindless_textures: &BindlessTextures,
+        cached_descriptor_set_layout: &DescriptorSetLayout,
         debug_info: DebugInfo,
     ) -> crate::Result<Self> {
         let entry_name = CString::new("main").expect("Valid c string");
@@ -149,6 +342,7 @@ impl GenericGraphicsPipeline {
             debug_info!("GenericGraphicsPipeline-fragment-ShaderModule"),
         )?;
 
+        let specialization_constants = specialization_constants.extended_with(&config.specialization_constants);
         let specialization_info = vk::SpecializationInfo::builder()
             .map_entries(specialization_constants.map_entries())
             .data(specialization_constants.data())
@@ -172,39 +366,17 @@ impl GenericGraphicsPipeline {
         ];
 
         info!("Create pipeline layout for GenericGraphicsPipeline \"{}\"", debug_info.name());
-        let descriptor_set_layout = Arc::new(
-            DescriptorSetLayout::builder()
-                .push_uniform_buffer::<PerFrameData>(0, 1)
-                .push_storage_buffer::<Camera>(1, 1)
-                .push_storage_buffer::<CameraInstance>(2, 1)
-                .push_storage_buffer::<u32>(3, 1)
-                .push_storage_buffer::<Vector4<f32>>(5, 1)
-                .push_storage_buffer::<u32>(6, 1)
-                .push_storage_buffer::<Vector4<f32>>(7, 1)
-                .push_storage_buffer::<MeshAttributes>(8, 1)
-                .push_storage_buffer::<RigidMesh>(9, 1)
-                .push_storage_buffer::<u32>(10, 1)
-                .push_storage_buffer::<RigidMeshInstance>(11, 1)
-                .push_storage_buffer::<shader_interface::Meshlet>(12, 1)
-                .push_storage_buffer::<u32>(13, 1)
-                .push_storage_buffer::<u32>(14, 1)
-                .push_storage_buffer::<u32>(15, 1)
-                .push_storage_buffer::<shader_interface::PointCloud>(16, 1)
-                .push_storage_buffer::<shader_interface::PointCloudInstance>(17, 1)
-                .push_storage_buffer::<u32>(18, 1)
-                .push_storage_buffer::<shader_interface::PointCloudAttributes>(19, 1)
-                .push_storage_buffer::<Vector4<f32>>(20, 1)
-                .push_storage_buffer::<Vector4<f32>>(21, 1)
-                .push_storage_buffer::<PointCloudPage>(22, 1)
-                .push_storage_buffer::<u32>(23, 1)
-                .push_storage_buffer::<PointCloudPage>(24, 1)
-                .push_storage_buffer::<u32>(25, 1)
-                .push_storage_buffer::<u32>(26, 1)
-                .push_storage_buffer::<shader_interface::FrameTelemetry>(27, 1)
-                .push_storage_buffer::<f32>(28, 1)
-                .build(device)?,
-        );
-        let descriptor_set_layouts = [*descriptor_set_layout.as_raw_vulkan()];
+        let descriptor_set_layout = Arc::new(DescriptorSetLayout::from_reflection(
+            device,
+            [vertex_shader.reflection(), fragment_shader.reflection()],
+            &PersistentFrameState::available_descriptors(),
+            cached_descriptor_set_layout.descriptors(),
+        )?);
+        let descriptor_set_layouts = [
+            *descriptor_set_layout.as_raw_vulkan(),
+            bindless_textures.descriptor_set_layout(),
+            *cached_descriptor_set_layout.as_raw_vulkan(),
+        ];
 
         let push_constant_range = [vk::PushConstantRange::builder()
             .stage_flags(vk::ShaderStageFlags::ALL)
@@ -257,31 +429,83 @@ impl GenericGraphicsPipeline {
             rasterization_samples: vk::SampleCountFlags::TYPE_1,
             ..Default::default()
         };
-        let noop_stencil_state = vk::StencilOpState {
+        let stencil_op_state = config.stencil_state.map(vk::StencilOpState::from).unwrap_or(vk::StencilOpState {
             fail_op: vk::StencilOp::KEEP,
             pass_op: vk::StencilOp::KEEP,
             depth_fail_op: vk::StencilOp::KEEP,
             compare_op: vk::CompareOp::ALWAYS,
             ..Default::default()
+        });
+        let depth_enable = match config.depth_mode {
+            DepthMode::TestAndWrite => 1,
+            DepthMode::Disabled => 0,
         };
         let depth_state_info = vk::PipelineDepthStencilStateCreateInfo {
-            depth_test_enable: 1,
-            depth_write_enable: 1,
+            depth_test_enable: depth_enable,
+            depth_write_enable: depth_enable,
             depth_compare_op: vk::CompareOp::LESS_OR_EQUAL,
-            front: noop_stencil_state,
-            back: noop_stencil_state,
+            stencil_test_enable: config.stencil_state.is_some() as u32,
+            front: stencil_op_state,
+            back: stencil_op_state,
             max_depth_bounds: 1.0,
             ..Default::default()
         };
-        let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
-            blend_enable: 0,
-            src_color_blend_factor: vk::BlendFactor::SRC_COLOR,
-            dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_DST_COLOR,
-            color_blend_op: vk::BlendOp::ADD,
-            src_alpha_blend_factor: vk::BlendFactor::ZERO,
-            dst_alpha_blend_factor: vk::BlendFactor::ZERO,
-            alpha_blend_op: vk::BlendOp::ADD,
-            color_write_mask: vk::ColorComponentFlags::RGBA,
+        let color_blend_attachment_states = [match config.blend_mode {
+            BlendMode::Opaque => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 0,
+                src_color_blend_factor: vk::BlendFactor::SRC_COLOR,
+                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_DST_COLOR,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ZERO,
+                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            BlendMode::Additive => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 1,
+                src_color_blend_factor: vk::BlendFactor::ONE,
+                dst_color_blend_factor: vk::BlendFactor::ONE,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            BlendMode::Multiply => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 1,
+                src_color_blend_factor: vk::BlendFactor::ZERO,
+                dst_color_blend_factor: vk::BlendFactor::SRC_COLOR,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ZERO,
+                dst_alpha_blend_factor: vk::BlendFactor::ONE,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            BlendMode::AlphaBlend => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 1,
+                src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            BlendMode::PremultipliedAlpha => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 1,
+                src_color_blend_factor: vk::BlendFactor::ONE,
+                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            BlendMode::NoColorWrite => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 0,
+                color_write_mask: vk::ColorComponentFlags::empty(),
+                ..Default::default()
+            },
         }];
         let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
             .logic_op(vk::LogicOp::CLEAR)
@@ -291,6 +515,12 @@ impl GenericGraphicsPipeline {
         if config.use_dynamic_state_line_width {
             dynamic_state.push(vk::DynamicState::LINE_WIDTH);
         }
+        if config.use_dynamic_state_scissor {
+            dynamic_state.push(vk::DynamicState::SCISSOR);
+        }
+        if config.use_dynamic_state_viewport {
+            dynamic_state.push(vk::DynamicState::VIEWPORT);
+        }
         let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_state);
 
         let mut vertex_input_binding_descriptions = Vec::new();
@@ -300,6 +530,27 @@ impl GenericGraphicsPipeline {
                 stride: mem::size_of::<Vector3<f32>>() as u32,
                 input_rate: vk::VertexInputRate::VERTEX,
             });
+            if config.use_texture_coordinates {
+                vertex_input_binding_descriptions.push(vk::VertexInputBindingDescription {
+                    binding: 1,
+                    stride: mem::size_of::<Vector2<f32>>() as u32,
+                    input_rate: vk::VertexInputRate::VERTEX,
+                });
+            }
+            if config.use_vertex_colors {
+                vertex_input_binding_descriptions.push(vk::VertexInputBindingDescription {
+                    binding: 2,
+                    stride: mem::size_of::<[u8; 4]>() as u32,
+                    input_rate: vk::VertexInputRate::VERTEX,
+                });
+            }
+            if config.use_point_attributes {
+                vertex_input_binding_descriptions.push(vk::VertexInputBindingDescription {
+                    binding: 3,
+                    stride: mem::size_of::<Vector3<f32>>() as u32,
+                    input_rate: vk::VertexInputRate::VERTEX,
+                });
+            }
         }
 
         let mut vertex_input_attribute_descriptions = Vec::new();
@@ -310,6 +561,30 @@ impl GenericGraphicsPipeline {
                 format: vk::Format::R32G32B32_SFLOAT,
                 offset: 0,
             });
+            if config.use_texture_coordinates {
+                vertex_input_attribute_descriptions.push(vk::VertexInputAttributeDescription {
+                    location: 1,
+                    binding: 1,
+                    format: vk::Format::R32G32_SFLOAT,
+                    offset: 0,
+                });
+            }
+            if config.use_vertex_colors {
+                vertex_input_attribute_descriptions.push(vk::VertexInputAttributeDescription {
+                    location: 2,
+                    binding: 2,
+                    format: vk::Format::R8G8B8A8_UNORM,
+                    offset: 0,
+                });
+            }
+            if config.use_point_attributes {
+                vertex_input_attribute_descriptions.push(vk::VertexInputAttributeDescription {
+                    location: 3,
+                    binding: 3,
+                    format: vk::Format::R32G32B32_SFLOAT,
+                    offset: 0,
+                });
+            }
         }
 
         let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::builder()
@@ -334,10 +609,12 @@ impl GenericGraphicsPipeline {
         let graphics_pipeline = unsafe {
             device
                 .as_raw_vulkan()
-                .create_graphics_pipelines(vk::PipelineCache::null(), &[graphic_pipeline_info.build()], None)
+                .create_graphics_pipelines(*pipeline_cache.as_raw_vulkan(), &[graphic_pipeline_info.build()], None)
                 .map_err(|(_, err)| err)?[0]
         };
 
+        device.set_debug_name(graphics_pipeline, &debug_info);
+        device.set_debug_name(graphics_pipeline_layout, &debug_info);
         info!("Done creating GenericGraphicsPipeline \"{}\"", debug_info.name());
         Ok(Self {
             config: config.clone(),
@@ -382,8 +659,14 @@ mod tests {
         use jeriya_shared::debug_info;
 
         use crate::{
+            bindless_textures::BindlessTextures,
             device::TestFixtureDevice,
-            graphics_pipeline::{GenericGraphicsPipeline, GenericGraphicsPipelineConfig, PrimitiveTopology},
+            graphics_pipeline::{
+                BlendMode, DepthMode, GenericGraphicsPipeline, GenericGraphicsPipelineConfig, PipelineRenderPass, PrimitiveTopology,
+                StencilState,
+            },
+            persistent_frame_state::PersistentFrameState,
+            pipeline_cache::PipelineCache,
             specialization_constants::SpecializationConstants,
             swapchain::Swapchain,
             swapchain_render_pass::SwapchainRenderPass,
@@ -403,13 +686,121 @@ mod tests {
                 ..Default::default()
             };
             let specialization_constants = SpecializationConstants::new();
+            let pipeline_cache = PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let cached_descriptor_set_layout = PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap();
+            let _graphics_pipeline = GenericGraphicsPipeline::new(
+                &test_fixture_device.device,
+                &pipeline_cache,
+                &config,
+                include_bytes!("../test_data/red_triangle.vert.spv"),
+                include_bytes!("../test_data/red_triangle.frag.spv"),
+                &render_pass,
+                &specialization_constants,
+                &bindless_textures,
+                &cached_descriptor_set_layout,
+                debug_info!("my_graphics_pipeline"),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn additive_without_depth() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let swapchain = Swapchain::new(&test_fixture_device.device, &test_fixture_device.surface, 2, None).unwrap();
+            let render_pass = SwapchainRenderPass::new(&test_fixture_device.device, &swapchain).unwrap();
+            let config = GenericGraphicsPipelineConfig {
+                vertex_shader: Some(AssetKey::new("vertex_shader")),
+                fragment_shader: Some(AssetKey::new("fragment_shader")),
+                primitive_topology: PrimitiveTopology::TriangleList,
+                blend_mode: BlendMode::Additive,
+                depth_mode: DepthMode::Disabled,
+                framebuffer_width: swapchain.extent().width,
+                framebuffer_height: swapchain.extent().height,
+                ..Default::default()
+            };
+            let specialization_constants = SpecializationConstants::new();
+            let pipeline_cache = PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let cached_descriptor_set_layout = PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap();
+            let _graphics_pipeline = GenericGraphicsPipeline::new(
+                &test_fixture_device.device,
+                &pipeline_cache,
+                &config,
+                include_bytes!("../test_data/red_triangle.vert.spv"),
+                include_bytes!("../test_data/red_triangle.frag.spv"),
+                &render_pass,
+                &specialization_constants,
+                &bindless_textures,
+                &cached_descriptor_set_layout,
+                debug_info!("my_graphics_pipeline"),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn stencil_write() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let swapchain = Swapchain::new(&test_fixture_device.device, &test_fixture_device.surface, 2, None).unwrap();
+            let render_pass = SwapchainRenderPass::new(&test_fixture_device.device, &swapchain).unwrap();
+            let config = GenericGraphicsPipelineConfig {
+                vertex_shader: Some(AssetKey::new("vertex_shader")),
+                fragment_shader: Some(AssetKey::new("fragment_shader")),
+                primitive_topology: PrimitiveTopology::TriangleList,
+                stencil_state: Some(StencilState::write(1)),
+                framebuffer_width: swapchain.extent().width,
+                framebuffer_height: swapchain.extent().height,
+                ..Default::default()
+            };
+            let specialization_constants = SpecializationConstants::new();
+            let pipeline_cache = PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let cached_descriptor_set_layout = PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap();
+            let _graphics_pipeline = GenericGraphicsPipeline::new(
+                &test_fixture_device.device,
+                &pipeline_cache,
+                &config,
+                include_bytes!("../test_data/red_triangle.vert.spv"),
+                include_bytes!("../test_data/red_triangle.frag.spv"),
+                &render_pass,
+                &specialization_constants,
+                &bindless_textures,
+                &cached_descriptor_set_layout,
+                debug_info!("my_graphics_pipeline"),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn multiply_in_post_processing() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let swapchain = Swapchain::new(&test_fixture_device.device, &test_fixture_device.surface, 2, None).unwrap();
+            let render_pass = SwapchainRenderPass::new_post_processing(&test_fixture_device.device, &swapchain).unwrap();
+            let config = GenericGraphicsPipelineConfig {
+                vertex_shader: Some(AssetKey::new("vertex_shader")),
+                fragment_shader: Some(AssetKey::new("fragment_shader")),
+                primitive_topology: PrimitiveTopology::TriangleList,
+                blend_mode: BlendMode::Multiply,
+                depth_mode: DepthMode::Disabled,
+                render_pass: PipelineRenderPass::PostProcessing,
+                framebuffer_width: swapchain.extent().width,
+                framebuffer_height: swapchain.extent().height,
+                ..Default::default()
+            };
+            let specialization_constants = SpecializationConstants::new();
+            let pipeline_cache = PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap();
+            let bindless_textures = BindlessTextures::new(&test_fixture_device.device, 16, debug_info!("my_bindless_textures")).unwrap();
+            let cached_descriptor_set_layout = PersistentFrameState::cached_descriptor_set_layout(&test_fixture_device.device).unwrap();
             let _graphics_pipeline = GenericGraphicsPipeline::new(
                 &test_fixture_device.device,
+                &pipeline_cache,
                 &config,
                 include_bytes!("../test_data/red_triangle.vert.spv"),
                 include_bytes!("../test_data/red_triangle.frag.spv"),
                 &render_pass,
                 &specialization_constants,
+                &bindless_textures,
+                &cached_descriptor_set_layout,
                 debug_info!("my_graphics_pipeline"),
             )
             .unwrap();
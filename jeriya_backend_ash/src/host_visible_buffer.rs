@@ -25,7 +25,8 @@ impl<T: Clone> HostVisibleBuffer<T> {
         let buffer = unsafe {
             let size = mem::size_of_val(data);
             let mut buffer = UnsafeBuffer::new(device, size, usage.into(), vk::SharingMode::CONCURRENT, debug_info)?;
-            buffer.allocate_memory(vk::MemoryPropertyFlags::HOST_VISIBLE)?;
+            // The memory is mapped persistently and never flushed
+            buffer.allocate_memory(vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)?;
             buffer.set_memory_unaligned(data)?;
             buffer
         };
@@ -0,0 +1,179 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::Arc,
+};
+
+use ash::vk;
+use jeriya_backend::overlay::{OverlayImage, OverlayImageId};
+use jeriya_content::font::{FontAsset, FontId};
+use jeriya_shared::{debug_info, AddressMode, SamplerConfig};
+
+use crate::{
+    buffer::BufferUsageFlags, command_buffer_builder::CommandBufferBuilder, device::Device, device_image::DeviceImage,
+    host_visible_buffer::HostVisibleBuffer, vulkan_resource_coordinator::VulkanResourceCoordinator,
+};
+
+/// Textures that have been uploaded for the immediate text rendering and the overlay
+///
+/// The font atlases and overlay images are identified by their [`FontId`] and [`OverlayImageId`] and are evicted when a
+/// frame doesn't render them.
+#[derive(Default)]
+pub struct ImmediateTextures {
+    /// Bindless texture indices of the font atlases
+    fonts: HashMap<FontId, u32>,
+    /// Bindless texture indices of the overlay images
+    overlay_images: HashMap<OverlayImageId, u32>,
+}
+
+impl ImmediateTextures {
+    /// Returns the index of the bindless texture that contains the atlas of the `font` if it has been uploaded
+    pub fn font_texture_index(&self, font: &FontAsset) -> Option<u32> {
+        self.fonts.get(&font.id()).copied()
+    }
+
+    /// Returns the index of the bindless texture that contains the `image` if it has been uploaded
+    pub fn overlay_image_texture_index(&self, image: &OverlayImage) -> Option<u32> {
+        self.overlay_images.get(&image.id()).copied()
+    }
+
+    /// Records the upload of the atlas of the `font` unless it has already been uploaded and returns the index of the bindless texture
+    ///
+    /// Has to be called outside of a render pass. The atlas is ready to be sampled by the commands that are recorded afterwards.
+    pub fn upload_font(
+        &mut self,
+        font: &FontAsset,
+        device: &Arc<Device>,
+        vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<u32> {
+        if let Some(texture_index) = self.font_texture_index(font) {
+            return Ok(texture_index);
+        }
+        let extent = vk::Extent2D {
+            width: font.atlas_width,
+            height: font.atlas_height,
+        };
+        let device_image = Arc::new(DeviceImage::with_mip_level_count(
+            device,
+            extent,
+            vk::Format::R8_UNORM,
+            1,
+            debug_info!("FontAtlas"),
+        )?);
+        let texture_index = upload(
+            &font.atlas,
+            &device_image,
+            device,
+            vulkan_resource_coordinator,
+            command_buffer_builder,
+        )?;
+        self.fonts.insert(font.id(), texture_index);
+        Ok(texture_index)
+    }
+
+    /// Records the upload of the `image` unless it has already been uploaded and returns the index of the bindless texture
+    ///
+    /// Has to be called outside of a render pass. The image is ready to be sampled by the commands that are recorded afterwards.
+    pub fn upload_overlay_image(
+        &mut self,
+        image: &OverlayImage,
+        device: &Arc<Device>,
+        vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<u32> {
+        if let Some(texture_index) = self.overlay_image_texture_index(image) {
+            return Ok(texture_index);
+        }
+        let extent = vk::Extent2D {
+            width: image.width(),
+            height: image.height(),
+        };
+        // The texels are displayed as they are because the swapchain isn't guaranteed to have an sRGB format. The full mip
+        // chain is generated so that images which are drawn smaller than their size don't alias.
+        let device_image = Arc::new(DeviceImage::new(
+            device,
+            extent,
+            vk::Format::R8G8B8A8_UNORM,
+            debug_info!("OverlayImage"),
+        )?);
+        let texture_index = upload(
+            image.rgba(),
+            &device_image,
+            device,
+            vulkan_resource_coordinator,
+            command_buffer_builder,
+        )?;
+        self.overlay_images.insert(image.id(), texture_index);
+        Ok(texture_index)
+    }
+
+    /// Removes the font atlases that are not used by the `rendered_fonts` of the current frame
+    ///
+    /// The slots of their bindless textures are only reused after the frames that might still sample them have finished.
+    pub fn evict_fonts<'a>(
+        &mut self,
+        rendered_fonts: impl IntoIterator<Item = &'a FontAsset>,
+        vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+    ) {
+        let rendered_fonts = rendered_fonts.into_iter().map(FontAsset::id).collect();
+        evict(&mut self.fonts, &rendered_fonts, vulkan_resource_coordinator);
+    }
+
+    /// Removes the overlay images that are not part of the `rendered_images` of the current frame
+    ///
+    /// The slots of their bindless textures are only reused after the frames that might still sample them have finished.
+    pub fn evict_overlay_images<'a>(
+        &mut self,
+        rendered_images: impl IntoIterator<Item = &'a OverlayImage>,
+        vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+    ) {
+        let rendered_images = rendered_images.into_iter().map(OverlayImage::id).collect();
+        evict(&mut self.overlay_images, &rendered_images, vulkan_resource_coordinator);
+    }
+}
+
+/// Removes the `textures` whose ids are not in `rendered_ids` and frees their bindless textures
+fn evict<K: Eq + Hash>(
+    textures: &mut HashMap<K, u32>,
+    rendered_ids: &HashSet<K>,
+    vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+) {
+    let bindless_textures = vulkan_resource_coordinator.bindless_textures_mut();
+    textures.retain(|id, texture_index| {
+        let is_rendered = rendered_ids.contains(id);
+        if !is_rendered {
+            bindless_textures.remove(*texture_index);
+        }
+        is_rendered
+    });
+}
+
+/// Records the upload of the tightly packed `texels` into the first mip level of the `device_image`, generates the
+/// other mip levels and inserts the image into the bindless textures. Returns the index of the bindless texture.
+fn upload(
+    texels: &[u8],
+    device_image: &Arc<DeviceImage>,
+    device: &Arc<Device>,
+    vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+    command_buffer_builder: &mut CommandBufferBuilder,
+) -> crate::Result<u32> {
+    let host_visible_buffer = Arc::new(HostVisibleBuffer::new(
+        device,
+        texels,
+        BufferUsageFlags::TRANSFER_SRC_BIT,
+        debug_info!("ImmediateTexture-HostVisibleBuffer"),
+    )?);
+    command_buffer_builder
+        .copy_buffer_to_image(&host_visible_buffer, device_image)
+        .generate_mipmaps(device_image);
+
+    let sampler_config = SamplerConfig {
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        address_mode_w: AddressMode::ClampToEdge,
+        ..SamplerConfig::linear()
+    };
+    let sampler = vulkan_resource_coordinator.query_sampler(&sampler_config)?;
+    vulkan_resource_coordinator.bindless_textures_mut().insert(device_image, &sampler)
+}
@@ -0,0 +1,255 @@
+use std::{collections::HashMap, sync::Arc};
+
+use jeriya_backend::immediate::{self, ImmediateCommand};
+use jeriya_shared::{
+    debug_info,
+    nalgebra::{Vector2, Vector3, Vector4},
+    DebugInfo,
+};
+
+use crate::{
+    buffer::BufferUsageFlags, command_buffer_builder::CommandBufferBuilder, device::Device, host_visible_buffer::HostVisibleBuffer,
+};
+
+/// Corners of the two triangles into which an immediate point is expanded when the device doesn't support large points
+const POINT_QUAD_CORNERS: [[f32; 2]; 6] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+/// Vertex buffers that contain the vertex attributes of all commands of an [`immediate::CommandBuffer`]
+///
+/// The vertices of the commands are stored in the order of the commands so that the first vertex of a command is the
+/// sum of the vertex counts of the preceding commands.
+pub struct ImmediateVertexBuffers {
+    positions: Option<Arc<HostVisibleBuffer<Vector3<f32>>>>,
+    texture_coordinates: Option<Arc<HostVisibleBuffer<Vector2<f32>>>>,
+    colors: Option<Arc<HostVisibleBuffer<[u8; 4]>>>,
+    points: Option<Arc<HostVisibleBuffer<Vector3<f32>>>>,
+    indices: Option<Arc<HostVisibleBuffer<u32>>>,
+    text_vertex_counts: Vec<usize>,
+    vertices_per_point: usize,
+}
+
+impl ImmediateVertexBuffers {
+    /// Collects the vertex attributes of the commands of the `command_buffer` into new vertex buffers
+    pub fn new(device: &Arc<Device>, command_buffer: &immediate::CommandBuffer) -> crate::Result<Self> {
+        let mut data = Vec::new();
+        let mut texture_coordinate_data = Vec::new();
+        let mut color_data = Vec::new();
+        let mut point_data = Vec::new();
+        let mut index_data = Vec::new();
+        let mut text_vertex_counts = Vec::new();
+        // Large points are drawn with the size of the point whereas the quads of the expanded points are not limited
+        let limits = &device.physical_device.physical_device_properties.limits;
+        let (point_corners, max_point_size): (&[[f32; 2]], f32) = if device.optional_features.large_points {
+            (&[[0.0, 0.0]], limits.point_size_range[1])
+        } else {
+            (&POINT_QUAD_CORNERS, f32::MAX)
+        };
+        for command in command_buffer.commands() {
+            let (positions, colors) = match command {
+                ImmediateCommand::Matrix(..) | ImmediateCommand::Space(..) => continue,
+                ImmediateCommand::PointList(point_list) => {
+                    // The point attributes are only collected for the points and the preceding vertices are padded
+                    color_data.resize(data.len(), [u8::MAX; 4]);
+                    point_data.resize(data.len(), Vector3::zeros());
+                    for (index, position) in point_list.positions().iter().enumerate() {
+                        let color = point_list.colors().map_or([u8::MAX; 4], |colors| vertex_color(&colors[index]));
+                        let size = point_list.size(index).min(max_point_size);
+                        for corner in point_corners {
+                            data.push(*position);
+                            color_data.push(color);
+                            point_data.push(Vector3::new(corner[0], corner[1], size));
+                        }
+                    }
+                    continue;
+                }
+                ImmediateCommand::LineList(line_list) => (line_list.positions(), line_list.colors()),
+                ImmediateCommand::LineStrip(line_strip) => (line_strip.positions(), line_strip.colors()),
+                ImmediateCommand::TriangleList(triangle_list) => (triangle_list.positions(), triangle_list.colors()),
+                ImmediateCommand::TriangleStrip(triangle_strip) => (triangle_strip.positions(), triangle_strip.colors()),
+                ImmediateCommand::IndexedTriangleList(indexed_triangle_list) => {
+                    // The indices are relative to the first vertex of the list which is passed as vertex offset
+                    index_data.extend_from_slice(indexed_triangle_list.indices());
+                    (indexed_triangle_list.positions(), indexed_triangle_list.colors())
+                }
+                ImmediateCommand::Text(text) => {
+                    let (positions, texture_coordinates) = text.vertices();
+                    // The texture coordinates are only collected for the texts and the preceding vertices are padded
+                    texture_coordinate_data.resize(data.len(), Vector2::zeros());
+                    data.extend_from_slice(&positions);
+                    texture_coordinate_data.extend_from_slice(&texture_coordinates);
+                    text_vertex_counts.push(positions.len());
+                    continue;
+                }
+            };
+            // The vertex colors are multiplied with the color of the config so that white keeps the config color
+            color_data.resize(data.len(), [u8::MAX; 4]);
+            data.extend_from_slice(positions);
+            match colors {
+                Some(colors) => color_data.extend(colors.iter().map(vertex_color)),
+                None => color_data.resize(data.len(), [u8::MAX; 4]),
+            }
+        }
+        Ok(Self {
+            positions: new_buffer(
+                device,
+                &data,
+                BufferUsageFlags::VERTEX_BUFFER,
+                debug_info!("Immediate-VertexBuffer"),
+            )?,
+            texture_coordinates: new_buffer(
+                device,
+                &texture_coordinate_data,
+                BufferUsageFlags::VERTEX_BUFFER,
+                debug_info!("Immediate-TextureCoordinateBuffer"),
+            )?,
+            colors: new_buffer(
+                device,
+                &color_data,
+                BufferUsageFlags::VERTEX_BUFFER,
+                debug_info!("Immediate-ColorBuffer"),
+            )?,
+            points: new_buffer(
+                device,
+                &point_data,
+                BufferUsageFlags::VERTEX_BUFFER,
+                debug_info!("Immediate-PointBuffer"),
+            )?,
+            indices: new_buffer(
+                device,
+                &index_data,
+                BufferUsageFlags::INDEX_BUFFER,
+                debug_info!("Immediate-IndexBuffer"),
+            )?,
+            text_vertex_counts,
+            vertices_per_point: point_corners.len(),
+        })
+    }
+
+    /// Binds the vertex buffers and the index buffer for the draw commands of the [`immediate::CommandBuffer`]
+    pub fn bind(&self, command_buffer_builder: &mut CommandBufferBuilder) {
+        if let Some(positions) = &self.positions {
+            command_buffer_builder.bind_vertex_buffers(0, positions);
+        }
+        if let Some(texture_coordinates) = &self.texture_coordinates {
+            command_buffer_builder.bind_vertex_buffers(1, texture_coordinates);
+        }
+        if let Some(colors) = &self.colors {
+            command_buffer_builder.bind_vertex_buffers(2, colors);
+        }
+        if let Some(points) = &self.points {
+            command_buffer_builder.bind_vertex_buffers(3, points);
+        }
+        if let Some(indices) = &self.indices {
+            command_buffer_builder.bind_index_buffer(indices);
+        }
+    }
+
+    /// Returns the number of vertices of every [`Text`](immediate::Text) in the order of the commands
+    pub fn text_vertex_counts(&self) -> &[usize] {
+        &self.text_vertex_counts
+    }
+
+    /// Returns the number of vertices with which a single point is drawn
+    pub fn vertices_per_point(&self) -> usize {
+        self.vertices_per_point
+    }
+}
+
+/// Vertex buffers of the [`immediate::CommandBuffer`]s that are rendered by a presenter
+///
+/// The command buffers are identified by [`immediate::CommandBuffer::id`]. The cache holds a clone of every command buffer
+/// so that the identifier can't be reused by another one while the vertex buffers are cached.
+#[derive(Default)]
+pub struct ImmediateVertexBufferCache {
+    entries: HashMap<usize, ImmediateVertexBufferCacheEntry>,
+}
+
+struct ImmediateVertexBufferCacheEntry {
+    _command_buffer: immediate::CommandBuffer,
+    vertex_buffers: ImmediateVertexBuffers,
+}
+
+impl ImmediateVertexBufferCache {
+    /// Creates the vertex buffers of the `command_buffers` that aren't cached yet and removes the vertex buffers of
+    /// the command buffers that are not rendered anymore.
+    pub fn update<'a>(
+        &mut self,
+        device: &Arc<Device>,
+        command_buffers: impl IntoIterator<Item = &'a immediate::CommandBuffer>,
+    ) -> crate::Result<()> {
+        let mut entries = HashMap::new();
+        for command_buffer in command_buffers {
+            let entry = match self.entries.remove(&command_buffer.id()) {
+                Some(entry) => entry,
+                None => ImmediateVertexBufferCacheEntry {
+                    _command_buffer: command_buffer.clone(),
+                    vertex_buffers: ImmediateVertexBuffers::new(device, command_buffer)?,
+                },
+            };
+            entries.insert(command_buffer.id(), entry);
+        }
+        // The command buffers that are still executing keep the vertex buffers of the removed entries alive
+        self.entries = entries;
+        Ok(())
+    }
+
+    /// Returns the vertex buffers of the `command_buffer` if they have been created by [`ImmediateVertexBufferCache::update`]
+    pub fn get(&self, command_buffer: &immediate::CommandBuffer) -> Option<&ImmediateVertexBuffers> {
+        self.entries.get(&command_buffer.id()).map(|entry| &entry.vertex_buffers)
+    }
+}
+
+/// Creates a buffer with the `data` or returns `None` when there is no data because Vulkan doesn't allow empty buffers
+fn new_buffer<T: Copy + Send + Sync + 'static>(
+    device: &Arc<Device>,
+    data: &[T],
+    buffer_usage_flags: BufferUsageFlags,
+    debug_info: DebugInfo,
+) -> crate::Result<Option<Arc<HostVisibleBuffer<T>>>> {
+    if data.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(HostVisibleBuffer::new(
+        device,
+        data,
+        buffer_usage_flags,
+        debug_info,
+    )?)))
+}
+
+/// Converts the color of an immediate vertex into the RGBA8 format of the vertex buffer
+fn vertex_color(color: &Vector4<f32>) -> [u8; 4] {
+    color.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8).into()
+}
+
+#[cfg(test)]
+mod tests {
+    mod update {
+        use jeriya_backend::immediate::{CommandBufferBuilder, LineConfig, LineList};
+        use jeriya_shared::{debug_info, nalgebra::Vector3};
+
+        use crate::{device::TestFixtureDevice, immediate_vertex_buffers::ImmediateVertexBufferCache};
+
+        #[test]
+        fn keeps_rendered_command_buffers() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let line_list = LineList::new(vec![Vector3::zeros(), Vector3::x()], LineConfig::default());
+            let command_buffer = CommandBufferBuilder::new(debug_info!("my_command_buffer"))
+                .push_line_lists(&[line_list])
+                .unwrap()
+                .build()
+                .unwrap();
+            let mut cache = ImmediateVertexBufferCache::default();
+            cache.update(&test_fixture_device.device, [&command_buffer]).unwrap();
+            let positions = cache.get(&command_buffer).unwrap().positions.clone().unwrap();
+
+            // The vertex buffers are reused while the command buffer is rendered
+            cache.update(&test_fixture_device.device, [&command_buffer.clone()]).unwrap();
+            let reused_positions = cache.get(&command_buffer).unwrap().positions.clone().unwrap();
+            assert!(std::sync::Arc::ptr_eq(&positions, &reused_positions));
+
+            cache.update(&test_fixture_device.device, []).unwrap();
+            assert!(cache.get(&command_buffer).is_none());
+        }
+    }
+}
@@ -64,6 +64,11 @@ impl Instance {
         if enable_validation_layer {
             active_extensions.push(expect_extension(DebugUtils::name()));
         }
+        // Required for the HDR color spaces of the swapchain but not available on every platform
+        let swapchain_colorspace = expect_extension(vk::ExtSwapchainColorspaceFn::name());
+        if available_extensions(entry)?.contains(&swapchain_colorspace) {
+            active_extensions.push(swapchain_colorspace);
+        }
         info!("Active Extensions:\n{}", list_strings(&active_extensions));
 
         let active_layers_c = active_layers
@@ -135,6 +140,16 @@ fn available_layers(entry: &Entry) -> Result<Vec<String>> {
     Ok(result)
 }
 
+fn available_extensions(entry: &Entry) -> Result<Vec<String>> {
+    let extension_properties = entry.as_raw_vulkan().enumerate_instance_extension_properties(None)?;
+    let result = extension_properties
+        .iter()
+        .map(|properties| &properties.extension_name)
+        .map(|array| jeriya_shared::c_null_terminated_char_array_to_string(array).map_err(Error::StringUtf8Error))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -7,40 +7,61 @@
 
 mod ash_backend;
 mod backend_shared;
+mod bindless_textures;
 mod buffer;
+mod cached_descriptor_set;
 mod command_buffer;
 mod command_buffer_builder;
 mod command_pool;
 mod compiled_frame_graph;
 mod compute_pipeline;
+mod custom_pass;
 mod debug;
 mod descriptor;
+mod descriptor_pool;
 mod descriptor_set_layout;
 mod device;
+mod device_image;
 mod device_visible_buffer;
 mod entry;
 mod fence;
+mod frame_capture;
 mod frame_index;
 mod frame_local_buffer;
+mod frame_telemetry;
+mod frame_timings;
 mod graphics_pipeline;
 mod host_visible_buffer;
+mod immediate_textures;
+mod immediate_vertex_buffers;
 mod instance;
+mod memory_allocator;
+mod optional_features;
 // PageBuffer is currently not used fully
 mod debug_label_guard;
 #[allow(dead_code)]
 mod page_buffer;
 mod persistent_frame_state;
 mod physical_device;
+mod pipeline_cache;
+mod point_cloud_page_streaming;
+mod post_processing_pass;
 mod presenter;
 mod presenter_shared;
 mod push_descriptors;
+mod query_pool;
 mod queue;
 mod queue_plan;
 mod queue_scheduler;
+mod readback;
+mod render_target;
+mod retired_resources;
+mod sampler;
 mod semaphore;
 mod shader_interface;
 mod shader_module;
 mod specialization_constants;
+mod spirv_reflection;
 mod staged_push_only_buffer;
 mod surface;
 mod swapchain;
@@ -49,9 +70,22 @@ mod swapchain_framebuffers;
 mod swapchain_render_pass;
 mod swapchain_vec;
 mod unsafe_buffer;
+mod user_compute_pass;
+mod user_interface;
 mod vulkan_resource_coordinator;
 
 pub use ash_backend::*;
+pub use custom_pass::{Access, CustomPass, CustomPassContext, CustomPassId, CustomPassPosition, FrameResource, PassStage, ResourceAccess};
+pub use frame_capture::{CapturedFrame, FrameCaptureConfig, FrameCaptureHandle, FrameCaptureTarget};
+pub use frame_telemetry::FrameTelemetry;
+pub use frame_timings::{FrameTimings, PassTiming};
+pub use memory_allocator::{MemoryBlockStatistics, MemoryStatistics};
+pub use optional_features::{OptionalFeature, OptionalFeatures};
+pub use physical_device::{PhysicalDeviceInfo, PhysicalDeviceSelection, PhysicalDeviceSelector};
+pub use post_processing_pass::PostProcessingPass;
+pub use readback::{DepthImage, DepthRegion, Readback, ReadbackBuffer};
+pub use shader_interface::bindings;
+pub use user_compute_pass::{UserComputePass, UserComputePassDispatch, USER_COMPUTE_PASS_LOCAL_SIZE_X};
 
 use jeriya_content::common::AssetKey;
 pub use vk::{DispatchIndirectCommand, DrawIndirectCommand};
@@ -129,11 +163,13 @@ impl<T> IntoJeriya for VkResult<T> {
 
 #[derive(Debug, Clone)]
 pub enum PhysicalDeviceFeature {
-    WideLines,
     ShaderInt64,
     MultiDrawIndirect,
+    ShaderClipDistance,
     ShaderDrawParameters,
     DrawIndirectCount,
+    PipelineStatisticsQuery,
+    DescriptorIndexing,
 }
 
 /// Errors in the ash backend
@@ -151,6 +187,10 @@ pub enum Error {
     StringUtf8Error(#[from] Utf8Error),
     #[error("Failed to find physical devices")]
     NoPhysicalDevices,
+    #[error("The physical device with the index {0} doesn't exist")]
+    UnknownPhysicalDevice(usize),
+    #[error("The selection callback didn't select a physical device")]
+    NoPhysicalDeviceSelected,
     #[error("Failed to find suitable queues")]
     NoSuitableQueues,
     #[error("Failed to find a suitable swapchain surface format")]
@@ -159,8 +199,14 @@ pub enum Error {
     UnknownWindowId(WindowId),
     #[error("Failed to find a matching memory type for the memory requirements")]
     UnsupportedMemoryType(vk::MemoryRequirements),
+    #[error("The image format {:?} doesn't support the required operations", .0)]
+    UnsupportedImageFormat(vk::Format),
     #[error("Failed to decode SPIR-V code")]
     SpirvDecode,
+    #[error("The descriptor binding {binding} doesn't match the shader: {details}")]
+    DescriptorMismatch { binding: u32, details: String },
+    #[error("The compute shader doesn't take the x component of its workgroup size from the specialization constant {0}")]
+    WorkgroupSizeNotSpecialized(u32),
     #[error("No Pipeline bound")]
     NoPipelineBound,
     #[error("The physical device doesn't support a feature that is expected")]
@@ -171,19 +217,47 @@ pub enum Error {
     FailedToAllocate(&'static str),
     #[error("BufferOverflow")]
     WouldOverflow,
+    #[error("The index {index} exceeds the capacity {capacity}")]
+    CapacityExceeded { index: usize, capacity: usize },
     #[error("Element was not found")]
     NotFound,
     #[error("Failed to receive asset from asset importer")]
     FailedToReceiveAsset(String), // String contains the details
     #[error("Failed to get asset '{asset_key}' from asset importer: {details}")]
     AssetNotFound { asset_key: AssetKey, details: String },
+    #[error("The pipeline of the shader '{asset_key}' failed before and is only built again when the shader is updated: {details}")]
+    PipelineFailedBefore { asset_key: AssetKey, details: String },
+    #[error("The readback was aborted before the data was received")]
+    ReadbackAborted,
+    #[error("The swapchain of the window doesn't support capturing frames")]
+    FrameCaptureNotSupported,
+    #[error("Failed to write the captured frame: {0}")]
+    FrameCaptureFailed(String),
+    #[error("The UiMesh {mesh} is invalid: {details}")]
+    InvalidUiMesh { mesh: usize, details: String },
+    #[error("The adaptive frame rate must satisfy 0 < min <= max but is min = {min} and max = {max}")]
+    InvalidAdaptiveFrameRate { min: u32, max: u32 },
+    #[error("The extent {:?} must not be empty", .0)]
+    EmptyExtent(vk::Extent2D),
+    #[error("Error while accessing a file: {:?}", .0)]
+    Io(#[from] std::io::Error),
     #[error("Error from the content module: {:?}", .0)]
     ContentError(#[from] jeriya_content::Error),
 }
 
+impl Error {
+    /// Returns whether the error is caused by a swapchain that doesn't match its surface anymore and has to be recreated
+    pub fn is_swapchain_out_of_date(&self) -> bool {
+        matches!(self, Error::Result(vk::Result::ERROR_OUT_OF_DATE_KHR))
+    }
+}
+
 impl From<Error> for jeriya_backend::Error {
     fn from(value: Error) -> Self {
-        jeriya_backend::Error::Backend(Box::new(value))
+        match value {
+            Error::CapacityExceeded { capacity, .. } => jeriya_backend::Error::MaximumCapacityReached(capacity),
+            value => jeriya_backend::Error::Backend(Box::new(value)),
+        }
     }
 }
 
@@ -201,4 +275,5 @@ impl Default for ValidationLayerConfig {
 #[derive(Default)]
 pub struct Config {
     pub validation_layer: ValidationLayerConfig,
+    pub physical_device_selection: PhysicalDeviceSelection,
 }
@@ -0,0 +1,411 @@
+use std::{ffi::c_void, ops::Range};
+
+use ash::vk;
+use jeriya_shared::{
+    debug_info,
+    log::{info, trace},
+    parking_lot::Mutex,
+    AsDebugInfo, DebugInfo,
+};
+
+use crate::DebugInfoAshExtension;
+
+/// Default size of the `vk::DeviceMemory` blocks from which the allocations are suballocated
+pub const DEFAULT_BLOCK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Statistics of a single [`MemoryBlock`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryBlockStatistics {
+    /// [`DebugInfo`] of the `vk::DeviceMemory`
+    pub debug_info: DebugInfo,
+    pub memory_type_index: u32,
+    /// Size of the `vk::DeviceMemory` in bytes
+    pub size: u64,
+    /// Number of bytes that are handed out to allocations
+    pub used_bytes: u64,
+    /// [`DebugInfo`]s of the resources that are bound to the block
+    pub allocations: Vec<DebugInfo>,
+}
+
+/// Statistics of the [`MemoryAllocator`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryStatistics {
+    pub blocks: Vec<MemoryBlockStatistics>,
+}
+
+impl MemoryStatistics {
+    /// Returns the number of `vk::DeviceMemory` objects that are allocated
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the number of allocations in all blocks
+    pub fn allocation_count(&self) -> usize {
+        self.blocks.iter().map(|block| block.allocations.len()).sum()
+    }
+
+    /// Returns the sum of the sizes of all blocks in bytes
+    pub fn block_bytes(&self) -> u64 {
+        self.blocks.iter().map(|block| block.size).sum()
+    }
+
+    /// Returns the sum of the sizes of all allocations in bytes
+    pub fn used_bytes(&self) -> u64 {
+        self.blocks.iter().map(|block| block.used_bytes).sum()
+    }
+}
+
+/// Pointer to persistently mapped memory
+#[derive(Debug, Clone, Copy)]
+struct MappedPtr(*mut c_void);
+
+// The pointer is only dereferenced by the owner of the `MemoryAllocation` which is responsible for the synchronization.
+unsafe impl Send for MappedPtr {}
+unsafe impl Sync for MappedPtr {}
+
+/// Range of a `vk::DeviceMemory` that has been handed out by the [`MemoryAllocator`]
+#[derive(Debug)]
+pub struct MemoryAllocation {
+    memory: vk::DeviceMemory,
+    offset: u64,
+    size: u64,
+    mapped_ptr: Option<MappedPtr>,
+}
+
+impl MemoryAllocation {
+    /// Returns the `vk::DeviceMemory` in which the allocation lives
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    /// Returns the offset of the allocation in the `vk::DeviceMemory` in bytes
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the size of the allocation in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the pointer to the beginning of the allocation when the memory is host visible
+    pub fn mapped_ptr(&self) -> Option<*mut c_void> {
+        self.mapped_ptr
+            .map(|MappedPtr(ptr)| unsafe { ptr.cast::<u8>().add(self.offset as usize).cast::<c_void>() })
+    }
+}
+
+/// Free ranges of a [`MemoryBlock`] sorted by their offset
+#[derive(Debug, Clone, PartialEq)]
+struct FreeList {
+    ranges: Vec<Range<u64>>,
+}
+
+impl FreeList {
+    /// Creates a new `FreeList` in which the whole range `0..size` is free
+    fn new(size: u64) -> Self {
+        Self {
+            ranges: vec![Range { start: 0, end: size }],
+        }
+    }
+
+    /// Takes the first free range that fits `size` bytes at the given `alignment` and returns its offset
+    fn allocate(&mut self, size: u64, alignment: u64) -> Option<u64> {
+        let alignment = alignment.max(1);
+        let (index, offset) = self.ranges.iter().enumerate().find_map(|(index, range)| {
+            let offset = range.start.next_multiple_of(alignment);
+            (offset + size <= range.end).then_some((index, offset))
+        })?;
+        let range = self.ranges.remove(index);
+        let mut insert_index = index;
+        if range.start < offset {
+            self.ranges.insert(insert_index, range.start..offset);
+            insert_index += 1;
+        }
+        if offset + size < range.end {
+            self.ranges.insert(insert_index, offset + size..range.end);
+        }
+        Some(offset)
+    }
+
+    /// Returns the `range` to the `FreeList` and merges it with the adjacent free ranges
+    fn free(&mut self, range: Range<u64>) {
+        let index = self.ranges.partition_point(|free_range| free_range.start < range.start);
+        jeriya_shared::assert!(
+            index == 0 || self.ranges[index - 1].end <= range.start,
+            "the range overlaps with a free range"
+        );
+        self.ranges.insert(index, range);
+        if index + 1 < self.ranges.len() && self.ranges[index].end == self.ranges[index + 1].start {
+            let next = self.ranges.remove(index + 1);
+            self.ranges[index].end = next.end;
+        }
+        if index > 0 && self.ranges[index - 1].end == self.ranges[index].start {
+            let current = self.ranges.remove(index);
+            self.ranges[index - 1].end = current.end;
+        }
+    }
+
+    /// Returns the number of free bytes
+    fn free_bytes(&self) -> u64 {
+        self.ranges.iter().map(|range| range.end - range.start).sum()
+    }
+}
+
+/// A `vk::DeviceMemory` from which allocations are suballocated
+struct MemoryBlock {
+    memory: vk::DeviceMemory,
+    memory_type_index: u32,
+    size: u64,
+    mapped_ptr: Option<MappedPtr>,
+    free_list: FreeList,
+    /// Offset, size and [`DebugInfo`] of the resources that are bound to the block
+    allocations: Vec<(u64, u64, DebugInfo)>,
+    debug_info: DebugInfo,
+}
+
+impl AsDebugInfo for MemoryBlock {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+/// Allocates `vk::DeviceMemory` in large blocks and suballocates the memory of resources from them.
+///
+/// Vulkan implementations limit the number of `vk::DeviceMemory` objects (`maxMemoryAllocationCount`) which would
+/// be exhausted quickly when every buffer had its own allocation. Host visible blocks are mapped persistently
+/// because a `vk::DeviceMemory` can only be mapped once at a time.
+pub struct MemoryAllocator {
+    block_size: u64,
+    blocks: Mutex<Vec<MemoryBlock>>,
+}
+
+impl MemoryAllocator {
+    /// Creates a new `MemoryAllocator` that allocates blocks of at least `block_size` bytes
+    pub fn new(block_size: u64) -> Self {
+        Self {
+            block_size,
+            blocks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Suballocates memory for the given `memory_requirements` from a block of the given memory type.
+    ///
+    /// Allocates a new block when no existing block has enough space. Allocations that are larger than the
+    /// block size get a block of their own. The `debug_info` is stored to be reported by [`MemoryAllocator::statistics`].
+    ///
+    /// # Safety
+    ///
+    /// The `device` must be the device on which all previous allocations have been made.
+    pub unsafe fn allocate(
+        &self,
+        device: &ash::Device,
+        memory_requirements: &vk::MemoryRequirements,
+        memory_type_index: u32,
+        host_visible: bool,
+        debug_info: &DebugInfo,
+    ) -> crate::Result<MemoryAllocation> {
+        let mut blocks = self.blocks.lock();
+
+        // Try to suballocate from an existing block
+        let existing = blocks
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, block)| block.memory_type_index == memory_type_index)
+            .find_map(|(index, block)| {
+                let offset = block.free_list.allocate(memory_requirements.size, memory_requirements.alignment)?;
+                Some((index, offset))
+            });
+        let (block_index, offset) = match existing {
+            Some(existing) => existing,
+            None => {
+                let size = memory_requirements.size.max(self.block_size);
+                let mut block = Self::allocate_block(device, memory_type_index, size, host_visible, blocks.len())?;
+                let offset = block
+                    .free_list
+                    .allocate(memory_requirements.size, memory_requirements.alignment)
+                    .expect("a new block must fit the allocation");
+                blocks.push(block);
+                (blocks.len() - 1, offset)
+            }
+        };
+
+        let block = &mut blocks[block_index];
+        block.allocations.push((offset, memory_requirements.size, debug_info.clone()));
+        Ok(MemoryAllocation {
+            memory: block.memory,
+            offset,
+            size: memory_requirements.size,
+            mapped_ptr: block.mapped_ptr,
+        })
+    }
+
+    /// Returns the memory of the `allocation` to its block. Blocks without allocations are freed.
+    ///
+    /// # Safety
+    ///
+    /// The `allocation` must have been allocated by this `MemoryAllocator` on the given `device`
+    /// and the resource bound to it must not be in use anymore.
+    pub unsafe fn free(&self, device: &ash::Device, allocation: MemoryAllocation) {
+        let mut blocks = self.blocks.lock();
+        let block_index = blocks
+            .iter()
+            .position(|block| block.memory == allocation.memory)
+            .expect("the allocation must belong to a block of the MemoryAllocator");
+        let block = &mut blocks[block_index];
+        block.allocations.retain(|(offset, _, _)| *offset != allocation.offset);
+        block.free_list.free(allocation.offset..allocation.offset + allocation.size);
+        if block.allocations.is_empty() {
+            let block = blocks.swap_remove(block_index);
+            trace!("Freeing empty {}", block.debug_info.format_one_line());
+            Self::free_block(device, block);
+        }
+    }
+
+    /// Frees all blocks
+    ///
+    /// # Safety
+    ///
+    /// Must only be called when the `device` is about to be destroyed.
+    pub unsafe fn destroy(&self, device: &ash::Device) {
+        for block in self.blocks.lock().drain(..) {
+            Self::free_block(device, block);
+        }
+    }
+
+    /// Returns the [`MemoryStatistics`] of all blocks
+    pub fn statistics(&self) -> MemoryStatistics {
+        let blocks = self.blocks.lock();
+        MemoryStatistics {
+            blocks: blocks
+                .iter()
+                .map(|block| MemoryBlockStatistics {
+                    debug_info: block.debug_info.clone(),
+                    memory_type_index: block.memory_type_index,
+                    size: block.size,
+                    used_bytes: block.size - block.free_list.free_bytes(),
+                    allocations: block.allocations.iter().map(|(_, _, debug_info)| debug_info.clone()).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    unsafe fn allocate_block(
+        device: &ash::Device,
+        memory_type_index: u32,
+        size: u64,
+        host_visible: bool,
+        index: usize,
+    ) -> crate::Result<MemoryBlock> {
+        let memory_allocate_info = vk::MemoryAllocateInfo {
+            allocation_size: size,
+            memory_type_index,
+            ..Default::default()
+        };
+        let memory = device.allocate_memory(&memory_allocate_info, None)?;
+        let mapped_ptr = if host_visible {
+            match device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) {
+                Ok(ptr) => Some(MappedPtr(ptr)),
+                Err(err) => {
+                    device.free_memory(memory, None);
+                    return Err(err.into());
+                }
+            }
+        } else {
+            None
+        };
+        let debug_info = debug_info!(format!("MemoryBlock-{index}-for-MemoryType{memory_type_index}")).with_vulkan_ptr(memory);
+        info!("Allocated {} with {size} bytes", debug_info.format_one_line());
+        Ok(MemoryBlock {
+            memory,
+            memory_type_index,
+            size,
+            mapped_ptr,
+            free_list: FreeList::new(size),
+            allocations: Vec::new(),
+            debug_info,
+        })
+    }
+
+    unsafe fn free_block(device: &ash::Device, block: MemoryBlock) {
+        if block.mapped_ptr.is_some() {
+            device.unmap_memory(block.memory);
+        }
+        device.free_memory(block.memory, None);
+    }
+}
+
+impl Default for MemoryAllocator {
+    fn default() -> Self {
+        Self::new(DEFAULT_BLOCK_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod free_list {
+        use super::*;
+
+        #[test]
+        fn allocate() {
+            let mut free_list = FreeList::new(100);
+            assert_eq!(free_list.allocate(10, 1), Some(0));
+            assert_eq!(free_list.allocate(10, 16), Some(16));
+            assert_eq!(free_list.ranges, vec![10..16, 26..100]);
+            assert_eq!(free_list.allocate(100, 1), None);
+            assert_eq!(free_list.free_bytes(), 80);
+        }
+
+        #[test]
+        fn free_merges_adjacent_ranges() {
+            let mut free_list = FreeList::new(30);
+            let a = free_list.allocate(10, 1).unwrap();
+            let b = free_list.allocate(10, 1).unwrap();
+            let c = free_list.allocate(10, 1).unwrap();
+            free_list.free(a..a + 10);
+            free_list.free(c..c + 10);
+            assert_eq!(free_list.ranges, vec![0..10, 20..30]);
+            free_list.free(b..b + 10);
+            assert_eq!(free_list.ranges, vec![Range { start: 0, end: 30 }]);
+        }
+    }
+
+    mod allocate {
+        use jeriya_shared::debug_info;
+
+        use crate::{device::TestFixtureDevice, AsRawVulkan};
+
+        use super::*;
+
+        #[test]
+        fn suballocates_from_one_block() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let device = test_fixture_device.device.as_raw_vulkan();
+            let memory_allocator = MemoryAllocator::new(1024);
+            let memory_requirements = vk::MemoryRequirements {
+                size: 100,
+                alignment: 64,
+                memory_type_bits: u32::MAX,
+            };
+            unsafe {
+                let a = memory_allocator
+                    .allocate(device, &memory_requirements, 0, false, &debug_info!("a"))
+                    .unwrap();
+                let b = memory_allocator
+                    .allocate(device, &memory_requirements, 0, false, &debug_info!("b"))
+                    .unwrap();
+                assert_eq!(a.memory(), b.memory());
+                assert_eq!(b.offset(), 128);
+                let statistics = memory_allocator.statistics();
+                assert_eq!(statistics.block_count(), 1);
+                assert_eq!(statistics.allocation_count(), 2);
+                assert_eq!(statistics.blocks[0].allocations[1].name(), "b");
+                memory_allocator.free(device, a);
+                memory_allocator.free(device, b);
+                assert_eq!(memory_allocator.statistics().block_count(), 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use ash::vk;
+use jeriya_shared::log::info;
+
+/// Features of the physical device without which the renderer still works with reduced functionality
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptionalFeature {
+    /// Immediate and overlay lines are drawn with a width of 1.0 when `wideLines` is not available
+    WideLines,
+    /// Pipeline statistics are not collected when `pipelineStatisticsQuery` is not available
+    PipelineStatisticsQuery,
+    /// Samplers fall back to isotropic filtering when `samplerAnisotropy` is not available
+    SamplerAnisotropy,
+    /// Immediate points are expanded into quads when `largePoints` is not available
+    LargePoints,
+    /// [`ClipPlane`](jeriya_backend::clip_plane::ClipPlane)s can't be set and pipelines whose shaders write `gl_ClipDistance`
+    /// can't be created when `shaderClipDistance` is not available
+    ShaderClipDistance,
+    /// Textures can't be inserted into the [`BindlessTextures`](crate::bindless_textures::BindlessTextures) and texts, the
+    /// overlay and the user interface are not rendered when the descriptor indexing features of Vulkan 1.2 are not available
+    DescriptorIndexing,
+}
+
+impl OptionalFeature {
+    /// All `OptionalFeature`s in the order in which they are negotiated
+    pub const ALL: [OptionalFeature; 6] = [
+        OptionalFeature::WideLines,
+        OptionalFeature::PipelineStatisticsQuery,
+        OptionalFeature::SamplerAnisotropy,
+        OptionalFeature::LargePoints,
+        OptionalFeature::ShaderClipDistance,
+        OptionalFeature::DescriptorIndexing,
+    ];
+
+    fn is_available(
+        &self,
+        available_features: &vk::PhysicalDeviceFeatures,
+        available_vulkan_1_2_features: &vk::PhysicalDeviceVulkan12Features,
+    ) -> bool {
+        let available = |feature: vk::Bool32| feature == vk::TRUE;
+        match self {
+            OptionalFeature::WideLines => available(available_features.wide_lines),
+            OptionalFeature::PipelineStatisticsQuery => available(available_features.pipeline_statistics_query),
+            OptionalFeature::SamplerAnisotropy => available(available_features.sampler_anisotropy),
+            OptionalFeature::LargePoints => available(available_features.large_points),
+            OptionalFeature::ShaderClipDistance => available(available_features.shader_clip_distance),
+            // The bindless texture array is a partially bound, variable-sized array that is updated while in use
+            OptionalFeature::DescriptorIndexing => [
+                available_vulkan_1_2_features.descriptor_indexing,
+                available_vulkan_1_2_features.runtime_descriptor_array,
+                available_vulkan_1_2_features.shader_sampled_image_array_non_uniform_indexing,
+                available_vulkan_1_2_features.descriptor_binding_partially_bound,
+                available_vulkan_1_2_features.descriptor_binding_variable_descriptor_count,
+                available_vulkan_1_2_features.descriptor_binding_sampled_image_update_after_bind,
+                available_vulkan_1_2_features.descriptor_binding_update_unused_while_pending,
+            ]
+            .into_iter()
+            .all(available),
+        }
+    }
+}
+
+/// Records which [`OptionalFeature`]s are enabled on the device
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptionalFeatures {
+    pub wide_lines: bool,
+    pub pipeline_statistics_query: bool,
+    pub sampler_anisotropy: bool,
+    pub large_points: bool,
+    pub shader_clip_distance: bool,
+    pub descriptor_indexing: bool,
+}
+
+impl OptionalFeatures {
+    /// Enables every [`OptionalFeature`] that is available on the physical device and logs the decisions
+    pub fn negotiate(
+        available_features: &vk::PhysicalDeviceFeatures,
+        available_vulkan_1_2_features: &vk::PhysicalDeviceVulkan12Features,
+    ) -> Self {
+        let mut optional_features = Self::default();
+        for feature in OptionalFeature::ALL {
+            let available = feature.is_available(available_features, available_vulkan_1_2_features);
+            optional_features.set(feature, available);
+            if available {
+                info!("Enabling the optional feature {feature:?}");
+            } else {
+                info!("The PhysicalDevice doesn't support the optional feature {feature:?}. Dependent render paths are disabled.");
+            }
+        }
+        optional_features
+    }
+
+    /// Returns whether the given [`OptionalFeature`] is enabled
+    pub fn is_enabled(&self, feature: OptionalFeature) -> bool {
+        match feature {
+            OptionalFeature::WideLines => self.wide_lines,
+            OptionalFeature::PipelineStatisticsQuery => self.pipeline_statistics_query,
+            OptionalFeature::SamplerAnisotropy => self.sampler_anisotropy,
+            OptionalFeature::LargePoints => self.large_points,
+            OptionalFeature::ShaderClipDistance => self.shader_clip_distance,
+            OptionalFeature::DescriptorIndexing => self.descriptor_indexing,
+        }
+    }
+
+    /// Returns the [`OptionalFeature`]s that are not enabled
+    pub fn disabled(&self) -> Vec<OptionalFeature> {
+        OptionalFeature::ALL
+            .into_iter()
+            .filter(|feature| !self.is_enabled(*feature))
+            .collect()
+    }
+
+    fn set(&mut self, feature: OptionalFeature, enabled: bool) {
+        match feature {
+            OptionalFeature::WideLines => self.wide_lines = enabled,
+            OptionalFeature::PipelineStatisticsQuery => self.pipeline_statistics_query = enabled,
+            OptionalFeature::SamplerAnisotropy => self.sampler_anisotropy = enabled,
+            OptionalFeature::LargePoints => self.large_points = enabled,
+            OptionalFeature::ShaderClipDistance => self.shader_clip_distance = enabled,
+            OptionalFeature::DescriptorIndexing => self.descriptor_indexing = enabled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod negotiate {
+        use ash::vk;
+
+        use crate::optional_features::{OptionalFeature, OptionalFeatures};
+
+        fn descriptor_indexing() -> vk::PhysicalDeviceVulkan12Features {
+            vk::PhysicalDeviceVulkan12Features::builder()
+                .descriptor_indexing(true)
+                .runtime_descriptor_array(true)
+                .shader_sampled_image_array_non_uniform_indexing(true)
+                .descriptor_binding_partially_bound(true)
+                .descriptor_binding_variable_descriptor_count(true)
+                .descriptor_binding_sampled_image_update_after_bind(true)
+                .descriptor_binding_update_unused_while_pending(true)
+                .build()
+        }
+
+        #[test]
+        fn all_available() {
+            let available_features = vk::PhysicalDeviceFeatures::builder()
+                .wide_lines(true)
+                .pipeline_statistics_query(true)
+                .sampler_anisotropy(true)
+                .large_points(true)
+                .shader_clip_distance(true)
+                .build();
+            let optional_features = OptionalFeatures::negotiate(&available_features, &descriptor_indexing());
+            assert!(OptionalFeature::ALL.iter().all(|feature| optional_features.is_enabled(*feature)));
+            assert!(optional_features.disabled().is_empty());
+        }
+
+        #[test]
+        fn wide_lines_missing() {
+            let available_features = vk::PhysicalDeviceFeatures::builder()
+                .pipeline_statistics_query(true)
+                .sampler_anisotropy(true)
+                .large_points(true)
+                .shader_clip_distance(true)
+                .build();
+            let optional_features = OptionalFeatures::negotiate(&available_features, &descriptor_indexing());
+            assert!(!optional_features.wide_lines);
+            assert_eq!(optional_features.disabled(), vec![OptionalFeature::WideLines]);
+        }
+
+        #[test]
+        fn descriptor_indexing_partially_missing() {
+            let available_vulkan_1_2_features = vk::PhysicalDeviceVulkan12Features {
+                descriptor_binding_update_unused_while_pending: vk::FALSE,
+                ..descriptor_indexing()
+            };
+            let optional_features = OptionalFeatures::negotiate(&vk::PhysicalDeviceFeatures::default(), &available_vulkan_1_2_features);
+            assert!(!optional_features.descriptor_indexing);
+            assert!(optional_features.disabled().contains(&OptionalFeature::DescriptorIndexing));
+        }
+    }
+}
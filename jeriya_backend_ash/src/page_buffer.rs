@@ -94,6 +94,7 @@ impl<P: Clone + Send + Sync + 'static> PageBuffer<P> {
                 command_buffer.push_dependency(self.device_visible_buffer.clone());
             }
         }
+        self.device.record_upload(host_visible_buffer.byte_size());
 
         Ok(indices)
     }
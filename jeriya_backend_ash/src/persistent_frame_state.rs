@@ -1,27 +1,56 @@
-use std::{collections::VecDeque, mem, sync::Arc};
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet, VecDeque},
+    mem,
+    sync::Arc,
+};
 
 use crate::{
     backend_shared::BackendShared,
     buffer::BufferUsageFlags,
+    cached_descriptor_set::CachedDescriptorSet,
     command_buffer_builder::CommandBufferBuilder,
     command_buffer_builder::PipelineBindPoint,
     command_pool::{CommandPool, CommandPoolCreateFlags},
+    descriptor::Descriptor,
     descriptor_set_layout::DescriptorSetLayout,
+    device::Device,
     device_visible_buffer::DeviceVisibleBuffer,
     fence::Fence,
+    frame_capture::PendingFrameCapture,
     frame_local_buffer::FrameLocalBuffer,
+    frame_telemetry::FrameTelemetry,
+    frame_timings::FrameTimings,
     host_visible_buffer::HostVisibleBuffer,
     push_descriptors::PushDescriptors,
+    query_pool::{QueryPool, QueryType},
+    readback::{PendingDepthReadback, PendingReadback, ReadbackBuffer},
     semaphore::Semaphore,
-    shader_interface, DispatchIndirectCommand, DrawIndirectCommand,
+    shader_interface::{self, bindings, sets},
+    DispatchIndirectCommand, DrawIndirectCommand,
 };
 use jeriya_backend::{
     elements::{camera, point_cloud, rigid_mesh},
-    instances::{camera_instance, point_cloud_instance, rigid_mesh_instance},
+    gpu_index_allocator::GpuIndexAllocation,
+    instances::{
+        camera_instance,
+        point_cloud_instance::{self, PointCloudInstance},
+        rigid_mesh_instance::{self, RigidMeshInstance},
+    },
+    render_target::RenderTargetId,
     transactions::{self, Transaction},
 };
 use jeriya_macros::profile;
-use jeriya_shared::{debug_info, log::info, winit::window::WindowId};
+use jeriya_shared::{
+    debug_info,
+    log::{info, warn},
+    nalgebra::Vector4,
+    parking_lot::Mutex,
+    winit::window::WindowId,
+    DebugInfo,
+};
+
+/// Maximum number of passes per frame for which the GPU time is measured. The passes after it are not timed.
+pub const MAX_TIMED_PASSES: u32 = 24;
 
 pub struct PersistentFrameState {
     pub presenter_index: usize,
@@ -29,11 +58,20 @@ pub struct PersistentFrameState {
     pub image_available_semaphore: Semaphore,
     pub rendering_complete_semaphore: Semaphore,
     pub rendering_complete_fence: Fence,
+    /// Index of the frame that has been submitted with the `rendering_complete_fence`
+    pub submitted_frame_index: Option<u64>,
 
     pub command_pool: Arc<CommandPool>,
 
     pub per_frame_data_buffer: HostVisibleBuffer<shader_interface::PerFrameData>,
-    pub frame_telemetry_buffer: HostVisibleBuffer<shader_interface::FrameTelemetry>,
+    /// [`PerFrameData`](shader_interface::PerFrameData) with the camera and the extent of the render targets
+    pub render_target_per_frame_data_buffers: BTreeMap<RenderTargetId, HostVisibleBuffer<shader_interface::PerFrameData>>,
+    /// [`PerFrameData`](shader_interface::PerFrameData) of the viewports of the window in the order in which they are rendered
+    pub viewport_per_frame_data_buffers: Vec<HostVisibleBuffer<shader_interface::PerFrameData>>,
+    pub frame_telemetry_buffer: Arc<HostVisibleBuffer<shader_interface::FrameTelemetry>>,
+    /// Contains the [`PointCloudPageRequest`](shader_interface::PointCloudPageRequest)s that the point cloud cluster culling
+    /// has written in this frame. At the front of the buffer is a counter that contains the number of requests.
+    pub point_cloud_page_requests_buffer: Arc<HostVisibleBuffer<u32>>,
 
     pub mesh_attributes_active_buffer: FrameLocalBuffer<u32>, // every u32 represents a bool
     pub point_cloud_attributes_active_buffer: FrameLocalBuffer<u32>, // every u32 represents a bool
@@ -43,6 +81,12 @@ pub struct PersistentFrameState {
     pub camera_instance_buffer: FrameLocalBuffer<shader_interface::CameraInstance>,
     pub rigid_mesh_buffer: FrameLocalBuffer<shader_interface::RigidMesh>,
     pub rigid_mesh_instance_buffer: FrameLocalBuffer<shader_interface::RigidMeshInstance>,
+    /// Contains the [`Motion`](rigid_mesh_instance::Motion)s of the rigid mesh instances that are advanced on the GPU
+    pub rigid_mesh_instance_motion_buffer: FrameLocalBuffer<shader_interface::RigidMeshInstanceMotion>,
+    /// Number of rigid mesh instances that have a motion. The animation pass is skipped when there are none.
+    pub rigid_mesh_instance_motion_count: usize,
+    /// GPU indices of the selected rigid mesh instances. The selection outline is skipped when there are none.
+    pub selected_rigid_mesh_instances: BTreeSet<usize>,
     pub point_cloud_buffer: FrameLocalBuffer<shader_interface::PointCloud>,
     pub point_cloud_instance_buffer: FrameLocalBuffer<shader_interface::PointCloudInstance>,
 
@@ -70,7 +114,35 @@ pub struct PersistentFrameState {
     /// Layout: [line1_start, line1_end, line1_color, line2_start, ...]
     pub device_local_debug_lines_buffer: Arc<DeviceVisibleBuffer<f32>>,
 
-    pub transactions: VecDeque<Transaction>,
+    /// Descriptor set with the buffers that stay the same for the whole frame. Allocated when the first frame is recorded.
+    pub cached_descriptor_set: Option<CachedDescriptorSet>,
+
+    /// Readbacks that have been recorded into the command buffer of this frame and
+    /// can be finished when the frame has finished rendering.
+    pub pending_readbacks: Vec<PendingReadback>,
+    /// Copies of the depth buffer that have been recorded into the command buffer of this frame
+    pub pending_depth_readbacks: Vec<PendingDepthReadback>,
+    /// Buffers of the finished depth readbacks that are reused by the next ones
+    pub depth_readback_buffers: Vec<Arc<Mutex<HostVisibleBuffer<u32>>>>,
+
+    /// Copy of the swapchain image that has been recorded into the command buffer of this frame when frames are captured
+    pub pending_frame_capture: Option<PendingFrameCapture>,
+    /// Copies of the render targets that have been recorded into the command buffer of this frame
+    pub pending_render_target_readbacks: Vec<PendingFrameCapture>,
+    /// Buffers of the finished frame captures and render target readbacks that are reused by the next ones
+    pub frame_capture_buffers: Vec<Arc<Mutex<HostVisibleBuffer<u8>>>>,
+
+    /// Timestamps that are written at the beginning and the end of the passes of the frame
+    pub timestamp_query_pool: Arc<QueryPool>,
+    /// Names of the passes that have been timed in the command buffer of this frame
+    pub timed_passes: Vec<&'static str>,
+    /// Pipeline statistics of the frame. Only available when they are enabled in the `RendererConfig` and supported by the device.
+    pub pipeline_statistics_query_pool: Option<Arc<QueryPool>>,
+    /// Whether the frame telemetry has been written by the command buffer of this frame
+    pub frame_telemetry_recorded: bool,
+
+    /// [`Transaction`]s together with the [`BackendShared::time`] at which the presenter received them
+    pub transactions: VecDeque<(Transaction, f32)>,
 }
 
 #[profile]
@@ -83,12 +155,23 @@ impl PersistentFrameState {
             debug_info!(format!("PerFrameDataBuffer-for-Window{:?}", window_id)),
         )?;
 
-        let frame_telemetry_buffer = HostVisibleBuffer::new(
+        let frame_telemetry_buffer = Arc::new(HostVisibleBuffer::new(
             &backend_shared.device,
             &[shader_interface::FrameTelemetry::default(); 1],
-            BufferUsageFlags::STORAGE_BUFFER,
+            BufferUsageFlags::STORAGE_BUFFER | BufferUsageFlags::TRANSFER_DST_BIT,
             debug_info!(format!("FrameTelemetryBuffer-for-Window{:?}", window_id)),
-        )?;
+        )?);
+
+        info!("Create point cloud page requests buffer");
+        let len = shader_interface::PointCloudPageRequest::HEADER_LEN
+            + backend_shared.renderer_config.maximum_number_of_point_cloud_page_requests
+                * (mem::size_of::<shader_interface::PointCloudPageRequest>() / mem::size_of::<u32>());
+        let point_cloud_page_requests_buffer = Arc::new(HostVisibleBuffer::new(
+            &backend_shared.device,
+            &vec![0; len],
+            BufferUsageFlags::STORAGE_BUFFER | BufferUsageFlags::TRANSFER_DST_BIT,
+            debug_info!(format!("PointCloudPageRequestsBuffer-for-Window{:?}", window_id)),
+        )?);
 
         // Create camera buffer
         let len = backend_shared.renderer_config.maximum_number_of_cameras;
@@ -147,6 +230,14 @@ impl PersistentFrameState {
             debug_info!(format!("RigidMeshInstanceBuffer-for-Window{:?}", window_id)),
         )?;
 
+        let len = backend_shared.renderer_config.maximum_number_of_rigid_mesh_instances;
+        info!("Create rigid mesh instance motion buffer with length: {len}");
+        let rigid_mesh_instance_motion_buffer = FrameLocalBuffer::new(
+            &backend_shared.device,
+            len,
+            debug_info!(format!("RigidMeshInstanceMotionBuffer-for-Window{:?}", window_id)),
+        )?;
+
         let len = backend_shared.renderer_config.maximum_number_of_point_clouds;
         info!("Create point cloud buffer with length: {len}");
         let point_cloud_buffer = FrameLocalBuffer::new(
@@ -172,7 +263,7 @@ impl PersistentFrameState {
         let visible_rigid_mesh_instances_simple_buffer = DeviceVisibleBuffer::new(
             &backend_shared.device,
             byte_size_count + byte_size_draw_indirect_commands + byte_size_rigid_mesh_instance_indices,
-            // BufferUsageFlags::TRANSFER_SRC_BIT is only needed for debugging
+            // BufferUsageFlags::TRANSFER_SRC_BIT is needed for reading the buffer back to the host
             BufferUsageFlags::INDIRECT_BUFFER
                 | BufferUsageFlags::STORAGE_BUFFER
                 | BufferUsageFlags::TRANSFER_DST_BIT
@@ -187,7 +278,7 @@ impl PersistentFrameState {
         let visible_rigid_mesh_instances = DeviceVisibleBuffer::new(
             &backend_shared.device,
             byte_size_dispatch_indirect_command + byte_size_count + byte_size_indices,
-            // BufferUsageFlags::TRANSFER_SRC_BIT is only needed for debugging
+            // BufferUsageFlags::TRANSFER_SRC_BIT is needed for reading the buffer back to the host
             BufferUsageFlags::STORAGE_BUFFER
                 | BufferUsageFlags::INDIRECT_BUFFER
                 | BufferUsageFlags::TRANSFER_DST_BIT
@@ -205,7 +296,7 @@ impl PersistentFrameState {
         let visible_rigid_mesh_meshlets = DeviceVisibleBuffer::new(
             &backend_shared.device,
             byte_size_count + byte_size_meshlet_indices + byte_size_draw_indirect_commands + byte_size_rigid_mesh_instance_indices,
-            // BufferUsageFlags::TRANSFER_SRC_BIT is only needed for debugging
+            // BufferUsageFlags::TRANSFER_SRC_BIT is needed for reading the buffer back to the host
             BufferUsageFlags::STORAGE_BUFFER
                 | BufferUsageFlags::INDIRECT_BUFFER
                 | BufferUsageFlags::TRANSFER_DST_BIT
@@ -222,7 +313,7 @@ impl PersistentFrameState {
         let visible_point_cloud_instances_simple = DeviceVisibleBuffer::new(
             &backend_shared.device,
             byte_size_count + byte_size_draw_indirect_commands + byte_size_point_cloud_instance_indices,
-            // BufferUsageFlags::TRANSFER_SRC_BIT is only needed for debugging
+            // BufferUsageFlags::TRANSFER_SRC_BIT is needed for reading the buffer back to the host
             BufferUsageFlags::STORAGE_BUFFER
                 | BufferUsageFlags::INDIRECT_BUFFER
                 | BufferUsageFlags::TRANSFER_DST_BIT
@@ -238,7 +329,7 @@ impl PersistentFrameState {
         let visible_point_cloud_instances = DeviceVisibleBuffer::new(
             &backend_shared.device,
             byte_size_dispatch_indirect_command + byte_size_count + byte_size_point_cloud_instance_indices,
-            // BufferUsageFlags::TRANSFER_SRC_BIT is only needed for debugging
+            // BufferUsageFlags::TRANSFER_SRC_BIT is needed for reading the buffer back to the host
             BufferUsageFlags::STORAGE_BUFFER
                 | BufferUsageFlags::INDIRECT_BUFFER
                 | BufferUsageFlags::TRANSFER_DST_BIT
@@ -255,7 +346,7 @@ impl PersistentFrameState {
         let visible_point_cloud_clusters = DeviceVisibleBuffer::new(
             &backend_shared.device,
             byte_size_dispatch_indirect_command + byte_size_count + byte_size_point_cloud_cluster_indices,
-            // BufferUsageFlags::TRANSFER_SRC_BIT is only needed for debugging
+            // BufferUsageFlags::TRANSFER_SRC_BIT is needed for reading the buffer back to the host
             BufferUsageFlags::STORAGE_BUFFER
                 | BufferUsageFlags::INDIRECT_BUFFER
                 | BufferUsageFlags::TRANSFER_DST_BIT
@@ -277,6 +368,29 @@ impl PersistentFrameState {
             debug_info!(format!("DeviceLocalDebugLinesBuffer-for-Window{:?}", window_id)),
         )?;
 
+        let timestamp_query_pool = Arc::new(QueryPool::new(
+            &backend_shared.device,
+            QueryType::Timestamp,
+            2 * MAX_TIMED_PASSES,
+            debug_info!(format!("TimestampQueryPool-for-Window{:?}", window_id)),
+        )?);
+
+        let pipeline_statistics_query_pool = if backend_shared.renderer_config.collect_pipeline_statistics {
+            if backend_shared.device.optional_features.pipeline_statistics_query {
+                Some(Arc::new(QueryPool::new(
+                    &backend_shared.device,
+                    QueryType::PipelineStatistics,
+                    1,
+                    debug_info!(format!("PipelineStatisticsQueryPool-for-Window{:?}", window_id)),
+                )?))
+            } else {
+                warn!("Pipeline statistics are requested but not supported by the device");
+                None
+            }
+        } else {
+            None
+        };
+
         // The `Fence` is created in the signalled state so that the first frame can call `wait` on it and not block.
         let rendering_complete_fence = Fence::with_state(&backend_shared.device, true, debug_info!("rendering-complete-Fence"))?;
         let rendering_complete_semaphore = Semaphore::new(&backend_shared.device, debug_info!("rendering-complete-Semaphore"))?;
@@ -297,9 +411,13 @@ impl PersistentFrameState {
             image_available_semaphore,
             rendering_complete_semaphore,
             rendering_complete_fence,
+            submitted_frame_index: None,
             command_pool,
             per_frame_data_buffer,
+            render_target_per_frame_data_buffers: BTreeMap::new(),
+            viewport_per_frame_data_buffers: Vec::new(),
             frame_telemetry_buffer,
+            point_cloud_page_requests_buffer,
             mesh_attributes_active_buffer,
             point_cloud_attributes_active_buffer,
             point_cloud_pages_active_buffer,
@@ -307,6 +425,9 @@ impl PersistentFrameState {
             camera_instance_buffer,
             rigid_mesh_buffer,
             rigid_mesh_instance_buffer,
+            rigid_mesh_instance_motion_buffer,
+            rigid_mesh_instance_motion_count: 0,
+            selected_rigid_mesh_instances: BTreeSet::new(),
             point_cloud_buffer,
             point_cloud_instance_buffer,
             visible_rigid_mesh_instances_simple_buffer,
@@ -316,25 +437,203 @@ impl PersistentFrameState {
             visible_point_cloud_instances,
             visible_point_cloud_clusters,
             device_local_debug_lines_buffer,
+            cached_descriptor_set: None,
+            pending_readbacks: Vec::new(),
+            pending_depth_readbacks: Vec::new(),
+            depth_readback_buffers: Vec::new(),
+            pending_frame_capture: None,
+            frame_capture_buffers: Vec::new(),
+            pending_render_target_readbacks: Vec::new(),
+            timestamp_query_pool,
+            timed_passes: Vec::new(),
+            pipeline_statistics_query_pool,
+            frame_telemetry_recorded: false,
             transactions: VecDeque::new(),
         })
     }
 
+    /// Returns the buffer that corresponds to the given [`ReadbackBuffer`].
+    pub fn readback_buffer(&self, readback_buffer: ReadbackBuffer) -> &Arc<DeviceVisibleBuffer<u32>> {
+        match readback_buffer {
+            ReadbackBuffer::VisibleRigidMeshInstancesSimple => &self.visible_rigid_mesh_instances_simple_buffer,
+            ReadbackBuffer::VisibleRigidMeshInstances => &self.visible_rigid_mesh_instances,
+            ReadbackBuffer::VisibleRigidMeshMeshlets => &self.visible_rigid_mesh_meshlets,
+            ReadbackBuffer::VisiblePointCloudInstancesSimple => &self.visible_point_cloud_instances_simple,
+            ReadbackBuffer::VisiblePointCloudInstances => &self.visible_point_cloud_instances,
+            ReadbackBuffer::VisiblePointCloudClusters => &self.visible_point_cloud_clusters,
+        }
+    }
+
+    /// Sends the data of the pending readbacks and the captured frame to the receivers.
+    ///
+    /// Must only be called after the `rendering_complete_fence` of the frame has been waited on.
+    pub fn finish_pending_readbacks(&mut self) -> crate::Result<()> {
+        for pending_readback in self.pending_readbacks.drain(..) {
+            pending_readback.finish()?;
+        }
+        for pending_depth_readback in self.pending_depth_readbacks.drain(..) {
+            let host_visible_buffer = pending_depth_readback.finish()?;
+            self.depth_readback_buffers.push(host_visible_buffer);
+        }
+        if let Some(pending_frame_capture) = self.pending_frame_capture.take() {
+            let host_visible_buffer = pending_frame_capture.finish()?;
+            self.frame_capture_buffers.push(host_visible_buffer);
+        }
+        for pending_render_target_readback in self.pending_render_target_readbacks.drain(..) {
+            let host_visible_buffer = pending_render_target_readback.finish()?;
+            self.frame_capture_buffers.push(host_visible_buffer);
+        }
+        Ok(())
+    }
+
+    /// Returns a buffer for a frame capture or render target readback with at least `len` bytes
+    ///
+    /// The buffers of the previous captures are reused when they are large enough.
+    pub fn take_frame_capture_buffer(
+        &mut self,
+        len: usize,
+        backend_shared: &BackendShared,
+        debug_info: DebugInfo,
+    ) -> crate::Result<Arc<Mutex<HostVisibleBuffer<u8>>>> {
+        let reusable_buffer_index = self
+            .frame_capture_buffers
+            .iter()
+            .position(|host_visible_buffer| host_visible_buffer.lock().len() >= len);
+        match reusable_buffer_index {
+            Some(index) => Ok(self.frame_capture_buffers.swap_remove(index)),
+            None => Ok(Arc::new(Mutex::new(HostVisibleBuffer::new(
+                &backend_shared.device,
+                &vec![0; len],
+                BufferUsageFlags::TRANSFER_DST_BIT,
+                debug_info,
+            )?))),
+        }
+    }
+
+    /// Writes the `per_frame_data` into the buffer of the render target with the given `id`
+    ///
+    /// The buffer is created when the render target is rendered for the first time.
+    pub fn update_render_target_per_frame_data(
+        &mut self,
+        id: RenderTargetId,
+        per_frame_data: shader_interface::PerFrameData,
+        backend_shared: &BackendShared,
+    ) -> crate::Result<()> {
+        let per_frame_data_buffer = match self.render_target_per_frame_data_buffers.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(HostVisibleBuffer::new(
+                &backend_shared.device,
+                &[shader_interface::PerFrameData::default(); 1],
+                BufferUsageFlags::UNIFORM_BUFFER,
+                debug_info!(format!("PerFrameDataBuffer-for-RenderTarget{}", id.0)),
+            )?),
+        };
+        per_frame_data_buffer.set_memory_unaligned(&[per_frame_data])
+    }
+
+    /// Writes the `per_frame_data` into the buffer of the viewport with the given `index`
+    ///
+    /// The buffers are created when a viewport with the `index` is rendered for the first time.
+    pub fn update_viewport_per_frame_data(
+        &mut self,
+        index: usize,
+        per_frame_data: shader_interface::PerFrameData,
+        backend_shared: &BackendShared,
+    ) -> crate::Result<()> {
+        while self.viewport_per_frame_data_buffers.len() <= index {
+            let per_frame_data_buffer = HostVisibleBuffer::new(
+                &backend_shared.device,
+                &[shader_interface::PerFrameData::default(); 1],
+                BufferUsageFlags::UNIFORM_BUFFER,
+                debug_info!(format!(
+                    "PerFrameDataBuffer-for-Viewport{}",
+                    self.viewport_per_frame_data_buffers.len()
+                )),
+            )?;
+            self.viewport_per_frame_data_buffers.push(per_frame_data_buffer);
+        }
+        self.viewport_per_frame_data_buffers[index].set_memory_unaligned(&[per_frame_data])
+    }
+
+    /// Returns the GPU times of the passes that have been timed in this frame.
+    ///
+    /// Must only be called after the `rendering_complete_fence` of the frame has been waited on.
+    /// Returns `None` when no passes have been timed since the last call.
+    pub fn collect_frame_timings(&mut self, timestamp_period: f32) -> crate::Result<Option<FrameTimings>> {
+        if self.timed_passes.is_empty() {
+            return Ok(None);
+        }
+        let timed_passes = mem::take(&mut self.timed_passes);
+        let timestamps = self.timestamp_query_pool.get_results(0, 2 * timed_passes.len() as u32)?;
+        Ok(timestamps.map(|timestamps| FrameTimings::from_timestamps(&timed_passes, &timestamps, timestamp_period)))
+    }
+
+    /// Returns the [`PipelineStatistics`](shader_interface::PipelineStatistics) that have been written to the frame telemetry buffer.
+    ///
+    /// Must only be called after the `rendering_complete_fence` of the frame has been waited on.
+    /// Returns `None` when the pipeline statistics are not collected.
+    pub fn pipeline_statistics(&self) -> crate::Result<Option<shader_interface::PipelineStatistics>> {
+        if self.pipeline_statistics_query_pool.is_none() {
+            return Ok(None);
+        }
+        let frame_telemetry = self.frame_telemetry_buffer.get_memory_unaligned_index(0)?;
+        Ok(Some(frame_telemetry.pipeline_statistics))
+    }
+
+    /// Returns the [`FrameTelemetry`] that has been written by the frame telemetry compute shader.
+    ///
+    /// Must only be called after the `rendering_complete_fence` of the frame has been waited on.
+    /// Returns `None` when no frame has been rendered in this position yet.
+    pub fn frame_telemetry(&self) -> crate::Result<Option<FrameTelemetry>> {
+        if !self.frame_telemetry_recorded {
+            return Ok(None);
+        }
+        let frame_telemetry = self.frame_telemetry_buffer.get_memory_unaligned_index(0)?;
+        let per_frame_data = self.per_frame_data_buffer.get_memory_unaligned_index(0)?;
+        Ok(Some(FrameTelemetry::new(&frame_telemetry, &per_frame_data)))
+    }
+
+    /// Returns the [`PointCloudPageRequest`](shader_interface::PointCloudPageRequest)s that the point cloud cluster culling has written in this frame.
+    ///
+    /// Must only be called after the `rendering_complete_fence` of the frame has been waited on.
+    pub fn point_cloud_page_requests(&self) -> crate::Result<Vec<shader_interface::PointCloudPageRequest>> {
+        let mut data = vec![0; self.point_cloud_page_requests_buffer.len()];
+        self.point_cloud_page_requests_buffer.get_memory_unaligned(&mut data)?;
+        let (header, requests) = data.split_at(shader_interface::PointCloudPageRequest::HEADER_LEN);
+
+        // The counter is incremented for requests that don't fit into the buffer
+        const REQUEST_LEN: usize = mem::size_of::<shader_interface::PointCloudPageRequest>() / mem::size_of::<u32>();
+        let count = (header[0] as usize).min(requests.len() / REQUEST_LEN);
+        Ok(requests
+            .chunks_exact(REQUEST_LEN)
+            .take(count)
+            .map(|request| shader_interface::PointCloudPageRequest {
+                point_cloud_attributes_index: request[0],
+                page_index: request[1],
+                density: f32::from_bits(request[2]),
+                padding: 0,
+            })
+            .collect())
+    }
+
     /// Pushes a [`Transaction`] to the frame to be processed when the frame is rendered.
-    pub fn push_transaction(&mut self, transaction: Transaction) {
-        self.transactions.push_back(transaction);
+    ///
+    /// The `time` is the [`BackendShared::time`] at which the [`Transaction`] has been received. It has to be
+    /// the same for all frames so that the motions that are started by the [`Transaction`] are in sync.
+    pub fn push_transaction(&mut self, transaction: Transaction, time: f32) {
+        self.transactions.push_back((transaction, time));
     }
 
     /// Processes the [`Transaction`]s pushed to the frame.
     pub fn process_transactions(&mut self) -> crate::Result<()> {
         use transactions::Event;
         let drain = self.transactions.drain(..).collect::<Vec<_>>();
-        for transaction in drain {
+        for (transaction, time) in drain {
             for event in transaction.process() {
                 match event {
                     Event::RigidMesh(rigid_mesh) => self.process_rigid_mesh_event(rigid_mesh)?,
                     Event::RigidMeshInstance(rigid_mesh_instance_event) => {
-                        self.process_rigid_mesh_instance_event(rigid_mesh_instance_event)?
+                        self.process_rigid_mesh_instance_event(rigid_mesh_instance_event, time)?
                     }
                     Event::PointCloud(point_cloud) => self.process_point_cloud_event(point_cloud)?,
                     Event::PointCloudInstance(point_cloud_instance_event) => {
@@ -365,7 +664,7 @@ impl PersistentFrameState {
     fn process_rigid_mesh_event(&mut self, event: rigid_mesh::Event) -> crate::Result<()> {
         use rigid_mesh::Event;
         match event {
-            Event::Insert(rigid_mesh) => {
+            Event::Insert(rigid_mesh) | Event::Update(rigid_mesh) => {
                 self.rigid_mesh_buffer.set(
                     rigid_mesh.gpu_index_allocation(),
                     &shader_interface::RigidMesh {
@@ -396,18 +695,64 @@ impl PersistentFrameState {
         Ok(())
     }
 
-    /// Processes a [`rigid_mesh_instance::Event`].
-    fn process_rigid_mesh_instance_event(&mut self, event: rigid_mesh_instance::Event) -> crate::Result<()> {
+    /// Processes a [`rigid_mesh_instance::Event`] of a [`Transaction`] that has been received at `time`.
+    fn process_rigid_mesh_instance_event(&mut self, event: rigid_mesh_instance::Event, time: f32) -> crate::Result<()> {
         use rigid_mesh_instance::Event;
         match event {
             Event::Noop => {}
-            Event::Insert(rigid_mesh_instance) => {
+            Event::Insert(rigid_mesh_instance) => self.insert_rigid_mesh_instance(&rigid_mesh_instance, time)?,
+            Event::InsertMany(rigid_mesh_instances) => {
+                for rigid_mesh_instance in &rigid_mesh_instances {
+                    self.insert_rigid_mesh_instance(rigid_mesh_instance, time)?;
+                }
+            }
+            Event::UpdateTransform(gpu_index_allocation, transform) => {
+                let mut rigid_mesh_instance = self.rigid_mesh_instance_buffer.get(&gpu_index_allocation)?;
+                rigid_mesh_instance.transform = transform;
+                self.rigid_mesh_instance_buffer.set(&gpu_index_allocation, &rigid_mesh_instance)?;
+
+                // The motion continues from the new transform. It's restarted because the GPU applies the motion
+                // that elapsed since its start time on top of the base transform.
+                let mut motion = self.rigid_mesh_instance_motion_buffer.get(&gpu_index_allocation)?;
+                if motion.kind != shader_interface::MotionKind::None {
+                    motion.base_transform = transform;
+                    motion.start_time = time;
+                    self.rigid_mesh_instance_motion_buffer.set(&gpu_index_allocation, &motion)?;
+                }
+            }
+            Event::UpdateColor(gpu_index_allocation, color) => {
+                let mut rigid_mesh_instance = self.rigid_mesh_instance_buffer.get(&gpu_index_allocation)?;
+                rigid_mesh_instance.color = color;
+                self.rigid_mesh_instance_buffer.set(&gpu_index_allocation, &rigid_mesh_instance)?;
+            }
+            Event::UpdateSelected(gpu_index_allocation, selected) => {
+                if selected {
+                    self.selected_rigid_mesh_instances.insert(gpu_index_allocation.index());
+                } else {
+                    self.selected_rigid_mesh_instances.remove(&gpu_index_allocation.index());
+                }
+            }
+            Event::UpdateMotion(gpu_index_allocation, motion) => {
+                let previous_motion = self.rigid_mesh_instance_motion_buffer.get(&gpu_index_allocation)?;
+                let mut rigid_mesh_instance = self.rigid_mesh_instance_buffer.get(&gpu_index_allocation)?;
+                if previous_motion.kind != shader_interface::MotionKind::None {
+                    // The transform in the buffer has been overwritten by the GPU
+                    rigid_mesh_instance.transform = previous_motion.base_transform;
+                    self.rigid_mesh_instance_buffer.set(&gpu_index_allocation, &rigid_mesh_instance)?;
+                }
+                self.set_rigid_mesh_instance_motion(
+                    &gpu_index_allocation,
+                    &shader_interface::RigidMeshInstanceMotion::new(&motion, rigid_mesh_instance.transform, time),
+                )?;
+            }
+            Event::Remove(gpu_index_allocation) => {
+                self.set_rigid_mesh_instance_motion(&gpu_index_allocation, &shader_interface::RigidMeshInstanceMotion::default())?;
+                self.selected_rigid_mesh_instances.remove(&gpu_index_allocation.index());
                 self.rigid_mesh_instance_buffer.set(
-                    rigid_mesh_instance.gpu_index_allocation(),
+                    &gpu_index_allocation,
                     &shader_interface::RigidMeshInstance {
-                        rigid_mesh_index: rigid_mesh_instance.rigid_mesh_gpu_index_allocation().index() as u64,
-                        _padding: 0,
-                        transform: *rigid_mesh_instance.transform(),
+                        rigid_mesh_index: shader_interface::RigidMeshInstance::REMOVED_RIGID_MESH_INDEX,
+                        ..Default::default()
                     },
                 )?;
             }
@@ -415,20 +760,80 @@ impl PersistentFrameState {
         Ok(())
     }
 
+    /// Writes a newly inserted [`RigidMeshInstance`] into the buffer.
+    fn insert_rigid_mesh_instance(&mut self, rigid_mesh_instance: &RigidMeshInstance, time: f32) -> crate::Result<()> {
+        self.rigid_mesh_instance_buffer.set(
+            rigid_mesh_instance.gpu_index_allocation(),
+            &shader_interface::RigidMeshInstance {
+                rigid_mesh_index: rigid_mesh_instance.rigid_mesh_gpu_index_allocation().index() as u64,
+                transform: *rigid_mesh_instance.transform(),
+                color: *rigid_mesh_instance.color(),
+                ..Default::default()
+            },
+        )?;
+        if rigid_mesh_instance.is_selected() {
+            self.selected_rigid_mesh_instances
+                .insert(rigid_mesh_instance.gpu_index_allocation().index());
+        }
+        self.set_rigid_mesh_instance_motion(
+            rigid_mesh_instance.gpu_index_allocation(),
+            &shader_interface::RigidMeshInstanceMotion::new(rigid_mesh_instance.motion(), *rigid_mesh_instance.transform(), time),
+        )
+    }
+
+    /// Writes the motion of a [`RigidMeshInstance`] into the buffer and keeps track of the number of motions.
+    fn set_rigid_mesh_instance_motion(
+        &mut self,
+        gpu_index_allocation: &GpuIndexAllocation<RigidMeshInstance>,
+        motion: &shader_interface::RigidMeshInstanceMotion,
+    ) -> crate::Result<()> {
+        let previous_motion = self.rigid_mesh_instance_motion_buffer.get(gpu_index_allocation)?;
+        let was_moving = previous_motion.kind != shader_interface::MotionKind::None;
+        let is_moving = motion.kind != shader_interface::MotionKind::None;
+        if !was_moving && !is_moving {
+            // Don't raise the high water mark of the buffer for instances without motion
+            return Ok(());
+        }
+        match (was_moving, is_moving) {
+            (false, true) => self.rigid_mesh_instance_motion_count += 1,
+            (true, false) => self.rigid_mesh_instance_motion_count -= 1,
+            _ => {}
+        }
+        self.rigid_mesh_instance_motion_buffer.set(gpu_index_allocation, motion)
+    }
+
+    /// Writes a newly inserted [`PointCloudInstance`] into the buffer.
+    fn insert_point_cloud_instance(&mut self, point_cloud_instance: &PointCloudInstance) -> crate::Result<()> {
+        let mut point_cloud_instance_gpu = shader_interface::PointCloudInstance {
+            point_cloud_index: point_cloud_instance.point_cloud_gpu_index_allocation().index() as u64,
+            transform: *point_cloud_instance.transform(),
+            ..Default::default()
+        };
+        point_cloud_instance_gpu.set_point_render_mode(point_cloud_instance.point_render_mode());
+        self.point_cloud_instance_buffer
+            .set(point_cloud_instance.gpu_index_allocation(), &point_cloud_instance_gpu)
+    }
+
     /// Processes a [`point_cloud_instance::Event`].
     fn process_point_cloud_instance_event(&mut self, event: point_cloud_instance::Event) -> crate::Result<()> {
         use point_cloud_instance::Event;
         match event {
             Event::Noop => {}
-            Event::Insert(point_cloud_instance) => {
-                self.point_cloud_instance_buffer.set(
-                    point_cloud_instance.gpu_index_allocation(),
-                    &shader_interface::PointCloudInstance {
-                        point_cloud_index: point_cloud_instance.point_cloud_gpu_index_allocation().index() as u64,
-                        _padding: 0,
-                        transform: *point_cloud_instance.transform(),
-                    },
-                )?;
+            Event::Insert(point_cloud_instance) => self.insert_point_cloud_instance(&point_cloud_instance)?,
+            Event::InsertMany(point_cloud_instances) => {
+                for point_cloud_instance in &point_cloud_instances {
+                    self.insert_point_cloud_instance(point_cloud_instance)?;
+                }
+            }
+            Event::UpdateTransform(gpu_index_allocation, transform) => {
+                let mut point_cloud_instance = self.point_cloud_instance_buffer.get(&gpu_index_allocation)?;
+                point_cloud_instance.transform = transform;
+                self.point_cloud_instance_buffer.set(&gpu_index_allocation, &point_cloud_instance)?;
+            }
+            Event::UpdatePointRenderMode(gpu_index_allocation, point_render_mode) => {
+                let mut point_cloud_instance = self.point_cloud_instance_buffer.get(&gpu_index_allocation)?;
+                point_cloud_instance.set_point_render_mode(point_render_mode);
+                self.point_cloud_instance_buffer.set(&gpu_index_allocation, &point_cloud_instance)?;
             }
         }
         Ok(())
@@ -462,6 +867,11 @@ impl PersistentFrameState {
                     },
                 )?;
             }
+            Event::Remove(gpu_index_allocation) => {
+                info!("Remove Camera at {:?}", gpu_index_allocation.index());
+                self.camera_buffer
+                    .set(&gpu_index_allocation, &shader_interface::Camera::default())?;
+            }
         }
         Ok(())
     }
@@ -492,49 +902,181 @@ impl PersistentFrameState {
                     },
                 )?;
             }
+            Event::Remove(gpu_index_allocation) => {
+                info!("Remove CameraInstance at {:?}", gpu_index_allocation.index());
+                self.camera_instance_buffer
+                    .set(&gpu_index_allocation, &shader_interface::CameraInstance::default())?;
+            }
         }
         Ok(())
     }
 
-    /// Pushes the required descriptors to the [`CommandBufferBuilder`].
+    /// Returns the [`Descriptor`]s of all buffers and images that are pushed for the passes of a frame.
+    ///
+    /// The pipelines only include the descriptors in their layouts that are declared in their shaders.
+    pub fn available_descriptors() -> Vec<Descriptor> {
+        DescriptorSetLayout::builder()
+            .push_uniform_buffer::<shader_interface::PerFrameData>(bindings::PER_FRAME_DATA, 1)
+            .push_combined_image_sampler(bindings::DEPTH_BUFFER, 1)
+            .push_combined_image_sampler(bindings::OUTPUT_TRANSFORM_INPUT, 1)
+            .push_combined_image_sampler(bindings::POST_PROCESSING_INPUT, 1)
+            .push_combined_image_sampler(bindings::SELECTION_STENCIL, 1)
+            .descriptors()
+            .to_vec()
+    }
+
+    /// Creates the [`DescriptorSetLayout`] of the [`CachedDescriptorSet`] that contains the buffers which stay the same for the whole frame
+    pub fn cached_descriptor_set_layout(device: &Arc<Device>) -> crate::Result<DescriptorSetLayout> {
+        DescriptorSetLayout::builder()
+            .push_storage_buffer::<shader_interface::Camera>(bindings::CAMERAS, 1)
+            .push_storage_buffer::<shader_interface::CameraInstance>(bindings::CAMERA_INSTANCES, 1)
+            .push_storage_buffer::<u32>(bindings::VISIBLE_RIGID_MESH_INSTANCES_SIMPLE, 1)
+            .push_storage_buffer::<Vector4<f32>>(bindings::STATIC_VERTEX_POSITIONS, 1)
+            .push_storage_buffer::<u32>(bindings::STATIC_INDICES, 1)
+            .push_storage_buffer::<Vector4<f32>>(bindings::STATIC_VERTEX_NORMALS, 1)
+            .push_storage_buffer::<shader_interface::MeshAttributes>(bindings::MESH_ATTRIBUTES, 1)
+            .push_storage_buffer::<shader_interface::RigidMesh>(bindings::RIGID_MESHES, 1)
+            .push_storage_buffer::<u32>(bindings::MESH_ATTRIBUTES_ACTIVE, 1)
+            .push_storage_buffer::<shader_interface::RigidMeshInstance>(bindings::RIGID_MESH_INSTANCES, 1)
+            .push_storage_buffer::<shader_interface::RigidMeshInstanceMotion>(bindings::RIGID_MESH_INSTANCE_MOTIONS, 1)
+            .push_storage_buffer::<shader_interface::Meshlet>(bindings::STATIC_MESHLETS, 1)
+            .push_storage_buffer::<u32>(bindings::VISIBLE_RIGID_MESH_INSTANCES, 1)
+            .push_storage_buffer::<u32>(bindings::VISIBLE_RIGID_MESH_MESHLETS, 1)
+            .push_storage_buffer::<u32>(bindings::POINT_CLOUD_ATTRIBUTES_ACTIVE, 1)
+            .push_storage_buffer::<shader_interface::PointCloud>(bindings::POINT_CLOUDS, 1)
+            .push_storage_buffer::<shader_interface::PointCloudInstance>(bindings::POINT_CLOUD_INSTANCES, 1)
+            .push_storage_buffer::<u32>(bindings::VISIBLE_POINT_CLOUD_INSTANCES_SIMPLE, 1)
+            .push_storage_buffer::<shader_interface::PointCloudAttributes>(bindings::POINT_CLOUD_ATTRIBUTES, 1)
+            .push_storage_buffer::<Vector4<f32>>(bindings::STATIC_POINT_POSITIONS, 1)
+            .push_storage_buffer::<Vector4<f32>>(bindings::STATIC_POINT_COLORS, 1)
+            .push_storage_buffer::<shader_interface::PointCloudPage>(bindings::POINT_CLOUD_PAGES, 1)
+            .push_storage_buffer::<u32>(bindings::POINT_CLOUD_PAGES_ACTIVE, 1)
+            .push_storage_buffer::<shader_interface::PointCloudPage>(bindings::STATIC_POINT_CLOUD_PAGES, 1)
+            .push_storage_buffer::<u32>(bindings::VISIBLE_POINT_CLOUD_INSTANCES, 1)
+            .push_storage_buffer::<u32>(bindings::VISIBLE_POINT_CLOUD_CLUSTERS, 1)
+            .push_storage_buffer::<shader_interface::FrameTelemetry>(bindings::FRAME_TELEMETRY, 1)
+            .push_storage_buffer::<f32>(bindings::DEVICE_LOCAL_DEBUG_LINES, 1)
+            .push_storage_buffer::<u32>(bindings::POINT_CLOUD_PAGE_REQUESTS, 1)
+            .build_for_descriptor_pool(device)
+    }
+
+    /// Writes the buffers into the [`CachedDescriptorSet`] and allocates it with the given layout if necessary.
+    ///
+    /// The set is only rewritten when one of the buffers has been replaced. Must be called before the frame is recorded
+    /// and after the previous frame in this position has finished rendering.
+    pub fn update_cached_descriptor_set(
+        &mut self,
+        backend_shared: &BackendShared,
+        descriptor_set_layout: &Arc<DescriptorSetLayout>,
+    ) -> crate::Result<()> {
+        let is_allocated_with_layout = self
+            .cached_descriptor_set
+            .as_ref()
+            .is_some_and(|cached_descriptor_set| Arc::ptr_eq(cached_descriptor_set.descriptor_set_layout(), descriptor_set_layout));
+        if !is_allocated_with_layout {
+            self.cached_descriptor_set = Some(CachedDescriptorSet::new(
+                &backend_shared.device,
+                descriptor_set_layout,
+                debug_info!(format!("CachedDescriptorSet-for-Presenter{}", self.presenter_index)),
+            )?);
+        }
+        let descriptors = PushDescriptors::builder(descriptor_set_layout)
+            .push_storage_buffer(bindings::CAMERAS, &self.camera_buffer)
+            .push_storage_buffer(bindings::CAMERA_INSTANCES, &self.camera_instance_buffer)
+            .push_storage_buffer(
+                bindings::VISIBLE_RIGID_MESH_INSTANCES_SIMPLE,
+                &self.visible_rigid_mesh_instances_simple_buffer,
+            )
+            .push_storage_buffer(
+                bindings::STATIC_VERTEX_POSITIONS,
+                &*backend_shared.static_vertex_position_buffer.lock(),
+            )
+            .push_storage_buffer(bindings::STATIC_INDICES, &*backend_shared.static_indices_buffer.lock())
+            .push_storage_buffer(
+                bindings::STATIC_VERTEX_NORMALS,
+                &*backend_shared.static_vertex_normals_buffer.lock(),
+            )
+            .push_storage_buffer(bindings::MESH_ATTRIBUTES, &*backend_shared.mesh_attributes_buffer.lock())
+            .push_storage_buffer(bindings::RIGID_MESHES, &self.rigid_mesh_buffer)
+            .push_storage_buffer(bindings::MESH_ATTRIBUTES_ACTIVE, &self.mesh_attributes_active_buffer)
+            .push_storage_buffer(bindings::RIGID_MESH_INSTANCES, &self.rigid_mesh_instance_buffer)
+            .push_storage_buffer(bindings::RIGID_MESH_INSTANCE_MOTIONS, &self.rigid_mesh_instance_motion_buffer)
+            .push_storage_buffer(bindings::STATIC_MESHLETS, &*backend_shared.static_meshlet_buffer.lock())
+            .push_storage_buffer(bindings::VISIBLE_RIGID_MESH_INSTANCES, &self.visible_rigid_mesh_instances)
+            .push_storage_buffer(bindings::VISIBLE_RIGID_MESH_MESHLETS, &self.visible_rigid_mesh_meshlets)
+            .push_storage_buffer(bindings::POINT_CLOUD_ATTRIBUTES_ACTIVE, &self.point_cloud_attributes_active_buffer)
+            .push_storage_buffer(bindings::POINT_CLOUDS, &self.point_cloud_buffer)
+            .push_storage_buffer(bindings::POINT_CLOUD_INSTANCES, &self.point_cloud_instance_buffer)
+            .push_storage_buffer(
+                bindings::VISIBLE_POINT_CLOUD_INSTANCES_SIMPLE,
+                &self.visible_point_cloud_instances_simple,
+            )
+            .push_storage_buffer(
+                bindings::POINT_CLOUD_ATTRIBUTES,
+                &*backend_shared.point_cloud_attributes_buffer.lock(),
+            )
+            .push_storage_buffer(
+                bindings::STATIC_POINT_POSITIONS,
+                &*backend_shared.static_point_positions_buffer.lock(),
+            )
+            .push_storage_buffer(bindings::STATIC_POINT_COLORS, &*backend_shared.static_point_colors_buffer.lock())
+            .push_storage_buffer(bindings::POINT_CLOUD_PAGES, &*backend_shared.point_cloud_page_buffer.lock())
+            .push_storage_buffer(bindings::POINT_CLOUD_PAGES_ACTIVE, &self.point_cloud_pages_active_buffer)
+            .push_storage_buffer(
+                bindings::STATIC_POINT_CLOUD_PAGES,
+                &*backend_shared.static_point_cloud_pages_buffer.lock(),
+            )
+            .push_storage_buffer(bindings::VISIBLE_POINT_CLOUD_INSTANCES, &self.visible_point_cloud_instances)
+            .push_storage_buffer(bindings::VISIBLE_POINT_CLOUD_CLUSTERS, &self.visible_point_cloud_clusters)
+            .push_storage_buffer(bindings::FRAME_TELEMETRY, &*self.frame_telemetry_buffer)
+            .push_storage_buffer(bindings::DEVICE_LOCAL_DEBUG_LINES, &self.device_local_debug_lines_buffer)
+            .push_storage_buffer(bindings::POINT_CLOUD_PAGE_REQUESTS, &*self.point_cloud_page_requests_buffer)
+            .build();
+        self.cached_descriptor_set
+            .as_mut()
+            .expect("CachedDescriptorSet has been allocated above")
+            .update(&descriptors);
+        Ok(())
+    }
+
+    /// Pushes the `PerFrameData` to the [`CommandBufferBuilder`] and binds the [`CachedDescriptorSet`].
+    ///
+    /// Buffers that are not contained in the `descriptor_set_layout` are skipped because the shaders of the pipeline don't use them.
     pub fn push_descriptors(
         &self,
         pipeline_bind_point: PipelineBindPoint,
         descriptor_set_layout: &DescriptorSetLayout,
-        backend_shared: &BackendShared,
         command_buffer_builder: &mut CommandBufferBuilder,
     ) -> crate::Result<()> {
+        self.push_descriptors_with_per_frame_data(
+            &self.per_frame_data_buffer,
+            pipeline_bind_point,
+            descriptor_set_layout,
+            command_buffer_builder,
+        )
+    }
+
+    /// Pushes the same descriptors as [`PersistentFrameState::push_descriptors`] but with the given `PerFrameData` buffer
+    ///
+    /// This is used to render the scene from the camera of a render target.
+    pub fn push_descriptors_with_per_frame_data(
+        &self,
+        per_frame_data_buffer: &HostVisibleBuffer<shader_interface::PerFrameData>,
+        pipeline_bind_point: PipelineBindPoint,
+        descriptor_set_layout: &DescriptorSetLayout,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<()> {
+        let cached_descriptor_set = self
+            .cached_descriptor_set
+            .as_ref()
+            .expect("PersistentFrameState::update_cached_descriptor_set must be called before recording the frame");
+        // Shaders that declare the buffers of the cached descriptor set in the push descriptor set get them pushed
         let push_descriptors = &PushDescriptors::builder(descriptor_set_layout)
-            .push_uniform_buffer(0, &self.per_frame_data_buffer)
-            .push_storage_buffer(1, &self.camera_buffer)
-            .push_storage_buffer(2, &self.camera_instance_buffer)
-            .push_storage_buffer(3, &self.visible_rigid_mesh_instances_simple_buffer)
-            .push_storage_buffer(5, &*backend_shared.static_vertex_position_buffer.lock())
-            .push_storage_buffer(6, &*backend_shared.static_indices_buffer.lock())
-            .push_storage_buffer(7, &*backend_shared.static_vertex_normals_buffer.lock())
-            .push_storage_buffer(8, &*backend_shared.mesh_attributes_buffer.lock())
-            .push_storage_buffer(9, &self.rigid_mesh_buffer)
-            .push_storage_buffer(10, &self.mesh_attributes_active_buffer)
-            .push_storage_buffer(11, &self.rigid_mesh_instance_buffer)
-            .push_storage_buffer(12, &*backend_shared.static_meshlet_buffer.lock())
-            .push_storage_buffer(13, &self.visible_rigid_mesh_instances)
-            .push_storage_buffer(14, &self.visible_rigid_mesh_meshlets)
-            .push_storage_buffer(15, &self.point_cloud_attributes_active_buffer)
-            .push_storage_buffer(16, &self.point_cloud_buffer)
-            .push_storage_buffer(17, &self.point_cloud_instance_buffer)
-            .push_storage_buffer(18, &self.visible_point_cloud_instances_simple)
-            .push_storage_buffer(19, &*backend_shared.point_cloud_attributes_buffer.lock())
-            .push_storage_buffer(20, &*backend_shared.static_point_positions_buffer.lock())
-            .push_storage_buffer(21, &*backend_shared.static_point_colors_buffer.lock())
-            .push_storage_buffer(22, &*backend_shared.point_cloud_page_buffer.lock())
-            .push_storage_buffer(23, &self.point_cloud_pages_active_buffer)
-            .push_storage_buffer(24, &*backend_shared.static_point_cloud_pages_buffer.lock())
-            .push_storage_buffer(25, &self.visible_point_cloud_instances)
-            .push_storage_buffer(26, &self.visible_point_cloud_clusters)
-            .push_storage_buffer(27, &self.frame_telemetry_buffer)
-            .push_storage_buffer(28, &self.device_local_debug_lines_buffer)
+            .push_uniform_buffer(bindings::PER_FRAME_DATA, per_frame_data_buffer)
+            .push_raw_storage_buffers(cached_descriptor_set.written_buffers())
             .build();
-        command_buffer_builder.push_descriptors(0, pipeline_bind_point, push_descriptors)?;
+        command_buffer_builder.push_descriptors(sets::PUSH_DESCRIPTORS, pipeline_bind_point, push_descriptors)?;
+        command_buffer_builder.bind_cached_descriptor_set(pipeline_bind_point, cached_descriptor_set)?;
         Ok(())
     }
 }
@@ -544,9 +1086,12 @@ mod tests {
     use std::sync::mpsc::channel;
 
     use crate::device::TestFixtureDevice;
-    use jeriya_backend::{elements::camera::Camera, gpu_index_allocator::GpuIndexAllocation, transactions::PushEvent};
+    use jeriya_backend::{elements::camera::Camera, transactions::PushEvent};
     use jeriya_content::asset_importer::AssetImporter;
-    use jeriya_shared::Handle;
+    use jeriya_shared::{
+        nalgebra::{Matrix4, Vector3},
+        Handle,
+    };
 
     use super::*;
 
@@ -578,10 +1123,49 @@ mod tests {
             GpuIndexAllocation::new_unchecked(0),
         );
         transaction.push_event(transactions::Event::Camera(camera::Event::Insert(camera.clone())));
-        frame.push_transaction(transaction);
+        frame.push_transaction(transaction, 0.0);
         frame.process_transactions().unwrap();
         let mut data = vec![shader_interface::Camera::default(); frame.camera_buffer.capacity()];
         frame.camera_buffer.host_visible_buffer().get_memory_unaligned(&mut data).unwrap();
         assert_eq!(data[0].projection_matrix, camera.projection().projection_matrix());
     }
+
+    #[test]
+    fn update_transform_restarts_motion() {
+        let test_fixture_device = TestFixtureDevice::new().unwrap();
+        let (resource_sender, _resource_receiver) = channel();
+        let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
+        let backend_shared = BackendShared::new(
+            &test_fixture_device.device,
+            &Arc::new(Default::default()),
+            resource_sender,
+            &asset_importer,
+        )
+        .unwrap();
+        let mut frame = PersistentFrameState::new(0, &test_fixture_device.window.id(), &backend_shared).unwrap();
+        let gpu_index_allocation = GpuIndexAllocation::new_unchecked(0);
+
+        let mut transaction = Transaction::new();
+        transaction.push_event(transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateMotion(
+            gpu_index_allocation,
+            rigid_mesh_instance::Motion::LinearVelocity(Vector3::new(1.0, 0.0, 0.0)),
+        )));
+        frame.push_transaction(transaction, 2.0);
+        frame.process_transactions().unwrap();
+
+        let transform = Matrix4::new_translation(&Vector3::new(0.0, 3.0, 0.0));
+        let mut transaction = Transaction::new();
+        transaction.push_event(transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateTransform(
+            gpu_index_allocation,
+            transform,
+        )));
+        frame.push_transaction(transaction, 5.0);
+        frame.process_transactions().unwrap();
+
+        // The motion that elapsed before the update must not be applied on top of the new transform
+        let motion = frame.rigid_mesh_instance_motion_buffer.get(&gpu_index_allocation).unwrap();
+        assert_eq!(motion.kind, shader_interface::MotionKind::LinearVelocity);
+        assert_eq!(motion.base_transform, transform);
+        assert_eq!(motion.start_time, 5.0);
+    }
 }
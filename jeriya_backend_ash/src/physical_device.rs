@@ -1,3 +1,5 @@
+use std::cmp::Reverse;
+
 use ash::vk::{self, PhysicalDeviceType};
 use jeriya_shared::log::info;
 
@@ -17,27 +19,60 @@ impl AsRawVulkan for PhysicalDevice {
     }
 }
 
+/// Summary of a physical device from which the device of the backend is selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhysicalDeviceInfo {
+    /// Index of the physical device in the order in which Vulkan enumerates them
+    pub index: usize,
+    pub name: String,
+    pub device_type: PhysicalDeviceType,
+    /// Size of all device local memory heaps in bytes
+    pub device_local_memory: u64,
+}
+
+/// Callback that returns the [`PhysicalDeviceInfo::index`] of the physical device that should be used
+pub type PhysicalDeviceSelector = Box<dyn Fn(&[PhysicalDeviceInfo]) -> Option<usize>>;
+
+/// Determines which physical device is used by the backend
+#[derive(Default)]
+pub enum PhysicalDeviceSelection {
+    /// Prefers discrete GPUs over integrated GPUs and then the physical device with the most device local memory.
+    /// Ties are resolved by the lower index so that the selection is deterministic.
+    #[default]
+    Automatic,
+    /// Selects the physical device with the given [`PhysicalDeviceInfo::index`]
+    Index(usize),
+    /// Selects the physical device whose [`PhysicalDeviceInfo::index`] is returned by the callback
+    Callback(PhysicalDeviceSelector),
+}
+
 impl PhysicalDevice {
     /// Select a physical device that can be used for the device creation
     pub fn new(instance: &Instance) -> crate::Result<PhysicalDevice> {
+        Self::with_selection(instance, &PhysicalDeviceSelection::Automatic)
+    }
+
+    /// Selects the physical device according to the [`PhysicalDeviceSelection`]
+    pub fn with_selection(instance: &Instance, selection: &PhysicalDeviceSelection) -> crate::Result<PhysicalDevice> {
         let instance = instance.as_raw_vulkan();
 
-        // Get Physical Devices
         let physical_devices = unsafe { instance.enumerate_physical_devices()? };
-        if physical_devices.is_empty() {
-            return Err(Error::NoPhysicalDevices);
-        }
+        let physical_device_infos = physical_device_infos(instance, &physical_devices)?;
+        info!("Available PhysicalDevices: {physical_device_infos:#?}");
 
-        // Rate PhysicalDevices and select the best one
-        let rated = rate_physical_devices(instance, physical_devices)?;
-        let physical_device = rated.first().expect("no physical devices after rating");
+        let index = match selection {
+            PhysicalDeviceSelection::Automatic => automatic_selection(&physical_device_infos).ok_or(Error::NoPhysicalDevices)?,
+            PhysicalDeviceSelection::Index(index) => *index,
+            PhysicalDeviceSelection::Callback(selector) => selector(&physical_device_infos).ok_or(Error::NoPhysicalDeviceSelected)?,
+        };
+        let physical_device = *physical_devices.get(index).ok_or(Error::UnknownPhysicalDevice(index))?;
 
-        let physical_device_properties = unsafe { instance.get_physical_device_properties(*physical_device) };
+        let physical_device_properties = unsafe { instance.get_physical_device_properties(physical_device) };
         info!("Selected PhysicalDevice: {:#?}", physical_device_properties);
 
-        let physical_device_memory_properties = unsafe { instance.get_physical_device_memory_properties(*physical_device) };
+        let physical_device_memory_properties = unsafe { instance.get_physical_device_memory_properties(physical_device) };
 
-        let physical_device_queue_family_properties = unsafe { instance.get_physical_device_queue_family_properties(*physical_device) };
+        let physical_device_queue_family_properties = unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
         for queue_family_properties in physical_device_queue_family_properties.iter() {
             info!("Queue Family: {:#?}", queue_family_properties);
         }
@@ -45,40 +80,96 @@ impl PhysicalDevice {
         Ok(PhysicalDevice {
             physical_device_properties,
             physical_device_memory_properties,
-            physical_device: *physical_device,
+            physical_device,
         })
     }
+
+    /// Returns the [`PhysicalDeviceInfo`]s of all physical devices in the order in which Vulkan enumerates them
+    pub fn enumerate(instance: &Instance) -> crate::Result<Vec<PhysicalDeviceInfo>> {
+        let instance = instance.as_raw_vulkan();
+        let physical_devices = unsafe { instance.enumerate_physical_devices()? };
+        physical_device_infos(instance, &physical_devices)
+    }
 }
 
-/// Rate the physical devices based on some characteristics so that the most capable is selected
-fn rate_physical_devices(instance: &ash::Instance, physical_devices: Vec<vk::PhysicalDevice>) -> crate::Result<Vec<vk::PhysicalDevice>> {
-    let mut rated = physical_devices
-        .into_iter()
-        .map(|physical_device| {
-            let physical_device_properties = unsafe { instance.get_physical_device_properties(physical_device) };
-            let name = jeriya_shared::c_null_terminated_char_array_to_string(&physical_device_properties.device_name)?;
-            let mut rating = 0;
-            if physical_device_properties.device_type == PhysicalDeviceType::DISCRETE_GPU {
-                rating += 1;
-            }
-            Ok((rating, name, physical_device))
+fn physical_device_infos(instance: &ash::Instance, physical_devices: &[vk::PhysicalDevice]) -> crate::Result<Vec<PhysicalDeviceInfo>> {
+    physical_devices
+        .iter()
+        .enumerate()
+        .map(|(index, physical_device)| {
+            let properties = unsafe { instance.get_physical_device_properties(*physical_device) };
+            let memory_properties = unsafe { instance.get_physical_device_memory_properties(*physical_device) };
+            let device_local_memory = memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize]
+                .iter()
+                .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+                .map(|heap| heap.size)
+                .sum();
+            Ok(PhysicalDeviceInfo {
+                index,
+                name: jeriya_shared::c_null_terminated_char_array_to_string(&properties.device_name)?,
+                device_type: properties.device_type,
+                device_local_memory,
+            })
         })
-        .collect::<crate::Result<Vec<_>>>()?;
-    let list = rated
+        .collect()
+}
+
+/// Returns the index of the most capable physical device for [`PhysicalDeviceSelection::Automatic`]
+fn automatic_selection(physical_device_infos: &[PhysicalDeviceInfo]) -> Option<usize> {
+    let type_rating = |device_type: PhysicalDeviceType| match device_type {
+        PhysicalDeviceType::DISCRETE_GPU => 3,
+        PhysicalDeviceType::INTEGRATED_GPU => 2,
+        PhysicalDeviceType::VIRTUAL_GPU => 1,
+        _ => 0,
+    };
+    physical_device_infos
         .iter()
-        .map(|device| format!("({}, {})", device.0, device.1))
-        .collect::<Vec<String>>()
-        .join(", ");
-    info!("Rated Physical Devices: {list}");
-    rated.sort_by(|a, b| a.0.cmp(&b.0));
-    Ok(rated
-        .into_iter()
-        .map(|(_, _, physical_device)| physical_device)
-        .collect::<Vec<vk::PhysicalDevice>>())
+        .max_by_key(|info| (type_rating(info.device_type), info.device_local_memory, Reverse(info.index)))
+        .map(|info| info.index)
 }
 
 #[cfg(test)]
 mod tests {
+    mod automatic_selection {
+        use ash::vk::PhysicalDeviceType;
+
+        use crate::physical_device::{automatic_selection, PhysicalDeviceInfo};
+
+        fn info(index: usize, device_type: PhysicalDeviceType, device_local_memory: u64) -> PhysicalDeviceInfo {
+            PhysicalDeviceInfo {
+                index,
+                name: format!("Device {index}"),
+                device_type,
+                device_local_memory,
+            }
+        }
+
+        #[test]
+        fn prefers_discrete_gpu() {
+            let infos = [
+                info(0, PhysicalDeviceType::INTEGRATED_GPU, 16 << 30),
+                info(1, PhysicalDeviceType::DISCRETE_GPU, 8 << 30),
+                info(2, PhysicalDeviceType::CPU, 32 << 30),
+            ];
+            assert_eq!(automatic_selection(&infos), Some(1));
+        }
+
+        #[test]
+        fn prefers_more_memory_and_then_lower_index() {
+            let infos = [
+                info(0, PhysicalDeviceType::DISCRETE_GPU, 8 << 30),
+                info(1, PhysicalDeviceType::DISCRETE_GPU, 12 << 30),
+                info(2, PhysicalDeviceType::DISCRETE_GPU, 12 << 30),
+            ];
+            assert_eq!(automatic_selection(&infos), Some(1));
+        }
+
+        #[test]
+        fn empty() {
+            assert_eq!(automatic_selection(&[]), None);
+        }
+    }
+
     mod new {
         use crate::{entry::Entry, instance::Instance, physical_device::PhysicalDevice};
 
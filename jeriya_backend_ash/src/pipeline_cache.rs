@@ -0,0 +1,173 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ash::vk;
+use jeriya_shared::{
+    log::{info, warn},
+    AsDebugInfo, DebugInfo,
+};
+
+use crate::{device::Device, AsRawVulkan, DebugInfoAshExtension};
+
+/// Cache for the compiled pipelines that can be persisted on disk to reduce the time it takes to create the pipelines on startup
+pub struct PipelineCache {
+    pipeline_cache: vk::PipelineCache,
+    path: Option<PathBuf>,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl Drop for PipelineCache {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = self.save(path) {
+                warn!("Failed to save the PipelineCache to '{}': {err}", path.display());
+            }
+        }
+        unsafe { self.device.as_raw_vulkan().destroy_pipeline_cache(self.pipeline_cache, None) }
+    }
+}
+
+impl AsDebugInfo for PipelineCache {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl PipelineCache {
+    /// Creates a new [`PipelineCache`] that is initialized with the given data.
+    ///
+    /// The data is ignored by the driver when it was created by an incompatible device or driver.
+    pub fn new(device: &Arc<Device>, initial_data: &[u8], debug_info: DebugInfo) -> crate::Result<Self> {
+        let create_info = vk::PipelineCacheCreateInfo::builder().initial_data(initial_data);
+        let pipeline_cache = unsafe { device.as_raw_vulkan().create_pipeline_cache(&create_info, None)? };
+        let debug_info = debug_info.with_vulkan_ptr(pipeline_cache);
+        device.set_debug_name(pipeline_cache, &debug_info);
+        Ok(Self {
+            pipeline_cache,
+            path: None,
+            device: device.clone(),
+            debug_info,
+        })
+    }
+
+    /// Creates a new [`PipelineCache`] from the file at the given path and saves it to the same path with
+    /// [`PipelineCache::save_to_path`] and when it is dropped.
+    ///
+    /// When the file doesn't exist or cannot be read, the [`PipelineCache`] starts empty.
+    pub fn persistent(device: &Arc<Device>, path: impl AsRef<Path>, debug_info: DebugInfo) -> crate::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let initial_data = match fs::read(&path) {
+            Ok(data) => {
+                info!("Loaded PipelineCache with {} bytes from '{}'", data.len(), path.display());
+                data
+            }
+            Err(err) => {
+                info!(
+                    "Starting with an empty PipelineCache because '{}' cannot be read: {err}",
+                    path.display()
+                );
+                Vec::new()
+            }
+        };
+        let mut pipeline_cache = Self::new(device, &initial_data, debug_info)?;
+        pipeline_cache.path = Some(path);
+        Ok(pipeline_cache)
+    }
+
+    /// Returns the serialized data of the [`PipelineCache`]
+    pub fn data(&self) -> crate::Result<Vec<u8>> {
+        Ok(unsafe { self.device.as_raw_vulkan().get_pipeline_cache_data(self.pipeline_cache)? })
+    }
+
+    /// Writes the serialized data of the [`PipelineCache`] to the path from which it was created with [`PipelineCache::persistent`].
+    /// Does nothing when the [`PipelineCache`] is not persistent.
+    pub fn save_to_path(&self) -> crate::Result<()> {
+        match &self.path {
+            Some(path) => self.save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the serialized data of the [`PipelineCache`] to the given path
+    pub fn save(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let data = self.data()?;
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path.as_ref(), &data)?;
+        info!("Saved PipelineCache with {} bytes to '{}'", data.len(), path.as_ref().display());
+        Ok(())
+    }
+}
+
+impl AsRawVulkan for PipelineCache {
+    type Output = vk::PipelineCache;
+    fn as_raw_vulkan(&self) -> &Self::Output {
+        &self.pipeline_cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod new {
+        use jeriya_shared::debug_info;
+
+        use crate::{device::TestFixtureDevice, pipeline_cache::PipelineCache};
+
+        #[test]
+        fn smoke() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let pipeline_cache = PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap();
+            let data = pipeline_cache.data().unwrap();
+            let _pipeline_cache = PipelineCache::new(&test_fixture_device.device, &data, debug_info!("my_pipeline_cache")).unwrap();
+        }
+    }
+
+    mod persistent {
+        use std::{
+            path::PathBuf,
+            process,
+            sync::atomic::{AtomicUsize, Ordering},
+        };
+
+        use jeriya_shared::debug_info;
+
+        use crate::{device::TestFixtureDevice, pipeline_cache::PipelineCache};
+
+        /// Returns a path in a directory that is unique to the test so that tests running in parallel don't share the file
+        fn unique_path() -> PathBuf {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let directory = std::env::temp_dir().join(format!(
+                "jeriya_test_pipeline_cache_{}_{}",
+                process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            directory.join("pipeline_cache.bin")
+        }
+
+        #[test]
+        fn save_on_drop() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let path = unique_path();
+            let pipeline_cache = PipelineCache::persistent(&test_fixture_device.device, &path, debug_info!("my_pipeline_cache")).unwrap();
+            drop(pipeline_cache);
+            assert!(path.exists());
+            let _pipeline_cache = PipelineCache::persistent(&test_fixture_device.device, &path, debug_info!("my_pipeline_cache")).unwrap();
+            std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+
+        #[test]
+        fn save_to_path() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let path = unique_path();
+            let pipeline_cache = PipelineCache::persistent(&test_fixture_device.device, &path, debug_info!("my_pipeline_cache")).unwrap();
+            pipeline_cache.save_to_path().unwrap();
+            assert!(path.exists());
+            std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+}
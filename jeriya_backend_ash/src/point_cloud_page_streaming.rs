@@ -0,0 +1,434 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use jeriya_backend::resources::point_cloud_attributes::PointCloudAttributes;
+use jeriya_content::{
+    asset_importer::{AssetImporter, PointCloudPageImport},
+    point_cloud::clustered_point_cloud::Page,
+};
+use jeriya_shared::{
+    bus::BusReader,
+    log::{error, info, trace, warn},
+};
+
+use crate::{
+    command_buffer_builder::CommandBufferBuilder,
+    shader_interface::{PointCloudCluster, PointCloudPage, PointCloudPageRequest},
+    staged_push_only_buffer::StagedPushOnlyBuffer,
+};
+
+/// Number of pages that can be requested from the [`AssetImporter`] at the same time
+const MAXIMUM_REQUESTS_IN_FLIGHT: usize = 64;
+
+/// Reference from a cluster to one of its children
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ChildReference {
+    /// Page of the cluster that references the child
+    parent_page_index: usize,
+    /// Index of the cluster in its page
+    cluster_index: usize,
+    /// Index into the `children_page_indices` of the cluster
+    child_index: usize,
+}
+
+struct ResidentPage {
+    /// Index of the page in the static point cloud pages buffer
+    slot: usize,
+    /// Update in which the page or one of its descendants was needed the last time
+    last_used: u64,
+    /// Pages that contain children of the clusters of this page
+    child_pages: Vec<usize>,
+}
+
+struct StreamedPointCloud {
+    point_cloud_attributes: Arc<PointCloudAttributes>,
+    root_page_index: usize,
+    resident_pages: HashMap<usize, ResidentPage>,
+    /// Pages that have been requested from the [`AssetImporter`] but haven't been received yet
+    requested_pages: HashSet<usize>,
+    /// Maps the pages to the clusters of the resident pages that reference them as children
+    child_references: HashMap<usize, Vec<ChildReference>>,
+}
+
+impl StreamedPointCloud {
+    /// Marks the page and all of its resident ancestors as used
+    fn touch(&mut self, page_index: usize, update_index: u64) {
+        let mut visited = HashSet::new();
+        let mut stack = vec![page_index];
+        while let Some(page_index) = stack.pop() {
+            if !visited.insert(page_index) {
+                continue;
+            }
+            if let Some(resident_page) = self.resident_pages.get_mut(&page_index) {
+                resident_page.last_used = update_index;
+            }
+            if let Some(child_references) = self.child_references.get(&page_index) {
+                stack.extend(child_references.iter().map(|child_reference| child_reference.parent_page_index));
+            }
+        }
+    }
+
+    /// Returns the index of the page that should be evicted next. Only pages without resident children are evicted so that
+    /// the resident pages always form a connected hierarchy. Pages that have been used in the current update are kept.
+    fn least_recently_used_page(&self, update_index: u64) -> Option<(usize, u64)> {
+        self.resident_pages
+            .iter()
+            .filter(|(page_index, resident_page)| {
+                **page_index != self.root_page_index
+                    && resident_page.last_used < update_index
+                    && resident_page
+                        .child_pages
+                        .iter()
+                        .all(|child_page| !self.resident_pages.contains_key(child_page))
+            })
+            .map(|(page_index, resident_page)| (*page_index, resident_page.last_used))
+            .min_by_key(|(_, last_used)| *last_used)
+    }
+}
+
+/// Streams the pages of [`PointCloudAttributes`] with a [`PageStreamingSource`](jeriya_backend::resources::point_cloud_attributes::PageStreamingSource)
+/// into the static point cloud pages buffer.
+///
+/// The point cloud cluster culling requests the pages that it would need to refine the clusters further. The requested pages
+/// are imported by the [`AssetImporter`] and uploaded in the order of the density of the clusters that they refine. When the
+/// maximum number of resident pages is reached, the least recently used pages are evicted.
+pub struct PointCloudPageStreaming {
+    asset_importer: Arc<AssetImporter>,
+    page_receiver: BusReader<Arc<PointCloudPageImport>>,
+    /// Maps the GPU index of the [`PointCloudAttributes`] to their streaming state
+    streamed_point_clouds: HashMap<usize, StreamedPointCloud>,
+    /// Requests of the GPU that haven't been handled yet with the lowest density with which they were requested
+    pending_requests: HashMap<(usize, usize), f32>,
+    /// Slots of the evicted pages that haven't been taken with [`PointCloudPageStreaming::take_evicted_slots`]
+    evicted_slots: Vec<usize>,
+    resident_pages_len: usize,
+    maximum_resident_pages: usize,
+    update_index: u64,
+}
+
+impl PointCloudPageStreaming {
+    /// Creates a new `PointCloudPageStreaming` that keeps at most `maximum_resident_pages` pages resident
+    pub fn new(asset_importer: &Arc<AssetImporter>, maximum_resident_pages: usize) -> Self {
+        Self {
+            asset_importer: asset_importer.clone(),
+            page_receiver: asset_importer.receive_point_cloud_pages(),
+            streamed_point_clouds: HashMap::new(),
+            pending_requests: HashMap::new(),
+            evicted_slots: Vec::new(),
+            resident_pages_len: 0,
+            maximum_resident_pages,
+            update_index: 0,
+        }
+    }
+
+    /// Returns `true` when there are [`PointCloudAttributes`] whose pages are streamed
+    pub fn is_streaming(&self) -> bool {
+        !self.streamed_point_clouds.is_empty()
+    }
+
+    /// Returns the slots of the pages that have been evicted since the last call. The slots must not be reused before
+    /// the frames in flight and the uploads that have been recorded until now have finished.
+    pub fn take_evicted_slots(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.evicted_slots)
+    }
+
+    /// Starts streaming the pages of the [`PointCloudAttributes`] by requesting the page that contains the root cluster
+    ///
+    /// # Panics
+    ///
+    /// If the [`PointCloudAttributes`] don't have a [`PageStreamingSource`](jeriya_backend::resources::point_cloud_attributes::PageStreamingSource).
+    pub fn insert(&mut self, point_cloud_attributes: Arc<PointCloudAttributes>) {
+        let page_streaming_source = point_cloud_attributes
+            .page_streaming_source()
+            .expect("PointCloudAttributes without a PageStreamingSource can't be streamed");
+        let root_page_index = point_cloud_attributes.root_cluster_index().page_index;
+        info!(
+            "Start streaming {} pages of PointCloudAttributes {} from '{}'",
+            page_streaming_source.pages_len,
+            point_cloud_attributes.debug_info().format_one_line(),
+            page_streaming_source.asset_key
+        );
+        self.asset_importer
+            .request_point_cloud_page(&page_streaming_source.asset_key, root_page_index);
+        let gpu_index = point_cloud_attributes.gpu_index_allocation().index();
+        self.streamed_point_clouds.insert(
+            gpu_index,
+            StreamedPointCloud {
+                point_cloud_attributes,
+                root_page_index,
+                resident_pages: HashMap::new(),
+                requested_pages: HashSet::from([root_page_index]),
+                child_references: HashMap::new(),
+            },
+        );
+    }
+
+    /// Stops streaming the pages of the [`PointCloudAttributes`] with the given GPU index. The slots of its resident pages are returned by [`PointCloudPageStreaming::take_evicted_slots`].
+    pub fn remove(&mut self, gpu_index: usize) {
+        let Some(streamed_point_cloud) = self.streamed_point_clouds.remove(&gpu_index) else {
+            return;
+        };
+        self.resident_pages_len -= streamed_point_cloud.resident_pages.len();
+        self.evicted_slots
+            .extend(streamed_point_cloud.resident_pages.values().map(|resident_page| resident_page.slot));
+        self.pending_requests.retain(|(index, _), _| *index != gpu_index);
+    }
+
+    /// Pushes the requests that the point cloud cluster culling has written in a frame. They are handled in the next [`PointCloudPageStreaming::update`].
+    pub fn push_requests(&mut self, requests: &[PointCloudPageRequest]) {
+        for request in requests {
+            let key = (request.point_cloud_attributes_index as usize, request.page_index as usize);
+            let density = self.pending_requests.entry(key).or_insert(f32::MAX);
+            *density = density.min(request.density);
+        }
+    }
+
+    /// Uploads the pages that have been imported since the last update, evicts pages when necessary and requests the pages
+    /// that have been requested by the GPU from the [`AssetImporter`].
+    ///
+    /// Returns the [`PointCloudAttributes`] whose root page has been uploaded in this update together with the slot of the root page.
+    pub fn update(
+        &mut self,
+        static_point_cloud_pages_buffer: &mut StagedPushOnlyBuffer<PointCloudPage>,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<Vec<(Arc<PointCloudAttributes>, usize)>> {
+        self.update_index += 1;
+        let update_index = self.update_index;
+
+        // Upload the imported pages
+        let mut uploaded_root_pages = Vec::new();
+        while let Ok(page_import) = self.page_receiver.try_recv() {
+            let page = match &page_import.page {
+                Ok(page) => page,
+                Err(err) => {
+                    error!(
+                        "Failed to import page {} of point cloud '{}': {err}",
+                        page_import.page_index, page_import.asset_key
+                    );
+                    continue;
+                }
+            };
+            let gpu_indices = self
+                .streamed_point_clouds
+                .iter()
+                .filter(|(_, streamed_point_cloud)| {
+                    streamed_point_cloud.requested_pages.contains(&page_import.page_index)
+                        && streamed_point_cloud
+                            .point_cloud_attributes
+                            .page_streaming_source()
+                            .is_some_and(|source| source.asset_key == page_import.asset_key)
+                })
+                .map(|(gpu_index, _)| *gpu_index)
+                .collect::<Vec<_>>();
+            for gpu_index in gpu_indices {
+                if let Some(slot) = self.upload_page(
+                    gpu_index,
+                    page_import.page_index,
+                    page,
+                    static_point_cloud_pages_buffer,
+                    command_buffer_builder,
+                )? {
+                    let streamed_point_cloud = &self.streamed_point_clouds[&gpu_index];
+                    if page_import.page_index == streamed_point_cloud.root_page_index {
+                        uploaded_root_pages.push((streamed_point_cloud.point_cloud_attributes.clone(), slot));
+                    }
+                }
+            }
+        }
+
+        // Request the pages that refine the clusters with the lowest density first
+        let mut pending_requests = self.pending_requests.drain().collect::<Vec<_>>();
+        pending_requests.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        let mut requests_in_flight = self
+            .streamed_point_clouds
+            .values()
+            .map(|streamed_point_cloud| streamed_point_cloud.requested_pages.len())
+            .sum::<usize>();
+        for ((gpu_index, page_index), _density) in pending_requests {
+            let Some(streamed_point_cloud) = self.streamed_point_clouds.get_mut(&gpu_index) else {
+                continue;
+            };
+            if streamed_point_cloud.resident_pages.contains_key(&page_index) {
+                continue;
+            }
+
+            // The clusters that would be refined by the page are in use
+            if let Some(child_references) = streamed_point_cloud.child_references.get(&page_index).cloned() {
+                for child_reference in child_references {
+                    streamed_point_cloud.touch(child_reference.parent_page_index, update_index);
+                }
+            }
+
+            if streamed_point_cloud.requested_pages.contains(&page_index) || requests_in_flight >= MAXIMUM_REQUESTS_IN_FLIGHT {
+                continue;
+            }
+            let Some(page_streaming_source) = streamed_point_cloud.point_cloud_attributes.page_streaming_source() else {
+                continue;
+            };
+            if page_index >= page_streaming_source.pages_len {
+                warn!(
+                    "Page {page_index} of point cloud '{}' was requested but doesn't exist",
+                    page_streaming_source.asset_key
+                );
+                continue;
+            }
+            trace!("Requesting page {page_index} of point cloud '{}'", page_streaming_source.asset_key);
+            self.asset_importer
+                .request_point_cloud_page(&page_streaming_source.asset_key, page_index);
+            streamed_point_cloud.requested_pages.insert(page_index);
+            requests_in_flight += 1;
+        }
+
+        Ok(uploaded_root_pages)
+    }
+
+    /// Uploads the page into the static point cloud pages buffer and links it with the resident pages. Returns the slot of the
+    /// page or `None` when the page couldn't be uploaded because no page can be evicted.
+    fn upload_page(
+        &mut self,
+        gpu_index: usize,
+        page_index: usize,
+        page: &Page,
+        static_point_cloud_pages_buffer: &mut StagedPushOnlyBuffer<PointCloudPage>,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<Option<usize>> {
+        let update_index = self.update_index;
+        self.streamed_point_clouds
+            .get_mut(&gpu_index)
+            .expect("failed to find the streamed point cloud")
+            .requested_pages
+            .remove(&page_index);
+
+        // Make space for the page
+        while self.resident_pages_len >= self.maximum_resident_pages {
+            if !self.evict_least_recently_used_page(static_point_cloud_pages_buffer, command_buffer_builder)? {
+                trace!("Dropping page {page_index} because no page can be evicted");
+                return Ok(None);
+            }
+        }
+
+        // The eagerly uploaded PointCloudAttributes share the buffer with the streamed pages. When it's full, a page
+        // is evicted so that its slot can be reused once it's no longer accessed and the page is requested again.
+        if static_point_cloud_pages_buffer.available_len() == 0 {
+            self.evict_least_recently_used_page(static_point_cloud_pages_buffer, command_buffer_builder)?;
+            trace!("Dropping page {page_index} because the static point cloud pages buffer is full");
+            return Ok(None);
+        }
+
+        // Children that are resident are referenced by their slot and the other ones are marked as non-resident
+        let streamed_point_cloud = self
+            .streamed_point_clouds
+            .get_mut(&gpu_index)
+            .expect("failed to find the streamed point cloud");
+        let point_cloud_page = PointCloudPage::new(page, |child_page_index| {
+            match streamed_point_cloud.resident_pages.get(&child_page_index) {
+                Some(resident_page) => resident_page.slot as u32,
+                None => PointCloudCluster::NON_RESIDENT_PAGE_BIT | child_page_index as u32,
+            }
+        });
+        let slot = static_point_cloud_pages_buffer
+            .push(&[point_cloud_page], command_buffer_builder)?
+            .expect("failed to push the page");
+
+        // Link the clusters of the resident pages that reference the page as their child
+        if let Some(child_references) = streamed_point_cloud.child_references.get(&page_index) {
+            for child_reference in child_references {
+                let parent_slot = streamed_point_cloud.resident_pages[&child_reference.parent_page_index].slot;
+                let byte_offset = PointCloudPage::children_page_indices_offset(child_reference.cluster_index)
+                    + child_reference.child_index * std::mem::size_of::<u32>();
+                static_point_cloud_pages_buffer.write_at(parent_slot, byte_offset, &(slot as u32), command_buffer_builder)?;
+            }
+        }
+
+        // Register the children of the clusters so that they can be linked when they become resident
+        let mut child_pages = Vec::new();
+        for (cluster_index, cluster) in page.clusters().iter().enumerate() {
+            for (child_index, child) in cluster.children.iter().enumerate() {
+                streamed_point_cloud
+                    .child_references
+                    .entry(child.page_index)
+                    .or_default()
+                    .push(ChildReference {
+                        parent_page_index: page_index,
+                        cluster_index,
+                        child_index,
+                    });
+                if !child_pages.contains(&child.page_index) {
+                    child_pages.push(child.page_index);
+                }
+            }
+        }
+
+        streamed_point_cloud.resident_pages.insert(
+            page_index,
+            ResidentPage {
+                slot,
+                last_used: update_index,
+                child_pages,
+            },
+        );
+        streamed_point_cloud.touch(page_index, update_index);
+        self.resident_pages_len += 1;
+
+        Ok(Some(slot))
+    }
+
+    /// Evicts the least recently used page of all streamed point clouds. Returns `false` when no page can be evicted.
+    fn evict_least_recently_used_page(
+        &mut self,
+        static_point_cloud_pages_buffer: &mut StagedPushOnlyBuffer<PointCloudPage>,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<bool> {
+        let update_index = self.update_index;
+        let Some((gpu_index, page_index)) = self
+            .streamed_point_clouds
+            .iter()
+            .filter_map(|(gpu_index, streamed_point_cloud)| {
+                streamed_point_cloud
+                    .least_recently_used_page(update_index)
+                    .map(|(page_index, last_used)| (*gpu_index, page_index, last_used))
+            })
+            .min_by_key(|(_, _, last_used)| *last_used)
+            .map(|(gpu_index, page_index, _)| (gpu_index, page_index))
+        else {
+            return Ok(false);
+        };
+
+        let streamed_point_cloud = self
+            .streamed_point_clouds
+            .get_mut(&gpu_index)
+            .expect("failed to find the streamed point cloud");
+        let resident_page = streamed_point_cloud
+            .resident_pages
+            .remove(&page_index)
+            .expect("failed to find the resident page");
+
+        // Unlink the page from the clusters that reference it so that the culling requests it again when needed
+        if let Some(child_references) = streamed_point_cloud.child_references.get(&page_index) {
+            for child_reference in child_references {
+                let parent_slot = streamed_point_cloud.resident_pages[&child_reference.parent_page_index].slot;
+                let byte_offset = PointCloudPage::children_page_indices_offset(child_reference.cluster_index)
+                    + child_reference.child_index * std::mem::size_of::<u32>();
+                let non_resident_page_index = PointCloudCluster::NON_RESIDENT_PAGE_BIT | page_index as u32;
+                static_point_cloud_pages_buffer.write_at(parent_slot, byte_offset, &non_resident_page_index, command_buffer_builder)?;
+            }
+        }
+
+        // The clusters of the evicted page don't reference their children anymore
+        for child_page in &resident_page.child_pages {
+            if let Some(child_references) = streamed_point_cloud.child_references.get_mut(child_page) {
+                child_references.retain(|child_reference| child_reference.parent_page_index != page_index);
+                if child_references.is_empty() {
+                    streamed_point_cloud.child_references.remove(child_page);
+                }
+            }
+        }
+
+        trace!("Evicting page {page_index} from slot {}", resident_page.slot);
+        self.evicted_slots.push(resident_page.slot);
+        self.resident_pages_len -= 1;
+        Ok(true)
+    }
+}
@@ -0,0 +1,31 @@
+use jeriya_content::common::AssetKey;
+
+use crate::custom_pass::CustomPassId;
+
+/// Fullscreen pass of the application that is rendered into the swapchain image after the scene has been rendered
+///
+/// The passes run after the eye-dome lighting and before the overlay and the user interface. Before every pass, the
+/// swapchain image is copied so that the fragment shader can sample the rendered color with the combined image sampler
+/// at `binding = 33` and the depth buffer at `binding = 29` in `set = 0`. The output of the fragment shader at
+/// `location = 0` replaces the color of the swapchain image. The `PerFrameData` and the buffers of the scene are bound
+/// like for the built-in passes.
+///
+/// The passes are skipped when the swapchain images can't be copied.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PostProcessingPass {
+    pub fragment_shader: AssetKey,
+}
+
+impl PostProcessingPass {
+    /// Creates a new `PostProcessingPass` that renders a fullscreen triangle with the given fragment shader
+    pub fn new(fragment_shader: AssetKey) -> Self {
+        Self { fragment_shader }
+    }
+}
+
+/// [`PostProcessingPass`] that has been added to a window
+#[derive(Debug, Clone)]
+pub(crate) struct RegisteredPostProcessingPass {
+    pub id: CustomPassId,
+    pub post_processing_pass: PostProcessingPass,
+}
@@ -1,30 +1,53 @@
 use std::{
     collections::BTreeMap,
-    sync::Arc,
+    sync::{mpsc::SyncSender, Arc},
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
+use ash::vk;
+
 use crate::{
-    backend_shared::BackendShared, compiled_frame_graph::CompiledFrameGraph, fence::Fence, persistent_frame_state::PersistentFrameState,
-    presenter_shared::PresenterShared, semaphore::Semaphore, surface::Surface, swapchain_vec::SwapchainVec,
+    backend_shared::BackendShared,
+    compiled_frame_graph::CompiledFrameGraph,
+    custom_pass::{CustomPass, CustomPassId, CustomPassPosition, RegisteredCustomPass},
+    fence::Fence,
+    frame_capture::{is_capturable_format, CapturedFrame, FrameCapture, FrameCaptureConfig, FrameCaptureHandle},
+    frame_telemetry::FrameTelemetry,
+    frame_timings::{AdaptiveFrameRate, FrameTimings},
+    persistent_frame_state::PersistentFrameState,
+    post_processing_pass::{PostProcessingPass, RegisteredPostProcessingPass},
+    presenter_shared::{PresentationTarget, PresenterShared, SwapchainPreparation},
+    readback::{DepthReadbackRequest, ReadbackRequest},
+    semaphore::Semaphore,
+    swapchain_vec::SwapchainVec,
+    user_compute_pass::{RegisteredUserComputePass, UserComputePass},
 };
 use jeriya_backend::{
-    immediate::{CommandBuffer, ImmediateRenderingFrame, ImmediateRenderingFrameTask},
-    instances::camera_instance::CameraInstance,
+    clip_plane::ClipPlane,
+    debug::DebugShadingMode,
+    immediate::{CommandBuffer, ImmediateRenderingFrame, ImmediateRenderingFrameTask, Timeout},
+    instances::camera_instance::{self, CameraInstance},
+    overlay::OverlayCommandBuffer,
+    performance_hud::{PerformanceHud, PerformanceSample},
+    render_target::RenderTargetId,
     resources::ResourceEvent,
-    transactions::Transaction,
+    statistics::WindowStatistics,
+    transactions::{self, Transaction},
+    ui::UiFrame,
+    viewport::Viewport,
+    ResizeCallback,
 };
 use jeriya_content::{asset_importer::Asset, shader::ShaderAsset};
 use jeriya_macros::profile;
 use jeriya_shared::{
     debug_info,
     log::{info, trace},
-    parking_lot::Mutex,
-    spin_sleep_util,
-    tracy_client::Client,
+    parking_lot::{Mutex, MutexGuard},
+    plot_with_index, spin_sleep_util,
+    tracy_client::{plot, Client},
     winit::window::WindowId,
-    EventQueue, FrameRate,
+    ByteColor4, ColorSpace, EventQueue, FrameRate, PresentMode, WindowMode,
 };
 
 pub enum PresenterEvent {
@@ -32,15 +55,37 @@ pub enum PresenterEvent {
         command_buffer: CommandBuffer,
         immediate_rendering_frame: ImmediateRenderingFrame,
     },
+    RenderOverlayCommandBuffer {
+        command_buffer: OverlayCommandBuffer,
+        immediate_rendering_frame: ImmediateRenderingFrame,
+    },
+    /// Replaces the user interface that is rendered on top of the overlay
+    RenderUiFrame(UiFrame),
     ProcessTransaction(Transaction),
     ShaderImported(Asset<ShaderAsset>),
+    Readback(ReadbackRequest),
+    /// Copies a region of the depth buffer to the host after the next frame has been rendered
+    ReadDepthBuffer(DepthReadbackRequest),
+    /// Copies the image of the render target to the host after the next frame has been rendered
+    ReadRenderTarget {
+        render_target_id: RenderTargetId,
+        sender: SyncSender<CapturedFrame>,
+    },
 }
 
+/// Name under which the [`PerformanceHud`] is inserted into the immediate rendering frames so that it's rendered with the overlay
+const PERFORMANCE_HUD_UPDATE_LOOP_NAME: &str = "jeriya-performance-hud";
+
+/// Interval in which the presenter thread checks whether a minimized window has been restored when the frame rate is unlimited
+const EMPTY_PRESENTATION_TARGET_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
 pub struct Presenter {
     _presenter_index: usize,
     _thread: JoinHandle<()>,
     event_queue: Arc<Mutex<EventQueue<PresenterEvent>>>,
     presenter_shared: Arc<Mutex<PresenterShared>>,
+    /// Called by the presenter thread without holding the lock of the `PresenterShared`
+    resize_callback: Arc<Mutex<Option<ResizeCallback>>>,
 }
 
 #[profile]
@@ -51,12 +96,23 @@ impl Presenter {
         window_id: WindowId,
         backend_shared: Arc<BackendShared>,
         frame_rate: FrameRate,
-        surface: &Arc<Surface>,
+        present_mode: PresentMode,
+        color_space: ColorSpace,
+        presentation_target: PresentationTarget,
     ) -> jeriya_backend::Result<Self> {
-        let presenter_shared = Arc::new(Mutex::new(PresenterShared::new(&window_id, &backend_shared, surface)?));
+        let frame_pacing = FramePacing::new(frame_rate)?;
+        let presenter_shared = Arc::new(Mutex::new(PresenterShared::new(
+            &window_id,
+            &backend_shared,
+            presentation_target,
+            present_mode,
+            color_space,
+        )?));
         let presenter_shared2 = presenter_shared.clone();
         let event_queue = Arc::new(Mutex::new(EventQueue::new()));
         let event_queue2 = event_queue.clone();
+        let resize_callback = Arc::new(Mutex::new(None));
+        let resize_callback2 = resize_callback.clone();
         let thread = thread::Builder::new()
             .name(format!("presenter-thread-{presenter_index}"))
             .spawn(move || {
@@ -65,8 +121,9 @@ impl Presenter {
                     backend_shared,
                     presenter_shared2,
                     window_id,
-                    frame_rate,
+                    frame_pacing,
                     event_queue2,
+                    resize_callback2,
                 ) {
                     panic!("Error on PresenterThread {presenter_index} (Window: {window_id:?}): {err:?}");
                 }
@@ -78,6 +135,7 @@ impl Presenter {
             _thread: thread,
             event_queue,
             presenter_shared,
+            resize_callback,
         })
     }
 
@@ -91,10 +149,242 @@ impl Presenter {
         self._presenter_index
     }
 
+    /// Locks the [`PresenterShared`] so that the presenter can't record or submit frames until the guard is dropped
+    pub fn lock_frames(&self) -> MutexGuard<'_, PresenterShared> {
+        self.presenter_shared.lock()
+    }
+
     /// Sets the active camera
     pub fn set_active_camera(&self, camera_instance: &CameraInstance) {
         self.presenter_shared.lock().active_camera_instance = Some(*camera_instance.gpu_index_allocation());
     }
+
+    /// Sets the callback that is called after the swapchain has been recreated with a new extent
+    pub fn set_resize_callback(&self, resize_callback: Option<ResizeCallback>) {
+        *self.resize_callback.lock() = resize_callback;
+    }
+
+    /// Sets the mode of the window so that the swapchain is recreated for it
+    ///
+    /// The [`Window`](jeriya_shared::winit::window::Window) itself has to be switched to the mode by the caller.
+    pub fn set_window_mode(&self, window_mode: WindowMode) {
+        let mut presenter_shared = self.presenter_shared.lock();
+        if presenter_shared.window_mode != window_mode {
+            info!("Switching window {:?} to {window_mode:?}", presenter_shared.window_id);
+            presenter_shared.window_mode = window_mode;
+            presenter_shared.swapchain_outdated = true;
+        }
+    }
+
+    /// Sets the viewports into which the scene is rendered
+    pub fn set_viewports(&self, viewports: Vec<Viewport>) {
+        self.presenter_shared.lock().viewports = viewports;
+    }
+
+    /// Sets the planes that clip the scene
+    pub fn set_clip_planes(&self, clip_planes: Vec<ClipPlane>) {
+        self.presenter_shared.lock().clip_planes = clip_planes;
+    }
+
+    /// Creates a render target with the given `extent` into which the scene is rendered from the `camera_instance`
+    pub fn create_render_target(
+        &self,
+        render_target_id: RenderTargetId,
+        camera_instance: &CameraInstance,
+        extent: vk::Extent2D,
+    ) -> crate::Result<()> {
+        if extent.width == 0 || extent.height == 0 {
+            return Err(crate::Error::EmptyExtent(extent));
+        }
+        self.presenter_shared.lock().vulkan_resource_coordinator.create_render_target(
+            render_target_id,
+            *camera_instance.gpu_index_allocation(),
+            extent,
+        )
+    }
+
+    /// Removes the render target. Returns `false` when the render target doesn't exist.
+    pub fn remove_render_target(&self, render_target_id: RenderTargetId) -> bool {
+        self.presenter_shared
+            .lock()
+            .vulkan_resource_coordinator
+            .remove_render_target(render_target_id)
+            .is_some()
+    }
+
+    /// Enables or disables the rendering of the bounding volumes
+    pub fn set_debug_bounding_volumes(&self, enabled: bool) {
+        self.presenter_shared.lock().debug_bounding_volumes = enabled;
+    }
+
+    /// Returns the GPU times of the passes of the most recently completed frame
+    pub fn frame_timings(&self) -> Option<FrameTimings> {
+        self.presenter_shared.lock().frame_timings.clone()
+    }
+
+    /// Returns the culling results of the most recently completed frame
+    ///
+    /// The [`FrameTelemetry`] lags behind the recorded frames by the number of frames in flight.
+    pub fn frame_telemetry(&self) -> Option<FrameTelemetry> {
+        self.presenter_shared.lock().frame_telemetry.clone()
+    }
+
+    /// Returns the [`WindowStatistics`] of the most recent frame
+    pub fn statistics(&self) -> WindowStatistics {
+        let presenter_shared = self.presenter_shared.lock();
+        WindowStatistics {
+            window_id: presenter_shared.window_id,
+            cpu_frame_time: presenter_shared.cpu_frame_time,
+            pending_transactions: presenter_shared.pending_transactions,
+        }
+    }
+
+    /// Sets the [`DebugShadingMode`]
+    pub fn set_debug_shading_mode(&self, debug_shading_mode: DebugShadingMode) {
+        self.presenter_shared.lock().debug_shading_mode = debug_shading_mode;
+    }
+
+    /// Enables or disables the eye-dome lighting
+    pub fn set_eye_dome_lighting(&self, enabled: bool) {
+        self.presenter_shared.lock().eye_dome_lighting = enabled;
+    }
+
+    /// Sets the color of the outline around the selected rigid mesh instances
+    pub fn set_selection_outline_color(&self, color: ByteColor4) {
+        self.presenter_shared.lock().selection_outline_color = color;
+    }
+
+    /// Sets the maximum number of points that are rendered for the point clouds. `None` removes the limit.
+    pub fn set_maximum_rendered_points(&self, maximum_rendered_points: Option<usize>) {
+        self.presenter_shared.lock().maximum_rendered_points = maximum_rendered_points;
+    }
+
+    /// Adds a [`CustomPass`] that is recorded after the passes that have been added before at the same position
+    pub fn add_custom_pass(&self, id: CustomPassId, position: CustomPassPosition, custom_pass: Arc<dyn CustomPass>) {
+        self.presenter_shared
+            .lock()
+            .custom_passes
+            .push(Arc::new(RegisteredCustomPass { id, position, custom_pass }));
+    }
+
+    /// Adds a [`UserComputePass`] that is dispatched after the user compute passes that have been added before at the same position
+    pub fn add_user_compute_pass(&self, id: CustomPassId, user_compute_pass: UserComputePass) {
+        self.presenter_shared
+            .lock()
+            .user_compute_passes
+            .push(RegisteredUserComputePass { id, user_compute_pass });
+    }
+
+    /// Adds a [`PostProcessingPass`] that is rendered after the post-processing passes that have been added before
+    pub fn add_post_processing_pass(&self, id: CustomPassId, post_processing_pass: PostProcessingPass) {
+        self.presenter_shared
+            .lock()
+            .post_processing_passes
+            .push(RegisteredPostProcessingPass { id, post_processing_pass });
+    }
+
+    /// Removes the [`CustomPass`], [`UserComputePass`] or [`PostProcessingPass`] and returns whether it was found
+    pub fn remove_custom_pass(&self, id: CustomPassId) -> bool {
+        let mut presenter_shared = self.presenter_shared.lock();
+        let pass_count = |presenter_shared: &PresenterShared| {
+            presenter_shared.custom_passes.len()
+                + presenter_shared.user_compute_passes.len()
+                + presenter_shared.post_processing_passes.len()
+        };
+        let len = pass_count(&presenter_shared);
+        presenter_shared
+            .custom_passes
+            .retain(|registered_custom_pass| registered_custom_pass.id != id);
+        presenter_shared
+            .user_compute_passes
+            .retain(|registered_user_compute_pass| registered_user_compute_pass.id != id);
+        presenter_shared
+            .post_processing_passes
+            .retain(|registered_post_processing_pass| registered_post_processing_pass.id != id);
+        pass_count(&presenter_shared) != len
+    }
+
+    /// Enables or disables the [`PerformanceHud`]. The recorded samples are discarded when it's disabled.
+    pub fn set_performance_hud(&self, enabled: bool) {
+        let mut presenter_shared = self.presenter_shared.lock();
+        match (enabled, &presenter_shared.performance_hud) {
+            (true, None) => presenter_shared.performance_hud = Some(PerformanceHud::default()),
+            (false, Some(_)) => presenter_shared.performance_hud = None,
+            _ => {}
+        }
+    }
+
+    /// Starts capturing the presented frames and replaces the capture that is running
+    pub fn start_frame_capture(&self, config: FrameCaptureConfig, backend_shared: &BackendShared) -> crate::Result<FrameCaptureHandle> {
+        let mut presenter_shared = self.presenter_shared.lock();
+        let swapchain = &presenter_shared.swapchain;
+        if !swapchain.supports_capture() || !is_capturable_format(swapchain.surface_format().format) {
+            return Err(crate::Error::FrameCaptureNotSupported);
+        }
+        // The capture continues from the current time so that motions don't jump when a capture replaces another one
+        let start_time = presenter_shared.time(backend_shared);
+        let (frame_capture, frame_capture_handle) = FrameCapture::start(config, start_time);
+        presenter_shared.frame_capture = Some(frame_capture);
+        Ok(frame_capture_handle)
+    }
+
+    /// Stops capturing the presented frames. The frames that have already been captured are still written.
+    pub fn stop_frame_capture(&self) {
+        self.presenter_shared.lock().frame_capture = None;
+    }
+}
+
+/// Paces the frames of a presenter thread according to its [`FrameRate`]
+struct FramePacing {
+    frame_rate: FrameRate,
+    /// Only used for [`FrameRate::Adaptive`]
+    adaptive_frame_rate: Option<AdaptiveFrameRate>,
+    /// `None` when the frame rate is unlimited
+    interval: Option<spin_sleep_util::Interval>,
+}
+
+impl FramePacing {
+    /// Creates a new `FramePacing`. Returns an error when the range of a [`FrameRate::Adaptive`] is invalid.
+    fn new(frame_rate: FrameRate) -> crate::Result<Self> {
+        let adaptive_frame_rate = match frame_rate {
+            FrameRate::Adaptive { min, max } => Some(AdaptiveFrameRate::new(min, max)?),
+            FrameRate::Limited(_) | FrameRate::Unlimited => None,
+        };
+        let interval = match frame_rate {
+            FrameRate::Limited(frame_rate) => Some(spin_sleep_util::interval(Duration::from_secs_f32(1.0 / frame_rate as f32))),
+            FrameRate::Adaptive { .. } => adaptive_frame_rate
+                .as_ref()
+                .map(|adaptive_frame_rate| spin_sleep_util::interval(adaptive_frame_rate.period())),
+            FrameRate::Unlimited => None,
+        };
+        Ok(Self {
+            frame_rate,
+            adaptive_frame_rate,
+            interval,
+        })
+    }
+
+    /// Adds the GPU time of a frame to the adaptive frame rate and recreates the interval when its period changed
+    fn update(&mut self, presenter_index: usize, frame_timings: &FrameTimings) {
+        let Some(adaptive_frame_rate) = &mut self.adaptive_frame_rate else {
+            return;
+        };
+        if let Some(period) = adaptive_frame_rate.update(frame_timings.total()) {
+            plot_with_index!("adaptive_frame_rate_on_presenter_", presenter_index, 1.0 / period.as_secs_f64());
+            self.interval = Some(spin_sleep_util::interval(period));
+        }
+    }
+
+    /// Waits until the next frame is due. Returns `false` without waiting when the frame rate is unlimited.
+    fn tick(&mut self) -> bool {
+        match &mut self.interval {
+            Some(interval) => {
+                interval.tick();
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 fn run_presenter_thread(
@@ -102,8 +392,9 @@ fn run_presenter_thread(
     backend_shared: Arc<BackendShared>,
     presenter_shared: Arc<Mutex<PresenterShared>>,
     window_id: WindowId,
-    frame_rate: FrameRate,
+    mut frame_pacing: FramePacing,
     event_queue: Arc<Mutex<EventQueue<PresenterEvent>>>,
+    resize_callback: Arc<Mutex<Option<ResizeCallback>>>,
 ) -> jeriya_backend::Result<()> {
     // Setup Tracy profiling
     #[rustfmt::skip]
@@ -133,19 +424,25 @@ fn run_presenter_thread(
     // loop is still in the same iteration in case the rendering happens at a higher frame rate compared
     // to the update loop.
     let mut immediate_rendering_frames = BTreeMap::<&'static str, ImmediateRenderingFrameTask>::new();
+    let performance_hud_frame = ImmediateRenderingFrame::new(PERFORMANCE_HUD_UPDATE_LOOP_NAME, 0, Timeout::Infinite);
 
-    let mut interval = match frame_rate {
-        FrameRate::Limited(frame_rate) => Some(spin_sleep_util::interval(Duration::from_secs_f32(1.0 / frame_rate as f32))),
-        FrameRate::Unlimited => None,
-    };
+    // The upload bandwidth is measured between the starts of two consecutive frames
+    let mut previous_frame_start = Instant::now();
+    let mut previous_uploaded_bytes = backend_shared.device.uploaded_bytes();
 
-    info!("Starting presenter loop with frame rate: {:?}", frame_rate);
+    info!("Starting presenter loop with frame rate: {:?}", frame_pacing.frame_rate);
     loop {
+        let frame_start = Instant::now();
         let mut presenter_shared = presenter_shared.lock();
 
         // Set the swapchain index to None to indicate that the swapchain image is not yet determined
         presenter_shared.frame_index.set_swapchain_index(None);
 
+        // The frame is in flight before it receives the transactions so that the resources which are removed
+        // by them aren't released while the frame might still read them
+        let frame_index = presenter_shared.frame_index.index();
+        backend_shared.frames_in_flight.lock().start_frame(presenter_index, frame_index);
+
         backend_shared
             .resource_event_sender
             .send(ResourceEvent::FrameStart)
@@ -167,41 +464,125 @@ fn run_presenter_thread(
             &mut immediate_rendering_frames,
             &mut persistent_frame_states,
             &mut presenter_shared,
+            &backend_shared,
         )?;
 
+        // The performance HUD shows the samples up to the previous frame
+        match &presenter_shared.performance_hud {
+            Some(performance_hud) => {
+                immediate_rendering_frames.insert(
+                    PERFORMANCE_HUD_UPDATE_LOOP_NAME,
+                    ImmediateRenderingFrameTask {
+                        start_time: frame_start,
+                        is_timed_out: false,
+                        immediate_rendering_frame: performance_hud_frame.clone(),
+                        command_buffers: Vec::new(),
+                        overlay_command_buffers: vec![performance_hud.overlay_command_buffer()?],
+                    },
+                );
+            }
+            None => {
+                immediate_rendering_frames.remove(PERFORMANCE_HUD_UPDATE_LOOP_NAME);
+            }
+        }
+
         // Finish command buffer execution
         let mut queues = backend_shared.queue_scheduler.queues();
         queues.presentation_queue(window_id).poll_completed_fences()?;
         drop(queues);
 
+        // The swapchain is recreated before the frame graph is compiled so that the pipelines match its extent
+        let swapchain_preparation = presenter_shared.prepare_swapchain(&backend_shared)?;
+
         // Render the frame
-        match CompiledFrameGraph::new(&mut presenter_shared) {
-            Ok(compiled_frame_graph) => {
-                render_frame(
-                    compiled_frame_graph,
-                    &window_id,
-                    &mut compiled_frame_graphs,
-                    &mut immediate_rendering_frames,
-                    &mut persistent_frame_states,
-                    &mut presenter_shared,
-                    &backend_shared,
-                )?;
-            }
-            Err(err) => {
-                trace!("Failed to compile frame graph: {err:?}");
+        let mut submitted_frame = None;
+        if swapchain_preparation != SwapchainPreparation::Empty {
+            match CompiledFrameGraph::new(&mut presenter_shared) {
+                Ok(compiled_frame_graph) => {
+                    submitted_frame = render_frame(
+                        compiled_frame_graph,
+                        &window_id,
+                        &mut compiled_frame_graphs,
+                        &mut immediate_rendering_frames,
+                        &mut persistent_frame_states,
+                        &mut presenter_shared,
+                        &backend_shared,
+                    )?;
+                }
+                Err(err) => {
+                    trace!("Failed to compile frame graph: {err:?}");
+                }
             }
+
+            presenter_shared.frame_index.increment();
+        }
+        if submitted_frame.is_none() {
+            backend_shared.frames_in_flight.lock().skip_frame(presenter_index, frame_index);
         }
 
-        presenter_shared.frame_index.increment();
+        // Every frame in flight applies the transactions when it's rendered next
+        presenter_shared.pending_transactions = persistent_frame_states
+            .iter()
+            .map(|persistent_frame_state| persistent_frame_state.transactions.len())
+            .max()
+            .unwrap_or(0);
+
+        // Only the timings that were collected in this frame are added so that the same sample isn't counted again
+        // in skipped frames
+        if let Some(frame_timings) = submitted_frame.as_ref().and_then(|frame| frame.frame_timings.as_ref()) {
+            frame_pacing.update(presenter_index, frame_timings);
+        }
 
+        // The time that is spent waiting for the previous frame in the same swapchain position is not CPU time
+        let cpu_frame_start = submitted_frame.as_ref().map_or(frame_start, |frame| frame.wait_end);
+        let cpu_frame_time = cpu_frame_start.elapsed();
+        presenter_shared.cpu_frame_time = Some(cpu_frame_time);
+
+        let uploaded_bytes = backend_shared.device.uploaded_bytes();
+        let upload_interval = frame_start.duration_since(previous_frame_start).as_secs_f64();
+        let upload_bandwidth = if upload_interval > 0.0 {
+            (uploaded_bytes - previous_uploaded_bytes) as f64 / upload_interval
+        } else {
+            0.0
+        };
+        previous_frame_start = frame_start;
+        previous_uploaded_bytes = uploaded_bytes;
+
+        let visible_meshlets = presenter_shared
+            .frame_telemetry
+            .as_ref()
+            .map_or(0, |frame_telemetry| frame_telemetry.visible_rigid_mesh_meshlets);
+        if let Some(performance_hud) = &mut presenter_shared.performance_hud {
+            performance_hud.push_sample(PerformanceSample {
+                frame_time: cpu_frame_time,
+                visible_meshlets,
+                upload_bandwidth,
+            });
+        }
         drop(presenter_shared);
 
-        if let Some(interval) = &mut interval {
-            interval.tick();
+        // The callback is called without the lock so that it can call into the renderer
+        if let SwapchainPreparation::Resized(extent) = swapchain_preparation {
+            if let Some(resize_callback) = resize_callback.lock().as_mut() {
+                resize_callback(extent.width, extent.height);
+            }
+        }
+
+        if !frame_pacing.tick() && swapchain_preparation == SwapchainPreparation::Empty {
+            thread::sleep(EMPTY_PRESENTATION_TARGET_POLL_INTERVAL);
         }
     }
 }
 
+/// Frame that was submitted by [`render_frame`]
+struct SubmittedFrame {
+    /// Time at which the wait for the frame that was previously rendered in the same swapchain position ended
+    wait_end: Instant,
+    /// Timings that were collected from the frame that was previously rendered in the same swapchain position
+    frame_timings: Option<FrameTimings>,
+}
+
+/// Renders and presents the frame. Returns `None` when the frame was skipped.
 fn render_frame(
     mut compiled_frame_graph: CompiledFrameGraph,
     window_id: &WindowId,
@@ -210,23 +591,34 @@ fn render_frame(
     persistent_frame_states: &mut SwapchainVec<PersistentFrameState>,
     presenter_shared: &mut PresenterShared,
     backend_shared: &BackendShared,
-) -> jeriya_backend::Result<()> {
+) -> jeriya_backend::Result<Option<SubmittedFrame>> {
     // Setup synchronization primitives for the next frame
     let image_available_semaphore = Semaphore::new(&backend_shared.device, debug_info!("image-available-Semaphore"))?;
     let rendering_complete_semaphore = Semaphore::new(&backend_shared.device, debug_info!("rendering-complete-Semaphore"))?;
     let rendering_complete_fence = Fence::new(&backend_shared.device, debug_info!("rendering-complete-Fence"))?;
 
     // Acquire the next swapchain image
+    //
+    // When the swapchain is out of date, the frame is skipped because the frame graph has been compiled for the extent
+    // of the current swapchain. A suboptimal swapchain can still be rendered to and is recreated in the next frame.
     let acquire_span = jeriya_shared::span!("acquire swapchain image");
-    let swapchain_image_index = loop {
-        match presenter_shared.swapchain.acquire_next_image(&image_available_semaphore) {
-            Ok(index) => break index,
-            Err(_) => {
-                info!("Failed to acquire next swapchain image. Recreating swapchain.");
-                presenter_shared.recreate(&backend_shared)?;
-            }
+    let swapchain_image_index = match presenter_shared.swapchain.acquire_next_image(&image_available_semaphore) {
+        Ok((index, is_suboptimal)) => {
+            presenter_shared.swapchain_outdated |= is_suboptimal;
+            index
         }
+        Err(err) if err.is_swapchain_out_of_date() => {
+            info!("Swapchain is out of date. Recreating swapchain in the next frame.");
+            presenter_shared.swapchain_outdated = true;
+            return Ok(None);
+        }
+        Err(err) => return Err(err.into()),
     };
+    if presenter_shared.swapchain.is_offscreen() {
+        // The offscreen images are available immediately but the rendering waits for the semaphore
+        let mut queues = backend_shared.queue_scheduler.queues();
+        queues.presentation_queue(*window_id).signal_semaphore(&image_available_semaphore)?;
+    }
     presenter_shared.frame_index.set_swapchain_index(swapchain_image_index as usize);
     drop(acquire_span);
 
@@ -235,6 +627,64 @@ fn render_frame(
     let wait_span = jeriya_shared::span!("wait for rendering complete");
     persistent_frame_state.rendering_complete_fence.wait()?;
     drop(wait_span);
+    let wait_end = Instant::now();
+
+    // The frame that was previously rendered in this position and the ones before it have finished
+    if let Some(submitted_frame_index) = persistent_frame_state.submitted_frame_index.take() {
+        backend_shared
+            .frames_in_flight
+            .lock()
+            .complete_frame(persistent_frame_state.presenter_index, submitted_frame_index);
+    }
+
+    // The data of the readbacks that were recorded in the previous frame in this position is available now
+    persistent_frame_state.finish_pending_readbacks()?;
+
+    // The same applies to the timestamps of the passes
+    let timestamp_period = backend_shared
+        .device
+        .physical_device
+        .physical_device_properties
+        .limits
+        .timestamp_period;
+    let frame_timings = persistent_frame_state.collect_frame_timings(timestamp_period)?;
+    if let Some(frame_timings) = &frame_timings {
+        presenter_shared
+            .frame_timings_plotter
+            .plot(persistent_frame_state.presenter_index, frame_timings);
+        presenter_shared.frame_timings = Some(frame_timings.clone());
+    }
+    if let Some(pipeline_statistics) = persistent_frame_state.pipeline_statistics()? {
+        let presenter_index = persistent_frame_state.presenter_index;
+        plot_with_index!(
+            "vertex_shader_invocations_on_presenter_",
+            presenter_index,
+            pipeline_statistics.vertex_shader_invocations as f64
+        );
+        plot_with_index!(
+            "fragment_shader_invocations_on_presenter_",
+            presenter_index,
+            pipeline_statistics.fragment_shader_invocations as f64
+        );
+        plot_with_index!(
+            "compute_shader_invocations_on_presenter_",
+            presenter_index,
+            pipeline_statistics.compute_shader_invocations as f64
+        );
+    }
+
+    if let Some(frame_telemetry) = persistent_frame_state.frame_telemetry()? {
+        presenter_shared.frame_telemetry = Some(frame_telemetry);
+    }
+
+    // Hand the page requests of the point cloud cluster culling to the streaming
+    let point_cloud_page_requests = persistent_frame_state.point_cloud_page_requests()?;
+    if !point_cloud_page_requests.is_empty() {
+        backend_shared
+            .point_cloud_page_streaming
+            .lock()
+            .push_requests(&point_cloud_page_requests);
+    }
 
     // Process Transactions which update the persistent frame state
     persistent_frame_state.process_transactions()?;
@@ -258,6 +708,10 @@ fn render_frame(
         presenter_shared,
         immediate_rendering_frames,
     )?;
+    persistent_frame_state.submitted_frame_index = Some(presenter_shared.frame_index.index());
+    if let Some(frame_capture) = &mut presenter_shared.frame_capture {
+        frame_capture.next_frame();
+    }
     // Set the compiled frame graph for the current frame
     assert!(compiled_frame_graphs.get(&presenter_shared.frame_index).is_none());
     compiled_frame_graphs
@@ -271,29 +725,55 @@ fn render_frame(
         &persistent_frame_state.rendering_complete_semaphore,
         queues.presentation_queue(*window_id),
     );
-    // The queues must be dropped before `recreate` is called to prevent a deadlock.
+    // The queues must be dropped before the swapchain is recreated to prevent a deadlock.
     drop(queues);
     match result {
-        Ok(is_suboptimal) => {
-            if is_suboptimal {
-                info!("Swapchain is suboptimal. Recreating swapchain.");
-                presenter_shared.recreate(&backend_shared)?;
-            }
+        Ok(false) => {}
+        Ok(true) => {
+            info!("Swapchain is suboptimal. Recreating swapchain in the next frame.");
+            presenter_shared.swapchain_outdated = true;
         }
-        Err(_err) => {
-            info!("Failed to present swapchain image. Recreating swapchain.");
-            presenter_shared.recreate(&backend_shared)?;
+        Err(err) if err.is_swapchain_out_of_date() => {
+            info!("Swapchain is out of date. Recreating swapchain in the next frame.");
+            presenter_shared.swapchain_outdated = true;
         }
+        Err(err) => return Err(err.into()),
     }
 
-    Ok(())
+    Ok(Some(SubmittedFrame { wait_end, frame_timings }))
+}
+
+/// Returns the [`ImmediateRenderingFrameTask`] of the update loop of the `immediate_rendering_frame`
+///
+/// The task is replaced when the `immediate_rendering_frame` is newer than the one that is already rendering.
+fn immediate_rendering_frame_task<'a>(
+    immediate_rendering_frames: &'a mut BTreeMap<&'static str, ImmediateRenderingFrameTask>,
+    immediate_rendering_frame: &ImmediateRenderingFrame,
+) -> &'a mut ImmediateRenderingFrameTask {
+    let update_loop_name = immediate_rendering_frame.update_loop_name();
+    let is_newer = immediate_rendering_frames
+        .get(update_loop_name)
+        .is_some_and(|task| immediate_rendering_frame.index() > task.immediate_rendering_frame.index());
+    if is_newer {
+        immediate_rendering_frames.remove(update_loop_name);
+    }
+    immediate_rendering_frames
+        .entry(update_loop_name)
+        .or_insert_with(|| ImmediateRenderingFrameTask {
+            start_time: Instant::now(),
+            is_timed_out: false,
+            immediate_rendering_frame: immediate_rendering_frame.clone(),
+            command_buffers: Vec::new(),
+            overlay_command_buffers: Vec::new(),
+        })
 }
 
 fn handle_events(
     event_queue: &Arc<Mutex<EventQueue<PresenterEvent>>>,
-    immediate_rendering_frames: &mut BTreeMap<&str, ImmediateRenderingFrameTask>,
+    immediate_rendering_frames: &mut BTreeMap<&'static str, ImmediateRenderingFrameTask>,
     persistent_frame_states: &mut SwapchainVec<PersistentFrameState>,
     presenter_shared: &mut PresenterShared,
+    backend_shared: &BackendShared,
 ) -> Result<(), jeriya_backend::Error> {
     let mut event_queue = event_queue.lock().take();
     Ok(while let Some(new_events) = event_queue.pop() {
@@ -302,41 +782,53 @@ fn handle_events(
                 command_buffer,
                 immediate_rendering_frame,
             } => {
-                // Check if the newly received immediate rendering frame is newer than the one that is already rendering
-                let mut remove_frame = false;
-                if let Some(task) = immediate_rendering_frames.get_mut(&immediate_rendering_frame.update_loop_name()) {
-                    if immediate_rendering_frame.index() > task.immediate_rendering_frame.index() {
-                        remove_frame = true;
-                    }
-                }
-                if remove_frame {
-                    immediate_rendering_frames.remove(&immediate_rendering_frame.update_loop_name());
-                }
-
-                // Insert the newly received command buffer
-                if let Some(task) = immediate_rendering_frames.get_mut(&immediate_rendering_frame.update_loop_name()) {
-                    task.command_buffers.push(command_buffer);
-                } else {
-                    let task = ImmediateRenderingFrameTask {
-                        start_time: Instant::now(),
-                        is_timed_out: false,
-                        immediate_rendering_frame: immediate_rendering_frame.clone(),
-                        command_buffers: vec![command_buffer],
-                    };
-                    immediate_rendering_frames.insert(immediate_rendering_frame.update_loop_name(), task);
-                }
+                immediate_rendering_frame_task(immediate_rendering_frames, &immediate_rendering_frame)
+                    .command_buffers
+                    .push(command_buffer);
             }
+            PresenterEvent::RenderOverlayCommandBuffer {
+                command_buffer,
+                immediate_rendering_frame,
+            } => {
+                immediate_rendering_frame_task(immediate_rendering_frames, &immediate_rendering_frame)
+                    .overlay_command_buffers
+                    .push(command_buffer);
+            }
+            PresenterEvent::RenderUiFrame(ui_frame) => presenter_shared.user_interface.push_frame(ui_frame)?,
             PresenterEvent::ProcessTransaction(transaction) => {
+                // Deactivate the active camera and remove the viewports and render targets when their CameraInstance is removed
+                for event in &transaction {
+                    if let transactions::Event::CameraInstance(camera_instance::Event::Remove(gpu_index_allocation)) = event {
+                        if presenter_shared.active_camera_instance == Some(*gpu_index_allocation) {
+                            presenter_shared.active_camera_instance = None;
+                        }
+                        presenter_shared
+                            .viewports
+                            .retain(|viewport| viewport.camera_instance() != gpu_index_allocation);
+                        presenter_shared
+                            .vulkan_resource_coordinator
+                            .remove_render_targets_of_camera_instance(gpu_index_allocation);
+                    }
+                }
+                // All frames start the motions of the transaction at the same time
+                let time = presenter_shared.time(backend_shared);
                 let len = persistent_frame_states.len();
                 for (index, frame) in persistent_frame_states.iter_mut().enumerate() {
                     if index == len - 1 {
-                        frame.push_transaction(transaction);
+                        frame.push_transaction(transaction, time);
                         break;
                     }
-                    frame.push_transaction(transaction.clone());
+                    frame.push_transaction(transaction.clone(), time);
                 }
             }
             PresenterEvent::ShaderImported(shader_asset) => presenter_shared.vulkan_resource_coordinator.update_shader(shader_asset)?,
+            PresenterEvent::Readback(readback_request) => presenter_shared.readback_requests.push(readback_request),
+            PresenterEvent::ReadDepthBuffer(depth_readback_request) => {
+                presenter_shared.depth_readback_requests.push(depth_readback_request);
+            }
+            PresenterEvent::ReadRenderTarget { render_target_id, sender } => {
+                presenter_shared.render_target_readbacks.push((render_target_id, sender));
+            }
         }
     })
 }
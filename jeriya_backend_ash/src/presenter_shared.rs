@@ -1,32 +1,179 @@
-use std::sync::Arc;
+use std::{
+    sync::{mpsc::SyncSender, Arc},
+    time::Duration,
+};
+
+use ash::vk;
 
 use crate::{
-    backend_shared::BackendShared, device::Device, frame_index::FrameIndex, surface::Surface, swapchain::Swapchain,
+    backend_shared::BackendShared,
+    custom_pass::RegisteredCustomPass,
+    device::Device,
+    frame_capture::{CapturedFrame, FrameCapture},
+    frame_index::FrameIndex,
+    frame_telemetry::FrameTelemetry,
+    frame_timings::{FrameTimings, FrameTimingsPlotter},
+    immediate_textures::ImmediateTextures,
+    immediate_vertex_buffers::ImmediateVertexBufferCache,
+    post_processing_pass::RegisteredPostProcessingPass,
+    readback::{DepthReadbackRequest, ReadbackRequest},
+    surface::Surface,
+    swapchain::Swapchain,
+    user_compute_pass::RegisteredUserComputePass,
+    user_interface::UserInterface,
     vulkan_resource_coordinator::VulkanResourceCoordinator,
 };
-use jeriya_backend::{gpu_index_allocator::GpuIndexAllocation, instances::camera_instance::CameraInstance};
-use jeriya_shared::winit::window::WindowId;
+use jeriya_backend::{
+    clip_plane::ClipPlane, debug::DebugShadingMode, gpu_index_allocator::GpuIndexAllocation, instances::camera_instance::CameraInstance,
+    performance_hud::PerformanceHud, render_target::RenderTargetId, viewport::Viewport,
+};
+use jeriya_shared::{log::info, winit::window::WindowId, ByteColor4, ColorSpace, PresentMode, WindowMode};
+
+/// Target into which a presenter renders its frames
+#[derive(Clone)]
+pub enum PresentationTarget {
+    /// The frames are presented to the [`Surface`] of a window
+    Surface(Arc<Surface>),
+    /// The frames are rendered into offscreen images of the given extent
+    Offscreen(vk::Extent2D),
+}
+
+impl PresentationTarget {
+    /// Creates a [`Swapchain`] for the `PresentationTarget`
+    fn create_swapchain(
+        &self,
+        device: &Arc<Device>,
+        desired_swapchain_length: u32,
+        previous_swapchain: Option<&Swapchain>,
+        present_mode: PresentMode,
+        color_space: ColorSpace,
+    ) -> crate::Result<Swapchain> {
+        match self {
+            PresentationTarget::Surface(surface) => Swapchain::with_preferences(
+                device,
+                surface,
+                desired_swapchain_length,
+                previous_swapchain,
+                preferred_present_modes(present_mode),
+                color_space,
+            ),
+            PresentationTarget::Offscreen(extent) => Swapchain::new_offscreen(device, *extent, desired_swapchain_length),
+        }
+    }
+
+    /// Returns the extent that a new [`Swapchain`] would have
+    fn current_extent(&self, device: &Device) -> crate::Result<vk::Extent2D> {
+        match self {
+            PresentationTarget::Surface(surface) => surface.current_extent(&device.physical_device),
+            PresentationTarget::Offscreen(extent) => Ok(*extent),
+        }
+    }
+}
+
+/// Returns the present modes that are preferred for the [`PresentMode`] in the order of preference
+///
+/// The [`Swapchain`] falls back to `FIFO` when none of them is supported. Only `Immediate` accepts tearing, so `Mailbox`
+/// never falls back to `IMMEDIATE` regardless of the [`WindowMode`].
+fn preferred_present_modes(present_mode: PresentMode) -> &'static [vk::PresentModeKHR] {
+    match present_mode {
+        PresentMode::Fifo => &[vk::PresentModeKHR::FIFO],
+        PresentMode::Mailbox => &[vk::PresentModeKHR::MAILBOX],
+        PresentMode::Immediate => &[vk::PresentModeKHR::IMMEDIATE, vk::PresentModeKHR::MAILBOX],
+    }
+}
+
+/// Result of [`PresenterShared::prepare_swapchain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapchainPreparation {
+    /// The swapchain can be rendered to
+    Ready,
+    /// The swapchain has been recreated with a new extent and can be rendered to
+    Resized(vk::Extent2D),
+    /// Nothing can be rendered because the presentation target is empty, e.g. while the window is minimized
+    Empty,
+}
 
-/// All the state that is required for presenting to the [`Surface`]
+/// All the state that is required for presenting to the [`PresentationTarget`]
 pub struct PresenterShared {
     pub window_id: WindowId,
     pub frame_index: FrameIndex,
     pub desired_swapchain_length: u32,
-    pub surface: Arc<Surface>,
+    pub presentation_target: PresentationTarget,
     pub swapchain: Swapchain,
+    /// Whether the swapchain has to be recreated before the next frame is rendered
+    pub swapchain_outdated: bool,
+    /// Present mode that has been requested for the window
+    pub present_mode: PresentMode,
+    /// Mode of the window. The swapchain is recreated when it changes.
+    pub window_mode: WindowMode,
+    /// Color space that has been requested for the window. The swapchain falls back to SDR when it's not supported.
+    pub color_space: ColorSpace,
     pub vulkan_resource_coordinator: VulkanResourceCoordinator,
     pub active_camera_instance: Option<GpuIndexAllocation<CameraInstance>>,
+    /// Viewports into which the scene is rendered. When empty, the scene is rendered into the whole window from the active camera.
+    pub viewports: Vec<Viewport>,
+    /// Planes that clip the scene. At most `MAX_CLIP_PLANES` planes are written into the `PerFrameData`.
+    pub clip_planes: Vec<ClipPlane>,
+    /// Readbacks that will be recorded in the next frame
+    pub readback_requests: Vec<ReadbackRequest>,
+    /// Copies of the depth buffer that will be recorded in the next frame
+    pub depth_readback_requests: Vec<DepthReadbackRequest>,
+    /// Copies of the render targets that will be recorded in the next frame
+    pub render_target_readbacks: Vec<(RenderTargetId, SyncSender<CapturedFrame>)>,
+    /// Whether the bounding volumes are rendered as debug lines
+    pub debug_bounding_volumes: bool,
+    pub debug_shading_mode: DebugShadingMode,
+    /// Whether the eye-dome lighting is applied after the scene has been rendered
+    pub eye_dome_lighting: bool,
+    /// Color of the outline around the selected rigid mesh instances
+    pub selection_outline_color: ByteColor4,
+    /// Maximum number of points that are rendered for the point clouds. `None` means that there is no limit.
+    pub maximum_rendered_points: Option<usize>,
+    /// GPU times of the passes of the most recently completed frame
+    pub frame_timings: Option<FrameTimings>,
+    pub frame_timings_plotter: FrameTimingsPlotter,
+    /// Culling results of the most recently completed frame
+    pub frame_telemetry: Option<FrameTelemetry>,
+    /// Time that the presenter thread spent on the most recent frame without waiting for the frame rate limit and the
+    /// previous frame in the same swapchain position
+    pub cpu_frame_time: Option<Duration>,
+    /// Number of transactions that are not yet applied to all frames in flight
+    pub pending_transactions: usize,
+    /// Performance graphs that are rendered on top of the overlay when enabled
+    pub performance_hud: Option<PerformanceHud>,
+    /// Passes of the application that are recorded in the order in which they have been added
+    pub custom_passes: Vec<Arc<RegisteredCustomPass>>,
+    /// Compute shaders of the application that are dispatched after the custom passes at the same position
+    pub user_compute_passes: Vec<RegisteredUserComputePass>,
+    /// Fullscreen passes of the application that are rendered in the order in which they have been added
+    pub post_processing_passes: Vec<RegisteredPostProcessingPass>,
+    /// Font atlases and overlay images that have been uploaded for the immediate rendering and the overlay
+    pub immediate_textures: ImmediateTextures,
+    /// Vertex buffers of the immediate command buffers that are kept while the command buffers are rendered
+    pub immediate_vertex_buffers: ImmediateVertexBufferCache,
+    /// User interface that is rendered on top of the overlay
+    pub user_interface: UserInterface,
+    /// Capture of the presented frames that is running
+    pub frame_capture: Option<FrameCapture>,
     pub device: Arc<Device>,
 }
 
 impl PresenterShared {
-    /// Creates a new `Presenter` for the [`Surface`]
-    pub fn new(window_id: &WindowId, backend_shared: &BackendShared, surface: &Arc<Surface>) -> jeriya_backend::Result<Self> {
+    /// Creates a new `Presenter` for the [`PresentationTarget`]
+    pub fn new(
+        window_id: &WindowId,
+        backend_shared: &BackendShared,
+        presentation_target: PresentationTarget,
+        present_mode: PresentMode,
+        color_space: ColorSpace,
+    ) -> jeriya_backend::Result<Self> {
         let desired_swapchain_length = backend_shared.renderer_config.default_desired_swapchain_length;
-        let swapchain = Swapchain::new(&backend_shared.device, surface, desired_swapchain_length, None)?;
+        let swapchain =
+            presentation_target.create_swapchain(&backend_shared.device, desired_swapchain_length, None, present_mode, color_space)?;
 
         let vulkan_resource_coordinator = VulkanResourceCoordinator::new(
             &backend_shared.device,
+            &backend_shared.pipeline_cache,
             &backend_shared.asset_importer,
             &swapchain,
             &backend_shared.renderer_config,
@@ -36,14 +183,75 @@ impl PresenterShared {
             window_id: *window_id,
             frame_index: FrameIndex::new(),
             desired_swapchain_length,
-            surface: surface.clone(),
+            presentation_target,
             swapchain,
+            swapchain_outdated: false,
+            present_mode,
+            window_mode: WindowMode::default(),
+            color_space,
             vulkan_resource_coordinator,
             active_camera_instance: None,
+            viewports: Vec::new(),
+            clip_planes: Vec::new(),
+            readback_requests: Vec::new(),
+            depth_readback_requests: Vec::new(),
+            render_target_readbacks: Vec::new(),
+            debug_bounding_volumes: false,
+            debug_shading_mode: DebugShadingMode::default(),
+            eye_dome_lighting: false,
+            selection_outline_color: ByteColor4::new(255, 160, 0, 255),
+            maximum_rendered_points: None,
+            frame_timings: None,
+            frame_timings_plotter: FrameTimingsPlotter::default(),
+            frame_telemetry: None,
+            cpu_frame_time: None,
+            pending_transactions: 0,
+            performance_hud: None,
+            custom_passes: Vec::new(),
+            user_compute_passes: Vec::new(),
+            post_processing_passes: Vec::new(),
+            immediate_textures: ImmediateTextures::default(),
+            immediate_vertex_buffers: ImmediateVertexBufferCache::default(),
+            user_interface: UserInterface::default(),
+            frame_capture: None,
             device: backend_shared.device.clone(),
         })
     }
 
+    /// Returns the time of the renderer for the current frame
+    ///
+    /// While frames are captured with a fixed time step, the time advances by that step per presented frame instead of
+    /// following the wall clock.
+    pub fn time(&self, backend_shared: &BackendShared) -> f32 {
+        let wall_time = backend_shared.time();
+        match &self.frame_capture {
+            Some(frame_capture) => frame_capture.time(wall_time),
+            None => wall_time,
+        }
+    }
+
+    /// Recreates the swapchain when it's outdated or when its extent doesn't match the presentation target anymore
+    ///
+    /// Must be called before the frame graph is compiled so that the pipelines are created for the extent of the swapchain.
+    pub fn prepare_swapchain(&mut self, backend_shared: &BackendShared) -> crate::Result<SwapchainPreparation> {
+        let current_extent = self.presentation_target.current_extent(&self.device)?;
+        if current_extent.width == 0 || current_extent.height == 0 {
+            // A swapchain can't be created without pixels so the recreation is deferred until the window is restored
+            return Ok(SwapchainPreparation::Empty);
+        }
+        if !self.swapchain_outdated && current_extent == self.swapchain.extent() {
+            return Ok(SwapchainPreparation::Ready);
+        }
+        let previous_extent = self.swapchain.extent();
+        self.recreate(backend_shared)?;
+        if self.swapchain.extent() == previous_extent {
+            Ok(SwapchainPreparation::Ready)
+        } else {
+            info!("Swapchain resized from {previous_extent:?} to {:?}", self.swapchain.extent());
+            Ok(SwapchainPreparation::Resized(self.swapchain.extent()))
+        }
+    }
+
     /// Creates the swapchain and all state that depends on it
     pub fn recreate(&mut self, backend_shared: &BackendShared) -> crate::Result<()> {
         // Locking all the queues at once so that no thread can submit to any
@@ -51,8 +259,15 @@ impl PresenterShared {
         let _lock = backend_shared.queue_scheduler.queues();
 
         self.device.wait_for_idle()?;
-        self.swapchain = Swapchain::new(&self.device, &self.surface, self.desired_swapchain_length, Some(&self.swapchain))?;
+        self.swapchain = self.presentation_target.create_swapchain(
+            &self.device,
+            self.desired_swapchain_length,
+            Some(&self.swapchain),
+            self.present_mode,
+            self.color_space,
+        )?;
         self.vulkan_resource_coordinator.recreate(&self.swapchain)?;
+        self.swapchain_outdated = false;
 
         Ok(())
     }
@@ -60,6 +275,24 @@ impl PresenterShared {
 
 #[cfg(test)]
 mod tests {
+    mod preferred_present_modes {
+        use ash::vk;
+        use jeriya_shared::PresentMode;
+
+        use crate::presenter_shared::preferred_present_modes;
+
+        #[test]
+        fn mailbox_never_tears() {
+            assert_eq!(preferred_present_modes(PresentMode::Mailbox), &[vk::PresentModeKHR::MAILBOX]);
+        }
+
+        #[test]
+        fn requested_present_mode_comes_first() {
+            assert_eq!(preferred_present_modes(PresentMode::Fifo), &[vk::PresentModeKHR::FIFO]);
+            assert_eq!(preferred_present_modes(PresentMode::Immediate)[0], vk::PresentModeKHR::IMMEDIATE);
+        }
+    }
+
     mod new {
         use std::{
             iter,
@@ -67,11 +300,17 @@ mod tests {
         };
 
         use crate::{
-            backend_shared::BackendShared, device::Device, entry::Entry, instance::Instance, physical_device::PhysicalDevice,
-            presenter_shared::PresenterShared, queue_plan::QueuePlan, surface::Surface,
+            backend_shared::BackendShared,
+            device::Device,
+            entry::Entry,
+            instance::Instance,
+            physical_device::PhysicalDevice,
+            presenter_shared::{PresentationTarget, PresenterShared},
+            queue_plan::QueuePlan,
+            surface::Surface,
         };
         use jeriya_content::asset_importer::AssetImporter;
-        use jeriya_shared::RendererConfig;
+        use jeriya_shared::{ColorSpace, PresentMode, RendererConfig};
         use jeriya_test::create_window;
 
         #[test]
@@ -87,7 +326,78 @@ mod tests {
             let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
             let backend_shared =
                 BackendShared::new(&device, &Arc::new(RendererConfig::default()), resource_sender, &asset_importer).unwrap();
-            let _presenter = PresenterShared::new(&window.id(), &backend_shared, &surface).unwrap();
+            let _presenter = PresenterShared::new(
+                &window.id(),
+                &backend_shared,
+                PresentationTarget::Surface(surface),
+                PresentMode::default(),
+                ColorSpace::default(),
+            )
+            .unwrap();
+        }
+    }
+
+    mod prepare_swapchain {
+        use std::{
+            iter,
+            sync::{mpsc, Arc},
+        };
+
+        use ash::vk;
+
+        use crate::{
+            backend_shared::BackendShared,
+            device::Device,
+            entry::Entry,
+            instance::Instance,
+            physical_device::PhysicalDevice,
+            presenter_shared::{PresentationTarget, PresenterShared, SwapchainPreparation},
+            queue_plan::QueuePlan,
+            surface::Surface,
+        };
+        use jeriya_content::asset_importer::AssetImporter;
+        use jeriya_shared::{winit::dpi::PhysicalSize, ColorSpace, PresentMode, RendererConfig};
+        use jeriya_test::create_window;
+
+        #[test]
+        fn recreates_outdated_and_resized_swapchain() {
+            let window = create_window();
+            let entry = Entry::new().unwrap();
+            let instance = Instance::new(&entry, "my_application", false).unwrap();
+            let surface = Surface::new(&entry, &instance, &window).unwrap();
+            let physical_device = PhysicalDevice::new(&instance).unwrap();
+            let queue_plan = QueuePlan::new(&instance, &physical_device, iter::once((&window.id(), &surface))).unwrap();
+            let device = Device::new(physical_device, &instance, queue_plan).unwrap();
+            let (resource_sender, _resource_receiver) = mpsc::channel();
+            let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
+            let backend_shared =
+                BackendShared::new(&device, &Arc::new(RendererConfig::default()), resource_sender, &asset_importer).unwrap();
+            let mut presenter = PresenterShared::new(
+                &window.id(),
+                &backend_shared,
+                PresentationTarget::Surface(surface),
+                PresentMode::default(),
+                ColorSpace::default(),
+            )
+            .unwrap();
+            assert_eq!(presenter.prepare_swapchain(&backend_shared).unwrap(), SwapchainPreparation::Ready);
+
+            presenter.swapchain_outdated = true;
+            assert_eq!(presenter.prepare_swapchain(&backend_shared).unwrap(), SwapchainPreparation::Ready);
+            assert!(!presenter.swapchain_outdated);
+
+            let size = window.inner_size();
+            let new_size = PhysicalSize::new(size.width + 2, size.height + 2);
+            let _new_size = window.request_inner_size(new_size);
+            let expected_extent = vk::Extent2D {
+                width: new_size.width,
+                height: new_size.height,
+            };
+            assert_eq!(
+                presenter.prepare_swapchain(&backend_shared).unwrap(),
+                SwapchainPreparation::Resized(expected_extent)
+            );
+            assert_eq!(presenter.swapchain.extent(), expected_extent);
         }
     }
 }
@@ -1,7 +1,7 @@
 use ash::vk::{self};
 use jeriya_shared::bumpalo::Bump;
 
-use crate::{buffer::Buffer, descriptor::DescriptorType, descriptor_set_layout::DescriptorSetLayout};
+use crate::{buffer::Buffer, descriptor::DescriptorType, descriptor_set_layout::DescriptorSetLayout, sampler::Sampler, AsRawVulkan};
 
 pub struct PushDescriptorBuilder<'a> {
     descriptor_set: &'a DescriptorSetLayout,
@@ -27,8 +27,20 @@ impl<'a> PushDescriptorBuilder<'a> {
     }
 
     /// Creates a `vk::WriteDescriptorSet` for a `vk::DescriptorType::UNIFORM_BUFFER`
+    ///
+    /// The buffer is skipped when the `DescriptorSetLayout` doesn't contain the binding.
     pub fn push_uniform_buffer<T: 'static>(mut self, destination_binding: u32, buffer: &impl Buffer<T>) -> Self {
-        assert!(self.contains_typed_binding(destination_binding, DescriptorType::new_uniform_buffer::<T>()));
+        if !self.contains_binding(destination_binding) {
+            return self;
+        }
+        assert! {
+            self.contains_typed_binding(destination_binding, DescriptorType::new_uniform_buffer::<T>()),
+            "The descriptor set layout does not contain \
+                the descriptor binding {destination_binding} with \
+                the type DescriptorType::UniformBuffer(TypeId::of::<{type_name}>())",
+            destination_binding = destination_binding,
+            type_name = std::any::type_name::<T>(),
+        }
         // Must be allocated in an allocator until the write descriptor set is submitted
         let buffer_info = self.allocator.alloc(vk::DescriptorBufferInfo {
             buffer: *buffer.as_raw_vulkan(),
@@ -50,10 +62,11 @@ impl<'a> PushDescriptorBuilder<'a> {
     }
 
     /// Creates a `vk::WriteDescriptorSet` for a `vk::DescriptorType::STORAGE_BUFFER`
+    ///
+    /// The buffer is skipped when the `DescriptorSetLayout` doesn't contain the binding.
     pub fn push_storage_buffer<T: 'static>(mut self, destination_binding: u32, buffer: &impl Buffer<T>) -> Self {
-        assert! {
-            self.contains_binding(destination_binding),
-            "The descriptor set layout does not contain the descriptor binding {destination_binding}",
+        if !self.contains_binding(destination_binding) {
+            return self;
         }
         assert! {
             self.contains_typed_binding(destination_binding, DescriptorType::new_storage_buffer::<T>()),
@@ -84,6 +97,77 @@ impl<'a> PushDescriptorBuilder<'a> {
         self
     }
 
+    /// Creates a `vk::WriteDescriptorSet` of type `vk::DescriptorType::STORAGE_BUFFER` for every binding of the `buffers`
+    ///
+    /// In contrast to [`PushDescriptorBuilder::push_storage_buffer`], the element type of the buffers is not checked.
+    /// The buffers are skipped when the `DescriptorSetLayout` doesn't contain their binding.
+    pub fn push_raw_storage_buffers(mut self, buffers: &[(u32, vk::Buffer)]) -> Self {
+        for &(destination_binding, buffer) in buffers {
+            if !self.contains_binding(destination_binding) {
+                continue;
+            }
+            // Must be allocated in an allocator until the write descriptor set is submitted
+            let buffer_info = self.allocator.alloc(vk::DescriptorBufferInfo {
+                buffer,
+                offset: 0,
+                range: vk::WHOLE_SIZE,
+            });
+            let write_descriptor_set = vk::WriteDescriptorSet {
+                // Not used for push descriptors
+                dst_set: vk::DescriptorSet::null(),
+                dst_binding: destination_binding,
+                dst_array_element: 0,
+                descriptor_count: 1,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                p_buffer_info: buffer_info as *const _,
+                ..Default::default()
+            };
+            self.write_descriptor_sets.push(write_descriptor_set);
+        }
+        self
+    }
+
+    /// Creates a `vk::WriteDescriptorSet` for a `vk::DescriptorType::COMBINED_IMAGE_SAMPLER`
+    ///
+    /// The image is skipped when the `DescriptorSetLayout` doesn't contain the binding.
+    pub fn push_combined_image_sampler(
+        mut self,
+        destination_binding: u32,
+        image_view: vk::ImageView,
+        sampler: &Sampler,
+        image_layout: vk::ImageLayout,
+    ) -> Self {
+        if !self.contains_binding(destination_binding) {
+            return self;
+        }
+        assert! {
+            self.contains_typed_binding(destination_binding, DescriptorType::CombinedImageSampler),
+            "The descriptor set layout does not contain \
+                the descriptor binding {destination_binding} with \
+                the type DescriptorType::CombinedImageSampler",
+            destination_binding = destination_binding,
+        }
+
+        // Must be allocated in an allocator until the write descriptor set is submitted
+        let image_info = self.allocator.alloc(vk::DescriptorImageInfo {
+            sampler: *sampler.as_raw_vulkan(),
+            image_view,
+            image_layout,
+        });
+        let write_descriptor_set = vk::WriteDescriptorSet {
+            // Not used for push descriptors
+            dst_set: vk::DescriptorSet::null(),
+            dst_binding: destination_binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            p_image_info: image_info as *const _,
+            ..Default::default()
+        };
+        self.write_descriptor_sets.push(write_descriptor_set);
+        self
+    }
+
     pub fn build(self) -> PushDescriptors {
         PushDescriptors {
             write_descriptor_sets: self.write_descriptor_sets,
@@ -102,6 +186,20 @@ impl PushDescriptors {
         &self.write_descriptor_sets
     }
 
+    /// Returns the bindings together with the buffers that are written to them
+    pub(crate) fn buffers(&self) -> Vec<(u32, vk::Buffer)> {
+        self.write_descriptor_sets
+            .iter()
+            .filter(|write_descriptor_set| !write_descriptor_set.p_buffer_info.is_null())
+            // The buffer infos live in the allocator as long as the `PushDescriptors`
+            .map(|write_descriptor_set| {
+                (write_descriptor_set.dst_binding, unsafe {
+                    (*write_descriptor_set.p_buffer_info).buffer
+                })
+            })
+            .collect()
+    }
+
     pub fn builder(descriptor_set: &DescriptorSetLayout) -> PushDescriptorBuilder {
         PushDescriptorBuilder {
             descriptor_set,
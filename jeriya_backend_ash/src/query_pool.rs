@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use ash::vk;
+use jeriya_shared::{AsDebugInfo, DebugInfo};
+
+use crate::{command_buffer::CommandBufferDependency, device::Device, AsRawVulkan, DebugInfoAshExtension, Error, PhysicalDeviceFeature};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryType {
+    Timestamp,
+    /// Counts the vertex shader, fragment shader and compute shader invocations in this order
+    PipelineStatistics,
+}
+
+impl QueryType {
+    /// Returns the number of values that a single query of this type produces
+    pub fn result_count(&self) -> u32 {
+        match self {
+            QueryType::Timestamp => 1,
+            QueryType::PipelineStatistics => Self::pipeline_statistic_flags().as_raw().count_ones(),
+        }
+    }
+
+    /// Statistics that are collected by a query of type [`QueryType::PipelineStatistics`]
+    fn pipeline_statistic_flags() -> vk::QueryPipelineStatisticFlags {
+        vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+            | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS
+            | vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS
+    }
+}
+
+impl From<QueryType> for vk::QueryType {
+    fn from(query_type: QueryType) -> Self {
+        match query_type {
+            QueryType::Timestamp => vk::QueryType::TIMESTAMP,
+            QueryType::PipelineStatistics => vk::QueryType::PIPELINE_STATISTICS,
+        }
+    }
+}
+
+pub struct QueryPool {
+    query_pool: vk::QueryPool,
+    query_type: QueryType,
+    query_count: u32,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        unsafe { self.device.as_raw_vulkan().destroy_query_pool(self.query_pool, None) }
+    }
+}
+
+impl CommandBufferDependency for QueryPool {}
+
+impl AsDebugInfo for QueryPool {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl QueryPool {
+    /// Creates a new `QueryPool` with `query_count` queries of the given [`QueryType`]
+    ///
+    /// [`QueryType::PipelineStatistics`] requires [`OptionalFeatures::pipeline_statistics_query`](crate::OptionalFeatures::pipeline_statistics_query).
+    pub fn new(device: &Arc<Device>, query_type: QueryType, query_count: u32, debug_info: DebugInfo) -> crate::Result<Self> {
+        let pipeline_statistics = match query_type {
+            QueryType::Timestamp => vk::QueryPipelineStatisticFlags::empty(),
+            QueryType::PipelineStatistics => {
+                if !device.optional_features.pipeline_statistics_query {
+                    return Err(Error::PhysicalDeviceFeatureMissing(PhysicalDeviceFeature::PipelineStatisticsQuery));
+                }
+                QueryType::pipeline_statistic_flags()
+            }
+        };
+        let create_info = vk::QueryPoolCreateInfo::builder()
+            .query_type(query_type.into())
+            .query_count(query_count)
+            .pipeline_statistics(pipeline_statistics);
+        let query_pool = unsafe { device.as_raw_vulkan().create_query_pool(&create_info, None)? };
+        let debug_info = debug_info.with_vulkan_ptr(query_pool);
+        device.set_debug_name(query_pool, &debug_info);
+        Ok(Self {
+            query_pool,
+            query_type,
+            query_count,
+            device: device.clone(),
+            debug_info,
+        })
+    }
+
+    /// Returns the [`QueryType`] of the queries in the `QueryPool`
+    pub fn query_type(&self) -> QueryType {
+        self.query_type
+    }
+
+    /// Returns the number of queries in the `QueryPool`
+    pub fn query_count(&self) -> u32 {
+        self.query_count
+    }
+
+    /// Returns the results of the queries in the range `first_query..first_query + query_count` as 64-bit values.
+    /// Only supports query types that produce a single value per query.
+    ///
+    /// Returns `None` when the results of the queries are not available yet. Doesn't block.
+    pub fn get_results(&self, first_query: u32, query_count: u32) -> crate::Result<Option<Vec<u64>>> {
+        jeriya_shared::assert!(
+            first_query + query_count <= self.query_count,
+            "the queried range exceeds the QueryPool"
+        );
+        jeriya_shared::assert_eq!(self.query_type.result_count(), 1, "only single-value queries are supported");
+        let mut results = vec![0u64; query_count as usize];
+        let result = unsafe {
+            self.device.as_raw_vulkan().get_query_pool_results(
+                self.query_pool,
+                first_query,
+                query_count,
+                &mut results,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+        match result {
+            Ok(()) => Ok(Some(results)),
+            Err(vk::Result::NOT_READY) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl AsRawVulkan for QueryPool {
+    type Output = vk::QueryPool;
+    fn as_raw_vulkan(&self) -> &Self::Output {
+        &self.query_pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_shared::debug_info;
+
+    use crate::device::TestFixtureDevice;
+
+    use super::*;
+
+    mod new {
+        use super::*;
+
+        #[test]
+        fn smoke() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let query_pool = QueryPool::new(&test_fixture_device.device, QueryType::Timestamp, 4, debug_info!("my_query_pool")).unwrap();
+            assert_eq!(query_pool.query_type(), QueryType::Timestamp);
+            assert_eq!(query_pool.query_count(), 4);
+        }
+
+        #[test]
+        fn pipeline_statistics() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            if !test_fixture_device.device.optional_features.pipeline_statistics_query {
+                return;
+            }
+            let query_pool = QueryPool::new(
+                &test_fixture_device.device,
+                QueryType::PipelineStatistics,
+                1,
+                debug_info!("my_query_pool"),
+            )
+            .unwrap();
+            assert_eq!(query_pool.query_type().result_count(), 3);
+        }
+    }
+}
@@ -43,6 +43,8 @@ pub struct Queue {
     pub queue_family_index: u32,
     pub queue_index: u32,
     pub submitted_command_buffers: VecDeque<SubmittedCommandBuffer>,
+    submission_count: u64,
+    completed_submission_count: u64,
     queue: vk::Queue,
     device: Arc<Device>,
     debug_info: DebugInfo,
@@ -70,6 +72,7 @@ impl Queue {
     pub(crate) unsafe fn get_from_family(device: &Arc<Device>, queue_family_index: u32, queue_index: u32, debug_info: DebugInfo) -> Self {
         let vk_queue = device.as_raw_vulkan().get_device_queue(queue_family_index, queue_index);
         let debug_info = debug_info.with_vulkan_ptr(vk_queue);
+        device.set_debug_name(vk_queue, &debug_info);
         info! {
             "Creating queue with queue_family_index: {}, queue_index: {}: {}",
             queue_family_index,
@@ -80,6 +83,8 @@ impl Queue {
             queue_family_index,
             queue_index,
             submitted_command_buffers: VecDeque::new(),
+            submission_count: 0,
+            completed_submission_count: 0,
             queue: vk_queue,
             device: device.clone(),
             debug_info,
@@ -114,6 +119,7 @@ impl Queue {
         }
         self.submitted_command_buffers
             .push_back(SubmittedCommandBuffer::Value(command_buffer));
+        self.submission_count += 1;
         Ok(())
     }
 
@@ -151,6 +157,20 @@ impl Queue {
         Ok(())
     }
 
+    /// Signals the given [`Semaphore`] without executing any commands
+    ///
+    /// This is used in place of acquiring a swapchain image when the images are rendered offscreen.
+    pub fn signal_semaphore(&mut self, semaphore: &Semaphore) -> crate::Result<()> {
+        let signal_semaphores = [*semaphore.as_raw_vulkan()];
+        let submit_info = vk::SubmitInfo::builder().signal_semaphores(&signal_semaphores).build();
+        unsafe {
+            self.device
+                .as_raw_vulkan()
+                .queue_submit(self.queue, &[submit_info], vk::Fence::null())?
+        };
+        Ok(())
+    }
+
     /// Polls the fences that signal the completion of the submitted [`CommandBuffer`]s and executes the finished operations of the [`CommandBuffer`]s that have finished executing.s
     pub fn poll_completed_fences(&mut self) -> crate::Result<()> {
         let _span = jeriya_shared::span!("poll_completed_fences");
@@ -166,6 +186,7 @@ impl Queue {
                     for finished_operation in finished_command_buffer.command_buffer().finished_operations() {
                         finished_operation()?;
                     }
+                    self.completed_submission_count += 1;
                 }
                 drop(finished_command_buffer);
             } else {
@@ -175,6 +196,18 @@ impl Queue {
         Ok(())
     }
 
+    /// Returns the number of [`CommandBuffer`]s that have been submitted with [`Queue::submit`]
+    pub fn submission_count(&self) -> u64 {
+        self.submission_count
+    }
+
+    /// Returns the number of [`CommandBuffer`]s submitted with [`Queue::submit`] that have been found to be completed by [`Queue::poll_completed_fences`]
+    ///
+    /// The submissions complete in the order in which they have been submitted.
+    pub fn completed_submission_count(&self) -> u64 {
+        self.completed_submission_count
+    }
+
     /// Waits for the `Queue` to be idle.
     pub fn wait_idle(&self) -> crate::Result<()> {
         unsafe { self.device.as_raw_vulkan().queue_wait_idle(self.queue) }?;
@@ -59,8 +59,21 @@ impl QueuePlan {
         instance: &Instance,
         physical_device: &PhysicalDevice,
         surfaces: impl IntoIterator<Item = (&'w WindowId, &'s Arc<Surface>)>,
+    ) -> crate::Result<Self> {
+        Self::with_headless(instance, physical_device, surfaces, [])
+    }
+
+    /// Creates a new `QueuePlan` for the given `PhysicalDevice`, `Surface`s and headless render targets.
+    ///
+    /// The headless render targets don't present their images so that every queue family with graphics support is suitable for them.
+    pub fn with_headless<'w, 's>(
+        instance: &Instance,
+        physical_device: &PhysicalDevice,
+        surfaces: impl IntoIterator<Item = (&'w WindowId, &'s Arc<Surface>)>,
+        headless_window_ids: impl IntoIterator<Item = WindowId>,
     ) -> crate::Result<Self> {
         let surfaces = surfaces.into_iter().collect::<Vec<_>>();
+        let headless_window_ids = headless_window_ids.into_iter().collect::<Vec<_>>();
 
         let queue_family_properties = unsafe {
             instance
@@ -75,6 +88,12 @@ impl QueuePlan {
                 let support = surface.supports_presentation(physical_device.as_raw_vulkan(), queue_family_index)?;
                 surface_support.insert((**window_id, queue_family_index as u32), support);
             }
+            for window_id in &headless_window_ids {
+                let support = queue_family_properties[queue_family_index]
+                    .queue_flags
+                    .contains(QueueFlags::GRAPHICS);
+                surface_support.insert((*window_id, queue_family_index as u32), support);
+            }
         }
 
         // Print a table that summarizes the surface support
@@ -100,7 +119,7 @@ impl QueuePlan {
             info!("Surface support:\n{message}");
         }
 
-        let window_ids = surfaces.iter().map(|(window_id, _)| *window_id);
+        let window_ids = surfaces.iter().map(|(window_id, _)| *window_id).chain(&headless_window_ids);
         plan_queues(window_ids, queue_family_properties, &surface_support)
     }
 
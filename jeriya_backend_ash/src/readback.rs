@@ -0,0 +1,368 @@
+use std::{
+    mem,
+    ops::Range,
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
+
+use ash::vk;
+use jeriya_shared::parking_lot::Mutex;
+
+use crate::{host_visible_buffer::HostVisibleBuffer, DispatchIndirectCommand, Error};
+
+/// Per-frame buffers on the GPU that can be read back to the host with [`AshBackend::read_buffer`](crate::AshBackend::read_buffer).
+///
+/// All of these buffers consist of `u32`s. The layout of the buffers is defined by the shaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReadbackBuffer {
+    /// Counter, `VkDrawIndirectCommand`s and indices of the visible rigid mesh instances that are rendered with the simple mesh representation
+    VisibleRigidMeshInstancesSimple,
+    /// `VkDispatchIndirectCommand`, counter and indices of the visible rigid mesh instances
+    VisibleRigidMeshInstances,
+    /// Counter, `VkDrawIndirectCommand`s and indices of the visible meshlets
+    VisibleRigidMeshMeshlets,
+    /// Counter, `VkDrawIndirectCommand`s and indices of the visible point cloud instances that are rendered without clusters
+    VisiblePointCloudInstancesSimple,
+    /// `VkDispatchIndirectCommand`, counter and indices of the visible point cloud instances that are rendered with clusters
+    VisiblePointCloudInstances,
+    /// Counter, `VkDrawIndirectCommand`s and ids of the visible point cloud clusters
+    VisiblePointCloudClusters,
+}
+
+impl ReadbackBuffer {
+    /// Index of the `u32` that contains the number of visible elements in the buffer
+    pub fn count_index(&self) -> usize {
+        match self {
+            // The counter is preceded by a `VkDispatchIndirectCommand`
+            Self::VisibleRigidMeshInstances | Self::VisiblePointCloudInstances => {
+                mem::size_of::<DispatchIndirectCommand>() / mem::size_of::<u32>()
+            }
+            Self::VisibleRigidMeshInstancesSimple
+            | Self::VisibleRigidMeshMeshlets
+            | Self::VisiblePointCloudInstancesSimple
+            | Self::VisiblePointCloudClusters => 0,
+        }
+    }
+
+    /// Range of the `u32` that contains the number of visible elements in the buffer
+    pub fn count_range(&self) -> Range<usize> {
+        let index = self.count_index();
+        index..index + 1
+    }
+}
+
+/// Rectangle of the depth buffer that is read back with [`AshBackend::read_depth_buffer`](crate::AshBackend::read_depth_buffer).
+///
+/// Measured in pixels of the window with the origin in the upper left corner like the cursor position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DepthRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DepthRegion {
+    /// Creates a new `DepthRegion` with the upper left corner at `x` and `y`
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Creates a `DepthRegion` that only contains the pixel at `x` and `y`, e.g. the one under the cursor
+    pub fn pixel(x: u32, y: u32) -> Self {
+        Self::new(x, y, 1, 1)
+    }
+
+    /// Returns the number of pixels in the region
+    pub fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Returns whether the region doesn't contain any pixels
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the region lies completely within a framebuffer of the given size
+    pub fn is_within(&self, width: u32, height: u32) -> bool {
+        self.x.checked_add(self.width).is_some_and(|right| right <= width)
+            && self.y.checked_add(self.height).is_some_and(|bottom| bottom <= height)
+    }
+}
+
+/// Depth values of a [`DepthRegion`] that have been read back from the depth buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthImage {
+    pub region: DepthRegion,
+    /// Tightly packed rows of depth values in [0, 1] starting with the top row. The values are not linearized and the
+    /// background has the value with which the depth buffer has been cleared.
+    pub depths: Vec<f32>,
+}
+
+impl DepthImage {
+    /// Returns the depth at `x` and `y` relative to the upper left corner of the region or `None` when it's outside of the region
+    pub fn get(&self, x: u32, y: u32) -> Option<f32> {
+        if x >= self.region.width || y >= self.region.height {
+            return None;
+        }
+        self.depths.get(y as usize * self.region.width as usize + x as usize).copied()
+    }
+}
+
+/// Handle to data that is read back from the GPU when the frame for which it was requested has finished rendering.
+pub struct Readback<T = Vec<u32>> {
+    receiver: Receiver<T>,
+}
+
+impl<T> Readback<T> {
+    /// Returns the data if it has been read back already and `None` otherwise. Doesn't block.
+    pub fn try_recv(&self) -> crate::Result<Option<T>> {
+        match self.receiver.try_recv() {
+            Ok(data) => Ok(Some(data)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(Error::ReadbackAborted),
+        }
+    }
+
+    /// Blocks until the data has been read back
+    pub fn recv(&self) -> crate::Result<T> {
+        self.receiver.recv().map_err(|_| Error::ReadbackAborted)
+    }
+
+    /// Blocks until the data has been read back or the `timeout` has elapsed. Returns `None` on timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> crate::Result<Option<T>> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(data) => Ok(Some(data)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::ReadbackAborted),
+        }
+    }
+}
+
+/// Request that is sent to the presenter thread to read back a range of a [`ReadbackBuffer`] in the next frame.
+pub(crate) struct ReadbackRequest {
+    pub buffer: ReadbackBuffer,
+    /// Range of the buffer measured in `u32`s
+    pub range: Range<usize>,
+    pub sender: Sender<Vec<u32>>,
+}
+
+impl ReadbackRequest {
+    /// Creates a new `ReadbackRequest` and the [`Readback`] on which the data will be received.
+    pub fn new(buffer: ReadbackBuffer, range: Range<usize>) -> (Self, Readback) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        (Self { buffer, range, sender }, Readback { receiver })
+    }
+}
+
+/// Request that is sent to the presenter thread to read back a [`DepthRegion`] of the depth buffer in the next frame.
+pub(crate) struct DepthReadbackRequest {
+    pub region: DepthRegion,
+    pub sender: Sender<DepthImage>,
+}
+
+impl DepthReadbackRequest {
+    /// Creates a new `DepthReadbackRequest` and the [`Readback`] on which the [`DepthImage`] will be received.
+    pub fn new(region: DepthRegion) -> (Self, Readback<DepthImage>) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        (Self { region, sender }, Readback { receiver })
+    }
+}
+
+/// Readback for which the copy command has been recorded but the frame hasn't finished rendering yet.
+pub(crate) struct PendingReadback {
+    pub host_visible_buffer: Arc<Mutex<HostVisibleBuffer<u32>>>,
+    pub sender: Sender<Vec<u32>>,
+}
+
+impl PendingReadback {
+    /// Reads the data from the [`HostVisibleBuffer`] and sends it to the [`Readback`].
+    ///
+    /// Must only be called when the command buffer that copies into the [`HostVisibleBuffer`] has finished executing.
+    pub fn finish(self) -> crate::Result<()> {
+        let host_visible_buffer = self.host_visible_buffer.lock();
+        let mut data = vec![0; host_visible_buffer.len()];
+        host_visible_buffer.get_memory_unaligned(&mut data)?;
+        // The receiver might have been dropped when the application is no longer interested in the data
+        let _ = self.sender.send(data);
+        Ok(())
+    }
+}
+
+/// Depth readback for which the copy command has been recorded but the frame hasn't finished rendering yet.
+pub(crate) struct PendingDepthReadback {
+    /// Contains one texel of the depth aspect per `u32`. Might be larger than the region when it is reused.
+    pub host_visible_buffer: Arc<Mutex<HostVisibleBuffer<u32>>>,
+    /// Format of the depth buffer from which the texels have been copied
+    pub format: vk::Format,
+    pub region: DepthRegion,
+    pub sender: Sender<DepthImage>,
+}
+
+impl PendingDepthReadback {
+    /// Converts the texels in the [`HostVisibleBuffer`] into depth values and sends them to the [`Readback`].
+    ///
+    /// Returns the [`HostVisibleBuffer`] so that it can be reused by the next readbacks. Must only be called when
+    /// the command buffer that copies into the [`HostVisibleBuffer`] has finished executing.
+    pub fn finish(self) -> crate::Result<Arc<Mutex<HostVisibleBuffer<u32>>>> {
+        let host_visible_buffer = self.host_visible_buffer.lock();
+        let mut texels = vec![0; host_visible_buffer.len()];
+        host_visible_buffer.get_memory_unaligned(&mut texels)?;
+        drop(host_visible_buffer);
+        let depth_image = DepthImage {
+            region: self.region,
+            depths: texels
+                .into_iter()
+                .take(self.region.len())
+                .map(|texel| unpack_depth(self.format, texel))
+                .collect(),
+        };
+        // The receiver might have been dropped when the application is no longer interested in the data
+        let _ = self.sender.send(depth_image);
+        Ok(self.host_visible_buffer)
+    }
+}
+
+/// Returns whether the depth aspect of the `format` is copied into a buffer with one `u32` per texel that can be unpacked with [`unpack_depth`]
+pub(crate) fn is_depth_readback_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::D24_UNORM_S8_UINT | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT | vk::Format::D32_SFLOAT_S8_UINT
+    )
+}
+
+/// Converts a texel of the depth aspect of an image with the given `format` into a depth value in [0, 1]
+///
+/// The 24 bit formats store the depth in the lower 24 bits and the upper 8 bits are undefined when the depth aspect is
+/// copied to a buffer. The 32 bit formats store the depth as float.
+fn unpack_depth(format: vk::Format, texel: u32) -> f32 {
+    const D24_MAX: u32 = (1 << 24) - 1;
+    match format {
+        vk::Format::D24_UNORM_S8_UINT | vk::Format::X8_D24_UNORM_PACK32 => (texel & D24_MAX) as f32 / D24_MAX as f32,
+        vk::Format::D32_SFLOAT | vk::Format::D32_SFLOAT_S8_UINT => f32::from_bits(texel),
+        format => panic!("the depth aspect of the format {format:?} can't be unpacked from a u32"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use jeriya_shared::{debug_info, parking_lot::Mutex};
+
+    use crate::{buffer::BufferUsageFlags, device::TestFixtureDevice, host_visible_buffer::HostVisibleBuffer, Error};
+
+    use super::*;
+
+    #[test]
+    fn count_index() {
+        assert_eq!(ReadbackBuffer::VisibleRigidMeshMeshlets.count_index(), 0);
+        assert_eq!(ReadbackBuffer::VisibleRigidMeshInstances.count_index(), 3);
+        assert_eq!(ReadbackBuffer::VisiblePointCloudInstances.count_range(), 3..4);
+    }
+
+    #[test]
+    fn pending_readback_finish() {
+        let test_fixture_device = TestFixtureDevice::new().unwrap();
+        let host_visible_buffer = HostVisibleBuffer::new(
+            &test_fixture_device.device,
+            &[7, 8, 9],
+            BufferUsageFlags::TRANSFER_DST_BIT,
+            debug_info!("my_host_visible_buffer"),
+        )
+        .unwrap();
+        let (request, readback) = ReadbackRequest::new(ReadbackBuffer::VisibleRigidMeshMeshlets, 0..3);
+        assert_eq!(readback.try_recv().unwrap(), None);
+        let pending_readback = PendingReadback {
+            host_visible_buffer: Arc::new(Mutex::new(host_visible_buffer)),
+            sender: request.sender,
+        };
+        pending_readback.finish().unwrap();
+        assert_eq!(readback.recv().unwrap(), vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn depth_region_is_within() {
+        assert!(DepthRegion::new(0, 0, 4, 3).is_within(4, 3));
+        assert!(DepthRegion::pixel(3, 2).is_within(4, 3));
+        assert!(!DepthRegion::pixel(4, 2).is_within(4, 3));
+        assert!(!DepthRegion::new(2, 0, 3, 1).is_within(4, 3));
+        assert!(!DepthRegion::new(u32::MAX, 0, 1, 1).is_within(4, 3));
+    }
+
+    #[test]
+    fn depth_image_get() {
+        let depth_image = DepthImage {
+            region: DepthRegion::new(10, 20, 2, 2),
+            depths: vec![0.1, 0.2, 0.3, 0.4],
+        };
+        assert_eq!(depth_image.get(1, 0), Some(0.2));
+        assert_eq!(depth_image.get(0, 1), Some(0.3));
+        assert_eq!(depth_image.get(2, 0), None);
+    }
+
+    #[test]
+    fn unpack_d24() {
+        let format = vk::Format::D24_UNORM_S8_UINT;
+        assert_eq!(unpack_depth(format, 0), 0.0);
+        assert_eq!(unpack_depth(format, 0x00FF_FFFF), 1.0);
+        // The bits of the stencil are ignored
+        assert_eq!(unpack_depth(format, 0xAB00_0000), 0.0);
+        assert!((unpack_depth(format, 0x0080_0000) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unpack_d32() {
+        let format = vk::Format::D32_SFLOAT;
+        assert_eq!(unpack_depth(format, 1.0f32.to_bits()), 1.0);
+        // Reversed-Z stores the far plane at 0.0 and relies on the precision of small values
+        assert_eq!(unpack_depth(format, 1e-7f32.to_bits()), 1e-7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unpack_unsupported_format() {
+        assert!(!is_depth_readback_format(vk::Format::D16_UNORM));
+        unpack_depth(vk::Format::D16_UNORM, 0);
+    }
+
+    #[test]
+    fn pending_depth_readback_reversed_z() {
+        let test_fixture_device = TestFixtureDevice::new().unwrap();
+        // With reversed-Z the near plane is at 1.0 and the far plane and the cleared background are at 0.0. The buffer
+        // has been reused for a larger region before so that the last texel doesn't belong to the region.
+        let texels = [1.0f32, 0.75, 0.0, 0.25].map(f32::to_bits);
+        let host_visible_buffer = HostVisibleBuffer::new(
+            &test_fixture_device.device,
+            &texels,
+            BufferUsageFlags::TRANSFER_DST_BIT,
+            debug_info!("my_host_visible_buffer"),
+        )
+        .unwrap();
+        let region = DepthRegion::new(4, 2, 3, 1);
+        let (request, readback) = DepthReadbackRequest::new(region);
+        let pending_depth_readback = PendingDepthReadback {
+            host_visible_buffer: Arc::new(Mutex::new(host_visible_buffer)),
+            format: vk::Format::D32_SFLOAT,
+            region,
+            sender: request.sender,
+        };
+        let host_visible_buffer = pending_depth_readback.finish().unwrap();
+        assert_eq!(host_visible_buffer.lock().len(), 4);
+        let depth_image = readback.recv().unwrap();
+        assert_eq!(depth_image.depths, vec![1.0, 0.75, 0.0]);
+        // The nearest depth is the largest one
+        assert_eq!(depth_image.get(0, 0), Some(1.0));
+        assert_eq!(depth_image.get(2, 0), Some(0.0));
+    }
+
+    #[test]
+    fn aborted() {
+        let (request, readback) = ReadbackRequest::new(ReadbackBuffer::VisibleRigidMeshMeshlets, 0..1);
+        drop(request);
+        assert!(matches!(readback.recv(), Err(Error::ReadbackAborted)));
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use ash::vk;
+use jeriya_backend::{gpu_index_allocator::GpuIndexAllocation, instances::camera_instance::CameraInstance, render_target::RenderTargetId};
+use jeriya_shared::debug_info;
+
+use crate::{
+    command_buffer::CommandBufferDependency, device::Device, device_image::DeviceImage, swapchain_depth_buffer::SwapchainDepthBuffer,
+    swapchain_render_pass::SwapchainRenderPass, AsRawVulkan,
+};
+
+/// Format of the color image of a [`RenderTarget`]
+pub const RENDER_TARGET_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+/// Color and depth image into which the scene is rendered from a [`CameraInstance`] before the scene of the presenter
+///
+/// The color image is sampled with the index of the bindless texture that is returned by [`RenderTarget::texture_index`].
+pub struct RenderTarget {
+    id: RenderTargetId,
+    camera_instance: GpuIndexAllocation<CameraInstance>,
+    color_image: Arc<DeviceImage>,
+    _depth_buffer: SwapchainDepthBuffer,
+    framebuffer: vk::Framebuffer,
+    texture_index: u32,
+    device: Arc<Device>,
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.as_raw_vulkan().destroy_framebuffer(self.framebuffer, None);
+        }
+    }
+}
+
+impl CommandBufferDependency for RenderTarget {}
+
+impl RenderTarget {
+    /// Creates the color image of a `RenderTarget` with the given `extent`
+    ///
+    /// The image has to be inserted into the bindless textures before the `RenderTarget` is created with [`RenderTarget::new`].
+    pub fn create_color_image(device: &Arc<Device>, id: RenderTargetId, extent: vk::Extent2D) -> crate::Result<Arc<DeviceImage>> {
+        let color_image = DeviceImage::new_color_attachment(
+            device,
+            extent,
+            RENDER_TARGET_FORMAT,
+            debug_info!(format!("RenderTarget-{}-ColorImage", id.0)),
+        )?;
+        Ok(Arc::new(color_image))
+    }
+
+    /// Creates a new `RenderTarget` for the `color_image` that has been inserted into the bindless textures at `texture_index`
+    pub fn new(
+        device: &Arc<Device>,
+        id: RenderTargetId,
+        camera_instance: GpuIndexAllocation<CameraInstance>,
+        color_image: Arc<DeviceImage>,
+        texture_index: u32,
+        render_pass: &SwapchainRenderPass,
+    ) -> crate::Result<Self> {
+        let extent = color_image.extent();
+        let depth_buffer = SwapchainDepthBuffer::new(device, extent)?;
+        let framebuffer_attachments = [color_image.image_view(), depth_buffer.depth_stencil_image_view];
+        let framebuffer_create_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(render_pass.render_pass)
+            .attachments(&framebuffer_attachments)
+            .width(extent.width)
+            .height(extent.height)
+            .layers(1);
+        let framebuffer = unsafe { device.as_raw_vulkan().create_framebuffer(&framebuffer_create_info, None)? };
+        Ok(Self {
+            id,
+            camera_instance,
+            color_image,
+            _depth_buffer: depth_buffer,
+            framebuffer,
+            texture_index,
+            device: device.clone(),
+        })
+    }
+
+    /// Returns the id with which the `RenderTarget` is referenced from the overlay
+    pub fn id(&self) -> RenderTargetId {
+        self.id
+    }
+
+    /// Returns the [`CameraInstance`] from which the scene is rendered
+    pub fn camera_instance(&self) -> &GpuIndexAllocation<CameraInstance> {
+        &self.camera_instance
+    }
+
+    /// Returns the extent of the color and depth image
+    pub fn extent(&self) -> vk::Extent2D {
+        self.color_image.extent()
+    }
+
+    /// Returns the image into which the scene is rendered
+    pub fn color_image(&self) -> &Arc<DeviceImage> {
+        &self.color_image
+    }
+
+    /// Returns the framebuffer that contains the color and depth image
+    pub fn framebuffer(&self) -> vk::Framebuffer {
+        self.framebuffer
+    }
+
+    /// Returns the index of the bindless texture with which the color image is sampled
+    pub fn texture_index(&self) -> u32 {
+        self.texture_index
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+};
+
+use jeriya_backend::{
+    gpu_index_allocator::GpuIndexAllocation,
+    resources::{mesh_attributes::MeshAttributes, point_cloud_attributes::PointCloudAttributes},
+};
+
+/// Resource that has been removed but whose data might still be accessed by the GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetiredResource {
+    MeshAttributes(GpuIndexAllocation<MeshAttributes>),
+    PointCloudAttributes(GpuIndexAllocation<PointCloudAttributes>),
+    /// Slot of an evicted page in the static point cloud pages buffer
+    PointCloudPage(usize),
+    /// Slot in the [`BindlessTextures`](crate::bindless_textures::BindlessTextures) of a presenter
+    BindlessTexture(u32),
+}
+
+/// Tracks the frames of the presenters that have been started but haven't finished executing on the GPU
+#[derive(Debug, Default)]
+pub struct FramesInFlight {
+    /// Indices of the frames in flight per presenter index
+    frames: BTreeMap<usize, BTreeSet<u64>>,
+}
+
+impl FramesInFlight {
+    /// Creates a new `FramesInFlight` without any frames in flight
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the frame with the given `frame_index` of the presenter as started. It might read data from now on.
+    pub fn start_frame(&mut self, presenter_index: usize, frame_index: u64) {
+        self.frames.entry(presenter_index).or_default().insert(frame_index);
+    }
+
+    /// Marks the frame as finished without having been submitted to the GPU
+    pub fn skip_frame(&mut self, presenter_index: usize, frame_index: u64) {
+        if let Some(frames) = self.frames.get_mut(&presenter_index) {
+            frames.remove(&frame_index);
+        }
+    }
+
+    /// Marks the frame as completed on the GPU. The frames of a presenter complete in the order in which they
+    /// have been submitted. Therefore, the frames before it are completed as well.
+    pub fn complete_frame(&mut self, presenter_index: usize, frame_index: u64) {
+        if let Some(frames) = self.frames.get_mut(&presenter_index) {
+            frames.retain(|frame| *frame > frame_index);
+        }
+    }
+
+    /// Returns the latest frame of every presenter that is in flight
+    fn latest_frames(&self) -> Vec<(usize, u64)> {
+        self.frames
+            .iter()
+            .filter_map(|(presenter_index, frames)| frames.last().map(|frame_index| (*presenter_index, *frame_index)))
+            .collect()
+    }
+
+    /// Returns `true` when the given frames and all frames of the same presenters before them have finished
+    fn are_finished(&self, latest_frames: &[(usize, u64)]) -> bool {
+        latest_frames.iter().all(|(presenter_index, frame_index)| {
+            self.frames
+                .get(presenter_index)
+                .and_then(|frames| frames.first())
+                .is_none_or(|first| first > frame_index)
+        })
+    }
+}
+
+/// Condition that has to be met before the data of a [`RetiredResource`] can be reused
+#[derive(Debug, Clone, PartialEq)]
+enum Retirement {
+    /// Uploads might still write the data until the transfer queue has completed the given number of submissions
+    TransferSubmissions(u64),
+    /// The frames that have been in flight when the uploads had completed might still read the data
+    Frames(Vec<(usize, u64)>),
+}
+
+/// Keeps the removed resources until neither the transfer queue nor the frames in flight access their data anymore
+#[derive(Debug, Default)]
+pub struct RetiredResources {
+    retired_resources: Vec<(Retirement, RetiredResource)>,
+}
+
+impl RetiredResources {
+    /// Creates a new `RetiredResources` without any retired resources
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retires the `retired_resource` whose data might be written by the transfer queue until it has completed
+    /// `transfer_submissions` submissions
+    pub fn retire(&mut self, retired_resource: RetiredResource, transfer_submissions: u64) {
+        self.retired_resources
+            .push((Retirement::TransferSubmissions(transfer_submissions), retired_resource));
+    }
+
+    /// Returns the resources whose data is no longer accessed by the GPU
+    pub fn release(&mut self, completed_transfer_submissions: u64, frames_in_flight: &FramesInFlight) -> Vec<RetiredResource> {
+        let mut released_resources = Vec::new();
+        for (retirement, retired_resource) in mem::take(&mut self.retired_resources) {
+            let retirement = match retirement {
+                Retirement::TransferSubmissions(transfer_submissions) if completed_transfer_submissions >= transfer_submissions => {
+                    Retirement::Frames(frames_in_flight.latest_frames())
+                }
+                retirement => retirement,
+            };
+            match &retirement {
+                Retirement::Frames(latest_frames) if frames_in_flight.are_finished(latest_frames) => {
+                    released_resources.push(retired_resource);
+                }
+                _ => self.retired_resources.push((retirement, retired_resource)),
+            }
+        }
+        released_resources
+    }
+
+    /// Returns the GPU indices of the [`MeshAttributes`] that have been retired but not released yet
+    pub fn mesh_attributes(&self) -> impl Iterator<Item = &GpuIndexAllocation<MeshAttributes>> {
+        self.retired_resources
+            .iter()
+            .filter_map(|(_, retired_resource)| match retired_resource {
+                RetiredResource::MeshAttributes(gpu_index_allocation) => Some(gpu_index_allocation),
+                RetiredResource::PointCloudAttributes(_) | RetiredResource::PointCloudPage(_) | RetiredResource::BindlessTexture(_) => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_after_transfer_and_frames() {
+        let mut frames_in_flight = FramesInFlight::new();
+        let mut retired_resources = RetiredResources::new();
+        let mesh_attributes = RetiredResource::MeshAttributes(GpuIndexAllocation::new_unchecked(4));
+
+        frames_in_flight.start_frame(0, 7);
+        retired_resources.retire(mesh_attributes, 3);
+        assert_eq!(retired_resources.mesh_attributes().count(), 1);
+
+        // The upload hasn't completed yet
+        assert!(retired_resources.release(2, &frames_in_flight).is_empty());
+
+        // Frame 8 is started before the upload completes and might still read the data
+        frames_in_flight.start_frame(0, 8);
+        assert!(retired_resources.release(3, &frames_in_flight).is_empty());
+        frames_in_flight.complete_frame(0, 7);
+        assert!(retired_resources.release(3, &frames_in_flight).is_empty());
+
+        // Frames that are started afterwards don't matter
+        frames_in_flight.start_frame(0, 9);
+        frames_in_flight.complete_frame(0, 8);
+        assert_eq!(retired_resources.release(3, &frames_in_flight), vec![mesh_attributes]);
+        assert_eq!(retired_resources.mesh_attributes().count(), 0);
+    }
+
+    #[test]
+    fn waits_for_every_presenter() {
+        let mut frames_in_flight = FramesInFlight::new();
+        let mut retired_resources = RetiredResources::new();
+        let page = RetiredResource::PointCloudPage(2);
+
+        frames_in_flight.start_frame(0, 10);
+        frames_in_flight.start_frame(1, 3);
+        retired_resources.retire(page, 0);
+        assert!(retired_resources.release(0, &frames_in_flight).is_empty());
+
+        // The frames of the first presenter don't finish the frames of the second one
+        frames_in_flight.start_frame(0, 11);
+        frames_in_flight.complete_frame(0, 10);
+        assert!(retired_resources.release(0, &frames_in_flight).is_empty());
+
+        // A frame that is skipped is never submitted and doesn't read anything
+        frames_in_flight.skip_frame(1, 3);
+        assert_eq!(retired_resources.release(0, &frames_in_flight), vec![page]);
+    }
+
+    #[test]
+    fn released_immediately_without_frames_in_flight() {
+        let frames_in_flight = FramesInFlight::new();
+        let mut retired_resources = RetiredResources::new();
+        let point_cloud_attributes = RetiredResource::PointCloudAttributes(GpuIndexAllocation::new_unchecked(1));
+        retired_resources.retire(point_cloud_attributes, 1);
+        assert_eq!(retired_resources.release(1, &frames_in_flight), vec![point_cloud_attributes]);
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use ash::vk;
+use jeriya_shared::{AddressMode, AsDebugInfo, DebugInfo, Filter, SamplerConfig};
+
+use crate::{command_buffer::CommandBufferDependency, device::Device, AsRawVulkan, DebugInfoAshExtension};
+
+fn vk_filter(filter: Filter) -> vk::Filter {
+    match filter {
+        Filter::Nearest => vk::Filter::NEAREST,
+        Filter::Linear => vk::Filter::LINEAR,
+    }
+}
+
+fn vk_sampler_address_mode(address_mode: AddressMode) -> vk::SamplerAddressMode {
+    match address_mode {
+        AddressMode::Repeat => vk::SamplerAddressMode::REPEAT,
+        AddressMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        AddressMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+    }
+}
+
+/// Vulkan sampler that is created from a [`SamplerConfig`]
+pub struct Sampler {
+    sampler: vk::Sampler,
+    device: Arc<Device>,
+    debug_info: DebugInfo,
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe { self.device.as_raw_vulkan().destroy_sampler(self.sampler, None) }
+    }
+}
+
+impl CommandBufferDependency for Sampler {}
+
+impl AsDebugInfo for Sampler {
+    fn as_debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+}
+
+impl AsRawVulkan for Sampler {
+    type Output = vk::Sampler;
+    fn as_raw_vulkan(&self) -> &Self::Output {
+        &self.sampler
+    }
+}
+
+impl Sampler {
+    /// Creates a new `Sampler` from the given [`SamplerConfig`]
+    ///
+    /// The anisotropy is clamped to the limit of the device and disabled when the device doesn't support it.
+    pub fn new(device: &Arc<Device>, config: &SamplerConfig, debug_info: DebugInfo) -> crate::Result<Self> {
+        let max_anisotropy = config
+            .max_anisotropy
+            .filter(|_| device.optional_features.sampler_anisotropy)
+            .map(|max_anisotropy| {
+                (max_anisotropy as f32).min(device.physical_device.physical_device_properties.limits.max_sampler_anisotropy)
+            });
+        let mipmap_mode = match config.mipmap_filter {
+            Filter::Nearest => vk::SamplerMipmapMode::NEAREST,
+            Filter::Linear => vk::SamplerMipmapMode::LINEAR,
+        };
+        let sampler_create_info = vk::SamplerCreateInfo::builder()
+            .mag_filter(vk_filter(config.mag_filter))
+            .min_filter(vk_filter(config.min_filter))
+            .mipmap_mode(mipmap_mode)
+            .address_mode_u(vk_sampler_address_mode(config.address_mode_u))
+            .address_mode_v(vk_sampler_address_mode(config.address_mode_v))
+            .address_mode_w(vk_sampler_address_mode(config.address_mode_w))
+            .mip_lod_bias(config.mip_lod_bias)
+            .anisotropy_enable(max_anisotropy.is_some())
+            .max_anisotropy(max_anisotropy.unwrap_or(1.0))
+            .max_lod(vk::LOD_CLAMP_NONE);
+        let sampler = unsafe { device.as_raw_vulkan().create_sampler(&sampler_create_info, None)? };
+        let debug_info = debug_info.with_vulkan_ptr(sampler);
+        device.set_debug_name(sampler, &debug_info);
+        Ok(Self {
+            sampler,
+            device: device.clone(),
+            debug_info,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod new {
+        use jeriya_shared::{debug_info, AsDebugInfo, SamplerConfig};
+
+        use crate::{device::TestFixtureDevice, sampler::Sampler};
+
+        #[test]
+        fn smoke() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let config = SamplerConfig::linear().with_max_anisotropy(16).with_mip_lod_bias(-0.5);
+            let sampler = Sampler::new(&test_fixture_device.device, &config, debug_info!("my_sampler")).unwrap();
+            assert_eq!(sampler.as_debug_info().name(), "my_sampler");
+        }
+    }
+}
@@ -28,6 +28,7 @@ impl Semaphore {
         let create_info = Default::default();
         let semaphore = unsafe { device.as_raw_vulkan().create_semaphore(&create_info, None)? };
         let debug_info = debug_info.with_vulkan_ptr(semaphore);
+        device.set_debug_name(semaphore, &debug_info);
         Ok(Self {
             semaphore,
             device: device.clone(),
@@ -1,42 +1,109 @@
-use jeriya_backend::{elements, instances, resources};
+use std::mem;
+
+use jeriya_backend::{clip_plane::MAX_CLIP_PLANES, debug, elements, instances, resources};
 use jeriya_content::point_cloud::clustered_point_cloud::Page;
-use jeriya_shared::nalgebra::{Matrix4, Vector4};
+use jeriya_shared::nalgebra::{Matrix4, Vector3, Vector4};
 
 pub trait Represents<T> {}
 
+/// Indices of the descriptor sets as they are declared in the shaders
+pub mod sets {
+    /// Descriptors that differ between the passes of a frame and are pushed for every pipeline
+    pub const PUSH_DESCRIPTORS: u32 = 0;
+    /// Texture array that is indexed by the materials
+    pub const BINDLESS_TEXTURES: u32 = 1;
+    /// Buffers that stay the same for the whole frame and are bound with a cached descriptor set
+    pub const CACHED_DESCRIPTORS: u32 = 2;
+}
+
+/// Binding indices of the buffers in the descriptor sets 0 and 2 as they are declared in the shaders
+pub mod bindings {
+    pub const PER_FRAME_DATA: u32 = 0;
+    pub const CAMERAS: u32 = 1;
+    pub const CAMERA_INSTANCES: u32 = 2;
+    pub const VISIBLE_RIGID_MESH_INSTANCES_SIMPLE: u32 = 3;
+    pub const STATIC_VERTEX_POSITIONS: u32 = 5;
+    pub const STATIC_INDICES: u32 = 6;
+    pub const STATIC_VERTEX_NORMALS: u32 = 7;
+    pub const MESH_ATTRIBUTES: u32 = 8;
+    pub const RIGID_MESHES: u32 = 9;
+    pub const MESH_ATTRIBUTES_ACTIVE: u32 = 10;
+    pub const RIGID_MESH_INSTANCES: u32 = 11;
+    pub const STATIC_MESHLETS: u32 = 12;
+    pub const VISIBLE_RIGID_MESH_INSTANCES: u32 = 13;
+    pub const VISIBLE_RIGID_MESH_MESHLETS: u32 = 14;
+    pub const POINT_CLOUD_ATTRIBUTES_ACTIVE: u32 = 15;
+    pub const POINT_CLOUDS: u32 = 16;
+    pub const POINT_CLOUD_INSTANCES: u32 = 17;
+    pub const VISIBLE_POINT_CLOUD_INSTANCES_SIMPLE: u32 = 18;
+    pub const POINT_CLOUD_ATTRIBUTES: u32 = 19;
+    pub const STATIC_POINT_POSITIONS: u32 = 20;
+    pub const STATIC_POINT_COLORS: u32 = 21;
+    pub const POINT_CLOUD_PAGES: u32 = 22;
+    pub const POINT_CLOUD_PAGES_ACTIVE: u32 = 23;
+    pub const STATIC_POINT_CLOUD_PAGES: u32 = 24;
+    pub const VISIBLE_POINT_CLOUD_INSTANCES: u32 = 25;
+    pub const VISIBLE_POINT_CLOUD_CLUSTERS: u32 = 26;
+    pub const FRAME_TELEMETRY: u32 = 27;
+    pub const DEVICE_LOCAL_DEBUG_LINES: u32 = 28;
+    pub const DEPTH_BUFFER: u32 = 29;
+    pub const POINT_CLOUD_PAGE_REQUESTS: u32 = 30;
+    pub const RIGID_MESH_INSTANCE_MOTIONS: u32 = 31;
+    pub const OUTPUT_TRANSFORM_INPUT: u32 = 32;
+    pub const POST_PROCESSING_INPUT: u32 = 33;
+    pub const SELECTION_STENCIL: u32 = 34;
+}
+
 impl Represents<resources::mesh_attributes::MeshAttributes> for u32 {}
 impl Represents<resources::point_cloud_attributes::PointCloudAttributes> for u32 {}
 
 #[repr(C)]
 #[derive(Debug, Clone, Default)]
 pub struct FrameTelemetry {
-    max_cameras: u32,
-    max_camera_instances: u32,
-
-    max_mesh_attributes: u32,
-    max_point_cloud_attributes: u32,
-
-    max_rigid_meshes: u32,
-    max_rigid_mesh_instances: u32,
-    max_meshlets: u32,
-    max_visible_rigid_mesh_instances: u32,
-    max_visible_rigid_mesh_meshlets: u32,
-
-    max_point_clouds: u32,
-    max_point_cloud_instances: u32,
-    max_point_cloud_pages: u32,
-    max_point_cloud_page_clusters: u32,
-    max_visible_point_cloud_clusters: u32,
-
-    visible_rigid_mesh_instances: u32,
-    visible_rigid_mesh_instances_simple: u32,
-    visible_rigid_mesh_meshlets: u32,
+    pub max_cameras: u32,
+    pub max_camera_instances: u32,
+
+    pub max_mesh_attributes: u32,
+    pub max_point_cloud_attributes: u32,
+
+    pub max_rigid_meshes: u32,
+    pub max_rigid_mesh_instances: u32,
+    pub max_meshlets: u32,
+    pub max_visible_rigid_mesh_instances: u32,
+    pub max_visible_rigid_mesh_meshlets: u32,
+
+    pub max_point_clouds: u32,
+    pub max_point_cloud_instances: u32,
+    pub max_point_cloud_pages: u32,
+    pub max_point_cloud_page_clusters: u32,
+    pub max_visible_point_cloud_clusters: u32,
+
+    pub visible_rigid_mesh_instances: u32,
+    pub visible_rigid_mesh_instances_simple: u32,
+    pub visible_rigid_mesh_meshlets: u32,
     /// Number of vertices in the visible meshlets. This is not the number of vertices stored in the visible meshlets but the number of indices referencing vertices and therefore the number of rendered vertices.
-    visible_rigid_mesh_meshlet_vertices: u32,
+    pub visible_rigid_mesh_meshlet_vertices: u32,
+
+    pub visible_point_cloud_instances: u32,
+    pub visible_point_cloud_instances_simple: u32,
+    pub visible_point_cloud_clusters: u32,
+
+    /// Written by copying the results of the pipeline statistics query into the buffer
+    pub pipeline_statistics: PipelineStatistics,
+}
+
+impl FrameTelemetry {
+    /// Byte offset of the [`PipelineStatistics`] in the `FrameTelemetry`
+    pub const PIPELINE_STATISTICS_OFFSET: usize = mem::size_of::<FrameTelemetry>() - mem::size_of::<PipelineStatistics>();
+}
 
-    visible_point_cloud_instances: u32,
-    visible_point_cloud_instances_simple: u32,
-    visible_point_cloud_clusters: u32,
+/// Results of the pipeline statistics query in the order in which Vulkan writes them
+#[repr(C)]
+#[derive(Debug, Clone, Default)]
+pub struct PipelineStatistics {
+    pub vertex_shader_invocations: u32,
+    pub fragment_shader_invocations: u32,
+    pub compute_shader_invocations: u32,
 }
 
 #[repr(C)]
@@ -49,6 +116,44 @@ pub struct PerFrameData {
     pub point_cloud_instance_count: u32,
     pub framebuffer_width: u32,
     pub framebuffer_height: u32,
+    /// Maximum number of points that are rendered for the point clouds. `u32::MAX` means that there is no limit.
+    pub maximum_rendered_points: u32,
+    /// Seconds since the backend has been created. The [`RigidMeshInstanceMotion`]s are evaluated at this time.
+    pub time: f32,
+    /// Number of valid entries in `clip_planes`
+    pub clip_plane_count: u32,
+    pub _padding: [u32; 2],
+    /// Clip planes in world space. The normal is stored in xyz and the distance in w.
+    pub clip_planes: [Vector4<f32>; MAX_CLIP_PLANES],
+}
+
+#[repr(u32)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum DebugShadingMode {
+    #[default]
+    None = 0,
+    Normals = 1,
+    Depth = 2,
+    MeshletId = 3,
+    InstanceId = 4,
+    Overdraw = 5,
+    FillLevel = 6,
+    ClusterDepth = 7,
+}
+
+impl From<debug::DebugShadingMode> for DebugShadingMode {
+    fn from(debug_shading_mode: debug::DebugShadingMode) -> Self {
+        match debug_shading_mode {
+            debug::DebugShadingMode::None => Self::None,
+            debug::DebugShadingMode::Normals => Self::Normals,
+            debug::DebugShadingMode::Depth => Self::Depth,
+            debug::DebugShadingMode::MeshletId => Self::MeshletId,
+            debug::DebugShadingMode::InstanceId => Self::InstanceId,
+            debug::DebugShadingMode::Overdraw => Self::Overdraw,
+            debug::DebugShadingMode::FillLevel => Self::FillLevel,
+            debug::DebugShadingMode::ClusterDepth => Self::ClusterDepth,
+        }
+    }
 }
 
 #[repr(C)]
@@ -194,6 +299,12 @@ pub struct RigidMeshInstance {
     pub rigid_mesh_index: u64,
     pub _padding: u64,
     pub transform: Matrix4<f32>,
+    pub color: Vector4<f32>,
+}
+
+impl RigidMeshInstance {
+    /// Value of `rigid_mesh_index` that marks a slot whose [`RigidMeshInstance`] has been removed
+    pub const REMOVED_RIGID_MESH_INDEX: u64 = u64::MAX;
 }
 
 impl Represents<instances::rigid_mesh_instance::RigidMeshInstance> for RigidMeshInstance {}
@@ -204,6 +315,80 @@ impl Default for RigidMeshInstance {
             rigid_mesh_index: 0,
             _padding: 0,
             transform: Matrix4::identity(),
+            color: instances::rigid_mesh_instance::RigidMeshInstance::NO_TINT,
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum MotionKind {
+    #[default]
+    None = 0,
+    Spin = 1,
+    Orbit = 2,
+    LinearVelocity = 3,
+}
+
+/// Motion of a [`RigidMeshInstance`] that is evaluated by the `animate_rigid_mesh_instances` compute shader
+///
+/// The shader overwrites the transform of the [`RigidMeshInstance`] with the `base_transform` moved by the
+/// motion since `start_time`. See [`Motion::transform_at`](instances::rigid_mesh_instance::Motion::transform_at).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct RigidMeshInstanceMotion {
+    pub kind: MotionKind,
+    /// Angular velocity in radians per second for [`MotionKind::Spin`] and [`MotionKind::Orbit`]
+    pub angular_velocity: f32,
+    /// Value of [`PerFrameData::time`] at which the motion started
+    pub start_time: f32,
+    pub _padding: u32,
+    /// Rotation axis for [`MotionKind::Spin`] and [`MotionKind::Orbit`]
+    pub axis: Vector4<f32>,
+    /// Center for [`MotionKind::Orbit`] and velocity for [`MotionKind::LinearVelocity`]
+    pub vector: Vector4<f32>,
+    /// Transform of the [`RigidMeshInstance`] on which the motion is applied
+    pub base_transform: Matrix4<f32>,
+}
+
+impl RigidMeshInstanceMotion {
+    /// Creates the [`RigidMeshInstanceMotion`] for the given [`Motion`](instances::rigid_mesh_instance::Motion)
+    pub fn new(motion: &instances::rigid_mesh_instance::Motion, base_transform: Matrix4<f32>, start_time: f32) -> Self {
+        use instances::rigid_mesh_instance::Motion;
+        let (kind, angular_velocity, axis, vector) = match *motion {
+            Motion::None => (MotionKind::None, 0.0, Vector3::zeros(), Vector3::zeros()),
+            Motion::Spin { axis, angular_velocity } => (MotionKind::Spin, angular_velocity, axis.into_inner(), Vector3::zeros()),
+            Motion::Orbit {
+                center,
+                axis,
+                angular_velocity,
+            } => (MotionKind::Orbit, angular_velocity, axis.into_inner(), center),
+            Motion::LinearVelocity(velocity) => (MotionKind::LinearVelocity, 0.0, Vector3::zeros(), velocity),
+        };
+        Self {
+            kind,
+            angular_velocity,
+            start_time,
+            _padding: 0,
+            axis: axis.push(0.0),
+            vector: vector.push(0.0),
+            base_transform,
+        }
+    }
+}
+
+impl Represents<instances::rigid_mesh_instance::RigidMeshInstance> for RigidMeshInstanceMotion {}
+
+impl Default for RigidMeshInstanceMotion {
+    fn default() -> Self {
+        Self {
+            kind: MotionKind::None,
+            angular_velocity: 0.0,
+            start_time: 0.0,
+            _padding: 0,
+            axis: Vector4::zeros(),
+            vector: Vector4::zeros(),
+            base_transform: Matrix4::identity(),
         }
     }
 }
@@ -227,6 +412,15 @@ impl From<elements::point_cloud::PointCloudRepresentation> for PointCloudReprese
     }
 }
 
+/// Determines how the points of a point cloud instance are rendered
+#[repr(u32)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum PointRenderMode {
+    #[default]
+    Triangles = 0,
+    Splats = 1,
+}
+
 #[repr(C)]
 #[derive(Default, Debug, Clone)]
 pub struct PointCloudCluster {
@@ -250,6 +444,12 @@ pub struct PointCloudCluster {
     pub padding: [u32; 3],
 }
 
+impl PointCloudCluster {
+    /// Set in `children_page_indices` when the page containing the child is not resident on the GPU. The
+    /// remaining bits contain the index of the page in the point cloud asset from which it can be streamed.
+    pub const NON_RESIDENT_PAGE_BIT: u32 = 1 << 31;
+}
+
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct PointCloudPage {
@@ -260,9 +460,110 @@ pub struct PointCloudPage {
     pub _padding: [u32; 2],
     pub point_positions: [Vector4<f32>; Page::MAX_POINTS],
     pub point_colors: [Vector4<f32>; Page::MAX_POINTS],
+    /// Normals of the points in xyz. The w component is unused.
+    pub point_normals: [Vector4<f32>; Page::MAX_POINTS],
     pub clusters: [PointCloudCluster; Page::MAX_CLUSTERS],
 }
 
+impl PointCloudPage {
+    /// Converts the `Page` into its GPU representation. The page indices of the children of the clusters are mapped with `map_child_page_index`.
+    pub fn new(page: &Page, map_child_page_index: impl Fn(usize) -> u32) -> Self {
+        let point_positions = page
+            .point_positions()
+            .iter()
+            .map(|v| Vector4::new(v.x, v.y, v.z, 0.0))
+            .chain(std::iter::repeat_n(
+                Vector4::zeros(),
+                Page::MAX_POINTS - page.point_positions().len(),
+            ))
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("point positions have wrong length");
+        let point_colors = page
+            .point_colors()
+            .iter()
+            .map(|v| v.as_vector4())
+            .chain(std::iter::repeat_n(Vector4::zeros(), Page::MAX_POINTS - page.point_colors().len()))
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("point colors have wrong length");
+        let point_normals = page
+            .point_normals()
+            .iter()
+            .map(|v| Vector4::new(v.x, v.y, v.z, 0.0))
+            .chain(std::iter::repeat_n(Vector4::zeros(), Page::MAX_POINTS - page.point_normals().len()))
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("point normals have wrong length");
+        let padding = std::iter::repeat_n(PointCloudCluster::default(), Page::MAX_CLUSTERS - page.clusters().len());
+        let clusters = page
+            .clusters()
+            .iter()
+            .map(|cluster| PointCloudCluster {
+                center_radius: Vector4::new(cluster.center.x, cluster.center.y, cluster.center.z, cluster.radius),
+                points_start_offset: cluster.index_start,
+                points_len: cluster.len,
+                level: cluster.level as u32,
+                depth: cluster.depth as u32,
+                children_count: cluster.children.len() as u32,
+                children_page_indices: cluster
+                    .children
+                    .iter()
+                    .map(|child| map_child_page_index(child.page_index))
+                    .chain(std::iter::repeat_n(u32::MAX, 2 - cluster.children.len()))
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .expect("clusters have wrong length"),
+                children_cluster_indices: cluster
+                    .children
+                    .iter()
+                    .map(|child| child.cluster_index as u32)
+                    .chain(std::iter::repeat_n(u32::MAX, 2 - cluster.children.len()))
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .expect("clusters have wrong length"),
+                padding: [0; 3],
+            })
+            .chain(padding)
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("clusters have wrong length");
+        Self {
+            points_len: page.point_positions().len() as u32,
+            clusters_len: page.clusters().len() as u32,
+            _padding: [0; 2],
+            point_positions,
+            point_colors,
+            point_normals,
+            clusters,
+        }
+    }
+
+    /// Byte offset of the `children_page_indices` of the cluster with the given `cluster_index` in the `PointCloudPage`
+    pub fn children_page_indices_offset(cluster_index: usize) -> usize {
+        mem::offset_of!(PointCloudPage, clusters)
+            + cluster_index * mem::size_of::<PointCloudCluster>()
+            + mem::offset_of!(PointCloudCluster, children_page_indices)
+    }
+}
+
+/// Request of the point cloud cluster culling for a page of a streamed point cloud that is not resident on the GPU
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct PointCloudPageRequest {
+    pub point_cloud_attributes_index: u32,
+    /// Index of the requested page in the point cloud asset
+    pub page_index: u32,
+    /// Point density on the screen of the cluster that would have been refined by the page. Pages with a lower density are loaded first.
+    pub density: f32,
+    pub padding: u32,
+}
+
+impl PointCloudPageRequest {
+    /// Number of `u32`s in front of the requests in the point cloud page requests buffer. The first one is the counter.
+    pub const HEADER_LEN: usize = 4;
+}
+
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct PointCloud {
@@ -286,17 +587,31 @@ impl Default for PointCloud {
 #[derive(Debug, Clone)]
 pub struct PointCloudInstance {
     pub point_cloud_index: u64,
-    pub _padding: u64,
+    pub point_render_mode: PointRenderMode,
+    /// Radius of the splats when the `point_render_mode` is [`PointRenderMode::Splats`]
+    pub point_radius: f32,
     pub transform: Matrix4<f32>,
 }
 
+impl PointCloudInstance {
+    /// Sets the `point_render_mode` and the `point_radius` from the [`PointRenderMode`](instances::point_cloud_instance::PointRenderMode) of the instance
+    pub fn set_point_render_mode(&mut self, point_render_mode: instances::point_cloud_instance::PointRenderMode) {
+        use instances::point_cloud_instance::PointRenderMode as InstancePointRenderMode;
+        (self.point_render_mode, self.point_radius) = match point_render_mode {
+            InstancePointRenderMode::Triangles => (PointRenderMode::Triangles, 0.0),
+            InstancePointRenderMode::Splats { radius } => (PointRenderMode::Splats, radius),
+        };
+    }
+}
+
 impl Represents<instances::point_cloud_instance::PointCloudInstance> for PointCloudInstance {}
 
 impl Default for PointCloudInstance {
     fn default() -> Self {
         Self {
             point_cloud_index: 0,
-            _padding: 0,
+            point_render_mode: PointRenderMode::default(),
+            point_radius: 0.0,
             transform: Matrix4::identity(),
         }
     }
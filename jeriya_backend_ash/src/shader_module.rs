@@ -3,10 +3,11 @@ use jeriya_shared::{AsDebugInfo, DebugInfo};
 
 use std::{io, sync::Arc};
 
-use crate::{device::Device, AsRawVulkan, DebugInfoAshExtension};
+use crate::{device::Device, spirv_reflection::ShaderReflection, AsRawVulkan, DebugInfoAshExtension, PhysicalDeviceFeature};
 
 pub struct ShaderModule {
     shader_module: vk::ShaderModule,
+    reflection: ShaderReflection,
     debug_info: DebugInfo,
     device: Arc<Device>,
 }
@@ -17,15 +18,33 @@ impl ShaderModule {
         R: io::Read + io::Seek,
     {
         let code = read_spv(&mut byte_code).map_err(|_| crate::Error::SpirvDecode)?;
+        let reflection = ShaderReflection::new(&code)?;
+        if reflection.uses_clip_distance && !device.optional_features.shader_clip_distance {
+            return Err(crate::Error::PhysicalDeviceFeatureMissing(
+                PhysicalDeviceFeature::ShaderClipDistance,
+            ));
+        }
+        if reflection.uses_runtime_descriptor_array && !device.optional_features.descriptor_indexing {
+            return Err(crate::Error::PhysicalDeviceFeatureMissing(
+                PhysicalDeviceFeature::DescriptorIndexing,
+            ));
+        }
         let shader_info = vk::ShaderModuleCreateInfo::builder().code(&code);
         let shader_module = unsafe { device.as_raw_vulkan().create_shader_module(&shader_info, None)? };
         let debug_info = debug_info.with_vulkan_ptr(shader_module);
+        device.set_debug_name(shader_module, &debug_info);
         Ok(Self {
             shader_module,
+            reflection,
             debug_info,
             device: device.clone(),
         })
     }
+
+    /// Returns the stage and the buffer bindings that are declared in the SPIR-V code
+    pub fn reflection(&self) -> &ShaderReflection {
+        &self.reflection
+    }
 }
 
 impl Drop for ShaderModule {
@@ -65,5 +84,15 @@ mod tests {
             let _shader_module =
                 ShaderModule::new(&test_fixture_device.device, Cursor::new(&spirv), debug_info!("my_shader_module")).unwrap();
         }
+
+        #[test]
+        fn reflection() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let spirv = include_bytes!("../test_data/color.vert.spv").to_vec();
+            let shader_module =
+                ShaderModule::new(&test_fixture_device.device, Cursor::new(&spirv), debug_info!("my_shader_module")).unwrap();
+            assert_eq!(shader_module.reflection().stage, ash::vk::ShaderStageFlags::VERTEX);
+            assert!(!shader_module.reflection().bindings.is_empty());
+        }
     }
 }
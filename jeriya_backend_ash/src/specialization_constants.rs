@@ -1,4 +1,4 @@
-use std::mem;
+use std::{collections::BTreeMap, mem};
 
 use ash::vk;
 use jeriya_shared::byteorder::{LittleEndian, WriteBytesExt};
@@ -25,6 +25,78 @@ impl PushSpecializationConstant for u32 {
     }
 }
 
+/// Specialization constant that is declared in the shaders with a fixed `constant_id`
+pub trait SpecializationConstant {
+    /// The `constant_id` with which the constant is declared in the shaders
+    const CONSTANT_ID: u32;
+
+    /// Type of the value in Rust. Every value is passed to the shader as a 32-bit value.
+    type Value: Into<u32>;
+}
+
+/// Size of the workgroup in x direction. Declared in the shaders as `layout (local_size_x_id = 16) in;`
+pub struct LocalSizeX;
+
+impl SpecializationConstant for LocalSizeX {
+    const CONSTANT_ID: u32 = 16;
+    type Value = u32;
+}
+
+/// Whether the text and immediate pipelines place the vertices on the screen instead of in the world. Declared in the shaders as
+/// `layout (constant_id = 18) const bool SCREEN_SPACE = false;`
+pub struct ScreenSpace;
+
+impl SpecializationConstant for ScreenSpace {
+    const CONSTANT_ID: u32 = 18;
+    type Value = bool;
+}
+
+/// Color space into which the output transform encodes the rendered image. Declared in the shaders as
+/// `layout (constant_id = 19) const uint OUTPUT_COLOR_SPACE = 0;` with 0 for SDR, 1 for HDR10 and 2 for scRGB.
+pub struct OutputColorSpace;
+
+impl SpecializationConstant for OutputColorSpace {
+    const CONSTANT_ID: u32 = 19;
+    type Value = u32;
+}
+
+/// Whether the immediate point pipeline expands every point into a quad of two triangles because the device doesn't
+/// support large points. Declared in the shaders as `layout (constant_id = 20) const bool EXPAND_POINTS = false;`
+pub struct ExpandPoints;
+
+impl SpecializationConstant for ExpandPoints {
+    const CONSTANT_ID: u32 = 20;
+    type Value = bool;
+}
+
+/// Whether the text pipeline places the glyphs in pixels around the projected position of the label. Declared in the
+/// shaders as `layout (constant_id = 21) const bool BILLBOARD = false;`
+pub struct Billboard;
+
+impl SpecializationConstant for Billboard {
+    const CONSTANT_ID: u32 = 21;
+    type Value = bool;
+}
+
+/// Specialization constants that are specific to a single pipeline and are part of its config
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct PipelineSpecializationConstants {
+    values: BTreeMap<u32, u32>,
+}
+
+impl PipelineSpecializationConstants {
+    /// Sets the value of the specialization constant `C`
+    pub fn with<C: SpecializationConstant>(mut self, value: C::Value) -> Self {
+        self.values.insert(C::CONSTANT_ID, value.into());
+        self
+    }
+
+    /// Returns the value of the specialization constant `C` when it has been set
+    pub fn get<C: SpecializationConstant>(&self) -> Option<u32> {
+        self.values.get(&C::CONSTANT_ID).copied()
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct SpecializationConstants {
     map_entries: Vec<vk::SpecializationMapEntry>,
@@ -82,6 +154,26 @@ impl SpecializationConstants {
         })
     }
 
+    /// Returns a copy of the `SpecializationConstants` that additionally contains the given pipeline specific constants
+    ///
+    /// A pipeline specific constant replaces the value of a constant with the same `constant_id`.
+    pub fn extended_with(&self, pipeline_specialization_constants: &PipelineSpecializationConstants) -> Self {
+        let mut result = self.clone();
+        for (constant_id, value) in &pipeline_specialization_constants.values {
+            let existing_entry = result.map_entries.iter().find(|entry| entry.constant_id == *constant_id);
+            match existing_entry {
+                Some(entry) => {
+                    let offset = entry.offset as usize;
+                    result.data[offset..offset + mem::size_of::<u32>()].copy_from_slice(&value.to_le_bytes());
+                }
+                None => {
+                    result.push(*constant_id, *value);
+                }
+            }
+        }
+        result
+    }
+
     /// Returns the map entries
     pub fn map_entries(&self) -> &[vk::SpecializationMapEntry] {
         &self.map_entries
@@ -119,4 +211,25 @@ mod tests {
         assert_eq!(specialization_constants.read_u32(0).unwrap().unwrap(), 73);
         assert_eq!(specialization_constants.read_u32(2).unwrap().unwrap(), 12);
     }
+
+    #[test]
+    fn extended_with() {
+        let mut specialization_constants = SpecializationConstants::new();
+        specialization_constants.push(0, 73u32);
+        specialization_constants.push(LocalSizeX::CONSTANT_ID, 12u32);
+
+        let pipeline_specialization_constants = PipelineSpecializationConstants::default()
+            .with::<LocalSizeX>(128)
+            .with::<ScreenSpace>(true);
+        assert_eq!(pipeline_specialization_constants.get::<LocalSizeX>(), Some(128));
+
+        let extended = specialization_constants.extended_with(&pipeline_specialization_constants);
+        assert_eq!(extended.len(), 3);
+        assert_eq!(extended.read_u32(0).unwrap().unwrap(), 73);
+        assert_eq!(extended.read_u32(LocalSizeX::CONSTANT_ID).unwrap().unwrap(), 128);
+        assert_eq!(extended.read_u32(ScreenSpace::CONSTANT_ID).unwrap().unwrap(), 1);
+
+        // The original constants are not modified
+        assert_eq!(specialization_constants.read_u32(LocalSizeX::CONSTANT_ID).unwrap().unwrap(), 12);
+    }
 }
@@ -0,0 +1,570 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ash::vk;
+
+const MAGIC_NUMBER: u32 = 0x07230203;
+const HEADER_WORD_COUNT: usize = 5;
+
+#[cfg(test)]
+const OP_NAME: u32 = 5;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_CAPABILITY: u32 = 17;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+#[cfg(test)]
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+#[cfg(test)]
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_BUILT_IN: u32 = 11;
+#[cfg(test)]
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+#[cfg(test)]
+const DECORATION_LOCATION: u32 = 30;
+#[cfg(test)]
+const DECORATION_OFFSET: u32 = 35;
+
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+
+const CAPABILITY_CLIP_DISTANCE: u32 = 32;
+const CAPABILITY_RUNTIME_DESCRIPTOR_ARRAY: u32 = 5302;
+
+const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+#[cfg(test)]
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+const EXECUTION_MODEL_GL_COMPUTE: u32 = 5;
+
+/// Buffer or image binding that is declared in a shader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflectedBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub descriptor_count: u32,
+}
+
+/// Memory layout of a struct that is stored in an array in a buffer
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflectedArrayLayout {
+    /// Byte offsets of the struct members in the order of their declaration
+    pub member_offsets: Vec<u32>,
+    /// Distance in bytes between two consecutive elements of the array
+    pub array_stride: u32,
+}
+
+/// Information about the resource interface of a shader that is extracted from its SPIR-V code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShaderReflection {
+    pub stage: vk::ShaderStageFlags,
+    pub bindings: Vec<ReflectedBinding>,
+    /// Id of the specialization constant that sets the x component of the workgroup size of a compute shader
+    pub workgroup_size_x_spec_id: Option<u32>,
+    /// Whether the shader declares the `ClipDistance` capability which requires the `shaderClipDistance` feature
+    pub uses_clip_distance: bool,
+    /// Whether the shader declares the `RuntimeDescriptorArray` capability like the shaders that sample the bindless textures
+    pub uses_runtime_descriptor_array: bool,
+}
+
+impl ShaderReflection {
+    /// Extracts the shader stage, the buffer and image bindings and the specialization of the workgroup size from the given SPIR-V code
+    ///
+    /// Only uniform buffers, storage buffers and combined image samplers are reflected because the backend doesn't
+    /// push other resources. Runtime arrays of images like the bindless textures are skipped because they are bound separately.
+    pub fn new(code: &[u32]) -> crate::Result<Self> {
+        if code.len() < HEADER_WORD_COUNT || code[0] != MAGIC_NUMBER {
+            return Err(crate::Error::SpirvDecode);
+        }
+
+        let mut stage = vk::ShaderStageFlags::empty();
+        let mut sets = HashMap::new();
+        let mut bindings = HashMap::new();
+        let mut blocks = HashSet::new();
+        let mut buffer_blocks = HashSet::new();
+        let mut sampled_images = HashSet::new();
+        let mut constants = HashMap::new();
+        let mut arrays = HashMap::new();
+        let mut pointers = HashMap::new();
+        let mut variables = Vec::new();
+        let mut spec_ids = HashMap::new();
+        let mut workgroup_size = None;
+        let mut spec_constant_composites = HashMap::new();
+        let mut uses_clip_distance = false;
+        let mut uses_runtime_descriptor_array = false;
+
+        let mut offset = HEADER_WORD_COUNT;
+        while offset < code.len() {
+            let word_count = (code[offset] >> 16) as usize;
+            let opcode = code[offset] & 0xffff;
+            if word_count == 0 || offset + word_count > code.len() {
+                return Err(crate::Error::SpirvDecode);
+            }
+            let operands = &code[offset + 1..offset + word_count];
+            match (opcode, operands) {
+                (OP_ENTRY_POINT, [execution_model, ..]) => {
+                    stage |= match *execution_model {
+                        EXECUTION_MODEL_VERTEX => vk::ShaderStageFlags::VERTEX,
+                        EXECUTION_MODEL_FRAGMENT => vk::ShaderStageFlags::FRAGMENT,
+                        EXECUTION_MODEL_GL_COMPUTE => vk::ShaderStageFlags::COMPUTE,
+                        _ => vk::ShaderStageFlags::ALL,
+                    };
+                }
+                (OP_CAPABILITY, [CAPABILITY_CLIP_DISTANCE]) => {
+                    uses_clip_distance = true;
+                }
+                (OP_CAPABILITY, [CAPABILITY_RUNTIME_DESCRIPTOR_ARRAY]) => {
+                    uses_runtime_descriptor_array = true;
+                }
+                (OP_DECORATE, [target, DECORATION_DESCRIPTOR_SET, set, ..]) => {
+                    sets.insert(*target, *set);
+                }
+                (OP_DECORATE, [target, DECORATION_BINDING, binding, ..]) => {
+                    bindings.insert(*target, *binding);
+                }
+                (OP_DECORATE, [target, DECORATION_SPEC_ID, spec_id]) => {
+                    spec_ids.insert(*target, *spec_id);
+                }
+                (OP_DECORATE, [target, DECORATION_BUILT_IN, BUILT_IN_WORKGROUP_SIZE]) => {
+                    workgroup_size = Some(*target);
+                }
+                (OP_DECORATE, [target, DECORATION_BLOCK, ..]) => {
+                    blocks.insert(*target);
+                }
+                (OP_DECORATE, [target, DECORATION_BUFFER_BLOCK, ..]) => {
+                    buffer_blocks.insert(*target);
+                }
+                (OP_CONSTANT | OP_SPEC_CONSTANT, [_result_type, result_id, value, ..]) => {
+                    constants.insert(*result_id, *value);
+                }
+                (OP_SPEC_CONSTANT_COMPOSITE, [_result_type, result_id, x, ..]) => {
+                    spec_constant_composites.insert(*result_id, *x);
+                }
+                (OP_TYPE_SAMPLED_IMAGE, [result_id, _image_type]) => {
+                    sampled_images.insert(*result_id);
+                }
+                (OP_TYPE_ARRAY, [result_id, element_type, length]) => {
+                    arrays.insert(*result_id, (*element_type, *length));
+                }
+                (OP_TYPE_POINTER, [result_id, storage_class, pointee_type]) => {
+                    pointers.insert(*result_id, (*storage_class, *pointee_type));
+                }
+                (OP_VARIABLE, [result_type, result_id, storage_class, ..]) => {
+                    variables.push((*result_type, *result_id, *storage_class));
+                }
+                _ => {}
+            }
+            offset += word_count;
+        }
+
+        let mut reflected_bindings = Vec::new();
+        for (result_type, result_id, storage_class) in variables {
+            if ![STORAGE_CLASS_UNIFORM_CONSTANT, STORAGE_CLASS_UNIFORM, STORAGE_CLASS_STORAGE_BUFFER].contains(&storage_class) {
+                continue;
+            }
+            let (Some(set), Some(binding)) = (sets.get(&result_id), bindings.get(&result_id)) else {
+                continue;
+            };
+            let Some((_, mut block_type)) = pointers.get(&result_type).copied() else {
+                return Err(crate::Error::SpirvDecode);
+            };
+
+            // Arrays of buffers are bound with a descriptor count that is the length of the array
+            let mut descriptor_count = 1;
+            if let Some((element_type, length)) = arrays.get(&block_type) {
+                descriptor_count = *constants.get(length).ok_or(crate::Error::SpirvDecode)?;
+                block_type = *element_type;
+            }
+
+            let descriptor_type = match storage_class {
+                STORAGE_CLASS_UNIFORM_CONSTANT if sampled_images.contains(&block_type) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                STORAGE_CLASS_UNIFORM_CONSTANT => continue,
+                STORAGE_CLASS_STORAGE_BUFFER => vk::DescriptorType::STORAGE_BUFFER,
+                _ if buffer_blocks.contains(&block_type) => vk::DescriptorType::STORAGE_BUFFER,
+                _ if blocks.contains(&block_type) => vk::DescriptorType::UNIFORM_BUFFER,
+                _ => continue,
+            };
+            reflected_bindings.push(ReflectedBinding {
+                set: *set,
+                binding: *binding,
+                descriptor_type,
+                descriptor_count,
+            });
+        }
+        reflected_bindings.sort_by_key(|reflected_binding| (reflected_binding.set, reflected_binding.binding));
+
+        // The workgroup size is only specialized when it is a composite of specialization constants
+        let workgroup_size_x_spec_id = workgroup_size
+            .and_then(|workgroup_size| spec_constant_composites.get(&workgroup_size))
+            .and_then(|x| spec_ids.get(x))
+            .copied();
+
+        Ok(Self {
+            stage,
+            bindings: reflected_bindings,
+            workgroup_size_x_spec_id,
+            uses_clip_distance,
+            uses_runtime_descriptor_array,
+        })
+    }
+
+    /// Returns the layout of the arrays whose elements are structs with the given name
+    ///
+    /// Only structs with explicit offsets are considered, which are the ones that are declared in buffers. Returns
+    /// `None` when no such array is declared. The struct name is only known when the shader contains debug names.
+    #[cfg(test)]
+    pub fn array_layout(code: &[u32], struct_name: &str) -> crate::Result<Option<ReflectedArrayLayout>> {
+        if code.len() < HEADER_WORD_COUNT || code[0] != MAGIC_NUMBER {
+            return Err(crate::Error::SpirvDecode);
+        }
+
+        let mut struct_ids = HashSet::new();
+        let mut member_offsets = HashMap::<u32, BTreeMap<u32, u32>>::new();
+        let mut array_element_types = HashMap::new();
+        let mut array_strides = HashMap::new();
+
+        let mut offset = HEADER_WORD_COUNT;
+        while offset < code.len() {
+            let word_count = (code[offset] >> 16) as usize;
+            let opcode = code[offset] & 0xffff;
+            if word_count == 0 || offset + word_count > code.len() {
+                return Err(crate::Error::SpirvDecode);
+            }
+            let operands = &code[offset + 1..offset + word_count];
+            match (opcode, operands) {
+                (OP_NAME, [target, name @ ..]) if decode_string(name) == struct_name => {
+                    struct_ids.insert(*target);
+                }
+                (OP_MEMBER_DECORATE, [target, member, DECORATION_OFFSET, member_offset]) => {
+                    member_offsets.entry(*target).or_default().insert(*member, *member_offset);
+                }
+                (OP_TYPE_ARRAY, [result_id, element_type, _]) | (OP_TYPE_RUNTIME_ARRAY, [result_id, element_type]) => {
+                    array_element_types.insert(*result_id, *element_type);
+                }
+                (OP_DECORATE, [target, DECORATION_ARRAY_STRIDE, array_stride]) => {
+                    array_strides.insert(*target, *array_stride);
+                }
+                _ => {}
+            }
+            offset += word_count;
+        }
+
+        let mut array_layout = None;
+        for (array_id, element_type) in array_element_types {
+            let (true, Some(offsets), Some(array_stride)) = (
+                struct_ids.contains(&element_type),
+                member_offsets.get(&element_type),
+                array_strides.get(&array_id),
+            ) else {
+                continue;
+            };
+            let layout = ReflectedArrayLayout {
+                member_offsets: offsets.values().copied().collect(),
+                array_stride: *array_stride,
+            };
+            // The same struct must have the same layout in every buffer
+            if array_layout.as_ref().is_some_and(|array_layout| *array_layout != layout) {
+                return Err(crate::Error::SpirvDecode);
+            }
+            array_layout = Some(layout);
+        }
+        Ok(array_layout)
+    }
+
+    /// Returns the sorted locations of the input variables of the shader
+    ///
+    /// Built-in inputs like `gl_VertexIndex` don't have a location and are skipped.
+    #[cfg(test)]
+    pub fn input_locations(code: &[u32]) -> crate::Result<Vec<u32>> {
+        if code.len() < HEADER_WORD_COUNT || code[0] != MAGIC_NUMBER {
+            return Err(crate::Error::SpirvDecode);
+        }
+
+        let mut locations = HashMap::new();
+        let mut inputs = HashSet::new();
+
+        let mut offset = HEADER_WORD_COUNT;
+        while offset < code.len() {
+            let word_count = (code[offset] >> 16) as usize;
+            let opcode = code[offset] & 0xffff;
+            if word_count == 0 || offset + word_count > code.len() {
+                return Err(crate::Error::SpirvDecode);
+            }
+            let operands = &code[offset + 1..offset + word_count];
+            match (opcode, operands) {
+                (OP_DECORATE, [target, DECORATION_LOCATION, location]) => {
+                    locations.insert(*target, *location);
+                }
+                (OP_VARIABLE, [_result_type, result_id, STORAGE_CLASS_INPUT, ..]) => {
+                    inputs.insert(*result_id);
+                }
+                _ => {}
+            }
+            offset += word_count;
+        }
+
+        let mut input_locations = inputs.iter().filter_map(|input| locations.get(input).copied()).collect::<Vec<_>>();
+        input_locations.sort();
+        Ok(input_locations)
+    }
+
+    /// Merges the bindings of multiple shaders that are used in the same pipeline
+    ///
+    /// Returns the bindings together with the stages in which they are used. Fails when two shaders
+    /// declare the same binding with different descriptor types or counts.
+    pub fn merge<'a>(
+        reflections: impl IntoIterator<Item = &'a ShaderReflection>,
+    ) -> crate::Result<Vec<(ReflectedBinding, vk::ShaderStageFlags)>> {
+        let mut merged = BTreeMap::<(u32, u32), (ReflectedBinding, vk::ShaderStageFlags)>::new();
+        for reflection in reflections {
+            for reflected_binding in &reflection.bindings {
+                let key = (reflected_binding.set, reflected_binding.binding);
+                match merged.get_mut(&key) {
+                    Some((existing, stage)) => {
+                        if existing != reflected_binding {
+                            return Err(crate::Error::DescriptorMismatch {
+                                binding: reflected_binding.binding,
+                                details: format!("the shader stages declare it differently: {existing:?} and {reflected_binding:?}"),
+                            });
+                        }
+                        *stage |= reflection.stage;
+                    }
+                    None => {
+                        merged.insert(key, (reflected_binding.clone(), reflection.stage));
+                    }
+                }
+            }
+        }
+        Ok(merged.into_values().collect())
+    }
+}
+
+/// Decodes a nul-terminated UTF-8 string literal
+#[cfg(test)]
+fn decode_string(words: &[u32]) -> String {
+    let bytes = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|byte| *byte != 0)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    mod new {
+        use std::io::Cursor;
+
+        use ash::{util::read_spv, vk};
+
+        use crate::{
+            specialization_constants::{LocalSizeX, SpecializationConstant},
+            spirv_reflection::{ReflectedBinding, ShaderReflection},
+        };
+
+        fn reflect(spirv: &[u8]) -> ShaderReflection {
+            let code = read_spv(&mut Cursor::new(spirv)).unwrap();
+            ShaderReflection::new(&code).unwrap()
+        }
+
+        #[test]
+        fn smoke() {
+            let reflection = reflect(include_bytes!("../test_data/color.vert.spv"));
+            assert_eq!(reflection.stage, vk::ShaderStageFlags::VERTEX);
+            assert_eq!(
+                reflection.bindings.first(),
+                Some(&ReflectedBinding {
+                    set: 0,
+                    binding: 0,
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                    descriptor_count: 1,
+                })
+            );
+            assert!(reflection.bindings[1..]
+                .iter()
+                .all(|binding| binding.descriptor_type == vk::DescriptorType::STORAGE_BUFFER));
+        }
+
+        #[test]
+        fn compute() {
+            let reflection = reflect(include_bytes!("../test_data/cull_rigid_mesh_instances.comp.spv"));
+            assert_eq!(reflection.stage, vk::ShaderStageFlags::COMPUTE);
+            assert!(!reflection.bindings.is_empty());
+        }
+
+        #[test]
+        fn workgroup_size_x_spec_id() {
+            let shaders: [&[u8]; 2] = [
+                include_bytes!("../test_data/cull_rigid_mesh_instances.comp.spv"),
+                include_bytes!("../test_data/cull_point_cloud_instances.comp.spv"),
+            ];
+            for shader in shaders {
+                assert_eq!(reflect(shader).workgroup_size_x_spec_id, Some(LocalSizeX::CONSTANT_ID));
+            }
+            let reflection = reflect(include_bytes!("../test_data/cull_rigid_mesh_meshlets.comp.spv"));
+            assert_eq!(reflection.workgroup_size_x_spec_id, None);
+        }
+
+        #[test]
+        fn uses_clip_distance() {
+            let spirv = include_bytes!("../test_data/red_triangle.vert.spv");
+            assert!(!reflect(spirv).uses_clip_distance);
+
+            // OpCapability ClipDistance directly after the header
+            let mut code = read_spv(&mut Cursor::new(spirv)).unwrap();
+            code.splice(5..5, [2 << 16 | 17, 32]);
+            assert!(ShaderReflection::new(&code).unwrap().uses_clip_distance);
+        }
+
+        #[test]
+        fn uses_runtime_descriptor_array() {
+            let spirv = include_bytes!("../test_data/red_triangle.frag.spv");
+            assert!(!reflect(spirv).uses_runtime_descriptor_array);
+
+            // OpCapability RuntimeDescriptorArray directly after the header
+            let mut code = read_spv(&mut Cursor::new(spirv)).unwrap();
+            code.splice(5..5, [2 << 16 | 17, 5302]);
+            assert!(ShaderReflection::new(&code).unwrap().uses_runtime_descriptor_array);
+        }
+
+        #[test]
+        fn invalid_magic_number() {
+            assert!(ShaderReflection::new(&[0, 0, 0, 0, 0]).is_err());
+        }
+    }
+
+    mod merge {
+        use ash::vk;
+
+        use crate::spirv_reflection::{ReflectedBinding, ShaderReflection};
+
+        fn binding(binding: u32, descriptor_type: vk::DescriptorType) -> ReflectedBinding {
+            ReflectedBinding {
+                set: 0,
+                binding,
+                descriptor_type,
+                descriptor_count: 1,
+            }
+        }
+
+        #[test]
+        fn combines_stages() {
+            let vertex = ShaderReflection {
+                stage: vk::ShaderStageFlags::VERTEX,
+                bindings: vec![binding(0, vk::DescriptorType::UNIFORM_BUFFER)],
+                ..Default::default()
+            };
+            let fragment = ShaderReflection {
+                stage: vk::ShaderStageFlags::FRAGMENT,
+                bindings: vec![
+                    binding(0, vk::DescriptorType::UNIFORM_BUFFER),
+                    binding(1, vk::DescriptorType::STORAGE_BUFFER),
+                ],
+                ..Default::default()
+            };
+            let merged = ShaderReflection::merge([&vertex, &fragment]).unwrap();
+            assert_eq!(
+                merged,
+                vec![
+                    (
+                        binding(0, vk::DescriptorType::UNIFORM_BUFFER),
+                        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT
+                    ),
+                    (binding(1, vk::DescriptorType::STORAGE_BUFFER), vk::ShaderStageFlags::FRAGMENT),
+                ]
+            );
+        }
+
+        #[test]
+        fn conflicting_types() {
+            let vertex = ShaderReflection {
+                stage: vk::ShaderStageFlags::VERTEX,
+                bindings: vec![binding(0, vk::DescriptorType::UNIFORM_BUFFER)],
+                ..Default::default()
+            };
+            let fragment = ShaderReflection {
+                stage: vk::ShaderStageFlags::FRAGMENT,
+                bindings: vec![binding(0, vk::DescriptorType::STORAGE_BUFFER)],
+                ..Default::default()
+            };
+            assert!(ShaderReflection::merge([&vertex, &fragment]).is_err());
+        }
+    }
+
+    mod array_layout {
+        use std::{io::Cursor, mem};
+
+        use ash::util::read_spv;
+
+        use crate::{shader_interface, spirv_reflection::ShaderReflection};
+
+        #[test]
+        fn rigid_mesh_instance_matches_shader_interface() {
+            let shaders: [&[u8]; 11] = [
+                include_bytes!("../test_data/color.vert.spv"),
+                include_bytes!("../test_data/cull_point_cloud_clusters.comp.spv"),
+                include_bytes!("../test_data/cull_point_cloud_instances.comp.spv"),
+                include_bytes!("../test_data/cull_rigid_mesh_instances.comp.spv"),
+                include_bytes!("../test_data/cull_rigid_mesh_meshlets.comp.spv"),
+                include_bytes!("../test_data/device_local_debug_line.vert.spv"),
+                include_bytes!("../test_data/frame_telemetry.comp.spv"),
+                include_bytes!("../test_data/indirect_meshlet.vert.spv"),
+                include_bytes!("../test_data/indirect_simple.vert.spv"),
+                include_bytes!("../test_data/point_cloud.vert.spv"),
+                include_bytes!("../test_data/point_cloud_cluster.vert.spv"),
+            ];
+            let expected_member_offsets = vec![
+                mem::offset_of!(shader_interface::RigidMeshInstance, rigid_mesh_index) as u32,
+                mem::offset_of!(shader_interface::RigidMeshInstance, transform) as u32,
+                mem::offset_of!(shader_interface::RigidMeshInstance, color) as u32,
+            ];
+            for shader in shaders {
+                let code = read_spv(&mut Cursor::new(shader)).unwrap();
+                let array_layout = ShaderReflection::array_layout(&code, "RigidMeshInstance").unwrap().unwrap();
+                assert_eq!(
+                    array_layout.array_stride as usize,
+                    mem::size_of::<shader_interface::RigidMeshInstance>()
+                );
+                assert_eq!(array_layout.member_offsets, expected_member_offsets);
+            }
+        }
+
+        #[test]
+        fn not_declared() {
+            let code = read_spv(&mut Cursor::new(include_bytes!("../test_data/red_triangle.vert.spv"))).unwrap();
+            assert_eq!(ShaderReflection::array_layout(&code, "RigidMeshInstance").unwrap(), None);
+        }
+    }
+
+    mod input_locations {
+        use std::io::Cursor;
+
+        use ash::util::read_spv;
+
+        use crate::spirv_reflection::ShaderReflection;
+
+        #[test]
+        fn color_shaders_read_the_vertex_color() {
+            // The position is at location 0 and the color at location 2 like in `ImmediateVertexBuffers`
+            let code = read_spv(&mut Cursor::new(include_bytes!("../test_data/color.vert.spv"))).unwrap();
+            assert_eq!(ShaderReflection::input_locations(&code).unwrap(), vec![0, 2]);
+            let code = read_spv(&mut Cursor::new(include_bytes!("../test_data/color.frag.spv"))).unwrap();
+            assert_eq!(ShaderReflection::input_locations(&code).unwrap(), vec![0]);
+        }
+    }
+}
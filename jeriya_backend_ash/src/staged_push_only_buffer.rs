@@ -1,4 +1,4 @@
-use std::{mem, sync::Arc};
+use std::{mem, ops::Range, sync::Arc};
 
 use crate::{
     buffer::{Buffer, BufferUsageFlags, GeneralBuffer},
@@ -11,11 +11,35 @@ use crate::{
 use ash::vk;
 use jeriya_shared::{debug_info, AsDebugInfo, DebugInfo};
 
+/// Describes that the elements in `old_range` have been moved to `new_start` by [`StagedPushOnlyBuffer::compact`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    pub old_range: Range<usize>,
+    pub new_start: usize,
+}
+
+impl Relocation {
+    /// Returns the new offset of the element at `offset`. Offsets that haven't been relocated are returned unchanged.
+    pub fn relocate(relocations: &[Relocation], offset: usize) -> usize {
+        relocations
+            .iter()
+            .find(|relocation| relocation.old_range.contains(&offset))
+            .map(|relocation| relocation.new_start + (offset - relocation.old_range.start))
+            .unwrap_or(offset)
+    }
+}
+
 /// Device visible buffer of a constant size which can be filled by pushing chunks of data to it via a staging buffer.
+///
+/// Chunks that are no longer needed can be freed. Freed ranges are reused by subsequent pushes and can be
+/// reclaimed completely by compacting the buffer.
 pub struct StagedPushOnlyBuffer<T> {
     device_visible_buffer: Arc<DeviceVisibleBuffer<T>>,
+    device_buffer_usage_flags: BufferUsageFlags,
     capacity: usize,
     len: usize,
+    /// Freed ranges below `len` sorted by their start
+    free_ranges: Vec<Range<usize>>,
     device: Arc<Device>,
     debug_info: DebugInfo,
 }
@@ -28,17 +52,16 @@ impl<T: Clone + 'static + Send + Sync> StagedPushOnlyBuffer<T> {
         device_buffer_usage_flags: BufferUsageFlags,
         debug_info: DebugInfo,
     ) -> crate::Result<Self> {
-        let device_visible_buffer = DeviceVisibleBuffer::new(
-            device,
-            size * mem::size_of::<T>(),
-            device_buffer_usage_flags | BufferUsageFlags::TRANSFER_DST_BIT | BufferUsageFlags::TRANSFER_SRC_BIT,
-            debug_info.clone(),
-        )?;
+        let device_buffer_usage_flags = device_buffer_usage_flags | BufferUsageFlags::TRANSFER_DST_BIT | BufferUsageFlags::TRANSFER_SRC_BIT;
+        let device_visible_buffer =
+            DeviceVisibleBuffer::new(device, size * mem::size_of::<T>(), device_buffer_usage_flags, debug_info.clone())?;
         Ok(Self {
             device_visible_buffer,
+            device_buffer_usage_flags,
             device: device.clone(),
             capacity: size,
             len: 0,
+            free_ranges: Vec::new(),
             debug_info,
         })
     }
@@ -48,9 +71,16 @@ impl<T: Clone + 'static + Send + Sync> StagedPushOnlyBuffer<T> {
         if data.is_empty() {
             return Ok(None);
         }
-        if self.len + data.len() > self.capacity {
-            return Err(Error::WouldOverflow);
-        }
+        let offset = match self.take_free_range(data.len()) {
+            Some(offset) => offset,
+            None => {
+                if self.len + data.len() > self.capacity {
+                    return Err(Error::WouldOverflow);
+                }
+                self.len += data.len();
+                self.len - data.len()
+            }
+        };
         let host_visible_buffer = Arc::new(HostVisibleBuffer::<T>::new(
             &self.device,
             data,
@@ -60,7 +90,7 @@ impl<T: Clone + 'static + Send + Sync> StagedPushOnlyBuffer<T> {
 
         // Copy the data from the host visible buffer to the device visible buffer
         let data_byte_size = mem::size_of_val(data);
-        let data_offset = self.len * mem::size_of::<T>();
+        let data_offset = offset * mem::size_of::<T>();
         let command_buffer = command_buffer_builder.command_buffer();
         unsafe {
             let copy_region = vk::BufferCopy {
@@ -77,11 +107,172 @@ impl<T: Clone + 'static + Send + Sync> StagedPushOnlyBuffer<T> {
             command_buffer.push_dependency(host_visible_buffer.clone());
             command_buffer.push_dependency(self.device_visible_buffer.clone());
         }
-        let offset = self.len;
-        self.len += data.len();
+        self.device.record_upload(data_byte_size);
         Ok(Some(offset))
     }
 
+    /// Overwrites the bytes at `byte_offset` inside of the element at `index` with `value` and issues the copy command
+    /// to the [`CommandBufferBuilder`]. This is used to patch single fields of elements that have been pushed before.
+    ///
+    /// # Panics
+    ///
+    /// * If `index` is not smaller than the length of the buffer.
+    /// * If `value` doesn't fit into the element at `byte_offset`.
+    pub fn write_at<U: Clone + 'static + Send + Sync>(
+        &mut self,
+        index: usize,
+        byte_offset: usize,
+        value: &U,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<()> {
+        assert!(index < self.len, "index {index} is out of bounds for length {}", self.len);
+        assert!(
+            byte_offset + mem::size_of::<U>() <= mem::size_of::<T>(),
+            "value doesn't fit into the element at byte offset {byte_offset}"
+        );
+        let host_visible_buffer = Arc::new(HostVisibleBuffer::<U>::new(
+            &self.device,
+            std::slice::from_ref(value),
+            BufferUsageFlags::TRANSFER_SRC_BIT,
+            debug_info!("PushOnlyBuffer-write_at"),
+        )?);
+
+        // Copy the value from the host visible buffer into the element of the device visible buffer
+        let command_buffer = command_buffer_builder.command_buffer();
+        unsafe {
+            let copy_region = vk::BufferCopy {
+                src_offset: 0,
+                dst_offset: (index * mem::size_of::<T>() + byte_offset) as u64,
+                size: mem::size_of::<U>() as u64,
+            };
+            self.device.as_raw_vulkan().cmd_copy_buffer(
+                *command_buffer.as_raw_vulkan(),
+                *host_visible_buffer.as_raw_vulkan(),
+                *self.device_visible_buffer.as_raw_vulkan(),
+                &[copy_region],
+            );
+            command_buffer.push_dependency(host_visible_buffer.clone());
+            command_buffer.push_dependency(self.device_visible_buffer.clone());
+        }
+        self.device.record_upload(mem::size_of::<U>());
+        Ok(())
+    }
+
+    /// Takes the first free range that fits `len` elements and returns its start
+    fn take_free_range(&mut self, len: usize) -> Option<usize> {
+        let index = self.free_ranges.iter().position(|range| range.len() >= len)?;
+        let range = &mut self.free_ranges[index];
+        let start = range.start;
+        range.start += len;
+        if range.start == range.end {
+            self.free_ranges.remove(index);
+        }
+        Some(start)
+    }
+
+    /// Frees the elements in `range` so that they can be reused by subsequent pushes.
+    ///
+    /// The caller must make sure that the GPU doesn't access the elements anymore when they are reused.
+    pub fn free(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        jeriya_shared::assert!(range.end <= self.len, "the range must have been pushed to the buffer");
+        let index = self.free_ranges.partition_point(|free_range| free_range.start < range.start);
+        jeriya_shared::assert!(
+            index == 0 || self.free_ranges[index - 1].end <= range.start,
+            "the range has already been freed"
+        );
+        self.free_ranges.insert(index, range);
+
+        // Merge with the adjacent free ranges
+        if index + 1 < self.free_ranges.len() && self.free_ranges[index].end == self.free_ranges[index + 1].start {
+            let next = self.free_ranges.remove(index + 1);
+            self.free_ranges[index].end = next.end;
+        }
+        if index > 0 && self.free_ranges[index - 1].end == self.free_ranges[index].start {
+            let current = self.free_ranges.remove(index);
+            self.free_ranges[index - 1].end = current.end;
+        }
+
+        // A free range at the end of the buffer is returned to the unused capacity
+        if self.free_ranges.last().is_some_and(|last| last.end == self.len) {
+            let last = self.free_ranges.pop().expect("last free range must exist");
+            self.len = last.start;
+        }
+    }
+
+    /// Returns the number of freed elements that haven't been reused yet
+    pub fn free_len(&self) -> usize {
+        self.free_ranges.iter().map(|range| range.len()).sum()
+    }
+
+    /// Returns the number of elements that can still be pushed into the freed ranges and the unused capacity
+    pub fn available_len(&self) -> usize {
+        self.capacity - self.len + self.free_len()
+    }
+
+    /// Moves all elements that haven't been freed to the front of a new [`DeviceVisibleBuffer`] and issues the copy
+    /// commands to the [`CommandBufferBuilder`]. Returns the [`Relocation`]s of the moved elements.
+    ///
+    /// The new buffer replaces the old one immediately. The caller must make sure that the buffer isn't used
+    /// before the copy commands have finished and that the offsets into the buffer are updated by the [`Relocation`]s.
+    pub fn compact(&mut self, command_buffer_builder: &mut CommandBufferBuilder) -> crate::Result<Vec<Relocation>> {
+        if self.free_ranges.is_empty() {
+            return Ok(Vec::new());
+        }
+        let device_visible_buffer = DeviceVisibleBuffer::new(
+            &self.device,
+            self.capacity * mem::size_of::<T>(),
+            self.device_buffer_usage_flags,
+            self.debug_info.clone(),
+        )?;
+
+        // The ranges between the free ranges are the ones that are still in use
+        let mut relocations = Vec::new();
+        let mut start = 0;
+        for free_range in self.free_ranges.iter().chain(std::iter::once(&(self.len..self.len))) {
+            if start < free_range.start {
+                let new_start = relocations
+                    .last()
+                    .map(|relocation: &Relocation| relocation.new_start + relocation.old_range.len())
+                    .unwrap_or(0);
+                relocations.push(Relocation {
+                    old_range: start..free_range.start,
+                    new_start,
+                });
+            }
+            start = free_range.end;
+        }
+
+        let copy_regions = relocations
+            .iter()
+            .map(|relocation| vk::BufferCopy {
+                src_offset: (relocation.old_range.start * mem::size_of::<T>()) as u64,
+                dst_offset: (relocation.new_start * mem::size_of::<T>()) as u64,
+                size: (relocation.old_range.len() * mem::size_of::<T>()) as u64,
+            })
+            .collect::<Vec<_>>();
+        if !copy_regions.is_empty() {
+            let command_buffer = command_buffer_builder.command_buffer();
+            unsafe {
+                self.device.as_raw_vulkan().cmd_copy_buffer(
+                    *command_buffer.as_raw_vulkan(),
+                    *self.device_visible_buffer.as_raw_vulkan(),
+                    *device_visible_buffer.as_raw_vulkan(),
+                    &copy_regions,
+                );
+            }
+            command_buffer.push_dependency(self.device_visible_buffer.clone());
+            command_buffer.push_dependency(device_visible_buffer.clone());
+        }
+
+        self.len -= self.free_len();
+        self.free_ranges.clear();
+        self.device_visible_buffer = device_visible_buffer;
+        Ok(relocations)
+    }
+
     /// Returns the length of the buffer.
     pub fn len(&self) -> usize {
         self.len
@@ -220,4 +411,134 @@ mod tests {
             assert_eq!(read_data, vec![0.0, 0.0, 1.0, 1.0]);
         }
     }
+
+    mod free {
+        use jeriya_shared::debug_info;
+
+        use crate::{
+            buffer::BufferUsageFlags,
+            command_buffer::{tests::TestFixtureCommandBuffer, CommandBuffer},
+            command_buffer_builder::CommandBufferBuilder,
+            device::TestFixtureDevice,
+            staged_push_only_buffer::{Relocation, StagedPushOnlyBuffer},
+        };
+
+        #[test]
+        fn reuse_and_compact() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let mut test_fixture_command_buffer = TestFixtureCommandBuffer::new(&test_fixture_device).unwrap();
+
+            let mut buffer = StagedPushOnlyBuffer::<f32>::new(
+                &test_fixture_device.device,
+                8,
+                BufferUsageFlags::STORAGE_BUFFER,
+                debug_info!("my_host_visible_buffer"),
+            )
+            .unwrap();
+
+            let mut command_buffer_builder =
+                CommandBufferBuilder::new(&test_fixture_device.device, &mut test_fixture_command_buffer.command_buffer).unwrap();
+            command_buffer_builder.begin_command_buffer().unwrap();
+            buffer.push(&[0.0, 0.0], &mut command_buffer_builder).unwrap();
+            buffer.push(&[1.0, 1.0], &mut command_buffer_builder).unwrap();
+            buffer.push(&[2.0, 2.0], &mut command_buffer_builder).unwrap();
+            assert_eq!(buffer.available_len(), 2);
+
+            // Freed ranges are reused
+            buffer.free(0..2);
+            assert_eq!(buffer.free_len(), 2);
+            assert_eq!(buffer.available_len(), 4);
+            assert_eq!(buffer.push(&[3.0], &mut command_buffer_builder).unwrap(), Some(0));
+            assert_eq!(buffer.free_len(), 1);
+
+            // Freeing the end of the buffer reduces the length
+            buffer.free(4..6);
+            assert_eq!(buffer.len(), 4);
+            assert_eq!(buffer.free_len(), 1);
+            assert_eq!(buffer.available_len(), 5);
+
+            let relocations = buffer.compact(&mut command_buffer_builder).unwrap();
+            assert_eq!(
+                relocations,
+                vec![
+                    Relocation {
+                        old_range: 0..1,
+                        new_start: 0
+                    },
+                    Relocation {
+                        old_range: 2..4,
+                        new_start: 1
+                    }
+                ]
+            );
+            assert_eq!(Relocation::relocate(&relocations, 3), 2);
+            assert_eq!(buffer.len(), 3);
+            assert_eq!(buffer.free_len(), 0);
+            command_buffer_builder.end_command_buffer().unwrap();
+            test_fixture_command_buffer
+                .queue
+                .submit(test_fixture_command_buffer.command_buffer)
+                .unwrap();
+
+            let mut command_buffer = CommandBuffer::new(
+                &test_fixture_device.device,
+                &test_fixture_command_buffer.command_pool,
+                debug_info!("my_command_buffer"),
+            )
+            .unwrap();
+            let mut command_buffer_builder = CommandBufferBuilder::new(&test_fixture_device.device, &mut command_buffer).unwrap();
+            command_buffer_builder.begin_command_buffer().unwrap();
+            command_buffer_builder.transfer_to_transfer_command_barrier();
+            let receiver = buffer.read_all(&mut command_buffer_builder).unwrap();
+            command_buffer_builder.end_command_buffer().unwrap();
+            test_fixture_command_buffer.queue.submit(command_buffer).unwrap();
+            test_fixture_device.device.wait_for_idle().unwrap();
+            test_fixture_command_buffer.queue.poll_completed_fences().unwrap();
+
+            assert_eq!(receiver.recv().unwrap(), vec![3.0, 1.0, 1.0]);
+        }
+    }
+
+    mod write_at {
+        use jeriya_shared::debug_info;
+
+        use crate::{
+            buffer::BufferUsageFlags, command_buffer::tests::TestFixtureCommandBuffer, command_buffer_builder::CommandBufferBuilder,
+            device::TestFixtureDevice, staged_push_only_buffer::StagedPushOnlyBuffer,
+        };
+
+        #[test]
+        fn patch_field() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let mut test_fixture_command_buffer = TestFixtureCommandBuffer::new(&test_fixture_device).unwrap();
+
+            let mut buffer = StagedPushOnlyBuffer::<[u32; 2]>::new(
+                &test_fixture_device.device,
+                4,
+                BufferUsageFlags::STORAGE_BUFFER,
+                debug_info!("my_host_visible_buffer"),
+            )
+            .unwrap();
+
+            let mut command_buffer_builder =
+                CommandBufferBuilder::new(&test_fixture_device.device, &mut test_fixture_command_buffer.command_buffer).unwrap();
+            command_buffer_builder.begin_command_buffer().unwrap();
+            buffer.push(&[[0, 0], [1, 1]], &mut command_buffer_builder).unwrap();
+            command_buffer_builder.transfer_to_transfer_command_barrier();
+            buffer
+                .write_at(1, std::mem::size_of::<u32>(), &7u32, &mut command_buffer_builder)
+                .unwrap();
+            command_buffer_builder.transfer_to_transfer_command_barrier();
+            let receiver = buffer.read_all(&mut command_buffer_builder).unwrap();
+            command_buffer_builder.end_command_buffer().unwrap();
+            test_fixture_command_buffer
+                .queue
+                .submit(test_fixture_command_buffer.command_buffer)
+                .unwrap();
+            test_fixture_device.device.wait_for_idle().unwrap();
+            test_fixture_command_buffer.queue.poll_completed_fences().unwrap();
+
+            assert_eq!(receiver.recv().unwrap(), vec![[0, 0], [1, 7]]);
+        }
+    }
 }
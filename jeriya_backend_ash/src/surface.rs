@@ -1,4 +1,4 @@
-use std::{default::Default, os::raw::c_void, ptr, sync::Arc};
+use std::{default::Default, iter, os::raw::c_void, ptr, sync::Arc};
 
 use ash::{
     extensions::khr::{self},
@@ -7,8 +7,8 @@ use ash::{
 
 use winapi::um::libloaderapi::GetModuleHandleW;
 
-use crate::{entry::Entry, instance::Instance, AsRawVulkan, Error};
-use jeriya_shared::winit;
+use crate::{entry::Entry, instance::Instance, physical_device::PhysicalDevice, swapchain::hdr_surface_format, AsRawVulkan, Error};
+use jeriya_shared::{winit, ColorSpace};
 
 /// Surface of a window to create the `Swapchain`.
 pub struct Surface {
@@ -29,6 +29,29 @@ impl Surface {
         }))
     }
 
+    /// Returns the current extent of the surface which is empty while the window is minimized
+    pub fn current_extent(&self, physical_device: &PhysicalDevice) -> crate::Result<vk::Extent2D> {
+        let surface_capabilities = unsafe {
+            self.surface
+                .get_physical_device_surface_capabilities(*physical_device.as_raw_vulkan(), self.surface_khr)?
+        };
+        Ok(surface_capabilities.current_extent)
+    }
+
+    /// Returns the [`ColorSpace`]s in which a [`Swapchain`](crate::swapchain::Swapchain) can present to the surface
+    ///
+    /// [`ColorSpace::Sdr`] is always supported.
+    pub fn supported_color_spaces(&self, physical_device: &PhysicalDevice) -> crate::Result<Vec<ColorSpace>> {
+        let surface_formats = unsafe {
+            self.surface
+                .get_physical_device_surface_formats(*physical_device.as_raw_vulkan(), self.surface_khr)?
+        };
+        let hdr_color_spaces = [ColorSpace::Hdr10, ColorSpace::ScRgb]
+            .into_iter()
+            .filter(|color_space| hdr_surface_format(&surface_formats, *color_space).is_some());
+        Ok(iter::once(ColorSpace::Sdr).chain(hdr_color_spaces).collect())
+    }
+
     /// Returns whether the given queue family index of the physical device supports presentation
     pub fn supports_presentation(&self, physical_device: &vk::PhysicalDevice, queue_family_index: usize) -> crate::Result<bool> {
         unsafe {
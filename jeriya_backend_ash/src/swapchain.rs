@@ -1,21 +1,39 @@
 use ash::{extensions::khr, prelude::VkResult, vk};
-use jeriya_shared::log::{info, warn};
+use jeriya_shared::{
+    log::{info, warn},
+    ColorSpace,
+};
 
-use std::{ops::Drop, sync::Arc};
+use std::{cell::Cell, ops::Drop, sync::Arc};
 
 use crate::{device::Device, frame_index::FrameIndex, queue::Queue, semaphore::Semaphore, surface::Surface, AsRawVulkan, Error};
 
 /// Represents the swapchain.
+///
+/// A swapchain either presents its images to a [`Surface`] or is offscreen. The images of an offscreen swapchain are
+/// owned by it and never presented so that frames can be rendered without a display.
 pub struct Swapchain {
-    swapchain: khr::Swapchain,
-    swapchain_khr: vk::SwapchainKHR,
+    presentation: Presentation,
     pub images: Vec<vk::Image>,
     image_views: Vec<vk::ImageView>,
     _format: vk::SurfaceFormatKHR,
     extent: vk::Extent2D,
+    image_usage: vk::ImageUsageFlags,
     device: Arc<Device>,
 }
 
+enum Presentation {
+    Surface {
+        swapchain: khr::Swapchain,
+        swapchain_khr: vk::SwapchainKHR,
+        present_mode: vk::PresentModeKHR,
+    },
+    Offscreen {
+        image_memories: Vec<vk::DeviceMemory>,
+        next_image_index: Cell<u32>,
+    },
+}
+
 impl Drop for Swapchain {
     fn drop(&mut self) {
         unsafe {
@@ -24,7 +42,17 @@ impl Drop for Swapchain {
             for image_view in &self.image_views {
                 device.destroy_image_view(*image_view, None);
             }
-            self.swapchain.destroy_swapchain(self.swapchain_khr, None);
+            match &self.presentation {
+                Presentation::Surface {
+                    swapchain, swapchain_khr, ..
+                } => swapchain.destroy_swapchain(*swapchain_khr, None),
+                Presentation::Offscreen { image_memories, .. } => {
+                    for (image, image_memory) in self.images.iter().zip(image_memories) {
+                        device.destroy_image(*image, None);
+                        device.free_memory(*image_memory, None);
+                    }
+                }
+            }
         }
     }
 }
@@ -36,6 +64,29 @@ impl Swapchain {
         surface: &Surface,
         desired_swapchain_length: u32,
         previous_swapchain: Option<&Swapchain>,
+    ) -> crate::Result<Self> {
+        Self::with_preferences(
+            device,
+            surface,
+            desired_swapchain_length,
+            previous_swapchain,
+            &[vk::PresentModeKHR::MAILBOX],
+            ColorSpace::Sdr,
+        )
+    }
+
+    /// Creates a new swapchain for the given [`Surface`] with the first of the `preferred_present_modes` that is supported
+    /// and the `preferred_color_space`
+    ///
+    /// Falls back to `FIFO` which is always supported and to [`ColorSpace::Sdr`] when the surface doesn't support the
+    /// `preferred_color_space`.
+    pub fn with_preferences(
+        device: &Arc<Device>,
+        surface: &Surface,
+        desired_swapchain_length: u32,
+        previous_swapchain: Option<&Swapchain>,
+        preferred_present_modes: &[vk::PresentModeKHR],
+        preferred_color_space: ColorSpace,
     ) -> crate::Result<Self> {
         let surface_capabilities = unsafe {
             surface
@@ -45,9 +96,15 @@ impl Swapchain {
         info!("Surface capabilities: {surface_capabilities:?}");
 
         // Image Count
-        let desired_image_count = desired_swapchain_length
-            .max(surface_capabilities.min_image_count)
-            .min(surface_capabilities.max_image_count);
+        //
+        // A maximum image count of zero means that there is no limit.
+        let desired_image_count = if surface_capabilities.max_image_count == 0 {
+            desired_swapchain_length.max(surface_capabilities.min_image_count)
+        } else {
+            desired_swapchain_length
+                .max(surface_capabilities.min_image_count)
+                .min(surface_capabilities.max_image_count)
+        };
 
         // Format
         let format = {
@@ -56,17 +113,31 @@ impl Swapchain {
                     .surface
                     .get_physical_device_surface_formats(*device.physical_device.as_raw_vulkan(), surface.surface_khr)?
             };
-            let format = surface_formats
-                .iter()
-                .map(|sfmt| match sfmt.format {
-                    vk::Format::UNDEFINED => vk::SurfaceFormatKHR {
-                        format: vk::Format::B8G8R8_UNORM,
-                        color_space: sfmt.color_space,
-                    },
-                    _ => *sfmt,
-                })
-                .next()
-                .ok_or(Error::SwapchainSurfaceFormatError)?;
+            let hdr_format = match preferred_color_space {
+                ColorSpace::Sdr => None,
+                _ => {
+                    let hdr_format = hdr_surface_format(&surface_formats, preferred_color_space);
+                    if hdr_format.is_none() {
+                        warn!("The surface doesn't support {preferred_color_space:?}. Falling back to SDR.");
+                    }
+                    hdr_format
+                }
+            };
+            let format = match hdr_format {
+                Some(hdr_format) => hdr_format,
+                None => surface_formats
+                    .iter()
+                    .filter(|sfmt| sfmt.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
+                    .map(|sfmt| match sfmt.format {
+                        vk::Format::UNDEFINED => vk::SurfaceFormatKHR {
+                            format: vk::Format::B8G8R8_UNORM,
+                            color_space: sfmt.color_space,
+                        },
+                        _ => *sfmt,
+                    })
+                    .next()
+                    .ok_or(Error::SwapchainSurfaceFormatError)?,
+            };
             info!("Format: {format:?}");
             format
         };
@@ -75,6 +146,35 @@ impl Swapchain {
         let extent = surface_capabilities.current_extent;
         info!("Swapchain extent: {extent:?}");
 
+        // Image Usage
+        //
+        // The images are copied to the host when frames are captured which most surfaces support.
+        let image_usage = if surface_capabilities
+            .supported_usage_flags
+            .contains(vk::ImageUsageFlags::TRANSFER_SRC)
+        {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
+        } else {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+        };
+
+        // Present Mode
+        //
+        // FIFO is the only present mode that is guaranteed to be supported.
+        let present_mode = {
+            let present_modes = unsafe {
+                surface
+                    .surface
+                    .get_physical_device_surface_present_modes(*device.physical_device.as_raw_vulkan(), surface.surface_khr)?
+            };
+            preferred_present_modes
+                .iter()
+                .cloned()
+                .find(|mode| present_modes.contains(mode))
+                .unwrap_or(vk::PresentModeKHR::FIFO)
+        };
+        info!("Present mode: {present_mode:?}");
+
         // Swapchain
         let swapchain_loader = khr::Swapchain::new(device.instance().as_raw_vulkan(), device.as_raw_vulkan());
         let swapchain = {
@@ -86,79 +186,128 @@ impl Swapchain {
             } else {
                 surface_capabilities.current_transform
             };
-            let present_modes = unsafe {
-                surface
-                    .surface
-                    .get_physical_device_surface_present_modes(*device.physical_device.as_raw_vulkan(), surface.surface_khr)?
-            };
-            let present_mode = present_modes
-                .iter()
-                .cloned()
-                .find(|&mode| mode == vk::PresentModeKHR::MAILBOX)
-                .unwrap_or(vk::PresentModeKHR::FIFO);
             let mut swapchain_create_info: ash::vk::SwapchainCreateInfoKHRBuilder<'_> = vk::SwapchainCreateInfoKHR::builder()
                 .surface(surface.surface_khr)
                 .min_image_count(desired_image_count)
                 .image_color_space(format.color_space)
                 .image_format(format.format)
                 .image_extent(extent)
-                .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+                .image_usage(image_usage)
                 .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
                 .pre_transform(pre_transform)
                 .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
                 .present_mode(present_mode)
                 .clipped(true)
                 .image_array_layers(1);
-            if let Some(previous_swapchain) = previous_swapchain {
-                swapchain_create_info = swapchain_create_info.old_swapchain(previous_swapchain.swapchain_khr);
+            if let Some(Presentation::Surface { swapchain_khr, .. }) = previous_swapchain.map(|swapchain| &swapchain.presentation) {
+                swapchain_create_info = swapchain_create_info.old_swapchain(*swapchain_khr);
             }
             info!("SwapchainCreateInfoKHR: {:#?}", *swapchain_create_info);
-            unsafe { swapchain_loader.create_swapchain(&swapchain_create_info, None).unwrap() }
+            unsafe { swapchain_loader.create_swapchain(&swapchain_create_info, None)? }
         };
 
         // Images
         let images = unsafe { swapchain_loader.get_swapchain_images(swapchain)? };
-        let image_views: Vec<vk::ImageView> = images
-            .iter()
-            .map(|&image| {
-                let create_view_info = vk::ImageViewCreateInfo::builder()
-                    .view_type(vk::ImageViewType::TYPE_2D)
-                    .format(format.format)
-                    .components(vk::ComponentMapping {
-                        r: vk::ComponentSwizzle::R,
-                        g: vk::ComponentSwizzle::G,
-                        b: vk::ComponentSwizzle::B,
-                        a: vk::ComponentSwizzle::A,
-                    })
-                    .subresource_range(vk::ImageSubresourceRange {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        base_mip_level: 0,
-                        level_count: 1,
-                        base_array_layer: 0,
-                        layer_count: 1,
-                    })
-                    .image(image);
-                unsafe { device.as_raw_vulkan().create_image_view(&create_view_info, None) }
-            })
-            .collect::<VkResult<Vec<_>>>()?;
+        let image_views = create_image_views(device, &images, format.format)?;
 
         Ok(Self {
-            swapchain: swapchain_loader,
-            swapchain_khr: swapchain,
+            presentation: Presentation::Surface {
+                swapchain: swapchain_loader,
+                swapchain_khr: swapchain,
+                present_mode,
+            },
             images,
             image_views,
             _format: format,
             extent,
+            image_usage,
             device: device.clone(),
         })
     }
 
-    pub fn acquire_next_image(&self, semaphore_to_signal: &Semaphore) -> crate::Result<u32> {
+    /// Creates a new offscreen swapchain with `swapchain_length` images of the given `extent`
+    ///
+    /// The images have the format `R8G8B8A8_UNORM` and can be copied to the host.
+    pub fn new_offscreen(device: &Arc<Device>, extent: vk::Extent2D, swapchain_length: u32) -> crate::Result<Self> {
+        jeriya_shared::assert!(
+            extent.width > 0 && extent.height > 0,
+            "offscreen swapchain must have a non-zero extent"
+        );
+        let format = vk::SurfaceFormatKHR {
+            format: vk::Format::R8G8B8A8_UNORM,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        };
+        let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC;
+        info!("Offscreen swapchain extent: {extent:?}");
+
+        let mut images = Vec::new();
+        let mut image_memories = Vec::new();
+        for _ in 0..swapchain_length.max(1) {
+            let image_create_info = vk::ImageCreateInfo::builder()
+                .image_type(vk::ImageType::TYPE_2D)
+                .format(format.format)
+                .extent(vk::Extent3D {
+                    width: extent.width,
+                    height: extent.height,
+                    depth: 1,
+                })
+                .mip_levels(1)
+                .array_layers(1)
+                .samples(vk::SampleCountFlags::TYPE_1)
+                .tiling(vk::ImageTiling::OPTIMAL)
+                .usage(image_usage)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .initial_layout(vk::ImageLayout::UNDEFINED);
+            let image = unsafe { device.as_raw_vulkan().create_image(&image_create_info, None)? };
+            let image_memory_requirements = unsafe { device.as_raw_vulkan().get_image_memory_requirements(image) };
+            let image_memory_index = device
+                .find_memorytype_index(&image_memory_requirements, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+                .ok_or_else(|| Error::UnsupportedMemoryType(image_memory_requirements))?;
+            let image_allocate_info = vk::MemoryAllocateInfo::builder()
+                .allocation_size(image_memory_requirements.size)
+                .memory_type_index(image_memory_index);
+            let image_memory = unsafe { device.as_raw_vulkan().allocate_memory(&image_allocate_info, None)? };
+            unsafe { device.as_raw_vulkan().bind_image_memory(image, image_memory, 0)? };
+            images.push(image);
+            image_memories.push(image_memory);
+        }
+        let image_views = create_image_views(device, &images, format.format)?;
+
+        Ok(Self {
+            presentation: Presentation::Offscreen {
+                image_memories,
+                next_image_index: Cell::new(0),
+            },
+            images,
+            image_views,
+            _format: format,
+            extent,
+            image_usage,
+            device: device.clone(),
+        })
+    }
+
+    /// Acquires the next image and returns its index and whether the swapchain is suboptimal
+    ///
+    /// The images of an offscreen swapchain are used in turn and `semaphore_to_signal` is not signalled. See [`Swapchain::is_offscreen`].
+    /// When the swapchain doesn't match the surface anymore, an error is returned for which [`Error::is_swapchain_out_of_date`]
+    /// is `true`.
+    pub fn acquire_next_image(&self, semaphore_to_signal: &Semaphore) -> crate::Result<(u32, bool)> {
         let _span = jeriya_shared::span!("acquire_next_image");
 
+        let (swapchain, swapchain_khr) = match &self.presentation {
+            Presentation::Surface {
+                swapchain, swapchain_khr, ..
+            } => (swapchain, *swapchain_khr),
+            Presentation::Offscreen { next_image_index, .. } => {
+                let index = next_image_index.get();
+                next_image_index.set((index + 1) % self.images.len() as u32);
+                return Ok((index, false));
+            }
+        };
         let (present_index, is_suboptimal) = unsafe {
-            self.swapchain.acquire_next_image(
-                self.swapchain_khr,
+            swapchain.acquire_next_image(
+                swapchain_khr,
                 std::u64::MAX,
                 *semaphore_to_signal.as_raw_vulkan(),
                 vk::Fence::null(),
@@ -167,9 +316,13 @@ impl Swapchain {
         if is_suboptimal {
             warn!("Suboptimal swapchain image");
         }
-        Ok(present_index)
+        Ok((present_index, is_suboptimal))
     }
 
+    /// Presents the image of the `frame_index` and returns whether the swapchain is suboptimal
+    ///
+    /// Offscreen swapchains don't present their images and always succeed. Like [`Swapchain::acquire_next_image`], an error
+    /// is returned when the swapchain is out of date.
     pub fn present(
         &self,
         frame_index: &FrameIndex,
@@ -177,14 +330,20 @@ impl Swapchain {
         present_queue: &Queue,
     ) -> crate::Result<bool> {
         let _span = jeriya_shared::span!("Swapchain::present");
+        let Presentation::Surface {
+            swapchain, swapchain_khr, ..
+        } = &self.presentation
+        else {
+            return Ok(false);
+        };
         let wait_semaphores = [*rendering_complete_semaphore.as_raw_vulkan()];
-        let swapchains = [self.swapchain_khr];
+        let swapchains = [*swapchain_khr];
         let image_indices = [frame_index.swapchain_index().expect("swapchain image must be set for presenting") as u32];
         let present_info = vk::PresentInfoKHR::builder()
             .wait_semaphores(&wait_semaphores)
             .swapchains(&swapchains)
             .image_indices(&image_indices);
-        unsafe { Ok(self.swapchain.queue_present(*present_queue.as_raw_vulkan(), &present_info)?) }
+        unsafe { Ok(swapchain.queue_present(*present_queue.as_raw_vulkan(), &present_info)?) }
     }
 
     /// Returns a copy of the `ImageView`s for the swapchain images
@@ -192,25 +351,145 @@ impl Swapchain {
         self.image_views.clone()
     }
 
-    #[allow(dead_code)]
     pub fn extent(&self) -> vk::Extent2D {
         self.extent
     }
 
-    #[allow(dead_code)]
+    /// Returns the present mode or `None` when the swapchain is offscreen
+    pub fn present_mode(&self) -> Option<vk::PresentModeKHR> {
+        match &self.presentation {
+            Presentation::Surface { present_mode, .. } => Some(*present_mode),
+            Presentation::Offscreen { .. } => None,
+        }
+    }
+
     pub fn surface_format(&self) -> vk::SurfaceFormatKHR {
         self._format
     }
 
+    /// Returns the [`ColorSpace`] in which the images are presented
+    ///
+    /// For the HDR color spaces, the rendered image has to be transformed by the output transform before it's presented.
+    pub fn color_space(&self) -> ColorSpace {
+        color_space_of(self._format.color_space)
+    }
+
+    /// Returns whether the images can be copied to the host
+    pub fn supports_capture(&self) -> bool {
+        self.image_usage.contains(vk::ImageUsageFlags::TRANSFER_SRC)
+    }
+
+    /// Returns whether the images are rendered offscreen instead of being presented to a [`Surface`]
+    ///
+    /// Acquiring an image of an offscreen swapchain doesn't signal a semaphore. The caller has to signal it before the
+    /// rendering waits for it.
+    pub fn is_offscreen(&self) -> bool {
+        matches!(self.presentation, Presentation::Offscreen { .. })
+    }
+
     /// Returns the number of images in the swapchain
-    #[allow(clippy::len_without_is_empty, dead_code)]
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.images.len()
     }
 }
 
+/// Creates the `ImageView`s for the swapchain `images`
+fn create_image_views(device: &Device, images: &[vk::Image], format: vk::Format) -> crate::Result<Vec<vk::ImageView>> {
+    Ok(images
+        .iter()
+        .map(|&image| {
+            let create_view_info = vk::ImageViewCreateInfo::builder()
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(format)
+                .components(vk::ComponentMapping {
+                    r: vk::ComponentSwizzle::R,
+                    g: vk::ComponentSwizzle::G,
+                    b: vk::ComponentSwizzle::B,
+                    a: vk::ComponentSwizzle::A,
+                })
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+                .image(image);
+            unsafe { device.as_raw_vulkan().create_image_view(&create_view_info, None) }
+        })
+        .collect::<VkResult<Vec<_>>>()?)
+}
+
+/// Returns the [`ColorSpace`] that corresponds to the `vk::ColorSpaceKHR`. Unknown color spaces are treated as SDR.
+pub fn color_space_of(color_space: vk::ColorSpaceKHR) -> ColorSpace {
+    match color_space {
+        vk::ColorSpaceKHR::HDR10_ST2084_EXT => ColorSpace::Hdr10,
+        vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => ColorSpace::ScRgb,
+        _ => ColorSpace::Sdr,
+    }
+}
+
+/// Returns the surface format of the `surface_formats` that presents the HDR `color_space`
+///
+/// HDR10 requires 10 bits per channel and scRGB requires 16-bit floats. `None` is returned for [`ColorSpace::Sdr`].
+pub fn hdr_surface_format(surface_formats: &[vk::SurfaceFormatKHR], color_space: ColorSpace) -> Option<vk::SurfaceFormatKHR> {
+    let (vk_color_space, formats): (_, &[vk::Format]) = match color_space {
+        ColorSpace::Sdr => return None,
+        ColorSpace::Hdr10 => (
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+            &[vk::Format::A2B10G10R10_UNORM_PACK32, vk::Format::A2R10G10B10_UNORM_PACK32],
+        ),
+        ColorSpace::ScRgb => (vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT, &[vk::Format::R16G16B16A16_SFLOAT]),
+    };
+    surface_formats
+        .iter()
+        .find(|sfmt| sfmt.color_space == vk_color_space && formats.contains(&sfmt.format))
+        .cloned()
+}
+
 #[cfg(test)]
 mod tests {
+    mod hdr_surface_format {
+        use ash::vk;
+        use jeriya_shared::ColorSpace;
+
+        use crate::swapchain::{color_space_of, hdr_surface_format};
+
+        const SURFACE_FORMATS: [vk::SurfaceFormatKHR; 3] = [
+            vk::SurfaceFormatKHR {
+                format: vk::Format::B8G8R8A8_UNORM,
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            },
+            vk::SurfaceFormatKHR {
+                format: vk::Format::A2B10G10R10_UNORM_PACK32,
+                color_space: vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+            },
+            vk::SurfaceFormatKHR {
+                format: vk::Format::B8G8R8A8_UNORM,
+                color_space: vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+            },
+        ];
+
+        #[test]
+        fn hdr10() {
+            let format = hdr_surface_format(&SURFACE_FORMATS, ColorSpace::Hdr10).unwrap();
+            assert_eq!(format, SURFACE_FORMATS[1]);
+            assert_eq!(color_space_of(format.color_space), ColorSpace::Hdr10);
+        }
+
+        #[test]
+        fn scrgb_requires_float_format() {
+            assert_eq!(hdr_surface_format(&SURFACE_FORMATS, ColorSpace::ScRgb), None);
+        }
+
+        #[test]
+        fn sdr() {
+            assert_eq!(hdr_surface_format(&SURFACE_FORMATS, ColorSpace::Sdr), None);
+            assert_eq!(color_space_of(SURFACE_FORMATS[0].color_space), ColorSpace::Sdr);
+        }
+    }
+
     mod new {
         use std::iter;
 
@@ -259,4 +538,27 @@ mod tests {
             assert_eq!(swapchain.extent().height, new_height);
         }
     }
+
+    mod new_offscreen {
+        use ash::vk;
+        use jeriya_shared::debug_info;
+
+        use crate::{device::TestFixtureDevice, semaphore::Semaphore, swapchain::Swapchain};
+
+        #[test]
+        fn images_are_acquired_in_turn() {
+            let test_fixture_device = TestFixtureDevice::new().unwrap();
+            let extent = vk::Extent2D { width: 64, height: 32 };
+            let swapchain = Swapchain::new_offscreen(&test_fixture_device.device, extent, 2).unwrap();
+            assert!(swapchain.is_offscreen());
+            assert!(swapchain.supports_capture());
+            assert_eq!(swapchain.extent(), extent);
+            assert_eq!(swapchain.len(), 2);
+            let semaphore = Semaphore::new(&test_fixture_device.device, debug_info!("my_semaphore")).unwrap();
+            let indices = (0..3)
+                .map(|_| swapchain.acquire_next_image(&semaphore).unwrap().0)
+                .collect::<Vec<_>>();
+            assert_eq!(indices, vec![0, 1, 0]);
+        }
+    }
 }
@@ -4,7 +4,7 @@ use ash::vk;
 
 use crate::{device::Device, swapchain::Swapchain, swapchain_vec::SwapchainVec, AsRawVulkan};
 
-/// Depth Buffer for the Swapchain
+/// Depth and stencil buffer for the Swapchain
 pub struct SwapchainDepthBuffers {
     pub depth_buffers: SwapchainVec<SwapchainDepthBuffer>,
 }
@@ -12,7 +12,7 @@ pub struct SwapchainDepthBuffers {
 impl SwapchainDepthBuffers {
     /// Creates a new depth buffer for the given [`Swapchain`]
     pub fn new(device: &Arc<Device>, swapchain: &Swapchain) -> crate::Result<Self> {
-        let depth_buffers = SwapchainVec::new(swapchain, |_| SwapchainDepthBuffer::new(device, swapchain))?;
+        let depth_buffers = SwapchainVec::new(swapchain, |_| SwapchainDepthBuffer::new(device, swapchain.extent()))?;
         Ok(Self { depth_buffers })
     }
 }
@@ -21,7 +21,13 @@ impl SwapchainDepthBuffers {
 pub struct SwapchainDepthBuffer {
     pub depth_image: vk::Image,
     pub depth_image_memory: vk::DeviceMemory,
+    pub format: vk::Format,
+    /// View of the depth aspect with which the depth buffer is sampled
     pub depth_image_view: vk::ImageView,
+    /// View of the stencil aspect with which the selection outline samples the stencil buffer
+    pub stencil_image_view: vk::ImageView,
+    /// View of the depth and stencil aspect with which the image is used as framebuffer attachment
+    pub depth_stencil_image_view: vk::ImageView,
     device: Arc<Device>,
 }
 
@@ -30,6 +36,8 @@ impl Drop for SwapchainDepthBuffer {
         unsafe {
             let device = self.device.as_raw_vulkan();
             device.destroy_image_view(self.depth_image_view, None);
+            device.destroy_image_view(self.stencil_image_view, None);
+            device.destroy_image_view(self.depth_stencil_image_view, None);
             device.free_memory(self.depth_image_memory, None);
             device.destroy_image(self.depth_image, None);
         }
@@ -37,7 +45,8 @@ impl Drop for SwapchainDepthBuffer {
 }
 
 impl SwapchainDepthBuffer {
-    fn new(device: &Arc<Device>, swapchain: &Swapchain) -> crate::Result<Self> {
+    /// Creates a new depth buffer with an 8 bit stencil buffer with the given `extent`
+    pub fn new(device: &Arc<Device>, extent: vk::Extent2D) -> crate::Result<Self> {
         // Image
         let format = vk::Format::D24_UNORM_S8_UINT;
         let depth_image = {
@@ -45,15 +54,16 @@ impl SwapchainDepthBuffer {
                 .image_type(vk::ImageType::TYPE_2D)
                 .format(format)
                 .extent(vk::Extent3D {
-                    width: swapchain.extent().width,
-                    height: swapchain.extent().height,
+                    width: extent.width,
+                    height: extent.height,
                     depth: 1,
                 })
                 .mip_levels(1)
                 .array_layers(1)
                 .samples(vk::SampleCountFlags::TYPE_1)
                 .tiling(vk::ImageTiling::OPTIMAL)
-                .usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)
+                // Sampled by the post-processing passes like the eye-dome lighting and copied by the depth readbacks
+                .usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_SRC)
                 .sharing_mode(vk::SharingMode::EXCLUSIVE);
             unsafe { device.as_raw_vulkan().create_image(&depth_image_create_info, None)? }
         };
@@ -74,24 +84,34 @@ impl SwapchainDepthBuffer {
             depth_image_memory
         };
 
-        // Image View
-        let depth_image_view_info = vk::ImageViewCreateInfo::builder()
-            .subresource_range(
-                vk::ImageSubresourceRange::builder()
-                    .aspect_mask(vk::ImageAspectFlags::DEPTH)
-                    .level_count(1)
-                    .layer_count(1)
-                    .build(),
-            )
-            .image(depth_image)
-            .format(format)
-            .view_type(vk::ImageViewType::TYPE_2D);
-        let depth_image_view = unsafe { device.as_raw_vulkan().create_image_view(&depth_image_view_info, None)? };
+        // Image Views
+        //
+        // Images with a combined depth stencil format can only be sampled with a view of a single aspect
+        let create_image_view = |aspect_mask: vk::ImageAspectFlags| {
+            let image_view_info = vk::ImageViewCreateInfo::builder()
+                .subresource_range(
+                    vk::ImageSubresourceRange::builder()
+                        .aspect_mask(aspect_mask)
+                        .level_count(1)
+                        .layer_count(1)
+                        .build(),
+                )
+                .image(depth_image)
+                .format(format)
+                .view_type(vk::ImageViewType::TYPE_2D);
+            unsafe { device.as_raw_vulkan().create_image_view(&image_view_info, None) }
+        };
+        let depth_image_view = create_image_view(vk::ImageAspectFlags::DEPTH)?;
+        let stencil_image_view = create_image_view(vk::ImageAspectFlags::STENCIL)?;
+        let depth_stencil_image_view = create_image_view(vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL)?;
 
         Ok(Self {
             depth_image,
             depth_image_memory,
+            format,
             depth_image_view,
+            stencil_image_view,
+            depth_stencil_image_view,
             device: device.clone(),
         })
     }
@@ -120,6 +140,6 @@ mod tests {
         let queue_plan = QueuePlan::new(&instance, &physical_device, iter::once((&window.id(), &surface))).unwrap();
         let device = Device::new(physical_device, &instance, queue_plan).unwrap();
         let swapchain = Swapchain::new(&device, &surface, 2, None).unwrap();
-        let _swapchain_depthbuffer = SwapchainDepthBuffer::new(&device, &swapchain).unwrap();
+        let _swapchain_depthbuffer = SwapchainDepthBuffer::new(&device, swapchain.extent()).unwrap();
     }
 }
@@ -34,7 +34,7 @@ impl SwapchainFramebuffers {
             .iter()
             .zip(swapchain_depth_buffers.depth_buffers.iter())
             .map(|(present_image_view, depth_buffer)| {
-                let framebuffer_attachments = [*present_image_view, depth_buffer.depth_image_view];
+                let framebuffer_attachments = [*present_image_view, depth_buffer.depth_stencil_image_view];
                 let frame_buffer_create_info = vk::FramebufferCreateInfo::builder()
                     .render_pass(*swapchain_render_pass.as_raw_vulkan())
                     .attachments(&framebuffer_attachments)
@@ -49,6 +49,32 @@ impl SwapchainFramebuffers {
             device: device.clone(),
         })
     }
+
+    /// Creates a new `SwapchainFramebuffers` for the [`SwapchainRenderPass::new_post_processing`] that only contain the swapchain images
+    pub fn new_post_processing(
+        device: &Arc<Device>,
+        swapchain: &Swapchain,
+        post_processing_render_pass: &SwapchainRenderPass,
+    ) -> crate::Result<Self> {
+        let framebuffers = swapchain
+            .image_views()
+            .iter()
+            .map(|present_image_view| {
+                let framebuffer_attachments = [*present_image_view];
+                let frame_buffer_create_info = vk::FramebufferCreateInfo::builder()
+                    .render_pass(*post_processing_render_pass.as_raw_vulkan())
+                    .attachments(&framebuffer_attachments)
+                    .width(swapchain.extent().width)
+                    .height(swapchain.extent().height)
+                    .layers(1);
+                unsafe { device.as_raw_vulkan().create_framebuffer(&frame_buffer_create_info, None) }
+            })
+            .collect::<Result<Vec<vk::Framebuffer>, _>>()?;
+        Ok(Self {
+            framebuffers,
+            device: device.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -78,6 +104,9 @@ mod tests {
             let swapchain_render_pass = SwapchainRenderPass::new(&device, &swapchain).unwrap();
             let _swapchain_framebuffers =
                 SwapchainFramebuffers::new(&device, &swapchain, &swapchain_depth_buffer, &swapchain_render_pass).unwrap();
+            let post_processing_render_pass = SwapchainRenderPass::new_post_processing(&device, &swapchain).unwrap();
+            let _post_processing_framebuffers =
+                SwapchainFramebuffers::new_post_processing(&device, &swapchain, &post_processing_render_pass).unwrap();
         }
     }
 }
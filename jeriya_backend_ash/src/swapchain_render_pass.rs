@@ -2,7 +2,7 @@ use ash::vk;
 
 use std::sync::Arc;
 
-use crate::{device::Device, swapchain::Swapchain, AsRawVulkan};
+use crate::{device::Device, render_target::RENDER_TARGET_FORMAT, swapchain::Swapchain, AsRawVulkan};
 
 pub struct SwapchainRenderPass {
     pub render_pass: vk::RenderPass,
@@ -35,8 +35,13 @@ impl SwapchainRenderPass {
                 format: vk::Format::D24_UNORM_S8_UINT,
                 samples: vk::SampleCountFlags::TYPE_1,
                 load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::STORE,
+                // The stencil buffer is kept for the passes that continue rendering into the scene
+                stencil_load_op: vk::AttachmentLoadOp::CLEAR,
+                stencil_store_op: vk::AttachmentStoreOp::STORE,
                 initial_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                // The post-processing passes sample the depth buffer
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
                 ..Default::default()
             },
         ];
@@ -67,6 +72,235 @@ impl SwapchainRenderPass {
                 .src_access_mask(vk::AccessFlags::NONE)
                 .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::COLOR_ATTACHMENT_READ)
                 .build(),
+            // Depth Attachment sampled after the subpass
+            vk::SubpassDependency::builder()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL) // including everything after the subpass
+                .src_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .build(),
+        ];
+
+        let subpasses = [vk::SubpassDescription::builder()
+            .color_attachments(&color_attachment_refs)
+            .depth_stencil_attachment(&depth_attachment_ref)
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .build()];
+
+        let renderpass_create_info = vk::RenderPassCreateInfo::builder()
+            .attachments(&renderpass_attachments)
+            .subpasses(&subpasses)
+            .dependencies(&dependencies);
+
+        let render_pass = unsafe { device.as_raw_vulkan().create_render_pass(&renderpass_create_info, None)? };
+
+        Ok(Self {
+            render_pass,
+            device: device.clone(),
+        })
+    }
+
+    /// Creates a `SwapchainRenderPass` that continues rendering into the color and depth attachment of a [`SwapchainRenderPass::new`]
+    ///
+    /// The pass is compatible with the framebuffers of [`SwapchainRenderPass::new`] and is used when the scene is rendered
+    /// into several viewports of the window.
+    pub fn new_load(device: &Arc<Device>, swapchain: &Swapchain) -> crate::Result<Self> {
+        let renderpass_attachments = [
+            vk::AttachmentDescription {
+                format: swapchain.surface_format().format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::LOAD,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                ..Default::default()
+            },
+            vk::AttachmentDescription {
+                format: vk::Format::D24_UNORM_S8_UINT,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::LOAD,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::LOAD,
+                stencil_store_op: vk::AttachmentStoreOp::STORE,
+                initial_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                // The post-processing passes sample the depth buffer
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                ..Default::default()
+            },
+        ];
+        let color_attachment_refs = [vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        }];
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 1,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+        let dependencies = [
+            // Depth Attachment of the previous pass
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL) // including everything before the subpass
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ)
+                .build(),
+            // Color Attachment of the previous pass
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL) // including everything before the subpass
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::COLOR_ATTACHMENT_READ)
+                .build(),
+            // Depth Attachment sampled after the subpass
+            vk::SubpassDependency::builder()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL) // including everything after the subpass
+                .src_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .build(),
+        ];
+
+        let subpasses = [vk::SubpassDescription::builder()
+            .color_attachments(&color_attachment_refs)
+            .depth_stencil_attachment(&depth_attachment_ref)
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .build()];
+
+        let renderpass_create_info = vk::RenderPassCreateInfo::builder()
+            .attachments(&renderpass_attachments)
+            .subpasses(&subpasses)
+            .dependencies(&dependencies);
+
+        let render_pass = unsafe { device.as_raw_vulkan().create_render_pass(&renderpass_create_info, None)? };
+
+        Ok(Self {
+            render_pass,
+            device: device.clone(),
+        })
+    }
+
+    /// Creates a `SwapchainRenderPass` that draws on top of the swapchain image after the scene has been rendered
+    ///
+    /// The pass only has the color attachment so that the depth buffer can be sampled while it's active.
+    pub fn new_post_processing(device: &Arc<Device>, swapchain: &Swapchain) -> crate::Result<Self> {
+        let renderpass_attachments = [vk::AttachmentDescription {
+            format: swapchain.surface_format().format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::LOAD,
+            store_op: vk::AttachmentStoreOp::STORE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+            final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+            ..Default::default()
+        }];
+        let color_attachment_refs = [vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        }];
+        let dependencies = [
+            // Color Attachment
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL) // including everything before the subpass
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::COLOR_ATTACHMENT_READ)
+                .build(),
+        ];
+
+        let subpasses = [vk::SubpassDescription::builder()
+            .color_attachments(&color_attachment_refs)
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .build()];
+
+        let renderpass_create_info = vk::RenderPassCreateInfo::builder()
+            .attachments(&renderpass_attachments)
+            .subpasses(&subpasses)
+            .dependencies(&dependencies);
+
+        let render_pass = unsafe { device.as_raw_vulkan().create_render_pass(&renderpass_create_info, None)? };
+
+        Ok(Self {
+            render_pass,
+            device: device.clone(),
+        })
+    }
+
+    /// Creates a `SwapchainRenderPass` that renders the scene into the color image of a `RenderTarget`
+    ///
+    /// The color image is left in `SHADER_READ_ONLY_OPTIMAL` so that it can be sampled by the overlay afterwards.
+    pub fn new_render_target(device: &Arc<Device>) -> crate::Result<Self> {
+        let renderpass_attachments = [
+            vk::AttachmentDescription {
+                format: RENDER_TARGET_FORMAT,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
+            },
+            vk::AttachmentDescription {
+                format: vk::Format::D24_UNORM_S8_UINT,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                stencil_load_op: vk::AttachmentLoadOp::CLEAR,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                ..Default::default()
+            },
+        ];
+        let color_attachment_refs = [vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        }];
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 1,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+        let dependencies = [
+            // Depth Attachment
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL) // including everything before the subpass
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ)
+                .build(),
+            // Color Attachment sampled in the previous frame
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL) // including everything before the subpass
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::TRANSFER)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::NONE)
+                .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::COLOR_ATTACHMENT_READ)
+                .build(),
+            // Color Attachment sampled after the subpass
+            vk::SubpassDependency::builder()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL) // including everything after the subpass
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::TRANSFER)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::TRANSFER_READ)
+                .build(),
         ];
 
         let subpasses = [vk::SubpassDescription::builder()
@@ -120,5 +354,8 @@ mod tests {
         let device = Device::new(physical_device, &instance, queue_plan).unwrap();
         let swapchain = Swapchain::new(&device, &surface, 2, None).unwrap();
         let _swapchain_renderpass = SwapchainRenderPass::new(&device, &swapchain).unwrap();
+        let _load_renderpass = SwapchainRenderPass::new_load(&device, &swapchain).unwrap();
+        let _post_processing_renderpass = SwapchainRenderPass::new_post_processing(&device, &swapchain).unwrap();
+        let _render_target_renderpass = SwapchainRenderPass::new_render_target(&device).unwrap();
     }
 }
@@ -3,13 +3,13 @@ use jeriya_shared::{AsDebugInfo, DebugInfo};
 
 use std::{marker::PhantomData, mem, slice, sync::Arc};
 
-use crate::{device::Device, AsRawVulkan, DebugInfoAshExtension, Error};
+use crate::{device::Device, memory_allocator::MemoryAllocation, AsRawVulkan, DebugInfoAshExtension};
 
 /// Buffer implementation that is used by [`DeviceVisibleBuffer`] and [`HostVisibleBuffer`]
 pub struct UnsafeBuffer<T> {
     device: Arc<Device>,
     buffer: vk::Buffer,
-    buffer_memory: Option<vk::DeviceMemory>,
+    buffer_memory: Option<MemoryAllocation>,
     byte_size: usize,
     phantom_data: PhantomData<T>,
     debug_info: DebugInfo,
@@ -32,6 +32,7 @@ impl<T: Clone> UnsafeBuffer<T> {
             .queue_family_indices(&device.queue_plan.queue_family_indices);
         let buffer = device.as_raw_vulkan().create_buffer(&buffer_create_info, None)?;
         let debug_info = debug_info.with_vulkan_ptr(buffer);
+        device.set_debug_name(buffer, &debug_info);
         Ok(Self {
             device: device.clone(),
             buffer,
@@ -42,52 +43,44 @@ impl<T: Clone> UnsafeBuffer<T> {
         })
     }
 
-    /// Allocates memory for the buffer and binds it to the buffer
+    /// Allocates memory for the buffer from the [`MemoryAllocator`](crate::memory_allocator::MemoryAllocator) of the device and binds it to the buffer
     pub unsafe fn allocate_memory(&mut self, memory_properties: vk::MemoryPropertyFlags) -> crate::Result<()> {
-        assert_eq!(self.buffer_memory, None, "allocate_memory must only be called once");
-        let device = self.device.as_raw_vulkan();
-        let memory_requirements = device.get_buffer_memory_requirements(self.buffer);
-        let memory_type_index = self
+        assert!(self.buffer_memory.is_none(), "allocate_memory must only be called once");
+        let memory_requirements = self.device.as_raw_vulkan().get_buffer_memory_requirements(self.buffer);
+        let buffer_memory = self
+            .device
+            .allocate_memory(&memory_requirements, memory_properties, &self.debug_info)?;
+        if let Err(err) = self
             .device
-            .find_memorytype_index(&memory_requirements, memory_properties)
-            .ok_or(Error::UnsupportedMemoryType(memory_requirements))?;
-        let vertex_buffer_allocate_info = vk::MemoryAllocateInfo {
-            allocation_size: memory_requirements.size,
-            memory_type_index,
-            ..Default::default()
-        };
-        let buffer_memory = device.allocate_memory(&vertex_buffer_allocate_info, None)?;
-        device.bind_buffer_memory(self.buffer, buffer_memory, 0)?;
+            .as_raw_vulkan()
+            .bind_buffer_memory(self.buffer, buffer_memory.memory(), buffer_memory.offset())
+        {
+            self.device.free_memory(buffer_memory);
+            return Err(err.into());
+        }
         self.buffer_memory = Some(buffer_memory);
         Ok(())
     }
 
-    /// Maps the memory of the buffer and returns a slice to it
-    unsafe fn map_buffer_memory_mut(&mut self) -> crate::Result<(&mut [T], vk::DeviceMemory)> {
-        assert!(self.buffer_memory.is_some(), "allocate_memory must be called before set_memory");
+    /// Returns the pointer to the persistently mapped memory of the buffer
+    fn mapped_ptr(&self) -> *mut T {
         let buffer_memory = self
             .buffer_memory
-            .expect("buffer_memory must be initialized when set_memory is called");
-        let ptr = self
-            .device
-            .as_raw_vulkan()
-            .map_memory(buffer_memory, 0, self.byte_size as u64, vk::MemoryMapFlags::empty())?;
-        let slice = slice::from_raw_parts_mut(ptr as *mut T, self.byte_size / mem::size_of::<T>());
-        Ok((slice, buffer_memory))
+            .as_ref()
+            .expect("allocate_memory must be called before the memory is accessed");
+        buffer_memory
+            .mapped_ptr()
+            .expect("the memory of the buffer must be host visible to be accessed") as *mut T
     }
 
-    /// Maps the memory of the buffer and returns a slice to it
-    unsafe fn map_buffer_memory(&self) -> crate::Result<(&[T], vk::DeviceMemory)> {
-        assert!(self.buffer_memory.is_some(), "allocate_memory must be called before set_memory");
-        let buffer_memory = self
-            .buffer_memory
-            .expect("buffer_memory must be initialized when set_memory is called");
-        let ptr = self
-            .device
-            .as_raw_vulkan()
-            .map_memory(buffer_memory, 0, self.byte_size as u64, vk::MemoryMapFlags::empty())?;
-        let slice = slice::from_raw_parts(ptr as *const T, self.byte_size / mem::size_of::<T>());
-        Ok((slice, buffer_memory))
+    /// Returns a mutable slice to the mapped memory of the buffer
+    unsafe fn map_buffer_memory_mut(&mut self) -> &mut [T] {
+        slice::from_raw_parts_mut(self.mapped_ptr(), self.byte_size / mem::size_of::<T>())
+    }
+
+    /// Returns a slice to the mapped memory of the buffer
+    unsafe fn map_buffer_memory(&self) -> &[T] {
+        slice::from_raw_parts(self.mapped_ptr(), self.byte_size / mem::size_of::<T>())
     }
 
     /// Copies the given `data` into the buffer
@@ -101,9 +94,7 @@ impl<T: Clone> UnsafeBuffer<T> {
             mem::size_of_val(data),
             "the data has to fit into the buffer exactly"
         );
-        let (slice, buffer_memory) = self.map_buffer_memory_mut()?;
-        slice.clone_from_slice(data);
-        self.device.as_raw_vulkan().unmap_memory(buffer_memory);
+        self.map_buffer_memory_mut().clone_from_slice(data);
         Ok(())
     }
 
@@ -119,9 +110,7 @@ impl<T: Clone> UnsafeBuffer<T> {
             self.byte_size > offset + size,
             "the data doesn't fit into the buffer at the given offset"
         );
-        let (slice, buffer_memory) = self.map_buffer_memory_mut()?;
-        slice[index..index + 1].clone_from_slice(slice::from_ref(value));
-        self.device.as_raw_vulkan().unmap_memory(buffer_memory);
+        self.map_buffer_memory_mut()[index..index + 1].clone_from_slice(slice::from_ref(value));
         Ok(())
     }
 
@@ -132,9 +121,7 @@ impl<T: Clone> UnsafeBuffer<T> {
     /// Panics if the `data` does not have the same size as the buffer
     pub unsafe fn get_memory_unaligned(&self, data: &mut [T]) -> crate::Result<()> {
         assert_eq!(self.byte_size, mem::size_of_val(data), "data must have the same size as the buffer");
-        let (slice, buffer_memory) = self.map_buffer_memory()?;
-        data.clone_from_slice(slice);
-        self.device.as_raw_vulkan().unmap_memory(buffer_memory);
+        data.clone_from_slice(self.map_buffer_memory());
         Ok(())
     }
 
@@ -144,10 +131,7 @@ impl<T: Clone> UnsafeBuffer<T> {
     ///
     /// Panics if `index` is out of bounds.
     pub unsafe fn get_memory_unaligned_index(&self, index: usize) -> crate::Result<T> {
-        let (slice, buffer_memory) = self.map_buffer_memory()?;
-        let value = slice[index].clone();
-        self.device.as_raw_vulkan().unmap_memory(buffer_memory);
-        Ok(value)
+        Ok(self.map_buffer_memory()[index].clone())
     }
 
     /// Returns the size of the buffer in bytes
@@ -159,11 +143,10 @@ impl<T: Clone> UnsafeBuffer<T> {
 impl<T> Drop for UnsafeBuffer<T> {
     fn drop(&mut self) {
         unsafe {
-            let device = self.device.as_raw_vulkan();
-            if let Some(buffer_memory) = self.buffer_memory {
-                device.free_memory(buffer_memory, None);
+            self.device.as_raw_vulkan().destroy_buffer(self.buffer, None);
+            if let Some(buffer_memory) = self.buffer_memory.take() {
+                self.device.free_memory(buffer_memory);
             }
-            device.destroy_buffer(self.buffer, None);
         }
     }
 }
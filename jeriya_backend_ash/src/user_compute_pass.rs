@@ -0,0 +1,89 @@
+use jeriya_content::common::AssetKey;
+
+use crate::custom_pass::{Access, CustomPassId, CustomPassPosition, FrameResource, PassStage, ResourceAccess};
+
+/// Size of the workgroups of a [`UserComputePass`] that is dispatched with [`UserComputePassDispatch::PerRigidMeshInstance`]
+pub const USER_COMPUTE_PASS_LOCAL_SIZE_X: u32 = 128;
+
+/// Number of workgroups with which a [`UserComputePass`] is dispatched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserComputePassDispatch {
+    /// One invocation per rigid mesh instance slot. The shader must take its `local_size_x` from the `LocalSizeX`
+    /// specialization constant which is set to [`USER_COMPUTE_PASS_LOCAL_SIZE_X`].
+    PerRigidMeshInstance,
+    /// Fixed number of workgroups in x, y and z
+    WorkgroupCount(u32, u32, u32),
+}
+
+/// Compute shader of the application that is dispatched every frame
+///
+/// The shader is bound with the same descriptors as the culling shaders so that it can access the `PerFrameData`,
+/// the rigid mesh instances, the cameras and the camera instances with the bindings of the shader interface. The
+/// barriers around the dispatch are derived from the [`Access`] to the [`FrameResource::SceneBuffers`]. The results
+/// of the culling may only be read.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UserComputePass {
+    pub shader: AssetKey,
+    pub position: CustomPassPosition,
+    pub dispatch: UserComputePassDispatch,
+    /// Whether the shader reads or writes the scene buffers
+    pub access: Access,
+}
+
+impl UserComputePass {
+    /// Creates a new `UserComputePass` that reads the scene buffers with one invocation per rigid mesh instance
+    pub fn new(shader: AssetKey, position: CustomPassPosition) -> Self {
+        Self {
+            shader,
+            position,
+            dispatch: UserComputePassDispatch::PerRigidMeshInstance,
+            access: Access::Read,
+        }
+    }
+
+    /// Sets the [`UserComputePassDispatch`] of the pass
+    pub fn with_dispatch(mut self, dispatch: UserComputePassDispatch) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    /// Sets the [`Access`] of the pass to the scene buffers
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Returns the [`ResourceAccess`]es from which the barriers around the dispatch are derived
+    pub(crate) fn accesses(&self) -> Vec<ResourceAccess> {
+        let scene_buffers = ResourceAccess {
+            resource: FrameResource::SceneBuffers,
+            access: self.access,
+            stage: PassStage::Compute,
+        };
+        vec![
+            scene_buffers,
+            ResourceAccess::read(FrameResource::CullingResults, PassStage::Compute),
+        ]
+    }
+}
+
+/// [`UserComputePass`] that has been added to a window
+#[derive(Debug, Clone)]
+pub(crate) struct RegisteredUserComputePass {
+    pub id: CustomPassId,
+    pub user_compute_pass: UserComputePass,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accesses() {
+        let user_compute_pass =
+            UserComputePass::new(AssetKey::new("shaders/query.comp"), CustomPassPosition::AfterCulling).with_access(Access::Write);
+        let accesses = user_compute_pass.accesses();
+        assert_eq!(accesses[0], ResourceAccess::write(FrameResource::SceneBuffers, PassStage::Compute));
+        assert_eq!(accesses[1], ResourceAccess::read(FrameResource::CullingResults, PassStage::Compute));
+    }
+}
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, mem, sync::Arc};
+
+use ash::vk;
+use jeriya_backend::ui::{UiFrame, UiMesh, UiTextureDelta, UiTextureId};
+use jeriya_shared::{debug_info, log::warn};
+
+use crate::{
+    buffer::BufferUsageFlags, command_buffer_builder::CommandBufferBuilder, device::Device, device_image::DeviceImage,
+    host_visible_buffer::HostVisibleBuffer, vulkan_resource_coordinator::VulkanResourceCoordinator,
+};
+
+/// User interface of a presenter that is rendered on top of the overlay
+///
+/// The meshes of the most recent [`UiFrame`] are rendered every frame. The texture updates are queued when the
+/// `UiFrame` is received and recorded before the next frame is rendered.
+#[derive(Default)]
+pub struct UserInterface {
+    meshes: Vec<UiMesh>,
+    pending_operations: Vec<TextureOperation>,
+    /// Textures that are freed when the next `UiFrame` is received because the meshes of the current one might still use them
+    deferred_frees: Vec<UiTextureId>,
+    textures: HashMap<UiTextureId, UiTexture>,
+}
+
+enum TextureOperation {
+    Set(UiTextureDelta),
+    Free(UiTextureId),
+}
+
+struct UiTexture {
+    texture_index: u32,
+    device_image: Arc<DeviceImage>,
+}
+
+impl UserInterface {
+    /// Replaces the meshes with the ones of the `ui_frame` and queues its texture updates
+    ///
+    /// Returns an error and keeps the previous meshes when the indices of a mesh don't form triangles of its vertices.
+    pub fn push_frame(&mut self, ui_frame: UiFrame) -> crate::Result<()> {
+        Self::validate(&ui_frame)?;
+        self.pending_operations
+            .extend(self.deferred_frees.drain(..).map(TextureOperation::Free));
+        self.pending_operations
+            .extend(ui_frame.textures_set.into_iter().map(TextureOperation::Set));
+        self.deferred_frees = ui_frame.textures_free;
+        self.meshes = ui_frame.meshes;
+        Ok(())
+    }
+
+    /// Returns an error when an index of a mesh doesn't reference one of its vertices or doesn't belong to a whole triangle
+    pub fn validate(ui_frame: &UiFrame) -> crate::Result<()> {
+        for (mesh_index, mesh) in ui_frame.meshes.iter().enumerate() {
+            if mesh.indices.len() % 3 != 0 {
+                return Err(crate::Error::InvalidUiMesh {
+                    mesh: mesh_index,
+                    details: format!("the number of indices {} is not a multiple of 3", mesh.indices.len()),
+                });
+            }
+            if let Some(index) = mesh.indices.iter().find(|index| **index as usize >= mesh.vertices.len()) {
+                return Err(crate::Error::InvalidUiMesh {
+                    mesh: mesh_index,
+                    details: format!("the index {index} exceeds the number of vertices {}", mesh.vertices.len()),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the meshes that are rendered
+    pub fn meshes(&self) -> &[UiMesh] {
+        &self.meshes
+    }
+
+    /// Returns whether any of the meshes contains triangles
+    pub fn has_triangles(&self) -> bool {
+        self.meshes.iter().any(|mesh| !mesh.indices.is_empty())
+    }
+
+    /// Returns the index of the bindless texture for the given `id` if it has been uploaded
+    pub fn texture_index(&self, id: UiTextureId) -> Option<u32> {
+        self.textures.get(&id).map(|texture| texture.texture_index)
+    }
+
+    /// Records the pending texture updates
+    ///
+    /// Has to be called outside of a render pass. Partial updates only copy their region into the existing texture.
+    /// The slots of bindless textures that have been replaced or freed are only reused after the frames that might
+    /// still sample them have finished. The updates are dropped when the device doesn't support
+    /// [`OptionalFeature::DescriptorIndexing`](crate::OptionalFeature::DescriptorIndexing).
+    pub fn upload_textures(
+        &mut self,
+        device: &Arc<Device>,
+        vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+        command_buffer_builder: &mut CommandBufferBuilder,
+    ) -> crate::Result<()> {
+        if !device.optional_features.descriptor_indexing {
+            self.pending_operations.clear();
+            return Ok(());
+        }
+
+        for operation in mem::take(&mut self.pending_operations) {
+            match operation {
+                TextureOperation::Set(texture_delta) => {
+                    let texture = self.textures.get(&texture_delta.id);
+                    let extent = texture.map(|texture| texture.device_image.extent());
+                    if let Some(reason) = texture_delta_error(&texture_delta, extent) {
+                        warn!("Ignoring UiTextureDelta for {:?} because {reason}", texture_delta.id);
+                        continue;
+                    }
+                    match (texture_delta.position, texture) {
+                        (Some([x, y]), Some(texture)) => {
+                            let host_visible_buffer = Arc::new(HostVisibleBuffer::new(
+                                device,
+                                texture_delta.rgba.as_slice(),
+                                BufferUsageFlags::TRANSFER_SRC_BIT,
+                                debug_info!("UiTextureDelta-HostVisibleBuffer"),
+                            )?);
+                            command_buffer_builder.copy_buffer_to_image_region(
+                                &host_visible_buffer,
+                                &texture.device_image,
+                                vk::Offset2D { x: x as i32, y: y as i32 },
+                                vk::Extent2D {
+                                    width: texture_delta.width,
+                                    height: texture_delta.height,
+                                },
+                            );
+                        }
+                        _ => {
+                            let new_texture = upload(&texture_delta, device, vulkan_resource_coordinator, command_buffer_builder)?;
+                            if let Some(old_texture) = self.textures.insert(texture_delta.id, new_texture) {
+                                vulkan_resource_coordinator
+                                    .bindless_textures_mut()
+                                    .remove(old_texture.texture_index);
+                            }
+                        }
+                    }
+                }
+                TextureOperation::Free(id) => {
+                    if let Some(texture) = self.textures.remove(&id) {
+                        vulkan_resource_coordinator.bindless_textures_mut().remove(texture.texture_index);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns why the `texture_delta` can't be applied or `None` when it can be applied
+///
+/// `extent` is the extent of the existing texture with the id of the `texture_delta`.
+fn texture_delta_error(texture_delta: &UiTextureDelta, extent: Option<vk::Extent2D>) -> Option<&'static str> {
+    let UiTextureDelta {
+        position,
+        width,
+        height,
+        rgba,
+        ..
+    } = texture_delta;
+    if *width == 0 || *height == 0 || rgba.len() != *width as usize * *height as usize * 4 {
+        return Some("the length of the texels doesn't match the dimensions");
+    }
+    let Some([x, y]) = position else {
+        return None;
+    };
+    let Some(extent) = extent else {
+        return Some("the texture of the partial update doesn't exist");
+    };
+    if x + width > extent.width || y + height > extent.height {
+        return Some("the region exceeds the texture");
+    }
+    None
+}
+
+/// Records the upload of the whole texture of the `texture_delta` into a new bindless texture
+fn upload(
+    texture_delta: &UiTextureDelta,
+    device: &Arc<Device>,
+    vulkan_resource_coordinator: &mut VulkanResourceCoordinator,
+    command_buffer_builder: &mut CommandBufferBuilder,
+) -> crate::Result<UiTexture> {
+    let extent = vk::Extent2D {
+        width: texture_delta.width,
+        height: texture_delta.height,
+    };
+    // The texels are displayed as they are because the swapchain isn't guaranteed to have an sRGB format. The
+    // texture has a single mip level so that partial updates don't have to regenerate the mip chain.
+    let device_image = Arc::new(DeviceImage::with_mip_level_count(
+        device,
+        extent,
+        vk::Format::R8G8B8A8_UNORM,
+        1,
+        debug_info!("UiTexture"),
+    )?);
+    let host_visible_buffer = Arc::new(HostVisibleBuffer::new(
+        device,
+        texture_delta.rgba.as_slice(),
+        BufferUsageFlags::TRANSFER_SRC_BIT,
+        debug_info!("UiTexture-HostVisibleBuffer"),
+    )?);
+    command_buffer_builder
+        .copy_buffer_to_image(&host_visible_buffer, &device_image)
+        .generate_mipmaps(&device_image);
+    let sampler = vulkan_resource_coordinator.query_sampler(&texture_delta.sampler_config)?;
+    let texture_index = vulkan_resource_coordinator
+        .bindless_textures_mut()
+        .insert(&device_image, &sampler)?;
+    Ok(UiTexture {
+        texture_index,
+        device_image,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_backend::ui::UiVertex;
+    use jeriya_shared::{nalgebra::Vector2, SamplerConfig};
+
+    use super::*;
+
+    fn texture_delta(position: Option<[u32; 2]>, width: u32, height: u32, value: u8) -> UiTextureDelta {
+        UiTextureDelta {
+            id: UiTextureId(7),
+            position,
+            width,
+            height,
+            rgba: vec![value; width as usize * height as usize * 4],
+            sampler_config: SamplerConfig::linear(),
+        }
+    }
+
+    #[test]
+    fn partial_texture_delta() {
+        let extent = Some(vk::Extent2D { width: 4, height: 4 });
+        assert_eq!(texture_delta_error(&texture_delta(Some([1, 2]), 2, 1, 255), extent), None);
+        assert_eq!(texture_delta_error(&texture_delta(Some([2, 3]), 2, 1, 255), extent), None);
+        assert!(texture_delta_error(&texture_delta(Some([3, 3]), 2, 1, 255), extent).is_some());
+    }
+
+    #[test]
+    fn invalid_texture_deltas_are_ignored() {
+        assert!(texture_delta_error(&texture_delta(Some([0, 0]), 1, 1, 0), None).is_some());
+        let mut wrong_length = texture_delta(None, 2, 2, 0);
+        wrong_length.rgba.pop();
+        assert!(texture_delta_error(&wrong_length, None).is_some());
+        assert_eq!(texture_delta_error(&texture_delta(None, 2, 2, 0), None), None);
+    }
+
+    #[test]
+    fn invalid_meshes_are_rejected() {
+        let vertex = UiVertex {
+            position: Vector2::zeros(),
+            texture_coordinates: Vector2::zeros(),
+            color: [255; 4],
+        };
+        let mesh = |indices: Vec<u32>| UiMesh {
+            clip_rect_min: Vector2::zeros(),
+            clip_rect_max: Vector2::new(100.0, 100.0),
+            texture: UiTextureId(0),
+            vertices: vec![vertex; 3],
+            indices,
+        };
+        let mut user_interface = UserInterface::default();
+        let ui_frame = |meshes| UiFrame {
+            meshes,
+            ..Default::default()
+        };
+        assert!(user_interface.push_frame(ui_frame(vec![mesh(vec![0, 1, 2])])).is_ok());
+        assert!(matches!(
+            user_interface.push_frame(ui_frame(vec![mesh(vec![0, 1, 2]), mesh(vec![0, 1, 3])])),
+            Err(crate::Error::InvalidUiMesh { mesh: 1, .. })
+        ));
+        assert!(matches!(
+            user_interface.push_frame(ui_frame(vec![mesh(vec![0, 1])])),
+            Err(crate::Error::InvalidUiMesh { mesh: 0, .. })
+        ));
+        // The meshes of the last valid frame are kept
+        assert_eq!(user_interface.meshes().len(), 1);
+    }
+
+    #[test]
+    fn frees_are_deferred_to_the_next_frame() {
+        let mut user_interface = UserInterface::default();
+        user_interface
+            .push_frame(UiFrame {
+                textures_set: vec![texture_delta(None, 1, 1, 0)],
+                meshes: Vec::new(),
+                textures_free: vec![UiTextureId(3)],
+            })
+            .unwrap();
+        assert!(matches!(user_interface.pending_operations.as_slice(), [TextureOperation::Set(_)]));
+        user_interface.push_frame(UiFrame::default()).unwrap();
+        assert!(matches!(
+            user_interface.pending_operations.as_slice(),
+            [TextureOperation::Set(_), TextureOperation::Free(UiTextureId(3))]
+        ));
+    }
+}
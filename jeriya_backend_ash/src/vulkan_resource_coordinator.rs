@@ -1,26 +1,45 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use ash::vk;
 
 use crate::{
+    bindless_textures::BindlessTextures,
     compute_pipeline::{GenericComputePipeline, GenericComputePipelineConfig},
+    descriptor_set_layout::DescriptorSetLayout,
     device::Device,
-    graphics_pipeline::GenericGraphicsPipeline,
-    graphics_pipeline::GenericGraphicsPipelineConfig,
+    device_image::DeviceImage,
+    graphics_pipeline::{GenericGraphicsPipeline, GenericGraphicsPipelineConfig, PipelineRenderPass},
+    persistent_frame_state::PersistentFrameState,
+    pipeline_cache::PipelineCache,
+    render_target::RenderTarget,
+    sampler::Sampler,
     specialization_constants::SpecializationConstants,
     swapchain::Swapchain,
     swapchain_depth_buffer::SwapchainDepthBuffers,
     swapchain_framebuffers::SwapchainFramebuffers,
     swapchain_render_pass::SwapchainRenderPass,
+    swapchain_vec::SwapchainVec,
 };
+use jeriya_backend::{gpu_index_allocator::GpuIndexAllocation, instances::camera_instance::CameraInstance, render_target::RenderTargetId};
 use jeriya_content::asset_importer::{Asset, AssetImporter};
 use jeriya_content::common::AssetKey;
 use jeriya_content::shader::ShaderAsset;
-use jeriya_shared::{ahash, log::info, RendererConfig};
+use jeriya_shared::{
+    ahash,
+    log::{error, info, warn},
+    ColorSpace, RendererConfig, SamplerConfig,
+};
 use jeriya_shared::{debug_info, Handle, IndexingContainer};
 
 /// Responsible for creating vulkan resources and managing their dependencies.
 pub struct VulkanResourceCoordinator {
     device: Arc<Device>,
 
+    pipeline_cache: Arc<PipelineCache>,
+
     asset_importer: Arc<AssetImporter>,
 
     specialization_constants: SpecializationConstants,
@@ -34,15 +53,33 @@ pub struct VulkanResourceCoordinator {
 
     shader_asset_graphics_pipeline_mapping: ahash::HashMap<AssetKey, ahash::HashSet<Handle<Arc<GenericGraphicsPipeline>>>>,
     shader_asset_compute_pipeline_mapping: ahash::HashMap<AssetKey, ahash::HashSet<Handle<Arc<GenericComputePipeline>>>>,
+    /// Errors of the compute pipelines that couldn't be built. They are not built again until their shader is updated.
+    failed_compute_pipelines: ahash::HashMap<GenericComputePipelineConfig, String>,
 
     swapchain_depth_buffers: SwapchainDepthBuffers,
     swapchain_framebuffers: SwapchainFramebuffers,
     swapchain_render_pass: SwapchainRenderPass,
+    swapchain_load_render_pass: SwapchainRenderPass,
+    post_processing_framebuffers: SwapchainFramebuffers,
+    post_processing_render_pass: SwapchainRenderPass,
+    render_target_render_pass: SwapchainRenderPass,
+    /// Copies of the swapchain images that are sampled by the output transform. `None` when the swapchain is SDR.
+    output_transform_images: Option<SwapchainVec<Arc<DeviceImage>>>,
+    /// Copies of the swapchain images that are sampled by the post-processing passes. Only created when a window has post-processing passes.
+    post_processing_input_images: Option<SwapchainVec<Arc<DeviceImage>>>,
+
+    render_targets: BTreeMap<RenderTargetId, Arc<RenderTarget>>,
+
+    bindless_textures: BindlessTextures,
+    /// Layout of the [`CachedDescriptorSet`](crate::cached_descriptor_set::CachedDescriptorSet)s that is part of the layout of every pipeline
+    cached_descriptor_set_layout: Arc<DescriptorSetLayout>,
+    samplers: ahash::HashMap<SamplerConfig, Arc<Sampler>>,
 }
 
 impl VulkanResourceCoordinator {
     pub fn new(
         device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
         asset_importer: &Arc<AssetImporter>,
         swapchain: &Swapchain,
         renderer_config: &RendererConfig,
@@ -51,6 +88,11 @@ impl VulkanResourceCoordinator {
         let swapchain_depth_buffers = SwapchainDepthBuffers::new(device, swapchain)?;
         let swapchain_render_pass = SwapchainRenderPass::new(device, swapchain)?;
         let swapchain_framebuffers = SwapchainFramebuffers::new(device, swapchain, &swapchain_depth_buffers, &swapchain_render_pass)?;
+        let swapchain_load_render_pass = SwapchainRenderPass::new_load(device, swapchain)?;
+        let post_processing_render_pass = SwapchainRenderPass::new_post_processing(device, swapchain)?;
+        let post_processing_framebuffers = SwapchainFramebuffers::new_post_processing(device, swapchain, &post_processing_render_pass)?;
+        let render_target_render_pass = SwapchainRenderPass::new_render_target(device)?;
+        let output_transform_images = create_output_transform_images(device, swapchain)?;
 
         info!("Creating specialization constants");
         let specialization_constants = {
@@ -67,19 +109,31 @@ impl VulkanResourceCoordinator {
             specialization_constants.push(9, renderer_config.maximum_number_of_point_clouds as u32);
             specialization_constants.push(10, renderer_config.maximum_number_of_point_cloud_instances as u32);
             specialization_constants.push(11, renderer_config.maximum_number_of_point_cloud_pages as u32);
-            specialization_constants.push(12, 0);
+            specialization_constants.push(12, renderer_config.maximum_number_of_point_cloud_page_requests as u32);
             specialization_constants.push(13, 0);
             specialization_constants.push(14, renderer_config.maximum_number_of_visible_point_cloud_clusters as u32);
             specialization_constants.push(15, renderer_config.maximum_number_of_device_local_debug_lines as u32);
             specialization_constants
         };
 
+        info!("Creating bindless texture array");
+        let bindless_textures = BindlessTextures::new(
+            device,
+            renderer_config.maximum_number_of_textures as u32,
+            debug_info!("BindlessTextures"),
+        )?;
+
+        info!("Creating cached descriptor set layout");
+        let cached_descriptor_set_layout = Arc::new(PersistentFrameState::cached_descriptor_set_layout(device)?);
+
         Ok(VulkanResourceCoordinator {
             device: device.clone(),
+            pipeline_cache: pipeline_cache.clone(),
             asset_importer: asset_importer.clone(),
             specialization_constants,
             graphics_pipeline_mapping: HashMap::default(),
             compute_pipelines_mapping: HashMap::default(),
+            failed_compute_pipelines: HashMap::default(),
             graphics_pipelines: IndexingContainer::new(),
             compute_pipelines: IndexingContainer::new(),
             shader_asset_graphics_pipeline_mapping: HashMap::default(),
@@ -87,6 +141,16 @@ impl VulkanResourceCoordinator {
             swapchain_depth_buffers,
             swapchain_framebuffers,
             swapchain_render_pass,
+            swapchain_load_render_pass,
+            post_processing_framebuffers,
+            post_processing_render_pass,
+            render_target_render_pass,
+            output_transform_images,
+            post_processing_input_images: None,
+            render_targets: BTreeMap::new(),
+            bindless_textures,
+            cached_descriptor_set_layout,
+            samplers: HashMap::default(),
         })
     }
 
@@ -95,6 +159,12 @@ impl VulkanResourceCoordinator {
         self.swapchain_render_pass = SwapchainRenderPass::new(&self.device, swapchain)?;
         self.swapchain_framebuffers =
             SwapchainFramebuffers::new(&self.device, swapchain, &self.swapchain_depth_buffers, &self.swapchain_render_pass)?;
+        self.swapchain_load_render_pass = SwapchainRenderPass::new_load(&self.device, swapchain)?;
+        self.post_processing_render_pass = SwapchainRenderPass::new_post_processing(&self.device, swapchain)?;
+        self.post_processing_framebuffers =
+            SwapchainFramebuffers::new_post_processing(&self.device, swapchain, &self.post_processing_render_pass)?;
+        self.output_transform_images = create_output_transform_images(&self.device, swapchain)?;
+        self.post_processing_input_images = None;
         Ok(())
     }
 
@@ -108,9 +178,14 @@ impl VulkanResourceCoordinator {
                     .expect("pipeline not found due to inconsistent mapping")
                     .config
                     .clone();
-                self.try_build_graphics_pipeline(&old_pipeline_config)?;
+                // A shader that doesn't compile must not stop the renderer. The previous pipeline is used until the shader is fixed.
+                if let Err(err) = self.try_build_graphics_pipeline(&old_pipeline_config) {
+                    error!("Failed to rebuild the GenericGraphicsPipeline after updating the shader: {err}");
+                }
             }
         }
+        self.failed_compute_pipelines
+            .retain(|config, _| &config.shader != shader_asset.asset_key());
         if let Some(compute_pipeline_handles) = self.shader_asset_compute_pipeline_mapping.get(shader_asset.asset_key()).cloned() {
             for handle in compute_pipeline_handles.iter() {
                 let old_pipeline_config = self
@@ -119,7 +194,9 @@ impl VulkanResourceCoordinator {
                     .expect("pipeline not found due to inconsistent mapping")
                     .config
                     .clone();
-                self.try_build_compute_pipeline(&old_pipeline_config)?;
+                if let Err(err) = self.try_build_compute_pipeline(&old_pipeline_config) {
+                    error!("Failed to rebuild the GenericComputePipeline after updating the shader: {err}");
+                }
             }
         }
         Ok(())
@@ -192,17 +269,35 @@ impl VulkanResourceCoordinator {
                 details: "Asset not found via the get method. Starting and import if it's not already running.".to_owned(),
             });
         };
+        let render_pass = match config.render_pass {
+            PipelineRenderPass::Scene => &self.swapchain_render_pass,
+            PipelineRenderPass::PostProcessing => &self.post_processing_render_pass,
+            PipelineRenderPass::RenderTarget => &self.render_target_render_pass,
+        };
         let pipeline = Arc::new(GenericGraphicsPipeline::new(
             &self.device,
+            &self.pipeline_cache,
             config,
             &vertex_shader_spirv,
             &fragment_shader_spirv,
-            &self.swapchain_render_pass,
+            render_pass,
             &self.specialization_constants,
+            &self.bindless_textures,
+            &self.cached_descriptor_set_layout,
             debug_info!("GenericGraphicsPipeline"),
         )?);
-        let handle = self.graphics_pipelines.insert(pipeline.clone());
-        self.graphics_pipeline_mapping.insert(config.clone(), handle);
+        let handle = if let Some(handle) = self.graphics_pipeline_mapping.get(config) {
+            // Swap the rebuilt pipeline in so that the next frame uses it. Frames in flight keep the previous one alive.
+            *self
+                .graphics_pipelines
+                .get_mut(handle)
+                .expect("pipeline not found due to inconsistent mapping") = pipeline.clone();
+            *handle
+        } else {
+            let handle = self.graphics_pipelines.insert(pipeline.clone());
+            self.graphics_pipeline_mapping.insert(config.clone(), handle);
+            handle
+        };
         self.shader_asset_graphics_pipeline_mapping
             .entry(vertex_shader.clone())
             .or_default()
@@ -214,7 +309,17 @@ impl VulkanResourceCoordinator {
         Ok(pipeline)
     }
 
+    /// Returns the [`GenericComputePipeline`] for the given config and builds it when it is queried for the first time.
+    ///
+    /// A pipeline that couldn't be built returns [`Error::PipelineFailedBefore`](crate::Error::PipelineFailedBefore)
+    /// until its shader is updated so that the import and compilation aren't retried every frame.
     pub fn query_compute_pipeline(&mut self, config: &GenericComputePipelineConfig) -> crate::Result<Arc<GenericComputePipeline>> {
+        if let Some(details) = self.failed_compute_pipelines.get(config) {
+            return Err(crate::Error::PipelineFailedBefore {
+                asset_key: config.shader.clone(),
+                details: details.clone(),
+            });
+        }
         if self.compute_pipelines_mapping.contains_key(config) {
             let handle = &self.compute_pipelines_mapping[config];
             let pipeline = self
@@ -224,7 +329,9 @@ impl VulkanResourceCoordinator {
                 .clone();
             Ok(pipeline)
         } else {
-            self.try_build_compute_pipeline(config)
+            self.try_build_compute_pipeline(config).inspect_err(|err| {
+                self.failed_compute_pipelines.insert(config.clone(), err.to_string());
+            })
         }
     }
 
@@ -256,13 +363,25 @@ impl VulkanResourceCoordinator {
         };
         let pipeline = Arc::new(GenericComputePipeline::new(
             &self.device,
+            &self.pipeline_cache,
             config,
             &shader_spirv,
             &self.specialization_constants,
+            &self.bindless_textures,
+            &self.cached_descriptor_set_layout,
             debug_info!("GenericComputePipeline"),
         )?);
-        let handle = self.compute_pipelines.insert(pipeline.clone());
-        self.compute_pipelines_mapping.insert(config.clone(), handle);
+        let handle = if let Some(handle) = self.compute_pipelines_mapping.get(config) {
+            *self
+                .compute_pipelines
+                .get_mut(handle)
+                .expect("pipeline not found due to inconsistent mapping") = pipeline.clone();
+            *handle
+        } else {
+            let handle = self.compute_pipelines.insert(pipeline.clone());
+            self.compute_pipelines_mapping.insert(config.clone(), handle);
+            handle
+        };
         self.shader_asset_compute_pipeline_mapping
             .entry(config.shader.clone())
             .or_default()
@@ -270,6 +389,16 @@ impl VulkanResourceCoordinator {
         Ok(pipeline)
     }
 
+    /// Returns the [`Sampler`] for the given [`SamplerConfig`]. Samplers are created once and shared between all textures.
+    pub fn query_sampler(&mut self, config: &SamplerConfig) -> crate::Result<Arc<Sampler>> {
+        if let Some(sampler) = self.samplers.get(config) {
+            return Ok(sampler.clone());
+        }
+        let sampler = Arc::new(Sampler::new(&self.device, config, debug_info!("Sampler"))?);
+        self.samplers.insert(*config, sampler.clone());
+        Ok(sampler)
+    }
+
     pub fn swapchain_depth_buffers(&self) -> &SwapchainDepthBuffers {
         &self.swapchain_depth_buffers
     }
@@ -278,23 +407,274 @@ impl VulkanResourceCoordinator {
         &self.swapchain_render_pass
     }
 
+    /// Returns the render pass that continues rendering into the [`VulkanResourceCoordinator::swapchain_framebuffers`]
+    pub fn swapchain_load_render_pass(&self) -> &SwapchainRenderPass {
+        &self.swapchain_load_render_pass
+    }
+
     pub fn swapchain_framebuffers(&self) -> &SwapchainFramebuffers {
         &self.swapchain_framebuffers
     }
+
+    /// Returns the render pass that runs after the scene has been rendered
+    pub fn post_processing_render_pass(&self) -> &SwapchainRenderPass {
+        &self.post_processing_render_pass
+    }
+
+    /// Returns the images into which the swapchain images are copied before the output transform or `None` when the
+    /// swapchain doesn't need an output transform
+    pub fn output_transform_images(&self) -> Option<&SwapchainVec<Arc<DeviceImage>>> {
+        self.output_transform_images.as_ref()
+    }
+
+    /// Creates the images into which the swapchain images are copied before the post-processing passes unless they already
+    /// exist. Returns `false` when the swapchain images can't be copied.
+    pub fn prepare_post_processing_input_images(&mut self, swapchain: &Swapchain) -> crate::Result<bool> {
+        if self.post_processing_input_images.is_none() && swapchain.supports_capture() {
+            self.post_processing_input_images = Some(create_swapchain_image_copies(&self.device, swapchain, "PostProcessingInput")?);
+        }
+        Ok(self.post_processing_input_images.is_some())
+    }
+
+    /// Returns the images that have been created by [`VulkanResourceCoordinator::prepare_post_processing_input_images`]
+    pub fn post_processing_input_images(&self) -> Option<&SwapchainVec<Arc<DeviceImage>>> {
+        self.post_processing_input_images.as_ref()
+    }
+
+    /// Returns the framebuffers of the [`VulkanResourceCoordinator::post_processing_render_pass`]
+    pub fn post_processing_framebuffers(&self) -> &SwapchainFramebuffers {
+        &self.post_processing_framebuffers
+    }
+
+    /// Returns the render pass in which the scene is rendered into the [`RenderTarget`]s
+    pub fn render_target_render_pass(&self) -> &SwapchainRenderPass {
+        &self.render_target_render_pass
+    }
+
+    /// Creates a [`RenderTarget`] with the given `extent` into which the scene is rendered from the `camera_instance`
+    ///
+    /// The color image is inserted into the bindless textures so that it can be sampled right away.
+    pub fn create_render_target(
+        &mut self,
+        id: RenderTargetId,
+        camera_instance: GpuIndexAllocation<CameraInstance>,
+        extent: vk::Extent2D,
+    ) -> crate::Result<()> {
+        let color_image = RenderTarget::create_color_image(&self.device, id, extent)?;
+        let sampler = self.query_sampler(&SamplerConfig::linear())?;
+        let texture_index = self.bindless_textures.insert(&color_image, &sampler)?;
+        let render_target = RenderTarget::new(
+            &self.device,
+            id,
+            camera_instance,
+            color_image,
+            texture_index,
+            &self.render_target_render_pass,
+        );
+        match render_target {
+            Ok(render_target) => {
+                self.render_targets.insert(id, Arc::new(render_target));
+                Ok(())
+            }
+            Err(err) => {
+                self.bindless_textures.remove(texture_index);
+                Err(err)
+            }
+        }
+    }
+
+    /// Removes the [`RenderTarget`] with the given `id`
+    ///
+    /// The slot of the bindless texture is only reused after the frames that might still sample it have finished.
+    pub fn remove_render_target(&mut self, id: RenderTargetId) -> Option<Arc<RenderTarget>> {
+        let render_target = self.render_targets.remove(&id)?;
+        self.bindless_textures.remove(render_target.texture_index());
+        Some(render_target)
+    }
+
+    /// Removes all [`RenderTarget`]s that render the scene from the `camera_instance`
+    pub fn remove_render_targets_of_camera_instance(&mut self, camera_instance: &GpuIndexAllocation<CameraInstance>) {
+        let ids = self
+            .render_targets
+            .values()
+            .filter(|render_target| render_target.camera_instance() == camera_instance)
+            .map(|render_target| render_target.id())
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.remove_render_target(id);
+        }
+    }
+
+    /// Returns the [`RenderTarget`] with the given `id`
+    pub fn render_target(&self, id: RenderTargetId) -> Option<&Arc<RenderTarget>> {
+        self.render_targets.get(&id)
+    }
+
+    /// Returns all [`RenderTarget`]s ordered by their id
+    pub fn render_targets(&self) -> impl Iterator<Item = &Arc<RenderTarget>> {
+        self.render_targets.values()
+    }
+
+    /// Returns the texture array that is part of the layout of every pipeline
+    pub fn bindless_textures(&self) -> &BindlessTextures {
+        &self.bindless_textures
+    }
+
+    /// Returns the texture array so that textures can be inserted and removed
+    pub fn bindless_textures_mut(&mut self) -> &mut BindlessTextures {
+        &mut self.bindless_textures
+    }
+
+    /// Returns the layout of the [`CachedDescriptorSet`](crate::cached_descriptor_set::CachedDescriptorSet)s that is part of the layout of every pipeline
+    pub fn cached_descriptor_set_layout(&self) -> &Arc<DescriptorSetLayout> {
+        &self.cached_descriptor_set_layout
+    }
+}
+
+/// Creates the images into which the swapchain images are copied so that the output transform can sample them
+///
+/// SDR swapchains are presented as they are rendered and don't need the images. The copy requires that the swapchain
+/// images can be used as transfer source.
+fn create_output_transform_images(device: &Arc<Device>, swapchain: &Swapchain) -> crate::Result<Option<SwapchainVec<Arc<DeviceImage>>>> {
+    if swapchain.color_space() == ColorSpace::Sdr {
+        return Ok(None);
+    }
+    if !swapchain.supports_capture() {
+        warn!(
+            "The swapchain images can't be copied. The output transform for {:?} is skipped.",
+            swapchain.color_space()
+        );
+        return Ok(None);
+    }
+    Ok(Some(create_swapchain_image_copies(device, swapchain, "OutputTransform")?))
+}
+
+/// Creates one image per swapchain image with the same extent and format into which the swapchain image can be copied
+fn create_swapchain_image_copies(device: &Arc<Device>, swapchain: &Swapchain, name: &str) -> crate::Result<SwapchainVec<Arc<DeviceImage>>> {
+    SwapchainVec::new(swapchain, |index| {
+        let device_image = DeviceImage::with_mip_level_count(
+            device,
+            swapchain.extent(),
+            swapchain.surface_format().format,
+            1,
+            debug_info!(format!("{name}-Image-{index}")),
+        )?;
+        Ok(Arc::new(device_image))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::{device::TestFixtureDevice, swapchain::Swapchain};
+    use crate::{device::TestFixtureDevice, retired_resources::FramesInFlight, swapchain::Swapchain};
 
     #[test]
     fn smoke() {
         let test_fixture_device = TestFixtureDevice::new().unwrap();
         let swapchain = Swapchain::new(&test_fixture_device.device, &test_fixture_device.surface, 3, None).unwrap();
         let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
-        let _vulkan_resource_coordinator =
-            VulkanResourceCoordinator::new(&test_fixture_device.device, &asset_importer, &swapchain, &RendererConfig::default()).unwrap();
+        let pipeline_cache = Arc::new(PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap());
+        let mut vulkan_resource_coordinator = VulkanResourceCoordinator::new(
+            &test_fixture_device.device,
+            &pipeline_cache,
+            &asset_importer,
+            &swapchain,
+            &RendererConfig::default(),
+        )
+        .unwrap();
+
+        // Samplers are cached by their config
+        let linear = vulkan_resource_coordinator.query_sampler(&SamplerConfig::linear()).unwrap();
+        let nearest = vulkan_resource_coordinator.query_sampler(&SamplerConfig::nearest()).unwrap();
+        assert!(Arc::ptr_eq(
+            &linear,
+            &vulkan_resource_coordinator.query_sampler(&SamplerConfig::linear()).unwrap()
+        ));
+        assert!(!Arc::ptr_eq(&linear, &nearest));
+    }
+
+    #[test]
+    fn failed_compute_pipeline_is_cached() {
+        let test_fixture_device = TestFixtureDevice::new().unwrap();
+        let swapchain = Swapchain::new(&test_fixture_device.device, &test_fixture_device.surface, 3, None).unwrap();
+        let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
+        let pipeline_cache = Arc::new(PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap());
+        let mut vulkan_resource_coordinator = VulkanResourceCoordinator::new(
+            &test_fixture_device.device,
+            &pipeline_cache,
+            &asset_importer,
+            &swapchain,
+            &RendererConfig::default(),
+        )
+        .unwrap();
+
+        let config = GenericComputePipelineConfig::new(AssetKey::new("shaders/missing.comp"));
+        assert!(matches!(
+            vulkan_resource_coordinator.query_compute_pipeline(&config),
+            Err(crate::Error::AssetNotFound { .. })
+        ));
+        assert!(matches!(
+            vulkan_resource_coordinator.query_compute_pipeline(&config),
+            Err(crate::Error::PipelineFailedBefore { .. })
+        ));
+    }
+
+    #[test]
+    fn render_target_texture_is_released_after_the_frames_in_flight() {
+        let test_fixture_device = TestFixtureDevice::new().unwrap();
+        let swapchain = Swapchain::new(&test_fixture_device.device, &test_fixture_device.surface, 3, None).unwrap();
+        let asset_importer = Arc::new(AssetImporter::default_from("../assets/processed").unwrap());
+        let pipeline_cache = Arc::new(PipelineCache::new(&test_fixture_device.device, &[], debug_info!("my_pipeline_cache")).unwrap());
+        let mut vulkan_resource_coordinator = VulkanResourceCoordinator::new(
+            &test_fixture_device.device,
+            &pipeline_cache,
+            &asset_importer,
+            &swapchain,
+            &RendererConfig::default(),
+        )
+        .unwrap();
+
+        let id = RenderTargetId(7);
+        let camera_instance = GpuIndexAllocation::new_unchecked(0);
+        let extent = vk::Extent2D { width: 32, height: 16 };
+        vulkan_resource_coordinator
+            .create_render_target(id, camera_instance, extent)
+            .unwrap();
+        let texture_index = vulkan_resource_coordinator.render_target(id).unwrap().texture_index();
+        assert_eq!(vulkan_resource_coordinator.render_target(id).unwrap().extent(), extent);
+        assert!(vulkan_resource_coordinator.bindless_textures().get(texture_index).is_some());
+
+        // Removing the camera instance removes the render target but the slot is still sampled by the frames in flight
+        let mut frames_in_flight = FramesInFlight::new();
+        frames_in_flight.start_frame(0, 10);
+        vulkan_resource_coordinator.remove_render_targets_of_camera_instance(&camera_instance);
+        assert!(vulkan_resource_coordinator.render_target(id).is_none());
+        assert!(vulkan_resource_coordinator.bindless_textures().get(texture_index).is_none());
+        vulkan_resource_coordinator.bindless_textures_mut().release(&frames_in_flight);
+        vulkan_resource_coordinator
+            .create_render_target(RenderTargetId(8), camera_instance, extent)
+            .unwrap();
+        assert_ne!(
+            vulkan_resource_coordinator
+                .render_target(RenderTargetId(8))
+                .unwrap()
+                .texture_index(),
+            texture_index
+        );
+
+        // The slot is reused after the frame has finished
+        frames_in_flight.complete_frame(0, 10);
+        vulkan_resource_coordinator.bindless_textures_mut().release(&frames_in_flight);
+        vulkan_resource_coordinator
+            .create_render_target(RenderTargetId(9), camera_instance, extent)
+            .unwrap();
+        assert_eq!(
+            vulkan_resource_coordinator
+                .render_target(RenderTargetId(9))
+                .unwrap()
+                .texture_index(),
+            texture_index
+        );
     }
 }
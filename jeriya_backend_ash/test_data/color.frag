@@ -3,6 +3,8 @@
 layout (constant_id = 0) const uint MAX_CAMERAS = 8;
 layout (constant_id = 1) const uint MAX_INANIMATE_MESH_INSTANCES = 1024;
 
+layout (location = 0) in vec4 inColor;
+
 layout (location = 0) out vec4 outputColor;
 
 layout (push_constant) uniform PushConstants {
@@ -11,5 +13,6 @@ layout (push_constant) uniform PushConstants {
 } push_constants;
 
 void main() {
-    outputColor = push_constants.color;
+    // The vertex colors are white when the primitive only uses the color of its config
+    outputColor = inColor * push_constants.color;
 }
\ No newline at end of file
@@ -0,0 +1,108 @@
+#version 450
+
+#extension GL_EXT_shader_explicit_arithmetic_types_int64 : require
+
+layout (constant_id = 0) const uint MAX_CAMERAS = 16;
+layout (constant_id = 1) const uint MAX_CAMERA_INSTANCES = 64;
+
+// Strength of the darkening at the depth discontinuities
+const float EDL_STRENGTH = 300.0;
+// Distance in pixels of the neighbors that are compared with the pixel
+const int EDL_RADIUS = 1;
+
+const ivec2 NEIGHBORS[8] = ivec2[](
+    ivec2(-1, -1), ivec2(0, -1), ivec2(1, -1),
+    ivec2(-1,  0),               ivec2(1,  0),
+    ivec2(-1,  1), ivec2(0,  1), ivec2(1,  1)
+);
+
+struct Camera {
+    mat4 projection_matrix;
+    float znear;
+    float zfar;
+    float padding[14];
+};
+
+struct CameraInstance {
+    uint64_t camera_index;
+    mat4 view_matrix;
+};
+
+// `MAX_CLIP_PLANES` in `clip_plane.rs`
+const uint MAX_CLIP_PLANES = 4;
+
+layout (set = 0, binding = 0) uniform PerFrameData { 
+    int active_camera_instance; // -1 means no active camera
+    uint mesh_attributes_count;
+    uint rigid_mesh_count;
+    uint rigid_mesh_instance_count;
+    uint point_cloud_instance_count;
+    uint framebuffer_width;
+    uint framebuffer_height;
+    uint maximum_rendered_points; // u32::MAX means that there is no limit
+    float time; // seconds since the backend has been created
+    uint clip_plane_count;
+    vec4 clip_planes[MAX_CLIP_PLANES]; // xyz is the normal and w the distance of the plane in world space
+} per_frame_data;
+
+layout (set = 2, binding = 1) buffer Cameras { 
+    Camera cameras[MAX_CAMERAS];
+};
+
+layout (set = 2, binding = 2) buffer CameraInstanceBuffer { 
+    CameraInstance camera_instances[MAX_CAMERA_INSTANCES];
+};
+
+layout (set = 0, binding = 29) uniform sampler2D depth_buffer;
+
+layout (location = 0) out vec4 output_color;
+
+/// Returns the value of the depth buffer at the given pixel. Pixels outside of the framebuffer are clamped to the border.
+float depth_at(ivec2 pixel) {
+    ivec2 framebuffer_size = ivec2(per_frame_data.framebuffer_width, per_frame_data.framebuffer_height);
+    return texelFetch(depth_buffer, clamp(pixel, ivec2(0), framebuffer_size - 1), 0).r;
+}
+
+/// Returns the logarithm of the view space distance for the value of the depth buffer
+float log_linear_depth(float depth, Camera camera) {
+    float linear_depth;
+    if (camera.projection_matrix[2][3] == 0.0) {
+        // Orthographic projection
+        linear_depth = camera.znear + depth * (camera.zfar - camera.znear);
+    } else {
+        // Perspective projection that maps the depth to [0, 1]
+        linear_depth = camera.znear * camera.zfar / (camera.zfar - depth * (camera.zfar - camera.znear));
+    }
+    return log2(max(linear_depth, 1e-6));
+}
+
+void main() {
+    if (per_frame_data.active_camera_instance < 0) {
+        output_color = vec4(1.0);
+        return;
+    }
+    CameraInstance camera_instance = camera_instances[per_frame_data.active_camera_instance];
+    Camera camera = cameras[uint(camera_instance.camera_index)];
+
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    float depth = depth_at(pixel);
+
+    // The background is not shaded
+    if (depth >= 1.0) {
+        output_color = vec4(1.0);
+        return;
+    }
+
+    // Sums how much the neighbors are in front of the pixel
+    float log_depth = log_linear_depth(depth, camera);
+    float response = 0.0;
+    for (int i = 0; i < 8; i++) {
+        float neighbor_depth = depth_at(pixel + NEIGHBORS[i] * EDL_RADIUS);
+        float neighbor_log_depth = neighbor_depth >= 1.0 ? log_depth : log_linear_depth(neighbor_depth, camera);
+        response += max(0.0, log_depth - neighbor_log_depth);
+    }
+    response /= 8.0;
+
+    float shade = exp(-response * EDL_STRENGTH);
+    output_color = vec4(vec3(shade), 1.0);
+}
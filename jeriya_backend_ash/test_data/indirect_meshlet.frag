@@ -6,11 +6,29 @@ layout (constant_id = 1) const uint MAX_INANIMATE_MESH_INSTANCES = 1024;
 layout (location = 0) out vec4 outputColor;
 
 layout (push_constant) uniform PushConstants {
-    uint _non_zero;
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
 } push_constants;
 
+// `DebugShadingMode` enum in `shader_interface.rs`
+const uint DEBUG_SHADING_MODE_NONE = 0;
+const uint DEBUG_SHADING_MODE_NORMALS = 1;
+const uint DEBUG_SHADING_MODE_DEPTH = 2;
+const uint DEBUG_SHADING_MODE_MESHLET_ID = 3;
+const uint DEBUG_SHADING_MODE_INSTANCE_ID = 4;
+const uint DEBUG_SHADING_MODE_OVERDRAW = 5;
+const uint DEBUG_SHADING_MODE_FILL_LEVEL = 6;
+const uint DEBUG_SHADING_MODE_CLUSTER_DEPTH = 7;
+
+// Color that is added per fragment in the overdraw mode
+const vec3 OVERDRAW_COLOR = vec3(0.05, 0.02, 0.01);
+
 layout (location = 0) in vec3 in_vertex_normal;
 layout (location = 1) flat in uint in_meshlet_index;
+layout (location = 2) flat in uint in_instance_index;
+layout (location = 3) flat in float in_meshlet_fill_level;
+layout (location = 4) flat in vec4 in_color;
 
 const vec3 COLORS[] = {
     vec3(0.9020, 0.9725, 0.0000),
@@ -72,9 +90,53 @@ uint esgtsa(uint s) {
     return s;
 }
 
+/// Same color scheme as `pseudo_random_color` in `jeriya_shared` but with `esgtsa` as the hash function
+vec3 pseudo_random_color(uint index) {
+    uint hash = esgtsa(index);
+
+    const uint RESOLUTION = 36;
+    float r = float(hash % RESOLUTION) / float(RESOLUTION);
+    float g = float(hash % (RESOLUTION / 3)) / float(RESOLUTION);
+    float b = float(hash % (RESOLUTION / 9)) / float(RESOLUTION);
+
+    const float BASE = 0.4;
+    return vec3(BASE) + vec3(r, g, b) * (1.0 - BASE);
+}
+
+/// Maps `t` in [0, 1] to a color ramp from blue over green to red
+vec3 heatmap_color(float t) {
+    t = clamp(t, 0.0, 1.0);
+    return clamp(vec3(1.5) - abs(4.0 * vec3(t) - vec3(3.0, 2.0, 1.0)), 0.0, 1.0);
+}
+
 void main() {
     vec3 normal_color = 0.5 * in_vertex_normal + vec3(0.5);
-    vec3 color = COLORS[esgtsa(in_meshlet_index) % 50];
-    vec3 final_color = mix(normal_color, color, 0.3);
-    outputColor = vec4(final_color, 1.0);
+    vec3 meshlet_color = COLORS[esgtsa(in_meshlet_index) % 50];
+    switch (push_constants.debug_shading_mode) {
+        case DEBUG_SHADING_MODE_NORMALS:
+            outputColor = vec4(normal_color, 1.0);
+            break;
+        case DEBUG_SHADING_MODE_DEPTH:
+            outputColor = vec4(vec3(gl_FragCoord.z), 1.0);
+            break;
+        case DEBUG_SHADING_MODE_MESHLET_ID:
+            outputColor = vec4(meshlet_color, 1.0);
+            break;
+        case DEBUG_SHADING_MODE_INSTANCE_ID:
+            outputColor = vec4(COLORS[esgtsa(in_instance_index) % 50], 1.0);
+            break;
+        case DEBUG_SHADING_MODE_OVERDRAW:
+            outputColor = vec4(OVERDRAW_COLOR, 1.0);
+            break;
+        case DEBUG_SHADING_MODE_FILL_LEVEL:
+            outputColor = vec4(heatmap_color(1.0 - in_meshlet_fill_level), 1.0);
+            break;
+        case DEBUG_SHADING_MODE_CLUSTER_DEPTH:
+            // Meshlets are not organized in a hierarchy
+            outputColor = vec4(pseudo_random_color(0), 1.0);
+            break;
+        default:
+            outputColor = vec4(mix(mix(normal_color, meshlet_color, 0.3), in_color.rgb, in_color.a), 1.0);
+            break;
+    }
 }
\ No newline at end of file
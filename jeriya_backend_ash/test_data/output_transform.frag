@@ -0,0 +1,55 @@
+#version 450
+
+// 0: SDR, 1: HDR10, 2: scRGB
+layout (constant_id = 19) const uint OUTPUT_COLOR_SPACE = 0;
+
+const uint OUTPUT_COLOR_SPACE_HDR10 = 1;
+const uint OUTPUT_COLOR_SPACE_SCRGB = 2;
+
+// Luminance in nits with which SDR white is displayed (ITU-R BT.2408 reference white)
+const float PAPER_WHITE_NITS = 203.0;
+// Luminance in nits that corresponds to 1.0 in scRGB
+const float SCRGB_WHITE_NITS = 80.0;
+// Luminance in nits that corresponds to 1.0 in the PQ encoding
+const float PQ_MAX_NITS = 10000.0;
+
+// Converts linear BT.709 to linear BT.2020 (column-major)
+const mat3 BT709_TO_BT2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+layout (set = 0, binding = 32) uniform sampler2D output_transform_input;
+
+layout (location = 0) out vec4 output_color;
+
+/// Decodes the sRGB transfer function with which the scene has been rendered
+vec3 srgb_to_linear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(high, low, lessThanEqual(color, vec3(0.04045)));
+}
+
+/// Encodes the luminance normalized to 10000 nits with the PQ (ST 2084) transfer function
+vec3 pq_encode(vec3 normalized_luminance) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    vec3 y = pow(clamp(normalized_luminance, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+void main() {
+    vec4 color = texelFetch(output_transform_input, ivec2(gl_FragCoord.xy), 0);
+    vec3 linear = srgb_to_linear(clamp(color.rgb, 0.0, 1.0));
+    if (OUTPUT_COLOR_SPACE == OUTPUT_COLOR_SPACE_HDR10) {
+        output_color = vec4(pq_encode(BT709_TO_BT2020 * linear * (PAPER_WHITE_NITS / PQ_MAX_NITS)), color.a);
+    } else if (OUTPUT_COLOR_SPACE == OUTPUT_COLOR_SPACE_SCRGB) {
+        output_color = vec4(linear * (PAPER_WHITE_NITS / SCRGB_WHITE_NITS), color.a);
+    } else {
+        output_color = color;
+    }
+}
@@ -0,0 +1,26 @@
+#version 450
+
+#extension GL_EXT_nonuniform_qualifier : require
+
+const uint NO_TEXTURE = 0xFFFFFFFF;
+
+layout (set = 1, binding = 0) uniform sampler2D textures[];
+
+layout (location = 0) in vec2 inTextureCoordinates;
+
+layout (location = 0) out vec4 outputColor;
+
+layout (push_constant) uniform PushConstants {
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
+    uint texture_index;
+} push_constants;
+
+void main() {
+    if (push_constants.texture_index == NO_TEXTURE) {
+        outputColor = push_constants.color;
+    } else {
+        outputColor = push_constants.color * texture(textures[nonuniformEXT(push_constants.texture_index)], inTextureCoordinates);
+    }
+}
@@ -24,6 +24,12 @@ const float TAU = 6.283184;
 const float PI = 3.141592;
 
 
+struct PipelineStatistics {
+    uint vertex_shader_invocations;
+    uint fragment_shader_invocations;
+    uint compute_shader_invocations;
+};
+
 struct FrameTelemetry {
     uint max_cameras;
     uint max_camera_instances;
@@ -51,6 +57,8 @@ struct FrameTelemetry {
     uint visible_point_cloud_instances;
     uint visible_point_cloud_instances_simple;
     uint visible_point_cloud_clusters;
+
+    PipelineStatistics pipeline_statistics;
 };
 
 struct Camera {
@@ -128,6 +136,7 @@ struct RigidMesh {
 struct RigidMeshInstance {
     uint64_t rigid_mesh_index;
     mat4 transform;
+    vec4 color; // rgb is blended over the shaded color with a as weight
 };
 
 struct PointCloud {
@@ -135,8 +144,14 @@ struct PointCloud {
     uint preferred_point_cloud_representation;
 };
 
+// `PointRenderMode` enum in `shader_interface.rs`
+const uint POINT_RENDER_MODE_TRIANGLES = 0;
+const uint POINT_RENDER_MODE_SPLATS = 1;
+
 struct PointCloudInstance {
     uint64_t point_cloud_index;
+    uint point_render_mode;
+    float point_radius;
     mat4 transform;
 };
 
@@ -161,6 +176,7 @@ struct PointCloudPage {
     uint clusters_len;
     vec4 point_positions[MAX_POINT_CLOUD_PAGE_POINTS];
     vec4 point_colors[MAX_POINT_CLOUD_PAGE_POINTS];
+    vec4 point_normals[MAX_POINT_CLOUD_PAGE_POINTS];
     PointCloudCluster clusters[MAX_POINT_CLOUD_PAGE_CLUSTERS];
 };
 
@@ -170,6 +186,9 @@ struct PointCloudClusterId {
     uint cluster_index;
 };
 
+// `MAX_CLIP_PLANES` in `clip_plane.rs`
+const uint MAX_CLIP_PLANES = 4;
+
 layout (set = 0, binding = 0) uniform PerFrameData { 
     int active_camera_instance; // -1 means no active camera
     uint mesh_attributes_count;
@@ -178,126 +197,130 @@ layout (set = 0, binding = 0) uniform PerFrameData {
     uint point_cloud_instance_count;
     uint framebuffer_width;
     uint framebuffer_height;
+    uint maximum_rendered_points; // u32::MAX means that there is no limit
+    float time; // seconds since the backend has been created
+    uint clip_plane_count;
+    vec4 clip_planes[MAX_CLIP_PLANES]; // xyz is the normal and w the distance of the plane in world space
 } per_frame_data;
 
-layout (set = 0, binding = 1) buffer Cameras { 
+layout (set = 2, binding = 1) buffer Cameras { 
     Camera cameras[MAX_CAMERAS];
 };
 
-layout (set = 0, binding = 2) buffer CameraInstanceBuffer { 
+layout (set = 2, binding = 2) buffer CameraInstanceBuffer { 
     CameraInstance camera_instances[MAX_CAMERA_INSTANCES];
 };
 
-layout (set = 0, binding = 3) buffer VisibleRigidMeshInstancesSimpleBuffer { 
+layout (set = 2, binding = 3) buffer VisibleRigidMeshInstancesSimpleBuffer { 
     uint count;
     VkDrawIndirectCommand indirect_draw_commands[MAX_RIGID_MESH_INSTANCES];
     uint rigid_mesh_instance_indices[MAX_RIGID_MESH_INSTANCES];
 } visible_rigid_mesh_instances_simple;
 
-layout (set = 0, binding = 5) buffer StaticVertexPositionBuffer {
+layout (set = 2, binding = 5) buffer StaticVertexPositionBuffer {
     vec4 vertex_positions[];
 };
 
-layout (set = 0, binding = 6) buffer StaticIndexBuffer {
+layout (set = 2, binding = 6) buffer StaticIndexBuffer {
     uint indices[];
 };
 
-layout (set = 0, binding = 7) buffer StaticVertexNormalsBuffer {
+layout (set = 2, binding = 7) buffer StaticVertexNormalsBuffer {
     vec4 vertex_normals[];
 };
 
-layout (set = 0, binding = 8) buffer MeshAttributesBuffer {
+layout (set = 2, binding = 8) buffer MeshAttributesBuffer {
     MeshAttributes mesh_attributes[MAX_MESH_ATTRIBUTES];
 };
 
-layout (set = 0, binding = 9) buffer RigidMeshes {
+layout (set = 2, binding = 9) buffer RigidMeshes {
     RigidMesh rigid_meshes[MAX_RIGID_MESHES];
 };
 
-layout (set = 0, binding = 10) buffer MeshAttributesActiveBuffer {
+layout (set = 2, binding = 10) buffer MeshAttributesActiveBuffer {
     bool mesh_attributes_active[MAX_MESH_ATTRIBUTES];
 };
 
-layout (set = 0, binding = 11) buffer RigidMeshInstancesBuffer {
+layout (set = 2, binding = 11) buffer RigidMeshInstancesBuffer {
     RigidMeshInstance rigid_mesh_instances[MAX_RIGID_MESH_INSTANCES];
 };
 
-layout (set = 0, binding = 12) buffer StaticMeshletBuffer {
+layout (set = 2, binding = 12) buffer StaticMeshletBuffer {
     Meshlet meshlets[MAX_MESHLETS];
 };
 
-layout (set = 0, binding = 13) buffer VisibleRigidMeshInstancesBuffer {
+layout (set = 2, binding = 13) buffer VisibleRigidMeshInstancesBuffer {
     VkDispatchIndirectCommand dispatch_indirect_command;
     uint count;
     uint instance_indices[MAX_VISIBLE_RIGID_MESH_INSTANCES];
 } visible_rigid_mesh_instances;
 
-layout (set = 0, binding = 14) buffer VisibleRigidMeshMeshletsBuffer {
+layout (set = 2, binding = 14) buffer VisibleRigidMeshMeshletsBuffer {
     uint count;
     VkDrawIndirectCommand indirect_draw_commands[MAX_VISIBLE_RIGID_MESH_MESHLETS];
     uint meshlet_indices[MAX_VISIBLE_RIGID_MESH_MESHLETS];
     uint rigid_mesh_instance_indices[MAX_VISIBLE_RIGID_MESH_MESHLETS];
 } visible_rigid_mesh_meshlets;
 
-layout (set = 0, binding = 15) buffer PointCloudAttributesActiveBuffer {
+layout (set = 2, binding = 15) buffer PointCloudAttributesActiveBuffer {
     bool point_cloud_attributes_active[MAX_POINT_CLOUD_ATTRIBUTES];
 };
 
-layout (set = 0, binding = 16) buffer PointCloudBuffer {
+layout (set = 2, binding = 16) buffer PointCloudBuffer {
     PointCloud point_clouds[MAX_POINT_CLOUDS];
 };
 
-layout (set = 0, binding = 17) buffer PointCloudInstanceBuffer {
+layout (set = 2, binding = 17) buffer PointCloudInstanceBuffer {
     PointCloudInstance point_cloud_instances[MAX_POINT_CLOUD_INSTANCES];
 };
 
-layout (set = 0, binding = 18) buffer VisiblePointCloudInstanceSimpleBuffer {
+layout (set = 2, binding = 18) buffer VisiblePointCloudInstanceSimpleBuffer {
     uint count;
     VkDrawIndirectCommand indirect_draw_commands[MAX_POINT_CLOUD_INSTANCES];
     uint instance_indices[MAX_POINT_CLOUD_INSTANCES];
 } visible_point_cloud_instances_simple;
 
-layout (set = 0, binding = 19) buffer PointCloudAttributesBuffer {
+layout (set = 2, binding = 19) buffer PointCloudAttributesBuffer {
     PointCloudAttributes point_cloud_attributes[MAX_POINT_CLOUD_ATTRIBUTES];
 };
 
-layout (set = 0, binding = 20) buffer StaticPointPositionBuffer {
+layout (set = 2, binding = 20) buffer StaticPointPositionBuffer {
     vec4 point_positions[];
 };
 
-layout (set = 0, binding = 21) buffer StaticPointColorBuffer {
+layout (set = 2, binding = 21) buffer StaticPointColorBuffer {
     vec4 point_colors[];
 };
 
-layout (set = 0, binding = 22) buffer PointCloudPagesBuffer {
+layout (set = 2, binding = 22) buffer PointCloudPagesBuffer {
     PointCloudPage point_cloud_pages[MAX_POINT_CLOUD_PAGES];
 };
 
-layout (set = 0, binding = 23) buffer PointCloudPagesActiveBuffer {
+layout (set = 2, binding = 23) buffer PointCloudPagesActiveBuffer {
     bool point_cloud_pages_active[MAX_POINT_CLOUD_PAGES];
 };
 
-layout (set = 0, binding = 24) buffer StaticPointCloudPagesBuffer {
+layout (set = 2, binding = 24) buffer StaticPointCloudPagesBuffer {
     PointCloudPage static_point_cloud_pages[MAX_POINT_CLOUD_PAGES];
 };
 
-layout (set = 0, binding = 25) buffer VisiblePointCloudInstancesBuffer {
+layout (set = 2, binding = 25) buffer VisiblePointCloudInstancesBuffer {
     VkDispatchIndirectCommand dispatch_indirect_command;
     uint count;
     uint instance_indices[MAX_POINT_CLOUD_INSTANCES];
 } visible_point_cloud_instances;
 
-layout (set = 0, binding = 26) buffer VisiblePointCloudClustersBuffer {
+layout (set = 2, binding = 26) buffer VisiblePointCloudClustersBuffer {
     uint count;
     VkDrawIndirectCommand draw_indirect_commands[MAX_VISIBLE_POINT_CLOUD_CLUSTERS];
     PointCloudClusterId cluster_ids[MAX_VISIBLE_POINT_CLOUD_CLUSTERS];
 } visible_point_cloud_clusters;
 
-layout (set = 0, binding = 27) buffer FrameTelemetryBuffer {
+layout (set = 2, binding = 27) buffer FrameTelemetryBuffer {
     FrameTelemetry frame_telemetry;
 };
 
-layout (set = 0, binding = 28) buffer DeviceLocalDebugLineBuffer {
+layout (set = 2, binding = 28) buffer DeviceLocalDebugLineBuffer {
     uint count; // this is the requested number of lines which might be higher than the actually draw number
     VkDrawIndirectCommand draw_indirect_command;
     float lines[MAX_DEVICE_LOCAL_DEBUG_LINES_COMPONENT_COUNT];
@@ -407,6 +430,16 @@ layout (push_constant) uniform PushConstants {
 
 layout (location = 0) out vec4 out_point_color;
 layout (location = 1) out vec2 out_texcoord;
+layout (location = 2) flat out uint out_instance_index;
+
+out float gl_ClipDistance[MAX_CLIP_PLANES];
+
+/// Writes the signed distances of the vertex to the clip planes. Vertices with a negative distance are clipped.
+void write_clip_distances(vec3 world_position) {
+    for (uint i = 0; i < MAX_CLIP_PLANES; i++) {
+        gl_ClipDistance[i] = i < per_frame_data.clip_plane_count ? dot(per_frame_data.clip_planes[i], vec4(world_position, 1.0)) : 1.0;
+    }
+}
 
 void main() {
     uint point_cloud_instance_index = visible_point_cloud_instances_simple.instance_indices[gl_DrawIDARB];
@@ -441,8 +474,18 @@ void main() {
 
     vec4 view_position = view_matrix * model_matrix * vec4(point_position, 1.0);
 
+    // The points are clipped as a whole
+    write_clip_distances((model_matrix * vec4(point_position, 1.0)).xyz);
+
     out_point_color = point_color;
     out_texcoord = factor;
-    gl_Position = projection_matrix * view_position + vec4(triangle_size * factor, 0.0, 0.0);
+    out_instance_index = point_cloud_instance_index;
+    if (point_cloud_instance.point_render_mode == POINT_RENDER_MODE_SPLATS) {
+        // The points don't have normals which is why the disc faces the camera
+        vec2 offset = point_cloud_instance.point_radius / extent_down * factor;
+        gl_Position = projection_matrix * (view_position + vec4(offset, 0.0, 0.0));
+    } else {
+        gl_Position = projection_matrix * view_position + vec4(triangle_size * factor, 0.0, 0.0);
+    }
 
 }
\ No newline at end of file
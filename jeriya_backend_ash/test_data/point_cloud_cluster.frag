@@ -1,9 +1,35 @@
 #version 450
 
+layout (push_constant) uniform PushConstants {
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
+} push_constants;
+
+// `DebugShadingMode` enum in `shader_interface.rs`
+const uint DEBUG_SHADING_MODE_NONE = 0;
+const uint DEBUG_SHADING_MODE_NORMALS = 1;
+const uint DEBUG_SHADING_MODE_DEPTH = 2;
+const uint DEBUG_SHADING_MODE_MESHLET_ID = 3;
+const uint DEBUG_SHADING_MODE_INSTANCE_ID = 4;
+const uint DEBUG_SHADING_MODE_OVERDRAW = 5;
+const uint DEBUG_SHADING_MODE_FILL_LEVEL = 6;
+const uint DEBUG_SHADING_MODE_CLUSTER_DEPTH = 7;
+
+// Color that is added per fragment in the overdraw mode
+const vec3 OVERDRAW_COLOR = vec3(0.05, 0.02, 0.01);
+
+// Directional light for the basic lighting of the points
+const vec3 LIGHT_DIRECTION = vec3(0.3, 0.9, 0.3);
+const float AMBIENT_INTENSITY = 0.3;
 
 layout (location = 0) flat in uint in_cluster_index;
 layout (location = 1) in vec4 in_point_color;
 layout (location = 2) in vec2 in_texcoord;
+layout (location = 3) flat in uint in_instance_index;
+layout (location = 4) flat in float in_cluster_fill_level;
+layout (location = 5) flat in uint in_cluster_depth;
+layout (location = 6) in vec3 in_point_normal;
 
 layout (location = 0) out vec4 output_color;
 
@@ -67,14 +93,61 @@ uint esgtsa(uint s) {
     return s;
 }
 
-void main() {
-    vec3 color = COLORS[esgtsa(in_cluster_index) % 50];
+/// Same color scheme as `pseudo_random_color` in `jeriya_shared` but with `esgtsa` as the hash function
+vec3 pseudo_random_color(uint index) {
+    uint hash = esgtsa(index);
+
+    const uint RESOLUTION = 36;
+    float r = float(hash % RESOLUTION) / float(RESOLUTION);
+    float g = float(hash % (RESOLUTION / 3)) / float(RESOLUTION);
+    float b = float(hash % (RESOLUTION / 9)) / float(RESOLUTION);
+
+    const float BASE = 0.4;
+    return vec3(BASE) + vec3(r, g, b) * (1.0 - BASE);
+}
+
+/// Maps `t` in [0, 1] to a color ramp from blue over green to red
+vec3 heatmap_color(float t) {
+    t = clamp(t, 0.0, 1.0);
+    return clamp(vec3(1.5) - abs(4.0 * vec3(t) - vec3(3.0, 2.0, 1.0)), 0.0, 1.0);
+}
 
+void main() {
     const float extent_down = 0.288675;
     if (length(in_texcoord) > extent_down) {
         discard;
         return;
     }
 
-    output_color = in_point_color;
+    vec3 normal = normalize(in_point_normal);
+
+    switch (push_constants.debug_shading_mode) {
+        case DEBUG_SHADING_MODE_NORMALS:
+            output_color = vec4(0.5 * normal + vec3(0.5), 1.0);
+            break;
+        case DEBUG_SHADING_MODE_DEPTH:
+            output_color = vec4(vec3(gl_FragCoord.z), 1.0);
+            break;
+        case DEBUG_SHADING_MODE_MESHLET_ID:
+            output_color = vec4(COLORS[esgtsa(in_cluster_index) % 50], 1.0);
+            break;
+        case DEBUG_SHADING_MODE_INSTANCE_ID:
+            output_color = vec4(COLORS[esgtsa(in_instance_index) % 50], 1.0);
+            break;
+        case DEBUG_SHADING_MODE_OVERDRAW:
+            output_color = vec4(OVERDRAW_COLOR, 1.0);
+            break;
+        case DEBUG_SHADING_MODE_FILL_LEVEL:
+            output_color = vec4(heatmap_color(1.0 - in_cluster_fill_level), 1.0);
+            break;
+        case DEBUG_SHADING_MODE_CLUSTER_DEPTH:
+            output_color = vec4(pseudo_random_color(in_cluster_depth), 1.0);
+            break;
+        default:
+            // The orientation of estimated normals is ambiguous which is why both sides are lit
+            float diffuse_intensity = abs(dot(normal, normalize(LIGHT_DIRECTION)));
+            float intensity = AMBIENT_INTENSITY + (1.0 - AMBIENT_INTENSITY) * diffuse_intensity;
+            output_color = vec4(intensity * in_point_color.rgb, in_point_color.a);
+            break;
+    }
 }
@@ -0,0 +1,6 @@
+#version 450
+
+// The selection mask only writes the stencil buffer. The pipeline has an empty color write mask.
+
+void main() {
+}
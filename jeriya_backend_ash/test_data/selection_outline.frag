@@ -0,0 +1,63 @@
+#version 450
+
+// Width of the outline in pixels
+const int OUTLINE_WIDTH = 2;
+
+// Stencil value that the selection mask writes for the pixels of the selected instances
+const uint SELECTED = 1;
+
+// `MAX_CLIP_PLANES` in `clip_plane.rs`
+const uint MAX_CLIP_PLANES = 4;
+
+layout (set = 0, binding = 0) uniform PerFrameData { 
+    int active_camera_instance; // -1 means no active camera
+    uint mesh_attributes_count;
+    uint rigid_mesh_count;
+    uint rigid_mesh_instance_count;
+    uint point_cloud_instance_count;
+    uint framebuffer_width;
+    uint framebuffer_height;
+    uint maximum_rendered_points; // u32::MAX means that there is no limit
+    float time; // seconds since the backend has been created
+    uint clip_plane_count;
+    vec4 clip_planes[MAX_CLIP_PLANES]; // xyz is the normal and w the distance of the plane in world space
+} per_frame_data;
+
+layout (set = 0, binding = 34) uniform usampler2D selection_stencil;
+
+layout (push_constant) uniform PushConstants {
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
+    uint texture_index;
+} push_constants;
+
+layout (location = 0) out vec4 output_color;
+
+/// Returns whether the pixel belongs to a selected instance. Pixels outside of the framebuffer are not selected.
+bool is_selected(ivec2 pixel) {
+    ivec2 framebuffer_size = ivec2(per_frame_data.framebuffer_width, per_frame_data.framebuffer_height);
+    if (any(lessThan(pixel, ivec2(0))) || any(greaterThanEqual(pixel, framebuffer_size))) {
+        return false;
+    }
+    return texelFetch(selection_stencil, pixel, 0).r == SELECTED;
+}
+
+void main() {
+    // The selected instances themselves are not covered
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    if (is_selected(pixel)) {
+        discard;
+    }
+
+    // Dilates the mask by searching the neighborhood for a selected pixel
+    for (int y = -OUTLINE_WIDTH; y <= OUTLINE_WIDTH; y++) {
+        for (int x = -OUTLINE_WIDTH; x <= OUTLINE_WIDTH; x++) {
+            if (x * x + y * y <= OUTLINE_WIDTH * OUTLINE_WIDTH && is_selected(pixel + ivec2(x, y))) {
+                output_color = push_constants.color;
+                return;
+            }
+        }
+    }
+    discard;
+}
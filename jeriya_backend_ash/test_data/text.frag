@@ -0,0 +1,27 @@
+#version 450
+
+#extension GL_EXT_nonuniform_qualifier : require
+
+layout (set = 1, binding = 0) uniform sampler2D textures[];
+
+layout (location = 0) in vec2 inTextureCoordinates;
+
+layout (location = 0) out vec4 outputColor;
+
+layout (push_constant) uniform PushConstants {
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
+    uint texture_index;
+} push_constants;
+
+void main() {
+    // The atlas contains signed distance fields with the outline of the glyphs at 0.5
+    float distance = texture(textures[nonuniformEXT(push_constants.texture_index)], inTextureCoordinates).r;
+    float smoothing = max(fwidth(distance), 0.0001);
+    float alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance) * push_constants.color.a;
+    if (alpha < 0.01) {
+        discard;
+    }
+    outputColor = vec4(push_constants.color.rgb, alpha);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+#extension GL_EXT_nonuniform_qualifier : require
+
+layout (set = 1, binding = 0) uniform sampler2D textures[];
+
+layout (location = 0) in vec2 inTextureCoordinates;
+layout (location = 1) in vec4 inColor;
+
+layout (location = 0) out vec4 outputColor;
+
+layout (push_constant) uniform PushConstants {
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
+    uint texture_index;
+} push_constants;
+
+void main() {
+    // Vertex colors and texels have premultiplied alpha
+    outputColor = inColor * texture(textures[nonuniformEXT(push_constants.texture_index)], inTextureCoordinates);
+}
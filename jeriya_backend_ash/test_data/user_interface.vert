@@ -0,0 +1,22 @@
+#version 450
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec2 inTextureCoordinates;
+layout (location = 2) in vec4 inColor;
+
+layout (location = 0) out vec2 outTextureCoordinates;
+layout (location = 1) out vec4 outColor;
+
+layout (push_constant) uniform PushConstants {
+    vec4 color;
+    mat4 matrix;
+    uint debug_shading_mode;
+    uint texture_index;
+} push_constants;
+
+void main() {
+    // The matrix transforms pixels from the upper left corner of the framebuffer into clip space
+    gl_Position = push_constants.matrix * vec4(inPosition, 1.0);
+    outTextureCoordinates = inTextureCoordinates;
+    outColor = inColor;
+}
@@ -0,0 +1,295 @@
+//! # Overview
+//!
+//! Adapter that uses Jeriya as the rendering backend of a game loop that is
+//! based on `bevy_ecs`.
+//!
+//! Entities that have a [`JeriyaRigidMesh`] component are mirrored as
+//! [`RigidMeshInstance`]s in an [`InstanceGroup`]. Once per frame, the game
+//! loop calls [`EcsAdapter::sync`] which inserts, updates and removes the
+//! [`RigidMeshInstance`]s according to the state of the `World` and records
+//! the changes in a `Transaction` that is then passed to the renderer.
+//!
+//! The transform of an entity is taken from its `GlobalTransform` so that
+//! hierarchies that are propagated by `bevy_transform` are respected. When an
+//! entity has no `GlobalTransform`, its `Transform` is used instead.
+
+use std::collections::HashMap;
+
+use bevy_ecs::{component::Component, entity::Entity, world::World};
+use bevy_transform::components::{GlobalTransform, Transform};
+use jeriya_backend::{
+    elements::{rigid_mesh::RigidMesh, rigid_mesh_group::RigidMeshGroup},
+    instances::{
+        instance_group::InstanceGroup,
+        rigid_mesh_instance::{self, RigidMeshInstance},
+    },
+    transactions::PushEvent,
+};
+use jeriya_shared::{debug_info, nalgebra::Matrix4, thiserror, Handle};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("The RigidMesh of the entity {0:?} doesn't exist")]
+    UnknownRigidMesh(Entity),
+    #[error("Failed to insert the RigidMeshInstance: {0}")]
+    RigidMeshInstance(#[from] rigid_mesh_instance::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Component that displays the [`RigidMesh`] at the transform of the entity
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JeriyaRigidMesh(pub Handle<RigidMesh>);
+
+/// [`RigidMeshInstance`] that mirrors an entity
+#[derive(Debug, Clone, PartialEq)]
+struct SyncedInstance {
+    rigid_mesh: Handle<RigidMesh>,
+    rigid_mesh_instance: Handle<RigidMeshInstance>,
+    transform: Matrix4<f32>,
+}
+
+/// Mirrors the entities of a `World` into an [`InstanceGroup`]
+#[derive(Debug, Default)]
+pub struct EcsAdapter {
+    synced_instances: HashMap<Entity, SyncedInstance>,
+}
+
+impl EcsAdapter {
+    /// Creates a new [`EcsAdapter`] that hasn't synced any entities yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the [`RigidMeshInstance`] that mirrors the given entity
+    pub fn rigid_mesh_instance(&self, entity: Entity) -> Option<&Handle<RigidMeshInstance>> {
+        self.synced_instances
+            .get(&entity)
+            .map(|synced_instance| &synced_instance.rigid_mesh_instance)
+    }
+
+    /// Returns the number of entities that are mirrored
+    pub fn len(&self) -> usize {
+        self.synced_instances.len()
+    }
+
+    /// Returns `true` if no entities are mirrored
+    pub fn is_empty(&self) -> bool {
+        self.synced_instances.is_empty()
+    }
+
+    /// Pushes the changes of the entities since the last call into the given transaction.
+    ///
+    /// * Entities that got a [`JeriyaRigidMesh`] component get a new [`RigidMeshInstance`].
+    /// * Entities whose transform changed update the transform of their [`RigidMeshInstance`].
+    /// * Entities whose [`JeriyaRigidMesh`] changed get their [`RigidMeshInstance`] replaced.
+    /// * Entities that were despawned or lost their [`JeriyaRigidMesh`] component have their [`RigidMeshInstance`] removed.
+    pub fn sync(
+        &mut self,
+        world: &mut World,
+        rigid_mesh_group: &RigidMeshGroup,
+        instance_group: &mut InstanceGroup,
+        transaction: &mut impl PushEvent,
+    ) -> Result<()> {
+        let mut query = world.query::<(Entity, &JeriyaRigidMesh, Option<&GlobalTransform>, Option<&Transform>)>();
+        let entities = query
+            .iter(world)
+            .map(|(entity, jeriya_rigid_mesh, global_transform, transform)| {
+                let matrix = global_transform
+                    .map(GlobalTransform::compute_matrix)
+                    .or_else(|| transform.map(Transform::compute_matrix))
+                    .map(|matrix| Matrix4::from_column_slice(&matrix.to_cols_array()))
+                    .unwrap_or_else(Matrix4::identity);
+                (entity, jeriya_rigid_mesh.0, matrix)
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Remove the instances of entities that are gone or whose RigidMesh changed
+        self.synced_instances.retain(|entity, synced_instance| {
+            let keep = entities
+                .get(entity)
+                .is_some_and(|(rigid_mesh, _)| rigid_mesh == &synced_instance.rigid_mesh);
+            if !keep {
+                instance_group
+                    .rigid_mesh_instances()
+                    .mutate_via(transaction)
+                    .remove(&synced_instance.rigid_mesh_instance);
+            }
+            keep
+        });
+
+        for (entity, (rigid_mesh_handle, transform)) in entities {
+            match self.synced_instances.get_mut(&entity) {
+                Some(synced_instance) => {
+                    if synced_instance.transform != transform {
+                        synced_instance.transform = transform;
+                        instance_group
+                            .rigid_mesh_instances()
+                            .get_mut(&synced_instance.rigid_mesh_instance)
+                            .expect("synced RigidMeshInstance was removed from the InstanceGroup")
+                            .mutate_via(transaction)
+                            .set_transform(transform);
+                    }
+                }
+                None => {
+                    let rigid_mesh = rigid_mesh_group.get(&rigid_mesh_handle).ok_or(Error::UnknownRigidMesh(entity))?;
+                    let rigid_mesh_instance = instance_group.rigid_mesh_instances().mutate_via(transaction).insert_with(
+                        RigidMeshInstance::builder()
+                            .with_rigid_mesh(rigid_mesh)
+                            .with_transform(transform)
+                            .with_debug_info(debug_info!(format!("{entity:?}"))),
+                    )?;
+                    self.synced_instances.insert(
+                        entity,
+                        SyncedInstance {
+                            rigid_mesh: rigid_mesh_handle,
+                            rigid_mesh_instance,
+                            transform,
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use jeriya_backend::{
+        elements::{self, element_group::ElementGroup},
+        instances::{self, rigid_mesh_instance},
+        resources::{self, mesh_attributes::MeshAttributes, resource_group::ResourceGroup},
+        transactions::{self, Transaction},
+    };
+    use jeriya_shared::nalgebra::Vector3;
+
+    use super::*;
+
+    struct Setup {
+        // The groups only hold weak references to the renderers
+        _resources_renderer: Arc<resources::MockRenderer>,
+        _elements_renderer: Arc<elements::MockRenderer>,
+        _instances_renderer: Arc<instances::MockRenderer>,
+        world: World,
+        element_group: ElementGroup,
+        instance_group: InstanceGroup,
+        rigid_mesh: Handle<RigidMesh>,
+    }
+
+    fn setup() -> Setup {
+        let resources_renderer = resources::MockRenderer::new();
+        let mut resource_group = ResourceGroup::new(&resources_renderer, debug_info!("my_resource_group"));
+        let mesh_attributes = resource_group
+            .mesh_attributes()
+            .insert_with(
+                MeshAttributes::builder()
+                    .with_vertex_positions(vec![Vector3::new(0.0, 0.0, 0.0)])
+                    .with_vertex_normals(vec![Vector3::new(0.0, 1.0, 0.0)])
+                    .with_indices(vec![0]),
+            )
+            .unwrap();
+
+        let mut transaction = Transaction::new();
+        let elements_renderer = elements::MockRenderer::new();
+        let mut element_group = ElementGroup::new(&elements_renderer, debug_info!("my_element_group"));
+        let rigid_mesh = element_group
+            .rigid_meshes()
+            .mutate_via(&mut transaction)
+            .insert_with(RigidMesh::builder().with_mesh_attributes(mesh_attributes))
+            .unwrap();
+        transaction.process();
+
+        let instances_renderer = instances::MockRenderer::new();
+        Setup {
+            instance_group: InstanceGroup::new(&instances_renderer, debug_info!("my_instance_group")),
+            _resources_renderer: resources_renderer,
+            _elements_renderer: elements_renderer,
+            _instances_renderer: instances_renderer,
+            world: World::new(),
+            element_group,
+            rigid_mesh,
+        }
+    }
+
+    fn sync(adapter: &mut EcsAdapter, setup: &mut Setup) -> Vec<transactions::Event> {
+        let mut transaction = Transaction::new();
+        adapter
+            .sync(
+                &mut setup.world,
+                setup.element_group.rigid_meshes(),
+                &mut setup.instance_group,
+                &mut transaction,
+            )
+            .unwrap();
+        transaction.process()
+    }
+
+    #[test]
+    fn insert_update_remove() {
+        let mut setup = setup();
+        let mut adapter = EcsAdapter::new();
+        let entity = setup
+            .world
+            .spawn((JeriyaRigidMesh(setup.rigid_mesh), Transform::from_xyz(1.0, 2.0, 3.0)))
+            .id();
+
+        let events = sync(&mut adapter, &mut setup);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::Insert(instance))
+                if instance.transform() == &Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0))
+        ));
+        assert!(adapter.rigid_mesh_instance(entity).is_some());
+
+        // Nothing changed
+        assert!(sync(&mut adapter, &mut setup).is_empty());
+
+        setup.world.entity_mut(entity).insert(Transform::from_xyz(4.0, 5.0, 6.0));
+        let events = sync(&mut adapter, &mut setup);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::UpdateTransform(_, transform))
+                if transform == &Matrix4::new_translation(&Vector3::new(4.0, 5.0, 6.0))
+        ));
+
+        setup.world.despawn(entity);
+        let events = sync(&mut adapter, &mut setup);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            transactions::Event::RigidMeshInstance(rigid_mesh_instance::Event::Remove(_))
+        ));
+        assert!(adapter.is_empty());
+    }
+
+    #[test]
+    fn global_transform_preferred() {
+        let mut setup = setup();
+        let mut adapter = EcsAdapter::new();
+        let entity = setup
+            .world
+            .spawn((
+                JeriyaRigidMesh(setup.rigid_mesh),
+                Transform::from_xyz(1.0, 0.0, 0.0),
+                GlobalTransform::from_xyz(2.0, 0.0, 0.0),
+            ))
+            .id();
+        sync(&mut adapter, &mut setup);
+        let rigid_mesh_instance = adapter.rigid_mesh_instance(entity).unwrap();
+        assert_eq!(
+            setup
+                .instance_group
+                .rigid_mesh_instances()
+                .get(rigid_mesh_instance)
+                .unwrap()
+                .transform(),
+            &Matrix4::new_translation(&Vector3::new(2.0, 0.0, 0.0))
+        );
+    }
+}
@@ -1,31 +1,70 @@
 use crate::{
     common::{extract_extension_from_path, AssetKey},
+    font::{import_font, FontAsset},
+    manifest::{Manifest, ManifestEntry},
+    model::{import_model, ModelAsset},
+    point_cloud::clustered_point_cloud::{ClusteredPointCloudAsset, Page, PageStreamingInfo},
     read_asset::{FileSystem, FileSystemEvent, ImportSource},
+    scene::{import_scene, SceneAsset},
     shader::{import_shader, ShaderAsset},
+    texture::{import_texture, TextureAsset},
     Error, Result,
 };
 use jeriya_shared::{
     ahash::HashSet,
     bus::{Bus, BusReader},
     derive_where::derive_where,
-    log::{error, info, trace},
+    log::{error, info, trace, warn},
     parking_lot::{Mutex, RwLock},
     rayon::{ThreadPool, ThreadPoolBuilder},
 };
 use std::{
     any::{Any, TypeId},
     collections::BTreeMap,
+    future::Future,
     marker::PhantomData,
     path::Path,
-    sync::Arc,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Waker},
 };
 
 pub type Importer<T> = dyn Fn(&[u8]) -> Result<T> + Send + Sync;
 
+/// Counter that is used to determine the order in which the assets were accessed.
+static ACCESS_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 pub struct RawAsset {
     asset_key: AssetKey,
     _ty: TypeId,
     value: Mutex<Option<Arc<dyn Any + Send + Sync>>>,
+    /// Size of the imported data in bytes that is accounted for in the memory budget.
+    size: usize,
+    /// Value of the [`ACCESS_COUNTER`] when the asset was accessed the last time.
+    last_access: AtomicU64,
+}
+
+impl RawAsset {
+    fn new<T>(asset_key: AssetKey, value: T, size: usize) -> Self
+    where
+        T: 'static + Send + Sync,
+    {
+        Self {
+            asset_key,
+            _ty: TypeId::of::<T>(),
+            value: Mutex::new(Some(Arc::new(value))),
+            size,
+            last_access: AtomicU64::new(ACCESS_COUNTER.fetch_add(1, Ordering::Relaxed)),
+        }
+    }
+
+    /// Returns whether the data of the asset is loaded.
+    fn is_loaded(&self) -> bool {
+        self.value.lock().is_some()
+    }
 }
 
 #[derive_where(Clone)]
@@ -50,7 +89,13 @@ where
     }
 
     /// Returns the actual value of the `Asset<T>`.
+    ///
+    /// Accessing the value marks the asset as recently used so that it is evicted last when the memory budget of the
+    /// [`AssetImporter`] is exceeded. `None` is returned when the data was dropped.
     pub fn value(&self) -> Option<Arc<T>> {
+        self.raw_asset
+            .last_access
+            .store(ACCESS_COUNTER.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
         self.raw_asset
             .value
             .lock()
@@ -61,12 +106,62 @@ where
 
 type ImportFn = dyn for<'a> Fn(&AssetKey) + Send + Sync;
 
+/// Function that receives the `Arc<Result<Asset<T>>>` of an import as `&dyn Any` to complete a call to [`AssetImporter::import_async`].
+type CompleteFn = dyn FnOnce(&dyn Any) + Send;
+
+/// State that is shared between an [`ImportFuture`] and the import that completes it.
+struct ImportFutureState<T> {
+    result: Option<Result<Arc<Asset<T>>>>,
+    waker: Option<Waker>,
+}
+
+impl<T> ImportFutureState<T> {
+    fn complete(&mut self, result: Result<Arc<Asset<T>>>) {
+        self.result = Some(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Future that resolves when the import that was started by [`AssetImporter::import_async`] is done.
+struct ImportFuture<T> {
+    state: Arc<Mutex<ImportFutureState<T>>>,
+}
+
+impl<T> Future for ImportFuture<T> {
+    type Output = Result<Arc<Asset<T>>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Result of a request for a single `Page` of a clustered point cloud via [`AssetImporter::request_point_cloud_page`]
+pub struct PointCloudPageImport {
+    /// Asset of the clustered point cloud from which the `Page` was requested
+    pub asset_key: AssetKey,
+    /// Index of the `Page` in the clustered point cloud
+    pub page_index: usize,
+    pub page: Result<Page>,
+}
+
 pub struct AssetImporter {
     thread_pool: Arc<ThreadPool>,
 
     /// Maps the file extension to the importer function.
     importers: Arc<Mutex<BTreeMap<String, Arc<ImportFn>>>>,
 
+    /// Maps the file extension to the type of the asset that the importer returns.
+    asset_types: Arc<Mutex<BTreeMap<String, TypeId>>>,
+
     /// Maps the type id to the channel that is used to send the result of the import. Any
     /// is used because the type of the channel depends on the type of the asset.
     asset_buses: Arc<Mutex<BTreeMap<TypeId, Box<dyn Any + Sync + Send>>>>,
@@ -74,8 +169,21 @@ pub struct AssetImporter {
     /// The bus that is used to send notifications when an asset was imported.
     notification_buses: Arc<Mutex<Bus<()>>>,
 
+    /// The bus that is used to send the `Page`s that were requested with [`AssetImporter::request_point_cloud_page`].
+    point_cloud_page_bus: Arc<Mutex<Bus<Arc<PointCloudPageImport>>>>,
+
+    /// Maps the assets that are awaited via [`AssetImporter::import_async`] to the functions that complete the futures.
+    pending_imports: Arc<Mutex<BTreeMap<AssetKey, Vec<Box<CompleteFn>>>>>,
+
     importing_assets: Arc<RwLock<HashSet<AssetKey>>>,
     tracked_assets: Arc<RwLock<BTreeMap<AssetKey, Arc<RawAsset>>>>,
+
+    /// Maximum number of bytes of imported data that are kept in the tracked assets.
+    memory_budget: Arc<AtomicUsize>,
+
+    /// Maps the pinned assets to the number of times they were pinned. Pinned assets are never evicted.
+    pinned_assets: Arc<RwLock<BTreeMap<AssetKey, usize>>>,
+
     import_source: Arc<RwLock<dyn ImportSource>>,
 }
 
@@ -133,11 +241,16 @@ impl AssetImporter {
         Ok(Self {
             thread_pool,
             importers,
+            asset_types: Arc::new(Mutex::new(BTreeMap::new())),
             importing_assets,
             tracked_assets: Arc::new(RwLock::new(BTreeMap::new())),
             import_source,
             asset_buses: Arc::new(Mutex::new(BTreeMap::new())),
             notification_buses: Arc::new(Mutex::new(Bus::new(1024))),
+            point_cloud_page_bus: Arc::new(Mutex::new(Bus::new(1024))),
+            pending_imports: Arc::new(Mutex::new(BTreeMap::new())),
+            memory_budget: Arc::new(AtomicUsize::new(usize::MAX)),
+            pinned_assets: Arc::new(RwLock::new(BTreeMap::new())),
         })
     }
 
@@ -161,6 +274,16 @@ impl AssetImporter {
                 .register::<ShaderAsset>("vert", Box::new(import_shader))
                 .register::<ShaderAsset>("frag", Box::new(import_shader))
                 .register::<ShaderAsset>("comp", Box::new(import_shader))
+                .register::<TextureAsset>("png", Box::new(import_texture))
+                .register::<TextureAsset>("jpg", Box::new(import_texture))
+                .register::<TextureAsset>("jpeg", Box::new(import_texture))
+                .register::<ModelAsset>("glb", Box::new(import_model))
+                .register::<ModelAsset>("gltf", Box::new(import_model))
+                .register::<ModelAsset>("obj", Box::new(import_model))
+                .register::<ModelAsset>("fbx", Box::new(import_model))
+                .register::<SceneAsset>("scene", Box::new(import_scene))
+                .register::<FontAsset>("ttf", Box::new(import_font))
+                .register::<FontAsset>("otf", Box::new(import_font))
         })
     }
 
@@ -201,6 +324,8 @@ impl AssetImporter {
         }
         let tracked_assets2 = self.tracked_assets.clone();
         let import_source2 = self.import_source.clone();
+        let memory_budget2 = self.memory_budget.clone();
+        let pinned_assets2 = self.pinned_assets.clone();
 
         // Create bus to send the result of the import.
         let bus = Bus::<Arc<Result<Asset<T>>>>::new(1024);
@@ -210,6 +335,8 @@ impl AssetImporter {
         let buses2 = self.asset_buses.clone();
 
         let notification_buses2 = self.notification_buses.clone();
+        let pending_imports2 = self.pending_imports.clone();
+        let importing_assets2 = self.importing_assets.clone();
 
         // Function to import an asset from a file.
         let import_from_file = move |asset_key: &AssetKey| -> Result<Asset<T>> {
@@ -223,14 +350,17 @@ impl AssetImporter {
             trace!("Starting the import for asset '{asset_key}'");
             let value = (importer)(&content)?;
 
-            let raw_asset = Arc::new(RawAsset {
-                asset_key: asset_key.clone(),
-                _ty: TypeId::of::<T>(),
-                value: Mutex::new(Some(Arc::new(value))),
-            });
+            let raw_asset = Arc::new(RawAsset::new(asset_key.clone(), value, content.len()));
             if tracked_assets2.write().insert(asset_key.clone(), raw_asset.clone()).is_some() {
                 trace!("Tracked asset updated: {asset_key}");
             }
+            // The asset that was just imported is kept even if it exceeds the memory budget on its own.
+            evict(
+                &tracked_assets2,
+                &pinned_assets2,
+                memory_budget2.load(Ordering::Relaxed),
+                Some(asset_key),
+            );
             Ok(Asset {
                 raw_asset,
                 _phantom: PhantomData,
@@ -241,7 +371,18 @@ impl AssetImporter {
         importers.insert(
             extension.clone(),
             Arc::new(move |asset_key| {
-                let result = import_from_file(asset_key);
+                let result = Arc::new(import_from_file(asset_key));
+
+                // The asset has to be removed from the importing assets before the futures are completed. Otherwise, a
+                // future that is created in between would wait for an import that already finished.
+                trace!("Removing asset '{asset_key}' from the importing assets");
+                importing_assets2.write().remove(asset_key);
+
+                // Complete the futures that are awaiting the Asset.
+                let pending_imports = pending_imports2.lock().remove(asset_key).unwrap_or_default();
+                for complete in pending_imports {
+                    complete(&result);
+                }
 
                 // Send the Asset to the receivers.
                 let mut buses = buses2.lock();
@@ -249,7 +390,7 @@ impl AssetImporter {
                     .get_mut(&TypeId::of::<T>())
                     .and_then(|any| any.downcast_mut::<Bus<Arc<Result<Asset<T>>>>>())
                     .expect("failed to get bus for asset type although it must have been inserted at registration");
-                bus.broadcast(Arc::new(result));
+                bus.broadcast(result);
 
                 // Send the notification to the receivers.
                 let mut notification_buses = notification_buses2.lock();
@@ -257,6 +398,7 @@ impl AssetImporter {
             }),
         );
         drop(importers);
+        self.asset_types.lock().insert(extension.clone(), TypeId::of::<T>());
         info!("Registerd importer for extension '{extension}'");
         self
     }
@@ -360,6 +502,8 @@ impl AssetImporter {
 
     /// Adds an asset to the tracked assets.
     ///
+    /// Assets that are added manually can't be imported again and are therefore not accounted for in the memory budget.
+    ///
     /// # Example
     ///
     /// ```
@@ -377,11 +521,7 @@ impl AssetImporter {
     where
         T: 'static + Send + Sync,
     {
-        let raw_asset = Arc::new(RawAsset {
-            asset_key: asset_key.clone(),
-            _ty: TypeId::of::<T>(),
-            value: Mutex::new(Some(Arc::new(value))),
-        });
+        let raw_asset = Arc::new(RawAsset::new(asset_key.clone(), value, 0));
         self.tracked_assets.write().insert(asset_key.clone(), raw_asset.clone());
         Asset {
             raw_asset,
@@ -389,15 +529,242 @@ impl AssetImporter {
         }
     }
 
-    /// Imports all assets of the given type.
-    pub fn import_all<T>(&self) -> Result<()> {
-        todo!()
+    /// Sets the maximum number of bytes of imported data that are kept in memory. When the budget is exceeded, the data
+    /// of the least recently used assets is dropped until the budget is met again. The assets stay tracked and can be
+    /// imported again. By default, the budget is unlimited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use jeriya_content::{
+    /// #     asset_importer::{AssetImporter},
+    /// #     read_asset::FileSystem,
+    /// # };
+    /// # std::fs::create_dir_all("assets").unwrap();
+    /// # let asset_source = FileSystem::new("assets").unwrap();
+    /// let asset_importer = AssetImporter::new(asset_source, 4)
+    ///     .unwrap()
+    ///     .with_memory_budget(512 * 1024 * 1024);
+    /// ```
+    pub fn with_memory_budget(self, memory_budget: usize) -> Self {
+        self.memory_budget.store(memory_budget, Ordering::Relaxed);
+        evict(&self.tracked_assets, &self.pinned_assets, memory_budget, None);
+        self
+    }
+
+    /// Returns the number of bytes of imported data that are currently kept in memory.
+    pub fn memory_usage(&self) -> usize {
+        memory_usage(&self.tracked_assets.read())
+    }
+
+    /// Pins the asset so that its data is not evicted when the memory budget is exceeded. This is meant for assets
+    /// that are currently referenced by GPU resources. An asset has to be unpinned as often as it was pinned.
+    pub fn pin(&self, asset_key: &AssetKey) {
+        *self.pinned_assets.write().entry(asset_key.clone()).or_default() += 1;
+    }
+
+    /// Unpins an asset that was pinned with [`AssetImporter::pin`].
+    pub fn unpin(&self, asset_key: &AssetKey) {
+        let mut pinned_assets = self.pinned_assets.write();
+        if let Some(count) = pinned_assets.get_mut(asset_key) {
+            *count -= 1;
+            if *count == 0 {
+                pinned_assets.remove(asset_key);
+            }
+        }
+        drop(pinned_assets);
+        evict(
+            &self.tracked_assets,
+            &self.pinned_assets,
+            self.memory_budget.load(Ordering::Relaxed),
+            None,
+        );
+    }
+
+    /// Returns whether the asset is pinned.
+    pub fn is_pinned(&self, asset_key: &AssetKey) -> bool {
+        self.pinned_assets.read().contains_key(asset_key)
+    }
+
+    /// Reads the [`PageStreamingInfo`] of a processed point cloud without reading any of its `Page`s.
+    pub fn import_page_streaming_info(&self, asset_key: &AssetKey) -> Result<PageStreamingInfo> {
+        let import_source = self.import_source.read();
+        let meta_data = import_source.read_meta_data(asset_key)?;
+        let content = import_source.open_content(asset_key, &meta_data.file)?;
+        ClusteredPointCloudAsset::deserialize_page_streaming_info_from(content)
+    }
+
+    /// Requests a single `Page` of a processed point cloud. The `Page` is read on the thread pool and
+    /// sent to the receivers returned by [`AssetImporter::receive_point_cloud_pages`].
+    pub fn request_point_cloud_page(&self, asset_key: &AssetKey, page_index: usize) {
+        let import_source = self.import_source.clone();
+        let point_cloud_page_bus = self.point_cloud_page_bus.clone();
+        let asset_key = asset_key.clone();
+        self.thread_pool.spawn(move || {
+            trace!("Reading page {page_index} of asset '{asset_key}'");
+            let read_page = || -> Result<Page> {
+                let import_source = import_source.read();
+                let meta_data = import_source.read_meta_data(&asset_key)?;
+                let content = import_source.open_content(&asset_key, &meta_data.file)?;
+                ClusteredPointCloudAsset::deserialize_page_from(content, page_index)
+            };
+            let page = read_page();
+            point_cloud_page_bus.lock().broadcast(Arc::new(PointCloudPageImport {
+                asset_key,
+                page_index,
+                page,
+            }));
+        });
+    }
+
+    /// Returns the receiver for the `Page`s that were requested with [`AssetImporter::request_point_cloud_page`].
+    pub fn receive_point_cloud_pages(&self) -> BusReader<Arc<PointCloudPageImport>> {
+        self.point_cloud_page_bus.lock().add_rx()
+    }
+
+    /// Reads the [`Manifest`] that lists all processed assets.
+    pub fn manifest(&self) -> Result<Manifest> {
+        self.import_source.read().read_manifest()
+    }
+
+    /// Returns the [`ManifestEntry`]s of all processed assets that are imported as the given type.
+    pub fn assets<T>(&self) -> Result<Vec<ManifestEntry>>
+    where
+        T: 'static + Send + Sync,
+    {
+        let manifest = self.manifest()?;
+        let asset_types = self.asset_types.lock();
+        Ok(manifest
+            .entries()
+            .filter(|entry| asset_types.get(&entry.extension) == Some(&TypeId::of::<T>()))
+            .cloned()
+            .collect())
+    }
+
+    /// Imports all assets of the given type that are listed in the [`Manifest`].
+    pub fn import_all<T>(&self) -> Result<()>
+    where
+        T: 'static + Send + Sync,
+    {
+        for entry in self.assets::<T>()? {
+            self.import::<T>(entry.asset_key)?;
+        }
+        Ok(())
     }
 
     /// Imports an asset from the given path.
     pub fn import<T>(&self, asset_key: impl Into<AssetKey>) -> Result<()> {
         import(&asset_key.into(), &self.thread_pool, &self.importers, &self.importing_assets)
     }
+
+    /// Imports an asset from the given path and returns a `Future` that resolves when the import is done.
+    ///
+    /// The `Future` doesn't depend on a specific async runtime and can be awaited with any executor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use jeriya_content::{
+    /// #     asset_importer::{AssetImporter},
+    /// #     read_asset::FileSystem,
+    /// #     Error,
+    /// # };
+    /// # std::fs::create_dir_all("assets").unwrap();
+    /// # let asset_source = FileSystem::new("assets").unwrap();
+    /// let asset_importer = AssetImporter::new(asset_source, 4)
+    ///     .unwrap()
+    ///     .register::<String>(
+    ///          // snip
+    /// #        "txt",
+    /// #        Box::new(|data| {
+    /// #            std::str::from_utf8(data)
+    /// #                .map_err(|err| Error::Other(Box::new(err)))
+    /// #                .map(|s| s.to_owned())
+    /// #        })
+    ///     );
+    ///
+    /// async fn load(asset_importer: &AssetImporter) {
+    ///     let asset = asset_importer.import_async::<String>("test.txt").await;
+    /// }
+    /// ```
+    pub fn import_async<T>(&self, asset_key: impl Into<AssetKey>) -> impl Future<Output = Result<Arc<Asset<T>>>>
+    where
+        T: 'static + Send + Sync,
+    {
+        let asset_key = asset_key.into();
+        let state = Arc::new(Mutex::new(ImportFutureState { result: None, waker: None }));
+
+        // The function has to be registered before the import is started because the import might finish immediately.
+        let state2 = state.clone();
+        let complete_asset_key = asset_key.clone();
+        let complete = move |result: &dyn Any| {
+            let result = match result.downcast_ref::<Arc<Result<Asset<T>>>>().map(|result| result.as_ref()) {
+                Some(Ok(asset)) => Ok(Arc::new(asset.clone())),
+                Some(Err(err)) => Err(Error::FailedImport(complete_asset_key, err.to_string())),
+                None => Err(Error::AssetTypeMismatch(complete_asset_key)),
+            };
+            state2.lock().complete(result);
+        };
+        let complete: Box<CompleteFn> = Box::new(complete);
+        let complete_ptr = complete.as_ref() as *const CompleteFn as *const ();
+        self.pending_imports.lock().entry(asset_key.clone()).or_default().push(complete);
+
+        if let Err(err) = import(&asset_key, &self.thread_pool, &self.importers, &self.importing_assets) {
+            // The import was never started, so the registered function is dropped without being called.
+            let mut pending_imports = self.pending_imports.lock();
+            if let Some(functions) = pending_imports.get_mut(&asset_key) {
+                functions.retain(|function| function.as_ref() as *const CompleteFn as *const () != complete_ptr);
+                if functions.is_empty() {
+                    pending_imports.remove(&asset_key);
+                }
+            }
+            state.lock().complete(Err(err));
+        }
+
+        ImportFuture { state }
+    }
+}
+
+fn memory_usage(tracked_assets: &BTreeMap<AssetKey, Arc<RawAsset>>) -> usize {
+    tracked_assets
+        .values()
+        .filter(|raw_asset| raw_asset.is_loaded())
+        .map(|raw_asset| raw_asset.size)
+        .sum()
+}
+
+/// Drops the data of the least recently used assets that are neither pinned nor `kept` until the `memory_budget` is met.
+fn evict(
+    tracked_assets: &RwLock<BTreeMap<AssetKey, Arc<RawAsset>>>,
+    pinned_assets: &RwLock<BTreeMap<AssetKey, usize>>,
+    memory_budget: usize,
+    kept: Option<&AssetKey>,
+) {
+    let tracked_assets = tracked_assets.read();
+    let mut memory_usage = memory_usage(&tracked_assets);
+    if memory_usage <= memory_budget {
+        return;
+    }
+
+    let pinned_assets = pinned_assets.read();
+    let mut candidates = tracked_assets
+        .values()
+        .filter(|raw_asset| raw_asset.size > 0 && raw_asset.is_loaded())
+        .filter(|raw_asset| !pinned_assets.contains_key(&raw_asset.asset_key) && Some(&raw_asset.asset_key) != kept)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|raw_asset| raw_asset.last_access.load(Ordering::Relaxed));
+
+    for raw_asset in candidates {
+        if memory_usage <= memory_budget {
+            break;
+        }
+        trace!("Evicting asset '{}' to meet the memory budget", raw_asset.asset_key);
+        *raw_asset.value.lock() = None;
+        memory_usage -= raw_asset.size;
+    }
+    if memory_usage > memory_budget {
+        warn!("Memory budget of {memory_budget} bytes is exceeded by pinned assets ({memory_usage} bytes in use)");
+    }
 }
 
 fn import(
@@ -426,23 +793,17 @@ fn import(
     guard.insert(asset_key.clone());
     drop(guard);
 
-    let importing_assets2 = importing_assets.clone();
-
-    // Spawn a thread to import the asset.
+    // Spawn a thread to import the asset. The import function removes the asset from the importing assets.
     let asset_key = asset_key.clone();
     thread_pool.spawn(move || {
-        let importers = importers.lock();
         let importer = importers
+            .lock()
             .get(&extension)
             // The import function checks if the extension is registered and since there is way to
             // remove an extension, this should never fail.
             .expect("failed to find the configuration for the given extension")
             .clone();
         importer(&asset_key);
-
-        trace!("Removing asset '{asset_key}' from the importing assets");
-        let mut importing_assets = importing_assets2.write();
-        importing_assets.remove(&asset_key);
     });
 
     Ok(())
@@ -450,30 +811,38 @@ fn import(
 
 #[cfg(test)]
 mod tests {
-    use std::{fs, time::Duration};
+    use std::{
+        fs,
+        task::Wake,
+        thread::{self, Thread},
+        time::{Duration, Instant},
+    };
 
     use jeriya_shared::indoc::indoc;
     use jeriya_test::setup_logger;
     use tempdir::TempDir;
 
-    use crate::common::ASSET_META_FILE_NAME;
+    use crate::{common::ASSET_META_FILE_NAME, model::ModelAsset, point_cloud::simple_point_cloud::SimplePointCloud};
 
     use super::*;
 
     /// Creates a sample asset containing only a string.
     fn create_processed_asset(root: &Path, content: &str) {
-        let root = root.to_owned();
-
         // This is the folder in the target directory where the processed assets are
         // stored. Therefore, it has the same name as the original asset.
-        const ASSET_FOLDER_NAME: &str = "test.txt";
+        create_named_processed_asset(root, "test.txt", content);
+    }
+
+    /// Creates a sample asset with the given name containing only a string.
+    fn create_named_processed_asset(root: &Path, asset_folder_name: &str, content: &str) {
+        let root = root.to_owned();
 
         // This is the filename of the file that contains the actual content of the
         // processed asset. In this case it is just a text file with a `.bin` extension.
         const ASSET_CONTENT_FILE_NAME: &str = "test.bin";
 
         // Remove and create directory
-        let asset_folder = root.join(ASSET_FOLDER_NAME);
+        let asset_folder = root.join(asset_folder_name);
         let _ = fs::remove_dir_all(&asset_folder);
         fs::create_dir_all(&asset_folder).unwrap();
 
@@ -538,6 +907,208 @@ mod tests {
         assert_eq!(result.value(), None);
     }
 
+    /// Minimal executor that blocks the current thread until the future is resolved.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park_timeout(Duration::from_millis(100)),
+            }
+        }
+    }
+
+    fn string_asset_importer(root: &Path) -> AssetImporter {
+        let asset_source = FileSystem::new(root).unwrap();
+        AssetImporter::new(asset_source, 4).unwrap().register::<String>(
+            "txt",
+            Box::new(|data| {
+                std::str::from_utf8(data)
+                    .map_err(|err| Error::Other(Box::new(err)))
+                    .map(|s| s.to_owned())
+            }),
+        )
+    }
+
+    #[test]
+    fn import_async() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        create_processed_asset(root.path(), "Hello World!");
+        let asset_importer = string_asset_importer(root.path());
+
+        let asset = block_on(asset_importer.import_async::<String>("test.txt")).unwrap();
+        assert_eq!(asset.asset_key().as_path(), Path::new("test.txt"));
+        assert_eq!(asset.value(), Some(Arc::new("Hello World!".to_owned())));
+        assert!(asset_importer.pending_imports.lock().is_empty());
+    }
+
+    #[test]
+    fn import_async_while_completing() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        create_processed_asset(root.path(), "Hello World!");
+        let asset_importer = Arc::new(string_asset_importer(root.path()));
+
+        // Waker that starts the next import of the same asset while the futures of the first import are completed
+        type StringFuture = Pin<Box<dyn Future<Output = Result<Arc<Asset<String>>>> + Send>>;
+        struct ImportOnWake {
+            asset_importer: Arc<AssetImporter>,
+            future: Mutex<Option<StringFuture>>,
+        }
+        impl Wake for ImportOnWake {
+            fn wake(self: Arc<Self>) {
+                let mut future = self.future.lock();
+                if future.is_none() {
+                    *future = Some(Box::pin(self.asset_importer.import_async::<String>("test.txt")));
+                }
+            }
+        }
+        let import_on_wake = Arc::new(ImportOnWake {
+            asset_importer: asset_importer.clone(),
+            future: Mutex::new(None),
+        });
+        let waker = Waker::from(import_on_wake.clone());
+        let mut first = std::pin::pin!(asset_importer.import_async::<String>("test.txt"));
+        let first_result = loop {
+            if let Poll::Ready(result) = first.as_mut().poll(&mut Context::from_waker(&waker)) {
+                break result;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert!(first_result.is_ok());
+
+        // The import that was started during the completion must resolve as well
+        let second = import_on_wake
+            .future
+            .lock()
+            .take()
+            .expect("the waker must have been called when the first import completed");
+        struct NoopWake;
+        impl Wake for NoopWake {
+            fn wake(self: Arc<Self>) {}
+        }
+        let noop_waker = Waker::from(Arc::new(NoopWake));
+        let mut second = std::pin::pin!(second);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let second_result = loop {
+            if let Poll::Ready(result) = second.as_mut().poll(&mut Context::from_waker(&noop_waker)) {
+                break result;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "the import that was started during the completion never resolved"
+            );
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(second_result.unwrap().value(), Some(Arc::new("Hello World!".to_owned())));
+        assert!(asset_importer.pending_imports.lock().is_empty());
+    }
+
+    #[test]
+    fn import_async_failed() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let asset_importer = string_asset_importer(root.path());
+
+        // Extension not registered
+        let result = block_on(asset_importer.import_async::<String>("test.png"));
+        assert!(matches!(result, Err(Error::ExtensionNotRegistered(_))));
+        assert!(asset_importer.pending_imports.lock().is_empty());
+
+        // Asset doesn't exist
+        let result = block_on(asset_importer.import_async::<String>("test.txt"));
+        assert!(matches!(result, Err(Error::FailedImport(..))));
+
+        // Asset is imported as a different type
+        create_processed_asset(root.path(), "Hello World!");
+        let result = block_on(asset_importer.import_async::<u32>("test.txt"));
+        assert!(matches!(result, Err(Error::AssetTypeMismatch(_))));
+    }
+
+    #[test]
+    fn memory_budget() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            create_named_processed_asset(root.path(), name, "Hello");
+        }
+        let asset_importer = string_asset_importer(root.path()).with_memory_budget(10);
+        let mut receiver = asset_importer.receive_assets::<String>().unwrap();
+        let import = |name: &str, receiver: &mut BusReader<Arc<Result<Asset<String>>>>| {
+            asset_importer.import::<String>(name).unwrap();
+            expect_asset(receiver.recv_timeout(Duration::from_millis(1000)))
+        };
+        let is_loaded = |name: &str| asset_importer.get::<String>(&AssetKey::new(name)).unwrap().value().is_some();
+
+        let a = import("a.txt", &mut receiver);
+        import("b.txt", &mut receiver);
+        assert_eq!(asset_importer.memory_usage(), 10);
+
+        // "b.txt" is the least recently used asset after accessing "a.txt"
+        assert!(a.value().is_some());
+        import("c.txt", &mut receiver);
+        assert_eq!(asset_importer.memory_usage(), 10);
+        assert!(is_loaded("a.txt"));
+        assert!(!is_loaded("b.txt"));
+        assert!(is_loaded("c.txt"));
+
+        // "a.txt" is the least recently used asset but it is pinned
+        asset_importer.pin(&AssetKey::new("a.txt"));
+        assert!(asset_importer.is_pinned(&AssetKey::new("a.txt")));
+        assert!(is_loaded("c.txt"));
+        import("b.txt", &mut receiver);
+        assert_eq!(asset_importer.memory_usage(), 10);
+        assert!(is_loaded("a.txt"));
+        assert!(is_loaded("b.txt"));
+        assert!(!is_loaded("c.txt"));
+
+        asset_importer.unpin(&AssetKey::new("a.txt"));
+        assert!(!asset_importer.is_pinned(&AssetKey::new("a.txt")));
+    }
+
+    #[test]
+    fn assets_from_manifest() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        create_processed_asset(root.path(), "Hello World!");
+        let mut manifest = Manifest::default();
+        for (asset_key, extension) in [("test.txt", "txt"), ("test.png", "png")] {
+            manifest.insert(ManifestEntry {
+                asset_key: AssetKey::new(asset_key),
+                extension: extension.to_owned(),
+                size: 12,
+                content_hash: 0,
+                modified: std::time::SystemTime::UNIX_EPOCH,
+            });
+        }
+        manifest.write(root.path()).unwrap();
+
+        let asset_importer = string_asset_importer(root.path());
+        assert_eq!(asset_importer.manifest().unwrap(), manifest);
+        let assets = asset_importer.assets::<String>().unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].asset_key, AssetKey::new("test.txt"));
+
+        let mut receiver = asset_importer.receive_assets::<String>().unwrap();
+        asset_importer.import_all::<String>().unwrap();
+        let asset = expect_asset(receiver.recv_timeout(Duration::from_millis(1000)));
+        assert_eq!(asset.value(), Some(Arc::new("Hello World!".to_owned())));
+    }
+
     #[test]
     fn directory_not_found() {
         let asset_source = FileSystem::new("not_found");
@@ -603,4 +1174,38 @@ mod tests {
         let asset = expect_asset(asset_receiver.recv_timeout(Duration::from_millis(1000)));
         assert_eq!(asset.value(), Some(Arc::new("Hello World!".to_owned())));
     }
+
+    #[test]
+    fn request_point_cloud_page() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+
+        // Write a processed point cloud
+        let model = ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap();
+        let simple_point_cloud = SimplePointCloud::sample_from_model(&model, 200.0, 1.0);
+        let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+        let asset_folder = root.path().join("suzanne.ply");
+        fs::create_dir_all(&asset_folder).unwrap();
+        clustered_point_cloud
+            .serialize_to_file(&asset_folder.join("point_cloud.bin"))
+            .unwrap();
+        fs::write(asset_folder.join(ASSET_META_FILE_NAME), "file: \"point_cloud.bin\"").unwrap();
+
+        let asset_source = FileSystem::new(root.path()).unwrap();
+        let asset_importer = AssetImporter::new(asset_source, 4).unwrap();
+        let mut receiver = asset_importer.receive_point_cloud_pages();
+
+        let asset_key = AssetKey::new("suzanne.ply");
+        let page_streaming_info = asset_importer.import_page_streaming_info(&asset_key).unwrap();
+        assert_eq!(page_streaming_info.pages_len, clustered_point_cloud.pages().len());
+        assert_eq!(page_streaming_info.root_cluster_index, clustered_point_cloud.root_cluster_index());
+
+        let page_index = page_streaming_info.root_cluster_index.page_index;
+        asset_importer.request_point_cloud_page(&asset_key, page_index);
+        let result = receiver.recv_timeout(Duration::from_millis(1000)).unwrap();
+        assert_eq!(result.asset_key, asset_key);
+        assert_eq!(result.page_index, page_index);
+        assert_eq!(result.page.as_ref().unwrap(), &clustered_point_cloud.pages()[page_index]);
+    }
 }
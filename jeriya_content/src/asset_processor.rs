@@ -1,40 +1,192 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fs, io,
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     thread,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use crate::{
-    common::{extract_extension_from_path, modified_system_time, AssetKey, Directories, ASSET_META_FILE_NAME},
+    common::{asset_path_of_sidecar, content_hash, extract_extension_from_path, sidecar_path, AssetKey, Directories, ASSET_META_FILE_NAME},
+    manifest::{Manifest, ManifestEntry},
+    read_asset::AssetMetaData,
     Error, Result,
 };
 use jeriya_shared::{
     crossbeam_channel::{self, Receiver, Sender},
     log::{error, info, trace, warn},
     parking_lot::Mutex,
-    pathdiff,
     walkdir::WalkDir,
 };
 use notify_debouncer_full::{
     notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher},
     DebounceEventResult, DebouncedEvent, Debouncer, FileIdMap,
 };
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-type ProcessFn = dyn Fn(&AssetKey, &Path, &Path) + Send + Sync;
+type ProcessFn = dyn Fn(&AssetKey, &Path, &Path) -> Result<()> + Send + Sync;
 
 pub type Processor = dyn Fn(&mut AssetBuilder) -> Result<()> + Send + Sync;
 
+/// Maps the [`AssetKey`] of a dependency to the [`AssetKey`]s of the assets that depend on it.
+type Dependents = BTreeMap<AssetKey, BTreeSet<AssetKey>>;
+
+/// Maps the file extension to the [`RegisteredProcessor`] that processes files with this extension.
+type Processors = BTreeMap<String, RegisteredProcessor>;
+
+/// Name of the file in the processed asset directory that records the inputs of the last successful processing.
+const PROCESSING_STATE_FILE_NAME: &str = "processing_state.yaml";
+
+#[derive(Clone)]
+struct RegisteredProcessor {
+    version: u32,
+    process_fn: Arc<ProcessFn>,
+}
+
+/// Inputs of the last successful processing of an asset. The asset doesn't have to be processed
+/// again as long as its content, the content of its dependencies and the processor version match.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ProcessingState {
+    processor_version: u32,
+    content_hash: u64,
+    settings_hash: Option<u64>,
+    dependency_hashes: BTreeMap<PathBuf, u64>,
+}
+
+impl ProcessingState {
+    /// Reads the `ProcessingState` from the processed asset directory. Returns `None` when the asset has never been processed successfully.
+    fn read(processed_asset_path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(processed_asset_path.join(PROCESSING_STATE_FILE_NAME)).ok()?;
+        serde_yaml::from_str(&content).ok()
+    }
+
+    /// Writes the `ProcessingState` into the processed asset directory.
+    fn write(&self, processed_asset_path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self).map_err(|err| Error::FailedSerialization(Box::new(err)))?;
+        fs::write(processed_asset_path.join(PROCESSING_STATE_FILE_NAME), content)?;
+        Ok(())
+    }
+
+    /// Returns `true` when the asset was processed with the given processor version and neither
+    /// the asset, its sidecar file nor any of its dependencies changed since then.
+    fn is_up_to_date(&self, processor_version: u32, unprocessed_asset_path: &Path) -> bool {
+        self.processor_version == processor_version
+            && content_hash(unprocessed_asset_path).ok() == Some(self.content_hash)
+            && content_hash(&sidecar_path(unprocessed_asset_path)).ok() == self.settings_hash
+            && self
+                .dependency_hashes
+                .iter()
+                .all(|(dependency, hash)| content_hash(dependency).ok() == Some(*hash))
+    }
+}
+
+/// Name of the file in the processed asset directory that describes why the last processing failed.
+const PROCESSING_ERROR_FILE_NAME: &str = "processing_error.yaml";
+
+/// Report about an asset whose processing failed. It is written into the processed asset directory
+/// so that failures survive restarts and can be listed with [`AssetProcessor::failed_assets`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedAsset {
+    /// Asset whose processing failed
+    pub asset_key: AssetKey,
+    /// Error that the processor returned
+    pub error: String,
+    /// Point in time when the processing failed
+    pub timestamp: SystemTime,
+}
+
+impl FailedAsset {
+    /// Reads the `FailedAsset` from the processed asset directory. Returns `None` when the last processing didn't fail.
+    fn read(processed_asset_path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(processed_asset_path.join(PROCESSING_ERROR_FILE_NAME)).ok()?;
+        serde_yaml::from_str(&content)
+            .map_err(|err| {
+                warn!(
+                    "Failed to read the processing error report in '{}': {err}",
+                    processed_asset_path.display()
+                )
+            })
+            .ok()
+    }
+
+    /// Writes the `FailedAsset` into the processed asset directory.
+    fn write(&self, processed_asset_path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self).map_err(|err| Error::FailedSerialization(Box::new(err)))?;
+        fs::write(processed_asset_path.join(PROCESSING_ERROR_FILE_NAME), content)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Event {
     Processed(PathBuf),
 }
 
+/// Change in the processing of an asset that is reported to the observers of [`AssetProcessor::observe_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Asset whose processing state changed
+    pub asset_key: AssetKey,
+    /// New processing state of the asset
+    pub state: ProgressState,
+    /// Number of assets that are queued or being processed after the change
+    pub queue_len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressState {
+    /// The asset has been queued for processing
+    Queued,
+    /// A thread started processing the asset
+    Started,
+    /// The asset has been processed successfully
+    Finished,
+    /// The processing of the asset failed with the given error message
+    Failed(String),
+}
+
+/// Tracks the number of queued assets and sends [`Progress`] to the observers.
+#[derive(Default)]
+struct ProgressReporter {
+    senders: Mutex<Vec<Sender<Progress>>>,
+    queue_len: AtomicUsize,
+}
+
+impl ProgressReporter {
+    fn queued(&self, asset_key: &AssetKey) {
+        let queue_len = self.queue_len.fetch_add(1, Ordering::SeqCst) + 1;
+        self.send(asset_key, ProgressState::Queued, queue_len);
+    }
+
+    fn started(&self, asset_key: &AssetKey) {
+        let queue_len = self.queue_len.load(Ordering::SeqCst);
+        self.send(asset_key, ProgressState::Started, queue_len);
+    }
+
+    fn finished(&self, asset_key: &AssetKey, error: Option<String>) {
+        let queue_len = self.queue_len.fetch_sub(1, Ordering::SeqCst) - 1;
+        let state = match error {
+            Some(error) => ProgressState::Failed(error),
+            None => ProgressState::Finished,
+        };
+        self.send(asset_key, state, queue_len);
+    }
+
+    /// Sends the [`Progress`] to all observers and removes the channels that are no longer active.
+    fn send(&self, asset_key: &AssetKey, state: ProgressState, queue_len: usize) {
+        let progress = Progress {
+            asset_key: asset_key.clone(),
+            state,
+            queue_len,
+        };
+        self.senders.lock().retain(|sender| sender.send(progress.clone()).is_ok());
+    }
+}
+
 pub enum Item {
     Wakeup,
     Process(ProcessItem),
@@ -51,7 +203,10 @@ pub struct AssetProcessor {
     running: Arc<AtomicBool>,
     item_sender: Sender<Item>,
     senders: Arc<Mutex<Vec<Sender<Event>>>>,
-    processors: Arc<Mutex<BTreeMap<String, Arc<ProcessFn>>>>,
+    progress_reporter: Arc<ProgressReporter>,
+    processors: Arc<Mutex<Processors>>,
+    dependents: Arc<Mutex<Dependents>>,
+    manifest: Arc<Mutex<Manifest>>,
     _watcher: Debouncer<RecommendedWatcher, FileIdMap>,
 }
 
@@ -73,7 +228,18 @@ impl AssetProcessor {
         info!("Creating AssetProcessor for '{directories:?}'");
 
         let event_senders = Arc::new(Mutex::new(Vec::new()));
+        let progress_reporter = Arc::new(ProgressReporter::default());
         let processors = Arc::new(Mutex::new(BTreeMap::new()));
+        let dependents = Arc::new(Mutex::new(Dependents::new()));
+        let manifest = match Manifest::read(directories.processed_assets_path()) {
+            Ok(manifest) => manifest,
+            Err(Error::IoError(err)) if err.kind() == io::ErrorKind::NotFound => Manifest::default(),
+            Err(err) => {
+                warn!("Failed to read the asset manifest. It will be recreated: {err}");
+                Manifest::default()
+            }
+        };
+        let manifest = Arc::new(Mutex::new(manifest));
 
         // The [`AssetProcessor`] has to be started manually after the constructor has run so
         // that the user can register processors and receive events for all assets.
@@ -83,12 +249,21 @@ impl AssetProcessor {
 
         let (item_sender, item_receiver) = crossbeam_channel::unbounded::<Item>();
         for thread_index in 0..num_threads {
-            spawn_thread(&wants_drop, &item_receiver, &directories, &event_senders, thread_index)?;
+            spawn_thread(
+                &wants_drop,
+                &item_receiver,
+                &directories,
+                &event_senders,
+                &progress_reporter,
+                thread_index,
+            )?;
         }
 
         let running2 = running.clone();
         let sender2 = item_sender.clone();
         let processors2 = processors.clone();
+        let dependents2 = dependents.clone();
+        let progress_reporter2 = progress_reporter.clone();
         let directories2 = directories.clone();
         let watch_fn = move |result: DebounceEventResult| match result {
             Ok(events) => {
@@ -115,27 +290,20 @@ impl AssetProcessor {
 
                     // The file watcher returns absolute paths but he whole asset handling is based on
                     // relative paths because it's irrelavant where on the system they are located.
-                    let Some(path) = pathdiff::diff_paths(absolute_path, directories2.unprocessed_assets_path()) else {
+                    let Some(asset_key) = directories2.asset_key_of_unprocessed_path(absolute_path) else {
                         warn! {
-                            "Failed to get relative path of '{absolute_path}' relative to '{unprocessed_assets_path}'",
+                            "Failed to get the asset key of '{absolute_path}' because it is not located in an unprocessed assets directory",
                             absolute_path = absolute_path.display(),
-                            unprocessed_assets_path = directories2.unprocessed_assets_path().display()
                         };
                         return;
                     };
-                    assert!(path.is_relative(), "path '{}' is not relative", path.display());
-                    let asset_key = AssetKey::new(path);
 
                     match &event.kind {
                         EventKind::Create(_create_event) => {
-                            if let Err(err) = process(&asset_key, &directories2, &sender2, &processors) {
-                                error!("Failed to process file '{asset_key}': {err}");
-                            }
+                            process_with_dependents(&asset_key, &directories2, &sender2, &processors, &dependents2, &progress_reporter2);
                         }
                         EventKind::Modify(_modify_event) => {
-                            if let Err(err) = process(&asset_key, &directories2, &sender2, &processors) {
-                                error!("Failed to process file '{asset_key}': {err}");
-                            }
+                            process_with_dependents(&asset_key, &directories2, &sender2, &processors, &dependents2, &progress_reporter2);
                         }
                         _ => {}
                     }
@@ -150,10 +318,12 @@ impl AssetProcessor {
         // Start the directory watcher.
         let mut watcher = notify_debouncer_full::new_debouncer(Duration::from_millis(1000), None, watch_fn)
             .map_err(|_| Error::FailedToStartDirectoryWatcher(directories.unprocessed_assets_path().to_owned()))?;
-        watcher
-            .watcher()
-            .watch(directories.unprocessed_assets_path(), RecursiveMode::Recursive)
-            .map_err(|_| Error::FailedToStartDirectoryWatcher(directories.unprocessed_assets_path().to_owned()))?;
+        for (_, unprocessed_assets_path) in directories.unprocessed_roots() {
+            watcher
+                .watcher()
+                .watch(unprocessed_assets_path, RecursiveMode::Recursive)
+                .map_err(|_| Error::FailedToStartDirectoryWatcher(unprocessed_assets_path.to_owned()))?;
+        }
 
         Ok(Self {
             directories,
@@ -161,7 +331,10 @@ impl AssetProcessor {
             running,
             item_sender,
             senders: event_senders,
+            progress_reporter,
             processors,
+            dependents,
+            manifest,
             _watcher: watcher,
         })
     }
@@ -171,7 +344,13 @@ impl AssetProcessor {
         self.running.store(active, Ordering::SeqCst);
 
         if active {
-            run_inventory(&self.directories, &self.item_sender, &self.processors)?;
+            run_inventory(
+                &self.directories,
+                &self.item_sender,
+                &self.processors,
+                &self.progress_reporter,
+                &self.manifest,
+            )?;
         }
 
         Ok(())
@@ -197,26 +376,92 @@ impl AssetProcessor {
     ///     );
     /// ```
     pub fn register(self, extension: impl Into<String>, processor: Box<Processor>) -> Self {
+        self.register_with_version(extension, 0, processor)
+    }
+
+    /// Registers a [`Processor`] with the given version for the given file extension.
+    ///
+    /// Assets whose content and dependencies didn't change since they have been processed with the
+    /// same version are skipped when the [`AssetProcessor`] is activated. The version has to be
+    /// increased whenever the output or the settings of the [`Processor`] change.
+    pub fn register_with_version(self, extension: impl Into<String>, version: u32, processor: Box<Processor>) -> Self {
         let extension = extension.into();
         let mut processors = self.processors.lock();
         if processors.contains_key(&extension) {
             panic!("importer for extension '{extension}' already registered");
         }
-        processors.insert(
-            extension,
-            Arc::new(move |asset_key, unprocessed_asset_path, processed_asset_path| {
+        let dependents = self.dependents.clone();
+        let manifest = self.manifest.clone();
+        let directories = self.directories.clone();
+        let processed_assets_path = self.directories.processed_assets_path().to_owned();
+        let process_fn = Arc::new(
+            move |asset_key: &AssetKey, unprocessed_asset_path: &Path, processed_asset_path: &Path| {
                 info!("Processing file: {asset_key}");
+
+                // The hashes are computed before processing so that changes during the processing are not missed.
+                let settings_hash = content_hash(&sidecar_path(unprocessed_asset_path)).ok();
+                let content_hash = content_hash(unprocessed_asset_path)
+                    .map_err(|err| warn!("Failed to compute the content hash of asset '{asset_key}': {err}"))
+                    .ok();
+
+                // A stale state would otherwise mark the asset as up to date when the processing fails.
+                let _ = fs::remove_file(processed_asset_path.join(PROCESSING_STATE_FILE_NAME));
+
                 let mut asset_builder = AssetBuilder::new(asset_key, unprocessed_asset_path, processed_asset_path);
                 let process_result = (processor)(&mut asset_builder);
+
+                // The dependencies are recorded even when the processing failed so that fixing
+                // a dependency triggers the processing of the asset again.
+                record_dependencies(asset_key, &asset_builder.dependencies, &directories, &dependents);
+
                 match process_result {
-                    Ok(()) => match asset_builder.build() {
-                        Ok(_) => info!("Successfully processed and built file: {asset_key}"),
-                        Err(_) => error!("Failed to build the meta file for asset '{asset_key}'"),
-                    },
-                    Err(err) => error!("Failed to process file '{asset_key}': {err}"),
+                    Ok(()) => {
+                        let dependencies = asset_builder.dependencies.clone();
+                        match asset_builder.build() {
+                            Ok(_) => info!("Successfully processed and built file: {asset_key}"),
+                            Err(err) => {
+                                error!("Failed to build the meta file for asset '{asset_key}'");
+                                return Err(err.into());
+                            }
+                        }
+                        // Without all hashes the asset can't be checked for changes and is processed again on the next activation.
+                        let dependency_hashes = dependencies
+                            .into_iter()
+                            .map(|dependency| content_hash_of_dependency(asset_key, dependency))
+                            .collect::<Option<BTreeMap<_, _>>>();
+                        if let (Some(content_hash), Some(dependency_hashes)) = (content_hash, dependency_hashes) {
+                            let processing_state = ProcessingState {
+                                processor_version: version,
+                                content_hash,
+                                settings_hash,
+                                dependency_hashes,
+                            };
+                            if let Err(err) = processing_state.write(processed_asset_path) {
+                                error!("Failed to write the processing state of asset '{asset_key}': {err}");
+                            }
+                        }
+                        let manifest_entry = content_hash
+                            .and_then(|content_hash| manifest_entry(asset_key, unprocessed_asset_path, processed_asset_path, content_hash));
+                        update_manifest(&manifest, &processed_assets_path, |manifest| match manifest_entry {
+                            Some(manifest_entry) => manifest.insert(manifest_entry),
+                            None => {
+                                warn!("Failed to create the manifest entry of asset '{asset_key}'");
+                                manifest.remove(asset_key);
+                            }
+                        });
+                        Ok(())
+                    }
+                    Err(err) => {
+                        error!("Failed to process file '{asset_key}': {err}");
+                        update_manifest(&manifest, &processed_assets_path, |manifest| {
+                            manifest.remove(asset_key);
+                        });
+                        Err(err)
+                    }
                 }
-            }),
+            },
         );
+        processors.insert(extension, RegisteredProcessor { version, process_fn });
         drop(processors);
         self
     }
@@ -227,6 +472,58 @@ impl AssetProcessor {
         self.senders.lock().push(sender);
         receiver
     }
+
+    /// Returns a channel that reports when assets are queued, started, finished or failed together
+    /// with the number of assets that are still queued. Call this before [`AssetProcessor::set_active`]
+    /// to also observe the assets that are processed by the initial inventory.
+    pub fn observe_progress(&mut self) -> Receiver<Progress> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.progress_reporter.senders.lock().push(sender);
+        receiver
+    }
+
+    /// Returns the assets whose last processing failed, including the failures of previous runs.
+    pub fn failed_assets(&self) -> Vec<FailedAsset> {
+        let mut failed_assets = self
+            .directories
+            .processed_roots()
+            .flat_map(WalkDir::new)
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && entry.file_name() == PROCESSING_ERROR_FILE_NAME)
+            .filter_map(|entry| FailedAsset::read(entry.path().parent()?))
+            .collect::<Vec<_>>();
+        failed_assets.sort_by(|a, b| a.asset_key.cmp(&b.asset_key));
+        failed_assets
+    }
+
+    /// Queues all assets whose last processing failed to be processed again.
+    pub fn retry_failed_assets(&self) -> Result<()> {
+        for failed_asset in self.failed_assets() {
+            info!(
+                "Retrying to process '{}' which failed with: {}",
+                failed_asset.asset_key, failed_asset.error
+            );
+            process(
+                &failed_asset.asset_key,
+                &self.directories,
+                &self.item_sender,
+                &self.processors,
+                &self.progress_reporter,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of assets that are queued or being processed.
+    pub fn queue_len(&self) -> usize {
+        self.progress_reporter.queue_len.load(Ordering::SeqCst)
+    }
+
+    /// Returns the [`Manifest`] of the successfully processed assets. It is written to the root of the processed
+    /// assets directory whenever it changes.
+    pub fn manifest(&self) -> Manifest {
+        self.manifest.lock().clone()
+    }
 }
 
 fn spawn_thread(
@@ -234,9 +531,11 @@ fn spawn_thread(
     item_receiver: &Receiver<Item>,
     directories: &Directories,
     event_senders: &Arc<Mutex<Vec<Sender<Event>>>>,
+    progress_reporter: &Arc<ProgressReporter>,
     thread_index: usize,
 ) -> Result<()> {
     let wants_drop = wants_drop.clone();
+    let progress_reporter = progress_reporter.clone();
     let item_receiver = item_receiver.clone();
     let directories = directories.clone();
     let event_senders2 = event_senders.clone();
@@ -262,15 +561,30 @@ fn spawn_thread(
                 };
                 info!("AssetProcessor starting work on item: {}", process_item.asset_key);
 
-                if !directories.unprocessed_assets_path().exists() {
+                let (unprocessed_asset_path, processed_asset_path) = match (
+                    directories.unprocessed_asset_path(&process_item.asset_key),
+                    directories.processed_asset_path(&process_item.asset_key),
+                ) {
+                    (Ok(unprocessed_asset_path), Ok(processed_asset_path)) => (unprocessed_asset_path, processed_asset_path),
+                    (Err(err), _) | (_, Err(err)) => {
+                        error!("Failed to resolve the paths of asset '{}': {err}", process_item.asset_key);
+                        progress_reporter.finished(&process_item.asset_key, Some(err.to_string()));
+                        continue;
+                    }
+                };
+                if !unprocessed_asset_path.exists() {
                     info!("Asset '{}' was deleted before it could be processed", process_item.asset_key);
-                    return;
+                    progress_reporter.finished(
+                        &process_item.asset_key,
+                        Some("The asset was deleted before it could be processed".to_owned()),
+                    );
+                    continue;
                 }
-                (process_item.processor)(
-                    &process_item.asset_key,
-                    &directories.unprocessed_assets_path().join(process_item.asset_key.as_path()),
-                    &directories.processed_assets_path().join(process_item.asset_key.as_path()),
-                );
+                progress_reporter.started(&process_item.asset_key);
+                let process_result = (process_item.processor)(&process_item.asset_key, &unprocessed_asset_path, &processed_asset_path);
+                let error = process_result.err().map(|err| err.to_string());
+                report_processing_result(&process_item.asset_key, &processed_asset_path, error.as_deref());
+                progress_reporter.finished(&process_item.asset_key, error);
 
                 // Send a Processed event to all observers and remove the channels
                 // that are no longer active.
@@ -315,11 +629,134 @@ fn event_name(event: &DebouncedEvent) -> &str {
     }
 }
 
+/// Writes the report about a failed processing or removes the outdated report when the processing succeeded.
+fn report_processing_result(asset_key: &AssetKey, processed_asset_path: &Path, error: Option<&str>) {
+    match error {
+        Some(error) => {
+            let failed_asset = FailedAsset {
+                asset_key: asset_key.clone(),
+                error: error.to_owned(),
+                timestamp: SystemTime::now(),
+            };
+            if let Err(err) = failed_asset.write(processed_asset_path) {
+                error!("Failed to write the processing error report of asset '{asset_key}': {err}");
+            }
+        }
+        None => {
+            let report_path = processed_asset_path.join(PROCESSING_ERROR_FILE_NAME);
+            if report_path.exists() {
+                if let Err(err) = fs::remove_file(&report_path) {
+                    error!("Failed to remove the outdated processing error report of asset '{asset_key}': {err}");
+                }
+            }
+        }
+    }
+}
+
+/// Creates the [`ManifestEntry`] of a successfully processed asset. Returns `None` when the processed content file can't be found.
+fn manifest_entry(
+    asset_key: &AssetKey,
+    unprocessed_asset_path: &Path,
+    processed_asset_path: &Path,
+    content_hash: u64,
+) -> Option<ManifestEntry> {
+    let extension = extract_extension_from_path(asset_key.as_path()).ok()?;
+    let meta_file_content = fs::read_to_string(processed_asset_path.join(ASSET_META_FILE_NAME)).ok()?;
+    let meta_data = serde_yaml::from_str::<AssetMetaData>(&meta_file_content).ok()?;
+    let size = fs::metadata(processed_asset_path.join(meta_data.file)).ok()?.len();
+    let modified = fs::metadata(unprocessed_asset_path).and_then(|metadata| metadata.modified()).ok()?;
+    Some(ManifestEntry {
+        asset_key: asset_key.clone(),
+        extension,
+        size,
+        content_hash,
+        modified,
+    })
+}
+
+/// Applies `f` to the [`Manifest`] and writes it into the processed assets directory.
+fn update_manifest(manifest: &Mutex<Manifest>, processed_assets_path: &Path, f: impl FnOnce(&mut Manifest)) {
+    let mut manifest = manifest.lock();
+    f(&mut manifest);
+    if let Err(err) = manifest.write(processed_assets_path) {
+        error!("Failed to write the asset manifest: {err}");
+    }
+}
+
+/// Returns the path and content hash of the dependency or `None` when the hash can't be computed.
+fn content_hash_of_dependency(asset_key: &AssetKey, dependency: PathBuf) -> Option<(PathBuf, u64)> {
+    match content_hash(&dependency) {
+        Ok(hash) => Some((dependency, hash)),
+        Err(err) => {
+            warn!(
+                "Failed to compute the content hash of dependency '{}' of asset '{asset_key}': {err}",
+                dependency.display()
+            );
+            None
+        }
+    }
+}
+
+/// Processes the asset and all assets that depend on it.
+fn process_with_dependents(
+    asset_key: &AssetKey,
+    directories: &Directories,
+    sender: &Sender<Item>,
+    processors: &Arc<Mutex<Processors>>,
+    dependents: &Arc<Mutex<Dependents>>,
+    progress_reporter: &ProgressReporter,
+) {
+    // The sidecar file configures the processing of its asset, so the asset has to be processed again
+    if let Some(asset_path) = asset_path_of_sidecar(asset_key.as_path()) {
+        let asset_key = AssetKey::new(asset_path);
+        info!("Processing '{asset_key}' because its sidecar file changed");
+        process_with_dependents(&asset_key, directories, sender, processors, dependents, progress_reporter);
+        return;
+    }
+
+    let asset_dependents = dependents.lock().get(asset_key).cloned().unwrap_or_default();
+    match process(asset_key, directories, sender, processors, progress_reporter) {
+        // Files like shader includes are not processed themselves but only as part of their dependents.
+        Err(Error::ExtensionNotRegistered(_)) if !asset_dependents.is_empty() => {}
+        Err(err) => error!("Failed to process file '{asset_key}': {err}"),
+        Ok(()) => {}
+    }
+    for dependent in asset_dependents {
+        info!("Processing '{dependent}' because its dependency '{asset_key}' changed");
+        if let Err(err) = process(&dependent, directories, sender, processors, progress_reporter) {
+            error!("Failed to process file '{dependent}': {err}");
+        }
+    }
+}
+
+/// Replaces the dependencies that have been recorded for the given `asset_key` with the given `dependencies`.
+///
+/// Dependencies outside of the unprocessed assets directories are not watched and are therefore ignored.
+fn record_dependencies(asset_key: &AssetKey, dependencies: &[PathBuf], directories: &Directories, dependents: &Mutex<Dependents>) {
+    let mut dependents = dependents.lock();
+    for asset_dependents in dependents.values_mut() {
+        asset_dependents.remove(asset_key);
+    }
+    dependents.retain(|_, asset_dependents| !asset_dependents.is_empty());
+    for dependency in dependencies {
+        let dependency = dependency.canonicalize().unwrap_or_else(|_| dependency.clone());
+        let Some(dependency_key) = directories.asset_key_of_unprocessed_path(&dependency) else {
+            trace!(
+                "Dependency '{}' of asset '{asset_key}' is outside of the unprocessed assets directories",
+                dependency.display()
+            );
+            continue;
+        };
+        dependents.entry(dependency_key).or_default().insert(asset_key.clone());
+    }
+}
+
 fn process(
     asset_key: &AssetKey,
     directories: &Directories,
     sender: &Sender<Item>,
-    processors: &Arc<Mutex<BTreeMap<String, Arc<ProcessFn>>>>,
+    processors: &Arc<Mutex<Processors>>,
+    progress_reporter: &ProgressReporter,
 ) -> Result<()> {
     let processors = processors.clone();
     let asset_path = asset_key.as_path().to_owned();
@@ -337,12 +774,16 @@ fn process(
     let extension = extract_extension_from_path(&asset_path)?;
 
     trace!("Querying the processor for the extension '{extension}'");
-    let Some(processor) = processors.lock().get(&extension).cloned() else {
+    let Some(processor) = processors
+        .lock()
+        .get(&extension)
+        .map(|registered_processor| registered_processor.process_fn.clone())
+    else {
         return Err(Error::ExtensionNotRegistered(extension));
     };
 
     // Creating the directory for the processed asset that has the same name as the unprocessed asset.
-    let processed_asset_path = directories.processed_assets_path().join(&asset_path);
+    let processed_asset_path = directories.processed_asset_path(asset_key)?;
     info!("Creating directory for processed assets: {processed_asset_path:?}");
     fs::create_dir_all(&processed_asset_path)?;
 
@@ -350,8 +791,11 @@ fn process(
         asset_key: asset_key.clone(),
         processor,
     });
+    // The asset is counted before it is sent so that the queue length never drops below zero.
+    progress_reporter.queued(asset_key);
     if let Err(err) = sender.send(item) {
         error!("Failed to send item to AssetProcessor thread: {err}");
+        progress_reporter.finished(asset_key, Some(err.to_string()));
     }
 
     Ok(())
@@ -361,16 +805,27 @@ fn process(
 fn run_inventory(
     directories: &Directories,
     sender: &Sender<Item>,
-    processors: &Arc<Mutex<BTreeMap<String, Arc<ProcessFn>>>>,
+    processors: &Arc<Mutex<Processors>>,
+    progress_reporter: &ProgressReporter,
+    manifest: &Mutex<Manifest>,
 ) -> Result<()> {
     let mut inventory = BTreeMap::new();
+    let mut up_to_date_assets = Vec::new();
 
-    let extensions = processors.lock().keys().cloned().collect::<HashSet<_>>();
+    let versions = processors
+        .lock()
+        .iter()
+        .map(|(extension, registered_processor)| (extension.clone(), registered_processor.version))
+        .collect::<HashMap<_, _>>();
 
-    let path = directories.unprocessed_assets_path();
-    info!("Running inventory in path: {path:?}");
+    // Nested unprocessed assets directories would otherwise yield the same asset twice.
+    let mut visited_assets = BTreeSet::new();
 
-    for entry in WalkDir::new(path) {
+    let entries = directories.unprocessed_roots().flat_map(|(_, path)| {
+        info!("Running inventory in path: {path:?}");
+        WalkDir::new(path)
+    });
+    for entry in entries {
         let Ok(entry) = entry else {
             warn!(
                 "Failed to read directory entry in WalkDir {:?}: {}",
@@ -384,25 +839,27 @@ fn run_inventory(
             continue;
         }
 
-        let Some(relative_path) = pathdiff::diff_paths(entry.path(), path) else {
-            warn!("Failed to get relative path of '{}' relative to '{path:?}'", entry.path().display());
+        let Some(asset_key) = directories.asset_key_of_unprocessed_path(entry.path()) else {
+            warn!("Failed to get the asset key of '{}'", entry.path().display());
             continue;
         };
-        let asset_key = AssetKey::new(relative_path);
+        if !visited_assets.insert(asset_key.clone()) {
+            continue;
+        }
 
         // We are only interested in files with registered extensions.
-        let extension = if let Ok(extension) = extract_extension_from_path(asset_key.as_path()) {
-            if !extensions.contains(&extension) {
+        let (extension, version) = if let Ok(extension) = extract_extension_from_path(asset_key.as_path()) {
+            let Some(version) = versions.get(&extension).cloned() else {
                 continue;
-            }
-            extension
+            };
+            (extension, version)
         } else {
             info!("Failed to extract extension from path: {:?}", asset_key.as_path());
             continue;
         };
 
         // Check if the processed asset exists.
-        let processed_asset_path = directories.processed_assets_path().join(asset_key.as_path());
+        let processed_asset_path = directories.processed_asset_path(&asset_key)?;
         if !processed_asset_path.exists() {
             info!("Asset is going to be processed because it doesn't exist yet: {processed_asset_path:?}");
             inventory
@@ -413,28 +870,45 @@ fn run_inventory(
         }
 
         // Check if the processed asset is outdated.
-        let Some(unprocessed_modified) = modified_system_time(asset_key.as_path()) else {
-            continue;
-        };
-        let Some(processed_modified) = modified_system_time(&processed_asset_path) else {
-            continue;
-        };
-        if processed_modified < unprocessed_modified {
+        let up_to_date_state =
+            ProcessingState::read(&processed_asset_path).filter(|processing_state| processing_state.is_up_to_date(version, entry.path()));
+        let Some(processing_state) = up_to_date_state else {
             info!("Asset is going to be processed because it is outdated: {processed_asset_path:?}");
             inventory
                 .entry(extension)
                 .or_insert_with(Vec::new)
                 .push(asset_key.as_path().to_owned());
             continue;
-        }
+        };
 
         trace!("Asset doesn't need to be processed: {}", asset_key.as_path().display());
+        up_to_date_assets.push((
+            asset_key,
+            entry.path().to_owned(),
+            processed_asset_path,
+            processing_state.content_hash,
+        ));
     }
 
+    // The manifest is brought in sync with the assets that changed while the AssetProcessor wasn't running.
+    update_manifest(manifest, directories.processed_assets_path(), |manifest| {
+        manifest.retain(|entry| {
+            directories
+                .unprocessed_asset_path(&entry.asset_key)
+                .is_ok_and(|unprocessed_asset_path| unprocessed_asset_path.is_file())
+        });
+        for (asset_key, unprocessed_asset_path, processed_asset_path, content_hash) in up_to_date_assets {
+            match manifest_entry(&asset_key, &unprocessed_asset_path, &processed_asset_path, content_hash) {
+                Some(manifest_entry) => manifest.insert(manifest_entry),
+                None => warn!("Failed to create the manifest entry of asset '{asset_key}'"),
+            }
+        }
+    });
+
     trace!("Found {} assets to process", inventory.len());
     for (_, asset_paths) in inventory {
         for asset_path in asset_paths {
-            process(&AssetKey::new(asset_path), directories, sender, processors)?;
+            process(&AssetKey::new(asset_path), directories, sender, processors, progress_reporter)?;
         }
     }
 
@@ -447,6 +921,7 @@ pub struct AssetBuilder {
     unprocessed_asset_path: PathBuf,
     processed_asset_path: PathBuf,
     relative_content_file_path: Option<PathBuf>,
+    dependencies: Vec<PathBuf>,
 }
 
 impl AssetBuilder {
@@ -461,6 +936,7 @@ impl AssetBuilder {
             unprocessed_asset_path: unprocessed_asset_path.into(),
             processed_asset_path: processed_asset_path.into(),
             relative_content_file_path: None,
+            dependencies: Vec::new(),
         }
     }
 
@@ -488,6 +964,26 @@ impl AssetBuilder {
         self
     }
 
+    /// Reads the processing settings of the asset from its YAML [sidecar file](crate::common::sidecar_path).
+    /// The default settings are returned when the asset has no sidecar file.
+    pub fn settings<T: DeserializeOwned + Default>(&self) -> Result<T> {
+        let sidecar_path = sidecar_path(&self.unprocessed_asset_path);
+        if !sidecar_path.exists() {
+            return Ok(T::default());
+        }
+        let content = fs::read_to_string(&sidecar_path)?;
+        if content.trim().is_empty() {
+            return Ok(T::default());
+        }
+        serde_yaml::from_str(&content).map_err(|err| Error::FailedDeserialization(Box::new(err)))
+    }
+
+    /// Adds a file on which the asset depends. The asset is processed again when the file changes.
+    pub fn with_dependency(&mut self, file_path: impl Into<PathBuf>) -> &mut Self {
+        self.dependencies.push(file_path.into());
+        self
+    }
+
     /// Builds the asset by creating the asset meta file.
     fn build(self) -> io::Result<()> {
         let content_file_path = self.relative_content_file_path.expect("content file path not set");
@@ -502,13 +998,24 @@ mod tests {
     use std::{
         fs,
         path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
         time::Duration,
     };
 
     use jeriya_test::setup_logger;
     use tempdir::TempDir;
 
-    use crate::{asset_processor::Event, common::Directories};
+    use serde::Deserialize;
+
+    use crate::{
+        asset_processor::{Event, Progress, ProgressState},
+        common::{sidecar_path, AssetKey, Directories},
+        manifest::Manifest,
+        Error,
+    };
 
     use super::AssetProcessor;
 
@@ -610,4 +1117,308 @@ mod tests {
         let meta_file_content = fs::read_to_string(&asset_meta_file_path).unwrap();
         assert_eq!(meta_file_content, "file: test.bin");
     }
+
+    #[test]
+    fn unchanged_asset_skipped() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+
+        // Create a sample asset to be processed.
+        let asset_path = create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello World!");
+
+        // Expect the Processed event from the inventory.
+        let mut asset_processor = setup_dummy_txt_process_configuration(AssetProcessor::new(&directories, 4).unwrap());
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+        drop(asset_processor);
+
+        // The asset didn't change and is processed with the same version
+        let mut asset_processor = setup_dummy_txt_process_configuration(AssetProcessor::new(&directories, 4).unwrap());
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+        assert!(observer_channel.recv_timeout(Duration::from_millis(1500)).is_err());
+        drop(asset_processor);
+
+        // A new processor version invalidates the processed asset
+        let mut asset_processor = AssetProcessor::new(&directories, 4).unwrap().register_with_version(
+            "txt",
+            1,
+            Box::new(|asset_builder| {
+                fs::write(asset_builder.processed_asset_path.join("test.bin"), "Version 1").unwrap();
+                asset_builder.with_file("test.bin");
+                Ok(())
+            }),
+        );
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+
+        let processed_asset_path = directories.processed_assets_path().join(&asset_path).join("test.bin");
+        assert_eq!(fs::read_to_string(&processed_asset_path).unwrap(), "Version 1");
+    }
+
+    #[test]
+    fn manifest_maintained() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+        let asset_path = create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello World!");
+
+        let mut asset_processor = setup_dummy_txt_process_configuration(AssetProcessor::new(&directories, 4).unwrap());
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+
+        let manifest = asset_processor.manifest();
+        assert_eq!(manifest.len(), 1);
+        let entry = manifest.get(&AssetKey::new(&asset_path)).unwrap();
+        assert_eq!(entry.extension, "txt");
+        assert_eq!(entry.size, "Hello Universe!".len() as u64);
+        assert_eq!(Manifest::read(directories.processed_assets_path()).unwrap(), manifest);
+        drop(asset_processor);
+
+        // The entry is removed when the asset was deleted while the AssetProcessor wasn't running
+        fs::remove_file(directories.unprocessed_assets_path().join(&asset_path)).unwrap();
+        let asset_processor = setup_dummy_txt_process_configuration(AssetProcessor::new(&directories, 4).unwrap());
+        assert_eq!(asset_processor.manifest().len(), 1);
+        asset_processor.set_active(true).unwrap();
+        assert!(asset_processor.manifest().is_empty());
+        assert!(Manifest::read(directories.processed_assets_path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn progress_reported() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+        let asset_path = create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello World!");
+
+        let mut asset_processor = setup_dummy_txt_process_configuration(AssetProcessor::new(&directories, 4).unwrap());
+        let progress_channel = asset_processor.observe_progress();
+        asset_processor.set_active(true).unwrap();
+
+        let asset_key = AssetKey::new(&asset_path);
+        let expected = [
+            (ProgressState::Queued, 1),
+            (ProgressState::Started, 1),
+            (ProgressState::Finished, 0),
+        ];
+        for (state, queue_len) in expected {
+            let progress = progress_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+            assert_eq!(
+                progress,
+                Progress {
+                    asset_key: asset_key.clone(),
+                    state,
+                    queue_len
+                }
+            );
+        }
+        assert_eq!(asset_processor.queue_len(), 0);
+    }
+
+    #[test]
+    fn progress_reported_on_failure() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+        create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello World!");
+
+        let mut asset_processor = AssetProcessor::new(&directories, 4).unwrap().register(
+            "txt",
+            Box::new(|asset_builder| Err(Error::InvalidAssetData(asset_builder.unprocessed_asset_path().to_owned()))),
+        );
+        let progress_channel = asset_processor.observe_progress();
+        asset_processor.set_active(true).unwrap();
+
+        // Skip the Queued and Started progress
+        for _ in 0..2 {
+            progress_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        }
+        let progress = progress_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert!(matches!(progress.state, ProgressState::Failed(_)));
+        assert_eq!(progress.queue_len, 0);
+    }
+
+    #[test]
+    fn failed_assets_reported_and_retried() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+        let asset_path = create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello World!");
+
+        // Setup the AssetProcessor with a processor that fails until it is fixed.
+        let fixed = Arc::new(AtomicBool::new(false));
+        let fixed2 = fixed.clone();
+        let mut asset_processor = AssetProcessor::new(&directories, 4).unwrap().register(
+            "txt",
+            Box::new(move |asset_builder| {
+                if !fixed2.load(Ordering::SeqCst) {
+                    return Err(Error::InvalidAssetData(asset_builder.unprocessed_asset_path().to_owned()));
+                }
+                fs::write(asset_builder.processed_asset_path.join("test.bin"), "Fixed").unwrap();
+                asset_builder.with_file("test.bin");
+                Ok(())
+            }),
+        );
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+
+        // Expect the failure to be reported.
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+        let failed_assets = asset_processor.failed_assets();
+        assert_eq!(failed_assets.len(), 1);
+        assert_eq!(failed_assets[0].asset_key, AssetKey::new(&asset_path));
+        assert!(failed_assets[0].error.contains("Failed to read the asset"));
+
+        // Retrying after the fix removes the report.
+        fixed.store(true, Ordering::SeqCst);
+        asset_processor.retry_failed_assets().unwrap();
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+        assert!(asset_processor.failed_assets().is_empty());
+    }
+
+    #[test]
+    fn sidecar_settings() {
+        setup_logger();
+
+        #[derive(Default, Deserialize)]
+        #[serde(default)]
+        struct Settings {
+            greeting: Option<String>,
+        }
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+        let asset_path = create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello World!");
+        let sidecar_path = sidecar_path(&directories.unprocessed_assets_path().join(&asset_path));
+        fs::write(&sidecar_path, "greeting: Hi").unwrap();
+
+        // Setup the AssetProcessor with a processor that replaces the greeting from the sidecar file.
+        let mut asset_processor = AssetProcessor::new(&directories, 4).unwrap().register(
+            "txt",
+            Box::new(|asset_builder| {
+                let settings = asset_builder.settings::<Settings>()?;
+                let content = fs::read_to_string(asset_builder.unprocessed_asset_path()).unwrap();
+                let processed_content = match settings.greeting {
+                    Some(greeting) => content.replace("Hello", &greeting),
+                    None => content,
+                };
+                fs::write(asset_builder.processed_asset_path.join("test.bin"), processed_content).unwrap();
+                asset_builder.with_file("test.bin");
+                Ok(())
+            }),
+        );
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+
+        // Expect the Processed event from the inventory.
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+        let processed_asset_path = directories.processed_assets_path().join(&asset_path).join("test.bin");
+        assert_eq!(fs::read_to_string(&processed_asset_path).unwrap(), "Hi World!");
+
+        // Updating the sidecar file processes the asset again.
+        fs::write(&sidecar_path, "greeting: Goodbye").unwrap();
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+        assert_eq!(fs::read_to_string(&processed_asset_path).unwrap(), "Goodbye World!");
+    }
+
+    #[test]
+    fn dependency_modified() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories =
+            Directories::create_all_dir(root.path().to_owned().join("unprocessed"), root.path().to_owned().join("processed")).unwrap();
+
+        // Create a sample asset and the file it depends on.
+        const DEPENDENCY_PATH: &str = "dependency.inc";
+        let dependency_path = directories.unprocessed_assets_path().join(DEPENDENCY_PATH);
+        fs::write(&dependency_path, "World").unwrap();
+        let asset_path = create_unprocessed_asset(directories.unprocessed_assets_path(), "Hello ");
+
+        // Setup the AssetProcessor with a processor that appends the content of the dependency.
+        let mut asset_processor = AssetProcessor::new(&directories, 4).unwrap().register(
+            "txt",
+            Box::new(move |asset_builder| {
+                let content = fs::read_to_string(asset_builder.unprocessed_asset_path()).unwrap();
+                let dependency_content = fs::read_to_string(&dependency_path).unwrap();
+                fs::write(asset_builder.processed_asset_path.join("test.bin"), content + &dependency_content).unwrap();
+                asset_builder.with_file("test.bin").with_dependency(&dependency_path);
+                Ok(())
+            }),
+        );
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+
+        // Expect the Processed event from the inventory.
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+
+        // Update the dependency to trigger the processing of the dependent asset.
+        fs::write(directories.unprocessed_assets_path().join(DEPENDENCY_PATH), "Universe").unwrap();
+
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_path.clone()));
+
+        let processed_asset_path = directories.processed_assets_path().join(&asset_path).join("test.bin");
+        let processed_content = fs::read_to_string(&processed_asset_path).unwrap();
+        assert_eq!(processed_content, "Hello Universe");
+    }
+
+    #[test]
+    fn mount_point() {
+        setup_logger();
+
+        let root = TempDir::new("root").unwrap();
+        let directories = Directories::create_all_dir(root.path().join("unprocessed"), root.path().join("processed"))
+            .unwrap()
+            .mount(
+                "engine",
+                root.path().join("engine_unprocessed"),
+                root.path().join("engine_processed"),
+            )
+            .unwrap();
+        let mount_point = &directories.mount_points()["engine"];
+        create_unprocessed_asset(mount_point.unprocessed_assets_path(), "Hello World!");
+
+        let mut asset_processor = setup_dummy_txt_process_configuration(AssetProcessor::new(&directories, 4).unwrap());
+        let observer_channel = asset_processor.observe();
+        asset_processor.set_active(true).unwrap();
+
+        // Expect the Processed event from the inventory.
+        let asset_key = AssetKey::mounted("engine", ASSET_PATH);
+        let event = observer_channel.recv_timeout(Duration::from_millis(1500)).unwrap();
+        assert_eq!(event, Event::Processed(asset_key.as_path().to_owned()));
+
+        let processed_asset_path = mount_point.processed_assets_path().join(ASSET_PATH).join("test.bin");
+        assert_eq!(fs::read_to_string(&processed_asset_path).unwrap(), "Hello Universe!");
+        assert!(!directories.processed_assets_path().join(ASSET_PATH).exists());
+        assert!(asset_processor.manifest().get(&asset_key).is_some());
+
+        assert!(matches!(
+            directories.unprocessed_asset_path(&AssetKey::new("game://test.txt")),
+            Err(Error::UnknownMountPoint(name)) if name == "game"
+        ));
+    }
 }
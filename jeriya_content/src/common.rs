@@ -1,74 +1,205 @@
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     fmt::{self, Formatter},
-    fs, io,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
-    time::SystemTime,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
-use jeriya_shared::log::trace;
+use jeriya_shared::{
+    bitflags::bitflags,
+    byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
+    log::trace,
+};
 use serde::{Deserialize, Serialize};
 
 pub const ASSET_META_FILE_NAME: &str = "asset.yaml";
 
+/// Separates the name of the mount point from the path in an [`AssetKey`], e.g. `engine://shaders/color.vert`.
+pub const MOUNT_POINT_SEPARATOR: &str = "://";
+
+/// Extension of the YAML sidecar file next to an unprocessed asset that configures how the asset is processed, e.g. `suzanne.glb.meta`.
+pub const SIDECAR_EXTENSION: &str = "meta";
+
+/// Returns a number that is unique within the process. Together with the process id it names temporary files that
+/// don't collide with parallel processing jobs.
+pub(crate) fn unique_id() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Size of the header that is written by [`write_format_header`]
+pub(crate) const FORMAT_HEADER_SIZE: u64 = 8;
+
+/// Writes the `magic` bytes that identify the format of a processed asset file followed by the `version` of the format.
+pub(crate) fn write_format_header<W: Write>(mut writer: W, magic: &[u8; 4], version: u32) -> io::Result<()> {
+    writer.write_all(magic)?;
+    writer.write_u32::<LittleEndian>(version)
+}
+
+/// Reads the version of the format from a header that was written by [`write_format_header`].
+///
+/// Files that were written before the formats were versioned don't start with the `magic` bytes. In this case,
+/// version 0 is returned and the reader is left at its original position.
+pub(crate) fn read_format_version<R: Read + Seek>(mut reader: R, magic: &[u8; 4]) -> io::Result<u32> {
+    let start = reader.stream_position()?;
+    let mut found_magic = [0; 4];
+    if reader.read_exact(&mut found_magic).is_ok() && &found_magic == magic {
+        return reader.read_u32::<LittleEndian>();
+    }
+    reader.seek(SeekFrom::Start(start))?;
+    Ok(0)
+}
+
+bitflags! {
+    /// Flags that follow the format header of processed assets and describe how the content is stored
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FormatFlags: u32 {
+        /// The content is compressed with zstd
+        const ZSTD = 0x00000001;
+    }
+}
+
+/// Size of the [`FormatFlags`] that are written by [`write_format_flags`]
+pub(crate) const FORMAT_FLAGS_SIZE: u64 = 4;
+
+/// Writes the [`FormatFlags`] that follow the format header.
+pub(crate) fn write_format_flags<W: Write>(mut writer: W, flags: FormatFlags) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(flags.bits())
+}
+
+/// Reads the [`FormatFlags`] that were written by [`write_format_flags`] and rejects unknown flags.
+pub(crate) fn read_format_flags<R: Read>(mut reader: R) -> crate::Result<FormatFlags> {
+    let bits = reader.read_u32::<LittleEndian>()?;
+    FormatFlags::from_bits(bits).ok_or_else(|| crate::Error::Other(format!("Unknown format flags {bits:#010x}").into()))
+}
+
+/// Returns the [`FormatFlags`] for content that is compressed with the given zstd level or not compressed at all.
+pub(crate) fn compression_flags(zstd_level: Option<i32>) -> FormatFlags {
+    match zstd_level {
+        Some(_) => FormatFlags::ZSTD,
+        None => FormatFlags::empty(),
+    }
+}
+
 /// Directories that are used by the [`AssetProcessor`].
+///
+/// [`AssetKey`]s without a mount point are resolved relative to the default directories. Additional pairs of
+/// directories can be registered with [`Directories::mount`] so that, for example, the builtin shaders of the engine
+/// and the content of a project can live in different locations. [`AssetKey`]s that start with the name of the mount
+/// point, e.g. `engine://shaders/color.vert`, are resolved relative to the directories of that mount point.
 #[derive(Debug, Clone)]
 pub struct Directories {
     unprocessed_assets_path: PathBuf,
     processed_assets_path: PathBuf,
+    mount_points: BTreeMap<String, MountPoint>,
+}
+
+/// Pair of directories that is registered with [`Directories::mount`].
+#[derive(Debug, Clone)]
+pub struct MountPoint {
+    unprocessed_assets_path: PathBuf,
+    processed_assets_path: PathBuf,
+}
+
+impl MountPoint {
+    /// Returns the path to the directory where the unprocessed assets of the mount point are located.
+    pub fn unprocessed_assets_path(&self) -> &Path {
+        &self.unprocessed_assets_path
+    }
+
+    /// Returns the path to the directory where the processed assets of the mount point are located.
+    pub fn processed_assets_path(&self) -> &Path {
+        &self.processed_assets_path
+    }
+}
+
+/// Creates the directory and returns its canonicalized path.
+fn create_and_canonicalize(path: &Path) -> io::Result<PathBuf> {
+    trace!("Creating directory: {path:?}");
+    fs::create_dir_all(path)?;
+    path.canonicalize()
+}
+
+/// Returns an error when the directory doesn't exist.
+fn check_directory(path: &Path, description: &str) -> crate::Result<()> {
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Directory for {description} '{}' does not exist", path.display()),
+        )
+        .into());
+    }
+    Ok(())
 }
 
 impl Directories {
     /// Creates the directories that are used by the [`AssetProcessor`].
     pub fn create_all_dir(unprocessed_assets_path: impl AsRef<Path>, processed_assets_path: impl AsRef<Path>) -> io::Result<Directories> {
-        trace!("Creating directory for unprocessed assets: {:?}", unprocessed_assets_path.as_ref());
-        fs::create_dir_all(&unprocessed_assets_path)?;
-        trace!("Creating directory for processed assets: {:?}", processed_assets_path.as_ref());
-        fs::create_dir_all(&processed_assets_path)?;
-        let unprocessed_assets_path = unprocessed_assets_path
-            .as_ref()
-            .canonicalize()
-            .expect("failed to canonicalize path to the unprocessed assets")
-            .to_path_buf();
-        let processed_assets_path = processed_assets_path
-            .as_ref()
-            .canonicalize()
-            .expect("failed to canonicalize path to the processed assets")
-            .to_path_buf();
+        let unprocessed_assets_path = create_and_canonicalize(unprocessed_assets_path.as_ref())?;
+        let processed_assets_path = create_and_canonicalize(processed_assets_path.as_ref())?;
         let result = Self {
             unprocessed_assets_path,
             processed_assets_path,
+            mount_points: BTreeMap::new(),
         };
         assert!(result.check().is_ok());
         Ok(result)
     }
 
+    /// Creates the directories of the mount point with the given `name` and registers them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jeriya_content::common::{AssetKey, Directories};
+    /// let directories = Directories::create_all_dir("game_unprocessed", "game_processed")
+    ///     .unwrap()
+    ///     .mount("engine", "engine_unprocessed", "engine_processed")
+    ///     .unwrap();
+    /// let asset_key = AssetKey::new("engine://shaders/color.vert");
+    /// let path = directories.unprocessed_asset_path(&asset_key).unwrap();
+    /// assert!(path.ends_with("engine_unprocessed/shaders/color.vert"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If a mount point with the same `name` is already registered.
+    pub fn mount(
+        mut self,
+        name: impl Into<String>,
+        unprocessed_assets_path: impl AsRef<Path>,
+        processed_assets_path: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let name = name.into();
+        assert!(!self.mount_points.contains_key(&name), "mount point '{name}' is already registered");
+        let mount_point = MountPoint {
+            unprocessed_assets_path: create_and_canonicalize(unprocessed_assets_path.as_ref())?,
+            processed_assets_path: create_and_canonicalize(processed_assets_path.as_ref())?,
+        };
+        self.mount_points.insert(name, mount_point);
+        Ok(self)
+    }
+
     /// Returns `true` if the directories exist.
     pub fn exist(&self) -> bool {
-        self.unprocessed_assets_path.exists() && self.processed_assets_path.exists()
+        self.unprocessed_assets_path.exists()
+            && self.processed_assets_path.exists()
+            && self
+                .mount_points
+                .values()
+                .all(|mount_point| mount_point.unprocessed_assets_path.exists() && mount_point.processed_assets_path.exists())
     }
 
     /// Assets that the directories exist and returns a specific error if they don't.
     pub fn check(&self) -> crate::Result<()> {
-        if !self.processed_assets_path.exists() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                format!(
-                    "Directory for processed assets '{}' does not exist",
-                    self.processed_assets_path.display()
-                ),
-            )
-            .into());
-        }
-        if !self.unprocessed_assets_path.exists() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                format!(
-                    "Directory for unprocessed assets '{}' does not exist",
-                    self.unprocessed_assets_path.display()
-                ),
-            )
-            .into());
+        check_directory(&self.processed_assets_path, "processed assets")?;
+        check_directory(&self.unprocessed_assets_path, "unprocessed assets")?;
+        for mount_point in self.mount_points.values() {
+            check_directory(&mount_point.processed_assets_path, "processed assets")?;
+            check_directory(&mount_point.unprocessed_assets_path, "unprocessed assets")?;
         }
         Ok(())
     }
@@ -82,6 +213,68 @@ impl Directories {
     pub fn processed_assets_path(&self) -> &Path {
         &self.processed_assets_path
     }
+
+    /// Returns the registered mount points by their name.
+    pub fn mount_points(&self) -> &BTreeMap<String, MountPoint> {
+        &self.mount_points
+    }
+
+    /// Returns the path of the unprocessed asset with the given `asset_key`.
+    pub fn unprocessed_asset_path(&self, asset_key: &AssetKey) -> crate::Result<PathBuf> {
+        let (unprocessed_assets_path, _) = self.roots_of(asset_key)?;
+        Ok(unprocessed_assets_path.join(asset_key.path_in_mount_point()))
+    }
+
+    /// Returns the path of the directory in which the processed asset with the given `asset_key` is located.
+    pub fn processed_asset_path(&self, asset_key: &AssetKey) -> crate::Result<PathBuf> {
+        let (_, processed_assets_path) = self.roots_of(asset_key)?;
+        Ok(processed_assets_path.join(asset_key.path_in_mount_point()))
+    }
+
+    /// Returns the [`AssetKey`] of the unprocessed asset at the given absolute path or `None` when the path is not
+    /// located in one of the unprocessed assets directories. When the directories are nested, the innermost one wins.
+    pub fn asset_key_of_unprocessed_path(&self, absolute_path: &Path) -> Option<AssetKey> {
+        self.unprocessed_roots()
+            .filter_map(|(mount_point, root)| Some((mount_point, root, absolute_path.strip_prefix(root).ok()?)))
+            .max_by_key(|(_, root, _)| root.components().count())
+            .map(|(mount_point, _, path)| match mount_point {
+                Some(mount_point) => AssetKey::mounted(mount_point, path),
+                None => AssetKey::new(path),
+            })
+    }
+
+    /// Returns the unprocessed assets directories together with the name of their mount point. The default
+    /// directory has no mount point.
+    pub fn unprocessed_roots(&self) -> impl Iterator<Item = (Option<&str>, &Path)> {
+        std::iter::once((None, self.unprocessed_assets_path.as_path())).chain(
+            self.mount_points
+                .iter()
+                .map(|(name, mount_point)| (Some(name.as_str()), mount_point.unprocessed_assets_path.as_path())),
+        )
+    }
+
+    /// Returns the processed assets directories including the ones of the mount points.
+    pub fn processed_roots(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.processed_assets_path.as_path()).chain(
+            self.mount_points
+                .values()
+                .map(|mount_point| mount_point.processed_assets_path.as_path()),
+        )
+    }
+
+    /// Returns the unprocessed and processed assets directories in which the asset is located.
+    fn roots_of(&self, asset_key: &AssetKey) -> crate::Result<(&Path, &Path)> {
+        match asset_key.mount_point() {
+            Some(name) => {
+                let mount_point = self
+                    .mount_points
+                    .get(&*name)
+                    .ok_or_else(|| crate::Error::UnknownMountPoint(name.into_owned()))?;
+                Ok((&mount_point.unprocessed_assets_path, &mount_point.processed_assets_path))
+            }
+            None => Ok((&self.unprocessed_assets_path, &self.processed_assets_path)),
+        }
+    }
 }
 
 /// Identifies the asset. It's a relative path in the asset directory.
@@ -102,6 +295,58 @@ impl AssetKey {
         Self(path.into())
     }
 
+    /// Create a new [`AssetKey`] for the asset at the given `path` in the mount point with the given `name`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jeriya_content::common::AssetKey;
+    /// let asset_key = AssetKey::mounted("engine", "shaders/color.vert");
+    /// assert_eq!(asset_key.as_str(), "engine://shaders/color.vert");
+    /// ```
+    pub fn mounted(name: &str, path: impl AsRef<Path>) -> Self {
+        let path = path
+            .as_ref()
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Self::new(format!("{name}{MOUNT_POINT_SEPARATOR}{path}"))
+    }
+
+    /// Returns the name of the mount point or `None` when the asset is located in the default directories.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jeriya_content::common::AssetKey;
+    /// assert_eq!(AssetKey::new("engine://shaders/color.vert").mount_point().as_deref(), Some("engine"));
+    /// assert_eq!(AssetKey::new("models/suzanne.glb").mount_point(), None);
+    /// ```
+    pub fn mount_point(&self) -> Option<Cow<'_, str>> {
+        match self.as_str() {
+            Cow::Borrowed(key) => key.split_once(MOUNT_POINT_SEPARATOR).map(|(name, _)| Cow::Borrowed(name)),
+            Cow::Owned(key) => key.split_once(MOUNT_POINT_SEPARATOR).map(|(name, _)| Cow::Owned(name.to_owned())),
+        }
+    }
+
+    /// Returns the path of the asset relative to the directories of its mount point.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::path::Path;
+    /// use jeriya_content::common::AssetKey;
+    /// let asset_key = AssetKey::new("engine://shaders/color.vert");
+    /// assert_eq!(asset_key.path_in_mount_point(), Path::new("shaders/color.vert"));
+    /// ```
+    pub fn path_in_mount_point(&self) -> PathBuf {
+        match self.as_str().split_once(MOUNT_POINT_SEPARATOR) {
+            Some((_, path)) => PathBuf::from(path),
+            None => self.0.clone(),
+        }
+    }
+
     /// Returns the path of the asset.
     ///
     /// # Example
@@ -172,6 +417,46 @@ pub(crate) fn extract_file_name_from_path(path: &Path) -> crate::Result<String>
         .to_owned())
 }
 
-pub(crate) fn modified_system_time(path: &Path) -> Option<SystemTime> {
-    path.metadata().ok().and_then(|metadata| metadata.modified().ok())
+/// Computes the 64-bit FNV-1a hash of the file's content. In contrast to the hashers of the standard
+/// library, the result is stable across runs, platforms and compiler versions so that it can be persisted.
+pub(crate) fn content_hash(path: &Path) -> io::Result<u64> {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; 64 * 1024];
+    let mut hash = OFFSET_BASIS;
+    loop {
+        let len = file.read(&mut buffer)?;
+        if len == 0 {
+            return Ok(hash);
+        }
+        for byte in &buffer[..len] {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+}
+
+/// Returns the path of the sidecar file that configures the processing of the asset at the given path.
+///
+/// # Example
+///
+/// ```rust
+/// use std::path::Path;
+/// use jeriya_content::common::sidecar_path;
+/// assert_eq!(sidecar_path(Path::new("models/suzanne.glb")), Path::new("models/suzanne.glb.meta"));
+/// ```
+pub fn sidecar_path(asset_path: &Path) -> PathBuf {
+    let mut file_name = asset_path.file_name().unwrap_or_default().to_owned();
+    file_name.push(".");
+    file_name.push(SIDECAR_EXTENSION);
+    asset_path.with_file_name(file_name)
+}
+
+/// Returns the path of the asset when the given path is a sidecar file.
+pub(crate) fn asset_path_of_sidecar(path: &Path) -> Option<PathBuf> {
+    let is_sidecar = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(SIDECAR_EXTENSION));
+    is_sidecar.then(|| path.with_extension(""))
 }
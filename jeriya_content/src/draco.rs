@@ -0,0 +1,220 @@
+//! Decompression of glTF primitives that use the `KHR_draco_mesh_compression` extension
+//!
+//! The compressed primitives are decoded with the `draco_decoder` executable from the
+//! [Draco](https://github.com/google/draco) project which has to be available in the PATH.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+    process::{self, Command},
+};
+
+use jeriya_shared::serde_json::{self, Value};
+
+use crate::common::unique_id;
+
+/// Name of the glTF extension
+pub const EXTENSION_NAME: &str = "KHR_draco_mesh_compression";
+
+const GLB_MAGIC: &[u8] = b"glTF";
+
+/// Vertex data of a primitive that has been decoded from a Draco bitstream
+///
+/// The attributes are indexed by the same `indices`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodedPrimitive {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub texture_coordinates: Option<Vec<[f32; 2]>>,
+    pub indices: Vec<u32>,
+}
+
+/// Reads the JSON part of the glTF file at the given path. Supports .gltf and .glb files.
+pub fn read_json(path: &Path) -> crate::Result<Value> {
+    let bytes = fs::read(path)?;
+    let json = if bytes.starts_with(GLB_MAGIC) {
+        let glb = gltf::Glb::from_slice(&bytes).map_err(|err| crate::Error::Other(Box::new(err)))?;
+        serde_json::from_slice(&glb.json)
+    } else {
+        serde_json::from_slice(&bytes)
+    };
+    json.map_err(|err| crate::Error::FailedDeserialization(Box::new(err)))
+}
+
+/// Returns whether the glTF document uses Draco compressed primitives
+pub fn is_used(json: &Value) -> bool {
+    json["extensionsUsed"]
+        .as_array()
+        .is_some_and(|extensions| extensions.iter().any(|extension| extension == EXTENSION_NAME))
+}
+
+/// Returns the buffer views that contain the Draco bitstreams of the compressed primitives
+///
+/// The key is the index of the mesh and the index of the primitive in the mesh.
+pub fn compressed_primitives(json: &Value) -> BTreeMap<(usize, usize), usize> {
+    let mut buffer_views = BTreeMap::new();
+    let Some(meshes) = json["meshes"].as_array() else {
+        return buffer_views;
+    };
+    for (mesh_index, mesh) in meshes.iter().enumerate() {
+        let Some(primitives) = mesh["primitives"].as_array() else {
+            continue;
+        };
+        for (primitive_index, primitive) in primitives.iter().enumerate() {
+            if let Some(buffer_view) = primitive["extensions"][EXTENSION_NAME]["bufferView"].as_u64() {
+                buffer_views.insert((mesh_index, primitive_index), buffer_view as usize);
+            }
+        }
+    }
+    buffer_views
+}
+
+/// Decodes the Draco bitstream of a primitive
+pub fn decode(bitstream: &[u8]) -> crate::Result<DecodedPrimitive> {
+    // The decoder only works on files. The process id keeps parallel processing jobs apart.
+    let file_stem = format!("jeriya_draco_{}_{}", process::id(), unique_id());
+    let drc_path = std::env::temp_dir().join(format!("{file_stem}.drc"));
+    let obj_path = std::env::temp_dir().join(format!("{file_stem}.obj"));
+    fs::write(&drc_path, bitstream)?;
+    let result = run_decoder(&drc_path, &obj_path);
+    let _ = fs::remove_file(&drc_path);
+    let _ = fs::remove_file(&obj_path);
+    result
+}
+
+fn run_decoder(drc_path: &Path, obj_path: &Path) -> crate::Result<DecodedPrimitive> {
+    let output = Command::new("draco_decoder")
+        .arg("-i")
+        .arg(drc_path)
+        .arg("-o")
+        .arg(obj_path)
+        .output()
+        .map_err(|error| crate::Error::FailedToDecompressMesh(format!("Could not execute draco_decoder: {error}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr).map_err(crate::Error::Utf8Error)?;
+        return Err(crate::Error::FailedToDecompressMesh(format!(
+            "draco_decoder exited with exit code {:?}:\n{stderr}",
+            output.status.code()
+        )));
+    }
+    parse_obj(&fs::read_to_string(obj_path)?)
+}
+
+/// Parses the OBJ file that is written by the decoder
+///
+/// OBJ files index every attribute separately. Every distinct combination of indices becomes a vertex.
+fn parse_obj(obj: &str) -> crate::Result<DecodedPrimitive> {
+    fn parse_floats<const N: usize>(values: &mut std::str::SplitWhitespace) -> crate::Result<[f32; N]> {
+        let mut result = [0.0; N];
+        for value in result.iter_mut() {
+            *value = values
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| crate::Error::FailedToDecompressMesh("Invalid vertex attribute in decoded mesh".to_owned()))?;
+        }
+        Ok(result)
+    }
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut texture_coordinates = Vec::new();
+    let mut vertices = HashMap::new();
+    let mut decoded = DecodedPrimitive::default();
+    let mut decoded_texture_coordinates = Vec::new();
+
+    for line in obj.lines() {
+        let mut values = line.split_whitespace();
+        match values.next() {
+            Some("v") => positions.push(parse_floats::<3>(&mut values)?),
+            Some("vn") => normals.push(parse_floats::<3>(&mut values)?),
+            Some("vt") => texture_coordinates.push(parse_floats::<2>(&mut values)?),
+            Some("f") => {
+                let corners = values.collect::<Vec<_>>();
+                if corners.len() != 3 {
+                    return Err(crate::Error::FailedToDecompressMesh("Only triangles are supported".to_owned()));
+                }
+                for corner in corners {
+                    // Format: position/texture_coordinate/normal where the texture coordinate and normal are optional
+                    let mut corner_indices = corner.split('/').map(|index| index.parse::<usize>().ok());
+                    let position = corner_indices.next().flatten();
+                    let texture_coordinate = corner_indices.next().flatten();
+                    let normal = corner_indices.next().flatten();
+                    let key = (position, texture_coordinate, normal);
+                    let next_index = decoded.positions.len() as u32;
+                    let index = *vertices.entry(key).or_insert(next_index);
+                    if index == next_index {
+                        let get = |values: &[[f32; 3]], index: Option<usize>| {
+                            index
+                                .and_then(|index| values.get(index.wrapping_sub(1)).copied())
+                                .ok_or_else(|| crate::Error::FailedToDecompressMesh("Invalid index in decoded mesh".to_owned()))
+                        };
+                        decoded.positions.push(get(&positions, position)?);
+                        decoded
+                            .normals
+                            .push(get(&normals, normal).map_err(|_| crate::model::Error::NoVertexNormals)?);
+                        if let Some(texture_coordinate) = texture_coordinate {
+                            let texture_coordinate = texture_coordinates
+                                .get(texture_coordinate.wrapping_sub(1))
+                                .copied()
+                                .ok_or_else(|| crate::Error::FailedToDecompressMesh("Invalid index in decoded mesh".to_owned()))?;
+                            decoded_texture_coordinates.push(texture_coordinate);
+                        }
+                    }
+                    decoded.indices.push(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if decoded_texture_coordinates.len() == decoded.positions.len() && !decoded_texture_coordinates.is_empty() {
+        decoded.texture_coordinates = Some(decoded_texture_coordinates);
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_shared::serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn compressed_primitives_from_json() {
+        let json = json!({
+            "extensionsUsed": [EXTENSION_NAME],
+            "meshes": [
+                { "primitives": [{ "attributes": {} }] },
+                { "primitives": [{ "attributes": {}, "extensions": { EXTENSION_NAME: { "bufferView": 3, "attributes": {} } } }] }
+            ]
+        });
+        assert!(is_used(&json));
+        assert_eq!(compressed_primitives(&json), BTreeMap::from([((1, 0), 3)]));
+    }
+
+    #[test]
+    fn parse_obj_shares_vertices() {
+        let obj = "\
+            v 0 0 0\n\
+            v 1 0 0\n\
+            v 0 1 0\n\
+            v 1 1 0\n\
+            vt 0 0\n\
+            vt 1 1\n\
+            vn 0 0 1\n\
+            f 1/1/1 2/1/1 3/1/1\n\
+            f 2/1/1 4/2/1 3/1/1\n";
+        let decoded = parse_obj(obj).unwrap();
+        assert_eq!(decoded.positions.len(), 4);
+        assert_eq!(decoded.normals, vec![[0.0, 0.0, 1.0]; 4]);
+        assert_eq!(decoded.indices, vec![0, 1, 2, 1, 3, 2]);
+        assert_eq!(decoded.texture_coordinates.unwrap()[3], [1.0, 1.0]);
+    }
+
+    #[test]
+    fn parse_obj_without_normals() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        assert!(parse_obj(obj).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Cursor, Write},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use jeriya_shared::{log::info, nalgebra::Vector2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_processor::AssetBuilder,
+    common::{read_format_version, write_format_header},
+};
+
+/// Identifies the files that are written by [`process_font`]
+const FONT_MAGIC: &[u8; 4] = b"JFNT";
+
+/// Version of the format that is written by [`process_font`]
+pub const FONT_FORMAT_VERSION: u32 = 1;
+
+/// Character that is rendered in place of characters that are not part of the atlas
+pub const REPLACEMENT_CHARACTER: char = '?';
+
+/// Texels that are left empty between two glyphs in the atlas so that bilinear filtering doesn't bleed into the neighbours
+const GLYPH_PADDING: usize = 1;
+
+/// Settings for [`process_font`] that are read from the sidecar file of the font
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontProcessingSettings {
+    /// Size of an em in texels at which the glyphs are rasterized
+    pub glyph_size: u32,
+    /// Distance in texels from the outline of a glyph at which the signed distance field is saturated
+    pub spread: u32,
+    /// Characters that are baked into the atlas
+    pub characters: String,
+}
+
+impl Default for FontProcessingSettings {
+    fn default() -> Self {
+        Self {
+            glyph_size: 48,
+            spread: 6,
+            characters: (' '..='~').collect(),
+        }
+    }
+}
+
+/// Placement of a single glyph in the atlas of a [`FontAsset`]. All metrics are in em units with the y-axis pointing up.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Glyph {
+    /// Texture coordinates of the upper left corner of the glyph in the atlas
+    pub uv_min: Vector2<f32>,
+    /// Texture coordinates of the lower right corner of the glyph in the atlas
+    pub uv_max: Vector2<f32>,
+    /// Size of the quad that displays the glyph including the spread of the distance field
+    pub size: Vector2<f32>,
+    /// Offset of the lower left corner of the quad from the pen position on the baseline
+    pub offset: Vector2<f32>,
+    /// Distance by which the pen is moved after the glyph
+    pub advance: f32,
+}
+
+/// Quad of a single glyph that is returned by [`FontAsset::layout`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphQuad {
+    /// Lower left corner of the quad in em units
+    pub min: Vector2<f32>,
+    /// Upper right corner of the quad in em units
+    pub max: Vector2<f32>,
+    /// Texture coordinates of the upper left corner
+    pub uv_min: Vector2<f32>,
+    /// Texture coordinates of the lower right corner
+    pub uv_max: Vector2<f32>,
+}
+
+/// Identifies a [`FontAsset`] for the lifetime of the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FontId(u64);
+
+impl FontId {
+    /// Returns a new `FontId` that hasn't been returned before
+    fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Font that has been baked into an atlas of signed distance fields.
+///
+/// The atlas stores one byte per texel. A value of 128 lies on the outline of a glyph, larger values lie inside.
+///
+/// Every font gets a new [`FontId`] when it is created, cloned or deserialized so that renderers can cache its atlas.
+/// The id isn't considered when fonts are compared.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FontAsset {
+    #[serde(skip, default = "FontId::next")]
+    id: FontId,
+    pub atlas_width: u32,
+    pub atlas_height: u32,
+    pub atlas: Vec<u8>,
+    pub glyphs: BTreeMap<char, Glyph>,
+    /// Distance from the baseline to the top of the highest glyph in em units
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the lowest glyph in em units. Usually negative.
+    pub descent: f32,
+    /// Distance between the baselines of two lines in em units
+    pub line_height: f32,
+}
+
+impl FontAsset {
+    /// Creates a new `FontAsset` from an atlas that has already been baked
+    pub fn new(
+        atlas_width: u32,
+        atlas_height: u32,
+        atlas: Vec<u8>,
+        glyphs: BTreeMap<char, Glyph>,
+        ascent: f32,
+        descent: f32,
+        line_height: f32,
+    ) -> Self {
+        Self {
+            id: FontId::next(),
+            atlas_width,
+            atlas_height,
+            atlas,
+            glyphs,
+            ascent,
+            descent,
+            line_height,
+        }
+    }
+
+    /// Returns the [`FontId`] of the font
+    pub fn id(&self) -> FontId {
+        self.id
+    }
+
+    /// Rasterizes the `settings.characters` of the TrueType or OpenType font in `font_data` and bakes them into an atlas
+    pub fn bake(font_data: &[u8], settings: &FontProcessingSettings) -> crate::Result<Self> {
+        let glyph_size = settings.glyph_size.max(1) as f32;
+        let spread = settings.spread as usize;
+        let font = fontdue::Font::from_bytes(
+            font_data,
+            fontdue::FontSettings {
+                scale: glyph_size,
+                ..Default::default()
+            },
+        )
+        .map_err(|err| crate::Error::Other(err.into()))?;
+
+        // Rasterize the glyphs and convert them into distance fields
+        let mut characters = settings.characters.chars().collect::<Vec<_>>();
+        if !characters.contains(&REPLACEMENT_CHARACTER) {
+            characters.push(REPLACEMENT_CHARACTER);
+        }
+        characters.sort_unstable();
+        characters.dedup();
+        let mut fields = characters
+            .iter()
+            .map(|&character| {
+                let (metrics, coverage) = font.rasterize(character, glyph_size);
+                let field = if metrics.width == 0 || metrics.height == 0 {
+                    None
+                } else {
+                    Some(signed_distance_field(&coverage, metrics.width, metrics.height, spread))
+                };
+                (character, metrics, field)
+            })
+            .collect::<Vec<_>>();
+
+        // Pack the glyphs into rows that are sorted by height
+        fields.sort_by_key(|(_, _, field)| std::cmp::Reverse(field.as_ref().map_or(0, |field| field.height)));
+        let area = fields
+            .iter()
+            .filter_map(|(_, _, field)| field.as_ref())
+            .map(|field| (field.width + GLYPH_PADDING) * (field.height + GLYPH_PADDING))
+            .sum::<usize>();
+        let widest = fields
+            .iter()
+            .filter_map(|(_, _, field)| field.as_ref())
+            .map(|field| field.width + GLYPH_PADDING)
+            .max()
+            .unwrap_or(1);
+        let atlas_width = ((area as f32).sqrt().ceil() as usize).max(widest).next_power_of_two();
+        let mut positions = Vec::with_capacity(fields.len());
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for (_, _, field) in &fields {
+            let Some(field) = field else {
+                positions.push((0, 0));
+                continue;
+            };
+            if x + field.width + GLYPH_PADDING > atlas_width {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+            positions.push((x, y));
+            x += field.width + GLYPH_PADDING;
+            row_height = row_height.max(field.height + GLYPH_PADDING);
+        }
+        let atlas_height = (y + row_height).max(1).next_power_of_two();
+
+        // Copy the distance fields into the atlas
+        let mut atlas = vec![0; atlas_width * atlas_height];
+        let mut glyphs = BTreeMap::new();
+        for ((character, metrics, field), (x, y)) in fields.iter().zip(positions) {
+            let advance = metrics.advance_width / glyph_size;
+            let glyph = match field {
+                Some(field) => {
+                    for row in 0..field.height {
+                        let target = (y + row) * atlas_width + x;
+                        atlas[target..target + field.width].copy_from_slice(&field.data[row * field.width..(row + 1) * field.width]);
+                    }
+                    Glyph {
+                        uv_min: Vector2::new(x as f32 / atlas_width as f32, y as f32 / atlas_height as f32),
+                        uv_max: Vector2::new(
+                            (x + field.width) as f32 / atlas_width as f32,
+                            (y + field.height) as f32 / atlas_height as f32,
+                        ),
+                        size: Vector2::new(field.width as f32, field.height as f32) / glyph_size,
+                        offset: Vector2::new(metrics.xmin as f32 - spread as f32, metrics.ymin as f32 - spread as f32) / glyph_size,
+                        advance,
+                    }
+                }
+                None => Glyph {
+                    uv_min: Vector2::zeros(),
+                    uv_max: Vector2::zeros(),
+                    size: Vector2::zeros(),
+                    offset: Vector2::zeros(),
+                    advance,
+                },
+            };
+            glyphs.insert(*character, glyph);
+        }
+
+        let line_metrics = font.horizontal_line_metrics(glyph_size);
+        Ok(Self::new(
+            atlas_width as u32,
+            atlas_height as u32,
+            atlas,
+            glyphs,
+            line_metrics.map_or(1.0, |metrics| metrics.ascent / glyph_size),
+            line_metrics.map_or(0.0, |metrics| metrics.descent / glyph_size),
+            line_metrics.map_or(1.2, |metrics| metrics.new_line_size / glyph_size),
+        ))
+    }
+
+    /// Returns the [`Glyph`] for the given character or the [`REPLACEMENT_CHARACTER`] when the character is not part of the atlas
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character).or_else(|| self.glyphs.get(&REPLACEMENT_CHARACTER))
+    }
+
+    /// Lays out the `text` starting with the baseline of the first line at the origin.
+    ///
+    /// The quads are in em units with the y-axis pointing up. Every `'\n'` starts a new line below the previous one.
+    pub fn layout(&self, text: &str) -> Vec<GlyphQuad> {
+        let mut quads = Vec::with_capacity(text.len());
+        let mut pen = Vector2::zeros();
+        for character in text.chars() {
+            if character == '\n' {
+                pen = Vector2::new(0.0, pen.y - self.line_height);
+                continue;
+            }
+            let Some(glyph) = self.glyph(character) else {
+                continue;
+            };
+            if glyph.size.x > 0.0 && glyph.size.y > 0.0 {
+                let min = pen + glyph.offset;
+                quads.push(GlyphQuad {
+                    min,
+                    max: min + glyph.size,
+                    uv_min: glyph.uv_min,
+                    uv_max: glyph.uv_max,
+                });
+            }
+            pen.x += glyph.advance;
+        }
+        quads
+    }
+
+    /// Serializes the font in the format that is read by [`import_font`]
+    pub fn serialize_into<W: Write>(&self, mut writer: W) -> crate::Result<()> {
+        write_format_header(&mut writer, FONT_MAGIC, FONT_FORMAT_VERSION)?;
+        bincode::serialize_into(writer, self).map_err(|err| crate::Error::FailedSerialization(err))
+    }
+}
+
+impl Clone for FontAsset {
+    fn clone(&self) -> Self {
+        Self::new(
+            self.atlas_width,
+            self.atlas_height,
+            self.atlas.clone(),
+            self.glyphs.clone(),
+            self.ascent,
+            self.descent,
+            self.line_height,
+        )
+    }
+}
+
+impl PartialEq for FontAsset {
+    fn eq(&self, other: &Self) -> bool {
+        self.atlas_width == other.atlas_width
+            && self.atlas_height == other.atlas_height
+            && self.atlas == other.atlas
+            && self.glyphs == other.glyphs
+            && self.ascent == other.ascent
+            && self.descent == other.descent
+            && self.line_height == other.line_height
+    }
+}
+
+/// Signed distance field of a single glyph that is returned by [`signed_distance_field`]
+struct DistanceField {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+/// Converts the `coverage` of a rasterized glyph into a signed distance field that is extended by `spread` texels on every side.
+///
+/// Texels with a coverage of at least 50% are considered to be inside the glyph. The distance to the nearest texel on the
+/// other side of the outline is searched within `spread` texels and mapped to the range 0..=255 with 128 on the outline.
+fn signed_distance_field(coverage: &[u8], width: usize, height: usize, spread: usize) -> DistanceField {
+    let inside = |x: isize, y: isize| {
+        x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && coverage[y as usize * width + x as usize] >= 128
+    };
+    let field_width = width + 2 * spread;
+    let field_height = height + 2 * spread;
+    let radius = spread as isize;
+    let mut data = Vec::with_capacity(field_width * field_height);
+    for field_y in 0..field_height as isize {
+        for field_x in 0..field_width as isize {
+            let (x, y) = (field_x - radius, field_y - radius);
+            let is_inside = inside(x, y);
+            let mut nearest_squared = f32::MAX;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if inside(x + dx, y + dy) != is_inside {
+                        nearest_squared = nearest_squared.min((dx * dx + dy * dy) as f32);
+                    }
+                }
+            }
+            // The outline lies halfway between the texel and its nearest neighbour on the other side
+            let distance = (nearest_squared.sqrt() - 0.5).min(spread as f32);
+            let signed_distance = if is_inside { distance } else { -distance };
+            let normalized = 0.5 + signed_distance / (2.0 * spread.max(1) as f32);
+            data.push((normalized.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    DistanceField {
+        width: field_width,
+        height: field_height,
+        data,
+    }
+}
+
+/// Function for the [`AssetProcessor`](crate::asset_processor::AssetProcessor) that bakes TrueType and OpenType fonts
+pub fn process_font(asset_builder: &mut AssetBuilder) -> crate::Result<()> {
+    let path = asset_builder.unprocessed_asset_path().to_owned();
+    let settings = asset_builder.settings::<FontProcessingSettings>()?;
+    let font_data = std::fs::read(&path)?;
+    let font = FontAsset::bake(&font_data, &settings)?;
+    info!(
+        "Baked {} glyphs of font '{}' into an atlas with {}x{} texels",
+        font.glyphs.len(),
+        path.display(),
+        font.atlas_width,
+        font.atlas_height
+    );
+    let file_name = "font.bin";
+    let mut writer = BufWriter::new(File::create(asset_builder.processed_asset_path().join(file_name))?);
+    font.serialize_into(&mut writer)?;
+    writer.flush()?;
+    asset_builder.with_file(file_name);
+    Ok(())
+}
+
+/// Function for the [`AssetImporter`](crate::asset_importer::AssetImporter) that deserializes a font written by [`process_font`]
+pub fn import_font(bytes: &[u8]) -> crate::Result<FontAsset> {
+    let mut reader = Cursor::new(bytes);
+    match read_format_version(&mut reader, FONT_MAGIC)? {
+        FONT_FORMAT_VERSION => bincode::deserialize_from(reader).map_err(|err| crate::Error::FailedDeserialization(err)),
+        found => Err(crate::Error::UnsupportedAssetVersion {
+            found,
+            expected: FONT_FORMAT_VERSION,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> FontAsset {
+        let glyph = |x: f32, advance: f32| Glyph {
+            uv_min: Vector2::new(x, 0.0),
+            uv_max: Vector2::new(x + 0.25, 0.5),
+            size: Vector2::new(0.5, 1.0),
+            offset: Vector2::new(0.0, -0.25),
+            advance,
+        };
+        let mut glyphs = BTreeMap::new();
+        glyphs.insert('A', glyph(0.0, 0.5));
+        glyphs.insert('?', glyph(0.5, 0.4));
+        glyphs.insert(
+            ' ',
+            Glyph {
+                uv_min: Vector2::zeros(),
+                uv_max: Vector2::zeros(),
+                size: Vector2::zeros(),
+                offset: Vector2::zeros(),
+                advance: 0.25,
+            },
+        );
+        FontAsset::new(4, 2, vec![0, 64, 128, 255, 255, 128, 64, 0], glyphs, 0.8, -0.2, 1.25)
+    }
+
+    #[test]
+    fn distance_field() {
+        // A single row of 4 covered texels
+        let field = signed_distance_field(&[255, 255, 255, 255], 4, 1, 2);
+        assert_eq!((field.width, field.height), (8, 5));
+        let center_row = &field.data[2 * field.width..3 * field.width];
+        // Texels on the outside get darker with the distance and texels on the inside are above the edge value
+        assert!(center_row[0] < center_row[1]);
+        assert!(center_row[1] < 128);
+        assert!(center_row[2] > 128);
+        assert_eq!(center_row[2], center_row[5]);
+        // The texels in the corners are at least the spread away from the glyph
+        assert_eq!(field.data[0], 0);
+    }
+
+    #[test]
+    fn layout() {
+        let font = font();
+        let quads = font.layout("A A\nÄ");
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[0].min, Vector2::new(0.0, -0.25));
+        assert_eq!(quads[0].max, Vector2::new(0.5, 0.75));
+        // The space only advances the pen
+        assert_eq!(quads[1].min, Vector2::new(0.75, -0.25));
+        // The unknown character on the next line is replaced
+        assert_eq!(quads[2].min, Vector2::new(0.0, -1.5));
+        assert_eq!(quads[2].uv_min, Vector2::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn serialize_and_deserialize() {
+        let font = font();
+        let mut buffer = Vec::new();
+        font.serialize_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[0..4], FONT_MAGIC);
+        assert_eq!(import_font(&buffer).unwrap(), font);
+    }
+
+    #[test]
+    fn ids_are_unique() {
+        let font = font();
+        let clone = font.clone();
+        assert_eq!(clone, font);
+        assert_ne!(clone.id(), font.id());
+        let mut buffer = Vec::new();
+        font.serialize_into(&mut buffer).unwrap();
+        assert_ne!(import_font(&buffer).unwrap().id(), font.id());
+    }
+
+    #[test]
+    fn invalid_font_data() {
+        assert!(FontAsset::bake(&[0; 16], &FontProcessingSettings::default()).is_err());
+    }
+}
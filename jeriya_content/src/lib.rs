@@ -24,10 +24,15 @@ use jeriya_shared::thiserror;
 pub mod asset_importer;
 pub mod asset_processor;
 pub mod common;
+mod draco;
+pub mod font;
+pub mod manifest;
 pub mod model;
 pub mod point_cloud;
 pub mod read_asset;
+pub mod scene;
 pub mod shader;
+pub mod texture;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -55,6 +60,20 @@ pub enum Error {
     Other(Box<dyn std::error::Error + Send + Sync>),
     #[error("Failed to execute: {0}")]
     FailedToCompileShader(String),
+    #[error("Failed to resolve the include '{include}' in file {file}")]
+    IncludeNotFound { include: String, file: PathBuf },
+    #[error("Include cycle detected at file: {0}")]
+    IncludeCycle(PathBuf),
+    #[error("Failed to decompress mesh: {0}")]
+    FailedToDecompressMesh(String),
     #[error("Failed to convert from UTF-8: {0}")]
     Utf8Error(#[from] std::string::FromUtf8Error),
+    #[error("Failed to import the asset '{0}': {1}")]
+    FailedImport(common::AssetKey, String),
+    #[error("Unsupported version {found} of the asset format. Expected version {expected}.")]
+    UnsupportedAssetVersion { found: u32, expected: u32 },
+    #[error("The asset '{0}' was not imported as the requested type")]
+    AssetTypeMismatch(common::AssetKey),
+    #[error("No directories are mounted for the mount point '{0}'")]
+    UnknownMountPoint(String),
 }
@@ -0,0 +1,164 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{common::AssetKey, Error, Result};
+
+/// Name of the file in the root of the processed assets directory that lists all processed assets.
+pub const MANIFEST_FILE_NAME: &str = "manifest.yaml";
+
+/// Description of a processed asset in the [`Manifest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Key of the asset
+    pub asset_key: AssetKey,
+    /// Extension of the unprocessed asset which determines the processor and importer of the asset
+    pub extension: String,
+    /// Size of the processed content file in bytes
+    pub size: u64,
+    /// Content hash of the unprocessed asset
+    pub content_hash: u64,
+    /// Point in time when the unprocessed asset was modified the last time
+    pub modified: SystemTime,
+}
+
+/// List of all processed assets that is maintained by the [`AssetProcessor`](crate::asset_processor::AssetProcessor)
+/// so that the assets can be enumerated without walking the directory tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Entries sorted by their [`AssetKey`]. They are written as a list because the entries contain their keys.
+    #[serde(serialize_with = "serialize_entries", deserialize_with = "deserialize_entries")]
+    entries: BTreeMap<AssetKey, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Returns the path of the manifest file in the given processed assets directory.
+    pub fn path(processed_assets_path: &Path) -> PathBuf {
+        processed_assets_path.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Reads the `Manifest` from the given processed assets directory.
+    pub fn read(processed_assets_path: &Path) -> Result<Self> {
+        let content = fs::read(Self::path(processed_assets_path))?;
+        Self::from_slice(&content)
+    }
+
+    /// Deserializes the `Manifest` from the content of a manifest file.
+    pub fn from_slice(content: &[u8]) -> Result<Self> {
+        serde_yaml::from_slice(content).map_err(|err| Error::FailedDeserialization(Box::new(err)))
+    }
+
+    /// Writes the `Manifest` into the given processed assets directory. The file is replaced atomically so that
+    /// readers never see a partially written manifest.
+    pub fn write(&self, processed_assets_path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self).map_err(|err| Error::FailedSerialization(Box::new(err)))?;
+        let path = Self::path(processed_assets_path);
+        let temporary_path = path.with_extension("yaml.tmp");
+        fs::write(&temporary_path, content)?;
+        fs::rename(&temporary_path, &path)?;
+        Ok(())
+    }
+
+    /// Returns all entries sorted by their [`AssetKey`].
+    pub fn entries(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries.values()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the manifest doesn't contain any entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry of the given asset.
+    pub fn get(&self, asset_key: &AssetKey) -> Option<&ManifestEntry> {
+        self.entries.get(asset_key)
+    }
+
+    /// Returns the entries of the assets with the given extension.
+    pub fn entries_with_extension<'a>(&'a self, extension: &'a str) -> impl Iterator<Item = &'a ManifestEntry> {
+        self.entries().filter(move |entry| entry.extension == extension)
+    }
+
+    /// Inserts the entry or replaces the entry with the same [`AssetKey`].
+    pub fn insert(&mut self, entry: ManifestEntry) {
+        self.entries.insert(entry.asset_key.clone(), entry);
+    }
+
+    /// Removes the entry of the given asset and returns it.
+    pub fn remove(&mut self, asset_key: &AssetKey) -> Option<ManifestEntry> {
+        self.entries.remove(asset_key)
+    }
+
+    /// Removes all entries for which `f` returns `false`.
+    pub fn retain(&mut self, mut f: impl FnMut(&ManifestEntry) -> bool) {
+        self.entries.retain(|_, entry| f(entry));
+    }
+}
+
+fn serialize_entries<S: Serializer>(entries: &BTreeMap<AssetKey, ManifestEntry>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(entries.values())
+}
+
+fn deserialize_entries<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<BTreeMap<AssetKey, ManifestEntry>, D::Error> {
+    let entries = Vec::<ManifestEntry>::deserialize(deserializer)?;
+    Ok(entries.into_iter().map(|entry| (entry.asset_key.clone(), entry)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn entry(asset_key: &str, extension: &str) -> ManifestEntry {
+        ManifestEntry {
+            asset_key: AssetKey::new(asset_key),
+            extension: extension.to_owned(),
+            size: 12,
+            content_hash: 42,
+            modified: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut manifest = Manifest::default();
+        manifest.insert(entry("b.glb", "glb"));
+        manifest.insert(entry("a.png", "png"));
+        manifest.insert(entry("c.glb", "glb"));
+        manifest.insert(entry("b.glb", "glb"));
+        let asset_keys = manifest.entries().map(|entry| entry.asset_key.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            asset_keys,
+            vec![AssetKey::new("a.png"), AssetKey::new("b.glb"), AssetKey::new("c.glb")]
+        );
+        assert_eq!(manifest.entries_with_extension("glb").count(), 2);
+
+        assert_eq!(manifest.remove(&AssetKey::new("b.glb")), Some(entry("b.glb", "glb")));
+        assert_eq!(manifest.get(&AssetKey::new("b.glb")), None);
+        assert_eq!(manifest.get(&AssetKey::new("c.glb")), Some(&entry("c.glb", "glb")));
+    }
+
+    #[test]
+    fn write_and_read() {
+        let root = TempDir::new("root").unwrap();
+        let mut manifest = Manifest::default();
+        manifest.insert(entry("models/suzanne.glb", "glb"));
+        manifest.write(root.path()).unwrap();
+        assert_eq!(Manifest::read(root.path()).unwrap(), manifest);
+
+        // The entries are written as a list
+        let content = fs::read_to_string(Manifest::path(root.path())).unwrap();
+        assert!(content.contains("- asset_key: models/suzanne.glb"));
+    }
+}
@@ -1,22 +1,38 @@
 use std::{
     collections::BTreeMap,
     fs::File,
-    io::Write,
+    io::{BufWriter, Cursor, Write},
     path::{Path, PathBuf},
 };
 
 use gltf::{
     buffer::Data,
-    mesh::{util::ReadIndices, Mode},
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, WrappingMode},
 };
 use jeriya_shared::{
     log::trace,
-    nalgebra::{Vector2, Vector3},
-    thiserror, ByteColor3, ByteColor4,
+    nalgebra::{Vector2, Vector3, Vector4},
+    obj_writer::{ObjMaterial, ObjMesh, ObjWriter},
+    thiserror, AddressMode, ByteColor3, ByteColor4, Filter, SamplerConfig,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::asset_processor::AssetBuilder;
+use crate::{
+    asset_processor::AssetBuilder,
+    common::{compression_flags, read_format_flags, read_format_version, write_format_flags, write_format_header, FormatFlags},
+    draco::{self, DecodedPrimitive},
+};
+
+mod fbx;
+mod obj;
+
+/// Identifies the files that are written by [`process_model`]
+const MODEL_MAGIC: &[u8; 4] = b"JMDL";
+
+/// Version of the format that is written by [`process_model`]. Version 0 is the serialized [`ModelAsset`] without a header
+/// and version 1 lacks the [`FormatFlags`] which means that the content is not compressed.
+pub const MODEL_FORMAT_VERSION: u32 = 2;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -38,6 +54,8 @@ impl From<Error> for crate::Error {
 pub enum ObjWriteConfig {
     FromSimpleMesh,
     FromMeshlets,
+    /// Writes the simple meshes with their normals and texture coordinates and the base colors of their materials.
+    FromSimpleMeshWithShading,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -115,13 +133,142 @@ impl Texture {
             TextureFormat::R8G8B8 => self.sample_rgb(uv).as_byte_color4(),
         }
     }
+
+    /// Returns the color at the given UV coordinates with the address modes and the `mag_filter` of the `sampler_config`.
+    ///
+    /// The texture has no mip levels which is why the `min_filter` and the `mipmap_filter` are ignored.
+    pub fn sample_with(&self, uv: Vector2<f32>, sampler_config: &SamplerConfig) -> ByteColor4 {
+        let texel = |x: i64, y: i64| {
+            let x = address_texel(x, self.width, sampler_config.address_mode_u);
+            let y = address_texel(y, self.height, sampler_config.address_mode_v);
+            self.texel(x, y)
+        };
+        let x = uv.x * self.width as f32;
+        let y = uv.y * self.height as f32;
+        match sampler_config.mag_filter {
+            Filter::Nearest => {
+                let color = texel(x.floor() as i64, y.floor() as i64);
+                ByteColor4::new(color.x as u8, color.y as u8, color.z as u8, color.w as u8)
+            }
+            Filter::Linear => {
+                // Texel centers are at half-integer coordinates
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = texel(x0, y0).lerp(&texel(x0 + 1, y0), fx);
+                let bottom = texel(x0, y0 + 1).lerp(&texel(x0 + 1, y0 + 1), fx);
+                let color = top.lerp(&bottom, fy).map(|channel| channel.round().clamp(0.0, 255.0));
+                ByteColor4::new(color.x as u8, color.y as u8, color.z as u8, color.w as u8)
+            }
+        }
+    }
+
+    /// Returns the channels of the texel at the given pixel coordinates in the range [0, 255]. Textures without alpha are opaque.
+    fn texel(&self, x: usize, y: usize) -> Vector4<f32> {
+        let pixel_index = y * self.width as usize + x;
+        match self.format {
+            TextureFormat::R8G8B8A8 => {
+                let data = &self.data[pixel_index * 4..pixel_index * 4 + 4];
+                Vector4::new(data[0] as f32, data[1] as f32, data[2] as f32, data[3] as f32)
+            }
+            TextureFormat::R8G8B8 => {
+                let data = &self.data[pixel_index * 3..pixel_index * 3 + 3];
+                Vector4::new(data[0] as f32, data[1] as f32, data[2] as f32, 255.0)
+            }
+        }
+    }
+}
+
+/// Maps the pixel `coordinate` into the range [0, `size`) according to the `address_mode`
+fn address_texel(coordinate: i64, size: u32, address_mode: AddressMode) -> usize {
+    let size = size as i64;
+    let coordinate = match address_mode {
+        AddressMode::Repeat => coordinate.rem_euclid(size),
+        AddressMode::MirroredRepeat => {
+            let period = coordinate.rem_euclid(2 * size);
+            if period < size {
+                period
+            } else {
+                2 * size - 1 - period
+            }
+        }
+        AddressMode::ClampToEdge => coordinate.clamp(0, size - 1),
+    };
+    coordinate as usize
+}
+
+/// Transformation of the texture coordinates as specified by `KHR_texture_transform`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TextureTransform {
+    pub offset: Vector2<f32>,
+    /// Rotation in radians
+    pub rotation: f32,
+    pub scale: Vector2<f32>,
+    /// Overrides the set of texture coordinates that is used for the texture
+    pub texture_coordinate: Option<u32>,
+}
+
+impl TextureTransform {
+    /// Transforms the given texture coordinates by scaling, rotating and translating them in this order
+    pub fn apply(&self, uv: Vector2<f32>) -> Vector2<f32> {
+        let scaled = uv.component_mul(&self.scale);
+        let (sin, cos) = self.rotation.sin_cos();
+        let rotated = Vector2::new(cos * scaled.x + sin * scaled.y, -sin * scaled.x + cos * scaled.y);
+        rotated + self.offset
+    }
+}
+
+impl From<gltf::texture::TextureTransform<'_>> for TextureTransform {
+    fn from(texture_transform: gltf::texture::TextureTransform) -> Self {
+        Self {
+            offset: Vector2::from(texture_transform.offset()),
+            rotation: texture_transform.rotation(),
+            scale: Vector2::from(texture_transform.scale()),
+            texture_coordinate: texture_transform.tex_coord(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Material {
     pub name: String,
     pub base_color_texture_index: Option<usize>,
+    /// How the base color texture is sampled as specified by the sampler of the glTF texture
+    pub base_color_sampler: SamplerConfig,
+    pub base_color_texture_transform: Option<TextureTransform>,
     pub base_color_color: ByteColor4,
+    pub emissive_color: Vector3<f32>,
+    /// Multiplier for the `emissive_color` as specified by `KHR_materials_emissive_strength`. Allows values above 1.
+    pub emissive_strength: f32,
+    /// Percentage of light that is transmitted through the surface as specified by `KHR_materials_transmission`
+    pub transmission_factor: f32,
+    /// Texture whose red channel is multiplied with the `transmission_factor`
+    pub transmission_texture_index: Option<usize>,
+}
+
+impl Material {
+    /// Returns the base color at the given texture coordinates
+    ///
+    /// The base color texture is sampled with the `base_color_sampler` and multiplied with the `base_color_color`.
+    pub fn sample_base_color(&self, textures: &[Texture], uv: Vector2<f32>) -> ByteColor4 {
+        match self.base_color_texture_index {
+            Some(base_color_texture_index) => {
+                let uv = self
+                    .base_color_texture_transform
+                    .map_or(uv, |texture_transform| texture_transform.apply(uv));
+                let texture_color = textures[base_color_texture_index].sample_with(uv, &self.base_color_sampler);
+                let color = texture_color.as_vector4().component_mul(&self.base_color_color.as_vector4());
+                ByteColor4::new(
+                    (color.x * 255.0).round() as u8,
+                    (color.y * 255.0).round() as u8,
+                    (color.z * 255.0).round() as u8,
+                    (color.w * 255.0).round() as u8,
+                )
+            }
+            None => self.base_color_color,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,56 +280,34 @@ pub struct ModelAsset {
 }
 
 impl ModelAsset {
-    /// Import model from a glTF file.
+    /// Serializes the model in the format that is read by [`import_model`]. The content is compressed with zstd when a
+    /// `zstd_level` is given.
+    pub fn serialize_into<W: Write>(&self, mut writer: W, zstd_level: Option<i32>) -> crate::Result<()> {
+        write_format_header(&mut writer, MODEL_MAGIC, MODEL_FORMAT_VERSION)?;
+        write_format_flags(&mut writer, compression_flags(zstd_level))?;
+        match zstd_level {
+            Some(zstd_level) => {
+                let mut encoder = zstd::Encoder::new(writer, zstd_level)?;
+                bincode::serialize_into(&mut encoder, self).map_err(|err| crate::Error::FailedSerialization(err))?;
+                encoder.finish()?;
+            }
+            None => bincode::serialize_into(writer, self).map_err(|err| crate::Error::FailedSerialization(err))?,
+        }
+        Ok(())
+    }
+
+    /// Import model from a glTF, OBJ or FBX file. The format is determined by the file extension.
+    ///
+    /// Primitives that are compressed with `KHR_draco_mesh_compression` are decompressed.
     pub fn import(path: impl AsRef<Path>) -> crate::Result<ModelAsset> {
-        let (document, buffers, images) = gltf::import(&path).map_err(|err| Error::FailedLoading {
-            path: path.as_ref().to_owned(),
-            error_message: err.to_string(),
-        })?;
-
-        let textures = images
-            .iter()
-            .map(|image| {
-                let data = image.pixels.clone();
-                let width = image.width;
-                let height = image.height;
-                Texture {
-                    data,
-                    format: image.format.into(),
-                    width,
-                    height,
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let materials = document
-            .materials()
-            .map(|material| {
-                let base_color_color = ByteColor4::from(material.pbr_metallic_roughness().base_color_factor());
-                let base_color_texture_index = material
-                    .pbr_metallic_roughness()
-                    .base_color_texture()
-                    .map(|texture| texture.texture().index());
-                Material {
-                    name: material.name().unwrap_or("unknown").to_owned(),
-                    base_color_texture_index,
-                    base_color_color,
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let model_name = path.as_ref().to_str().unwrap_or("unknown");
-        let meshes = document
-            .meshes()
-            .map(|mesh| build_mesh(model_name, &mesh, &buffers))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(ModelAsset {
-            name: model_name.to_owned(),
-            meshes,
-            textures,
-            materials,
-        })
+        let path = path.as_ref();
+        if has_extension(path, "obj") {
+            obj::import(path).map(|obj_import| obj_import.model)
+        } else if has_extension(path, "fbx") {
+            fbx::import(path)
+        } else {
+            import_gltf(path).map(|(model, _document)| model)
+        }
     }
 
     /// Writes the model to an OBJ file. The MTL file must be written to the same directory as the OBJ file. `mtl_reference_name` must be the filename of the MTL file.
@@ -196,6 +321,7 @@ impl ModelAsset {
         match &config {
             ObjWriteConfig::FromSimpleMesh => self.to_obj_from_simple_mesh(obj_writer, mtl_writer, mtl_reference_name),
             ObjWriteConfig::FromMeshlets => self.to_obj_from_meshlets(obj_writer, mtl_writer, mtl_reference_name),
+            ObjWriteConfig::FromSimpleMeshWithShading => self.to_obj_with_shading(obj_writer, mtl_writer, mtl_reference_name),
         }
     }
 
@@ -274,6 +400,40 @@ impl ModelAsset {
 
         Ok(())
     }
+
+    fn to_obj_with_shading(&self, obj_writer: impl Write, mtl_writer: impl Write, mtl_reference_name: &str) -> crate::Result<()> {
+        let mut obj_writer = ObjWriter::new(obj_writer, mtl_writer, mtl_reference_name)?;
+
+        for (material_index, material) in self.materials.iter().enumerate() {
+            let color = material.base_color_color.as_vector3();
+            obj_writer.write_material(&ObjMaterial::from_color(format!("material_{material_index}"), color))?;
+        }
+        // Meshes without a material use a pseudo random color
+        for (mesh_index, mesh) in self.meshes.iter().enumerate() {
+            if mesh.simple_mesh.material_index.is_none() {
+                let color = jeriya_shared::pseudo_random_color(mesh_index).xyz();
+                obj_writer.write_material(&ObjMaterial::from_color(format!("mesh_{mesh_index}"), color))?;
+            }
+        }
+
+        for (mesh_index, mesh) in self.meshes.iter().enumerate() {
+            let simple_mesh = &mesh.simple_mesh;
+            obj_writer.write_object(&format!("mesh_{mesh_index}"))?;
+            match simple_mesh.material_index {
+                Some(material_index) => obj_writer.use_material(&format!("material_{material_index}"))?,
+                None => obj_writer.use_material(&format!("mesh_{mesh_index}"))?,
+            }
+            obj_writer.write_mesh(&ObjMesh {
+                positions: &simple_mesh.vertex_positions,
+                normals: (simple_mesh.vertex_normals.len() == simple_mesh.vertex_positions.len())
+                    .then_some(&simple_mesh.vertex_normals[..]),
+                texture_coordinates: simple_mesh.vertex_texture_coordinates.as_deref(),
+                indices: &simple_mesh.indices,
+            })?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -304,18 +464,187 @@ impl Meshlet {
     pub const MAX_TRIANGLES: usize = 126;
 }
 
+/// Settings for [`process_model`] that are read from the sidecar file of the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelProcessingSettings {
+    /// Maximum number of vertices in a meshlet. Must not exceed [`Meshlet::MAX_VERTICES`].
+    pub meshlet_max_vertices: usize,
+    /// Maximum number of triangles in a meshlet. Must not exceed [`Meshlet::MAX_TRIANGLES`].
+    pub meshlet_max_triangles: usize,
+    /// Level of the zstd compression of the processed model. The model is not compressed when `None`.
+    pub zstd_level: Option<i32>,
+}
+
+impl Default for ModelProcessingSettings {
+    fn default() -> Self {
+        Self {
+            meshlet_max_vertices: Meshlet::MAX_VERTICES,
+            meshlet_max_triangles: Meshlet::MAX_TRIANGLES,
+            zstd_level: None,
+        }
+    }
+}
+
 /// Function for the [`AssetProcessor`]
 pub fn process_model(asset_builder: &mut AssetBuilder) -> crate::Result<()> {
     let path = asset_builder.unprocessed_asset_path().to_owned();
-    let model = ModelAsset::import(path)?;
+    let settings = asset_builder.settings::<ModelProcessingSettings>()?;
+    if !(1..=Meshlet::MAX_VERTICES).contains(&settings.meshlet_max_vertices)
+        || !(1..=Meshlet::MAX_TRIANGLES).contains(&settings.meshlet_max_triangles)
+    {
+        return Err(crate::Error::Other(
+            format!(
+                "Meshlets of model '{}' must have 1 to {} vertices and 1 to {} triangles",
+                path.display(),
+                Meshlet::MAX_VERTICES,
+                Meshlet::MAX_TRIANGLES
+            )
+            .into(),
+        ));
+    }
+    let mut model = if has_extension(&path, "obj") {
+        // The model has to be processed again when the materials or textures change
+        let obj_import = obj::import(&path)?;
+        for dependency in obj_import.dependencies {
+            asset_builder.with_dependency(dependency);
+        }
+        obj_import.model
+    } else {
+        ModelAsset::import(&path)?
+    };
+    if settings.meshlet_max_vertices != Meshlet::MAX_VERTICES || settings.meshlet_max_triangles != Meshlet::MAX_TRIANGLES {
+        for mesh in &mut model.meshes {
+            mesh.meshlets = build_meshlets(&mesh.simple_mesh, settings.meshlet_max_vertices, settings.meshlet_max_triangles)?;
+        }
+    }
     let file_name = "model.bin";
-    let file = File::create(asset_builder.processed_asset_path().join(file_name))?;
-    bincode::serialize_into(file, &model).map_err(|err| crate::Error::FailedSerialization(err))?;
+    let mut writer = BufWriter::new(File::create(asset_builder.processed_asset_path().join(file_name))?);
+    model.serialize_into(&mut writer, settings.zstd_level)?;
+    writer.flush()?;
     asset_builder.with_file(file_name);
     Ok(())
 }
 
-fn build_simple_mesh(mesh: &gltf::Mesh, buffers: &[Data]) -> crate::Result<SimpleMesh> {
+/// Function for the [`AssetImporter`](crate::asset_importer::AssetImporter) that deserializes a model written by [`process_model`]
+pub fn import_model(bytes: &[u8]) -> crate::Result<ModelAsset> {
+    let mut reader = Cursor::new(bytes);
+    let flags = match read_format_version(&mut reader, MODEL_MAGIC)? {
+        // Version 0 only lacks the header which has already been skipped and version 1 lacks the flags.
+        0 | 1 => FormatFlags::empty(),
+        MODEL_FORMAT_VERSION => read_format_flags(&mut reader)?,
+        found => {
+            return Err(crate::Error::UnsupportedAssetVersion {
+                found,
+                expected: MODEL_FORMAT_VERSION,
+            })
+        }
+    };
+    if flags.contains(FormatFlags::ZSTD) {
+        bincode::deserialize_from(zstd::Decoder::new(reader)?).map_err(|err| crate::Error::FailedDeserialization(err))
+    } else {
+        bincode::deserialize_from(reader).map_err(|err| crate::Error::FailedDeserialization(err))
+    }
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|path_extension| path_extension.to_str())
+        .is_some_and(|path_extension| path_extension.eq_ignore_ascii_case(extension))
+}
+
+/// Imports the glTF file and returns the document for the information that is not part of the [`ModelAsset`]
+fn import_gltf(path: &Path) -> crate::Result<(ModelAsset, gltf::Document)> {
+    let json = draco::read_json(path)?;
+    let uses_draco = draco::is_used(&json);
+    let (document, buffers, images) = if uses_draco {
+        // The gltf crate rejects files that require the extension
+        import_without_validation(path)
+    } else {
+        gltf::import(path)
+    }
+    .map_err(|err| Error::FailedLoading {
+        path: path.to_owned(),
+        error_message: err.to_string(),
+    })?;
+    let compressed_primitives = draco::compressed_primitives(&json);
+
+    let textures = images
+        .iter()
+        .map(|image| {
+            let data = image.pixels.clone();
+            let width = image.width;
+            let height = image.height;
+            Texture {
+                data,
+                format: image.format.into(),
+                width,
+                height,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let materials = document
+        .materials()
+        .map(|material| {
+            let base_color_color = ByteColor4::from(material.pbr_metallic_roughness().base_color_factor());
+            let base_color_texture = material.pbr_metallic_roughness().base_color_texture();
+            let base_color_texture_index = base_color_texture.as_ref().map(|texture| texture.texture().index());
+            let base_color_texture_transform = base_color_texture
+                .as_ref()
+                .and_then(|texture| texture.texture_transform())
+                .map(TextureTransform::from);
+            let base_color_sampler = base_color_texture
+                .map(|texture| sampler_config(&texture.texture().sampler()))
+                .unwrap_or_default();
+            Material {
+                name: material.name().unwrap_or("unknown").to_owned(),
+                base_color_texture_index,
+                base_color_sampler,
+                base_color_texture_transform,
+                base_color_color,
+                emissive_color: Vector3::from(material.emissive_factor()),
+                emissive_strength: material.emissive_strength().unwrap_or(1.0),
+                transmission_factor: material
+                    .transmission()
+                    .map_or(0.0, |transmission| transmission.transmission_factor()),
+                transmission_texture_index: material
+                    .transmission()
+                    .and_then(|transmission| transmission.transmission_texture())
+                    .map(|texture| texture.texture().index()),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let model_name = path.to_str().unwrap_or("unknown");
+    let meshes = document
+        .meshes()
+        .map(|mesh| {
+            let decoded_primitives = compressed_primitives
+                .range((mesh.index(), 0)..(mesh.index() + 1, 0))
+                .map(|(&(_, primitive_index), &view_index)| {
+                    let decoded_primitive = decode_primitive(&document, &buffers, view_index)?;
+                    Ok((primitive_index, decoded_primitive))
+                })
+                .collect::<crate::Result<BTreeMap<_, _>>>()?;
+            build_mesh(model_name, &mesh, &buffers, &decoded_primitives)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let model = ModelAsset {
+        name: model_name.to_owned(),
+        meshes,
+        textures,
+        materials,
+    };
+    Ok((model, document))
+}
+
+fn build_simple_mesh(
+    mesh: &gltf::Mesh,
+    buffers: &[Data],
+    decoded_primitives: &BTreeMap<usize, DecodedPrimitive>,
+) -> crate::Result<SimpleMesh> {
     let mut used_vertex_positions = BTreeMap::new();
     let mut used_vertex_normals = BTreeMap::new();
     let mut used_vertex_texture_coordinates = BTreeMap::new();
@@ -332,58 +661,39 @@ fn build_simple_mesh(mesh: &gltf::Mesh, buffers: &[Data]) -> crate::Result<Simpl
     for primitive in mesh.primitives() {
         trace!("Primitive mode: {:?}", primitive.mode());
         assert_eq!(primitive.mode(), Mode::Triangles, "Currently only triangles are supported");
-        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-        let temp_vertex_positions = reader.read_positions().ok_or(Error::NoVertexPositions)?.collect::<Vec<_>>();
-        let temp_vertex_normals = reader.read_normals().ok_or(Error::NoVertexNormals)?.collect::<Vec<_>>();
-        let temp_vertex_texture_coordinates = reader
-            .read_tex_coords(0)
-            .map(|iter| iter.into_f32().map(|uv| Vector2::new(uv[0], uv[1])).collect::<Vec<_>>());
-        if let Some(indices) = reader.read_indices() {
-            match &indices {
-                ReadIndices::U8(iter) => {
-                    for index in iter.clone() {
-                        old_indices.push(index as u32);
-                        used_vertex_positions
-                            .entry(index as u32)
-                            .or_insert(temp_vertex_positions[index as usize]);
-                        used_vertex_normals
-                            .entry(index as u32)
-                            .or_insert(temp_vertex_normals[index as usize]);
-                        if let Some(texture_coordinates) = &temp_vertex_texture_coordinates {
-                            used_vertex_texture_coordinates
-                                .entry(index as u32)
-                                .or_insert(texture_coordinates[index as usize]);
-                        }
-                    }
-                }
-                ReadIndices::U16(iter) => {
-                    for index in iter.clone() {
-                        old_indices.push(index as u32);
-                        used_vertex_positions
-                            .entry(index as u32)
-                            .or_insert(temp_vertex_positions[index as usize]);
-                        used_vertex_normals
-                            .entry(index as u32)
-                            .or_insert(temp_vertex_normals[index as usize]);
-                        if let Some(texture_coordinates) = &temp_vertex_texture_coordinates {
-                            used_vertex_texture_coordinates
-                                .entry(index as u32)
-                                .or_insert(texture_coordinates[index as usize]);
-                        }
-                    }
-                }
-                ReadIndices::U32(iter) => {
-                    for index in iter.clone() {
-                        old_indices.push(index);
-                        used_vertex_positions.entry(index).or_insert(temp_vertex_positions[index as usize]);
-                        used_vertex_normals.entry(index).or_insert(temp_vertex_normals[index as usize]);
-                        if let Some(texture_coordinates) = &temp_vertex_texture_coordinates {
-                            used_vertex_texture_coordinates
-                                .entry(index)
-                                .or_insert(texture_coordinates[index as usize]);
-                        }
-                    }
+        let (temp_vertex_positions, temp_vertex_normals, temp_vertex_texture_coordinates, temp_indices) =
+            match decoded_primitives.get(&primitive.index()) {
+                Some(decoded_primitive) => (
+                    decoded_primitive.positions.clone(),
+                    decoded_primitive.normals.clone(),
+                    decoded_primitive
+                        .texture_coordinates
+                        .as_ref()
+                        .map(|texture_coordinates| texture_coordinates.iter().map(|uv| Vector2::new(uv[0], uv[1])).collect::<Vec<_>>()),
+                    decoded_primitive.indices.clone(),
+                ),
+                None => {
+                    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                    let positions = reader.read_positions().ok_or(Error::NoVertexPositions)?.collect::<Vec<_>>();
+                    let normals = reader.read_normals().ok_or(Error::NoVertexNormals)?.collect::<Vec<_>>();
+                    let texture_coordinates = reader
+                        .read_tex_coords(0)
+                        .map(|iter| iter.into_f32().map(|uv| Vector2::new(uv[0], uv[1])).collect::<Vec<_>>());
+                    let indices = reader
+                        .read_indices()
+                        .map(|indices| indices.into_u32().collect::<Vec<_>>())
+                        .unwrap_or_default();
+                    (positions, normals, texture_coordinates, indices)
                 }
+            };
+        for index in temp_indices {
+            old_indices.push(index);
+            used_vertex_positions.entry(index).or_insert(temp_vertex_positions[index as usize]);
+            used_vertex_normals.entry(index).or_insert(temp_vertex_normals[index as usize]);
+            if let Some(texture_coordinates) = &temp_vertex_texture_coordinates {
+                used_vertex_texture_coordinates
+                    .entry(index)
+                    .or_insert(texture_coordinates[index as usize]);
             }
         }
     }
@@ -424,8 +734,13 @@ fn build_simple_mesh(mesh: &gltf::Mesh, buffers: &[Data]) -> crate::Result<Simpl
     })
 }
 
-fn build_meshlets(simple_mesh: &SimpleMesh) -> crate::Result<Vec<Meshlet>> {
-    let meshlets = meshopt::clusterize::build_meshlets(&simple_mesh.indices, simple_mesh.vertex_positions.len(), 64, 126);
+fn build_meshlets(simple_mesh: &SimpleMesh, max_vertices: usize, max_triangles: usize) -> crate::Result<Vec<Meshlet>> {
+    let meshlets = meshopt::clusterize::build_meshlets(
+        &simple_mesh.indices,
+        simple_mesh.vertex_positions.len(),
+        max_vertices,
+        max_triangles,
+    );
     let meshlets = meshlets
         .into_iter()
         .map(|meshlet| Meshlet {
@@ -436,12 +751,65 @@ fn build_meshlets(simple_mesh: &SimpleMesh) -> crate::Result<Vec<Meshlet>> {
     Ok(meshlets)
 }
 
-fn build_mesh(model_name: &str, mesh: &gltf::Mesh, buffers: &[Data]) -> crate::Result<Mesh> {
+/// Converts the sampler of a glTF texture into a [`SamplerConfig`]. Unspecified filters default to linear filtering.
+fn sampler_config(sampler: &gltf::texture::Sampler) -> SamplerConfig {
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => Filter::Nearest,
+        Some(MagFilter::Linear) | None => Filter::Linear,
+    };
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) | Some(MinFilter::NearestMipmapNearest) => (Filter::Nearest, Filter::Nearest),
+        Some(MinFilter::NearestMipmapLinear) => (Filter::Nearest, Filter::Linear),
+        Some(MinFilter::LinearMipmapNearest) => (Filter::Linear, Filter::Nearest),
+        Some(MinFilter::Linear) | Some(MinFilter::LinearMipmapLinear) | None => (Filter::Linear, Filter::Linear),
+    };
+    let address_mode = |wrapping_mode| match wrapping_mode {
+        WrappingMode::ClampToEdge => AddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => AddressMode::MirroredRepeat,
+        WrappingMode::Repeat => AddressMode::Repeat,
+    };
+    SamplerConfig {
+        mag_filter,
+        min_filter,
+        mipmap_filter,
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        ..SamplerConfig::default()
+    }
+}
+
+/// Imports the glTF file without validating the extensions that it requires
+fn import_without_validation(path: &Path) -> gltf::Result<(gltf::Document, Vec<Data>, Vec<gltf::image::Data>)> {
+    let base = path.parent();
+    let gltf = gltf::Gltf::from_slice_without_validation(&std::fs::read(path).map_err(gltf::Error::Io)?)?;
+    let buffers = gltf::import_buffers(&gltf.document, base, gltf.blob)?;
+    let images = gltf::import_images(&gltf.document, base, &buffers)?;
+    Ok((gltf.document, buffers, images))
+}
+
+/// Decodes the Draco bitstream that is stored in the buffer view with the given index
+fn decode_primitive(document: &gltf::Document, buffers: &[Data], view_index: usize) -> crate::Result<DecodedPrimitive> {
+    let view = document
+        .views()
+        .nth(view_index)
+        .ok_or_else(|| crate::Error::FailedToDecompressMesh(format!("Buffer view {view_index} doesn't exist")))?;
+    let bitstream = buffers[view.buffer().index()]
+        .get(view.offset()..view.offset() + view.length())
+        .ok_or_else(|| crate::Error::FailedToDecompressMesh(format!("Buffer view {view_index} exceeds its buffer")))?;
+    draco::decode(bitstream)
+}
+
+fn build_mesh(
+    model_name: &str,
+    mesh: &gltf::Mesh,
+    buffers: &[Data],
+    decoded_primitives: &BTreeMap<usize, DecodedPrimitive>,
+) -> crate::Result<Mesh> {
     let name = mesh.name().unwrap_or("unknown");
     trace!("Processing mesh '{name}' in model '{model_name}'");
 
-    let simple_mesh = build_simple_mesh(mesh, buffers)?;
-    let meshlets = build_meshlets(&simple_mesh)?;
+    let simple_mesh = build_simple_mesh(mesh, buffers, decoded_primitives)?;
+    let meshlets = build_meshlets(&simple_mesh, Meshlet::MAX_VERTICES, Meshlet::MAX_TRIANGLES)?;
 
     let mesh = Mesh { simple_mesh, meshlets };
 
@@ -498,6 +866,77 @@ mod tests {
         assert_eq!(contents.mtl, expected_mtl);
     }
 
+    mod sample_with {
+        use super::*;
+
+        /// 2x1 texture with a black and a white texel
+        fn black_and_white() -> Texture {
+            Texture {
+                data: vec![0, 0, 0, 255, 255, 255],
+                format: TextureFormat::R8G8B8,
+                width: 2,
+                height: 1,
+            }
+        }
+
+        #[test]
+        fn nearest() {
+            let sampler_config = SamplerConfig::nearest();
+            let texture = black_and_white();
+            assert_eq!(
+                texture.sample_with(Vector2::new(0.25, 0.5), &sampler_config),
+                ByteColor4::new(0, 0, 0, 255)
+            );
+            assert_eq!(
+                texture.sample_with(Vector2::new(0.75, 0.5), &sampler_config),
+                ByteColor4::new(255, 255, 255, 255)
+            );
+            // Repeats outside of [0, 1]
+            assert_eq!(
+                texture.sample_with(Vector2::new(-0.25, 0.5), &sampler_config),
+                ByteColor4::new(255, 255, 255, 255)
+            );
+        }
+
+        #[test]
+        fn linear() {
+            let sampler_config = SamplerConfig {
+                address_mode_u: AddressMode::ClampToEdge,
+                ..SamplerConfig::linear()
+            };
+            let texture = black_and_white();
+            assert_eq!(
+                texture.sample_with(Vector2::new(0.5, 0.5), &sampler_config),
+                ByteColor4::new(128, 128, 128, 255)
+            );
+            assert_eq!(
+                texture.sample_with(Vector2::new(0.0, 0.5), &sampler_config),
+                ByteColor4::new(0, 0, 0, 255)
+            );
+            assert_eq!(
+                texture.sample_with(Vector2::new(1.0, 0.5), &sampler_config),
+                ByteColor4::new(255, 255, 255, 255)
+            );
+        }
+
+        #[test]
+        fn mirrored_repeat() {
+            let sampler_config = SamplerConfig {
+                address_mode_u: AddressMode::MirroredRepeat,
+                ..SamplerConfig::nearest()
+            };
+            let texture = black_and_white();
+            assert_eq!(
+                texture.sample_with(Vector2::new(1.25, 0.5), &sampler_config),
+                ByteColor4::new(255, 255, 255, 255)
+            );
+            assert_eq!(
+                texture.sample_with(Vector2::new(1.75, 0.5), &sampler_config),
+                ByteColor4::new(0, 0, 0, 255)
+            );
+        }
+    }
+
     #[test]
     fn smoke() {
         setup_logger();
@@ -554,4 +993,69 @@ mod tests {
         );
         assert_obj_model(&contents, "expected_results/suzanne_meshlets.obj");
     }
+
+    #[test]
+    fn import_processed_model() {
+        let model = ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap();
+        let mut bytes = Vec::new();
+        model.serialize_into(&mut bytes, None).unwrap();
+        let imported = import_model(&bytes).unwrap();
+        assert_eq!(imported.name, model.name);
+        assert_eq!(imported.meshes.len(), model.meshes.len());
+        assert_eq!(imported.meshes[0].meshlets, model.meshes[0].meshlets);
+    }
+
+    #[test]
+    fn import_compressed_processed_model() {
+        let model = ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap();
+        let mut uncompressed = Vec::new();
+        model.serialize_into(&mut uncompressed, None).unwrap();
+        let mut compressed = Vec::new();
+        model.serialize_into(&mut compressed, Some(3)).unwrap();
+        assert!(compressed.len() < uncompressed.len());
+        let imported = import_model(&compressed).unwrap();
+        assert_eq!(imported.name, model.name);
+        assert_eq!(imported.meshes[0].meshlets, model.meshes[0].meshlets);
+    }
+
+    #[test]
+    fn import_processed_model_version_1() {
+        let model = ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap();
+        let mut bytes = Vec::new();
+        write_format_header(&mut bytes, MODEL_MAGIC, 1).unwrap();
+        bincode::serialize_into(&mut bytes, &model).unwrap();
+        let imported = import_model(&bytes).unwrap();
+        assert_eq!(imported.name, model.name);
+    }
+
+    #[test]
+    fn import_processed_model_without_header() {
+        let model = ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap();
+        let bytes = bincode::serialize(&model).unwrap();
+        let imported = import_model(&bytes).unwrap();
+        assert_eq!(imported.name, model.name);
+        assert_eq!(imported.meshes.len(), model.meshes.len());
+    }
+
+    #[test]
+    fn import_processed_model_with_unsupported_version() {
+        let mut bytes = Vec::new();
+        write_format_header(&mut bytes, MODEL_MAGIC, MODEL_FORMAT_VERSION + 1).unwrap();
+        assert!(matches!(
+            import_model(&bytes),
+            Err(crate::Error::UnsupportedAssetVersion { found, expected: MODEL_FORMAT_VERSION }) if found == MODEL_FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn texture_transform() {
+        let texture_transform = TextureTransform {
+            offset: Vector2::new(0.5, 0.0),
+            rotation: std::f32::consts::FRAC_PI_2,
+            scale: Vector2::new(2.0, 2.0),
+            texture_coordinate: None,
+        };
+        let uv = texture_transform.apply(Vector2::new(1.0, 0.0));
+        assert!((uv - Vector2::new(0.5, -2.0)).norm() < 1e-5);
+    }
 }
@@ -0,0 +1,145 @@
+//! Import of FBX files
+//!
+//! The FBX file is converted to a binary glTF file with the `FBX2glTF` executable from the
+//! [FBX2glTF](https://github.com/facebookincubator/FBX2glTF) project which has to be available in the PATH.
+//! In contrast to the glTF import, the transforms of the nodes are baked into the vertices because
+//! FBX scenes commonly place their meshes with node transforms.
+
+use std::{
+    fs,
+    path::Path,
+    process::{self, Command},
+};
+
+use jeriya_shared::nalgebra::{Matrix3, Matrix4, Vector3};
+
+use crate::common::unique_id;
+
+use super::{import_gltf, Error, Mesh, ModelAsset};
+
+/// Imports the FBX file at the given path
+pub fn import(path: &Path) -> crate::Result<ModelAsset> {
+    // The converter only works on files. The process id keeps parallel processing jobs apart.
+    let output_directory = std::env::temp_dir().join(format!("jeriya_fbx_{}_{}", process::id(), unique_id()));
+    fs::create_dir_all(&output_directory)?;
+    let result = convert(path, &output_directory).and_then(|glb_path| {
+        let (model, document) = import_gltf(&glb_path)?;
+        let meshes = bake_node_transforms(&document, &model.meshes);
+        Ok(ModelAsset {
+            name: path.to_str().unwrap_or("unknown").to_owned(),
+            meshes,
+            ..model
+        })
+    });
+    let _ = fs::remove_dir_all(&output_directory);
+    result
+}
+
+/// Converts the FBX file into a binary glTF file in the `output_directory` and returns its path
+fn convert(path: &Path, output_directory: &Path) -> crate::Result<std::path::PathBuf> {
+    // The converter appends the extension to the output path
+    let output_path = output_directory.join("model");
+    let failed_loading = |error_message| Error::FailedLoading {
+        path: path.to_owned(),
+        error_message,
+    };
+    let output = Command::new("FBX2glTF")
+        .arg("--binary")
+        .arg("--input")
+        .arg(path)
+        .arg("--output")
+        .arg(&output_path)
+        .output()
+        .map_err(|error| failed_loading(format!("Could not execute FBX2glTF: {error}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr).map_err(crate::Error::Utf8Error)?;
+        return Err(failed_loading(format!("FBX2glTF exited with exit code {:?}:\n{stderr}", output.status.code())).into());
+    }
+    Ok(output_path.with_extension("glb"))
+}
+
+/// Returns a copy of the mesh for every node of the default scene that references it. The vertices
+/// of the copies are transformed into the space of the scene.
+fn bake_node_transforms(document: &gltf::Document, meshes: &[Mesh]) -> Vec<Mesh> {
+    fn visit(node: gltf::Node, parent_transform: &Matrix4<f32>, meshes: &[Mesh], baked_meshes: &mut Vec<Mesh>) {
+        let transform = parent_transform * Matrix4::from(node.transform().matrix());
+        if let Some(mesh) = node.mesh() {
+            baked_meshes.push(transform_mesh(&meshes[mesh.index()], &transform));
+        }
+        for child in node.children() {
+            visit(child, &transform, meshes, baked_meshes);
+        }
+    }
+
+    let mut baked_meshes = Vec::new();
+    let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) else {
+        return meshes.to_vec();
+    };
+    for node in scene.nodes() {
+        visit(node, &Matrix4::identity(), meshes, &mut baked_meshes);
+    }
+    baked_meshes
+}
+
+fn transform_mesh(mesh: &Mesh, transform: &Matrix4<f32>) -> Mesh {
+    let mut mesh = mesh.clone();
+    let simple_mesh = &mut mesh.simple_mesh;
+    // Normals are transformed with the inverse transpose so that non-uniform scaling keeps them perpendicular
+    let normal_transform = transform
+        .fixed_view::<3, 3>(0, 0)
+        .try_inverse()
+        .map_or_else(Matrix3::identity, |inverse| inverse.transpose());
+    for position in &mut simple_mesh.vertex_positions {
+        *position = transform.transform_point(&(*position).into()).coords;
+    }
+    for normal in &mut simple_mesh.vertex_normals {
+        *normal = (normal_transform * *normal).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y);
+    }
+    // Mirroring transforms flip the winding order of the triangles
+    if transform.fixed_view::<3, 3>(0, 0).determinant() < 0.0 {
+        for triangle in simple_mesh.indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+        for meshlet in &mut mesh.meshlets {
+            for triangle in &mut meshlet.local_indices {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use jeriya_shared::nalgebra::{Translation3, Vector2};
+
+    use super::*;
+    use crate::model::{Meshlet, SimpleMesh};
+
+    #[test]
+    fn transform_mesh_mirrored() {
+        let mesh = Mesh {
+            simple_mesh: SimpleMesh {
+                material_index: None,
+                vertex_positions: vec![
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                    Vector3::new(0.0, 1.0, 0.0),
+                ],
+                vertex_normals: vec![Vector3::z(); 3],
+                vertex_texture_coordinates: Some(vec![Vector2::zeros(); 3]),
+                indices: vec![0, 1, 2],
+            },
+            meshlets: vec![Meshlet {
+                global_indices: vec![0, 1, 2],
+                local_indices: vec![[0, 1, 2]],
+            }],
+        };
+        let transform = Translation3::new(0.0, 0.0, 5.0).to_homogeneous() * Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, -2.0));
+        let transformed = transform_mesh(&mesh, &transform);
+        assert_eq!(transformed.simple_mesh.vertex_positions[1], Vector3::new(1.0, 0.0, 5.0));
+        assert_eq!(transformed.simple_mesh.vertex_normals[0], -Vector3::z());
+        assert_eq!(transformed.simple_mesh.indices, vec![0, 2, 1]);
+        assert_eq!(transformed.meshlets[0].local_indices, vec![[0, 2, 1]]);
+    }
+}
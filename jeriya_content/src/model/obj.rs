@@ -0,0 +1,237 @@
+//! Import of Wavefront OBJ files with their MTL materials
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use jeriya_shared::{
+    log::{trace, warn},
+    nalgebra::{Vector2, Vector3},
+    ByteColor4, SamplerConfig,
+};
+
+use super::{build_meshlets, Error, Material, Mesh, Meshlet, ModelAsset, SimpleMesh, Texture, TextureFormat};
+
+/// Result of importing an OBJ file
+pub struct ObjImport {
+    pub model: ModelAsset,
+    /// MTL files and textures that were read in addition to the OBJ file
+    pub dependencies: Vec<PathBuf>,
+}
+
+/// Imports the OBJ file at the given path
+///
+/// Every group of faces that uses the same material becomes a separate [`Mesh`]. Missing vertex
+/// normals are computed by averaging the normals of the adjacent faces.
+pub fn import(path: &Path) -> crate::Result<ObjImport> {
+    let load_options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ..Default::default()
+    };
+    let (models, materials) = tobj::load_obj(path, &load_options).map_err(|err| Error::FailedLoading {
+        path: path.to_owned(),
+        error_message: err.to_string(),
+    })?;
+    let materials = materials.unwrap_or_else(|err| {
+        warn!("Failed to load the materials of '{}': {err}", path.display());
+        Vec::new()
+    });
+
+    let base_directory = path.parent().unwrap_or(Path::new(""));
+    let mut dependencies = mtl_files(path)?
+        .into_iter()
+        .map(|mtl_file| base_directory.join(mtl_file))
+        .collect::<Vec<_>>();
+
+    // Textures that are referenced by multiple materials are only loaded once
+    let mut textures = Vec::new();
+    let mut texture_indices = HashMap::new();
+    let materials = materials
+        .iter()
+        .map(|material| {
+            let base_color_texture_index = match &material.diffuse_texture {
+                Some(diffuse_texture) => {
+                    let texture_path = base_directory.join(diffuse_texture);
+                    match texture_indices.get(&texture_path) {
+                        Some(&index) => Some(index),
+                        None => {
+                            textures.push(load_texture(&texture_path)?);
+                            texture_indices.insert(texture_path.clone(), textures.len() - 1);
+                            dependencies.push(texture_path);
+                            Some(textures.len() - 1)
+                        }
+                    }
+                }
+                None => None,
+            };
+            let diffuse = material.diffuse.unwrap_or([1.0, 1.0, 1.0]);
+            let alpha = material.dissolve.unwrap_or(1.0);
+            Ok(Material {
+                name: material.name.clone(),
+                base_color_texture_index,
+                base_color_sampler: SamplerConfig::default(),
+                base_color_texture_transform: None,
+                base_color_color: ByteColor4::from([diffuse[0], diffuse[1], diffuse[2], alpha]),
+                emissive_color: material.emissive.map(Vector3::from).unwrap_or_else(Vector3::zeros),
+                emissive_strength: 1.0,
+                transmission_factor: 0.0,
+                transmission_texture_index: None,
+            })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    let model_name = path.to_str().unwrap_or("unknown");
+    let meshes = models
+        .iter()
+        .filter(|model| !model.mesh.indices.is_empty())
+        .map(|model| {
+            trace!("Processing mesh '{}' in model '{model_name}'", model.name);
+            let simple_mesh = build_simple_mesh(&model.mesh)?;
+            let meshlets = build_meshlets(&simple_mesh, Meshlet::MAX_VERTICES, Meshlet::MAX_TRIANGLES)?;
+            Ok(Mesh { simple_mesh, meshlets })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    Ok(ObjImport {
+        model: ModelAsset {
+            name: model_name.to_owned(),
+            meshes,
+            textures,
+            materials,
+        },
+        dependencies,
+    })
+}
+
+/// Returns the MTL files that are referenced by the OBJ file
+fn mtl_files(path: &Path) -> crate::Result<Vec<String>> {
+    let obj = std::fs::read_to_string(path)?;
+    let mtl_files = obj
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("mtllib "))
+        .map(|mtl_file| mtl_file.trim().to_owned())
+        .collect();
+    Ok(mtl_files)
+}
+
+fn load_texture(path: &Path) -> crate::Result<Texture> {
+    let image = image::open(path)
+        .map_err(|err| Error::FailedLoading {
+            path: path.to_owned(),
+            error_message: err.to_string(),
+        })?
+        .into_rgba8();
+    Ok(Texture {
+        width: image.width(),
+        height: image.height(),
+        format: TextureFormat::R8G8B8A8,
+        data: image.into_raw(),
+    })
+}
+
+fn build_simple_mesh(mesh: &tobj::Mesh) -> crate::Result<SimpleMesh> {
+    if mesh.positions.is_empty() {
+        return Err(Error::NoVertexPositions.into());
+    }
+    let vertex_positions = mesh
+        .positions
+        .chunks_exact(3)
+        .map(|position| Vector3::new(position[0], position[1], position[2]))
+        .collect::<Vec<_>>();
+    let vertex_normals = if mesh.normals.is_empty() {
+        compute_vertex_normals(&vertex_positions, &mesh.indices)
+    } else {
+        mesh.normals
+            .chunks_exact(3)
+            .map(|normal| Vector3::new(normal[0], normal[1], normal[2]))
+            .collect::<Vec<_>>()
+    };
+    // OBJ places the origin of the texture coordinates in the bottom left corner
+    let vertex_texture_coordinates = (!mesh.texcoords.is_empty()).then(|| {
+        mesh.texcoords
+            .chunks_exact(2)
+            .map(|uv| Vector2::new(uv[0], 1.0 - uv[1]))
+            .collect::<Vec<_>>()
+    });
+    let indices = meshopt::optimize::optimize_vertex_cache(&mesh.indices, vertex_positions.len());
+    Ok(SimpleMesh {
+        material_index: mesh.material_id,
+        vertex_positions,
+        vertex_normals,
+        vertex_texture_coordinates,
+        indices,
+    })
+}
+
+/// Computes the vertex normals by summing the area-weighted normals of the adjacent triangles
+fn compute_vertex_normals(vertex_positions: &[Vector3<f32>], indices: &[u32]) -> Vec<Vector3<f32>> {
+    let mut vertex_normals = vec![Vector3::zeros(); vertex_positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+        let normal = (vertex_positions[b] - vertex_positions[a]).cross(&(vertex_positions[c] - vertex_positions[a]));
+        vertex_normals[a] += normal;
+        vertex_normals[b] += normal;
+        vertex_normals[c] += normal;
+    }
+    vertex_normals
+        .into_iter()
+        .map(|normal| normal.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn groups_and_materials() {
+        let directory = TempDir::new("obj_import").unwrap();
+        let obj = "\
+            mtllib quad.mtl\n\
+            v 0 0 0\n\
+            v 1 0 0\n\
+            v 1 1 0\n\
+            v 0 1 0\n\
+            vt 0 0\n\
+            vt 1 1\n\
+            usemtl red\n\
+            f 1/1 2/1 3/2\n\
+            usemtl green\n\
+            f 1/1 3/2 4/2\n";
+        let mtl = "\
+            newmtl red\n\
+            Kd 1 0 0\n\
+            newmtl green\n\
+            Kd 0 1 0\n\
+            Ke 0 0.5 0\n";
+        fs::write(directory.path().join("quad.obj"), obj).unwrap();
+        fs::write(directory.path().join("quad.mtl"), mtl).unwrap();
+
+        let obj_import = import(&directory.path().join("quad.obj")).unwrap();
+        let model = obj_import.model;
+        assert_eq!(obj_import.dependencies, vec![directory.path().join("quad.mtl")]);
+        assert_eq!(model.meshes.len(), 2);
+        assert_eq!(model.materials.len(), 2);
+        assert_eq!(model.materials[0].base_color_color, ByteColor4::new(255, 0, 0, 255));
+        assert_eq!(model.materials[1].emissive_color, Vector3::new(0.0, 0.5, 0.0));
+        for mesh in &model.meshes {
+            let simple_mesh = &mesh.simple_mesh;
+            assert_eq!(simple_mesh.indices.len(), 3);
+            assert_eq!(simple_mesh.vertex_normals.len(), simple_mesh.vertex_positions.len());
+            assert!(simple_mesh.vertex_normals.iter().all(|normal| *normal == Vector3::z()));
+            assert_eq!(simple_mesh.vertex_texture_coordinates.as_ref().unwrap()[0], Vector2::new(0.0, 1.0));
+        }
+        let material_names = model
+            .meshes
+            .iter()
+            .map(|mesh| model.materials[mesh.simple_mesh.material_index.unwrap()].name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(material_names, vec!["red", "green"]);
+    }
+}
@@ -1,3 +1,84 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{asset_processor::AssetBuilder, model::ModelAsset};
+
+use self::{clustered_point_cloud::ClusteredPointCloudAsset, simple_point_cloud::SimplePointCloud};
+
 pub mod clustered_point_cloud;
 pub mod point_clustering_octree;
+pub mod potree;
 pub mod simple_point_cloud;
+
+/// Settings for [`process_point_cloud`] that are read from the sidecar file of the point cloud
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PointCloudProcessingSettings {
+    /// Number of points per square unit with which the surface of a model is sampled. Not used for PLY files.
+    pub points_per_square_unit: f32,
+    /// Size of the voxels in which the points are merged before clustering. No downsampling is done when `None`.
+    pub grid_size: Option<f32>,
+    /// Fraction of the points of two child clusters that is kept in their parent cluster. Must be in the range (0, 1].
+    pub lod_ratio: f32,
+    /// Level of the zstd compression of the `Page`s. The `Page`s are not compressed when `None`.
+    pub zstd_level: Option<i32>,
+}
+
+impl Default for PointCloudProcessingSettings {
+    fn default() -> Self {
+        Self {
+            points_per_square_unit: 1.0,
+            grid_size: None,
+            lod_ratio: ClusteredPointCloudAsset::DEFAULT_LOD_RATIO,
+            zstd_level: None,
+        }
+    }
+}
+
+/// Function for the [`AssetProcessor`](crate::asset_processor::AssetProcessor) that clusters the points of a PLY file
+///
+/// Other files are imported as [`ModelAsset`] whose surface is sampled with the `points_per_square_unit` of the
+/// [`PointCloudProcessingSettings`].
+pub fn process_point_cloud(asset_builder: &mut AssetBuilder) -> crate::Result<()> {
+    let settings = asset_builder.settings::<PointCloudProcessingSettings>()?;
+    if !(settings.lod_ratio > 0.0 && settings.lod_ratio <= 1.0) {
+        return Err(crate::Error::Other(
+            format!("The lod ratio must be in the range (0, 1] but is {}", settings.lod_ratio).into(),
+        ));
+    }
+    let path = asset_builder.unprocessed_asset_path();
+    let is_ply = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ply"));
+    let simple_point_cloud = if is_ply {
+        SimplePointCloud::import_ply(path)?
+    } else if settings.points_per_square_unit > 0.0 {
+        SimplePointCloud::sample_from_model(&ModelAsset::import(path)?, settings.points_per_square_unit, 1.0)
+    } else {
+        return Err(crate::Error::Other(
+            format!(
+                "The points per square unit must be positive but are {}",
+                settings.points_per_square_unit
+            )
+            .into(),
+        ));
+    };
+    let simple_point_cloud = match settings.grid_size {
+        Some(grid_size) if grid_size > 0.0 => simple_point_cloud.downsample_voxel(grid_size),
+        Some(grid_size) => {
+            return Err(crate::Error::Other(
+                format!("The grid size must be positive but is {grid_size}").into(),
+            ))
+        }
+        None => simple_point_cloud,
+    };
+    let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud_with_lod_ratio(&simple_point_cloud, settings.lod_ratio);
+    let file_name = "point_cloud.bin";
+    let mut writer = BufWriter::new(File::create(asset_builder.processed_asset_path().join(file_name))?);
+    clustered_point_cloud.serialize_with_compression_into(&mut writer, settings.zstd_level)?;
+    writer.flush()?;
+    asset_builder.with_file(file_name);
+    Ok(())
+}
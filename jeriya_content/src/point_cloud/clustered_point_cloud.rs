@@ -1,18 +1,20 @@
 use std::{
     collections::HashMap,
     fs::File,
-    io::{self, Read, Seek, SeekFrom, Write},
+    io::{self, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
     path::Path,
     time::Instant,
 };
 
 use jeriya_shared::{
     aabb::AABB,
+    bounding_sphere::BoundingSphere,
     byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
     colors_transform::Hsl,
     itertools::Itertools,
     log::{info, trace},
     nalgebra::Vector3,
+    obj_writer::{ObjMaterial, ObjWriter, DEFAULT_DEDUPLICATION_CAPACITY},
     plotters::{
         backend::{DrawingBackend, SVGBackend},
         chart::ChartBuilder,
@@ -22,17 +24,34 @@ use jeriya_shared::{
         style::Color,
         style::{BLUE, WHITE},
     },
-    rand, serde_json, ByteColor3,
+    rand,
+    rayon::prelude::*,
+    serde_json, ByteColor3,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::point_cloud::point_clustering_octree::ProtoCluster;
+use crate::{
+    common::{
+        compression_flags, read_format_flags, read_format_version, write_format_flags, write_format_header, FormatFlags, FORMAT_FLAGS_SIZE,
+        FORMAT_HEADER_SIZE,
+    },
+    point_cloud::point_clustering_octree::ProtoCluster,
+    Error,
+};
 
 use super::{
     point_clustering_octree::{BuildContext, PointClusteringOctree},
     simple_point_cloud::SimplePointCloud,
 };
 
+/// Identifies the files that are written by [`ClusteredPointCloudAsset::serialize_into`]
+const POINT_CLOUD_MAGIC: &[u8; 4] = b"JPCL";
+
+/// Version of the format that is written by [`ClusteredPointCloudAsset::serialize_into`]. Version 0 is the same format
+/// without the format header and version 1 without the [`FormatFlags`]. Since all offsets in the file are absolute, they
+/// are valid in all versions.
+pub const POINT_CLOUD_FORMAT_VERSION: u32 = 2;
+
 pub enum ObjClusterWriteConfig {
     Points { point_size: f32, depth: usize },
 }
@@ -49,7 +68,7 @@ pub struct ClusterIndex {
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Cluster {
-    /// Index into the `Page`'s `point_positions` and `point_colors` `Vec`s
+    /// Index into the `Page`'s `point_positions`, `point_colors` and `point_normals` `Vec`s
     pub index_start: u32,
     /// Number of points in the cluster
     pub len: u32,
@@ -76,6 +95,7 @@ impl Cluster {
 pub struct Page {
     point_positions: Vec<Vector3<f32>>,
     point_colors: Vec<ByteColor3>,
+    point_normals: Vec<Vector3<f32>>,
     clusters: Vec<Cluster>,
 }
 
@@ -99,6 +119,11 @@ impl Page {
         &self.point_colors
     }
 
+    /// Returns the normals of the points in the `Page`.
+    pub fn point_normals(&self) -> &[Vector3<f32>] {
+        &self.point_normals
+    }
+
     /// Returns the `Cluster`s of the `Page`.
     pub fn clusters(&self) -> &[Cluster] {
         &self.clusters
@@ -109,11 +134,12 @@ impl Page {
     /// # Panics
     ///
     /// * If the `Page` is full. This can be checked with [`ClusteredPointCloud::has_space`].
-    /// * If the `point_positions` and `point_colors` `Iterator`s have different lengths.
-    pub fn push<'p, 'c>(
+    /// * If the `point_positions`, `point_colors` and `point_normals` `Iterator`s have different lengths.
+    pub fn push<'p, 'c, 'n>(
         &mut self,
         point_positions: impl Iterator<Item = &'p Vector3<f32>> + Clone,
         point_colors: impl Iterator<Item = &'c ByteColor3> + Clone,
+        point_normals: impl Iterator<Item = &'n Vector3<f32>> + Clone,
         depth: usize,
         level: usize,
         children: Vec<ClusterIndex>,
@@ -132,29 +158,26 @@ impl Page {
             point_positions.clone().count(), point_colors.clone().count(),
             "point_positions and point_colors must have the same length"
         }
+        jeriya_shared::assert_eq! {
+            point_positions.clone().count(), point_normals.clone().count(),
+            "point_positions and point_normals must have the same length"
+        }
 
         let index_start = self.point_positions.len() as u32;
         self.point_positions.extend(point_positions.clone());
         self.point_colors.extend(point_colors);
+        self.point_normals.extend(point_normals);
         let len = self.point_positions.len() as u32 - index_start;
-        let aabb = AABB::from_ref_iter(point_positions.clone());
-        let center = point_positions.clone().fold(Vector3::zeros(), |acc, position| acc + position) / len as f32;
-        let radius = point_positions.fold(0.0, |acc, position| {
-            let distance = (position - center).norm();
-            if distance > acc {
-                distance
-            } else {
-                acc
-            }
-        });
+        let aabb = AABB::from_ref_iter(point_positions);
+        let bounding_sphere = BoundingSphere::from_slice(&self.point_positions[index_start as usize..]);
 
         let index = self.clusters.len();
         self.clusters.push(Cluster {
             index_start,
             len,
             aabb,
-            center,
-            radius,
+            center: bounding_sphere.center,
+            radius: bounding_sphere.radius,
             depth,
             level,
             children,
@@ -168,6 +191,7 @@ impl Page {
         if result {
             jeriya_shared::assert!(self.point_positions.len() + Cluster::MAX_POINTS <= Page::MAX_POINTS);
             jeriya_shared::assert!(self.point_colors.len() + Cluster::MAX_POINTS <= Page::MAX_POINTS);
+            jeriya_shared::assert!(self.point_normals.len() + Cluster::MAX_POINTS <= Page::MAX_POINTS);
         }
         result
     }
@@ -180,56 +204,103 @@ pub struct ClusteredPointCloudAsset {
     max_cluster_depth: usize,
 }
 
+/// `ProtoCluster` in the order in which it is packed into the `Page`s
+struct FlattenedProtoCluster<'o> {
+    proto_cluster: &'o ProtoCluster,
+    depth: usize,
+    children: Vec<ClusterIndex>,
+}
+
 impl ClusteredPointCloudAsset {
+    /// Fraction of the points of two child clusters that is kept in their parent cluster by default, i.e. as many
+    /// points as fit into the parent cluster
+    pub const DEFAULT_LOD_RATIO: f32 = 1.0;
+
+    /// Clusters the points of the `SimplePointCloud`. The normals are estimated when the point cloud has none.
     pub fn from_simple_point_cloud(simple_point_cloud: &SimplePointCloud) -> Self {
+        Self::from_simple_point_cloud_with_lod_ratio(simple_point_cloud, Self::DEFAULT_LOD_RATIO)
+    }
+
+    /// Clusters the points of the `SimplePointCloud` and keeps the `lod_ratio` of the points of two child clusters in
+    /// their parent cluster. Lower ratios make the coarser levels of detail sparser.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the `lod_ratio` is not in the range (0, 1].
+    pub fn from_simple_point_cloud_with_lod_ratio(simple_point_cloud: &SimplePointCloud, lod_ratio: f32) -> Self {
+        jeriya_shared::assert!(lod_ratio > 0.0 && lod_ratio <= 1.0, "the lod ratio must be in the range (0, 1]");
         let start = Instant::now();
 
+        let estimated_point_normals;
+        let point_normals = match simple_point_cloud.point_normals() {
+            Some(point_normals) => point_normals,
+            None => {
+                info!("Estimating the normals of {} points", simple_point_cloud.len());
+                estimated_point_normals = simple_point_cloud.estimate_normals(SimplePointCloud::NORMAL_ESTIMATION_NEIGHBOR_COUNT);
+                &estimated_point_normals
+            }
+        };
+
         let build_parameters = &BuildContext {
             cluster_point_count: Cluster::MAX_POINTS,
+            lod_ratio,
             point_positions: simple_point_cloud.point_positions(),
         };
         let octree = PointClusteringOctree::new(build_parameters);
 
-        let mut pages = vec![Page::default()];
-
-        // Packs the proto clusters into pages and returns the (page, cluster) indices of the packed cluster.
-        fn visit(proto_cluster: &ProtoCluster, depth: usize, pages: &mut Vec<Page>, simple_point_cloud: &SimplePointCloud) -> ClusterIndex {
-            // Pack the children into pages and collect the (page, cluster) indices of the packed clusters.
-            // The children have to be packed first, so that the indices of the children are known.
+        // Flattens the proto clusters in the order in which they are packed into the pages and returns the
+        // (page, cluster) indices of the packed cluster. The children have to be visited first, so that the
+        // indices of the children are known.
+        fn flatten<'o>(proto_cluster: &'o ProtoCluster, depth: usize, flattened: &mut Vec<FlattenedProtoCluster<'o>>) -> ClusterIndex {
             let children = proto_cluster
                 .children
                 .iter()
-                .map(|child| visit(child, depth + 1, pages, simple_point_cloud))
+                .map(|child| flatten(child, depth + 1, flattened))
                 .collect_vec();
-
-            // Either take the last page or create a new one if the last page is full.
-            let has_space = pages.last().map(|page| page.has_space()).unwrap_or(false);
-            if !has_space {
-                pages.push(Page::new());
+            let index = flattened.len();
+            flattened.push(FlattenedProtoCluster {
+                proto_cluster,
+                depth,
+                children,
+            });
+            ClusterIndex {
+                page_index: index / Page::MAX_CLUSTERS,
+                cluster_index: index % Page::MAX_CLUSTERS,
             }
-            let page_index = pages.len() - 1;
-            let page = pages.last_mut().expect("failed to get last page");
-
-            // Create and insert a new cluster into the page.
-            let positions = simple_point_cloud.point_positions();
-            let colors = simple_point_cloud.point_colors();
-            let point_positions = proto_cluster.indices.iter().map(|index| &positions[*index]);
-            let point_colors = proto_cluster.indices.iter().map(|index| &colors[*index]);
-
-            trace!("Pushing cluster with {} points", proto_cluster.indices.len());
-
-            let cluster_index = page.push(point_positions, point_colors, depth, proto_cluster.level, children);
-
-            ClusterIndex { page_index, cluster_index }
         }
-        visit(octree.root(), 0, &mut pages, simple_point_cloud);
-
-        let root_cluster_index = ClusterIndex {
-            page_index: pages.len() - 1,
-            cluster_index: pages.last().expect("failed to get last page").clusters.len() - 1,
-        };
+        let mut flattened = Vec::with_capacity(octree.proto_cluster_count());
+        let root_cluster_index = flatten(octree.root(), 0, &mut flattened);
         trace!("Root cluster index: {:?}", root_cluster_index);
 
+        // Every page is filled with consecutive clusters so that the pages can be packed independently
+        let positions = simple_point_cloud.point_positions();
+        let colors = simple_point_cloud.point_colors();
+        let normals = point_normals;
+        let pages = flattened
+            .par_chunks(Page::MAX_CLUSTERS)
+            .map(|flattened_proto_clusters| {
+                let mut page = Page::new();
+                for flattened_proto_cluster in flattened_proto_clusters {
+                    let indices = &flattened_proto_cluster.proto_cluster.indices;
+                    let point_positions = indices.iter().map(|index| &positions[*index]);
+                    let point_colors = indices.iter().map(|index| &colors[*index]);
+                    let point_normals = indices.iter().map(|index| &normals[*index]);
+
+                    trace!("Pushing cluster with {} points", indices.len());
+
+                    page.push(
+                        point_positions,
+                        point_colors,
+                        point_normals,
+                        flattened_proto_cluster.depth,
+                        flattened_proto_cluster.proto_cluster.level,
+                        flattened_proto_cluster.children.clone(),
+                    );
+                }
+                page
+            })
+            .collect::<Vec<_>>();
+
         info!("Computing the clusters took {} ms", start.elapsed().as_secs_f32());
 
         Self {
@@ -359,66 +430,52 @@ impl ClusteredPointCloudAsset {
         Self::plot_histogram(self, &data, filepath, "Page fill level histogram")
     }
 
+    /// Writes the clusters as an OBJ file. The positions are streamed into the `obj_writer` and deduplicated so that
+    /// point clouds with millions of points can be exported. `mtl_filename` must be the filename of the MTL file.
     pub fn to_obj(
         &self,
-        mut obj_writer: impl Write,
-        mut mtl_writer: impl Write,
+        obj_writer: impl Write,
+        mtl_writer: impl Write,
         mtl_filename: &str,
         config: &ObjClusterWriteConfig,
     ) -> io::Result<()> {
         match config {
             ObjClusterWriteConfig::Points { point_size, depth } => {
-                writeln!(obj_writer, "mtllib {mtl_filename}")?;
+                let mut obj_writer =
+                    ObjWriter::new(obj_writer, mtl_writer, mtl_filename)?.with_deduplication(DEFAULT_DEDUPLICATION_CAPACITY);
 
-                // Write OBJ file
-                let mut vertex_index = 1;
                 let mut global_cluster_index = 0;
                 for (page_index, page) in self.pages().iter().enumerate() {
                     for (cluster_index, cluster) in page.clusters().iter().filter(|cluster| cluster.depth == *depth).enumerate() {
-                        writeln!(obj_writer)?;
-                        writeln!(obj_writer, "# Cluster {cluster_index} in page {page_index}")?;
-                        writeln!(obj_writer, "o cluster_{global_cluster_index}")?;
-                        writeln!(obj_writer, "usemtl cluster_{global_cluster_index}")?;
+                        // Write MTL entry
+                        use jeriya_shared::colors_transform::Color;
+                        let hue = rand::random::<f32>() * 360.0;
+                        let hsv = Hsl::from(hue, 100.0, 50.0);
+                        let rgb = hsv.to_rgb();
+                        let color = Vector3::new(rgb.get_red(), rgb.get_green(), rgb.get_blue()) / 255.0;
+                        obj_writer.write_material(&ObjMaterial {
+                            specular_exponent: 100.0,
+                            ..ObjMaterial::from_color(format!("cluster_{global_cluster_index}"), color)
+                        })?;
+
+                        // Write OBJ entry
+                        obj_writer.write_comment(&format!("Cluster {cluster_index} in page {page_index}"))?;
+                        obj_writer.write_object(&format!("cluster_{global_cluster_index}"))?;
+                        obj_writer.use_material(&format!("cluster_{global_cluster_index}"))?;
                         for index in cluster.index_start..cluster.index_start + cluster.len {
                             let position = &page.point_positions()[index as usize];
                             let (a, b, c) = SimplePointCloud::create_triangle_for_point(position, *point_size)?;
-                            writeln!(obj_writer, "v {} {} {}", a.x, a.y, a.z)?;
-                            writeln!(obj_writer, "v {} {} {}", b.x, b.y, b.z)?;
-                            writeln!(obj_writer, "v {} {} {}", c.x, c.y, c.z)?;
-                        }
-                        for i in 0..cluster.len {
-                            let f0 = vertex_index + i * 3;
-                            let f1 = vertex_index + i * 3 + 1;
-                            let f2 = vertex_index + i * 3 + 2;
-                            writeln!(obj_writer, "f {f0} {f1} {f2}")?;
+                            obj_writer.write_triangle(&[a, b, c])?;
                         }
-                        vertex_index += cluster.len * 3;
                         global_cluster_index += 1;
                     }
                 }
 
-                // Write MTL file
-                let mut global_clutser_index = 0;
-                for (page_index, page) in self.pages().iter().enumerate() {
-                    for (cluster_index, _cluster) in page.clusters().iter().filter(|cluster| cluster.depth == *depth).enumerate() {
-                        use jeriya_shared::colors_transform::Color;
-                        let hue = rand::random::<f32>() * 360.0;
-                        let hsv = Hsl::from(hue, 100.0, 50.0);
-                        let rgb = hsv.to_rgb();
-                        let r = rgb.get_red() / 255.0;
-                        let g = rgb.get_green() / 255.0;
-                        let b = rgb.get_blue() / 255.0;
-                        writeln!(mtl_writer, "# Material for cluster {cluster_index} in page {page_index}")?;
-                        writeln!(mtl_writer, "newmtl cluster_{global_clutser_index}")?;
-                        writeln!(mtl_writer, "Ka {r} {g} {b}")?;
-                        writeln!(mtl_writer, "Kd {r} {g} {b}")?;
-                        writeln!(mtl_writer, "Ks 1.0 1.0 1.0")?;
-                        writeln!(mtl_writer, "Ns 100")?;
-                        writeln!(mtl_writer)?;
-                        global_clutser_index += 1;
-                    }
-                }
-
+                trace!(
+                    "Wrote {} positions to the OBJ file and deduplicated {}",
+                    obj_writer.written_positions(),
+                    obj_writer.deduplicated_positions()
+                );
                 Ok(())
             }
         }
@@ -428,8 +485,8 @@ impl ClusteredPointCloudAsset {
     pub fn to_obj_file(&self, config: &ObjClusterWriteConfig, filepath: &impl AsRef<Path>) -> io::Result<()> {
         let obj_filepath = filepath.as_ref().with_extension("obj");
         let mtl_filepath = filepath.as_ref().with_extension("mtl");
-        let obj_file = File::create(obj_filepath)?;
-        let mtl_file = File::create(&mtl_filepath)?;
+        let obj_file = BufWriter::new(File::create(obj_filepath)?);
+        let mtl_file = BufWriter::new(File::create(&mtl_filepath)?);
         let mtl_filename = mtl_filepath
             .file_name()
             .expect("Failed to get MTL filename")
@@ -459,65 +516,94 @@ impl ClusteredPointCloudAsset {
         Ok(())
     }
 
-    /// Serializes the `Page`s into a stream.
-    fn serlialize_pages_into<W: Write + Seek>(&self, mut writer: W) -> io::Result<HashMap<u64, u64>> {
+    /// Serializes the `Page`s into a stream. When `zstd_level` is set, every `Page` is compressed individually so that
+    /// single `Page`s can still be streamed.
+    fn serlialize_pages_into<W: Write + Seek>(&self, mut writer: W, zstd_level: Option<i32>) -> io::Result<HashMap<u64, u64>> {
         let mut page_table = HashMap::<u64, u64>::new();
         for (page_index, page) in self.pages.iter().enumerate() {
             // Insert the offset into the page table
             let page_start = writer.stream_position()?;
             page_table.insert(page_index as u64, page_start);
 
-            let len = page.point_positions().len() as u64;
-            writer.write_u64::<LittleEndian>(len)?;
-
-            // Write the point positions
-            let positions_ptr = page.point_positions.as_ptr() as *const u8;
-            let positions_size = page.point_positions.len() * std::mem::size_of::<Vector3<f32>>();
-            let positions: &[u8] = unsafe { std::slice::from_raw_parts(positions_ptr, positions_size) };
-            let _ = writer.write(positions)?;
-
-            // Write the point colors
-            let colors_ptr = page.point_colors.as_ptr() as *const u8;
-            let colors_size = page.point_colors.len() * std::mem::size_of::<ByteColor3>();
-            let colors: &[u8] = unsafe { std::slice::from_raw_parts(colors_ptr, colors_size) };
-            let _ = writer.write(colors)?;
-
-            // Write the page
-            writer.write_u64::<LittleEndian>(page.clusters.len() as u64)?;
-            for cluster in &page.clusters {
-                writer.write_u64::<LittleEndian>(cluster.index_start as u64)?;
-                writer.write_u64::<LittleEndian>(cluster.len as u64)?;
-                writer.write_f32::<LittleEndian>(cluster.aabb.min.x)?;
-                writer.write_f32::<LittleEndian>(cluster.aabb.min.y)?;
-                writer.write_f32::<LittleEndian>(cluster.aabb.min.z)?;
-                writer.write_f32::<LittleEndian>(cluster.aabb.max.x)?;
-                writer.write_f32::<LittleEndian>(cluster.aabb.max.y)?;
-                writer.write_f32::<LittleEndian>(cluster.aabb.max.z)?;
-                writer.write_f32::<LittleEndian>(cluster.center.x)?;
-                writer.write_f32::<LittleEndian>(cluster.center.y)?;
-                writer.write_f32::<LittleEndian>(cluster.center.z)?;
-                writer.write_f32::<LittleEndian>(cluster.radius)?;
-                writer.write_u64::<LittleEndian>(cluster.depth as u64)?;
-                writer.write_u64::<LittleEndian>(cluster.level as u64)?;
-                writer.write_u64::<LittleEndian>(cluster.children.len() as u64)?;
-                for child in &cluster.children {
-                    writer.write_u64::<LittleEndian>(child.page_index as u64)?;
-                    writer.write_u64::<LittleEndian>(child.cluster_index as u64)?;
+            match zstd_level {
+                Some(zstd_level) => {
+                    let mut uncompressed = Vec::new();
+                    Self::serialize_page_into(page, &mut uncompressed)?;
+                    let compressed = zstd::bulk::compress(&uncompressed, zstd_level)?;
+                    writer.write_u64::<LittleEndian>(uncompressed.len() as u64)?;
+                    writer.write_u64::<LittleEndian>(compressed.len() as u64)?;
+                    writer.write_all(&compressed)?;
                 }
+                None => Self::serialize_page_into(page, &mut writer)?,
             }
         }
         Ok(page_table)
     }
 
+    /// Serializes a single `Page` into a stream.
+    fn serialize_page_into<W: Write>(page: &Page, mut writer: W) -> io::Result<()> {
+        let len = page.point_positions().len() as u64;
+        writer.write_u64::<LittleEndian>(len)?;
+
+        // Write the point positions
+        let positions_ptr = page.point_positions.as_ptr() as *const u8;
+        let positions_size = page.point_positions.len() * std::mem::size_of::<Vector3<f32>>();
+        let positions: &[u8] = unsafe { std::slice::from_raw_parts(positions_ptr, positions_size) };
+        let _ = writer.write(positions)?;
+
+        // Write the point colors
+        let colors_ptr = page.point_colors.as_ptr() as *const u8;
+        let colors_size = page.point_colors.len() * std::mem::size_of::<ByteColor3>();
+        let colors: &[u8] = unsafe { std::slice::from_raw_parts(colors_ptr, colors_size) };
+        let _ = writer.write(colors)?;
+
+        // Write the point normals
+        let normals_ptr = page.point_normals.as_ptr() as *const u8;
+        let normals_size = page.point_normals.len() * std::mem::size_of::<Vector3<f32>>();
+        let normals: &[u8] = unsafe { std::slice::from_raw_parts(normals_ptr, normals_size) };
+        let _ = writer.write(normals)?;
+
+        // Write the page
+        writer.write_u64::<LittleEndian>(page.clusters.len() as u64)?;
+        for cluster in &page.clusters {
+            writer.write_u64::<LittleEndian>(cluster.index_start as u64)?;
+            writer.write_u64::<LittleEndian>(cluster.len as u64)?;
+            writer.write_f32::<LittleEndian>(cluster.aabb.min.x)?;
+            writer.write_f32::<LittleEndian>(cluster.aabb.min.y)?;
+            writer.write_f32::<LittleEndian>(cluster.aabb.min.z)?;
+            writer.write_f32::<LittleEndian>(cluster.aabb.max.x)?;
+            writer.write_f32::<LittleEndian>(cluster.aabb.max.y)?;
+            writer.write_f32::<LittleEndian>(cluster.aabb.max.z)?;
+            writer.write_f32::<LittleEndian>(cluster.center.x)?;
+            writer.write_f32::<LittleEndian>(cluster.center.y)?;
+            writer.write_f32::<LittleEndian>(cluster.center.z)?;
+            writer.write_f32::<LittleEndian>(cluster.radius)?;
+            writer.write_u64::<LittleEndian>(cluster.depth as u64)?;
+            writer.write_u64::<LittleEndian>(cluster.level as u64)?;
+            writer.write_u64::<LittleEndian>(cluster.children.len() as u64)?;
+            for child in &cluster.children {
+                writer.write_u64::<LittleEndian>(child.page_index as u64)?;
+                writer.write_u64::<LittleEndian>(child.cluster_index as u64)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Serializes the `PointCloud` into a stream.
-    pub fn serialize_into<W: Write + Seek>(&self, mut writer: W) -> io::Result<()> {
+    pub fn serialize_into<W: Write + Seek>(&self, writer: W) -> io::Result<()> {
+        self.serialize_with_compression_into(writer, None)
+    }
+
+    /// Serializes the `PointCloud` into a stream and compresses the `Page`s with the given zstd level. The `Page`s are
+    /// not compressed when `zstd_level` is `None`.
+    pub fn serialize_with_compression_into<W: Write + Seek>(&self, mut writer: W, zstd_level: Option<i32>) -> io::Result<()> {
         // Leave space for the header
-        let header_size = 4 * std::mem::size_of::<u64>();
-        writer.seek(SeekFrom::Start(header_size as u64))?;
+        let header_size = FORMAT_HEADER_SIZE + FORMAT_FLAGS_SIZE + 4 * std::mem::size_of::<u64>() as u64;
+        writer.seek(SeekFrom::Start(header_size))?;
 
         // Write the pages
         let pages_offset = writer.stream_position()?;
-        let page_table = self.serlialize_pages_into(&mut writer)?;
+        let page_table = self.serlialize_pages_into(&mut writer, zstd_level)?;
 
         // Write the page table
         let page_table_offset = writer.stream_position()?;
@@ -525,6 +611,8 @@ impl ClusteredPointCloudAsset {
 
         // Write header at the start of the stream
         writer.rewind()?;
+        write_format_header(&mut writer, POINT_CLOUD_MAGIC, POINT_CLOUD_FORMAT_VERSION)?;
+        write_format_flags(&mut writer, compression_flags(zstd_level))?;
         self.serialize_header_into(&mut writer, pages_offset, page_table_offset)?;
 
         Ok(())
@@ -555,90 +643,129 @@ impl ClusteredPointCloudAsset {
     }
 
     /// Deserializes the header of the `PointCloud` from a stream.
-    fn deserialize_header_from<R: Read + Seek>(mut reader: R) -> io::Result<(u64, u64, u64, u64)> {
+    fn deserialize_header_from<R: Read + Seek>(mut reader: R) -> crate::Result<(u64, u64, u64, u64, FormatFlags)> {
+        let flags = match read_format_version(&mut reader, POINT_CLOUD_MAGIC)? {
+            0 | 1 => FormatFlags::empty(),
+            POINT_CLOUD_FORMAT_VERSION => read_format_flags(&mut reader)?,
+            found => {
+                return Err(Error::UnsupportedAssetVersion {
+                    found,
+                    expected: POINT_CLOUD_FORMAT_VERSION,
+                })
+            }
+        };
         let root_cluster_page_index = reader.read_u64::<LittleEndian>()?;
         let root_cluster_index = reader.read_u64::<LittleEndian>()?;
         let pages_offset = reader.read_u64::<LittleEndian>()?;
         let page_table_offset = reader.read_u64::<LittleEndian>()?;
-        Ok((root_cluster_page_index, root_cluster_index, pages_offset, page_table_offset))
+        Ok((root_cluster_page_index, root_cluster_index, pages_offset, page_table_offset, flags))
     }
 
     /// Deserializes the `Page`s from a stream.
-    fn deserialize_pages_from<R: Read + Seek>(mut reader: R, page_offsets: &Vec<(u64, u64)>) -> io::Result<Vec<Page>> {
+    fn deserialize_pages_from<R: Read + Seek>(mut reader: R, page_offsets: &Vec<(u64, u64)>, flags: FormatFlags) -> io::Result<Vec<Page>> {
         let mut pages = Vec::<Page>::new();
         for (_page_index, page_offset) in page_offsets {
-            reader.seek(SeekFrom::Start(*page_offset))?;
-
-            let len = reader.read_u64::<LittleEndian>()? as usize;
-
-            // Read the point positions
-            let mut point_positions = Vec::<Vector3<f32>>::with_capacity(len);
-            let positions_size = len * std::mem::size_of::<Vector3<f32>>();
-            let mut positions = vec![0u8; positions_size];
-            reader.read_exact(&mut positions)?;
-            let positions_ptr = positions.as_ptr() as *const Vector3<f32>;
-            let positions: &[Vector3<f32>] = unsafe { std::slice::from_raw_parts(positions_ptr, len) };
-            point_positions.extend_from_slice(positions);
-
-            // Read the point colors
-            let mut point_colors = Vec::<ByteColor3>::with_capacity(len);
-            let colors_size = len * std::mem::size_of::<ByteColor3>();
-            let mut colors = vec![0u8; colors_size];
-            reader.read_exact(&mut colors)?;
-            let colors_ptr = colors.as_ptr() as *const ByteColor3;
-            let colors: &[ByteColor3] = unsafe { std::slice::from_raw_parts(colors_ptr, len) };
-            point_colors.extend_from_slice(colors);
-
-            // Read the clusters
-            let clusters_len = reader.read_u64::<LittleEndian>()? as usize;
-            let mut clusters = Vec::<Cluster>::with_capacity(clusters_len);
-            for _ in 0..clusters_len {
-                let index_start = reader.read_u64::<LittleEndian>()? as u32;
-                let len = reader.read_u64::<LittleEndian>()? as u32;
-                let min_x = reader.read_f32::<LittleEndian>()?;
-                let min_y = reader.read_f32::<LittleEndian>()?;
-                let min_z = reader.read_f32::<LittleEndian>()?;
-                let max_x = reader.read_f32::<LittleEndian>()?;
-                let max_y = reader.read_f32::<LittleEndian>()?;
-                let max_z = reader.read_f32::<LittleEndian>()?;
-                let center_x = reader.read_f32::<LittleEndian>()?;
-                let center_y = reader.read_f32::<LittleEndian>()?;
-                let center_z = reader.read_f32::<LittleEndian>()?;
-                let radius = reader.read_f32::<LittleEndian>()?;
-                let depth = reader.read_u64::<LittleEndian>()? as usize;
-                let level = reader.read_u64::<LittleEndian>()? as usize;
-                let children_len = reader.read_u64::<LittleEndian>()? as usize;
-                let mut children = Vec::<ClusterIndex>::with_capacity(children_len);
-                for _ in 0..children_len {
-                    let page_index = reader.read_u64::<LittleEndian>()? as usize;
-                    let cluster_index = reader.read_u64::<LittleEndian>()? as usize;
-                    children.push(ClusterIndex { page_index, cluster_index });
-                }
-                clusters.push(Cluster {
-                    index_start,
-                    len,
-                    aabb: AABB::new(Vector3::new(min_x, min_y, min_z), Vector3::new(max_x, max_y, max_z)),
-                    center: Vector3::new(center_x, center_y, center_z),
-                    radius,
-                    depth,
-                    level,
-                    children,
-                });
-            }
+            pages.push(Self::deserialize_page_at(&mut reader, *page_offset, flags)?);
+        }
+        Ok(pages)
+    }
 
-            pages.push(Page {
-                point_positions,
-                point_colors,
-                clusters,
+    /// Deserializes a single `Page` that starts at `page_offset` in the stream.
+    fn deserialize_page_at<R: Read + Seek>(mut reader: R, page_offset: u64, flags: FormatFlags) -> io::Result<Page> {
+        reader.seek(SeekFrom::Start(page_offset))?;
+        if flags.contains(FormatFlags::ZSTD) {
+            let uncompressed_len = reader.read_u64::<LittleEndian>()? as usize;
+            let compressed_len = reader.read_u64::<LittleEndian>()? as usize;
+            let mut compressed = vec![0u8; compressed_len];
+            reader.read_exact(&mut compressed)?;
+            let uncompressed = zstd::bulk::decompress(&compressed, uncompressed_len)?;
+            Self::deserialize_page_content_from(Cursor::new(uncompressed))
+        } else {
+            Self::deserialize_page_content_from(reader)
+        }
+    }
+
+    /// Deserializes the uncompressed content of a single `Page` from a stream.
+    fn deserialize_page_content_from<R: Read>(mut reader: R) -> io::Result<Page> {
+        let len = reader.read_u64::<LittleEndian>()? as usize;
+
+        // Read the point positions
+        let mut point_positions = Vec::<Vector3<f32>>::with_capacity(len);
+        let positions_size = len * std::mem::size_of::<Vector3<f32>>();
+        let mut positions = vec![0u8; positions_size];
+        reader.read_exact(&mut positions)?;
+        let positions_ptr = positions.as_ptr() as *const Vector3<f32>;
+        let positions: &[Vector3<f32>] = unsafe { std::slice::from_raw_parts(positions_ptr, len) };
+        point_positions.extend_from_slice(positions);
+
+        // Read the point colors
+        let mut point_colors = Vec::<ByteColor3>::with_capacity(len);
+        let colors_size = len * std::mem::size_of::<ByteColor3>();
+        let mut colors = vec![0u8; colors_size];
+        reader.read_exact(&mut colors)?;
+        let colors_ptr = colors.as_ptr() as *const ByteColor3;
+        let colors: &[ByteColor3] = unsafe { std::slice::from_raw_parts(colors_ptr, len) };
+        point_colors.extend_from_slice(colors);
+
+        // Read the point normals
+        let mut point_normals = Vec::<Vector3<f32>>::with_capacity(len);
+        let normals_size = len * std::mem::size_of::<Vector3<f32>>();
+        let mut normals = vec![0u8; normals_size];
+        reader.read_exact(&mut normals)?;
+        let normals_ptr = normals.as_ptr() as *const Vector3<f32>;
+        let normals: &[Vector3<f32>] = unsafe { std::slice::from_raw_parts(normals_ptr, len) };
+        point_normals.extend_from_slice(normals);
+
+        // Read the clusters
+        let clusters_len = reader.read_u64::<LittleEndian>()? as usize;
+        let mut clusters = Vec::<Cluster>::with_capacity(clusters_len);
+        for _ in 0..clusters_len {
+            let index_start = reader.read_u64::<LittleEndian>()? as u32;
+            let len = reader.read_u64::<LittleEndian>()? as u32;
+            let min_x = reader.read_f32::<LittleEndian>()?;
+            let min_y = reader.read_f32::<LittleEndian>()?;
+            let min_z = reader.read_f32::<LittleEndian>()?;
+            let max_x = reader.read_f32::<LittleEndian>()?;
+            let max_y = reader.read_f32::<LittleEndian>()?;
+            let max_z = reader.read_f32::<LittleEndian>()?;
+            let center_x = reader.read_f32::<LittleEndian>()?;
+            let center_y = reader.read_f32::<LittleEndian>()?;
+            let center_z = reader.read_f32::<LittleEndian>()?;
+            let radius = reader.read_f32::<LittleEndian>()?;
+            let depth = reader.read_u64::<LittleEndian>()? as usize;
+            let level = reader.read_u64::<LittleEndian>()? as usize;
+            let children_len = reader.read_u64::<LittleEndian>()? as usize;
+            let mut children = Vec::<ClusterIndex>::with_capacity(children_len);
+            for _ in 0..children_len {
+                let page_index = reader.read_u64::<LittleEndian>()? as usize;
+                let cluster_index = reader.read_u64::<LittleEndian>()? as usize;
+                children.push(ClusterIndex { page_index, cluster_index });
+            }
+            clusters.push(Cluster {
+                index_start,
+                len,
+                aabb: AABB::new(Vector3::new(min_x, min_y, min_z), Vector3::new(max_x, max_y, max_z)),
+                center: Vector3::new(center_x, center_y, center_z),
+                radius,
+                depth,
+                level,
+                children,
             });
         }
-        Ok(pages)
+
+        Ok(Page {
+            point_positions,
+            point_colors,
+            point_normals,
+            clusters,
+        })
     }
 
     /// Deserializes the `PointCloud` from a stream.
-    pub fn deserialize_from<R: Read + Seek>(mut reader: R) -> io::Result<Self> {
+    pub fn deserialize_from<R: Read + Seek>(mut reader: R) -> crate::Result<Self> {
         // Read the header
-        let (root_cluster_page_index, root_cluster_index, pages_offset, page_table_offset) = Self::deserialize_header_from(&mut reader)?;
+        let (root_cluster_page_index, root_cluster_index, pages_offset, page_table_offset, flags) =
+            Self::deserialize_header_from(&mut reader)?;
 
         // Read the page table
         reader.seek(SeekFrom::Start(page_table_offset))?;
@@ -646,7 +773,7 @@ impl ClusteredPointCloudAsset {
 
         // Read the pages
         reader.seek(SeekFrom::Start(pages_offset))?;
-        let pages = Self::deserialize_pages_from(&mut reader, &page_offsets)?;
+        let pages = Self::deserialize_pages_from(&mut reader, &page_offsets, flags)?;
 
         let root_cluster_index = ClusterIndex {
             page_index: root_cluster_page_index as usize,
@@ -677,11 +804,57 @@ impl ClusteredPointCloudAsset {
     /// Deserializes the `Page` table from a file.
     pub fn deserialize_page_table_from_file(filepath: &impl AsRef<Path>) -> crate::Result<HashMap<u64, u64>> {
         let mut file = File::open(filepath).expect("Failed to open file");
-        let (_, _, _, page_table_offset) = Self::deserialize_header_from(&mut file)?;
+        let (_, _, _, page_table_offset, _) = Self::deserialize_header_from(&mut file)?;
         file.seek(SeekFrom::Start(page_table_offset)).expect("Failed to seek to page table");
         let page_table = Self::deserialize_page_table_from(&mut file)?;
         Ok(page_table.into_iter().collect::<HashMap<_, _>>())
     }
+
+    /// Deserializes the [`PageStreamingInfo`] from a stream without reading any `Page`s.
+    pub fn deserialize_page_streaming_info_from<R: Read + Seek>(mut reader: R) -> crate::Result<PageStreamingInfo> {
+        let (root_cluster_page_index, root_cluster_index, _, page_table_offset, _) = Self::deserialize_header_from(&mut reader)?;
+        reader.seek(SeekFrom::Start(page_table_offset))?;
+        let pages_len = reader.read_u64::<LittleEndian>()? as usize;
+        Ok(PageStreamingInfo {
+            root_cluster_index: ClusterIndex {
+                page_index: root_cluster_page_index as usize,
+                cluster_index: root_cluster_index as usize,
+            },
+            pages_len,
+        })
+    }
+
+    /// Deserializes the `Page` with the given `page_index` from a stream without reading the other `Page`s.
+    pub fn deserialize_page_from<R: Read + Seek>(mut reader: R, page_index: usize) -> crate::Result<Page> {
+        let (_, _, _, page_table_offset, flags) = Self::deserialize_header_from(&mut reader)?;
+        reader.seek(SeekFrom::Start(page_table_offset))?;
+        let page_table = Self::deserialize_page_table_from(&mut reader)?;
+        let (_, page_offset) = page_table
+            .into_iter()
+            .find(|(index, _)| *index == page_index as u64)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Page {page_index} not found in the page table"),
+                )
+            })?;
+        Ok(Self::deserialize_page_at(&mut reader, page_offset, flags)?)
+    }
+
+    /// Deserializes the `Page` with the given `page_index` from a file.
+    pub fn deserialize_page_from_file(filepath: &impl AsRef<Path>, page_index: usize) -> crate::Result<Page> {
+        let mut file = File::open(filepath)?;
+        Self::deserialize_page_from(&mut file, page_index)
+    }
+}
+
+/// Information about a serialized [`ClusteredPointCloudAsset`] that is required to stream its `Page`s individually
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PageStreamingInfo {
+    /// Index of the root `Cluster`
+    pub root_cluster_index: ClusterIndex,
+    /// Number of `Page`s in the serialized `ClusteredPointCloudAsset`
+    pub pages_len: usize,
 }
 
 impl std::fmt::Debug for ClusteredPointCloudAsset {
@@ -729,6 +902,84 @@ mod tests {
         clustered_point_cloud.write_statisics(&directory.join("statistics.json")).unwrap();
     }
 
+    #[test]
+    fn pack_clusters_into_pages() {
+        let simple_point_cloud =
+            SimplePointCloud::sample_from_model(&ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap(), 200.0, 1.0);
+        let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+
+        // Only the last page might not be full
+        let pages = clustered_point_cloud.pages();
+        for page in &pages[..pages.len() - 1] {
+            assert_eq!(page.clusters().len(), Page::MAX_CLUSTERS);
+        }
+
+        // The root cluster is the last one that has been packed
+        let root_cluster_index = clustered_point_cloud.root_cluster_index();
+        assert_eq!(root_cluster_index.page_index, pages.len() - 1);
+        assert_eq!(root_cluster_index.cluster_index, pages[pages.len() - 1].clusters().len() - 1);
+        assert_eq!(
+            pages[root_cluster_index.page_index].clusters()[root_cluster_index.cluster_index].depth,
+            0
+        );
+
+        // The children are packed before their parents and are one level deeper
+        for (page_index, page) in pages.iter().enumerate() {
+            for (cluster_index, cluster) in page.clusters().iter().enumerate() {
+                for child in &cluster.children {
+                    assert!((child.page_index, child.cluster_index) < (page_index, cluster_index));
+                    assert_eq!(pages[child.page_index].clusters()[child.cluster_index].depth, cluster.depth + 1);
+                }
+            }
+        }
+
+        let points_len = pages.iter().map(|page| page.point_positions().len()).sum::<usize>();
+        assert!(points_len >= simple_point_cloud.len());
+    }
+
+    #[test]
+    fn cluster_bounding_sphere() {
+        // The sphere around the centroid (1, 0, 0) would have a radius of 9 whereas the tightest sphere has a radius of 5
+        let mut point_positions = vec![Vector3::zeros(); 9];
+        point_positions.push(Vector3::new(10.0, 0.0, 0.0));
+        let point_colors = vec![ByteColor3::new(255, 255, 255); point_positions.len()];
+        let point_normals = vec![Vector3::y(); point_positions.len()];
+        let mut page = Page::new();
+        page.push(point_positions.iter(), point_colors.iter(), point_normals.iter(), 0, 0, Vec::new());
+        let cluster = &page.clusters()[0];
+        assert_eq!(cluster.center, Vector3::new(5.0, 0.0, 0.0));
+        assert_eq!(cluster.radius, 5.0);
+    }
+
+    #[test]
+    fn lod_ratio() {
+        let mut simple_point_cloud = SimplePointCloud::new();
+        for (x, y, z) in jeriya_shared::itertools::iproduct!(0..16, 0..16, 0..16) {
+            let position = Vector3::new(x as f32, y as f32, z as f32);
+            simple_point_cloud.push_with_normal(position, ByteColor3::new(255, 255, 255), Vector3::y());
+        }
+        let points_len = |clustered_point_cloud: &ClusteredPointCloudAsset| {
+            let root_cluster_index = clustered_point_cloud.root_cluster_index();
+            let root_page = &clustered_point_cloud.pages()[root_cluster_index.page_index];
+            let total = clustered_point_cloud
+                .pages()
+                .iter()
+                .map(|page| page.point_positions().len())
+                .sum::<usize>();
+            (root_page.clusters()[root_cluster_index.cluster_index].len, total)
+        };
+        let (dense_root_len, dense_len) = points_len(&ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud));
+        let (sparse_root_len, sparse_len) = points_len(&ClusteredPointCloudAsset::from_simple_point_cloud_with_lod_ratio(
+            &simple_point_cloud,
+            0.25,
+        ));
+        assert_eq!(dense_root_len as usize, Cluster::MAX_POINTS);
+        assert!(sparse_root_len < dense_root_len);
+        assert!(sparse_len < dense_len);
+        // The leaf clusters still contain all points
+        assert!(sparse_len >= simple_point_cloud.len());
+    }
+
     #[test]
     fn serialize_and_deserialize() {
         let simple_point_cloud =
@@ -752,4 +1003,79 @@ mod tests {
         let result = ClusteredPointCloudAsset::deserialize_page_table_from_file(&filepath).unwrap();
         assert_eq!(clustered_point_cloud.pages.len(), result.len());
     }
+
+    #[test]
+    fn deserialize_single_pages() {
+        let simple_point_cloud =
+            SimplePointCloud::sample_from_model(&ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap(), 200.0, 1.0);
+        let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+        let mut file = Cursor::new(Vec::new());
+        clustered_point_cloud.serialize_into(&mut file).unwrap();
+
+        file.rewind().unwrap();
+        let page_streaming_info = ClusteredPointCloudAsset::deserialize_page_streaming_info_from(&mut file).unwrap();
+        assert_eq!(page_streaming_info.root_cluster_index, clustered_point_cloud.root_cluster_index);
+        assert_eq!(page_streaming_info.pages_len, clustered_point_cloud.pages.len());
+
+        for (page_index, page) in clustered_point_cloud.pages.iter().enumerate().rev() {
+            file.rewind().unwrap();
+            let result = ClusteredPointCloudAsset::deserialize_page_from(&mut file, page_index).unwrap();
+            assert_eq!(page, &result);
+        }
+
+        file.rewind().unwrap();
+        let result = ClusteredPointCloudAsset::deserialize_page_from(&mut file, clustered_point_cloud.pages.len());
+        assert!(matches!(result, Err(Error::IoError(err)) if err.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn serialize_and_deserialize_compressed() {
+        let simple_point_cloud =
+            SimplePointCloud::sample_from_model(&ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap(), 200.0, 1.0);
+        let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+        let mut uncompressed = Cursor::new(Vec::new());
+        clustered_point_cloud.serialize_into(&mut uncompressed).unwrap();
+        let mut file = Cursor::new(Vec::new());
+        clustered_point_cloud.serialize_with_compression_into(&mut file, Some(3)).unwrap();
+        assert!(file.get_ref().len() < uncompressed.get_ref().len());
+
+        file.rewind().unwrap();
+        let result = ClusteredPointCloudAsset::deserialize_from(&mut file).unwrap();
+        assert_eq!(clustered_point_cloud, result);
+
+        for (page_index, page) in clustered_point_cloud.pages.iter().enumerate() {
+            file.rewind().unwrap();
+            let result = ClusteredPointCloudAsset::deserialize_page_from(&mut file, page_index).unwrap();
+            assert_eq!(page, &result);
+        }
+    }
+
+    #[test]
+    fn deserialize_unversioned_and_unsupported_versions() {
+        let simple_point_cloud =
+            SimplePointCloud::sample_from_model(&ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap(), 200.0, 1.0);
+        let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+        let mut file = Cursor::new(Vec::new());
+        clustered_point_cloud.serialize_into(&mut file).unwrap();
+
+        // Files written before the format was versioned start with the header but the absolute offsets are the same. The
+        // space of the format header and the flags is filled with zeros to keep the offsets valid.
+        let bytes = file.into_inner();
+        let prefix_size = (FORMAT_HEADER_SIZE + FORMAT_FLAGS_SIZE) as usize;
+        let header_end = prefix_size + 32;
+        let unversioned = [&bytes[prefix_size..header_end], &vec![0; prefix_size], &bytes[header_end..]].concat();
+        let result = ClusteredPointCloudAsset::deserialize_from(Cursor::new(unversioned)).unwrap();
+        assert_eq!(clustered_point_cloud, result);
+
+        let mut newer = bytes;
+        newer[4..8].copy_from_slice(&(POINT_CLOUD_FORMAT_VERSION + 1).to_le_bytes());
+        let result = ClusteredPointCloudAsset::deserialize_from(Cursor::new(newer));
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedAssetVersion {
+                expected: POINT_CLOUD_FORMAT_VERSION,
+                ..
+            })
+        ));
+    }
 }
@@ -2,12 +2,15 @@ use jeriya_shared::{
     aabb::AABB,
     log::{info, trace},
     nalgebra::Vector3,
-    rayon::iter::{IntoParallelRefIterator, ParallelIterator},
+    rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
 };
 
 /// Information for creating the `PointClusteringOctree`.
 pub struct BuildContext<'c> {
     pub cluster_point_count: usize,
+    /// Fraction of the points of two merged clusters that is kept in the merged cluster. The merged cluster never
+    /// contains more than `cluster_point_count` points.
+    pub lod_ratio: f32,
     pub point_positions: &'c [Vector3<f32>],
 }
 
@@ -173,8 +176,10 @@ impl PointClusteringOctree {
             .copied()
             .collect::<Vec<_>>();
 
+        let kept_point_count =
+            ((indices.len() as f32 * build_context.lod_ratio).ceil() as usize).clamp(1, build_context.cluster_point_count);
         let mut i = 0;
-        while indices.len() > build_context.cluster_point_count {
+        while indices.len() > kept_point_count {
             indices.remove(i);
             i += 2;
             if i >= indices.len() {
@@ -265,9 +270,9 @@ impl PointClusteringOctree {
             "the number of points in the quadrants should be equal to the number of points in the node"
         );
 
-        // Create a node for each quadrant and continue recursively
+        // Create a node for each quadrant and continue recursively. The subtrees are built in parallel.
         let child_quadrant_clusters: [Option<ProtoCluster>; 8] = quadrants_indices
-            .into_iter()
+            .into_par_iter()
             .enumerate()
             .map(|(quadrant_index, quadrant_indices)| {
                 let quadrant_aabb = Self::quadrant_aabb(aabb, quadrant_index);
@@ -0,0 +1,305 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+use jeriya_shared::{
+    aabb::AABB,
+    byteorder::{LittleEndian, WriteBytesExt},
+    log::info,
+    nalgebra::Vector3,
+    serde_json::{self, json},
+    ByteColor3,
+};
+
+use super::clustered_point_cloud::ClusteredPointCloudAsset;
+
+/// Number of `Cluster` depths that are mapped onto one level of the Potree octree. The number of points
+/// roughly doubles with every `Cluster` depth whereas it roughly quadruples with every octree level on
+/// the surfaces that are typically scanned.
+const CLUSTER_DEPTHS_PER_OCTREE_LEVEL: usize = 2;
+
+/// Deepest level of the Potree octree. Points of deeper `Cluster`s are written into the nodes of this level.
+const MAX_OCTREE_LEVEL: usize = 20;
+
+/// Size of a point in the `octree.bin` file: 3 x int32 for the position and 3 x uint16 for the color
+const POINT_SIZE: usize = 3 * 4 + 3 * 2;
+
+/// Size of a node in the `hierarchy.bin` file
+const NODE_SIZE: usize = 22;
+
+/// Node types of the Potree 2.0 hierarchy
+const NODE_TYPE_NORMAL: u8 = 0;
+const NODE_TYPE_LEAF: u8 = 1;
+
+/// Location of a node in the Potree octree. The coordinates are the indices of the cell on the given level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct NodeKey {
+    level: usize,
+    x: u32,
+    y: u32,
+    z: u32,
+}
+
+impl NodeKey {
+    const ROOT: Self = Self {
+        level: 0,
+        x: 0,
+        y: 0,
+        z: 0,
+    };
+
+    fn parent(&self) -> Option<Self> {
+        (self.level > 0).then(|| Self {
+            level: self.level - 1,
+            x: self.x >> 1,
+            y: self.y >> 1,
+            z: self.z >> 1,
+        })
+    }
+
+    /// Returns the child in the given octant. Potree encodes x in bit 2, y in bit 1 and z in bit 0.
+    fn child(&self, octant: u32) -> Self {
+        Self {
+            level: self.level + 1,
+            x: (self.x << 1) | ((octant >> 2) & 1),
+            y: (self.y << 1) | ((octant >> 1) & 1),
+            z: (self.z << 1) | (octant & 1),
+        }
+    }
+}
+
+impl ClusteredPointCloudAsset {
+    /// Writes the point cloud into the given directory in the Potree 2.0 format (`metadata.json`, `hierarchy.bin`
+    /// and `octree.bin`) so that it can be inspected in web-based viewers like Potree.
+    ///
+    /// Potree uses additive level of detail. Every point is therefore written once into the octree node of
+    /// the shallowest `Cluster` it appears in.
+    pub fn write_potree(&self, directory: &impl AsRef<Path>) -> crate::Result<()> {
+        let start = Instant::now();
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+
+        let (point_positions, point_colors, point_levels) = self.collect_points_for_potree();
+        let (tight_aabb, cube_min, cube_size) = potree_bounds(&point_positions);
+
+        // Sort the points into the nodes of the octree and make sure that all ancestors exist
+        let mut nodes = BTreeMap::<NodeKey, Vec<usize>>::new();
+        nodes.insert(NodeKey::ROOT, Vec::new());
+        for (point_index, (position, level)) in point_positions.iter().zip(&point_levels).enumerate() {
+            let cell_count = 1u32 << level;
+            let cell = |value: f32, min: f32| (((value - min) / cube_size * cell_count as f32) as u32).min(cell_count - 1);
+            let mut key = NodeKey {
+                level: *level,
+                x: cell(position.x, cube_min.x),
+                y: cell(position.y, cube_min.y),
+                z: cell(position.z, cube_min.z),
+            };
+            nodes.entry(key).or_default().push(point_index);
+            while let Some(parent) = key.parent() {
+                nodes.entry(parent).or_default();
+                key = parent;
+            }
+        }
+
+        // Scale and offset that are used to encode the positions as integers
+        let offset = cube_min.cast::<f64>();
+        let scale = (cube_size as f64 / (1u64 << 30) as f64).max(f64::EPSILON);
+
+        // Potree expects the nodes of a hierarchy chunk in breadth-first order
+        let mut octree_writer = BufWriter::new(File::create(directory.join("octree.bin"))?);
+        let mut hierarchy_writer = BufWriter::new(File::create(directory.join("hierarchy.bin"))?);
+        let mut queue = VecDeque::from([NodeKey::ROOT]);
+        let mut byte_offset = 0u64;
+        let mut node_count = 0usize;
+        let mut depth = 0;
+        while let Some(key) = queue.pop_front() {
+            let children = (0..8).filter(|octant| nodes.contains_key(&key.child(*octant))).collect::<Vec<_>>();
+            let child_mask = children.iter().fold(0u8, |mask, octant| mask | (1 << octant));
+            queue.extend(children.iter().map(|octant| key.child(*octant)));
+
+            let point_indices = &nodes[&key];
+            for point_index in point_indices {
+                let position = (point_positions[*point_index].cast::<f64>() - offset) / scale;
+                octree_writer.write_i32::<LittleEndian>(position.x.round() as i32)?;
+                octree_writer.write_i32::<LittleEndian>(position.y.round() as i32)?;
+                octree_writer.write_i32::<LittleEndian>(position.z.round() as i32)?;
+                let color = &point_colors[*point_index];
+                octree_writer.write_u16::<LittleEndian>(color.r as u16)?;
+                octree_writer.write_u16::<LittleEndian>(color.g as u16)?;
+                octree_writer.write_u16::<LittleEndian>(color.b as u16)?;
+            }
+
+            let byte_size = (point_indices.len() * POINT_SIZE) as u64;
+            let node_type = if child_mask == 0 { NODE_TYPE_LEAF } else { NODE_TYPE_NORMAL };
+            hierarchy_writer.write_u8(node_type)?;
+            hierarchy_writer.write_u8(child_mask)?;
+            hierarchy_writer.write_u32::<LittleEndian>(point_indices.len() as u32)?;
+            hierarchy_writer.write_u64::<LittleEndian>(byte_offset)?;
+            hierarchy_writer.write_u64::<LittleEndian>(byte_size)?;
+
+            byte_offset += byte_size;
+            node_count += 1;
+            depth = depth.max(key.level);
+        }
+        octree_writer.flush()?;
+        hierarchy_writer.flush()?;
+
+        let (color_min, color_max) = point_colors.iter().fold(([u8::MAX; 3], [u8::MIN; 3]), |(min, max), color| {
+            (
+                [min[0].min(color.r), min[1].min(color.g), min[2].min(color.b)],
+                [max[0].max(color.r), max[1].max(color.g), max[2].max(color.b)],
+            )
+        });
+        let name = directory.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let metadata = json!({
+            "version": "2.0",
+            "name": name,
+            "description": "",
+            "points": point_positions.len(),
+            "projection": "",
+            "hierarchy": {
+                "firstChunkSize": node_count * NODE_SIZE,
+                "stepSize": depth + 1,
+                "depth": depth,
+            },
+            "offset": [offset.x, offset.y, offset.z],
+            "scale": [scale, scale, scale],
+            "spacing": cube_size / 128.0,
+            "boundingBox": {
+                "min": [cube_min.x, cube_min.y, cube_min.z],
+                "max": [cube_min.x + cube_size, cube_min.y + cube_size, cube_min.z + cube_size],
+            },
+            "encoding": "DEFAULT",
+            "attributes": [
+                {
+                    "name": "position",
+                    "description": "",
+                    "size": 12,
+                    "numElements": 3,
+                    "elementSize": 4,
+                    "type": "int32",
+                    "min": [tight_aabb.min.x, tight_aabb.min.y, tight_aabb.min.z],
+                    "max": [tight_aabb.max.x, tight_aabb.max.y, tight_aabb.max.z],
+                },
+                {
+                    "name": "rgb",
+                    "description": "",
+                    "size": 6,
+                    "numElements": 3,
+                    "elementSize": 2,
+                    "type": "uint16",
+                    "min": color_min,
+                    "max": color_max,
+                },
+            ],
+        });
+        let metadata_file = File::create(directory.join("metadata.json"))?;
+        serde_json::to_writer_pretty(metadata_file, &metadata).map_err(io::Error::from)?;
+
+        info!(
+            "Writing {} points in {node_count} nodes in the Potree format took {} s",
+            point_positions.len(),
+            start.elapsed().as_secs_f32()
+        );
+        Ok(())
+    }
+
+    /// Traverses the `Cluster`s breadth-first and returns every point once together with the
+    /// octree level that corresponds to the shallowest `Cluster` the point appears in.
+    fn collect_points_for_potree(&self) -> (Vec<Vector3<f32>>, Vec<ByteColor3>, Vec<usize>) {
+        let mut visited = HashSet::<[u32; 3]>::new();
+        let mut point_positions = Vec::new();
+        let mut point_colors = Vec::new();
+        let mut point_levels = Vec::new();
+
+        let mut queue = VecDeque::from([self.root_cluster_index()]);
+        while let Some(cluster_index) = queue.pop_front() {
+            let page = &self.pages()[cluster_index.page_index];
+            let cluster = &page.clusters()[cluster_index.cluster_index];
+            let level = (cluster.depth / CLUSTER_DEPTHS_PER_OCTREE_LEVEL).min(MAX_OCTREE_LEVEL);
+            let range = cluster.index_start as usize..(cluster.index_start + cluster.len) as usize;
+            for (position, color) in page.point_positions()[range.clone()].iter().zip(&page.point_colors()[range]) {
+                if visited.insert([position.x.to_bits(), position.y.to_bits(), position.z.to_bits()]) {
+                    point_positions.push(*position);
+                    point_colors.push(*color);
+                    point_levels.push(level);
+                }
+            }
+            queue.extend(cluster.children.iter().cloned());
+        }
+
+        (point_positions, point_colors, point_levels)
+    }
+}
+
+/// Returns the tight bounding box of the points and the minimum and edge length of the cube that encloses them.
+fn potree_bounds(point_positions: &[Vector3<f32>]) -> (AABB, Vector3<f32>, f32) {
+    let tight_aabb = if point_positions.is_empty() {
+        AABB::new(Vector3::zeros(), Vector3::zeros())
+    } else {
+        AABB::from_slice(point_positions)
+    };
+    let cube_size = tight_aabb.size().max().max(f32::EPSILON);
+    (tight_aabb, tight_aabb.min, cube_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use jeriya_shared::function_name;
+    use jeriya_test::create_test_result_folder_for_function;
+
+    use crate::{model::ModelAsset, point_cloud::simple_point_cloud::SimplePointCloud};
+
+    use super::*;
+
+    #[test]
+    fn write_potree() {
+        let simple_point_cloud =
+            SimplePointCloud::sample_from_model(&ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap(), 200.0, 1.0);
+        let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+        let directory = create_test_result_folder_for_function(function_name!());
+        clustered_point_cloud.write_potree(&directory).unwrap();
+
+        let metadata: serde_json::Value = serde_json::from_reader(File::open(directory.join("metadata.json")).unwrap()).unwrap();
+        let points = metadata["points"].as_u64().unwrap() as usize;
+        let unique_points = simple_point_cloud
+            .point_positions()
+            .iter()
+            .map(|position| [position.x.to_bits(), position.y.to_bits(), position.z.to_bits()])
+            .collect::<HashSet<_>>();
+        assert_eq!(points, unique_points.len());
+
+        let mut hierarchy = Vec::new();
+        File::open(directory.join("hierarchy.bin"))
+            .unwrap()
+            .read_to_end(&mut hierarchy)
+            .unwrap();
+        assert_eq!(hierarchy.len() % NODE_SIZE, 0);
+        assert_eq!(metadata["hierarchy"]["firstChunkSize"].as_u64().unwrap() as usize, hierarchy.len());
+
+        // The nodes have to reference consecutive ranges of the octree file
+        let mut expected_byte_offset = 0;
+        let mut point_sum = 0;
+        for node in hierarchy.chunks(NODE_SIZE) {
+            let num_points = u32::from_le_bytes(node[2..6].try_into().unwrap()) as usize;
+            let byte_offset = u64::from_le_bytes(node[6..14].try_into().unwrap()) as usize;
+            let byte_size = u64::from_le_bytes(node[14..22].try_into().unwrap()) as usize;
+            assert_eq!(byte_offset, expected_byte_offset);
+            assert_eq!(byte_size, num_points * POINT_SIZE);
+            assert_eq!(node[0] == NODE_TYPE_LEAF, node[1] == 0);
+            expected_byte_offset += byte_size;
+            point_sum += num_points;
+        }
+        assert_eq!(point_sum, points);
+        assert_eq!(
+            fs::metadata(directory.join("octree.bin")).unwrap().len() as usize,
+            points * POINT_SIZE
+        );
+    }
+}
@@ -1,14 +1,27 @@
 use std::{
     collections::HashMap,
     fs::File,
-    io::{self, Write},
+    io::{self, BufReader, Write},
     path::Path,
     sync::Arc,
 };
 
 use jeriya_shared::{
-    aabb::AABB, float_cmp::approx_eq, log::info, nalgebra::Vector3, num_cpus, obj_writer::write_bounding_box_o, parking_lot::Mutex, rand,
-    random_direction, rayon, ByteColor3,
+    aabb::AABB,
+    float_cmp::approx_eq,
+    kdtree::{distance::squared_euclidean, KdTree},
+    log::info,
+    nalgebra::{Matrix3, Vector3},
+    num_cpus,
+    obj_writer::write_bounding_box_o,
+    parking_lot::Mutex,
+    rand, random_direction,
+    rayon::{self, prelude::*},
+    ByteColor3,
+};
+use ply_rs::{
+    parser::Parser,
+    ply::{DefaultElement, Property},
 };
 use serde::{Deserialize, Serialize};
 
@@ -25,9 +38,14 @@ pub struct SimplePointCloud {
     bounding_box: AABB,
     point_positions: Vec<Vector3<f32>>,
     point_colors: Vec<ByteColor3>,
+    /// Normals are only available when the source of the point cloud provides them
+    point_normals: Option<Vec<Vector3<f32>>>,
 }
 
 impl SimplePointCloud {
+    /// Number of neighbors that are considered when estimating the normal of a point
+    pub const NORMAL_ESTIMATION_NEIGHBOR_COUNT: usize = 16;
+
     /// Creates an empty `PointCloud`.
     pub fn new() -> Self {
         Self::default()
@@ -68,6 +86,7 @@ impl SimplePointCloud {
                     let mut aabb = AABB::empty();
                     let mut point_positions = Vec::new();
                     let mut point_colors = Vec::new();
+                    let mut point_normals = Vec::new();
                     for _ in 0..sample_cound_per_cpu {
                         // Pick a random mesh
                         let mesh_random = rand::random::<f32>();
@@ -99,11 +118,28 @@ impl SimplePointCloud {
                             a + (1.0 - alpha) * ab + (1.0 - beta) * ac
                         };
 
+                        // Interpolate the vertex normals
+                        let normal_a = mesh.simple_mesh.vertex_normals[triangle[0] as usize];
+                        let normal_b = mesh.simple_mesh.vertex_normals[triangle[1] as usize];
+                        let normal_c = mesh.simple_mesh.vertex_normals[triangle[2] as usize];
+                        let point_normal = if in_triangle {
+                            normal_a + alpha * (normal_b - normal_a) + beta * (normal_c - normal_a)
+                        } else {
+                            normal_a + (1.0 - alpha) * (normal_b - normal_a) + (1.0 - beta) * (normal_c - normal_a)
+                        };
+                        let point_normal = point_normal
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(|| ab.cross(&ac).normalize());
+
                         // Expand the AABB
                         aabb.include(&point_position);
 
-                        // Sample the point color
+                        // Sample the point color from the base color texture at the interpolated texture coordinates
                         const MISSING_COLOR: ByteColor3 = ByteColor3::new(255, 0, 0);
+                        let material = mesh
+                            .simple_mesh
+                            .material_index
+                            .map(|material_index| &model.materials[material_index]);
                         let point_color = if let Some(vertex_texture_coordinates) = &mesh.simple_mesh.vertex_texture_coordinates {
                             let uv_a = vertex_texture_coordinates[triangle[0] as usize];
                             let uv_b = vertex_texture_coordinates[triangle[1] as usize];
@@ -115,28 +151,23 @@ impl SimplePointCloud {
                             } else {
                                 uv_a + (1.0 - alpha) * uv_ab + (1.0 - beta) * uv_ac
                             };
-                            if let Some(material_index) = mesh.simple_mesh.material_index {
-                                let material = &model.materials[material_index];
-                                if let Some(base_color_texture_index) = &material.base_color_texture_index {
-                                    let base_color_texture = &model.textures[*base_color_texture_index];
-                                    base_color_texture.sample(uv).as_byte_color3()
-                                } else {
-                                    material.base_color_color.as_byte_color3()
-                                }
-                            } else {
-                                MISSING_COLOR
-                            }
+                            material.map_or(MISSING_COLOR, |material| {
+                                material.sample_base_color(&model.textures, uv).as_byte_color3()
+                            })
                         } else {
-                            MISSING_COLOR
+                            // Without texture coordinates only the constant base color can be used
+                            material.map_or(MISSING_COLOR, |material| material.base_color_color.as_byte_color3())
                         };
 
                         // Push the point to the point cloud
                         point_positions.push(scale * point_position);
                         point_colors.push(point_color);
+                        point_normals.push(point_normal);
                     }
                     let mut guard = simple_point_cloud.lock();
                     guard.point_positions.extend(point_positions);
                     guard.point_colors.extend(point_colors);
+                    guard.point_normals.get_or_insert_with(Vec::new).extend(point_normals);
                     guard.bounding_box.include(&aabb);
                 });
             }
@@ -145,6 +176,146 @@ impl SimplePointCloud {
         std::mem::take(&mut *guard)
     }
 
+    /// Imports a point cloud from a PLY file in ASCII or binary encoding.
+    ///
+    /// The points are read from the `vertex` element. Colors are read from the `red`, `green` and `blue`
+    /// properties and default to white. Normals are read from the `nx`, `ny` and `nz` properties when all points have them.
+    pub fn import_ply(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let mut reader = BufReader::new(File::open(&path)?);
+        let ply = Parser::<DefaultElement>::new().read_ply(&mut reader)?;
+        let vertices = ply
+            .payload
+            .get("vertex")
+            .ok_or_else(|| crate::Error::InvalidAssetData(path.as_ref().to_owned()))?;
+        info!("Importing {} points from '{}'", vertices.len(), path.as_ref().display());
+
+        let mut simple_point_cloud = Self::new();
+        let mut point_normals = Vec::with_capacity(vertices.len());
+        for vertex in vertices {
+            let vector = |x: &str, y: &str, z: &str| {
+                Some(Vector3::new(
+                    ply_scalar(vertex.get(x)?)?,
+                    ply_scalar(vertex.get(y)?)?,
+                    ply_scalar(vertex.get(z)?)?,
+                ))
+            };
+            let position = vector("x", "y", "z").ok_or_else(|| crate::Error::InvalidAssetData(path.as_ref().to_owned()))?;
+            let color = match (vertex.get("red"), vertex.get("green"), vertex.get("blue")) {
+                (Some(red), Some(green), Some(blue)) => ByteColor3::new(ply_color(red), ply_color(green), ply_color(blue)),
+                _ => ByteColor3::new(255, 255, 255),
+            };
+            simple_point_cloud.bounding_box.include(&position);
+            simple_point_cloud.point_positions.push(position);
+            simple_point_cloud.point_colors.push(color);
+            point_normals.push(vector("nx", "ny", "nz"));
+        }
+        simple_point_cloud.point_normals = point_normals.into_iter().collect::<Option<Vec<_>>>();
+        Ok(simple_point_cloud)
+    }
+
+    /// Estimates the normals of the points from their `neighbor_count` nearest neighbors.
+    ///
+    /// The normal is the direction in which the neighborhood has the least extent. Because the
+    /// orientation of this direction is ambiguous, the normals are oriented away from the center
+    /// of the bounding box which works well for scans of single objects.
+    pub fn estimate_normals(&self, neighbor_count: usize) -> Vec<Vector3<f32>> {
+        let mut kdtree = KdTree::new(3);
+        for (index, position) in self.point_positions.iter().enumerate() {
+            kdtree
+                .add([position.x, position.y, position.z], index)
+                .expect("failed to insert point into kdtree");
+        }
+        let center = self.bounding_box.center();
+        self.point_positions
+            .par_iter()
+            .map(|position| {
+                let neighbors = kdtree
+                    .nearest(&[position.x, position.y, position.z], neighbor_count, &squared_euclidean)
+                    .expect("failed to query the nearest neighbors");
+                let neighbor_positions = neighbors.iter().map(|(_, &index)| self.point_positions[index]);
+                let mean = neighbor_positions.clone().sum::<Vector3<f32>>() / neighbors.len() as f32;
+                let covariance = neighbor_positions
+                    .map(|neighbor_position| {
+                        let offset = neighbor_position - mean;
+                        offset * offset.transpose()
+                    })
+                    .sum::<Matrix3<f32>>();
+                let eigen = covariance.symmetric_eigen();
+                let (smallest_index, _) = eigen.eigenvalues.argmin();
+                let normal = eigen
+                    .eigenvectors
+                    .column(smallest_index)
+                    .into_owned()
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::y);
+                if normal.dot(&(position - center)) < 0.0 {
+                    -normal
+                } else {
+                    normal
+                }
+            })
+            .collect()
+    }
+
+    /// Reduces the number of points by merging all points in the same cell of a voxel grid with the cell size `grid_size`.
+    ///
+    /// Every occupied voxel results in a single point whose position and color are the averages of the points in the voxel.
+    /// The normals are averaged and normalized when the point cloud has normals.
+    ///
+    /// # Panics
+    ///
+    /// * If `grid_size` is not positive.
+    pub fn downsample_voxel(&self, grid_size: f32) -> Self {
+        jeriya_shared::assert!(grid_size > 0.0, "the grid size must be positive");
+
+        #[derive(Default)]
+        struct Voxel {
+            position_sum: Vector3<f64>,
+            color_sum: Vector3<u64>,
+            normal_sum: Vector3<f32>,
+            count: usize,
+        }
+
+        // The voxels are stored in the order in which they are encountered so that the result is deterministic
+        let mut voxel_indices = HashMap::<(i64, i64, i64), usize>::new();
+        let mut voxels = Vec::<Voxel>::new();
+        for (index, position) in self.point_positions.iter().enumerate() {
+            let cell = (position - self.bounding_box.min) / grid_size;
+            let key = (cell.x.floor() as i64, cell.y.floor() as i64, cell.z.floor() as i64);
+            let voxel_index = *voxel_indices.entry(key).or_insert_with(|| {
+                voxels.push(Voxel::default());
+                voxels.len() - 1
+            });
+            let voxel = &mut voxels[voxel_index];
+            let color = &self.point_colors[index];
+            voxel.position_sum += position.cast::<f64>();
+            voxel.color_sum += Vector3::new(color.r as u64, color.g as u64, color.b as u64);
+            if let Some(point_normals) = &self.point_normals {
+                voxel.normal_sum += point_normals[index];
+            }
+            voxel.count += 1;
+        }
+
+        let mut result = Self::new();
+        for voxel in voxels {
+            let position = (voxel.position_sum / voxel.count as f64).cast::<f32>();
+            let color = voxel.color_sum / voxel.count as u64;
+            let color = ByteColor3::new(color.x as u8, color.y as u8, color.z as u8);
+            if self.point_normals.is_some() {
+                let normal = voxel.normal_sum.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y);
+                result.push_with_normal(position, color, normal);
+            } else {
+                result.push(position, color);
+            }
+        }
+        info!(
+            "Downsampled {} points to {} points with a grid size of {grid_size}",
+            self.len(),
+            result.len()
+        );
+        result
+    }
+
     /// Writes the `PointCloud` to an OBJ file.
     pub fn to_obj(&self, mut obj_writer: impl Write, config: &ObjWriteConfig) -> io::Result<()> {
         match config {
@@ -202,10 +373,32 @@ impl SimplePointCloud {
         &self.point_colors
     }
 
+    /// Returns the normals of the points in the `PointCloud` if the source of the point cloud provided them.
+    pub fn point_normals(&self) -> Option<&[Vector3<f32>]> {
+        self.point_normals.as_deref()
+    }
+
     /// Pushes a point to the `PointCloud`.
     pub fn push(&mut self, position: Vector3<f32>, color: ByteColor3) {
+        jeriya_shared::assert!(
+            self.point_normals.is_none(),
+            "points with normals must be pushed with push_with_normal"
+        );
+        self.bounding_box.include(&position);
+        self.point_positions.push(position);
+        self.point_colors.push(color);
+    }
+
+    /// Pushes a point with a normal to the `PointCloud`. All points must have a normal.
+    pub fn push_with_normal(&mut self, position: Vector3<f32>, color: ByteColor3, normal: Vector3<f32>) {
+        jeriya_shared::assert!(
+            self.point_normals.is_some() || self.is_empty(),
+            "points without normals must be pushed with push"
+        );
+        self.bounding_box.include(&position);
         self.point_positions.push(position);
         self.point_colors.push(color);
+        self.point_normals.get_or_insert_with(Vec::new).push(normal);
     }
 
     /// Returns the number of points in the `PointCloud`.
@@ -233,11 +426,36 @@ impl SimplePointCloud {
     }
 }
 
+/// Converts a scalar property of a PLY element to `f32`. Returns `None` for list properties.
+fn ply_scalar(property: &Property) -> Option<f32> {
+    match *property {
+        Property::Char(value) => Some(value as f32),
+        Property::UChar(value) => Some(value as f32),
+        Property::Short(value) => Some(value as f32),
+        Property::UShort(value) => Some(value as f32),
+        Property::Int(value) => Some(value as f32),
+        Property::UInt(value) => Some(value as f32),
+        Property::Float(value) => Some(value),
+        Property::Double(value) => Some(value as f32),
+        _ => None,
+    }
+}
+
+/// Converts a color channel of a PLY element to a byte. Floating point channels are expected to be in the range [0, 1].
+fn ply_color(property: &Property) -> u8 {
+    match *property {
+        Property::Float(value) => (value.clamp(0.0, 1.0) * 255.0).round() as u8,
+        Property::Double(value) => (value.clamp(0.0, 1.0) * 255.0).round() as u8,
+        _ => ply_scalar(property).map_or(255, |value| value.clamp(0.0, 255.0) as u8),
+    }
+}
+
 impl std::fmt::Debug for SimplePointCloud {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SimplePointCloud")
             .field("point_positions", &self.point_positions.len())
             .field("point_colors", &self.point_colors.len())
+            .field("point_normals", &self.point_normals.as_ref().map(Vec::len))
             .finish()
     }
 }
@@ -357,6 +575,45 @@ mod tests {
         assert_eq!(point_cloud.point_colors(), &[ByteColor3::new(4, 5, 6), ByteColor3::new(10, 11, 12)]);
     }
 
+    mod downsample_voxel {
+        use super::*;
+
+        #[test]
+        fn average_points_per_voxel() {
+            let mut point_cloud = SimplePointCloud::new();
+            point_cloud.push(Vector3::new(0.0, 0.0, 0.0), ByteColor3::new(0, 0, 0));
+            point_cloud.push(Vector3::new(0.5, 0.5, 0.5), ByteColor3::new(100, 200, 50));
+            point_cloud.push(Vector3::new(2.5, 0.0, 0.0), ByteColor3::new(10, 20, 30));
+
+            let downsampled = point_cloud.downsample_voxel(1.0);
+            assert_eq!(downsampled.len(), 2);
+            assert_eq!(
+                downsampled.point_positions(),
+                &[Vector3::new(0.25, 0.25, 0.25), Vector3::new(2.5, 0.0, 0.0)]
+            );
+            assert_eq!(
+                downsampled.point_colors(),
+                &[ByteColor3::new(50, 100, 25), ByteColor3::new(10, 20, 30)]
+            );
+            assert!(downsampled.point_normals().is_none());
+        }
+
+        #[test]
+        fn average_normals() {
+            let mut point_cloud = SimplePointCloud::new();
+            let color = ByteColor3::new(255, 255, 255);
+            point_cloud.push_with_normal(Vector3::new(0.0, 0.0, 0.0), color, Vector3::x());
+            point_cloud.push_with_normal(Vector3::new(0.1, 0.0, 0.0), color, Vector3::y());
+
+            let downsampled = point_cloud.downsample_voxel(1.0);
+            assert_eq!(downsampled.len(), 1);
+            let normal = downsampled.point_normals().unwrap()[0];
+            assert!(approx_eq!(f32, normal.x, std::f32::consts::FRAC_1_SQRT_2, ulps = 2));
+            assert!(approx_eq!(f32, normal.y, std::f32::consts::FRAC_1_SQRT_2, ulps = 2));
+            assert_eq!(normal.z, 0.0);
+        }
+    }
+
     #[test]
     fn sample_from_model() {
         let model = ModelAsset::import("../sample_assets/models/suzanne.glb").unwrap();
@@ -369,6 +626,76 @@ mod tests {
         assert_eq!(point_cloud.len(), 5288);
     }
 
+    mod import_ply {
+        use std::fs;
+
+        use super::*;
+
+        #[test]
+        fn ascii_with_colors_and_normals() {
+            let directory = create_test_result_folder_for_function(function_name!());
+            let ply_path = directory.join("points.ply");
+            let ply = "\
+                ply\n\
+                format ascii 1.0\n\
+                element vertex 2\n\
+                property float x\n\
+                property float y\n\
+                property float z\n\
+                property float nx\n\
+                property float ny\n\
+                property float nz\n\
+                property uchar red\n\
+                property uchar green\n\
+                property uchar blue\n\
+                end_header\n\
+                1 2 3 0 0 1 255 0 0\n\
+                4 5 6 0 1 0 0 255 0\n";
+            fs::write(&ply_path, ply).unwrap();
+            let point_cloud = SimplePointCloud::import_ply(&ply_path).unwrap();
+            assert_eq!(
+                point_cloud.point_positions(),
+                &[Vector3::new(1.0, 2.0, 3.0), Vector3::new(4.0, 5.0, 6.0)]
+            );
+            assert_eq!(
+                point_cloud.point_colors(),
+                &[ByteColor3::new(255, 0, 0), ByteColor3::new(0, 255, 0)]
+            );
+            assert_eq!(point_cloud.point_normals().unwrap(), &[Vector3::z(), Vector3::y()]);
+        }
+
+        #[test]
+        fn binary_without_colors() {
+            let directory = create_test_result_folder_for_function(function_name!());
+            let ply_path = directory.join("points.ply");
+            let mut ply = b"ply\nformat binary_little_endian 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nend_header\n".to_vec();
+            for value in [1.0f32, 2.0, 3.0] {
+                ply.extend_from_slice(&value.to_le_bytes());
+            }
+            fs::write(&ply_path, ply).unwrap();
+            let point_cloud = SimplePointCloud::import_ply(&ply_path).unwrap();
+            assert_eq!(point_cloud.point_positions(), &[Vector3::new(1.0, 2.0, 3.0)]);
+            assert_eq!(point_cloud.point_colors(), &[ByteColor3::new(255, 255, 255)]);
+            assert!(point_cloud.point_normals().is_none());
+        }
+    }
+
+    #[test]
+    fn estimate_normals() {
+        let mut point_cloud = SimplePointCloud::new();
+        for x in 0..5 {
+            for z in 0..5 {
+                point_cloud.push(Vector3::new(x as f32, 1.0, z as f32), ByteColor3::new(0, 0, 0));
+            }
+        }
+        point_cloud.push(Vector3::new(2.0, 0.0, 2.0), ByteColor3::new(0, 0, 0));
+        let normals = point_cloud.estimate_normals(8);
+        assert_eq!(normals.len(), point_cloud.len());
+        for normal in &normals[..25] {
+            assert!((normal - Vector3::y()).norm() < 0.3, "unexpected normal: {normal:?}");
+        }
+    }
+
     #[test]
     fn index_from_cumulative_sums_smoke() {
         let cumulative_sums = vec![0.1, 0.2, 0.7, 1.0];
@@ -1,5 +1,6 @@
 use crate::{
     common::{extract_file_name_from_path, AssetKey, ASSET_META_FILE_NAME},
+    manifest::Manifest,
     Error, Result,
 };
 use jeriya_shared::{
@@ -11,15 +12,19 @@ use jeriya_shared::{
 use notify_debouncer_full::notify::{self, EventKind, ReadDirectoryChangesWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    env, fs,
+    collections::{BTreeMap, HashMap},
+    env,
+    fs::{self, File},
     hash::Hasher,
-    io,
+    io::{self, Read, Seek},
     path::{Path, PathBuf},
     result,
     sync::Arc,
 };
 
+pub mod archive;
+pub mod http;
+
 pub enum FileSystemEvent {
     Create(PathBuf),
     Modify(PathBuf),
@@ -32,12 +37,23 @@ pub struct AssetMetaData {
     pub file: PathBuf,
 }
 
+/// Content of an asset that can be read partially by seeking to the required positions
+pub trait ContentReader: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ContentReader for T {}
+
 pub trait ReadAsset {
     /// Read the [`AssetMetaData`] from the given asset path.
     fn read_meta_data(&self, asset_key: &AssetKey) -> Result<AssetMetaData>;
 
     /// Read the content of the file that belongs to the given `asset_key`.
     fn read_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Vec<u8>>;
+
+    /// Opens the file that belongs to the given `asset_key` without reading it completely.
+    fn open_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Box<dyn ContentReader>>;
+
+    /// Read the [`Manifest`] that lists all processed assets.
+    fn read_manifest(&self) -> Result<Manifest>;
 }
 
 pub trait ImportSource: ReadAsset + Send + Sync {
@@ -47,6 +63,7 @@ pub trait ImportSource: ReadAsset + Send + Sync {
 
 pub struct FileSystem {
     root: PathBuf,
+    mount_points: BTreeMap<String, PathBuf>,
     watcher: Option<ReadDirectoryChangesWatcher>,
 }
 
@@ -61,14 +78,55 @@ impl FileSystem {
     /// let _file_system = FileSystem::new("assets").unwrap();
     /// ```
     pub fn new(root: impl AsRef<Path>) -> Result<Self> {
-        let root = env::current_dir().expect("unable to determine the working directory").join(root);
-        if !root.exists() {
-            return Err(io::Error::new(io::ErrorKind::NotFound, format!("Directory '{}' does not exist", root.display())).into());
-        }
-        Ok(Self { root, watcher: None })
+        Ok(Self {
+            root: existing_directory(root.as_ref())?,
+            mount_points: BTreeMap::new(),
+            watcher: None,
+        })
+    }
+
+    /// Registers the directory `root` for the [`AssetKey`]s with the mount point `name`, e.g. `engine://shaders/color.vert`.
+    ///
+    /// # Panics
+    ///
+    /// If a mount point with the same `name` is already registered.
+    pub fn mount(mut self, name: impl Into<String>, root: impl AsRef<Path>) -> Result<Self> {
+        let name = name.into();
+        assert!(!self.mount_points.contains_key(&name), "mount point '{name}' is already registered");
+        self.mount_points.insert(name, existing_directory(root.as_ref())?);
+        Ok(self)
+    }
+
+    /// Returns the path of the directory in which the processed asset with the given `asset_key` is located.
+    fn asset_path(&self, asset_key: &AssetKey) -> Result<PathBuf> {
+        check_path(asset_key)?;
+        let root = match asset_key.mount_point() {
+            Some(name) => self
+                .mount_points
+                .get(&*name)
+                .ok_or_else(|| Error::UnknownMountPoint(name.into_owned()))?,
+            None => &self.root,
+        };
+        Ok(root.join(asset_key.path_in_mount_point()))
+    }
+
+    /// Returns the root directories together with the name of their mount point.
+    fn roots(&self) -> Vec<(Option<String>, PathBuf)> {
+        std::iter::once((None, self.root.clone()))
+            .chain(self.mount_points.iter().map(|(name, root)| (Some(name.clone()), root.clone())))
+            .collect()
     }
 }
 
+/// Returns the absolute path of the directory and checks that it exists.
+fn existing_directory(path: &Path) -> Result<PathBuf> {
+    let path = env::current_dir().expect("unable to determine the working directory").join(path);
+    if !path.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("Directory '{}' does not exist", path.display())).into());
+    }
+    Ok(path)
+}
+
 fn check_path(asset_key: &AssetKey) -> Result<()> {
     if asset_key.as_path().is_absolute() {
         return Err(Error::InvalidPath(asset_key.as_path().to_owned()));
@@ -89,18 +147,28 @@ fn is_meta_file(path: &Path) -> bool {
 
 impl ReadAsset for FileSystem {
     fn read_meta_data(&self, asset_key: &AssetKey) -> Result<AssetMetaData> {
-        check_path(asset_key)?;
-        let meta_file_path = self.root.join(asset_key.as_path()).join(ASSET_META_FILE_NAME);
+        let meta_file_path = self.asset_path(asset_key)?.join(ASSET_META_FILE_NAME);
         let meta_file_content = fs::read_to_string(&meta_file_path)?;
         let meta_data = serde_yaml::from_str(&meta_file_content).map_err(|_| Error::InvalidAssetData(meta_file_path.clone()))?;
         Ok(meta_data)
     }
 
     fn read_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Vec<u8>> {
-        check_path(asset_key)?;
-        let path = self.root.join(asset_key.as_path()).join(file_path);
+        let path = self.asset_path(asset_key)?.join(file_path);
         fs::read(&path).map_err(|_| Error::InvalidAssetData(path))
     }
+
+    fn open_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Box<dyn ContentReader>> {
+        let path = self.asset_path(asset_key)?.join(file_path);
+        let file = File::open(&path).map_err(|_| Error::InvalidAssetData(path))?;
+        Ok(Box::new(file))
+    }
+
+    /// Only the manifest of the default root is read. Assets of mount points are listed in it when they are processed
+    /// by the same [`AssetProcessor`](crate::asset_processor::AssetProcessor).
+    fn read_manifest(&self) -> Result<Manifest> {
+        Manifest::read(&self.root)
+    }
 }
 
 fn hash_asset_file(absolute_path: impl AsRef<Path>) -> std::io::Result<u64> {
@@ -120,7 +188,7 @@ impl ImportSource for FileSystem {
         if self.watcher.is_some() {
             panic!("set_observer called although the observer is already set. You can only set the observer once.");
         }
-        let root = self.root.clone();
+        let roots = self.roots();
 
         // Every file gets hashed and the hashes are stored in a map. If a file is modified
         // the hash is checked and if it's different the observer function is called. This is
@@ -129,14 +197,16 @@ impl ImportSource for FileSystem {
         let hashes = Arc::new(Mutex::new(HashMap::<PathBuf, u64>::new()));
 
         let watch_fn = move |result: result::Result<notify::Event, notify::Error>| {
-            handle_event(&result, &root, &hashes, &observer_fn);
+            handle_event(&result, &roots, &hashes, &observer_fn);
         };
 
         // Start the directory watcher.
         let mut watcher = notify::recommended_watcher(watch_fn).map_err(|_| Error::FailedToStartDirectoryWatcher(self.root.clone()))?;
-        watcher
-            .watch(&self.root, RecursiveMode::Recursive)
-            .map_err(|_| Error::FailedToStartDirectoryWatcher(self.root.clone()))?;
+        for (_, root) in self.roots() {
+            watcher
+                .watch(&root, RecursiveMode::Recursive)
+                .map_err(|_| Error::FailedToStartDirectoryWatcher(root.clone()))?;
+        }
 
         self.watcher = Some(watcher);
 
@@ -146,7 +216,7 @@ impl ImportSource for FileSystem {
 
 fn handle_event(
     result: &std::result::Result<notify::Event, notify::Error>,
-    root: &PathBuf,
+    roots: &[(Option<String>, PathBuf)],
     hashes: &Arc<Mutex<HashMap<PathBuf, u64>>>,
     observer_fn: &ObserverFn,
 ) {
@@ -169,11 +239,17 @@ fn handle_event(
 
     // The file watcher returns absolute paths but he whole asset handling is based on
     // relative paths because it's irrelavant where on the system they are located.
-    let Some(path) = pathdiff::diff_paths(absolute_path, root) else {
+    // When the roots are nested, the innermost one wins.
+    let Some((mount_point, path)) = roots
+        .iter()
+        .filter_map(|(mount_point, root)| Some((mount_point, root, pathdiff::diff_paths(absolute_path, root)?)))
+        .filter(|(_, _, path)| !path.starts_with(".."))
+        .max_by_key(|(_, root, _)| root.components().count())
+        .map(|(mount_point, _, path)| (mount_point, path))
+    else {
         warn! {
-            "Failed to get relative path of '{absolute_path}' relative to '{root}'",
+            "Failed to get relative path of '{absolute_path}' relative to the roots",
             absolute_path = absolute_path.display(),
-            root = root.display()
         };
         return;
     };
@@ -181,6 +257,10 @@ fn handle_event(
 
     // The asset path is the parent of the meta file.
     let asset_path = path.parent().expect("path has no parent");
+    let asset_key = match mount_point {
+        Some(mount_point) => AssetKey::mounted(mount_point, asset_path),
+        None => AssetKey::new(asset_path),
+    };
 
     if let EventKind::Modify(_modify_event) = &event.kind {
         let Ok(hash) = hash_asset_file(absolute_path) else {
@@ -199,6 +279,6 @@ fn handle_event(
         drop(hashes);
 
         info!("Emitting modify event for asset '{}'", path.display());
-        observer_fn(FileSystemEvent::Modify(asset_path.to_owned()))
+        observer_fn(FileSystemEvent::Modify(asset_key.as_path().to_owned()))
     }
 }
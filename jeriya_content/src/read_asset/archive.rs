@@ -0,0 +1,243 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+};
+
+use jeriya_shared::{
+    log::{info, trace},
+    parking_lot::Mutex,
+    walkdir::WalkDir,
+};
+use zip::{read::ZipFile, result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    common::{AssetKey, ASSET_META_FILE_NAME},
+    manifest::{Manifest, MANIFEST_FILE_NAME},
+    Error, Result,
+};
+
+use super::{check_path, AssetMetaData, ContentReader, ImportSource, ObserverFn, ReadAsset};
+
+/// [`ImportSource`] that reads processed assets from a single zip archive.
+///
+/// The archive contains the target directory of the [`AssetProcessor`](crate::asset_processor::AssetProcessor) and can
+/// be created with [`Archive::pack`].
+pub struct Archive {
+    path: PathBuf,
+    zip_archive: Mutex<ZipArchive<File>>,
+}
+
+impl Archive {
+    /// Opens the archive at the given `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let file = File::open(&path)?;
+        let zip_archive = ZipArchive::new(file).map_err(|err| Error::FailedDeserialization(Box::new(err)))?;
+        info!("Opened archive '{}' with {} files", path.display(), zip_archive.len());
+        Ok(Self {
+            path,
+            zip_archive: Mutex::new(zip_archive),
+        })
+    }
+
+    /// Packs all files in the `directory` into a new archive at `archive_path`. The `directory` is expected to be the
+    /// target directory of the [`AssetProcessor`](crate::asset_processor::AssetProcessor).
+    ///
+    /// The `.bin` files are stored without compression so that they can be read partially with [`ReadAsset::open_content`].
+    pub fn pack(directory: impl AsRef<Path>, archive_path: impl AsRef<Path>) -> Result<()> {
+        let directory = directory.as_ref();
+        let mut zip_writer = ZipWriter::new(File::create(archive_path)?);
+        for entry in WalkDir::new(directory).sort_by_file_name() {
+            let entry = entry.map_err(io::Error::from)?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative_path = entry.path().strip_prefix(directory).expect("entry is not in the directory");
+            let entry_name = entry_name(relative_path);
+            let compression_method = match relative_path.extension() {
+                Some(extension) if extension == "bin" => CompressionMethod::Stored,
+                _ => CompressionMethod::Deflated,
+            };
+            trace!("Packing '{entry_name}' ({compression_method})");
+            zip_writer
+                .start_file(entry_name, FileOptions::default().compression_method(compression_method))
+                .map_err(|err| Error::FailedSerialization(Box::new(err)))?;
+            io::copy(&mut File::open(entry.path())?, &mut zip_writer)?;
+        }
+        zip_writer.finish().map_err(|err| Error::FailedSerialization(Box::new(err)))?;
+        Ok(())
+    }
+
+    /// Reads the complete file at the given `path` relative to the root of the archive.
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let mut zip_archive = self.zip_archive.lock();
+        let mut zip_file = self.entry(&mut zip_archive, path)?;
+        let mut content = Vec::with_capacity(zip_file.size() as usize);
+        zip_file.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Returns the entry of the file at the given `path` relative to the root of the archive.
+    fn entry<'a>(&self, zip_archive: &'a mut ZipArchive<File>, path: &Path) -> Result<ZipFile<'a>> {
+        match zip_archive.by_name(&entry_name(path)) {
+            Ok(zip_file) => Ok(zip_file),
+            Err(ZipError::FileNotFound) => Err(Error::InvalidAssetData(self.path.join(path))),
+            Err(err) => Err(Error::FailedDeserialization(Box::new(err))),
+        }
+    }
+}
+
+/// Reads an entry that is stored without compression directly from the archive file.
+struct StoredEntry {
+    file: File,
+    data_start: u64,
+    size: u64,
+    position: u64,
+}
+
+impl Read for StoredEntry {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.size.saturating_sub(self.position);
+        let len = (buf.len() as u64).min(remaining) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+        self.file.seek(SeekFrom::Start(self.data_start + self.position))?;
+        let read = self.file.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for StoredEntry {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position"))?;
+        Ok(self.position)
+    }
+}
+
+/// Returns the name of the entry in the archive for the given relative `path`. Entries are always separated by `/`.
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(component) => Some(component.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl ReadAsset for Archive {
+    fn read_meta_data(&self, asset_key: &AssetKey) -> Result<AssetMetaData> {
+        check_path(asset_key)?;
+        let meta_file_path = asset_key.as_path().join(ASSET_META_FILE_NAME);
+        let meta_file_content = self.read(&meta_file_path)?;
+        let meta_data = serde_yaml::from_slice(&meta_file_content).map_err(|_| Error::InvalidAssetData(self.path.join(meta_file_path)))?;
+        Ok(meta_data)
+    }
+
+    fn read_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Vec<u8>> {
+        check_path(asset_key)?;
+        self.read(&asset_key.as_path().join(file_path))
+    }
+
+    /// Files that are stored without compression are read directly from the archive. Compressed files can't be read
+    /// partially. Therefore, they are read completely into memory.
+    fn open_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Box<dyn ContentReader>> {
+        check_path(asset_key)?;
+        let path = asset_key.as_path().join(file_path);
+        let mut zip_archive = self.zip_archive.lock();
+        let mut zip_file = self.entry(&mut zip_archive, &path)?;
+        if zip_file.compression() == CompressionMethod::Stored {
+            return Ok(Box::new(StoredEntry {
+                file: File::open(&self.path)?,
+                data_start: zip_file.data_start(),
+                size: zip_file.size(),
+                position: 0,
+            }));
+        }
+        let mut content = Vec::with_capacity(zip_file.size() as usize);
+        zip_file.read_to_end(&mut content)?;
+        Ok(Box::new(Cursor::new(content)))
+    }
+
+    fn read_manifest(&self) -> Result<Manifest> {
+        Manifest::from_slice(&self.read(Path::new(MANIFEST_FILE_NAME))?)
+    }
+}
+
+impl ImportSource for Archive {
+    /// The content of an archive doesn't change. Therefore, the observer is never called.
+    fn set_observer(&mut self, _observer_fn: Box<ObserverFn>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use jeriya_test::setup_logger;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn pack_and_read() {
+        setup_logger();
+        let directory = TempDir::new("processed").unwrap();
+        fs::create_dir_all(directory.path().join("models/test.txt")).unwrap();
+        fs::write(directory.path().join("models/test.txt/test.bin"), "Hello World!").unwrap();
+        fs::write(
+            directory.path().join("models/test.txt").join(ASSET_META_FILE_NAME),
+            "file: \"test.bin\"",
+        )
+        .unwrap();
+
+        let archive_directory = TempDir::new("archive").unwrap();
+        let archive_path = archive_directory.path().join("assets.zip");
+        Archive::pack(directory.path(), &archive_path).unwrap();
+
+        let archive = Archive::open(&archive_path).unwrap();
+        let asset_key = AssetKey::new("models/test.txt");
+        let meta_data = archive.read_meta_data(&asset_key).unwrap();
+        assert_eq!(meta_data.file, Path::new("test.bin"));
+        assert_eq!(archive.read_content(&asset_key, &meta_data.file).unwrap(), b"Hello World!");
+
+        let mut content = String::new();
+        archive
+            .open_content(&asset_key, &meta_data.file)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "Hello World!");
+
+        assert_eq!(
+            archive
+                .zip_archive
+                .lock()
+                .by_name("models/test.txt/test.bin")
+                .unwrap()
+                .compression(),
+            CompressionMethod::Stored
+        );
+        let mut content_reader = archive.open_content(&asset_key, &meta_data.file).unwrap();
+        content_reader.seek(SeekFrom::Start(6)).unwrap();
+        let mut content = String::new();
+        content_reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "World!");
+        assert_eq!(content_reader.seek(SeekFrom::End(-12)).unwrap(), 0);
+        assert!(content_reader.seek(SeekFrom::Current(-1)).is_err());
+
+        assert!(matches!(
+            archive.read_meta_data(&AssetKey::new("missing.txt")),
+            Err(Error::InvalidAssetData(_))
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use jeriya_shared::log::{info, trace, warn};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::{
+    common::{AssetKey, ASSET_META_FILE_NAME},
+    manifest::{Manifest, MANIFEST_FILE_NAME},
+    Error, Result,
+};
+
+use super::{check_path, AssetMetaData, ContentReader, ImportSource, ObserverFn, ReadAsset};
+
+/// Counter to create unique names for files that are being downloaded
+static DOWNLOAD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Characters that are percent-encoded in a path segment of a URL. Only the unreserved characters of RFC 3986 are kept.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// [`ImportSource`] that fetches processed assets from an HTTP(S) server.
+///
+/// The server is expected to serve the target directory of the [`AssetProcessor`](crate::asset_processor::AssetProcessor)
+/// as static files. Every fetched file is stored in a local cache directory together with the `ETag` that the server
+/// sent. Subsequent requests are conditional so that the file is only transferred again when it changed on the server.
+/// When the server is not reachable, the cached files are used.
+pub struct HttpServer {
+    base_url: String,
+    cache_directory: PathBuf,
+    agent: ureq::Agent,
+}
+
+impl HttpServer {
+    /// Creates a new [`HttpServer`] import source that fetches the assets relative to `base_url` and caches them in the
+    /// `cache_directory`. The `cache_directory` is created if it doesn't exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jeriya_content::read_asset::http::HttpServer;
+    /// let _http_server = HttpServer::new("https://example.com/assets", "asset_cache").unwrap();
+    /// ```
+    pub fn new(base_url: impl Into<String>, cache_directory: impl AsRef<Path>) -> Result<Self> {
+        let cache_directory = cache_directory.as_ref().to_owned();
+        fs::create_dir_all(&cache_directory)?;
+        let base_url = base_url.into().trim_end_matches('/').to_owned();
+        info!("Fetching assets from '{base_url}' into cache '{}'", cache_directory.display());
+        Ok(Self {
+            base_url,
+            cache_directory,
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build(),
+        })
+    }
+
+    /// Returns the URL of the file at the given `path` relative to the root of the server.
+    fn url(&self, path: &Path) -> String {
+        let mut url = self.base_url.clone();
+        for component in path.components() {
+            url.push('/');
+            url.extend(utf8_percent_encode(&component.as_os_str().to_string_lossy(), PATH_SEGMENT));
+        }
+        url
+    }
+
+    /// Makes sure that the file at the given `path` relative to the root of the server is up to date in the cache and
+    /// returns the path of the cached file.
+    ///
+    /// The `path` might come from the server itself. Therefore, it's rejected when it could point outside of the cache directory.
+    fn fetch(&self, path: &Path) -> Result<PathBuf> {
+        if path.components().any(|component| !matches!(component, Component::Normal(_))) {
+            return Err(Error::InvalidPath(path.to_owned()));
+        }
+        let cached_path = self.cache_directory.join(path);
+        let etag_path = append_to_path(&cached_path, ".etag");
+        let cached_etag = if cached_path.is_file() {
+            fs::read_to_string(&etag_path).ok()
+        } else {
+            None
+        };
+
+        let url = self.url(path);
+        let mut request = self.agent.get(&url);
+        if let Some(etag) = &cached_etag {
+            request = request.set("If-None-Match", etag);
+        }
+
+        match request.call() {
+            Ok(response) if response.status() == 304 => {
+                trace!("Cached file for '{url}' is up to date");
+                Ok(cached_path)
+            }
+            Ok(response) => {
+                trace!("Downloading '{url}'");
+                let etag = response.header("ETag").map(str::to_owned);
+                let mut content = Vec::new();
+                response.into_reader().read_to_end(&mut content)?;
+
+                // The file is written under a unique name and renamed afterwards so that concurrent
+                // imports never read a partially written file.
+                if let Some(parent) = cached_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let download_path = append_to_path(
+                    &cached_path,
+                    &format!(".download{}", DOWNLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)),
+                );
+                fs::write(&download_path, &content)?;
+                fs::rename(&download_path, &cached_path)?;
+                match etag {
+                    Some(etag) => fs::write(&etag_path, etag)?,
+                    None => {
+                        let _ = fs::remove_file(&etag_path);
+                    }
+                }
+                Ok(cached_path)
+            }
+            Err(ureq::Error::Status(status, _)) => Err(Error::Other(format!("Request for '{url}' failed with status {status}").into())),
+            Err(err) if cached_path.is_file() => {
+                warn!("Failed to reach '{url}'. Using the cached file instead: {err}");
+                Ok(cached_path)
+            }
+            Err(err) => Err(Error::Other(Box::new(err))),
+        }
+    }
+}
+
+fn append_to_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+impl ReadAsset for HttpServer {
+    fn read_meta_data(&self, asset_key: &AssetKey) -> Result<AssetMetaData> {
+        check_path(asset_key)?;
+        let meta_file_path = self.fetch(&asset_key.as_path().join(ASSET_META_FILE_NAME))?;
+        let meta_file_content = fs::read_to_string(&meta_file_path)?;
+        let meta_data = serde_yaml::from_str(&meta_file_content).map_err(|_| Error::InvalidAssetData(meta_file_path.clone()))?;
+        Ok(meta_data)
+    }
+
+    fn read_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Vec<u8>> {
+        check_path(asset_key)?;
+        let path = self.fetch(&asset_key.as_path().join(file_path))?;
+        fs::read(&path).map_err(|_| Error::InvalidAssetData(path))
+    }
+
+    fn open_content(&self, asset_key: &AssetKey, file_path: &Path) -> Result<Box<dyn ContentReader>> {
+        check_path(asset_key)?;
+        let path = self.fetch(&asset_key.as_path().join(file_path))?;
+        let file = File::open(&path).map_err(|_| Error::InvalidAssetData(path))?;
+        Ok(Box::new(file))
+    }
+
+    fn read_manifest(&self) -> Result<Manifest> {
+        let path = self.fetch(Path::new(MANIFEST_FILE_NAME))?;
+        Manifest::from_slice(&fs::read(path)?)
+    }
+}
+
+impl ImportSource for HttpServer {
+    /// The server doesn't notify about changes. Therefore, the observer is never called and changed assets
+    /// are only picked up when they are imported again.
+    fn set_observer(&mut self, _observer_fn: Box<ObserverFn>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::Arc,
+        thread,
+    };
+
+    use jeriya_shared::parking_lot::Mutex;
+    use jeriya_test::setup_logger;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    /// Minimal HTTP server that serves the given files and supports conditional requests.
+    struct TestServer {
+        url: String,
+        files: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+        transfers: Arc<AtomicUsize>,
+    }
+
+    impl TestServer {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let files = Arc::new(Mutex::new(BTreeMap::<String, Vec<u8>>::new()));
+            let transfers = Arc::new(AtomicUsize::new(0));
+            let files2 = files.clone();
+            let transfers2 = transfers.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_owned();
+                    let mut if_none_match = None;
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = header.split_once(':') {
+                            if name.eq_ignore_ascii_case("If-None-Match") {
+                                if_none_match = Some(value.trim().to_owned());
+                            }
+                        }
+                    }
+
+                    let files = files2.lock();
+                    let response = match files.get(&path) {
+                        Some(content) => {
+                            let etag = format!("\"{}\"", content.iter().map(|byte| *byte as u64).sum::<u64>());
+                            if if_none_match.as_ref() == Some(&etag) {
+                                b"HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_vec()
+                            } else {
+                                transfers2.fetch_add(1, Ordering::SeqCst);
+                                let header = format!(
+                                    "HTTP/1.1 200 OK\r\nETag: {etag}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                                    content.len()
+                                );
+                                [header.as_bytes(), content.as_slice()].concat()
+                            }
+                        }
+                        None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+                    };
+                    stream.write_all(&response).unwrap();
+                }
+            });
+            Self { url, files, transfers }
+        }
+
+        fn insert(&self, path: &str, content: &[u8]) {
+            self.files.lock().insert(path.to_owned(), content.to_vec());
+        }
+    }
+
+    #[test]
+    fn fetch_and_cache() {
+        setup_logger();
+        let server = TestServer::start();
+        server.insert("/test.txt/asset.yaml", b"file: \"test.bin\"");
+        server.insert("/test.txt/test.bin", b"Hello World!");
+
+        let cache_directory = TempDir::new("cache").unwrap();
+        let http_server = HttpServer::new(format!("{}/", server.url), cache_directory.path()).unwrap();
+        let asset_key = AssetKey::new("test.txt");
+
+        let meta_data = http_server.read_meta_data(&asset_key).unwrap();
+        assert_eq!(meta_data.file, Path::new("test.bin"));
+        let content = http_server.read_content(&asset_key, &meta_data.file).unwrap();
+        assert_eq!(content, b"Hello World!");
+        assert_eq!(server.transfers.load(Ordering::SeqCst), 2);
+        assert!(cache_directory.path().join("test.txt/test.bin").is_file());
+
+        // The cached file is used because it didn't change on the server
+        let mut content = String::new();
+        http_server
+            .open_content(&asset_key, &meta_data.file)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "Hello World!");
+        assert_eq!(server.transfers.load(Ordering::SeqCst), 2);
+
+        // The file is transferred again when it changed on the server
+        server.insert("/test.txt/test.bin", b"Hello World 2!");
+        let content = http_server.read_content(&asset_key, &meta_data.file).unwrap();
+        assert_eq!(content, b"Hello World 2!");
+        assert_eq!(server.transfers.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn not_found() {
+        setup_logger();
+        let server = TestServer::start();
+        let cache_directory = TempDir::new("cache").unwrap();
+        let http_server = HttpServer::new(server.url.clone(), cache_directory.path()).unwrap();
+        assert!(http_server.read_meta_data(&AssetKey::new("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn reject_paths_outside_of_the_cache() {
+        setup_logger();
+        let server = TestServer::start();
+        server.insert("/test.txt/asset.yaml", b"file: \"../../escaped.bin\"");
+        server.insert("/escaped.bin", b"Hello World!");
+
+        let root = TempDir::new("root").unwrap();
+        let cache_directory = root.path().join("cache");
+        let http_server = HttpServer::new(server.url.clone(), &cache_directory).unwrap();
+
+        let asset_key = AssetKey::new("test.txt");
+        let meta_data = http_server.read_meta_data(&asset_key).unwrap();
+        assert!(matches!(
+            http_server.read_content(&asset_key, &meta_data.file),
+            Err(Error::InvalidPath(_))
+        ));
+        assert!(matches!(
+            http_server.read_meta_data(&AssetKey::new("../escaped.bin")),
+            Err(Error::InvalidPath(_))
+        ));
+        assert!(!root.path().join("escaped.bin").exists());
+        assert_eq!(server.transfers.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn percent_encode_url() {
+        let cache_directory = TempDir::new("cache").unwrap();
+        let http_server = HttpServer::new("http://example.com/assets/", cache_directory.path()).unwrap();
+        assert_eq!(
+            http_server.url(Path::new("my model/a#b?.bin")),
+            "http://example.com/assets/my%20model/a%23b%3F.bin"
+        );
+    }
+
+    #[test]
+    fn server_not_reachable() {
+        setup_logger();
+        let cache_directory = TempDir::new("cache").unwrap();
+        let http_server = HttpServer::new("http://127.0.0.1:1", cache_directory.path()).unwrap();
+        let asset_key = AssetKey::new("test.txt");
+        assert!(http_server.read_content(&asset_key, Path::new("test.bin")).is_err());
+
+        // The cached file is used when the server is not reachable
+        fs::create_dir_all(cache_directory.path().join("test.txt")).unwrap();
+        fs::write(cache_directory.path().join("test.txt/test.bin"), "Hello World!").unwrap();
+        let content = http_server.read_content(&asset_key, Path::new("test.bin")).unwrap();
+        assert_eq!(content, b"Hello World!");
+    }
+}
@@ -0,0 +1,301 @@
+use std::{fs, path::Path};
+
+use jeriya_shared::nalgebra::{Matrix4, Translation3, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::{asset_processor::AssetBuilder, common::AssetKey, Error};
+
+/// Scene that references processed models and point clouds and places them together with cameras and lights.
+///
+/// Scenes are stored as YAML files with the extension `scene`:
+///
+/// ```yaml
+/// name: Sample
+/// entities:
+///   - name: Suzanne
+///     transform:
+///       position: [0.0, 1.0, 0.0]
+///     content: !Model models/suzanne.glb
+/// cameras:
+///   - name: Main
+///     transform:
+///       position: [0.0, 1.0, 5.0]
+///     projection: !Perspective
+///       fov: 90.0
+///       near: 0.1
+///       far: 1000.0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneAsset {
+    pub name: String,
+    pub entities: Vec<Entity>,
+    pub cameras: Vec<SceneCamera>,
+    pub lights: Vec<Light>,
+}
+
+/// Position, rotation and scale of an object in the scene. The transform of an [`Entity`] is relative to its parent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Transform {
+    pub position: Vector3<f32>,
+    /// Rotation as quaternion in the order `[x, y, z, w]`
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+    /// Returns the matrix that first scales, then rotates and finally translates.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Translation3::from(self.position).to_homogeneous() * self.rotation.to_homogeneous() * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+}
+
+/// Asset that is displayed by an [`Entity`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum EntityContent {
+    #[default]
+    Empty,
+    Model(AssetKey),
+    PointCloud(AssetKey),
+}
+
+/// Node in the hierarchy of the scene
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Entity {
+    pub name: String,
+    pub transform: Transform,
+    pub content: EntityContent,
+    pub children: Vec<Entity>,
+}
+
+impl Entity {
+    /// Calls `f` for the `Entity` and all its descendants with the transform relative to the scene.
+    pub fn visit(&self, parent_matrix: &Matrix4<f32>, f: &mut impl FnMut(&Entity, &Matrix4<f32>)) {
+        let matrix = parent_matrix * self.transform.matrix();
+        f(self, &matrix);
+        for child in &self.children {
+            child.visit(&matrix, f);
+        }
+    }
+}
+
+/// Projection of a [`SceneCamera`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    /// Perspective projection with the vertical field of view in degrees
+    Perspective { fov: f32, near: f32, far: f32 },
+    Orthographic {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self::Perspective {
+            fov: 90.0,
+            near: 0.1,
+            far: 10000.0,
+        }
+    }
+}
+
+/// Camera in the scene
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneCamera {
+    pub name: String,
+    pub transform: Transform,
+    pub projection: Projection,
+}
+
+/// Type of a [`Light`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum LightKind {
+    /// Light that shines along the negative z axis of its transform
+    #[default]
+    Directional,
+    /// Light that shines in all directions from the position of its transform
+    Point { range: f32 },
+}
+
+/// Light in the scene
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Light {
+    pub name: String,
+    pub transform: Transform,
+    pub kind: LightKind,
+    /// Linear RGB color of the light
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            transform: Transform::default(),
+            kind: LightKind::default(),
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+        }
+    }
+}
+
+impl SceneAsset {
+    /// Loads the scene from a YAML file.
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::from_slice(&fs::read(path)?)
+    }
+
+    /// Saves the scene as YAML file.
+    pub fn save(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        fs::write(path, self.to_yaml()?)?;
+        Ok(())
+    }
+
+    /// Deserializes the scene from YAML.
+    pub fn from_slice(bytes: &[u8]) -> crate::Result<Self> {
+        serde_yaml::from_slice(bytes).map_err(|err| Error::FailedDeserialization(Box::new(err)))
+    }
+
+    /// Serializes the scene to YAML.
+    pub fn to_yaml(&self) -> crate::Result<String> {
+        serde_yaml::to_string(self).map_err(|err| Error::FailedSerialization(Box::new(err)))
+    }
+
+    /// Returns the [`AssetKey`]s of all models and point clouds that are referenced by the scene.
+    pub fn referenced_assets(&self) -> Vec<AssetKey> {
+        let mut asset_keys = Vec::new();
+        for entity in &self.entities {
+            entity.visit(&Matrix4::identity(), &mut |entity, _| match &entity.content {
+                EntityContent::Model(asset_key) | EntityContent::PointCloud(asset_key) => asset_keys.push(asset_key.clone()),
+                EntityContent::Empty => {}
+            });
+        }
+        asset_keys.sort();
+        asset_keys.dedup();
+        asset_keys
+    }
+}
+
+/// Processes a scene by validating it and writing it in normalized form.
+pub fn process_scene(asset_builder: &mut AssetBuilder) -> crate::Result<()> {
+    let scene = SceneAsset::load(asset_builder.unprocessed_asset_path())?;
+    let file_name = "scene.yaml";
+    scene.save(asset_builder.processed_asset_path().join(file_name))?;
+    asset_builder.with_file(file_name);
+    Ok(())
+}
+
+/// Imports a scene asset.
+pub fn import_scene(bytes: &[u8]) -> crate::Result<SceneAsset> {
+    SceneAsset::from_slice(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use jeriya_shared::nalgebra::Vector4;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn sample_scene() -> SceneAsset {
+        SceneAsset {
+            name: "Sample".to_owned(),
+            entities: vec![Entity {
+                name: "Root".to_owned(),
+                transform: Transform {
+                    position: Vector3::new(0.0, 1.0, 0.0),
+                    ..Transform::default()
+                },
+                content: EntityContent::Model(AssetKey::new("models/suzanne.glb")),
+                children: vec![Entity {
+                    name: "Child".to_owned(),
+                    transform: Transform {
+                        position: Vector3::new(1.0, 0.0, 0.0),
+                        rotation: UnitQuaternion::from_axis_angle(&Vector3::y_axis(), FRAC_PI_2),
+                        scale: Vector3::new(2.0, 2.0, 2.0),
+                    },
+                    content: EntityContent::PointCloud(AssetKey::new("point_clouds/bunny.ply")),
+                    children: Vec::new(),
+                }],
+            }],
+            cameras: vec![SceneCamera {
+                name: "Main".to_owned(),
+                ..SceneCamera::default()
+            }],
+            lights: vec![Light {
+                kind: LightKind::Point { range: 10.0 },
+                ..Light::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let root = TempDir::new("root").unwrap();
+        let path = root.path().join("sample.scene");
+        let scene = sample_scene();
+        scene.save(&path).unwrap();
+        assert_eq!(SceneAsset::load(&path).unwrap(), scene);
+    }
+
+    #[test]
+    fn minimal_yaml() {
+        let scene = SceneAsset::from_slice(b"entities:\n  - content: !Model models/suzanne.glb\n").unwrap();
+        assert_eq!(scene.entities.len(), 1);
+        assert_eq!(scene.entities[0].transform, Transform::default());
+        assert_eq!(scene.referenced_assets(), vec![AssetKey::new("models/suzanne.glb")]);
+
+        assert!(matches!(
+            SceneAsset::from_slice(b"unknown_field: 1\n"),
+            Err(Error::FailedDeserialization(_))
+        ));
+    }
+
+    #[test]
+    fn hierarchical_transforms() {
+        let scene = sample_scene();
+        let mut positions = Vec::new();
+        scene.entities[0].visit(&Matrix4::identity(), &mut |_, matrix| {
+            positions.push((matrix * Vector4::new(0.0, 0.0, 1.0, 1.0)).xyz());
+        });
+        assert_eq!(positions.len(), 2);
+        assert!((positions[0] - Vector3::new(0.0, 1.0, 1.0)).norm() < 1e-5);
+        assert!((positions[1] - Vector3::new(3.0, 1.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn process_and_import() {
+        let root = TempDir::new("root").unwrap();
+        let unprocessed_asset_path = root.path().join("sample.scene");
+        let processed_asset_path = root.path().join("processed");
+        fs::create_dir_all(&processed_asset_path).unwrap();
+        sample_scene().save(&unprocessed_asset_path).unwrap();
+
+        let mut asset_builder = AssetBuilder::new("sample.scene", &unprocessed_asset_path, &processed_asset_path);
+        process_scene(&mut asset_builder).unwrap();
+        let bytes = fs::read(processed_asset_path.join("scene.yaml")).unwrap();
+        assert_eq!(import_scene(&bytes).unwrap(), sample_scene());
+    }
+}
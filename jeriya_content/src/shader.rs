@@ -1,9 +1,20 @@
 use std::{
+    fs,
+    io::{self, Cursor, Write},
     path::{Path, PathBuf},
     process::Command,
 };
 
-use crate::asset_processor::AssetBuilder;
+use crate::{
+    asset_processor::{AssetBuilder, Processor},
+    common::{read_format_version, write_format_header},
+};
+
+/// Identifies the files that are written by [`process_shader`]
+const SHADER_MAGIC: &[u8; 4] = b"JSHD";
+
+/// Version of the format that is written by [`process_shader`]. Version 0 is the SPIR-V bytecode without a header.
+pub const SHADER_FORMAT_VERSION: u32 = 1;
 
 pub struct ShaderAsset {
     name: String,
@@ -66,19 +77,140 @@ impl ShaderAsset {
     pub fn spriv(&self) -> &[u8] {
         &self.spriv
     }
+
+    /// Writes the SPIR-V bytecode of the shader with the header of the current format version.
+    pub fn serialize_into<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_format_header(&mut writer, SHADER_MAGIC, SHADER_FORMAT_VERSION)?;
+        writer.write_all(&self.spriv)
+    }
 }
 
-/// Processes a model asset.
+/// Processes a shader asset. `#include` directives are only resolved relative to the including file.
 pub fn process_shader(asset_builder: &mut AssetBuilder) -> crate::Result<()> {
+    process_shader_with_include_paths(asset_builder, &[])
+}
+
+/// Returns a [`Processor`] for shader assets that additionally searches the given `include_paths` when resolving `#include` directives.
+pub fn shader_processor(include_paths: Vec<PathBuf>) -> Box<Processor> {
+    Box::new(move |asset_builder| process_shader_with_include_paths(asset_builder, &include_paths))
+}
+
+fn process_shader_with_include_paths(asset_builder: &mut AssetBuilder, include_paths: &[PathBuf]) -> crate::Result<()> {
+    let src_path = asset_builder.unprocessed_asset_path().to_owned();
+    let (source, included_files) = resolve_includes(&src_path, include_paths)?;
+    for included_file in included_files {
+        asset_builder.with_dependency(included_file);
+    }
+
+    // The shader compiler determines the shader stage from the file extension which is why
+    // the expanded source is written with the same file name as the unprocessed asset.
+    let file_name = src_path.file_name().ok_or(crate::Error::InvalidPath(src_path.clone()))?;
+    let expanded_src_path = asset_builder.processed_asset_path().join(file_name);
+    fs::write(&expanded_src_path, source)?;
+
     let dst_path = asset_builder.processed_asset_path().join("shader.spv");
     asset_builder.with_file(&dst_path);
-    ShaderAsset::compile_from(asset_builder.unprocessed_asset_path(), dst_path)?;
+    let shader = ShaderAsset::compile_from(expanded_src_path, &dst_path)?;
+
+    // The shader compiler writes the plain SPIR-V bytecode which is replaced by the versioned format.
+    shader.serialize_into(fs::File::create(&dst_path)?)?;
     Ok(())
 }
 
+/// Reads the shader at `path` and replaces every `#include "file"` and `#include <file>` directive with the content of the included file.
+///
+/// Quoted includes are searched relative to the including file first and then in the `include_paths`. Includes in angle
+/// brackets are only searched in the `include_paths`. Returns the expanded source and the canonical paths of all files
+/// that have been included directly or indirectly.
+pub fn resolve_includes(path: impl AsRef<Path>, include_paths: &[PathBuf]) -> crate::Result<(String, Vec<PathBuf>)> {
+    let mut include_stack = Vec::new();
+    let mut included_files = Vec::new();
+    let source = resolve_includes_recursive(path.as_ref(), include_paths, &mut include_stack, &mut included_files)?;
+    Ok((source, included_files))
+}
+
+enum IncludeDirective<'a> {
+    Quoted(&'a str),
+    AngleBrackets(&'a str),
+}
+
+fn resolve_includes_recursive(
+    path: &Path,
+    include_paths: &[PathBuf],
+    include_stack: &mut Vec<PathBuf>,
+    included_files: &mut Vec<PathBuf>,
+) -> crate::Result<String> {
+    let canonical_path = path.canonicalize()?;
+    if include_stack.contains(&canonical_path) {
+        return Err(crate::Error::IncludeCycle(canonical_path));
+    }
+    if !include_stack.is_empty() && !included_files.contains(&canonical_path) {
+        included_files.push(canonical_path.clone());
+    }
+
+    let source = fs::read_to_string(&canonical_path)?;
+    include_stack.push(canonical_path.clone());
+
+    let mut expanded_source = String::with_capacity(source.len());
+    for line in source.lines() {
+        let Some(include_directive) = parse_include_directive(line) else {
+            expanded_source.push_str(line);
+            expanded_source.push('\n');
+            continue;
+        };
+        let include_path = find_include(&include_directive, &canonical_path, include_paths)?;
+        let included_source = resolve_includes_recursive(&include_path, include_paths, include_stack, included_files)?;
+        expanded_source.push_str(&included_source);
+    }
+
+    include_stack.pop();
+    Ok(expanded_source)
+}
+
+fn parse_include_directive(line: &str) -> Option<IncludeDirective<'_>> {
+    let rest = line
+        .trim_start()
+        .strip_prefix('#')?
+        .trim_start()
+        .strip_prefix("include")?
+        .trim_start();
+    if let Some(rest) = rest.strip_prefix('"') {
+        rest.split_once('"').map(|(include, _)| IncludeDirective::Quoted(include))
+    } else if let Some(rest) = rest.strip_prefix('<') {
+        rest.split_once('>').map(|(include, _)| IncludeDirective::AngleBrackets(include))
+    } else {
+        None
+    }
+}
+
+fn find_include(include_directive: &IncludeDirective, including_file: &Path, include_paths: &[PathBuf]) -> crate::Result<PathBuf> {
+    let (include, search_relative) = match include_directive {
+        IncludeDirective::Quoted(include) => (*include, true),
+        IncludeDirective::AngleBrackets(include) => (*include, false),
+    };
+    let relative_directory = including_file.parent().filter(|_| search_relative);
+    relative_directory
+        .into_iter()
+        .chain(include_paths.iter().map(PathBuf::as_path))
+        .map(|directory| directory.join(include))
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| crate::Error::IncludeNotFound {
+            include: include.to_owned(),
+            file: including_file.to_owned(),
+        })
+}
+
 /// Imports a shader asset.
 pub fn import_shader(bytes: &[u8]) -> crate::Result<ShaderAsset> {
-    Ok(ShaderAsset::new("TODO", bytes.to_vec()))
+    let mut reader = Cursor::new(bytes);
+    match read_format_version(&mut reader, SHADER_MAGIC)? {
+        // Version 0 only lacks the header which has already been skipped.
+        0 | SHADER_FORMAT_VERSION => Ok(ShaderAsset::new("TODO", bytes[reader.position() as usize..].to_vec())),
+        found => Err(crate::Error::UnsupportedAssetVersion {
+            found,
+            expected: SHADER_FORMAT_VERSION,
+        }),
+    }
 }
 
 #[cfg(test)]
@@ -96,4 +228,81 @@ mod tests {
         assert!(!shader.spriv().is_empty());
         assert_eq!(shader.name(), "test.vert");
     }
+
+    #[test]
+    fn import_versioned_shader() {
+        let spirv = vec![0x03, 0x02, 0x23, 0x07, 1, 2, 3, 4];
+        let mut bytes = Vec::new();
+        ShaderAsset::new("test.vert", spirv.clone()).serialize_into(&mut bytes).unwrap();
+        assert_eq!(import_shader(&bytes).unwrap().spriv(), spirv);
+
+        // Shaders that were processed before the format was versioned
+        assert_eq!(import_shader(&spirv).unwrap().spriv(), spirv);
+
+        let mut bytes = Vec::new();
+        write_format_header(&mut bytes, SHADER_MAGIC, SHADER_FORMAT_VERSION + 1).unwrap();
+        assert!(matches!(
+            import_shader(&bytes),
+            Err(crate::Error::UnsupportedAssetVersion {
+                expected: SHADER_FORMAT_VERSION,
+                ..
+            })
+        ));
+    }
+
+    mod resolve_includes {
+        use std::fs;
+
+        use tempdir::TempDir;
+
+        use crate::{shader::resolve_includes, Error};
+
+        #[test]
+        fn smoke() {
+            let root = TempDir::new("root").unwrap();
+            let include_dir = root.path().join("include");
+            fs::create_dir_all(&include_dir).unwrap();
+            fs::write(
+                root.path().join("test.vert"),
+                "#version 450\n#include \"local.glsl\"\n#include <common.glsl>\nvoid main() {}\n",
+            )
+            .unwrap();
+            fs::write(root.path().join("local.glsl"), "const int LOCAL = 1;\n").unwrap();
+            fs::write(include_dir.join("common.glsl"), "#include \"nested.glsl\"\nconst int COMMON = 2;\n").unwrap();
+            fs::write(include_dir.join("nested.glsl"), "const int NESTED = 3;\n").unwrap();
+
+            let (source, included_files) = resolve_includes(root.path().join("test.vert"), std::slice::from_ref(&include_dir)).unwrap();
+            assert_eq!(
+                source,
+                "#version 450\nconst int LOCAL = 1;\nconst int NESTED = 3;\nconst int COMMON = 2;\nvoid main() {}\n"
+            );
+            assert_eq!(
+                included_files,
+                vec![
+                    root.path().join("local.glsl").canonicalize().unwrap(),
+                    include_dir.join("common.glsl").canonicalize().unwrap(),
+                    include_dir.join("nested.glsl").canonicalize().unwrap(),
+                ]
+            );
+        }
+
+        #[test]
+        fn not_found() {
+            let root = TempDir::new("root").unwrap();
+            fs::write(root.path().join("test.vert"), "#include <local.glsl>\n").unwrap();
+            fs::write(root.path().join("local.glsl"), "").unwrap();
+            let result = resolve_includes(root.path().join("test.vert"), &[]);
+            assert!(matches!(result, Err(Error::IncludeNotFound { include, .. }) if include == "local.glsl"));
+        }
+
+        #[test]
+        fn cycle() {
+            let root = TempDir::new("root").unwrap();
+            fs::write(root.path().join("test.vert"), "#include \"a.glsl\"\n").unwrap();
+            fs::write(root.path().join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+            fs::write(root.path().join("b.glsl"), "#include \"a.glsl\"\n").unwrap();
+            let result = resolve_includes(root.path().join("test.vert"), &[]);
+            assert!(matches!(result, Err(Error::IncludeCycle(path)) if path.ends_with("a.glsl")));
+        }
+    }
 }
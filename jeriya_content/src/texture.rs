@@ -0,0 +1,345 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+use image::{imageops::FilterType, RgbaImage};
+use intel_tex_2::{bc7, RgbaSurface};
+use jeriya_shared::{
+    byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
+    log::info,
+};
+
+use crate::asset_processor::{AssetBuilder, Processor};
+
+/// Identifier at the beginning of every KTX2 file
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Size of the header including the identifier and the index
+const KTX2_HEADER_SIZE: u64 = 80;
+
+/// Size of one entry in the level index
+const KTX2_LEVEL_INDEX_ENTRY_SIZE: u64 = 24;
+
+/// BC7 compresses blocks of 4x4 texels into 16 bytes
+const BLOCK_DIMENSION: u32 = 4;
+const BLOCK_BYTE_SIZE: usize = 16;
+
+/// GPU-compressed format in which the texture is stored
+///
+/// ASTC isn't supported because the ASTC encoder of `intel_tex_2` is not implemented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// BC7 with sRGB encoding. Supported by desktop GPUs.
+    Bc7Srgb,
+}
+
+impl TextureFormat {
+    /// Returns the `VkFormat` that is stored in the KTX2 container
+    pub fn vk_format(&self) -> u32 {
+        match self {
+            TextureFormat::Bc7Srgb => 146, // VK_FORMAT_BC7_SRGB_BLOCK
+        }
+    }
+
+    /// Returns the [`TextureFormat`] for the given `VkFormat`
+    pub fn from_vk_format(vk_format: u32) -> Option<Self> {
+        match vk_format {
+            146 => Some(TextureFormat::Bc7Srgb),
+            _ => None,
+        }
+    }
+
+    /// Returns the color model of the format in the data format descriptor
+    fn dfd_color_model(&self) -> u8 {
+        match self {
+            TextureFormat::Bc7Srgb => 134, // KHR_DF_MODEL_BC7
+        }
+    }
+
+    /// Compresses the texels of the given image which must have dimensions that are multiples of 4
+    fn compress(&self, image: &RgbaImage) -> Vec<u8> {
+        let surface = RgbaSurface {
+            width: image.width(),
+            height: image.height(),
+            stride: image.width() * 4,
+            data: image.as_raw(),
+        };
+        match self {
+            TextureFormat::Bc7Srgb => bc7::compress_blocks(&bc7::alpha_basic_settings(), &surface),
+        }
+    }
+}
+
+/// Texture with a full mip chain in a GPU-compressed format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureAsset {
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    /// Compressed data of the mip levels starting with the largest level
+    mip_levels: Vec<Vec<u8>>,
+}
+
+impl TextureAsset {
+    /// Compresses the given image into the given [`TextureFormat`] and generates the mip levels down to 1x1
+    pub fn compress(image: &RgbaImage, format: TextureFormat) -> Self {
+        let (width, height) = image.dimensions();
+        let mip_level_count = 32 - width.max(height).max(1).leading_zeros();
+        let mip_levels = (0..mip_level_count)
+            .map(|level| {
+                let level_width = (width >> level).max(1);
+                let level_height = (height >> level).max(1);
+                let level_image = if level == 0 {
+                    image.clone()
+                } else {
+                    image::imageops::resize(image, level_width, level_height, FilterType::Triangle)
+                };
+                format.compress(&pad_to_block_size(&level_image))
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            format,
+            mip_levels,
+        }
+    }
+
+    /// Returns the width of the largest mip level in texels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the largest mip level in texels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the format of the compressed data
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Returns the compressed data of the mip levels starting with the largest level
+    pub fn mip_levels(&self) -> &[Vec<u8>] {
+        &self.mip_levels
+    }
+
+    /// Writes the texture as a KTX2 file without supercompression
+    pub fn serialize_into<W: Write + Seek>(&self, mut writer: W) -> io::Result<()> {
+        let dfd = self.data_format_descriptor();
+        let level_count = self.mip_levels.len() as u64;
+        let dfd_offset = KTX2_HEADER_SIZE + level_count * KTX2_LEVEL_INDEX_ENTRY_SIZE;
+        let data_offset = align_to(dfd_offset + dfd.len() as u64, BLOCK_BYTE_SIZE as u64);
+
+        // The levels are stored from the smallest to the largest level
+        let mut level_offsets = vec![0; self.mip_levels.len()];
+        let mut offset = data_offset;
+        for (level, data) in self.mip_levels.iter().enumerate().rev() {
+            level_offsets[level] = offset;
+            offset = align_to(offset + data.len() as u64, BLOCK_BYTE_SIZE as u64);
+        }
+
+        writer.write_all(&KTX2_IDENTIFIER)?;
+        writer.write_u32::<LittleEndian>(self.format.vk_format())?;
+        writer.write_u32::<LittleEndian>(1)?; // typeSize
+        writer.write_u32::<LittleEndian>(self.width)?;
+        writer.write_u32::<LittleEndian>(self.height)?;
+        writer.write_u32::<LittleEndian>(0)?; // pixelDepth
+        writer.write_u32::<LittleEndian>(0)?; // layerCount
+        writer.write_u32::<LittleEndian>(1)?; // faceCount
+        writer.write_u32::<LittleEndian>(self.mip_levels.len() as u32)?;
+        writer.write_u32::<LittleEndian>(0)?; // supercompressionScheme
+        writer.write_u32::<LittleEndian>(dfd_offset as u32)?;
+        writer.write_u32::<LittleEndian>(dfd.len() as u32)?;
+        writer.write_u32::<LittleEndian>(0)?; // kvdByteOffset
+        writer.write_u32::<LittleEndian>(0)?; // kvdByteLength
+        writer.write_u64::<LittleEndian>(0)?; // sgdByteOffset
+        writer.write_u64::<LittleEndian>(0)?; // sgdByteLength
+        for (data, offset) in self.mip_levels.iter().zip(&level_offsets) {
+            writer.write_u64::<LittleEndian>(*offset)?;
+            writer.write_u64::<LittleEndian>(data.len() as u64)?;
+            writer.write_u64::<LittleEndian>(data.len() as u64)?; // uncompressedByteLength
+        }
+        writer.write_all(&dfd)?;
+        for (level, data) in self.mip_levels.iter().enumerate().rev() {
+            writer.seek(SeekFrom::Start(level_offsets[level]))?;
+            writer.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a texture from a KTX2 file that was written by [`TextureAsset::serialize_into`]
+    pub fn deserialize_from<R: Read + Seek>(mut reader: R) -> io::Result<Self> {
+        let invalid_data = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_owned());
+
+        let mut identifier = [0; 12];
+        reader.read_exact(&mut identifier)?;
+        if identifier != KTX2_IDENTIFIER {
+            return Err(invalid_data("not a KTX2 file"));
+        }
+        let vk_format = reader.read_u32::<LittleEndian>()?;
+        let format = TextureFormat::from_vk_format(vk_format).ok_or_else(|| invalid_data("unsupported VkFormat"))?;
+        let _type_size = reader.read_u32::<LittleEndian>()?;
+        let width = reader.read_u32::<LittleEndian>()?;
+        let height = reader.read_u32::<LittleEndian>()?;
+        let _pixel_depth = reader.read_u32::<LittleEndian>()?;
+        let _layer_count = reader.read_u32::<LittleEndian>()?;
+        let _face_count = reader.read_u32::<LittleEndian>()?;
+        let level_count = reader.read_u32::<LittleEndian>()?.max(1);
+        let supercompression_scheme = reader.read_u32::<LittleEndian>()?;
+        if supercompression_scheme != 0 {
+            return Err(invalid_data("supercompression is not supported"));
+        }
+
+        reader.seek(SeekFrom::Start(KTX2_HEADER_SIZE))?;
+        let level_index = (0..level_count)
+            .map(|_| {
+                let offset = reader.read_u64::<LittleEndian>()?;
+                let length = reader.read_u64::<LittleEndian>()?;
+                let _uncompressed_length = reader.read_u64::<LittleEndian>()?;
+                Ok((offset, length))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let mip_levels = level_index
+            .into_iter()
+            .map(|(offset, length)| {
+                let mut data = vec![0; length as usize];
+                reader.seek(SeekFrom::Start(offset))?;
+                reader.read_exact(&mut data)?;
+                Ok(data)
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            mip_levels,
+        })
+    }
+
+    /// Returns the data format descriptor with a single basic descriptor block
+    fn data_format_descriptor(&self) -> Vec<u8> {
+        const BASIC_BLOCK_SIZE: u16 = 24 + 16;
+        let mut dfd = Vec::new();
+        let write = |dfd: &mut Vec<u8>| -> io::Result<()> {
+            dfd.write_u32::<LittleEndian>(4 + BASIC_BLOCK_SIZE as u32)?; // dfdTotalSize
+            dfd.write_u32::<LittleEndian>(0)?; // vendorId and descriptorType
+            dfd.write_u16::<LittleEndian>(2)?; // versionNumber
+            dfd.write_u16::<LittleEndian>(BASIC_BLOCK_SIZE)?;
+            dfd.write_all(&[self.format.dfd_color_model(), 1, 2, 0])?; // colorModel, BT709 primaries, sRGB transfer, flags
+            let block_dimension = (BLOCK_DIMENSION - 1) as u8;
+            dfd.write_all(&[block_dimension, block_dimension, 0, 0])?;
+            dfd.write_all(&[BLOCK_BYTE_SIZE as u8, 0, 0, 0, 0, 0, 0, 0])?; // bytesPlane0..7
+            dfd.write_u16::<LittleEndian>(0)?; // bitOffset
+            dfd.write_all(&[(BLOCK_BYTE_SIZE * 8 - 1) as u8, 0])?; // bitLength and channelType
+            dfd.write_u32::<LittleEndian>(0)?; // samplePosition
+            dfd.write_u32::<LittleEndian>(0)?; // sampleLower
+            dfd.write_u32::<LittleEndian>(u32::MAX)?; // sampleUpper
+            Ok(())
+        };
+        write(&mut dfd).expect("writing to a Vec cannot fail");
+        dfd
+    }
+}
+
+/// Returns a [`Processor`] that compresses images into the given [`TextureFormat`]
+pub fn texture_processor(format: TextureFormat) -> Box<Processor> {
+    Box::new(move |asset_builder| process_texture_with_format(asset_builder, format))
+}
+
+/// Processes an image into a BC7 compressed KTX2 texture.
+pub fn process_texture(asset_builder: &mut AssetBuilder) -> crate::Result<()> {
+    process_texture_with_format(asset_builder, TextureFormat::Bc7Srgb)
+}
+
+fn process_texture_with_format(asset_builder: &mut AssetBuilder, format: TextureFormat) -> crate::Result<()> {
+    let path = asset_builder.unprocessed_asset_path().to_owned();
+    let image = image::open(&path).map_err(|err| crate::Error::Other(Box::new(err)))?.into_rgba8();
+    info!(
+        "Compressing texture '{}' with {}x{} texels to {format:?}",
+        path.display(),
+        image.width(),
+        image.height()
+    );
+    let texture = TextureAsset::compress(&image, format);
+    let file_name = "texture.ktx2";
+    let file = std::fs::File::create(asset_builder.processed_asset_path().join(file_name))?;
+    texture.serialize_into(io::BufWriter::new(file))?;
+    asset_builder.with_file(file_name);
+    Ok(())
+}
+
+/// Imports a texture asset.
+pub fn import_texture(bytes: &[u8]) -> crate::Result<TextureAsset> {
+    Ok(TextureAsset::deserialize_from(Cursor::new(bytes))?)
+}
+
+/// Extends the image to dimensions that are multiples of the block size by repeating the last row and column
+fn pad_to_block_size(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let padded_width = align_to(width as u64, BLOCK_DIMENSION as u64) as u32;
+    let padded_height = align_to(height as u64, BLOCK_DIMENSION as u64) as u32;
+    if padded_width == width && padded_height == height {
+        return image.clone();
+    }
+    RgbaImage::from_fn(padded_width, padded_height, |x, y| {
+        *image.get_pixel(x.min(width - 1), y.min(height - 1))
+    })
+}
+
+fn align_to(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    fn checkerboard(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            if (x + y) % 2 == 0 {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn compress_generates_mip_chain() {
+        let texture = TextureAsset::compress(&checkerboard(16, 6), TextureFormat::Bc7Srgb);
+        assert_eq!(texture.width(), 16);
+        assert_eq!(texture.height(), 6);
+        let level_sizes = texture.mip_levels().iter().map(Vec::len).collect::<Vec<_>>();
+        // 16x6 -> 4x2 blocks, 8x3 -> 2x1 blocks, 4x1, 2x1 and 1x1 -> 1 block
+        assert_eq!(level_sizes, vec![8 * 16, 2 * 16, 16, 16, 16]);
+    }
+
+    #[test]
+    fn serialize_and_deserialize() {
+        let texture = TextureAsset::compress(&checkerboard(8, 8), TextureFormat::Bc7Srgb);
+        let mut buffer = Cursor::new(Vec::new());
+        texture.serialize_into(&mut buffer).unwrap();
+        assert_eq!(&buffer.get_ref()[0..12], &KTX2_IDENTIFIER);
+        let deserialized = import_texture(buffer.get_ref()).unwrap();
+        assert_eq!(deserialized, texture);
+    }
+
+    #[test]
+    fn data_format_descriptor() {
+        let texture = TextureAsset::compress(&checkerboard(4, 4), TextureFormat::Bc7Srgb);
+        let dfd = texture.data_format_descriptor();
+        assert_eq!(dfd.len(), 44);
+        // colorModel follows dfdTotalSize, vendorId, descriptorType, versionNumber and descriptorBlockSize
+        assert_eq!(dfd[12], 134);
+    }
+
+    #[test]
+    fn invalid_identifier() {
+        assert!(import_texture(&[0; 128]).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! # Overview
+//!
+//! Integration of [`egui`] so that debug UIs and tooling panels can be embedded
+//! in applications that use the `Renderer`.
+//!
+//! The application forwards its `WindowEvent`s to [`EguiIntegration::on_window_event`]
+//! and calls [`EguiIntegration::run`] once per update. `run` builds the user
+//! interface, tessellates it and returns a [`UiFrame`] that is passed to
+//! `Renderer::render_ui_frame`. The renderer keeps drawing the most recent
+//! [`UiFrame`] on top of the overlay until the next one is received.
+//!
+//! egui works in points while the renderer works in pixels. The meshes and clip
+//! rectangles are scaled with the `pixels_per_point` of the frame. Textures that
+//! are registered by the user with `TextureId::User` are not supported and the
+//! meshes that use them are skipped.
+
+use egui::{
+    epaint::{ImageDelta, Primitive},
+    ClippedPrimitive, Context, ImageData, TextureFilter, TextureId, TexturesDelta,
+};
+use egui_winit::EventResponse;
+use jeriya_backend::ui::{UiFrame, UiMesh, UiTextureDelta, UiTextureId, UiVertex};
+use jeriya_shared::{
+    log::warn,
+    nalgebra::Vector2,
+    winit::{event::WindowEvent, window::Window},
+    AddressMode, Filter, SamplerConfig,
+};
+
+/// Translates the winit events into egui input and the egui output into [`UiFrame`]s
+pub struct EguiIntegration {
+    context: Context,
+    state: egui_winit::State,
+}
+
+impl EguiIntegration {
+    /// Creates a new `EguiIntegration` for the given `window`
+    pub fn new(window: &Window) -> Self {
+        let context = Context::default();
+        let state = egui_winit::State::new(
+            context.clone(),
+            context.viewport_id(),
+            window,
+            Some(window.scale_factor() as f32),
+            None,
+        );
+        Self { context, state }
+    }
+
+    /// Returns the egui [`Context`]
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Passes the `event` to egui
+    ///
+    /// The returned [`EventResponse`] tells whether egui consumed the event so that the application can ignore it,
+    /// e.g. when the cursor is over a window of the user interface.
+    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> EventResponse {
+        self.state.on_window_event(window, event)
+    }
+
+    /// Builds the user interface with `run_ui` and returns the [`UiFrame`] that renders it
+    pub fn run(&mut self, window: &Window, run_ui: impl FnOnce(&Context)) -> UiFrame {
+        let raw_input = self.state.take_egui_input(window);
+        let full_output = self.context.run(raw_input, run_ui);
+        self.state.handle_platform_output(window, full_output.platform_output);
+        let clipped_primitives = self.context.tessellate(full_output.shapes, full_output.pixels_per_point);
+        ui_frame(&full_output.textures_delta, &clipped_primitives, full_output.pixels_per_point)
+    }
+}
+
+/// Converts the output of an egui frame into a [`UiFrame`] in pixels
+pub fn ui_frame(textures_delta: &TexturesDelta, clipped_primitives: &[ClippedPrimitive], pixels_per_point: f32) -> UiFrame {
+    let textures_set = textures_delta
+        .set
+        .iter()
+        .filter_map(|(texture_id, image_delta)| Some(texture_delta(ui_texture_id(*texture_id)?, image_delta)))
+        .collect();
+    let meshes = clipped_primitives
+        .iter()
+        .filter_map(|clipped_primitive| ui_mesh(clipped_primitive, pixels_per_point))
+        .collect();
+    let textures_free = textures_delta.free.iter().copied().filter_map(ui_texture_id).collect();
+    UiFrame {
+        textures_set,
+        meshes,
+        textures_free,
+    }
+}
+
+/// Returns the [`UiTextureId`] of an egui texture or `None` for textures that are registered by the user
+fn ui_texture_id(texture_id: TextureId) -> Option<UiTextureId> {
+    match texture_id {
+        TextureId::Managed(id) => Some(UiTextureId(id)),
+        TextureId::User(_) => None,
+    }
+}
+
+fn texture_delta(id: UiTextureId, image_delta: &ImageDelta) -> UiTextureDelta {
+    let rgba = match &image_delta.image {
+        ImageData::Color(color_image) => color_image.pixels.iter().flat_map(|color| color.to_array()).collect(),
+        ImageData::Font(font_image) => font_image.srgba_pixels(None).flat_map(|color| color.to_array()).collect(),
+    };
+    let filter = |texture_filter| match texture_filter {
+        TextureFilter::Nearest => Filter::Nearest,
+        TextureFilter::Linear => Filter::Linear,
+    };
+    let sampler_config = SamplerConfig {
+        mag_filter: filter(image_delta.options.magnification),
+        min_filter: filter(image_delta.options.minification),
+        mipmap_filter: Filter::Nearest,
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        address_mode_w: AddressMode::ClampToEdge,
+        max_anisotropy: None,
+        ..SamplerConfig::linear()
+    };
+    UiTextureDelta {
+        id,
+        position: image_delta.pos.map(|[x, y]| [x as u32, y as u32]),
+        width: image_delta.image.width() as u32,
+        height: image_delta.image.height() as u32,
+        rgba,
+        sampler_config,
+    }
+}
+
+fn ui_mesh(clipped_primitive: &ClippedPrimitive, pixels_per_point: f32) -> Option<UiMesh> {
+    let Primitive::Mesh(mesh) = &clipped_primitive.primitive else {
+        warn!("Paint callbacks are not supported by the egui integration");
+        return None;
+    };
+    let texture = ui_texture_id(mesh.texture_id)?;
+    let clip_rect = clipped_primitive.clip_rect;
+    let vertices = mesh
+        .vertices
+        .iter()
+        .map(|vertex| UiVertex {
+            position: Vector2::new(vertex.pos.x, vertex.pos.y) * pixels_per_point,
+            texture_coordinates: Vector2::new(vertex.uv.x, vertex.uv.y),
+            color: vertex.color.to_array(),
+        })
+        .collect();
+    Some(UiMesh {
+        clip_rect_min: Vector2::new(clip_rect.min.x, clip_rect.min.y) * pixels_per_point,
+        clip_rect_max: Vector2::new(clip_rect.max.x, clip_rect.max.y) * pixels_per_point,
+        texture,
+        vertices,
+        indices: mesh.indices.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{pos2, Color32, Mesh, RawInput, Rect};
+
+    use super::*;
+
+    #[test]
+    fn label() {
+        let context = Context::default();
+        let full_output = context.run(RawInput::default(), |context| {
+            egui::CentralPanel::default().show(context, |ui| ui.label("Hello Jeriya"));
+        });
+        let clipped_primitives = context.tessellate(full_output.shapes, full_output.pixels_per_point);
+        let ui_frame = ui_frame(&full_output.textures_delta, &clipped_primitives, full_output.pixels_per_point);
+
+        // The font atlas is uploaded in the first frame
+        let font_texture = ui_frame
+            .textures_set
+            .iter()
+            .find(|texture_delta| texture_delta.id == UiTextureId(0))
+            .unwrap();
+        assert!(!font_texture.is_partial());
+        assert_eq!(
+            font_texture.rgba.len(),
+            font_texture.width as usize * font_texture.height as usize * 4
+        );
+        assert!(!ui_frame.meshes.is_empty());
+        assert!(ui_frame.meshes.iter().all(|mesh| mesh.indices.len() % 3 == 0));
+    }
+
+    #[test]
+    fn points_are_scaled_to_pixels() {
+        let mut mesh = Mesh::default();
+        mesh.colored_vertex(pos2(10.0, 20.0), Color32::RED);
+        mesh.add_triangle(0, 0, 0);
+        let clipped_primitive = ClippedPrimitive {
+            clip_rect: Rect::from_min_max(pos2(1.0, 2.0), pos2(3.0, 4.0)),
+            primitive: Primitive::Mesh(mesh),
+        };
+        let ui_frame = ui_frame(&TexturesDelta::default(), &[clipped_primitive], 2.0);
+        let ui_mesh = &ui_frame.meshes[0];
+        assert_eq!(ui_mesh.clip_rect_min, Vector2::new(2.0, 4.0));
+        assert_eq!(ui_mesh.clip_rect_max, Vector2::new(6.0, 8.0));
+        assert_eq!(ui_mesh.vertices[0].position, Vector2::new(20.0, 40.0));
+        assert_eq!(ui_mesh.vertices[0].color, [255, 0, 0, 255]);
+        assert_eq!(ui_mesh.indices, vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn user_textures_are_skipped() {
+        let mesh = Mesh::with_texture(TextureId::User(3));
+        let clipped_primitive = ClippedPrimitive {
+            clip_rect: Rect::EVERYTHING,
+            primitive: Primitive::Mesh(mesh),
+        };
+        let ui_frame = ui_frame(&TexturesDelta::default(), &[clipped_primitive], 1.0);
+        assert!(ui_frame.meshes.is_empty());
+    }
+}
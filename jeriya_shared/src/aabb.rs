@@ -1,4 +1,4 @@
-use nalgebra::Vector3;
+use nalgebra::{Matrix4, Point3, Vector3};
 
 use serde::{Deserialize, Serialize};
 
@@ -312,6 +312,159 @@ impl AABB {
     pub fn is_empty(&self) -> bool {
         self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
     }
+
+    /// Returns the distance from the `point` to the closest point of the `AABB`. It is 0 when the `point` is inside of the `AABB`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// let bounding_box = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+    /// assert_eq!(bounding_box.distance_to(&Vector3::new(0.5, 0.5, 0.5)), 0.0);
+    /// assert_eq!(bounding_box.distance_to(&Vector3::new(0.5, 3.0, 0.5)), 2.0);
+    /// ```
+    pub fn distance_to(&self, point: &Vector3<f32>) -> f32 {
+        if self.is_empty() {
+            return f32::INFINITY;
+        }
+        (self.min - point).sup(&(point - self.max)).sup(&Vector3::zeros()).norm()
+    }
+
+    /// Returns the `AABB` that contains the corners of the `AABB` after they have been transformed by the `matrix`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Matrix4, Vector3};
+    /// # use jeriya_shared::aabb::AABB;
+    /// let bounding_box = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+    /// let transformed = bounding_box.transformed(&Matrix4::new_translation(&Vector3::new(2.0, 0.0, 0.0)));
+    /// assert_eq!(transformed, AABB::new(Vector3::new(2.0, 0.0, 0.0), Vector3::new(3.0, 1.0, 1.0)));
+    /// ```
+    pub fn transformed(&self, matrix: &Matrix4<f32>) -> AABB {
+        if self.is_empty() {
+            return *self;
+        }
+        self.corners()
+            .iter()
+            .map(|corner| matrix.transform_point(&Point3::from(*corner)).coords)
+            .collect()
+    }
+
+    /// Returns the eight corners of the `AABB`. The bits 0, 1 and 2 of the index select the maximum of the x, y and z axis.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// let bounding_box = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 2.0, 3.0));
+    /// let corners = bounding_box.corners();
+    /// assert_eq!(corners[0], Vector3::new(0.0, 0.0, 0.0));
+    /// assert_eq!(corners[3], Vector3::new(1.0, 2.0, 0.0));
+    /// assert_eq!(corners[7], Vector3::new(1.0, 2.0, 3.0));
+    /// ```
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        std::array::from_fn(|corner| {
+            Vector3::new(
+                if corner & 1 == 0 { self.min.x } else { self.max.x },
+                if corner & 2 == 0 { self.min.y } else { self.max.y },
+                if corner & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    /// Returns the intersection of the `AABB` and the `other` one. The result is empty when they don't overlap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// let bounding_box1 = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 2.0, 2.0));
+    /// let bounding_box2 = AABB::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(3.0, 3.0, 3.0));
+    /// let intersection = bounding_box1.intersection(&bounding_box2);
+    /// assert_eq!(intersection, AABB::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(2.0, 2.0, 2.0)));
+    ///
+    /// let bounding_box3 = AABB::new(Vector3::new(5.0, 5.0, 5.0), Vector3::new(6.0, 6.0, 6.0));
+    /// assert!(bounding_box1.intersection(&bounding_box3).is_empty());
+    /// ```
+    pub fn intersection(&self, other: &AABB) -> AABB {
+        AABB {
+            min: self.min.sup(&other.min),
+            max: self.max.inf(&other.max),
+        }
+    }
+
+    /// Returns whether the `AABB` and the `other` one overlap. `AABB`s that only touch each other are overlapping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// let bounding_box1 = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+    /// let bounding_box2 = AABB::new(Vector3::new(1.0, 0.0, 0.0), Vector3::new(2.0, 1.0, 1.0));
+    /// assert!(bounding_box1.intersects(&bounding_box2));
+    /// assert!(!bounding_box1.intersects(&AABB::empty()));
+    /// ```
+    pub fn intersects(&self, other: &AABB) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    /// Returns the surface area of the `AABB`. It is 0 when the `AABB` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// let bounding_box = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 2.0, 3.0));
+    /// assert_eq!(bounding_box.surface_area(), 22.0);
+    /// assert_eq!(AABB::empty().surface_area(), 0.0);
+    /// ```
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// Returns the distance along the ray at which it enters the `AABB` or `None` when the ray misses the `AABB`
+    ///
+    /// The distance is measured in multiples of the `direction`. When the `origin` is inside of the `AABB`, the distance is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// # use jeriya_shared::float_cmp::assert_approx_eq;
+    /// let bounding_box = AABB::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
+    /// let distance = bounding_box.intersect_ray(&Vector3::new(0.0, 0.0, -5.0), &Vector3::new(0.0, 0.0, 1.0));
+    /// assert_approx_eq!(f32, distance.unwrap(), 4.0, ulps = 1);
+    ///
+    /// // The AABB is behind the origin of the ray
+    /// assert!(bounding_box.intersect_ray(&Vector3::new(0.0, 0.0, 5.0), &Vector3::new(0.0, 0.0, 1.0)).is_none());
+    /// ```
+    pub fn intersect_ray(&self, origin: &Vector3<f32>, direction: &Vector3<f32>) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut entry = 0.0_f32;
+        let mut exit = f32::INFINITY;
+        for axis in 0..3 {
+            // A division by zero yields infinite slab distances which correctly classify rays that are parallel to the slab
+            let inverse_direction = 1.0 / direction[axis];
+            let near = (self.min[axis] - origin[axis]) * inverse_direction;
+            let far = (self.max[axis] - origin[axis]) * inverse_direction;
+            entry = entry.max(near.min(far));
+            exit = exit.min(near.max(far));
+        }
+        (entry <= exit).then_some(entry)
+    }
 }
 
 impl FromIterator<Vector3<f32>> for AABB {
@@ -418,6 +571,20 @@ mod tests {
         assert_approx_eq!(f32, bounding_box.max.z, 3.0, ulps = 1);
     }
 
+    #[test]
+    fn intersect_ray_parallel() {
+        let bounding_box = AABB::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
+        let direction = Vector3::new(1.0, 0.0, 0.0);
+        assert_approx_eq!(
+            f32,
+            bounding_box.intersect_ray(&Vector3::new(-3.0, 0.5, 0.0), &direction).unwrap(),
+            2.0,
+            ulps = 1
+        );
+        assert!(bounding_box.intersect_ray(&Vector3::new(-3.0, 2.0, 0.0), &direction).is_none());
+        assert!(AABB::empty().intersect_ray(&Vector3::zeros(), &direction).is_none());
+    }
+
     #[test]
     fn extend_ref() {
         let mut bounding_box = AABB::empty();
@@ -0,0 +1,162 @@
+use nalgebra::Vector3;
+
+use serde::{Deserialize, Serialize};
+
+use crate::aabb::AABB;
+
+/// Sphere that bounds a set of points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Creates a new [`BoundingSphere`] that contains nothing (radius: `-1.0`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::bounding_sphere::BoundingSphere;
+    /// assert!(BoundingSphere::empty().is_empty());
+    /// ```
+    pub fn empty() -> Self {
+        Self {
+            center: Vector3::zeros(),
+            radius: -1.0,
+        }
+    }
+
+    /// Creates a new [`BoundingSphere`] with the given `center` and `radius`.
+    pub fn new(center: Vector3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Creates a new [`BoundingSphere`] that contains the given `points`.
+    ///
+    /// The sphere is fitted with Ritter's algorithm which is fast but doesn't necessarily find the minimal sphere.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::bounding_sphere::BoundingSphere;
+    /// let points = [Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.5, 0.0)];
+    /// let bounding_sphere = BoundingSphere::from_slice(&points);
+    /// assert_eq!(bounding_sphere.center, Vector3::new(0.0, 0.0, 0.0));
+    /// assert_eq!(bounding_sphere.radius, 1.0);
+    /// ```
+    pub fn from_slice(points: &[Vector3<f32>]) -> Self {
+        let Some(first) = points.first() else {
+            return Self::empty();
+        };
+
+        // Start with the sphere spanned by two points that are far apart
+        let furthest_from = |point: &Vector3<f32>| {
+            *points
+                .iter()
+                .max_by(|a, b| (*a - point).norm_squared().total_cmp(&(*b - point).norm_squared()))
+                .expect("points must not be empty")
+        };
+        let a = furthest_from(first);
+        let b = furthest_from(&a);
+        let mut center = (a + b) / 2.0;
+        let mut radius = (b - a).norm() / 2.0;
+
+        // Grow the sphere until all points are contained
+        for point in points {
+            let distance = (point - center).norm();
+            if distance > radius {
+                let new_radius = (radius + distance) / 2.0;
+                center += (point - center) * ((new_radius - radius) / distance);
+                radius = new_radius;
+            }
+        }
+
+        Self { center, radius }
+    }
+
+    /// Creates a new [`BoundingSphere`] that contains the given `aabb`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// # use jeriya_shared::bounding_sphere::BoundingSphere;
+    /// let aabb = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 2.0, 2.0));
+    /// let bounding_sphere = BoundingSphere::from_aabb(&aabb);
+    /// assert_eq!(bounding_sphere.center, Vector3::new(1.0, 1.0, 1.0));
+    /// assert_eq!(bounding_sphere.radius, 3.0f32.sqrt());
+    /// ```
+    pub fn from_aabb(aabb: &AABB) -> Self {
+        if aabb.is_empty() {
+            return Self::empty();
+        }
+        Self {
+            center: aabb.center(),
+            radius: aabb.size().norm() / 2.0,
+        }
+    }
+
+    /// Returns the [`AABB`] that contains the [`BoundingSphere`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// # use jeriya_shared::bounding_sphere::BoundingSphere;
+    /// let bounding_sphere = BoundingSphere::new(Vector3::new(1.0, 0.0, 0.0), 2.0);
+    /// assert_eq!(bounding_sphere.to_aabb(), AABB::new(Vector3::new(-1.0, -2.0, -2.0), Vector3::new(3.0, 2.0, 2.0)));
+    /// assert!(BoundingSphere::empty().to_aabb().is_empty());
+    /// ```
+    pub fn to_aabb(&self) -> AABB {
+        if self.is_empty() {
+            return AABB::empty();
+        }
+        let extent = Vector3::repeat(self.radius);
+        AABB::new(self.center - extent, self.center + extent)
+    }
+
+    /// Returns `true` if the [`BoundingSphere`] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.radius < 0.0
+    }
+
+    /// Returns whether the `point` is inside of the [`BoundingSphere`]. Points on the surface are inside.
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        (point - self.center).norm_squared() <= self.radius * self.radius && !self.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_slice() {
+        assert!(BoundingSphere::from_slice(&[]).is_empty());
+    }
+
+    #[test]
+    fn contains_all_points() {
+        let points = (0..100)
+            .map(|i| {
+                let t = i as f32 * 0.37;
+                Vector3::new(t.sin() * 3.0, t.cos() * 2.0, (t * 1.7).sin())
+            })
+            .collect::<Vec<_>>();
+        let bounding_sphere = BoundingSphere::from_slice(&points);
+        for point in &points {
+            assert!((point - bounding_sphere.center).norm() <= bounding_sphere.radius + 1e-5);
+        }
+    }
+
+    #[test]
+    fn serialization() {
+        let bounding_sphere = BoundingSphere::new(Vector3::new(1.0, 2.0, 3.0), 4.0);
+        let json = serde_json::to_string(&bounding_sphere).unwrap();
+        assert_eq!(serde_json::from_str::<BoundingSphere>(&json).unwrap(), bounding_sphere);
+    }
+}
@@ -0,0 +1,251 @@
+use nalgebra::Vector3;
+
+use crate::aabb::AABB;
+
+/// Maximum number of values in a leaf of the [`Bvh`]
+const MAX_LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Inner { aabb: AABB, left: usize, right: usize },
+    Leaf { aabb: AABB, start: usize, end: usize },
+}
+
+impl Node {
+    fn aabb(&self) -> &AABB {
+        match self {
+            Node::Inner { aabb, .. } | Node::Leaf { aabb, .. } => aabb,
+        }
+    }
+}
+
+/// Bounding volume hierarchy over values that are enclosed by an [`AABB`]
+///
+/// The hierarchy is built top-down by splitting the values at the median of the longest axis of their [`AABB`]s. When
+/// the values move, [`Bvh::refit`] updates the [`AABB`]s without changing the hierarchy which is much cheaper than
+/// building a new `Bvh` but degrades the queries when the values move far.
+///
+/// # Examples
+///
+/// ```
+/// # use jeriya_shared::aabb::AABB;
+/// # use jeriya_shared::bvh::Bvh;
+/// # use jeriya_shared::nalgebra::Vector3;
+/// let unit_cube_at = |x: f32| AABB::from_center_and_size(Vector3::new(x, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+/// let bvh = Bvh::new((0..10).map(|i| (unit_cube_at(i as f32 * 2.0), i)));
+///
+/// let (nearest, distance) = bvh.nearest(&Vector3::new(4.0, 2.0, 0.0)).unwrap();
+/// assert_eq!(*nearest, 2);
+/// assert_eq!(distance, 1.5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Bvh<T> {
+    nodes: Vec<Node>,
+    values: Vec<(AABB, T)>,
+}
+
+impl<T> Bvh<T> {
+    /// Builds a new `Bvh` from the `values` and their [`AABB`]s
+    pub fn new(values: impl IntoIterator<Item = (AABB, T)>) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            values: values.into_iter().filter(|(aabb, _)| !aabb.is_empty()).collect(),
+        };
+        if !bvh.values.is_empty() {
+            bvh.build_node(0, bvh.values.len());
+        }
+        bvh
+    }
+
+    /// Returns the number of values in the `Bvh`
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether the `Bvh` contains no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the values and their [`AABB`]s in no particular order
+    pub fn values(&self) -> impl Iterator<Item = (&AABB, &T)> {
+        self.values.iter().map(|(aabb, value)| (aabb, value))
+    }
+
+    /// Replaces the [`AABB`]s of the values with the ones returned by `aabb` and updates the hierarchy to enclose them
+    pub fn refit(&mut self, mut aabb: impl FnMut(&T) -> AABB) {
+        for (value_aabb, value) in &mut self.values {
+            *value_aabb = aabb(value);
+        }
+        // The children are always stored after their parent so that iterating backwards visits them first
+        for node_index in (0..self.nodes.len()).rev() {
+            let refitted_aabb = match self.nodes[node_index] {
+                Node::Inner { left, right, .. } => self.nodes[left].aabb().union(self.nodes[right].aabb()),
+                Node::Leaf { start, end, .. } => self.values[start..end]
+                    .iter()
+                    .fold(AABB::empty(), |aabb, (value_aabb, _)| aabb.union(value_aabb)),
+            };
+            match &mut self.nodes[node_index] {
+                Node::Inner { aabb, .. } | Node::Leaf { aabb, .. } => *aabb = refitted_aabb,
+            }
+        }
+    }
+
+    /// Returns the value whose [`AABB`] is closest to the `point` together with the distance to the [`AABB`]
+    ///
+    /// The distance is 0 when the `point` is inside of the [`AABB`].
+    pub fn nearest(&self, point: &Vector3<f32>) -> Option<(&T, f32)> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut nearest = None;
+        let mut nearest_distance = f32::INFINITY;
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.aabb().distance_to(point) >= nearest_distance {
+                continue;
+            }
+            match node {
+                Node::Inner { left, right, .. } => {
+                    // The closer child is visited first so that the other one is more likely to be skipped
+                    let (near, far) = if self.nodes[*left].aabb().distance_to(point) <= self.nodes[*right].aabb().distance_to(point) {
+                        (*left, *right)
+                    } else {
+                        (*right, *left)
+                    };
+                    stack.extend([far, near]);
+                }
+                Node::Leaf { start, end, .. } => {
+                    for (aabb, value) in &self.values[*start..*end] {
+                        let distance = aabb.distance_to(point);
+                        if distance < nearest_distance {
+                            nearest = Some(value);
+                            nearest_distance = distance;
+                        }
+                    }
+                }
+            }
+        }
+        nearest.map(|value| (value, nearest_distance))
+    }
+
+    /// Returns the values whose [`AABB`] is hit by the ray together with the distance at which the ray enters the [`AABB`]
+    ///
+    /// The distance is measured in multiples of the `direction`. The values are returned in no particular order.
+    pub fn raycast(&self, origin: &Vector3<f32>, direction: &Vector3<f32>) -> Vec<(&T, f32)> {
+        let mut hits = Vec::new();
+        if self.nodes.is_empty() {
+            return hits;
+        }
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.aabb().intersect_ray(origin, direction).is_none() {
+                continue;
+            }
+            match node {
+                Node::Inner { left, right, .. } => stack.extend([*left, *right]),
+                Node::Leaf { start, end, .. } => {
+                    for (aabb, value) in &self.values[*start..*end] {
+                        if let Some(distance) = aabb.intersect_ray(origin, direction) {
+                            hits.push((value, distance));
+                        }
+                    }
+                }
+            }
+        }
+        hits
+    }
+
+    /// Builds the node for the values in `start..end` and returns its index
+    fn build_node(&mut self, start: usize, end: usize) -> usize {
+        let aabb = self.values[start..end]
+            .iter()
+            .fold(AABB::empty(), |aabb, (value_aabb, _)| aabb.union(value_aabb));
+        let node_index = self.nodes.len();
+        if end - start <= MAX_LEAF_SIZE {
+            self.nodes.push(Node::Leaf { aabb, start, end });
+            return node_index;
+        }
+
+        // The values are split at the median of their centers along the longest axis
+        let size = aabb.size();
+        let axis = size.imax();
+        self.values[start..end].sort_unstable_by(|(a, _), (b, _)| a.center()[axis].total_cmp(&b.center()[axis]));
+        let middle = start + (end - start) / 2;
+
+        // The children are pushed after their parent so that the root is always the first node
+        self.nodes.push(Node::Leaf { aabb, start, end });
+        let left = self.build_node(start, middle);
+        let right = self.build_node(middle, end);
+        self.nodes[node_index] = Node::Inner { aabb, left, right };
+        node_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_cube_at(x: f32) -> AABB {
+        AABB::from_center_and_size(Vector3::new(x, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn empty() {
+        let bvh = Bvh::<usize>::new([]);
+        assert!(bvh.is_empty());
+        assert!(bvh.raycast(&Vector3::zeros(), &Vector3::x()).is_empty());
+    }
+
+    #[test]
+    fn raycast() {
+        let bvh = Bvh::new((0..20).map(|i| (unit_cube_at(i as f32 * 3.0), i)));
+        assert_eq!(bvh.len(), 20);
+
+        // The ray along the x axis hits all cubes
+        let mut hits = bvh.raycast(&Vector3::new(-5.0, 0.0, 0.0), &Vector3::x());
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(
+            hits.iter().map(|(value, _)| **value).collect::<Vec<_>>(),
+            (0..20).collect::<Vec<_>>()
+        );
+        assert!((hits[1].1 - 7.5).abs() < 1e-5);
+
+        // The ray along the y axis only hits the cube below which it starts
+        let hits = bvh.raycast(&Vector3::new(9.0, -5.0, 0.0), &Vector3::y());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(*hits[0].0, 3);
+
+        assert!(bvh.raycast(&Vector3::new(0.0, 5.0, 0.0), &Vector3::x()).is_empty());
+    }
+
+    #[test]
+    fn nearest() {
+        let bvh = Bvh::new((0..20).map(|i| (unit_cube_at(i as f32 * 3.0), i)));
+        let (value, distance) = bvh.nearest(&Vector3::new(30.0, 0.0, 0.0)).unwrap();
+        assert_eq!(*value, 10);
+        assert_eq!(distance, 0.0);
+        let (value, distance) = bvh.nearest(&Vector3::new(-10.0, 0.0, 0.0)).unwrap();
+        assert_eq!(*value, 0);
+        assert!((distance - 9.5).abs() < 1e-5);
+        assert!(Bvh::<usize>::new([]).nearest(&Vector3::zeros()).is_none());
+    }
+
+    #[test]
+    fn refit() {
+        let mut bvh = Bvh::new((0..20).map(|i| (unit_cube_at(i as f32 * 3.0), i)));
+
+        // Moving the cubes onto the y axis requires the AABBs of the inner nodes to grow
+        bvh.refit(|i| AABB::from_center_and_size(Vector3::new(0.0, *i as f32 * 3.0, 0.0), Vector3::new(1.0, 1.0, 1.0)));
+        assert_eq!(bvh.raycast(&Vector3::new(-5.0, 0.0, 0.0), &Vector3::x()).len(), 1);
+        let mut hits = bvh.raycast(&Vector3::new(0.0, -5.0, 0.0), &Vector3::y());
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(
+            hits.iter().map(|(value, _)| **value).collect::<Vec<_>>(),
+            (0..20).collect::<Vec<_>>()
+        );
+        assert_eq!(*bvh.nearest(&Vector3::new(0.0, 30.0, 0.0)).unwrap().0, 10);
+    }
+}
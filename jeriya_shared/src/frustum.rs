@@ -0,0 +1,217 @@
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+use serde::{Deserialize, Serialize};
+
+use crate::aabb::AABB;
+
+/// Plane in Hessian normal form. Points on the side the `normal` is pointing to have a positive signed distance.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a new [`Plane`] from the coefficients `a*x + b*y + c*z + d = 0` and normalizes it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Vector3, Vector4};
+    /// # use jeriya_shared::frustum::Plane;
+    /// let plane = Plane::from_coefficients(&Vector4::new(0.0, 2.0, 0.0, -2.0));
+    /// assert_eq!(plane.normal, Vector3::new(0.0, 1.0, 0.0));
+    /// assert_eq!(plane.distance, -1.0);
+    /// ```
+    pub fn from_coefficients(coefficients: &Vector4<f32>) -> Self {
+        let length = coefficients.xyz().norm();
+        Self {
+            normal: coefficients.xyz() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    /// Returns the signed distance of the `point` to the [`Plane`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Vector3, Vector4};
+    /// # use jeriya_shared::frustum::Plane;
+    /// let plane = Plane::from_coefficients(&Vector4::new(0.0, 1.0, 0.0, -1.0));
+    /// assert_eq!(plane.signed_distance(&Vector3::new(5.0, 3.0, 0.0)), 2.0);
+    /// assert_eq!(plane.signed_distance(&Vector3::new(0.0, 0.0, 0.0)), -1.0);
+    /// ```
+    pub fn signed_distance(&self, point: &Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// View frustum defined by six [`Plane`]s whose normals point to the inside.
+///
+/// The planes are extracted from a view-projection matrix that maps to Vulkan clip space in which
+/// the depth is in the range `[0, 1]` (e.g. `nalgebra_glm::perspective_rh_zo`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub const LEFT: usize = 0;
+    pub const RIGHT: usize = 1;
+    pub const BOTTOM: usize = 2;
+    pub const TOP: usize = 3;
+    pub const NEAR: usize = 4;
+    pub const FAR: usize = 5;
+
+    /// Extracts the [`Frustum`] from the given view-projection matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Matrix4, Vector3};
+    /// # use jeriya_shared::frustum::Frustum;
+    /// let frustum = Frustum::from_view_projection(&Matrix4::identity());
+    /// assert!(frustum.contains_point(&Vector3::new(0.0, 0.0, 0.5)));
+    /// assert!(!frustum.contains_point(&Vector3::new(2.0, 0.0, 0.5)));
+    /// ```
+    pub fn from_view_projection(view_projection: &Matrix4<f32>) -> Self {
+        let row = |index: usize| view_projection.row(index).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Self {
+            planes: [
+                Plane::from_coefficients(&(w + x)),
+                Plane::from_coefficients(&(w - x)),
+                Plane::from_coefficients(&(w + y)),
+                Plane::from_coefficients(&(w - y)),
+                Plane::from_coefficients(&z),
+                Plane::from_coefficients(&(w - z)),
+            ],
+        }
+    }
+
+    /// Returns the [`Plane`]s of the [`Frustum`]. They can be indexed with [`Frustum::LEFT`], [`Frustum::RIGHT`], etc.
+    pub fn planes(&self) -> &[Plane; 6] {
+        &self.planes
+    }
+
+    /// Returns whether the `point` is inside the [`Frustum`]. Points on the boundary are inside.
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Returns whether the `aabb` is at least partially inside the [`Frustum`].
+    ///
+    /// This is a conservative test: `AABB`s that are close to the corners of the [`Frustum`] might be
+    /// reported as intersecting even though they are outside.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Matrix4, Vector3};
+    /// # use jeriya_shared::aabb::AABB;
+    /// # use jeriya_shared::frustum::Frustum;
+    /// let frustum = Frustum::from_view_projection(&Matrix4::identity());
+    /// assert!(frustum.intersects_aabb(&AABB::new(Vector3::new(0.5, 0.5, 0.5), Vector3::new(3.0, 3.0, 3.0))));
+    /// assert!(!frustum.intersects_aabb(&AABB::new(Vector3::new(2.0, 0.0, 0.0), Vector3::new(3.0, 1.0, 1.0))));
+    /// assert!(!frustum.intersects_aabb(&AABB::empty()));
+    /// ```
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        self.planes.iter().all(|plane| {
+            // The corner that is furthest along the normal of the plane
+            let positive_vertex = Vector3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.signed_distance(&positive_vertex) >= 0.0
+        })
+    }
+
+    /// Returns whether the sphere with the given `center` and `radius` is at least partially inside the [`Frustum`].
+    ///
+    /// This is a conservative test like [`Frustum::intersects_aabb`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Matrix4, Vector3};
+    /// # use jeriya_shared::frustum::Frustum;
+    /// let frustum = Frustum::from_view_projection(&Matrix4::identity());
+    /// assert!(frustum.intersects_sphere(&Vector3::new(1.5, 0.0, 0.5), 1.0));
+    /// assert!(!frustum.intersects_sphere(&Vector3::new(2.5, 0.0, 0.5), 1.0));
+    /// ```
+    pub fn intersects_sphere(&self, center: &Vector3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(center) >= -radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::assert_approx_eq;
+
+    use super::*;
+
+    fn perspective_frustum() -> Frustum {
+        let projection = nalgebra_glm::perspective_rh_zo(1.0, std::f32::consts::FRAC_PI_2, 1.0, 100.0);
+        let view = nalgebra_glm::look_at_rh(
+            &Vector3::new(0.0, 0.0, 0.0),
+            &Vector3::new(0.0, 0.0, -1.0),
+            &Vector3::new(0.0, 1.0, 0.0),
+        );
+        Frustum::from_view_projection(&(projection * view))
+    }
+
+    #[test]
+    fn planes() {
+        let frustum = perspective_frustum();
+        let planes = frustum.planes();
+        assert_approx_eq!(f32, planes[Frustum::NEAR].normal.z, -1.0, epsilon = 1e-5);
+        assert_approx_eq!(f32, planes[Frustum::NEAR].distance, -1.0, epsilon = 1e-5);
+        assert_approx_eq!(f32, planes[Frustum::FAR].normal.z, 1.0, epsilon = 1e-5);
+        assert_approx_eq!(f32, planes[Frustum::FAR].distance, 100.0, epsilon = 1e-3);
+        let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+        assert_approx_eq!(f32, planes[Frustum::LEFT].normal.x, half_sqrt2, epsilon = 1e-5);
+        assert_approx_eq!(f32, planes[Frustum::RIGHT].normal.x, -half_sqrt2, epsilon = 1e-5);
+        assert_approx_eq!(f32, planes[Frustum::BOTTOM].normal.y, half_sqrt2, epsilon = 1e-5);
+        assert_approx_eq!(f32, planes[Frustum::TOP].normal.y, -half_sqrt2, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn contains_point() {
+        let frustum = perspective_frustum();
+        assert!(frustum.contains_point(&Vector3::new(0.0, 0.0, -10.0)));
+        assert!(frustum.contains_point(&Vector3::new(9.0, -9.0, -10.0)));
+        assert!(!frustum.contains_point(&Vector3::new(11.0, 0.0, -10.0)));
+        assert!(!frustum.contains_point(&Vector3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(&Vector3::new(0.0, 0.0, -101.0)));
+        assert!(!frustum.contains_point(&Vector3::new(0.0, 0.0, 10.0)));
+    }
+
+    #[test]
+    fn intersects_aabb() {
+        let frustum = perspective_frustum();
+        let inside = AABB::new(Vector3::new(-1.0, -1.0, -11.0), Vector3::new(1.0, 1.0, -9.0));
+        let partially_inside = AABB::new(Vector3::new(9.0, -1.0, -11.0), Vector3::new(12.0, 1.0, -9.0));
+        let enclosing = AABB::new(Vector3::new(-1000.0, -1000.0, -1000.0), Vector3::new(1000.0, 1000.0, 1000.0));
+        let behind = AABB::new(Vector3::new(-1.0, -1.0, 1.0), Vector3::new(1.0, 1.0, 3.0));
+        let beside = AABB::new(Vector3::new(12.0, -1.0, -11.0), Vector3::new(14.0, 1.0, -9.0));
+        assert!(frustum.intersects_aabb(&inside));
+        assert!(frustum.intersects_aabb(&partially_inside));
+        assert!(frustum.intersects_aabb(&enclosing));
+        assert!(!frustum.intersects_aabb(&behind));
+        assert!(!frustum.intersects_aabb(&beside));
+    }
+
+    #[test]
+    fn intersects_sphere() {
+        let frustum = perspective_frustum();
+        assert!(frustum.intersects_sphere(&Vector3::new(0.0, 0.0, -10.0), 1.0));
+        assert!(frustum.intersects_sphere(&Vector3::new(0.0, 0.0, -101.5), 2.0));
+        assert!(!frustum.intersects_sphere(&Vector3::new(0.0, 0.0, -103.0), 2.0));
+        assert!(!frustum.intersects_sphere(&Vector3::new(0.0, 0.0, 5.0), 2.0));
+    }
+}
@@ -46,9 +46,15 @@ impl<T> Handle<T> {
     }
 }
 
+/// Container that hands out [`Handle`]s for the inserted elements.
+///
+/// Every slot has a generation that is incremented when its element is removed. A [`Handle`] stores the
+/// generation of the slot at the time of the insertion so that handles to removed elements are detected
+/// even when the slot has been reused.
 pub struct IndexingContainer<T> {
     data: Vec<T>,
     generations: Vec<usize>,
+    occupied: Vec<bool>,
     free_list: VecDeque<usize>,
 }
 
@@ -65,12 +71,34 @@ impl<T: Default> IndexingContainer<T> {
     ///
     /// This is currently only implemented for `T: Default` to prevent unsafe code.
     pub fn remove(&mut self, handle: &Handle<T>) -> Option<T> {
-        if handle.generation() == self.generations[handle.index()] {
-            self.generations[handle.index()] += 1;
-            self.free_list.push_back(handle.index());
-            Some(mem::take(&mut self.data[handle.index()]))
-        } else {
-            None
+        self.remove_with(handle, T::default())
+    }
+
+    /// Removes all elements for which `keep` returns `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jeriya_shared::IndexingContainer;
+    /// let mut indexing_container = IndexingContainer::<usize>::new();
+    /// let handle1 = indexing_container.insert(1);
+    /// let handle2 = indexing_container.insert(2);
+    /// indexing_container.retain(|_handle, value| *value % 2 == 0);
+    /// assert_eq!(indexing_container.get(&handle1), None);
+    /// assert_eq!(indexing_container.get(&handle2), Some(&2));
+    /// ```
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Handle<T>, &mut T) -> bool,
+    {
+        for index in 0..self.data.len() {
+            if !self.occupied[index] {
+                continue;
+            }
+            let handle = Handle::new_unchecked(index, self.generations[index]);
+            if !keep(&handle, &mut self.data[index]) {
+                self.remove(&handle);
+            }
         }
     }
 }
@@ -81,6 +109,7 @@ impl<T> IndexingContainer<T> {
         Self {
             data: Vec::new(),
             generations: Vec::new(),
+            occupied: Vec::new(),
             free_list: VecDeque::new(),
         }
     }
@@ -125,6 +154,7 @@ impl<T> IndexingContainer<T> {
             match insert(&handle) {
                 Ok(value) => {
                     self.data[free_index] = value;
+                    self.occupied[free_index] = true;
                     Ok(handle)
                 }
                 Err(err) => {
@@ -140,6 +170,7 @@ impl<T> IndexingContainer<T> {
             let value = insert(&handle)?;
             self.data.push(value);
             self.generations.push(0);
+            self.occupied.push(true);
             Ok(handle)
         }
     }
@@ -149,9 +180,28 @@ impl<T> IndexingContainer<T> {
         self.insert_with(|_| Result::<T, ()>::Ok(value)).expect("insertion cannot fail")
     }
 
+    /// Returns `true` if the element at the given handle hasn't been removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jeriya_shared::IndexingContainer;
+    /// let mut indexing_container = IndexingContainer::<usize>::new();
+    /// let handle = indexing_container.insert(7);
+    /// assert!(indexing_container.contains(&handle));
+    ///
+    /// // The handle stays outdated even when its slot is reused.
+    /// indexing_container.remove(&handle);
+    /// indexing_container.insert(8);
+    /// assert!(!indexing_container.contains(&handle));
+    /// ```
+    pub fn contains(&self, handle: &Handle<T>) -> bool {
+        self.generations.get(handle.index()) == Some(&handle.generation()) && self.occupied[handle.index()]
+    }
+
     /// Returns a reference to the element at the given handle.
     pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
-        if handle.generation() == self.generations[handle.index()] {
+        if self.contains(handle) {
             Some(&self.data[handle.index()])
         } else {
             None
@@ -160,13 +210,59 @@ impl<T> IndexingContainer<T> {
 
     /// Returns a mutable reference to the element at the given handle.
     pub fn get_mut(&mut self, handle: &Handle<T>) -> Option<&mut T> {
-        if handle.generation() == self.generations[handle.index()] {
+        if self.contains(handle) {
             Some(&mut self.data[handle.index()])
         } else {
             None
         }
     }
 
+    /// Returns an iterator over the elements that haven't been removed together with their handles.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jeriya_shared::IndexingContainer;
+    /// let mut indexing_container = IndexingContainer::<usize>::new();
+    /// let handle = indexing_container.insert(7);
+    /// indexing_container.insert(8);
+    /// indexing_container.remove(&handle);
+    /// let values = indexing_container.iter().map(|(_handle, value)| *value).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![8]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.occupied[*index])
+            .map(|(index, value)| (Handle::new_unchecked(index, self.generations[index]), value))
+    }
+
+    /// Returns an iterator over mutable references to the elements that haven't been removed together with their handles.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        let generations = &self.generations;
+        let occupied = &self.occupied;
+        self.data
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| occupied[*index])
+            .map(|(index, value)| (Handle::new_unchecked(index, generations[index]), value))
+    }
+
+    /// Removes the element at the given handle and returns it. The slot is filled with `replacement` until it is reused.
+    ///
+    /// This is the alternative to [`IndexingContainer::remove`] for types that don't implement `Default`.
+    pub fn remove_with(&mut self, handle: &Handle<T>, replacement: T) -> Option<T> {
+        if self.contains(handle) {
+            self.generations[handle.index()] += 1;
+            self.occupied[handle.index()] = false;
+            self.free_list.push_back(handle.index());
+            Some(mem::replace(&mut self.data[handle.index()], replacement))
+        } else {
+            None
+        }
+    }
+
     /// Returns the number of elements in the container.
     pub fn len(&self) -> usize {
         self.data.len() - self.free_list.len()
@@ -183,6 +279,8 @@ impl<T> IndexingContainer<T> {
     }
 
     /// Returns a slice containing all elements in the container.
+    ///
+    /// Removed elements stay in the slice until their slot is reused. Use [`IndexingContainer::iter`] to skip them.
     pub fn as_slice(&self) -> &[T] {
         &self.data
     }
@@ -326,6 +424,59 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 3);
     }
 
+    #[test]
+    fn test_outdated_handle() {
+        let mut container = IndexingContainer::<usize>::new();
+        let handle1 = container.insert(7);
+        container.remove(&handle1);
+        let handle2 = container.insert(8);
+        assert_eq!(handle1.index(), handle2.index());
+
+        assert_eq!(container.get(&handle1), None);
+        assert_eq!(container.get_mut(&handle1), None);
+        assert_eq!(container.remove(&handle1), None);
+        assert_eq!(container.get(&handle2), Some(&8));
+
+        // Handles from other containers don't panic
+        let foreign = IndexingContainer::<usize>::new().insert(0);
+        assert!(IndexingContainer::<usize>::new().get(&foreign).is_none());
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut container = IndexingContainer::<usize>::new();
+        let handle1 = container.insert(7);
+        let handle2 = container.insert(8);
+        let handle3 = container.insert(9);
+        container.remove(&handle2);
+
+        let entries = container.iter().map(|(handle, value)| (handle, *value)).collect::<Vec<_>>();
+        assert_eq!(entries, vec![(handle1, 7), (handle3, 9)]);
+
+        for (_handle, value) in container.iter_mut() {
+            *value += 1;
+        }
+        assert_eq!(container.get(&handle1), Some(&8));
+        assert_eq!(container.get(&handle3), Some(&10));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut container = IndexingContainer::<usize>::new();
+        let handles = (0..6).map(|value| container.insert(value)).collect::<Vec<_>>();
+        container.retain(|_handle, value| *value % 3 == 0);
+        assert_eq!(container.len(), 2);
+        assert_eq!(container.free_count(), 4);
+        assert!(container.contains(&handles[0]));
+        assert!(container.contains(&handles[3]));
+        assert!(!container.contains(&handles[1]));
+
+        // Removed slots are reused
+        let handle = container.insert(10);
+        assert_eq!(handle.generation(), 1);
+        assert_eq!(container.len(), 3);
+    }
+
     #[test]
     fn test_as_slice() {
         let mut container = IndexingContainer::<usize>::new();
@@ -1,22 +1,30 @@
 pub mod aabb;
+pub mod bounding_sphere;
+pub mod bvh;
 mod debug_info;
 mod event_queue;
+pub mod frustum;
 mod indexing_container;
+pub mod obb;
 pub mod obj_writer;
+mod sampler_config;
 
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    path::PathBuf,
     result,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use nalgebra::{Vector3, Vector4};
 use serde::{Deserialize, Serialize};
-use winit::window::Window;
+use winit::window::{Fullscreen, Window, WindowId};
 
 pub use debug_info::*;
 pub use event_queue::*;
 pub use indexing_container::*;
+pub use sampler_config::*;
 
 pub use ahash;
 pub use async_trait;
@@ -239,6 +247,81 @@ impl From<[f32; 4]> for ByteColor4 {
 pub enum FrameRate {
     Unlimited,
     Limited(u32),
+    /// The frame rate is adjusted between `min` and `max` frames per second depending on the GPU time of the recent frames
+    ///
+    /// The frame rate is lowered when the GPU is busy for most of a frame so that it isn't running at full load all the
+    /// time, e.g. to avoid thermal throttling on laptops. On fast machines, the frame rate rises up to `max`.
+    Adaptive {
+        min: u32,
+        max: u32,
+    },
+}
+
+/// Determines how the frames of a window are presented to the display.
+///
+/// In contrast to the [`FrameRate`] which only paces the CPU, the `PresentMode` determines how the presentation engine
+/// synchronizes with the vertical blank. When the `PresentMode` is not supported by the surface, it falls back to
+/// [`PresentMode::Mailbox`] and finally to [`PresentMode::Fifo`] which is always supported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Frames are queued and presented on the vertical blank. No tearing, but the latency increases when the queue is full.
+    Fifo,
+    /// Only the latest frame is presented on the vertical blank. No tearing and low latency.
+    #[default]
+    Mailbox,
+    /// Frames are presented immediately. Lowest latency, but tearing might be visible.
+    Immediate,
+}
+
+/// Color space in which the frames of a window are presented to the display.
+///
+/// The HDR color spaces are only available when the surface supports them. Otherwise, the window falls back to
+/// [`ColorSpace::Sdr`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// sRGB with 8 bits per channel
+    #[default]
+    Sdr,
+    /// BT.2020 primaries with the PQ (ST 2084) transfer function and 10 bits per channel
+    Hdr10,
+    /// Linear BT.709 primaries with 16-bit floats where 1.0 corresponds to 80 nits and values above 1.0 are brighter than SDR white
+    ScRgb,
+}
+
+/// Determines how a [`Window`] is displayed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowMode {
+    /// Window with decorations that can be moved and resized
+    #[default]
+    Windowed,
+    /// Window without decorations that covers the monitor on which it is displayed
+    Borderless,
+    /// Exclusive fullscreen with the video mode of the monitor that has the highest resolution and refresh rate
+    Exclusive,
+}
+
+impl WindowMode {
+    /// Returns the [`Fullscreen`] state of the `window` for the `WindowMode` on its current monitor
+    ///
+    /// Falls back to the borderless fullscreen when the monitor doesn't report any video modes.
+    pub fn fullscreen(&self, window: &Window) -> Option<Fullscreen> {
+        match self {
+            WindowMode::Windowed => None,
+            WindowMode::Borderless => Some(Fullscreen::Borderless(window.current_monitor())),
+            WindowMode::Exclusive => {
+                let video_mode = window.current_monitor().and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|video_mode| {
+                        let size = video_mode.size();
+                        (size.width as u64 * size.height as u64, video_mode.refresh_rate_millihertz())
+                    })
+                });
+                match video_mode {
+                    Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                    None => Some(Fullscreen::Borderless(window.current_monitor())),
+                }
+            }
+        }
+    }
 }
 
 /// Configuration for the [`Window`]s
@@ -246,6 +329,35 @@ pub enum FrameRate {
 pub struct WindowConfig<'w> {
     pub window: &'w Window,
     pub frame_rate: FrameRate,
+    pub present_mode: PresentMode,
+    pub color_space: ColorSpace,
+}
+
+/// Configuration for a render target that is rendered into an offscreen image instead of a [`Window`]
+///
+/// Headless render targets don't need a display, e.g. for rendering tests in CI or for generating thumbnails on a
+/// server. They are addressed with their `window_id` in the same way as windows.
+#[derive(Clone, Debug)]
+pub struct HeadlessConfig {
+    pub window_id: WindowId,
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: FrameRate,
+}
+
+impl HeadlessConfig {
+    /// Creates a `HeadlessConfig` with a `window_id` that is unique among the headless render targets
+    ///
+    /// The ids are counted down from `u64::MAX` so that they don't collide with the ids of the windows.
+    pub fn new(width: u32, height: u32, frame_rate: FrameRate) -> Self {
+        static NEXT_WINDOW_ID: AtomicU64 = AtomicU64::new(u64::MAX);
+        Self {
+            window_id: WindowId::from(NEXT_WINDOW_ID.fetch_sub(1, Ordering::Relaxed)),
+            width,
+            height,
+            frame_rate,
+        }
+    }
 }
 
 /// Configuration for the [`Renderer`]
@@ -262,10 +374,22 @@ pub struct RendererConfig {
     pub maximum_number_of_point_cloud_instances: usize,
     pub maximum_number_of_point_cloud_pages: usize,
     pub maximum_number_of_visible_point_cloud_clusters: usize,
+    /// Number of point cloud pages that the GPU can request per frame for the point clouds whose pages are streamed
+    pub maximum_number_of_point_cloud_page_requests: usize,
+    /// Number of streamed point cloud pages that are resident at the same time. They share the `maximum_number_of_point_cloud_pages`
+    /// with the eagerly uploaded pages. The least recently used pages are evicted when the limit is reached or the pages are used up.
+    pub maximum_number_of_streamed_point_cloud_pages: usize,
     pub maximum_meshlets: usize,
     pub maximum_visible_rigid_mesh_instances: usize,
     pub maximum_visible_rigid_mesh_meshlets: usize,
     pub maximum_number_of_device_local_debug_lines: usize,
+    /// Size of the bindless texture array through which materials index their textures
+    pub maximum_number_of_textures: usize,
+    /// Collects the pipeline statistics of every frame into the frame telemetry when the device supports it
+    pub collect_pipeline_statistics: bool,
+    /// File in which the compiled pipelines are cached between runs. The cache is loaded on startup and written
+    /// when `Renderer::save_pipeline_cache` is called.
+    pub pipeline_cache_path: Option<PathBuf>,
 }
 
 impl RendererConfig {
@@ -282,11 +406,16 @@ impl RendererConfig {
             maximum_number_of_point_cloud_instances: 8,
             maximum_number_of_point_cloud_pages: 16,
             maximum_number_of_visible_point_cloud_clusters: 64,
+            maximum_number_of_point_cloud_page_requests: 16,
+            maximum_number_of_streamed_point_cloud_pages: 8,
             maximum_number_of_rigid_mesh_instances: 32,
             maximum_meshlets: 64,
             maximum_visible_rigid_mesh_instances: 32,
             maximum_visible_rigid_mesh_meshlets: 64,
             maximum_number_of_device_local_debug_lines: 64,
+            maximum_number_of_textures: 64,
+            collect_pipeline_statistics: false,
+            pipeline_cache_path: None,
         }
     }
 
@@ -303,11 +432,16 @@ impl RendererConfig {
             maximum_number_of_point_cloud_instances: 2usize.pow(10),
             maximum_number_of_point_cloud_pages: 2usize.pow(14),
             maximum_number_of_visible_point_cloud_clusters: 2usize.pow(20),
+            maximum_number_of_point_cloud_page_requests: 2usize.pow(10),
+            maximum_number_of_streamed_point_cloud_pages: 2usize.pow(13),
             maximum_number_of_rigid_mesh_instances: 2usize.pow(10),
             maximum_meshlets: 2usize.pow(20),
             maximum_visible_rigid_mesh_instances: 2usize.pow(10),
             maximum_visible_rigid_mesh_meshlets: 2usize.pow(20),
             maximum_number_of_device_local_debug_lines: 2usize.pow(14),
+            maximum_number_of_textures: 2usize.pow(12),
+            collect_pipeline_statistics: false,
+            pipeline_cache_path: None,
         }
     }
 }
@@ -0,0 +1,225 @@
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
+
+use serde::{Deserialize, Serialize};
+
+use crate::aabb::AABB;
+
+/// Oriented bounding box defined by its `center`, the `half_extents` along its local axes and the `rotation`
+/// whose columns are the local axes in world space.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OBB {
+    pub center: Vector3<f32>,
+    pub half_extents: Vector3<f32>,
+    pub rotation: Matrix3<f32>,
+}
+
+impl OBB {
+    /// Creates a new [`OBB`] that contains nothing (half extents: `-1.0`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::obb::OBB;
+    /// assert!(OBB::empty().is_empty());
+    /// ```
+    pub fn empty() -> Self {
+        Self {
+            center: Vector3::zeros(),
+            half_extents: Vector3::repeat(-1.0),
+            rotation: Matrix3::identity(),
+        }
+    }
+
+    /// Creates a new [`OBB`] with the given `center`, `half_extents` and `rotation`.
+    pub fn new(center: Vector3<f32>, half_extents: Vector3<f32>, rotation: Matrix3<f32>) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+
+    /// Creates a new [`OBB`] that has the same extent as the given `aabb`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// # use jeriya_shared::obb::OBB;
+    /// let aabb = AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 4.0, 6.0));
+    /// let obb = OBB::from_aabb(&aabb);
+    /// assert_eq!(obb.center, Vector3::new(1.0, 2.0, 3.0));
+    /// assert_eq!(obb.half_extents, Vector3::new(1.0, 2.0, 3.0));
+    /// assert_eq!(obb.to_aabb(), aabb);
+    /// ```
+    pub fn from_aabb(aabb: &AABB) -> Self {
+        if aabb.is_empty() {
+            return Self::empty();
+        }
+        Self {
+            center: aabb.center(),
+            half_extents: aabb.size() / 2.0,
+            rotation: Matrix3::identity(),
+        }
+    }
+
+    /// Creates a new [`OBB`] that contains the given `points`.
+    ///
+    /// The axes are the principal components of the `points`. When the resulting box has a larger volume than the
+    /// [`AABB`] of the `points`, the [`AABB`] is returned instead so that the [`OBB`] is never looser than the [`AABB`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::aabb::AABB;
+    /// # use jeriya_shared::obb::OBB;
+    /// // Points along the diagonal of the xy plane
+    /// let points = (0..10)
+    ///     .flat_map(|i| {
+    ///         let t = i as f32;
+    ///         [Vector3::new(t, t + 0.1, 0.0), Vector3::new(t + 0.1, t, 0.0)]
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// let obb = OBB::from_slice(&points);
+    /// let aabb = AABB::from_slice(&points);
+    /// assert!(obb.volume() <= aabb.size().product());
+    /// assert!(obb.half_extents.max() > 6.0);
+    /// ```
+    pub fn from_slice(points: &[Vector3<f32>]) -> Self {
+        if points.is_empty() {
+            return Self::empty();
+        }
+
+        let count = points.len() as f32;
+        let mean = points.iter().sum::<Vector3<f32>>() / count;
+        let covariance = points
+            .iter()
+            .map(|point| {
+                let offset = point - mean;
+                offset * offset.transpose()
+            })
+            .sum::<Matrix3<f32>>()
+            / count;
+
+        // The eigenvectors of the covariance matrix are the principal axes. They are
+        // orthonormal but might form a left-handed basis which is corrected here.
+        let mut rotation = SymmetricEigen::new(covariance).eigenvectors;
+        if rotation.determinant() < 0.0 {
+            rotation.set_column(2, &-rotation.column(2));
+        }
+
+        let local_aabb = AABB::from_ref_iter(&points.iter().map(|point| rotation.transpose() * point).collect::<Vec<_>>());
+        let obb = Self {
+            center: rotation * local_aabb.center(),
+            half_extents: local_aabb.size() / 2.0,
+            rotation,
+        };
+
+        let aabb = AABB::from_slice(points);
+        if aabb.size().product() < obb.volume() {
+            Self::from_aabb(&aabb)
+        } else {
+            obb
+        }
+    }
+
+    /// Returns the [`AABB`] that contains the [`OBB`].
+    pub fn to_aabb(&self) -> AABB {
+        if self.is_empty() {
+            return AABB::empty();
+        }
+        AABB::from_slice(&self.corners())
+    }
+
+    /// Returns the eight corners of the [`OBB`] in world space. The bits 0, 1 and 2 of the index select the positive side of the local x, y and z axis.
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        std::array::from_fn(|corner| {
+            let sign = |bit: usize| if corner & (1 << bit) != 0 { 1.0 } else { -1.0 };
+            let local = Vector3::new(
+                sign(0) * self.half_extents.x,
+                sign(1) * self.half_extents.y,
+                sign(2) * self.half_extents.z,
+            );
+            self.center + self.rotation * local
+        })
+    }
+
+    /// Returns `true` if the [`OBB`] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.half_extents.x < 0.0 || self.half_extents.y < 0.0 || self.half_extents.z < 0.0
+    }
+
+    /// Returns the volume of the [`OBB`]. It is 0 when the [`OBB`] is empty.
+    pub fn volume(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        8.0 * self.half_extents.product()
+    }
+
+    /// Returns whether the `point` is inside of the [`OBB`]. Points on the boundary are inside.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::{Matrix3, Rotation3, Vector3};
+    /// # use jeriya_shared::obb::OBB;
+    /// let rotation = Rotation3::from_axis_angle(&Vector3::z_axis(), std::f32::consts::FRAC_PI_4);
+    /// let obb = OBB::new(Vector3::zeros(), Vector3::new(2.0, 0.1, 0.1), *rotation.matrix());
+    /// assert!(obb.contains_point(&Vector3::new(1.0, 1.0, 0.0)));
+    /// assert!(!obb.contains_point(&Vector3::new(1.0, -1.0, 0.0)));
+    /// ```
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        let local = self.rotation.transpose() * (point - self.center);
+        local.x.abs() <= self.half_extents.x && local.y.abs() <= self.half_extents.y && local.z.abs() <= self.half_extents.z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Rotation3;
+
+    use super::*;
+
+    #[test]
+    fn empty_slice() {
+        assert!(OBB::from_slice(&[]).is_empty());
+        assert!(OBB::empty().to_aabb().is_empty());
+    }
+
+    #[test]
+    fn contains_all_points() {
+        let rotation = Rotation3::from_euler_angles(0.3, 0.7, 1.1);
+        let points = (0..100)
+            .map(|i| {
+                let t = i as f32 * 0.37;
+                rotation * Vector3::new(t.sin() * 5.0, t.cos() * 0.5, (t * 1.7).sin() * 0.1)
+            })
+            .collect::<Vec<_>>();
+        let obb = OBB::from_slice(&points);
+        for point in &points {
+            let local = obb.rotation.transpose() * (point - obb.center);
+            assert!((local.abs() - obb.half_extents).max() <= 1e-4);
+        }
+        assert!(obb.volume() < AABB::from_slice(&points).size().product());
+        assert!((obb.rotation.determinant() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn to_aabb() {
+        let rotation = Rotation3::from_axis_angle(&Vector3::z_axis(), std::f32::consts::FRAC_PI_2);
+        let obb = OBB::new(Vector3::new(1.0, 0.0, 0.0), Vector3::new(2.0, 1.0, 1.0), *rotation.matrix());
+        let aabb = obb.to_aabb();
+        assert!((aabb.min - Vector3::new(0.0, -2.0, -1.0)).norm() < 1e-5);
+        assert!((aabb.max - Vector3::new(2.0, 2.0, 1.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn serialization() {
+        let obb = OBB::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(4.0, 5.0, 6.0), Matrix3::identity());
+        let json = serde_json::to_string(&obb).unwrap();
+        assert_eq!(serde_json::from_str::<OBB>(&json).unwrap(), obb);
+    }
+}
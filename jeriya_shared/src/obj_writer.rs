@@ -1,4 +1,9 @@
-use std::io::{self, Write};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use nalgebra::{Vector2, Vector3};
 
 use crate::aabb::AABB;
 
@@ -37,3 +42,407 @@ pub fn write_bounding_box_o(o_name: &str, vertex_offset: usize, mut obj_writer:
 
     Ok(8)
 }
+
+/// Material that is written to the MTL file by [`ObjWriter::write_material`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient_color: Vector3<f32>,
+    pub diffuse_color: Vector3<f32>,
+    pub specular_color: Vector3<f32>,
+    pub specular_exponent: f32,
+    /// Path of the diffuse texture relative to the MTL file
+    pub diffuse_texture: Option<String>,
+}
+
+impl ObjMaterial {
+    /// Creates a new [`ObjMaterial`] with the given `name` and `color` as ambient and diffuse color.
+    pub fn from_color(name: impl Into<String>, color: Vector3<f32>) -> Self {
+        Self {
+            name: name.into(),
+            ambient_color: color,
+            diffuse_color: color,
+            specular_color: Vector3::new(1.0, 1.0, 1.0),
+            specular_exponent: 10.0,
+            diffuse_texture: None,
+        }
+    }
+}
+
+/// Vertex attributes and triangle indices of a mesh that is written by [`ObjWriter::write_mesh`].
+///
+/// The `normals` and `texture_coordinates` must have the same length as the `positions` when they are given.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjMesh<'a> {
+    pub positions: &'a [Vector3<f32>],
+    pub normals: Option<&'a [Vector3<f32>]>,
+    pub texture_coordinates: Option<&'a [Vector2<f32>]>,
+    pub indices: &'a [u32],
+}
+
+/// Number of positions that [`ObjWriter::with_deduplication`] remembers by default which takes roughly 40 MiB of memory.
+pub const DEFAULT_DEDUPLICATION_CAPACITY: usize = 1 << 20;
+
+/// Writes an OBJ file and its accompanying MTL file.
+///
+/// The number of written vertex attributes is tracked so that the faces of consecutive meshes reference the correct vertices.
+///
+/// # Examples
+///
+/// ```
+/// # use jeriya_shared::nalgebra::{Vector2, Vector3};
+/// # use jeriya_shared::obj_writer::{ObjMaterial, ObjMesh, ObjWriter};
+/// let mut obj_writer = ObjWriter::new(Vec::new(), Vec::new(), "triangle.mtl").unwrap();
+/// obj_writer.write_material(&ObjMaterial::from_color("red", Vector3::new(1.0, 0.0, 0.0))).unwrap();
+/// obj_writer.write_object("triangle").unwrap();
+/// obj_writer.use_material("red").unwrap();
+/// obj_writer
+///     .write_mesh(&ObjMesh {
+///         positions: &[Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)],
+///         normals: Some(&[Vector3::z(); 3]),
+///         texture_coordinates: None,
+///         indices: &[0, 1, 2],
+///     })
+///     .unwrap();
+/// let (obj, mtl) = obj_writer.into_inner();
+/// assert!(String::from_utf8(obj).unwrap().contains("f 1//1 2//2 3//3"));
+/// assert!(String::from_utf8(mtl).unwrap().contains("newmtl red"));
+/// ```
+pub struct ObjWriter<O: Write, M: Write> {
+    obj_writer: O,
+    mtl_writer: M,
+    written_positions: usize,
+    written_normals: usize,
+    written_texture_coordinates: usize,
+    deduplication: Option<PositionDeduplication>,
+}
+
+/// Maps the bit patterns of the positions that have been written by [`ObjWriter::write_triangle`] to their OBJ index.
+struct PositionDeduplication {
+    indices: HashMap<[u32; 3], usize>,
+    capacity: usize,
+    deduplicated_positions: usize,
+}
+
+impl<O: Write, M: Write> ObjWriter<O, M> {
+    /// Creates a new [`ObjWriter`] and references the MTL file with the name `mtl_reference_name` in the OBJ file.
+    pub fn new(mut obj_writer: O, mtl_writer: M, mtl_reference_name: &str) -> io::Result<Self> {
+        writeln!(obj_writer, "mtllib {mtl_reference_name}")?;
+        Ok(Self {
+            obj_writer,
+            mtl_writer,
+            written_positions: 0,
+            written_normals: 0,
+            written_texture_coordinates: 0,
+            deduplication: None,
+        })
+    }
+
+    /// Deduplicates the positions that are written by [`ObjWriter::write_triangle`].
+    ///
+    /// At most `capacity` positions are remembered so that the memory stays bounded when streaming large
+    /// meshes. When the capacity is reached, the remembered positions are forgotten and positions that
+    /// have been written before will be written again.
+    pub fn with_deduplication(mut self, capacity: usize) -> Self {
+        self.deduplication = Some(PositionDeduplication {
+            indices: HashMap::with_capacity(capacity),
+            capacity,
+            deduplicated_positions: 0,
+        });
+        self
+    }
+
+    /// Returns the number of positions that have been written to the OBJ file.
+    pub fn written_positions(&self) -> usize {
+        self.written_positions
+    }
+
+    /// Returns the number of positions that didn't have to be written because they were deduplicated.
+    pub fn deduplicated_positions(&self) -> usize {
+        self.deduplication
+            .as_ref()
+            .map_or(0, |deduplication| deduplication.deduplicated_positions)
+    }
+
+    /// Writes a single triangle without normals and texture coordinates.
+    ///
+    /// The positions are written immediately so that arbitrarily large meshes can be streamed into the OBJ file.
+    /// When [`ObjWriter::with_deduplication`] has been called, positions that have already been written are reused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jeriya_shared::nalgebra::Vector3;
+    /// # use jeriya_shared::obj_writer::{ObjWriter, DEFAULT_DEDUPLICATION_CAPACITY};
+    /// let mut obj_writer = ObjWriter::new(Vec::new(), Vec::new(), "quad.mtl")
+    ///     .unwrap()
+    ///     .with_deduplication(DEFAULT_DEDUPLICATION_CAPACITY);
+    /// let (a, b, c, d) = (Vector3::x(), Vector3::y(), Vector3::z(), Vector3::zeros());
+    /// obj_writer.write_triangle(&[a, b, c]).unwrap();
+    /// obj_writer.write_triangle(&[a, c, d]).unwrap();
+    /// assert_eq!(obj_writer.written_positions(), 4);
+    /// assert_eq!(obj_writer.deduplicated_positions(), 2);
+    /// ```
+    pub fn write_triangle(&mut self, triangle: &[Vector3<f32>; 3]) -> io::Result<()> {
+        let [i0, i1, i2] = [
+            self.write_position(&triangle[0])?,
+            self.write_position(&triangle[1])?,
+            self.write_position(&triangle[2])?,
+        ];
+        writeln!(self.obj_writer, "f {i0} {i1} {i2}")
+    }
+
+    /// Writes the `position` unless it has been deduplicated and returns its OBJ index.
+    fn write_position(&mut self, position: &Vector3<f32>) -> io::Result<usize> {
+        // Adding 0.0 turns -0.0 into 0.0 so that both have the same bit pattern
+        let key = [
+            (position.x + 0.0).to_bits(),
+            (position.y + 0.0).to_bits(),
+            (position.z + 0.0).to_bits(),
+        ];
+        if let Some(deduplication) = &mut self.deduplication {
+            if let Some(index) = deduplication.indices.get(&key) {
+                deduplication.deduplicated_positions += 1;
+                return Ok(*index);
+            }
+        }
+
+        writeln!(self.obj_writer, "v {} {} {}", position.x, position.y, position.z)?;
+        self.written_positions += 1;
+
+        if let Some(deduplication) = &mut self.deduplication {
+            if deduplication.indices.len() >= deduplication.capacity {
+                deduplication.indices.clear();
+            }
+            deduplication.indices.insert(key, self.written_positions);
+        }
+        Ok(self.written_positions)
+    }
+
+    /// Starts a new object with the given `name`.
+    pub fn write_object(&mut self, name: &str) -> io::Result<()> {
+        writeln!(self.obj_writer, "o {name}")
+    }
+
+    /// Starts a new group with the given `name` in the current object.
+    pub fn write_group(&mut self, name: &str) -> io::Result<()> {
+        writeln!(self.obj_writer, "g {name}")
+    }
+
+    /// Writes a comment into the OBJ file.
+    pub fn write_comment(&mut self, comment: &str) -> io::Result<()> {
+        writeln!(self.obj_writer, "# {comment}")
+    }
+
+    /// Writes the `material` to the MTL file.
+    pub fn write_material(&mut self, material: &ObjMaterial) -> io::Result<()> {
+        let ObjMaterial {
+            name,
+            ambient_color: ka,
+            diffuse_color: kd,
+            specular_color: ks,
+            specular_exponent,
+            diffuse_texture,
+        } = material;
+        writeln!(self.mtl_writer, "newmtl {name}")?;
+        writeln!(self.mtl_writer, "Ka {} {} {}", ka.x, ka.y, ka.z)?;
+        writeln!(self.mtl_writer, "Kd {} {} {}", kd.x, kd.y, kd.z)?;
+        writeln!(self.mtl_writer, "Ks {} {} {}", ks.x, ks.y, ks.z)?;
+        writeln!(self.mtl_writer, "Ns {specular_exponent}")?;
+        if let Some(diffuse_texture) = diffuse_texture {
+            writeln!(self.mtl_writer, "map_Kd {diffuse_texture}")?;
+        }
+        Ok(())
+    }
+
+    /// Uses the material with the given `name` for the following faces.
+    pub fn use_material(&mut self, name: &str) -> io::Result<()> {
+        writeln!(self.obj_writer, "usemtl {name}")
+    }
+
+    /// Writes the vertex attributes and the triangles of the `mesh`.
+    ///
+    /// The texture coordinates are flipped vertically because the origin of the texture coordinates is in the bottom-left corner in OBJ files.
+    pub fn write_mesh(&mut self, mesh: &ObjMesh) -> io::Result<()> {
+        crate::assert_eq!(mesh.indices.len() % 3, 0, "Expected indices to be a multiple of 3");
+        if let Some(normals) = mesh.normals {
+            crate::assert_eq!(normals.len(), mesh.positions.len(), "Expected a normal for every position");
+        }
+        if let Some(texture_coordinates) = mesh.texture_coordinates {
+            crate::assert_eq!(
+                texture_coordinates.len(),
+                mesh.positions.len(),
+                "Expected a texture coordinate for every position"
+            );
+        }
+
+        for position in mesh.positions {
+            writeln!(self.obj_writer, "v {} {} {}", position.x, position.y, position.z)?;
+        }
+        for normal in mesh.normals.unwrap_or_default() {
+            writeln!(self.obj_writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+        for texture_coordinate in mesh.texture_coordinates.unwrap_or_default() {
+            writeln!(self.obj_writer, "vt {} {}", texture_coordinate.x, 1.0 - texture_coordinate.y)?;
+        }
+
+        for triangle in mesh.indices.chunks(3) {
+            write!(self.obj_writer, "f")?;
+            for index in triangle {
+                let index = *index as usize;
+                let v = self.written_positions + index + 1;
+                let vt = self.written_texture_coordinates + index + 1;
+                let vn = self.written_normals + index + 1;
+                match (mesh.texture_coordinates.is_some(), mesh.normals.is_some()) {
+                    (false, false) => write!(self.obj_writer, " {v}")?,
+                    (true, false) => write!(self.obj_writer, " {v}/{vt}")?,
+                    (false, true) => write!(self.obj_writer, " {v}//{vn}")?,
+                    (true, true) => write!(self.obj_writer, " {v}/{vt}/{vn}")?,
+                }
+            }
+            writeln!(self.obj_writer)?;
+        }
+
+        self.written_positions += mesh.positions.len();
+        self.written_normals += mesh.normals.map_or(0, <[_]>::len);
+        self.written_texture_coordinates += mesh.texture_coordinates.map_or(0, <[_]>::len);
+        Ok(())
+    }
+
+    /// Writes the lines of a bounding box as a new object. See [`write_bounding_box_o`].
+    pub fn write_bounding_box(&mut self, name: &str, aabb: &AABB) -> io::Result<()> {
+        self.written_positions += write_bounding_box_o(name, self.written_positions, &mut self.obj_writer, aabb)?;
+        Ok(())
+    }
+
+    /// Returns the underlying OBJ and MTL writers.
+    pub fn into_inner(self) -> (O, M) {
+        (self.obj_writer, self.mtl_writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Quad {
+        positions: Vec<Vector3<f32>>,
+        normals: Vec<Vector3<f32>>,
+        texture_coordinates: Vec<Vector2<f32>>,
+        indices: Vec<u32>,
+    }
+
+    fn quad() -> Quad {
+        let positions = vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ];
+        let normals = vec![Vector3::z(); 4];
+        let texture_coordinates = vec![
+            Vector2::new(0.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 0.0),
+        ];
+        Quad {
+            positions,
+            normals,
+            texture_coordinates,
+            indices: vec![0, 1, 2, 0, 2, 3],
+        }
+    }
+
+    #[test]
+    fn consecutive_meshes() {
+        let Quad {
+            positions,
+            normals,
+            texture_coordinates,
+            indices,
+        } = quad();
+        let mut obj_writer = ObjWriter::new(Vec::new(), Vec::new(), "quads.mtl").unwrap();
+        obj_writer.write_object("quads").unwrap();
+        obj_writer.write_group("positions_only").unwrap();
+        obj_writer
+            .write_mesh(&ObjMesh {
+                positions: &positions,
+                normals: None,
+                texture_coordinates: None,
+                indices: &indices,
+            })
+            .unwrap();
+        obj_writer.write_group("all_attributes").unwrap();
+        obj_writer
+            .write_mesh(&ObjMesh {
+                positions: &positions,
+                normals: Some(&normals),
+                texture_coordinates: Some(&texture_coordinates),
+                indices: &indices,
+            })
+            .unwrap();
+        obj_writer
+            .write_bounding_box("bounding_box", &AABB::from_slice(&positions))
+            .unwrap();
+        obj_writer.write_group("texture_coordinates_only").unwrap();
+        obj_writer
+            .write_mesh(&ObjMesh {
+                positions: &positions,
+                normals: None,
+                texture_coordinates: Some(&texture_coordinates),
+                indices: &indices[..3],
+            })
+            .unwrap();
+        let (obj, mtl) = obj_writer.into_inner();
+        let obj = String::from_utf8(obj).unwrap();
+
+        let faces = obj.lines().filter(|line| line.starts_with("f ")).collect::<Vec<_>>();
+        assert_eq!(
+            faces,
+            vec![
+                "f 1 2 3",
+                "f 1 3 4",
+                "f 5/1/1 6/2/2 7/3/3",
+                "f 5/1/1 7/3/3 8/4/4",
+                "f 17/5 18/6 19/7",
+            ]
+        );
+        assert!(obj.starts_with("mtllib quads.mtl\n"));
+        assert!(obj.contains("vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n"));
+        assert!(obj.contains("vn 0 0 1\n"));
+        assert!(mtl.is_empty());
+    }
+
+    #[test]
+    fn deduplication_capacity() {
+        let mut obj_writer = ObjWriter::new(Vec::new(), Vec::new(), "triangles.mtl")
+            .unwrap()
+            .with_deduplication(3);
+        let (a, b, c, d) = (Vector3::x(), Vector3::y(), Vector3::z(), Vector3::zeros());
+        obj_writer.write_triangle(&[a, b, c]).unwrap();
+        obj_writer.write_triangle(&[-a, -b, -c]).unwrap();
+        // `-d` is -0.0 which must be deduplicated with 0.0
+        obj_writer.write_triangle(&[-d, d, a]).unwrap();
+        let (obj, _mtl) = obj_writer.into_inner();
+        let obj = String::from_utf8(obj).unwrap();
+
+        let faces = obj.lines().filter(|line| line.starts_with("f ")).collect::<Vec<_>>();
+        assert_eq!(faces, vec!["f 1 2 3", "f 4 5 6", "f 7 7 8"]);
+    }
+
+    #[test]
+    fn material() {
+        let mut obj_writer = ObjWriter::new(Vec::new(), Vec::new(), "material.mtl").unwrap();
+        let material = ObjMaterial {
+            diffuse_texture: Some("base_color.png".to_owned()),
+            ..ObjMaterial::from_color("textured", Vector3::new(0.5, 0.25, 1.0))
+        };
+        obj_writer.write_material(&material).unwrap();
+        let (_obj, mtl) = obj_writer.into_inner();
+        assert_eq!(
+            String::from_utf8(mtl).unwrap(),
+            "newmtl textured\nKa 0.5 0.25 1\nKd 0.5 0.25 1\nKs 1 1 1\nNs 10\nmap_Kd base_color.png\n"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+/// Filter that is applied when a texture is magnified or minified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+/// Determines how texture coordinates outside of [0, 1] are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum AddressMode {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+/// Describes how a texture is sampled in the shaders
+///
+/// Samplers are shared between all textures with the same `SamplerConfig`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SamplerConfig {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub mipmap_filter: Filter,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    /// Address mode of the third texture coordinate. Only relevant for 3D textures.
+    #[serde(default)]
+    pub address_mode_w: AddressMode,
+    /// Maximum anisotropy or `None` to disable anisotropic filtering. Ignored when the device doesn't support it.
+    pub max_anisotropy: Option<u32>,
+    /// Bias that is added to the mip level that is selected by the hardware
+    pub mip_lod_bias: f32,
+}
+
+impl SamplerConfig {
+    /// Trilinear filtering with repeating texture coordinates
+    pub fn linear() -> Self {
+        Self::default()
+    }
+
+    /// Nearest filtering without interpolation between texels or mip levels
+    ///
+    /// Useful for data that must not be blended like the attribute textures of point clouds.
+    pub fn nearest() -> Self {
+        Self {
+            mag_filter: Filter::Nearest,
+            min_filter: Filter::Nearest,
+            mipmap_filter: Filter::Nearest,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the `SamplerConfig` with the given maximum anisotropy
+    pub fn with_max_anisotropy(mut self, max_anisotropy: u32) -> Self {
+        self.max_anisotropy = Some(max_anisotropy);
+        self
+    }
+
+    /// Returns the `SamplerConfig` with the given mip level bias
+    pub fn with_mip_lod_bias(mut self, mip_lod_bias: f32) -> Self {
+        self.mip_lod_bias = mip_lod_bias;
+        self
+    }
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_filter: Filter::Linear,
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            address_mode_w: AddressMode::Repeat,
+            max_anisotropy: None,
+            mip_lod_bias: 0.0,
+        }
+    }
+}
+
+// The mip bias is compared bitwise so that the config can be used as the key of the sampler cache
+impl PartialEq for SamplerConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.mag_filter == other.mag_filter
+            && self.min_filter == other.min_filter
+            && self.mipmap_filter == other.mipmap_filter
+            && self.address_mode_u == other.address_mode_u
+            && self.address_mode_v == other.address_mode_v
+            && self.address_mode_w == other.address_mode_w
+            && self.max_anisotropy == other.max_anisotropy
+            && self.mip_lod_bias.to_bits() == other.mip_lod_bias.to_bits()
+    }
+}
+
+impl Eq for SamplerConfig {}
+
+impl Hash for SamplerConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mag_filter.hash(state);
+        self.min_filter.hash(state);
+        self.mipmap_filter.hash(state);
+        self.address_mode_u.hash(state);
+        self.address_mode_v.hash(state);
+        self.address_mode_w.hash(state);
+        self.max_anisotropy.hash(state);
+        self.mip_lod_bias.to_bits().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn equal_configs_hash_equally() {
+        let mut set = HashSet::new();
+        set.insert(SamplerConfig::linear().with_mip_lod_bias(0.5));
+        assert!(set.contains(&SamplerConfig::linear().with_mip_lod_bias(0.5)));
+        assert!(!set.contains(&SamplerConfig::linear()));
+        assert!(!set.contains(&SamplerConfig::nearest().with_mip_lod_bias(0.5)));
+        assert!(!set.contains(&SamplerConfig {
+            address_mode_w: AddressMode::ClampToEdge,
+            ..SamplerConfig::linear().with_mip_lod_bias(0.5)
+        }));
+    }
+}
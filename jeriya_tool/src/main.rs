@@ -1,4 +1,7 @@
-use std::{io, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 use color_eyre as ey;
@@ -9,6 +12,7 @@ use jeriya_content::{
         clustered_point_cloud::{ClusteredPointCloudAsset, ObjClusterWriteConfig},
         simple_point_cloud::SimplePointCloud,
     },
+    read_asset::archive::Archive,
 };
 use jeriya_shared::log::{self, info};
 
@@ -43,6 +47,15 @@ enum ConvertType {
         /// Scale of the model. Use 0.1 to divide every coordinate by 10.
         #[clap(short, long, default_value = "1.0")]
         scale: f32,
+
+        /// Size of the voxels in which the points are merged before clustering. No downsampling is done when omitted.
+        #[clap(short, long)]
+        grid_size: Option<f32>,
+    },
+    PlyToPointCloud {
+        /// Size of the voxels in which the points are merged before clustering. No downsampling is done when omitted.
+        #[clap(short, long)]
+        grid_size: Option<f32>,
     },
     PointCloudToObj {
         /// Size of the points in the point cloud
@@ -53,6 +66,10 @@ enum ConvertType {
         #[clap(short, long, default_value = "0")]
         depth: usize,
     },
+    /// Writes the point cloud in the Potree 2.0 format into the destination directory
+    PointCloudToPotree,
+    /// Packs the directory of processed assets into an archive that can be read with `Archive`
+    DirectoryToArchive,
 }
 
 fn main() -> ey::Result<()> {
@@ -78,6 +95,7 @@ fn main() -> ey::Result<()> {
             ConvertType::GltfToPointCloud {
                 points_per_square_unit,
                 scale,
+                grid_size,
             } => {
                 info!("Importing model: {:?}", convert.source_filepath);
                 let model = ModelAsset::import(&convert.source_filepath).wrap_err("Failed to import model")?;
@@ -85,12 +103,14 @@ fn main() -> ey::Result<()> {
                 info!("Converting model to simple point cloud");
                 let simple_point_cloud = SimplePointCloud::sample_from_model(&model, points_per_square_unit, scale);
 
-                info!("Clustering point cloud");
-                let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
-                info!("Serializing point cloud");
-                clustered_point_cloud
-                    .serialize_to_file(&convert.destination_filepath)
-                    .wrap_err("Failed to serialize point cloud")?;
+                cluster_and_serialize(simple_point_cloud, grid_size, &convert.destination_filepath)?;
+            }
+            ConvertType::PlyToPointCloud { grid_size } => {
+                info!("Importing PLY point cloud: {:?}", convert.source_filepath);
+                let simple_point_cloud =
+                    SimplePointCloud::import_ply(&convert.source_filepath).wrap_err("Failed to import PLY point cloud")?;
+
+                cluster_and_serialize(simple_point_cloud, grid_size, &convert.destination_filepath)?;
             }
             ConvertType::PointCloudToObj { point_size, depth } => {
                 info!("Deserializing point cloud");
@@ -102,7 +122,40 @@ fn main() -> ey::Result<()> {
                     .to_obj_file(&ObjClusterWriteConfig::Points { point_size, depth }, &convert.destination_filepath)
                     .wrap_err("Failed to write point cloud to OBJ")?;
             }
+            ConvertType::PointCloudToPotree => {
+                info!("Deserializing point cloud");
+                let clustered_point_cloud = ClusteredPointCloudAsset::deserialize_from_file(&convert.source_filepath)
+                    .wrap_err("Failed to deserialize point cloud")?;
+
+                info!("Writing point cloud in the Potree format");
+                clustered_point_cloud
+                    .write_potree(&convert.destination_filepath)
+                    .wrap_err("Failed to write point cloud in the Potree format")?;
+            }
+            ConvertType::DirectoryToArchive => {
+                info!("Packing directory {:?} into archive", convert.source_filepath);
+                Archive::pack(&convert.source_filepath, &convert.destination_filepath).wrap_err("Failed to pack archive")?;
+            }
         },
     }
     Ok(())
 }
+
+/// Downsamples the point cloud when a `grid_size` is given, clusters it and writes it to `destination_filepath`.
+fn cluster_and_serialize(simple_point_cloud: SimplePointCloud, grid_size: Option<f32>, destination_filepath: &Path) -> ey::Result<()> {
+    let simple_point_cloud = match grid_size {
+        Some(grid_size) => {
+            info!("Downsampling point cloud with a grid size of {grid_size}");
+            simple_point_cloud.downsample_voxel(grid_size)
+        }
+        None => simple_point_cloud,
+    };
+
+    info!("Clustering point cloud");
+    let clustered_point_cloud = ClusteredPointCloudAsset::from_simple_point_cloud(&simple_point_cloud);
+    info!("Serializing point cloud");
+    clustered_point_cloud
+        .serialize_to_file(&destination_filepath)
+        .wrap_err("Failed to serialize point cloud")?;
+    Ok(())
+}
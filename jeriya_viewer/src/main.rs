@@ -3,6 +3,7 @@ mod camera_controller;
 use std::{
     f32::consts::TAU,
     io,
+    path::PathBuf,
     sync::Arc,
     thread,
     time::{Duration, Instant},
@@ -14,7 +15,7 @@ use ey::eyre::{Context, ContextCompat};
 use jeriya::Renderer;
 use jeriya_backend::{
     elements::{
-        camera::{Camera, CameraProjection},
+        camera::{Camera, CameraAccessMut, CameraProjection},
         element_group::ElementGroup,
         helper::{rigid_mesh_collection::RigidMeshCollection, rigid_mesh_instance_collection::RigidMeshInstanceCollection},
         point_cloud,
@@ -29,12 +30,16 @@ use jeriya_backend::{
     transactions::Transaction,
     Backend,
 };
-use jeriya_backend_ash::AshBackend;
+use jeriya_backend_ash::{AshBackend, FrameCaptureConfig};
 use jeriya_content::{
-    asset_importer::AssetImporter, asset_processor::AssetProcessor, common::Directories, model::ModelAsset,
+    asset_importer::AssetImporter,
+    asset_processor::{AssetProcessor, Progress, ProgressState},
+    common::{AssetKey, Directories},
+    model::ModelAsset,
     point_cloud::clustered_point_cloud::ClusteredPointCloudAsset,
 };
 use jeriya_shared::{
+    crossbeam_channel::Receiver,
     debug_info,
     log::{self, error, info},
     nalgebra::{self, Matrix4, Scale3, Translation3, Vector2, Vector3, Vector4},
@@ -47,11 +52,13 @@ use jeriya_shared::{
         keyboard::{Key, NamedKey},
         window::WindowBuilder,
     },
-    FrameRate, RendererConfig, WindowConfig,
+    ColorSpace, FrameRate, PresentMode, RendererConfig, WindowConfig, WindowMode,
 };
 
 use crate::camera_controller::CameraController;
 
+const WINDOW_TITLE: &str = "Jeriya Viewer";
+
 /// Shows how the immediate rendering API can be used.
 fn immediate_rendering<B>(
     renderer: &Renderer<B>,
@@ -131,6 +138,7 @@ where
             LineConfig {
                 color: Vector4::new(0.8, 0.8, 1.0, 1.0),
                 line_width: 5.0,
+                ..LineConfig::default()
             },
         )
     };
@@ -152,6 +160,7 @@ where
             LineConfig {
                 color: Vector4::new(1.0, 0.0, 0.0, 1.0),
                 line_width: 4.0,
+                ..LineConfig::default()
             },
         )
     };
@@ -166,6 +175,7 @@ where
         ],
         TriangleConfig {
             color: Vector4::new(1.0, 0.3, 0.7, 1.0),
+            ..TriangleConfig::default()
         },
     );
     let triangle_strip = TriangleStrip::new(
@@ -177,6 +187,7 @@ where
         ],
         TriangleConfig {
             color: Vector4::new(1.0, 1.0, 0.2, 1.0),
+            ..TriangleConfig::default()
         },
     );
 
@@ -193,17 +204,63 @@ where
     Ok(())
 }
 
-fn setup_asset_processor() -> ey::Result<AssetProcessor> {
+fn setup_asset_processor() -> ey::Result<(AssetProcessor, Receiver<Progress>)> {
     let directories = Directories::create_all_dir("assets/unprocessed", "assets/processed")
         .wrap_err("Failed to create Directories for AssetProcessor")?;
-    let asset_processor = AssetProcessor::new(&directories, 4)
+    let mut asset_processor = AssetProcessor::new(&directories, 4)
         .wrap_err("Failed to create AssetProcessor")?
         .register("glb", Box::new(jeriya_content::model::process_model))
+        .register("obj", Box::new(jeriya_content::model::process_model))
+        .register("fbx", Box::new(jeriya_content::model::process_model))
+        .register("ply", Box::new(jeriya_content::point_cloud::process_point_cloud))
+        .register("scene", Box::new(jeriya_content::scene::process_scene))
         .register("vert", Box::new(jeriya_content::shader::process_shader))
         .register("frag", Box::new(jeriya_content::shader::process_shader))
-        .register("comp", Box::new(jeriya_content::shader::process_shader));
+        .register("comp", Box::new(jeriya_content::shader::process_shader))
+        .register("png", Box::new(jeriya_content::texture::process_texture))
+        .register("jpg", Box::new(jeriya_content::texture::process_texture))
+        .register("jpeg", Box::new(jeriya_content::texture::process_texture))
+        .register("ttf", Box::new(jeriya_content::font::process_font))
+        .register("otf", Box::new(jeriya_content::font::process_font));
+    let progress_receiver = asset_processor.observe_progress();
     asset_processor.set_active(true)?;
-    Ok(asset_processor)
+    Ok((asset_processor, progress_receiver))
+}
+
+/// Counts the processed assets since the queue of the [`AssetProcessor`] was last empty.
+#[derive(Default)]
+struct AssetProcessingProgress {
+    processed: usize,
+    failed: usize,
+    queue_len: usize,
+}
+
+impl AssetProcessingProgress {
+    fn update(&mut self, progress: Progress) {
+        match progress.state {
+            ProgressState::Finished => self.processed += 1,
+            ProgressState::Failed(_) => self.failed += 1,
+            ProgressState::Queued | ProgressState::Started => {}
+        }
+        self.queue_len = progress.queue_len;
+    }
+
+    /// Returns the window title with a progress bar as long as assets are being processed.
+    fn window_title(&self, title: &str) -> String {
+        const BAR_LEN: usize = 20;
+        if self.queue_len == 0 {
+            return title.to_owned();
+        }
+        let done = self.processed + self.failed;
+        let total = done + self.queue_len;
+        let filled = BAR_LEN * done / total;
+        format!(
+            "{title} - Processing assets [{}{}] {done}/{total} ({} failed)",
+            "#".repeat(filled),
+            "-".repeat(BAR_LEN - filled),
+            self.failed
+        )
+    }
 }
 
 #[derive(ValueEnum, Debug, Clone)]
@@ -231,6 +288,56 @@ struct CommandLineArguments {
     /// Whether to open one or two windows
     #[arg(long, short)]
     single_window: bool,
+
+    /// Whether to present the first window in HDR10 when the monitor supports it
+    #[arg(long)]
+    hdr: bool,
+
+    /// Whether to stream the pages of the point cloud. The path is interpreted as the asset key of a processed point cloud.
+    #[arg(long)]
+    stream_pages: bool,
+
+    /// Maximum number of points that are rendered for the point clouds in each window
+    #[arg(long)]
+    maximum_rendered_points: Option<usize>,
+
+    /// Whether to import the model via the AssetImporter and reload it when it changes. The path is interpreted as the asset key of a processed model.
+    #[arg(long)]
+    hot_reload: bool,
+
+    /// Directory into which the frames of the first window are written as a PNG sequence at 60 FPS
+    #[arg(long)]
+    capture: Option<PathBuf>,
+}
+
+/// Inserts the `RigidMesh`es of the model and one instance of each of them.
+fn insert_model<B: Backend>(
+    model: &ModelAsset,
+    renderer: &Arc<Renderer<B>>,
+    resource_group: &mut ResourceGroup,
+    element_group: &mut ElementGroup,
+    instance_group: &mut InstanceGroup,
+) -> RigidMeshCollection {
+    let mut transaction = Transaction::record(renderer);
+
+    // Create a RigidMesh from model
+    //
+    // A RigidMeshCollection can be used to create multiple RigidMeshes from a single Model. To display
+    // the RigidMeshes in the scene, RigidMeshInstances must be created that reference the RigidMeshes.
+    // A RigidMeshInstanceCollection can be used for that.
+    let rigid_mesh_collection = RigidMeshCollection::from_model(model, resource_group, element_group, &mut transaction)
+        .expect("Failed to create RigidMeshCollection");
+    let _rigid_mesh_instance_collection = RigidMeshInstanceCollection::from_rigid_mesh_collection(
+        &rigid_mesh_collection,
+        element_group.rigid_meshes(),
+        instance_group,
+        &mut transaction,
+        &nalgebra::convert(Translation3::new(0.0, 0.0, 0.0)),
+    )
+    .expect("Failed to create RigidMeshInstanceCollection");
+
+    transaction.finish();
+    rigid_mesh_collection
 }
 
 fn main() -> ey::Result<()> {
@@ -257,7 +364,7 @@ fn main() -> ey::Result<()> {
     let event_loop = EventLoop::new().wrap_err("Failed to create EventLoop")?;
     event_loop.set_control_flow(ControlFlow::Poll);
     let mut windows = vec![WindowBuilder::new()
-        .with_title("Jeria Viewer")
+        .with_title(WINDOW_TITLE)
         .with_inner_size(LogicalSize::new(1000.0, 1000.0))
         .build(&event_loop)
         .wrap_err("Failed to create window 1")?];
@@ -277,28 +384,55 @@ fn main() -> ey::Result<()> {
     }
 
     // Setup Content Pipeline
-    let _asset_processor = setup_asset_processor()?;
+    let (_asset_processor, asset_processing_progress_receiver) = setup_asset_processor()?;
+    let mut asset_processing_progress = AssetProcessingProgress::default();
     let asset_importer = Arc::new(AssetImporter::default_from("assets/processed").wrap_err("Failed to create AssetImporter")?);
 
     // Prepare WindowConfigs
     let mut window_configs = vec![WindowConfig {
         window: &windows[0],
         frame_rate: FrameRate::Limited(60),
+        present_mode: PresentMode::Mailbox,
+        color_space: if command_line_arguments.hdr {
+            ColorSpace::Hdr10
+        } else {
+            ColorSpace::Sdr
+        },
     }];
     if !command_line_arguments.single_window {
         window_configs.push(WindowConfig {
             window: &windows[1],
             frame_rate: FrameRate::Unlimited,
+            present_mode: PresentMode::Immediate,
+            color_space: ColorSpace::Sdr,
         });
     }
 
     // Create Renderer
     let renderer = jeriya::Renderer::<AshBackend>::builder()
-        .add_renderer_config(RendererConfig::normal())
-        .add_asset_importer(asset_importer)
+        .add_renderer_config(RendererConfig {
+            pipeline_cache_path: Some("assets/processed/pipeline_cache.bin".into()),
+            ..RendererConfig::normal()
+        })
+        .add_asset_importer(asset_importer.clone())
         .add_windows(&window_configs)
         .build()
         .wrap_err("Failed to create renderer")?;
+    for window in &windows {
+        renderer
+            .set_maximum_rendered_points(window.id(), command_line_arguments.maximum_rendered_points)
+            .wrap_err("Failed to set the maximum number of rendered points")?;
+    }
+    let _frame_capture_handle = command_line_arguments
+        .capture
+        .as_ref()
+        .map(|directory| {
+            renderer
+                .backend()
+                .start_frame_capture(windows[0].id(), FrameCaptureConfig::png_sequence(directory, 60))
+        })
+        .transpose()
+        .wrap_err("Failed to start the frame capture")?;
 
     // Containers in which manage the GPU resources
     let mut resource_group = ResourceGroup::new(&renderer, debug_info!("my_resource_group"));
@@ -413,12 +547,88 @@ fn main() -> ey::Result<()> {
     let element_group = Arc::new(Mutex::new(element_group));
     let instance_group = Arc::new(Mutex::new(instance_group));
 
+    // Keep the aspect ratio of the perspective camera in sync with the size of the first window
+    let weak_renderer = Arc::downgrade(&renderer);
+    let element_group3 = Arc::clone(&element_group);
+    renderer
+        .set_resize_callback(windows[0].id(), move |width, height| {
+            let Some(renderer) = weak_renderer.upgrade() else {
+                return;
+            };
+            let mut element_group = element_group3.lock();
+            let Some(camera) = element_group.cameras().get_mut(&camera1_handle) else {
+                return;
+            };
+            let mut transaction = Transaction::record(&renderer);
+            CameraAccessMut::new(camera, &mut transaction)
+                .set_projection(CameraProjection::Perspective {
+                    fov: 90.0,
+                    aspect: width as f32 / height as f32,
+                    near: 0.1,
+                    far: 100.0,
+                })
+                .expect("perspective projections are always valid");
+            transaction.finish();
+        })
+        .wrap_err("Failed to set resize callback")?;
+
     let renderer2 = Arc::clone(&renderer);
     let resource_group2 = Arc::clone(&resource_group);
     let element_group2 = Arc::clone(&element_group);
     let instance_group2 = Arc::clone(&instance_group);
     thread::spawn(move || {
         match command_line_arguments.file_type {
+            FileType::Model if command_line_arguments.hot_reload => {
+                let asset_key = AssetKey::new(&command_line_arguments.path);
+                let mut model_receiver = asset_importer
+                    .receive_assets::<ModelAsset>()
+                    .expect("The importer for models is not registered");
+                asset_importer
+                    .import::<ModelAsset>(asset_key.clone())
+                    .wrap_err("Failed to import model")
+                    .expect("Failed to import model");
+
+                // The first import inserts the model and every subsequent import replaces its MeshAttributes
+                let mut rigid_mesh_collection = None;
+                while let Ok(result) = model_receiver.recv() {
+                    let model = match result.as_ref() {
+                        Ok(asset) if asset.asset_key() == &asset_key => asset.value(),
+                        Ok(_) => continue,
+                        Err(err) => {
+                            error!("Failed to import model: {err}");
+                            continue;
+                        }
+                    };
+                    let Some(model) = model else {
+                        continue;
+                    };
+
+                    let mut resource_group = resource_group2.lock();
+                    let mut element_group = element_group2.lock();
+                    let mut instance_group = instance_group2.lock();
+                    match &mut rigid_mesh_collection {
+                        None => {
+                            rigid_mesh_collection = Some(insert_model(
+                                &model,
+                                &renderer2,
+                                &mut resource_group,
+                                &mut element_group,
+                                &mut instance_group,
+                            ));
+                        }
+                        Some(rigid_mesh_collection) => {
+                            info!("Reloading model '{asset_key}'");
+                            let mut transaction = Transaction::record(&renderer2);
+                            if let Err(err) =
+                                rigid_mesh_collection.reload(&model, &mut resource_group, &mut element_group, &mut transaction)
+                            {
+                                error!("Failed to reload model '{asset_key}': {err}");
+                            }
+                            transaction.finish();
+                        }
+                    }
+                }
+            }
             FileType::Model => {
                 let main_model = ModelAsset::import(command_line_arguments.path)
                     .wrap_err("Failed to import model")
@@ -427,43 +637,40 @@ fn main() -> ey::Result<()> {
                 let mut resource_group = resource_group2.lock();
                 let mut element_group = element_group2.lock();
                 let mut instance_group = instance_group2.lock();
-
-                let mut transaction = Transaction::record(&renderer2);
-
-                // Create a RigidMesh from model
-                //
-                // A RigidMeshCollection can be used to create multiple RigidMeshes from a single Model. To display
-                // the RigidMeshes in the scene, RigidMeshInstances must be created that reference the RigidMeshes.
-                // A RigidMeshInstanceCollection can be used for that.
-                let rigid_mesh_collection =
-                    RigidMeshCollection::from_model(&main_model, &mut resource_group, &mut element_group, &mut transaction)
-                        .expect("Failed to create RigidMeshCollection");
-                let _rigid_mesh_instance_collection = RigidMeshInstanceCollection::from_rigid_mesh_collection(
-                    &rigid_mesh_collection,
-                    element_group.rigid_meshes(),
+                insert_model(
+                    &main_model,
+                    &renderer2,
+                    &mut resource_group,
+                    &mut element_group,
                     &mut instance_group,
-                    &mut transaction,
-                    &nalgebra::convert(Translation3::new(0.0, 0.0, 0.0)),
-                )
-                .expect("Failed to create RigidMeshInstanceCollection");
-
-                transaction.finish();
+                );
             }
             FileType::PointCloud => {
-                let clustered_point_cloud = ClusteredPointCloudAsset::deserialize_from_file(&command_line_arguments.path)
-                    .wrap_err("Failed to deserialize PointCloud")
-                    .expect("Failed to deserialize PointCloud");
-                info!("PointCloud to view: {clustered_point_cloud:?}");
+                // Create PointCloudAttributes
+                let point_cloud_attributes_builder = if command_line_arguments.stream_pages {
+                    let asset_key = AssetKey::new(&command_line_arguments.path);
+                    let page_streaming_info = asset_importer
+                        .import_page_streaming_info(&asset_key)
+                        .wrap_err("Failed to import the page streaming info of the PointCloud")
+                        .expect("Failed to import the page streaming info of the PointCloud");
+                    info!("PointCloud to stream: {page_streaming_info:?}");
+                    PointCloudAttributes::builder()
+                        .with_debug_info(debug_info!("my_point_cloud_attributes"))
+                        .with_page_streaming(asset_key, page_streaming_info)
+                } else {
+                    let clustered_point_cloud = ClusteredPointCloudAsset::deserialize_from_file(&command_line_arguments.path)
+                        .wrap_err("Failed to deserialize PointCloud")
+                        .expect("Failed to deserialize PointCloud");
+                    info!("PointCloud to view: {clustered_point_cloud:?}");
+                    PointCloudAttributes::builder()
+                        .with_debug_info(debug_info!("my_point_cloud_attributes"))
+                        .with_pages(clustered_point_cloud.pages().to_vec())
+                        .with_root_cluster_index(clustered_point_cloud.root_cluster_index().clone())
+                };
 
                 let mut resource_group = resource_group2.lock();
                 let mut element_group = element_group2.lock();
                 let mut instance_group = instance_group2.lock();
-
-                // Create PointCloudAttributes
-                let point_cloud_attributes_builder = PointCloudAttributes::builder()
-                    .with_debug_info(debug_info!("my_point_cloud_attributes"))
-                    .with_pages(clustered_point_cloud.pages().to_vec())
-                    .with_root_cluster_index(clustered_point_cloud.root_cluster_index().clone());
                 let point_cloud_attributes = resource_group
                     .point_cloud_attributes()
                     .insert_with(point_cloud_attributes_builder)
@@ -513,6 +720,9 @@ fn main() -> ey::Result<()> {
     let mut update_loop_frame_index = 0;
     let mut mesh_count = 0;
     let mut last_mesh_insert_t = Duration::from_secs(0);
+    let mut debug_bounding_volumes = false;
+    let mut eye_dome_lighting = false;
+    let mut window_mode = WindowMode::default();
     let mut interval = spin_sleep_util::interval(Duration::from_secs_f32(1.0 / UPDATE_FRAMERATE as f32));
     event_loop
         .run(move |event, event_loop_window_target| match event {
@@ -531,6 +741,28 @@ fn main() -> ey::Result<()> {
                             Key::Named(NamedKey::ArrowDown) => camera_controller2.set_rotating_down(event.state == ElementState::Pressed),
                             Key::Named(NamedKey::PageUp) => camera_controller2.set_zooming_in(event.state == ElementState::Pressed),
                             Key::Named(NamedKey::PageDown) => camera_controller2.set_zooming_out(event.state == ElementState::Pressed),
+                            Key::Character(character) if character.as_str() == "b" && event.state == ElementState::Pressed => {
+                                debug_bounding_volumes = !debug_bounding_volumes;
+                                renderer
+                                    .set_debug_bounding_volumes(window_id, debug_bounding_volumes)
+                                    .expect("Failed to toggle the bounding volumes");
+                            }
+                            Key::Character(character) if character.as_str() == "e" && event.state == ElementState::Pressed => {
+                                eye_dome_lighting = !eye_dome_lighting;
+                                renderer
+                                    .set_eye_dome_lighting(window_id, eye_dome_lighting)
+                                    .expect("Failed to toggle the eye-dome lighting");
+                            }
+                            Key::Named(NamedKey::F11) if event.state == ElementState::Pressed => {
+                                window_mode = match window_mode {
+                                    WindowMode::Windowed => WindowMode::Borderless,
+                                    WindowMode::Borderless => WindowMode::Exclusive,
+                                    WindowMode::Exclusive => WindowMode::Windowed,
+                                };
+                                renderer
+                                    .set_window_mode(&windows[0], window_mode)
+                                    .expect("Failed to switch the window mode");
+                            }
                             _ => {}
                         },
                         WindowEvent::CursorMoved { position, .. } => {
@@ -556,6 +788,17 @@ fn main() -> ey::Result<()> {
                 let t = frame_start_time - loop_start_time;
                 let dt = frame_start_time - last_frame_start_time;
 
+                // Show the progress of the asset processing in the title of the first window
+                if !asset_processing_progress_receiver.is_empty() {
+                    for progress in asset_processing_progress_receiver.try_iter() {
+                        asset_processing_progress.update(progress);
+                    }
+                    windows[0].set_title(&asset_processing_progress.window_title(WINDOW_TITLE));
+                    if asset_processing_progress.queue_len == 0 {
+                        asset_processing_progress = AssetProcessingProgress::default();
+                    }
+                }
+
                 let mut transaction = Transaction::record(&renderer);
 
                 camera_controller2
@@ -596,6 +839,12 @@ fn main() -> ey::Result<()> {
                 last_frame_start_time = frame_start_time;
                 interval.tick();
             }
+            Event::LoopExiting => {
+                // The renderer is still referenced by its threads and isn't dropped before the process exits
+                if let Err(err) = renderer.save_pipeline_cache() {
+                    error!("Failed to save the pipeline cache: {}", err);
+                }
+            }
             _ => (),
         })
         .wrap_err("Running the EventLoop failed")?;